@@ -3,6 +3,7 @@
 //! player data loading, and level data loading.
 
 pub mod block_entity;
+pub mod data_fixer;
 pub mod entity;
 pub mod level;
 pub mod player;
@@ -0,0 +1,519 @@
+//! Upgrades chunk NBT saved by older versions of Minecraft
+//! to the data version supported by Feather.
+//!
+//! A chunk is upgraded step by step, each step covering the changes
+//! made in a single data version, before it is deserialized into a
+//! [`ChunkRoot`](super::region::ChunkRoot). Worlds saved by 1.13 or later
+//! are supported. Older worlds predate the flattening of block IDs
+//! and must first be opened in a newer vanilla server.
+//!
+//! Numeric biome IDs did not change between 1.13 and 1.16, so
+//! only the layout of the biome array needs upgrading.
+
+use std::collections::HashMap;
+
+use nbt::Value;
+use uuid::Uuid;
+
+use super::region::{Error, DATA_VERSION};
+use crate::chunk::PackedArray;
+
+/// An NBT compound tag.
+pub type Compound = HashMap<String, Value>;
+
+/// The oldest data version which can be upgraded, corresponding to 1.13.
+pub const MIN_DATA_VERSION: i32 = 1519;
+
+/// A step which upgrades chunks saved before `version`.
+struct Fix {
+    version: i32,
+    /// Upgrades the `Level` tag of a chunk.
+    apply: fn(&mut Compound),
+}
+
+const FIXES: &[Fix] = &[
+    Fix {
+        version: 1901,
+        apply: rename_1_14_blocks_and_items,
+    },
+    Fix {
+        version: 1911,
+        apply: rename_chunk_statuses,
+    },
+    Fix {
+        version: 2202,
+        apply: expand_biomes,
+    },
+    Fix {
+        version: 2502,
+        apply: fix_wall_connections,
+    },
+    Fix {
+        version: 2509,
+        apply: rename_zombified_piglins,
+    },
+    Fix {
+        version: 2514,
+        apply: convert_uuids,
+    },
+    Fix {
+        version: 2527,
+        apply: align_packed_arrays,
+    },
+];
+
+/// 18w43a added wood types to signs and the new stone slab,
+/// renaming the old one to smooth stone slab.
+const BLOCK_RENAMES_1_14: &[(&str, &str)] = &[
+    ("minecraft:sign", "minecraft:oak_sign"),
+    ("minecraft:wall_sign", "minecraft:oak_wall_sign"),
+    ("minecraft:stone_slab", "minecraft:smooth_stone_slab"),
+];
+
+/// The renamed blocks as items, plus dyes,
+/// which were named after their source until 1.14.
+const ITEM_RENAMES_1_14: &[(&str, &str)] = &[
+    ("minecraft:sign", "minecraft:oak_sign"),
+    ("minecraft:stone_slab", "minecraft:smooth_stone_slab"),
+    ("minecraft:rose_red", "minecraft:red_dye"),
+    ("minecraft:cactus_green", "minecraft:green_dye"),
+    ("minecraft:dandelion_yellow", "minecraft:yellow_dye"),
+];
+
+/// The proto-chunk statuses of 1.13 and their 1.14 names.
+const CHUNK_STATUS_RENAMES: &[(&str, &str)] = &[
+    ("carved", "carvers"),
+    ("liquid_carved", "liquid_carvers"),
+    ("decorated", "features"),
+    ("lighted", "light"),
+    ("mobs_spawned", "spawn"),
+    ("finalized", "heightmaps"),
+    ("fullchunk", "full"),
+    ("postprocessed", "full"),
+];
+
+/// Keys under which entities and block entities store item stacks.
+const ITEM_KEYS: &[&str] = &["Item", "RecordItem", "Items", "HandItems", "ArmorItems"];
+
+/// Upgrades the root tag of a chunk to [`DATA_VERSION`].
+///
+/// Returns the data version the chunk was saved with.
+pub fn upgrade_chunk(root: &mut Compound) -> Result<i32, Error> {
+    let version = match root.get("DataVersion") {
+        Some(&Value::Int(version)) => version,
+        // Chunks saved before 1.9 have no data version.
+        _ => return Err(Error::UnsupportedDataVersion(0)),
+    };
+    if !(MIN_DATA_VERSION..=DATA_VERSION).contains(&version) {
+        return Err(Error::UnsupportedDataVersion(version));
+    }
+
+    let level = match root.get_mut("Level") {
+        Some(Value::Compound(level)) => level,
+        _ => return Err(Error::MissingRootTag),
+    };
+    for fix in FIXES.iter().filter(|fix| fix.version > version) {
+        (fix.apply)(level);
+    }
+
+    root.insert("DataVersion".to_owned(), Value::Int(DATA_VERSION));
+    Ok(version)
+}
+
+fn rename_1_14_blocks_and_items(level: &mut Compound) {
+    rename_blocks(level, BLOCK_RENAMES_1_14);
+    rename_items(level, ITEM_RENAMES_1_14);
+}
+
+fn rename_chunk_statuses(level: &mut Compound) {
+    if let Some(status) = level.get_mut("Status") {
+        rename(status, CHUNK_STATUS_RENAMES);
+    }
+}
+
+/// 19w36a made biomes three-dimensional, stored for each 4x4x4 cell
+/// rather than for each column of blocks.
+fn expand_biomes(level: &mut Compound) {
+    if let Some(Value::IntArray(biomes)) = level.get_mut("Biomes") {
+        if biomes.len() == 256 {
+            *biomes = expand_biome_columns(biomes);
+        }
+    }
+}
+
+/// Converts 256 biomes, one per column indexed by `z * 16 + x`, to 1024
+/// biomes indexed by `y * 16 + z * 4 + x` in 4x4x4 cells. Each cell takes
+/// the biome at the center of its columns.
+fn expand_biome_columns(columns: &[i32]) -> Vec<i32> {
+    let cells: Vec<i32> = (0..16)
+        .map(|index| {
+            let (x, z) = (index % 4, index / 4);
+            columns[(z * 4 + 2) * 16 + x * 4 + 2]
+        })
+        .collect();
+    (0..1024).map(|index| cells[index % 16]).collect()
+}
+
+/// 20w06a changed the sides of walls from booleans to `none`, `low` or `tall`.
+fn fix_wall_connections(level: &mut Compound) {
+    for_each_palette_entry(level, |entry| {
+        let is_wall =
+            matches!(entry.get("Name"), Some(Value::String(name)) if name.ends_with("_wall"));
+        if !is_wall {
+            return;
+        }
+        if let Some(Value::Compound(properties)) = entry.get_mut("Properties") {
+            for side in &["north", "east", "south", "west"] {
+                if let Some(connection) = properties.get_mut(*side) {
+                    rename(connection, &[("true", "low"), ("false", "none")]);
+                }
+            }
+        }
+    });
+}
+
+/// 20w09a renamed zombie pigmen to zombified piglins.
+fn rename_zombified_piglins(level: &mut Compound) {
+    for_each_entity(level, &mut |entity| {
+        if let Some(id) = entity.get_mut("id") {
+            rename(
+                id,
+                &[("minecraft:zombie_pigman", "minecraft:zombified_piglin")],
+            );
+        }
+    });
+    rename_items(
+        level,
+        &[(
+            "minecraft:zombie_pigman_spawn_egg",
+            "minecraft:zombified_piglin_spawn_egg",
+        )],
+    );
+}
+
+/// 20w12a stored UUIDs as arrays of four ints rather than pairs of longs.
+fn convert_uuids(level: &mut Compound) {
+    for_each_entity(level, &mut |entity| {
+        move_uuid(entity, "UUIDMost", "UUIDLeast", "UUID");
+        // Projectiles
+        move_uuid(entity, "OwnerUUIDMost", "OwnerUUIDLeast", "Owner");
+
+        // Item entities stored their owner and thrower as compounds.
+        for key in &["Owner", "Thrower"] {
+            let uuid = match entity.get(*key) {
+                Some(Value::Compound(uuid)) => match (uuid.get("M"), uuid.get("L")) {
+                    (Some(&Value::Long(most)), Some(&Value::Long(least))) => {
+                        Some(uuid_to_int_array(most as u64, least as u64))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            };
+            if let Some(uuid) = uuid {
+                entity.insert((*key).to_owned(), uuid);
+            }
+        }
+
+        // Tamed animals stored their owner as a string.
+        if let Some(Value::String(owner)) = entity.remove("OwnerUUID") {
+            if let Ok(owner) = Uuid::parse_str(&owner) {
+                let (most, least) = owner.as_u64_pair();
+                entity.insert("Owner".to_owned(), uuid_to_int_array(most, least));
+            }
+        }
+    });
+}
+
+fn move_uuid(compound: &mut Compound, most_key: &str, least_key: &str, key: &str) {
+    let most = compound.remove(most_key);
+    let least = compound.remove(least_key);
+    if let (Some(Value::Long(most)), Some(Value::Long(least))) = (most, least) {
+        compound.insert(key.to_owned(), uuid_to_int_array(most as u64, least as u64));
+    }
+}
+
+fn uuid_to_int_array(most: u64, least: u64) -> Value {
+    Value::IntArray(vec![
+        (most >> 32) as i32,
+        most as i32,
+        (least >> 32) as i32,
+        least as i32,
+    ])
+}
+
+/// 20w17a stopped packing values across two longs in block states
+/// and heightmaps, leaving unused bits at the end of each long instead.
+fn align_packed_arrays(level: &mut Compound) {
+    for section in compounds_mut(level, "Sections") {
+        if let Some(Value::LongArray(states)) = section.get_mut("BlockStates") {
+            *states = align_packed_array(states, 4096);
+        }
+    }
+    if let Some(Value::Compound(heightmaps)) = level.get_mut("Heightmaps") {
+        for heightmap in heightmaps.values_mut() {
+            if let Value::LongArray(heights) = heightmap {
+                *heights = align_packed_array(heights, 256);
+            }
+        }
+    }
+}
+
+fn align_packed_array(data: &[i64], length: usize) -> Vec<i64> {
+    let bits_per_value = data.len() * 64 / length;
+    if bits_per_value == 0 || bits_per_value > 64 {
+        return data.to_vec();
+    }
+
+    let data: Vec<u64> = data.iter().map(|&x| x as u64).collect();
+    PackedArray::from_spanning_u64_vec(&data, length, bits_per_value)
+        .as_u64_slice()
+        .iter()
+        .map(|&x| x as i64)
+        .collect()
+}
+
+/// Renames the blocks in palettes and scheduled block updates.
+fn rename_blocks(level: &mut Compound, renames: &[(&str, &str)]) {
+    for_each_palette_entry(level, |entry| {
+        if let Some(name) = entry.get_mut("Name") {
+            rename(name, renames);
+        }
+    });
+    for tick in compounds_mut(level, "TileTicks") {
+        if let Some(name) = tick.get_mut("i") {
+            rename(name, renames);
+        }
+    }
+}
+
+/// Renames the items held by entities and block entities.
+fn rename_items(level: &mut Compound, renames: &[(&str, &str)]) {
+    let mut rename_held_items = |holder: &mut Compound| {
+        for key in ITEM_KEYS {
+            let items: Vec<&mut Compound> = match holder.get_mut(*key) {
+                Some(Value::Compound(item)) => vec![item],
+                Some(Value::List(items)) => items.iter_mut().filter_map(as_compound).collect(),
+                _ => continue,
+            };
+            for item in items {
+                if let Some(id) = item.get_mut("id") {
+                    rename(id, renames);
+                }
+            }
+        }
+    };
+    for_each_entity(level, &mut rename_held_items);
+    for block_entity in compounds_mut(level, "TileEntities") {
+        rename_held_items(block_entity);
+    }
+}
+
+fn for_each_palette_entry(level: &mut Compound, mut f: impl FnMut(&mut Compound)) {
+    for section in compounds_mut(level, "Sections") {
+        compounds_mut(section, "Palette").for_each(&mut f);
+    }
+}
+
+/// Calls `f` on each entity in the chunk, including passengers.
+fn for_each_entity(level: &mut Compound, f: &mut dyn FnMut(&mut Compound)) {
+    fn visit(entity: &mut Compound, f: &mut dyn FnMut(&mut Compound)) {
+        f(entity);
+        for passenger in compounds_mut(entity, "Passengers") {
+            visit(passenger, f);
+        }
+    }
+
+    for entity in compounds_mut(level, "Entities") {
+        visit(entity, f);
+    }
+}
+
+/// Iterates over the compounds in the list at `key`.
+fn compounds_mut<'a>(
+    compound: &'a mut Compound,
+    key: &str,
+) -> impl Iterator<Item = &'a mut Compound> {
+    let list = match compound.get_mut(key) {
+        Some(Value::List(list)) => Some(list),
+        _ => None,
+    };
+    list.into_iter().flatten().filter_map(as_compound)
+}
+
+fn as_compound(value: &mut Value) -> Option<&mut Compound> {
+    match value {
+        Value::Compound(compound) => Some(compound),
+        _ => None,
+    }
+}
+
+/// Renames a string tag if it appears in `renames`.
+fn rename(value: &mut Value, renames: &[(&str, &str)]) {
+    if let Value::String(name) = value {
+        if let Some((_, new)) = renames.iter().find(|(old, _)| *old == name.as_str()) {
+            *name = (*new).to_owned();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compound(entries: Vec<(&str, Value)>) -> Value {
+        Value::Compound(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+        )
+    }
+
+    fn string(s: &str) -> Value {
+        Value::String(s.to_owned())
+    }
+
+    fn chunk(version: i32, level: Vec<(&str, Value)>) -> Compound {
+        let mut root = Compound::new();
+        root.insert("DataVersion".to_owned(), Value::Int(version));
+        root.insert("Level".to_owned(), compound(level));
+        root
+    }
+
+    fn as_compound_ref(value: &Value) -> &Compound {
+        match value {
+            Value::Compound(compound) => compound,
+            _ => panic!("expected a compound"),
+        }
+    }
+
+    fn as_list(value: &Value) -> &[Value] {
+        match value {
+            Value::List(list) => list,
+            _ => panic!("expected a list"),
+        }
+    }
+
+    #[test]
+    fn rejects_unsupported_versions() {
+        assert!(matches!(
+            upgrade_chunk(&mut chunk(1343, vec![])),
+            Err(Error::UnsupportedDataVersion(1343))
+        ));
+        assert!(matches!(
+            upgrade_chunk(&mut chunk(DATA_VERSION + 1, vec![])),
+            Err(Error::UnsupportedDataVersion(_))
+        ));
+        assert!(matches!(
+            upgrade_chunk(&mut Compound::new()),
+            Err(Error::UnsupportedDataVersion(0))
+        ));
+    }
+
+    #[test]
+    fn upgrades_1_13_chunk() {
+        let mut states = vec![0i64; 320];
+        // Index 12 spans the first two longs with 5 bits per value.
+        states[0] = 0b1111 << 60;
+        states[1] = 0b1;
+        let section = compound(vec![
+            ("Y", Value::Byte(0)),
+            ("BlockStates", Value::LongArray(states.clone())),
+            (
+                "Palette",
+                Value::List(vec![
+                    compound(vec![("Name", string("minecraft:air"))]),
+                    compound(vec![
+                        ("Name", string("minecraft:cobblestone_wall")),
+                        (
+                            "Properties",
+                            compound(vec![("north", string("true")), ("up", string("true"))]),
+                        ),
+                    ]),
+                    compound(vec![("Name", string("minecraft:wall_sign"))]),
+                ]),
+            ),
+        ]);
+        let pigman = compound(vec![
+            ("id", string("minecraft:zombie_pigman")),
+            ("UUIDMost", Value::Long(1 << 32 | 2)),
+            ("UUIDLeast", Value::Long(3 << 32 | 4)),
+            (
+                "HandItems",
+                Value::List(vec![compound(vec![("id", string("minecraft:sign"))])]),
+            ),
+        ]);
+        let mut biomes = vec![1; 256];
+        biomes[2 * 16 + 2] = 4;
+        let mut root = chunk(
+            1631,
+            vec![
+                ("Sections", Value::List(vec![section])),
+                ("Biomes", Value::IntArray(biomes)),
+                ("Entities", Value::List(vec![pigman])),
+                ("Status", string("postprocessed")),
+            ],
+        );
+
+        assert_eq!(upgrade_chunk(&mut root).unwrap(), 1631);
+        assert_eq!(root["DataVersion"], Value::Int(DATA_VERSION));
+
+        let level = as_compound_ref(&root["Level"]);
+        assert_eq!(level["Status"], string("full"));
+
+        let biomes = match &level["Biomes"] {
+            Value::IntArray(biomes) => biomes,
+            _ => unreachable!(),
+        };
+        assert_eq!(biomes.len(), 1024);
+        assert!(biomes.iter().enumerate().all(|(i, &biome)| {
+            let expected = if i % 16 == 0 { 4 } else { 1 };
+            biome == expected
+        }));
+
+        let entity = &as_list(&level["Entities"])[0];
+        let expected = compound(vec![
+            ("id", string("minecraft:zombified_piglin")),
+            ("UUID", Value::IntArray(vec![1, 2, 3, 4])),
+            (
+                "HandItems",
+                Value::List(vec![compound(vec![("id", string("minecraft:oak_sign"))])]),
+            ),
+        ]);
+        assert_eq!(entity, &expected);
+
+        let section = as_compound_ref(&as_list(&level["Sections"])[0]);
+        let palette = as_list(&section["Palette"]);
+        assert_eq!(
+            palette[1],
+            compound(vec![
+                ("Name", string("minecraft:cobblestone_wall")),
+                (
+                    "Properties",
+                    compound(vec![("north", string("low")), ("up", string("true"))]),
+                ),
+            ])
+        );
+        assert_eq!(
+            palette[2],
+            compound(vec![("Name", string("minecraft:oak_wall_sign"))])
+        );
+
+        let states = match &section["BlockStates"] {
+            Value::LongArray(states) => states,
+            _ => unreachable!(),
+        };
+        let states = PackedArray::from_u64_vec(states.iter().map(|&x| x as u64).collect(), 4096);
+        assert_eq!(states.bits_per_value(), 5);
+        assert_eq!(states.get(12), Some(0b11111));
+        assert_eq!(states.iter().filter(|&value| value != 0).count(), 1);
+    }
+
+    #[test]
+    fn current_chunks_are_unchanged() {
+        let level = vec![("Status", string("postprocessed"))];
+        let mut root = chunk(DATA_VERSION, level.clone());
+        assert_eq!(upgrade_chunk(&mut root).unwrap(), DATA_VERSION);
+        assert_eq!(root, chunk(DATA_VERSION, level));
+    }
+}
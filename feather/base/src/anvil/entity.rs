@@ -1,10 +1,12 @@
 use arrayvec::ArrayVec;
-use libcraft_items::{Item, ItemStack, ItemStackBuilder};
+use libcraft_items::{Enchantment, EnchantmentKind, Item, ItemStack, ItemStackBuilder};
 use serde::ser::Error;
 use serde::{Deserialize, Serialize, Serializer};
 use thiserror::Error;
 
-use crate::{vec3, Position, Vec3d};
+use quill_common::components::{ActiveEffects, EffectInstance};
+
+use crate::{vec3, Position, StatusEffect, Vec3d};
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum EntityDataKind {
@@ -150,12 +152,31 @@ pub struct AnimalData {
     pub base: BaseEntityData,
     #[serde(rename = "Health")]
     pub health: f32,
+    #[serde(
+        rename = "ActiveEffects",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub active_effects: Vec<EffectNbt>,
 }
 
 impl AnimalData {
     /// Creates an `AnimalData` from its parameters.
     pub fn new(base: BaseEntityData, health: f32) -> Self {
-        Self { base, health }
+        Self {
+            base,
+            health,
+            active_effects: Vec::new(),
+        }
+    }
+
+    /// Reads the active status effects. Effects with unknown IDs are skipped.
+    pub fn read_active_effects(&self) -> ActiveEffects {
+        let mut effects = ActiveEffects::new();
+        for (effect, instance) in self.active_effects.iter().filter_map(EffectNbt::effect) {
+            effects.add(effect, instance);
+        }
+        effects
     }
 }
 
@@ -164,6 +185,67 @@ impl Default for AnimalData {
         AnimalData {
             base: Default::default(),
             health: 20.0,
+            active_effects: Vec::new(),
+        }
+    }
+}
+
+/// A status effect stored in an entity's NBT.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EffectNbt {
+    #[serde(rename = "Id")]
+    pub id: i8,
+    #[serde(rename = "Amplifier")]
+    pub amplifier: i8,
+    #[serde(rename = "Duration")]
+    pub duration: i32,
+    #[serde(rename = "Ambient", default)]
+    pub ambient: bool,
+    #[serde(rename = "ShowParticles", default = "default_true")]
+    pub show_particles: bool,
+    #[serde(rename = "ShowIcon", default = "default_true")]
+    pub show_icon: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl EffectNbt {
+    /// Gets the status effect described by these tags,
+    /// or `None` if the effect ID is unknown.
+    pub fn effect(&self) -> Option<(StatusEffect, EffectInstance)> {
+        let effect = StatusEffect::from_id(self.id as u8)?;
+        Some((
+            effect,
+            EffectInstance {
+                amplifier: self.amplifier as u8,
+                duration: self.duration.max(0) as u32,
+                ambient: self.ambient,
+                show_particles: self.show_particles,
+                show_icon: self.show_icon,
+            },
+        ))
+    }
+
+    /// Converts all effects in an `ActiveEffects` to NBT.
+    pub fn from_active_effects(effects: &ActiveEffects) -> Vec<Self> {
+        effects
+            .iter()
+            .map(|(effect, instance)| Self::from((effect, instance)))
+            .collect()
+    }
+}
+
+impl From<(StatusEffect, &EffectInstance)> for EffectNbt {
+    fn from((effect, instance): (StatusEffect, &EffectInstance)) -> Self {
+        Self {
+            id: effect.id() as i8,
+            amplifier: instance.amplifier as i8,
+            duration: instance.duration.min(i32::MAX as u32) as i32,
+            ambient: instance.ambient,
+            show_particles: instance.show_particles,
+            show_icon: instance.show_icon,
         }
     }
 }
@@ -231,7 +313,15 @@ where
 pub struct ItemNbt {
     #[serde(rename = "Damage")]
     pub damage: Option<i32>,
-    // TODO enchantments, display name, ...
+    #[serde(
+        rename = "Enchantments",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub enchantments: Vec<EnchantmentNbt>,
+    #[serde(rename = "Potion", default, skip_serializing_if = "Option::is_none")]
+    pub potion: Option<String>,
+    // TODO display name, ...
 }
 
 impl ItemNbt {
@@ -240,17 +330,28 @@ impl ItemNbt {
     /// # Panics
     /// Panics if `count` is zero.
     pub fn item_stack(nbt: &Option<Self>, item: Item, count: u8) -> ItemStack {
+        let builder = ItemStackBuilder::with_item(item).count(count as u32);
         match nbt {
-            Some(ItemNbt {
-                damage: Some(damage),
-            }) => ItemStackBuilder::with_item(item)
-                .count(count as u32)
-                .damage(*damage)
-                .into(),
-
-            Some(ItemNbt { damage: None }) | None => {
-                ItemStackBuilder::with_item(item).count(count as u32).into()
-            }
+            Some(nbt) => nbt.apply(builder).into(),
+            None => builder.into(),
+        }
+    }
+
+    /// Applies the damage, enchantments and potion in these tags to an `ItemStackBuilder`.
+    pub fn apply(&self, builder: ItemStackBuilder) -> ItemStackBuilder {
+        let mut builder = builder.apply_damage(self.damage);
+        if let Some(potion) = &self.potion {
+            builder = builder.potion(potion.as_str());
+        }
+        if self.enchantments.is_empty() {
+            builder
+        } else {
+            builder.enchantments(
+                self.enchantments
+                    .iter()
+                    .filter_map(EnchantmentNbt::enchantment)
+                    .collect(),
+            )
         }
     }
 }
@@ -263,6 +364,39 @@ where
         let stack = s.borrow();
         Self {
             damage: stack.damage_taken().map(|d| d as i32),
+            enchantments: stack
+                .enchantments()
+                .iter()
+                .map(EnchantmentNbt::from)
+                .collect(),
+            potion: stack.potion().map(str::to_owned),
+        }
+    }
+}
+
+/// An enchantment stored in an item's NBT tags.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EnchantmentNbt {
+    /// Namespaced identifier of the enchantment, e.g. `minecraft:sharpness`.
+    pub id: String,
+    #[serde(rename = "lvl")]
+    pub level: i16,
+}
+
+impl EnchantmentNbt {
+    /// Converts these tags into an `Enchantment`,
+    /// or returns `None` if the enchantment is unknown.
+    pub fn enchantment(&self) -> Option<Enchantment> {
+        EnchantmentKind::from_identifier(&self.id)
+            .map(|kind| Enchantment::new(kind, self.level.max(0) as u32))
+    }
+}
+
+impl From<&Enchantment> for EnchantmentNbt {
+    fn from(enchantment: &Enchantment) -> Self {
+        Self {
+            id: enchantment.kind().identifier(),
+            level: enchantment.level() as i16,
         }
     }
 }
@@ -31,6 +31,18 @@ pub struct LevelData {
     pub border_safe_zone: f64,
     #[serde(rename = "BorderSize")]
     pub border_size: f64,
+    #[serde(default)]
+    #[serde(rename = "BorderSizeLerpTarget")]
+    pub border_size_lerp_target: f64,
+    #[serde(default)]
+    #[serde(rename = "BorderSizeLerpTime")]
+    pub border_size_lerp_time: i64,
+    #[serde(default = "default_border_warning_blocks")]
+    #[serde(rename = "BorderWarningBlocks")]
+    pub border_warning_blocks: f64,
+    #[serde(default = "default_border_warning_time")]
+    #[serde(rename = "BorderWarningTime")]
+    pub border_warning_time: f64,
 
     #[serde(rename = "clearWeatherTime")]
     pub clear_weather_time: i32,
@@ -77,6 +89,14 @@ pub struct LevelData {
     pub generator_options: Option<SuperflatGeneratorOptions>,
 }
 
+fn default_border_warning_blocks() -> f64 {
+    5.0
+}
+
+fn default_border_warning_time() -> f64 {
+    15.0
+}
+
 impl LevelData {
     pub fn load_from_file(file: &mut File) -> anyhow::Result<Self> {
         let mut buf = vec![];
@@ -35,6 +35,33 @@ pub struct PlayerData {
     #[serde(rename = "SelectedItemSlot")]
     pub held_item: i32,
     pub abilities: PlayerAbilities,
+    #[serde(rename = "foodLevel", default = "default_food_level")]
+    pub food_level: i32,
+    #[serde(
+        rename = "foodSaturationLevel",
+        default = "default_food_saturation_level"
+    )]
+    pub food_saturation_level: f32,
+    #[serde(rename = "foodExhaustionLevel", default)]
+    pub food_exhaustion_level: f32,
+    #[serde(rename = "XpLevel", default)]
+    pub xp_level: i32,
+    /// Progress towards the next level, from 0 to 1.
+    #[serde(rename = "XpP", default)]
+    pub xp_progress: f32,
+    #[serde(rename = "XpTotal", default)]
+    pub xp_total: i32,
+    /// Seed for the enchantments offered by enchanting tables.
+    #[serde(rename = "XpSeed", default)]
+    pub xp_seed: i32,
+}
+
+fn default_food_level() -> i32 {
+    20
+}
+
+fn default_food_saturation_level() -> f32 {
+    5.0
 }
 
 /// Represents player's abilities (flying, invulnerability, speed, etc.)
@@ -138,6 +165,22 @@ impl InventorySlot {
             if let Some(damage) = nbt.damage {
                 tags_compound.insert(String::from("Damage"), Value::Int(damage));
             }
+            if !nbt.enchantments.is_empty() {
+                let enchantments = nbt
+                    .enchantments
+                    .into_iter()
+                    .map(|enchantment| {
+                        let mut compound = HashMap::new();
+                        compound.insert(String::from("id"), Value::String(enchantment.id));
+                        compound.insert(String::from("lvl"), Value::Short(enchantment.level));
+                        Value::Compound(compound)
+                    })
+                    .collect();
+                tags_compound.insert(String::from("Enchantments"), Value::List(enchantments));
+            }
+            if let Some(potion) = nbt.potion {
+                tags_compound.insert(String::from("Potion"), Value::String(potion));
+            }
         }
         compound.insert(String::from("tag"), Value::Compound(tags_compound));
         Value::Compound(compound)
@@ -208,7 +251,15 @@ mod tests {
             Gamemode::Spectator.to_i32().unwrap()
         );
         assert_eq!(player.inventory[0].item, "minecraft:diamond_shovel");
-        assert_eq!(player.inventory[0].nbt, Some(ItemNbt { damage: Some(3) }));
+        assert_eq!(
+            player.inventory[0].nbt,
+            Some(ItemNbt {
+                damage: Some(3),
+                ..Default::default()
+            })
+        );
+        assert_eq!(player.food_level, 20);
+        assert_eq!(player.food_saturation_level, 5.0);
     }
 
     #[test]
@@ -231,7 +282,10 @@ mod tests {
             count: 1,
             slot: 2,
             item: String::from(Item::DiamondAxe.name()),
-            nbt: Some(ItemNbt { damage: Some(42) }),
+            nbt: Some(ItemNbt {
+                damage: Some(42),
+                ..Default::default()
+            }),
         };
 
         let item_stack: ItemStack = slot.into();
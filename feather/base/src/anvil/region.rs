@@ -2,15 +2,27 @@
 //! of Anvil region files.
 
 use crate::{
-    chunk::{BlockStore, LightStore, PackedArray, Palette},
+    chunk::{
+        BlockStore, Heightmap, HeightmapFunction, HeightmapStore, LightStore, PackedArray, Palette,
+    },
     Chunk, ChunkPosition, ChunkSection,
 };
 
-use super::{block_entity::BlockEntityData, entity::EntityData};
+use super::{
+    block_entity::BlockEntityData,
+    data_fixer::{self, Compound},
+    entity::EntityData,
+};
 use bitvec::{bitvec, vec::BitVec};
 use blocks::BlockId;
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use flate2::{
+    read::{GzDecoder, ZlibDecoder},
+    write::ZlibEncoder,
+    Compression,
+};
 use libcraft_core::Biome;
+use nbt::Value;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::collections::BTreeMap;
@@ -20,18 +32,34 @@ use std::io::prelude::*;
 use std::io::{Cursor, SeekFrom};
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::{fs, io, iter};
 
 /// The length and width of a region, in chunks.
 const REGION_SIZE: usize = 32;
 
 /// The data version supported by this code, currently corresponding
-/// to 1.16.5.
-const DATA_VERSION: i32 = 2586;
+/// to 1.16.5. Chunks saved with older versions are upgraded by the
+/// [`data_fixer`].
+pub const DATA_VERSION: i32 = 2586;
+
+/// NBT type ID of a compound tag.
+const TAG_COMPOUND: u8 = 0x0a;
 
 /// Length, in bytes, of a sector.
 const SECTOR_BYTES: usize = 4096;
 
+/// Maximum number of sectors a chunk can occupy in the region file,
+/// limited by the one-byte sector count in the header. Larger chunks
+/// are stored in external `c.X.Z.mcc` files.
+const MAX_CHUNK_SECTORS: usize = 255;
+
+/// Flag set on the compression type of chunks stored in external files.
+const EXTERNAL_FLAG: u8 = 0x80;
+
+/// Number of saved chunks after which a checkpoint is written.
+const CHECKPOINT_INTERVAL: usize = 64;
+
 /// Represents the data for a chunk after the "Chunk [x, y]" tag.
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "PascalCase")]
@@ -44,7 +72,6 @@ pub struct ChunkRoot {
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "PascalCase")]
 pub struct ChunkLevel {
-    // TODO heightmaps, etc.
     #[serde(rename = "xPos")]
     x_pos: i32,
     #[serde(rename = "zPos")]
@@ -53,6 +80,8 @@ pub struct ChunkLevel {
     inhabited_time: i64,
     #[serde(default)]
     sections: Vec<LevelSection>,
+    #[serde(default)]
+    heightmaps: LevelHeightmaps,
     #[serde(serialize_with = "nbt::i32_array")]
     biomes: Vec<i32>,
     #[serde(default)]
@@ -97,6 +126,111 @@ pub struct LevelSection {
     sky_light: Vec<i8>,
 }
 
+/// Represents the heightmaps of a chunk in a region file.
+///
+/// `LIGHT_BLOCKING` was dropped by 1.14 but is still written,
+/// since vanilla ignores it and it saves recalculating it on load.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct LevelHeightmaps {
+    #[serde(serialize_with = "nbt::i64_array")]
+    #[serde(default)]
+    motion_blocking: Vec<i64>,
+    #[serde(serialize_with = "nbt::i64_array")]
+    #[serde(default)]
+    motion_blocking_no_leaves: Vec<i64>,
+    #[serde(serialize_with = "nbt::i64_array")]
+    #[serde(default)]
+    light_blocking: Vec<i64>,
+    #[serde(serialize_with = "nbt::i64_array")]
+    #[serde(default)]
+    ocean_floor: Vec<i64>,
+    #[serde(serialize_with = "nbt::i64_array")]
+    #[serde(default)]
+    world_surface: Vec<i64>,
+}
+
+/// The generation stage a chunk has completed, stored in its `Status` tag.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ChunkStatus {
+    Empty,
+    StructureStarts,
+    StructureReferences,
+    Biomes,
+    Noise,
+    Surface,
+    Carvers,
+    LiquidCarvers,
+    Features,
+    Light,
+    Spawn,
+    Heightmaps,
+    Full,
+}
+
+impl ChunkStatus {
+    const ALL: [ChunkStatus; 13] = [
+        ChunkStatus::Empty,
+        ChunkStatus::StructureStarts,
+        ChunkStatus::StructureReferences,
+        ChunkStatus::Biomes,
+        ChunkStatus::Noise,
+        ChunkStatus::Surface,
+        ChunkStatus::Carvers,
+        ChunkStatus::LiquidCarvers,
+        ChunkStatus::Features,
+        ChunkStatus::Light,
+        ChunkStatus::Spawn,
+        ChunkStatus::Heightmaps,
+        ChunkStatus::Full,
+    ];
+
+    /// Returns the name of this status in the `Status` tag.
+    pub fn name(self) -> &'static str {
+        match self {
+            ChunkStatus::Empty => "empty",
+            ChunkStatus::StructureStarts => "structure_starts",
+            ChunkStatus::StructureReferences => "structure_references",
+            ChunkStatus::Biomes => "biomes",
+            ChunkStatus::Noise => "noise",
+            ChunkStatus::Surface => "surface",
+            ChunkStatus::Carvers => "carvers",
+            ChunkStatus::LiquidCarvers => "liquid_carvers",
+            ChunkStatus::Features => "features",
+            ChunkStatus::Light => "light",
+            ChunkStatus::Spawn => "spawn",
+            ChunkStatus::Heightmaps => "heightmaps",
+            ChunkStatus::Full => "full",
+        }
+    }
+
+    /// Gets the status with the given name, as stored in the `Status` tag.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|status| status.name() == name)
+    }
+
+    /// Determines whether the terrain of a chunk
+    /// with this status has been generated.
+    pub fn has_terrain(self) -> bool {
+        self >= ChunkStatus::Noise
+    }
+
+    /// Determines whether all blocks of a chunk
+    /// with this status have been placed.
+    pub fn has_blocks(self) -> bool {
+        self >= ChunkStatus::Features
+    }
+
+    /// Determines whether the light of a chunk
+    /// with this status has been computed.
+    pub fn has_light(self) -> bool {
+        self >= ChunkStatus::Light
+    }
+}
+
 /// Represents a palette entry in a region file.
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "PascalCase")]
@@ -140,13 +274,33 @@ pub struct SectorBlock {
 }
 
 /// A region file handle.
+///
+/// Saved chunks are written to newly allocated sectors, and the header
+/// on disk is only updated at checkpoints (see [`RegionHandle::sync`])
+/// after the chunk data has been flushed. Until then, the header on disk
+/// keeps pointing to the previous versions of the chunks, whose sectors
+/// are not reused, so a crash never leaves the header pointing to
+/// partially written data.
 pub struct RegionHandle {
     /// The region file.
     file: File,
+    /// The directory containing the region file, where chunks
+    /// too large for the region file are stored as well.
+    dir: PathBuf,
+    /// The position of this region.
+    position: RegionPosition,
     /// The region file's header, pre-loaded into memory.
     header: RegionHeader,
     /// Sector allocator to allocate sectors where we can store chunks.
     allocator: SectorAllocator,
+    /// Sectors of replaced chunks, which can only be reused once
+    /// the header on disk no longer points to them.
+    pending_free: Vec<SectorBlock>,
+    /// External chunk files which are no longer needed once
+    /// the header on disk no longer points to them.
+    pending_removals: Vec<PathBuf>,
+    /// Number of chunks saved since the last checkpoint.
+    unsynced_chunks: usize,
 }
 
 impl RegionHandle {
@@ -159,28 +313,161 @@ impl RegionHandle {
     /// region file.
     pub fn load_chunk(
         &mut self,
-        mut pos: ChunkPosition,
+        pos: ChunkPosition,
     ) -> Result<(Chunk, Vec<EntityData>, Vec<BlockEntityData>), Error> {
-        // Get a copy of the original position before clipping
-        let original_pos = pos;
-        // Clip chunk position to region-local coordinates.
-        pos.x %= 32;
-        pos.z %= 32;
+        let (chunk, status, entities, block_entities) = self.load_partial_chunk(pos)?;
+        if !status.has_blocks() {
+            return Err(Error::IncompleteChunk(status));
+        }
+        Ok((chunk, entities, block_entities))
+    }
+
+    /// Loads the chunk at the given position like [`RegionHandle::load_chunk`],
+    /// but also loads chunks whose generation stopped after their terrain
+    /// was generated, returning their status so they can be finished.
+    ///
+    /// Chunks without terrain have no blocks to keep,
+    /// and fail with [`Error::IncompleteChunk`].
+    ///
+    /// # Panics
+    /// Panics if the specified chunk position is not within this
+    /// region file.
+    #[allow(clippy::type_complexity)]
+    pub fn load_partial_chunk(
+        &mut self,
+        pos: ChunkPosition,
+    ) -> Result<(Chunk, ChunkStatus, Vec<EntityData>, Vec<BlockEntityData>), Error> {
+        let data = self.read_chunk_data(pos)?;
+
+        // Chunks saved by older versions go through the data fixer
+        // before they can be deserialized.
+        let mut root = match nbt::from_reader::<_, ChunkRoot>(Cursor::new(&data)) {
+            Ok(root) if root.data_version == DATA_VERSION => root,
+            _ => upgrade_chunk_root(&data)?,
+        };
+
+        let level = &mut root.level;
+
+        // Chunks saved by earlier versions of Feather used the 1.13 name
+        // `postprocessed`. Unknown statuses are treated as complete so
+        // that their blocks are never discarded.
+        let status = ChunkStatus::from_name(&level.worldgen_status).unwrap_or(ChunkStatus::Full);
+        if !status.has_terrain() {
+            return Err(Error::IncompleteChunk(status));
+        }
+
+        let mut chunk = Chunk::new(pos);
+        chunk.set_inhabited_time(level.inhabited_time.max(0) as u64);
+        chunk.set_last_update(level.last_update.max(0) as u64);
+
+        // Read sections
+        for section in &mut level.sections {
+            read_section_into_chunk(section, &mut chunk, status.has_light())?;
+        }
+
+        // Read biomes
+        if level.biomes.len() != 1024 {
+            return Err(Error::IndexOutOfBounds);
+        }
+        for index in 0..1024 {
+            let id = level.biomes[index];
+            chunk.biomes_mut().as_slice_mut()[index] =
+                Biome::from_id(id as u32).ok_or(Error::InvalidBiomeId(id))?;
+        }
+
+        // Heightmaps of chunks which have not been finished
+        // may be missing or out of date.
+        let heightmaps = &level.heightmaps;
+        let recalculate = status != ChunkStatus::Full;
+        let motion_blocking = read_heightmap(&chunk, &heightmaps.motion_blocking, recalculate);
+        let motion_blocking_no_leaves =
+            read_heightmap(&chunk, &heightmaps.motion_blocking_no_leaves, recalculate);
+        let light_blocking = read_heightmap(&chunk, &heightmaps.light_blocking, recalculate);
+        let ocean_floor = read_heightmap(&chunk, &heightmaps.ocean_floor, recalculate);
+        let world_surface = read_heightmap(&chunk, &heightmaps.world_surface, recalculate);
+        *chunk.heightmaps_mut() = HeightmapStore {
+            motion_blocking,
+            motion_blocking_no_leaves,
+            light_blocking,
+            ocean_floor,
+            world_surface,
+        };
+
+        Ok((
+            chunk,
+            status,
+            level.entities.clone(),
+            level.block_entities.clone(),
+        ))
+    }
+
+    /// Loads the NBT of the chunk at the given position
+    /// without upgrading or deserializing it.
+    ///
+    /// # Panics
+    /// Panics if the specified chunk position is not within this
+    /// region file.
+    pub fn load_chunk_nbt(&mut self, pos: ChunkPosition) -> Result<Compound, Error> {
+        read_compound(&self.read_chunk_data(pos)?)
+    }
+
+    /// Saves the NBT of the chunk at the given position,
+    /// replacing the chunk stored there.
+    pub fn save_chunk_nbt(&mut self, pos: ChunkPosition, root: Compound) -> Result<(), Error> {
+        let mut buf = Vec::with_capacity(4096);
+        buf.write_u8(2).map_err(Error::Io)?; // Compression type: zlib
+
+        let mut encoder = ZlibEncoder::new(buf, Compression::default());
+        write_compound(root, &mut encoder)?;
+        let buf = encoder.finish().map_err(Error::Io)?;
+
+        self.write_chunk_data(pos, &buf)
+    }
+
+    /// Upgrades the chunk at the given position in place if it was
+    /// saved by an older version, returning the data version it was
+    /// saved with.
+    ///
+    /// # Panics
+    /// Panics if the specified chunk position is not within this
+    /// region file.
+    pub fn upgrade_chunk(&mut self, pos: ChunkPosition) -> Result<i32, Error> {
+        let mut root = self.load_chunk_nbt(pos)?;
+        let version = data_fixer::upgrade_chunk(&mut root)?;
+        if version != DATA_VERSION {
+            self.save_chunk_nbt(pos, root)?;
+        }
+        Ok(version)
+    }
 
-        // Get the offset of the chunk within the file
-        // so that it can be read.
-        let offset = self.header.location_for_chunk(pos).0.offset;
+    /// Reads the uncompressed NBT data of the chunk at the given position.
+    fn read_chunk_data(&mut self, pos: ChunkPosition) -> Result<Vec<u8>, Error> {
+        let location = self.header.location_for_chunk(pos);
 
         // If the chunk doesn't exist, return early
-        if !self.header.location_for_chunk(pos).exists() {
+        if !location.exists() {
             return Err(Error::ChunkNotExist);
         }
 
+        let (compression_type, buf) = self.read_sectors(location.0)?;
+        if compression_type & EXTERNAL_FLAG != 0 {
+            let buf = fs::read(self.external_chunk_path(pos)).map_err(Error::Io)?;
+            decompress(compression_type & !EXTERNAL_FLAG, &buf)
+        } else {
+            decompress(compression_type, &buf)
+        }
+    }
+
+    /// Reads the compression type and compressed data
+    /// stored in the given block.
+    fn read_sectors(&mut self, block: SectorBlock) -> Result<(u8, Vec<u8>), Error> {
         // Seek to the offset position. Note that since the offset in the header
         // is in "sectors" of 4KiB each, the value needs to be multiplied by SECTOR_BYTES
         // to get the offset in bytes.
         self.file
-            .seek(SeekFrom::Start(u64::from(offset) * SECTOR_BYTES as u64))
+            .seek(SeekFrom::Start(
+                u64::from(block.offset) * SECTOR_BYTES as u64,
+            ))
             .map_err(Error::Io)?;
 
         // A chunk begins with a four-byte, big-endian value
@@ -188,59 +475,19 @@ impl RegionHandle {
         // in bytes.
         let len = self.file.read_u32::<BigEndian>().map_err(Error::Io)?;
 
-        // Avoid DoS attacks
-        if len > 1_048_576 {
+        // The data has to fit into the block; this also
+        // avoids DoS attacks.
+        if len == 0 || len as usize + 4 > block.count as usize * SECTOR_BYTES {
             return Err(Error::ChunkTooLarge(len as usize));
         }
 
-        if len == 0 {
-            return Err(Error::ChunkTooLarge(0));
-        }
-
         // Read `len` bytes into memory.
         let mut buf = vec![0u8; len as usize];
         self.file.read_exact(&mut buf).map_err(Error::Io)?;
 
-        // The compression type is indicated by a byte.
-        // 1 corresponds to gzip compression, while 2
-        // corresponds to zlib.
-        let compression_type = buf[0];
-
-        // Parse NBT data
-        let cursor = Cursor::new(&buf[1..]);
-        let mut root: ChunkRoot = match compression_type {
-            1 => nbt::from_gzip_reader(cursor).map_err(Error::Nbt)?,
-            2 => nbt::from_zlib_reader(cursor).map_err(Error::Nbt)?,
-            _ => return Err(Error::InvalidCompression(compression_type)),
-        };
-
-        // Check data version
-        if root.data_version != DATA_VERSION {
-            return Err(Error::UnsupportedDataVersion(root.data_version));
-        }
-
-        let level = &mut root.level;
-
-        let mut chunk = Chunk::new(original_pos);
-
-        // Read sections
-        for section in &mut level.sections {
-            read_section_into_chunk(section, &mut chunk)?;
-        }
-
-        // Read biomes
-        if level.biomes.len() != 1024 {
-            return Err(Error::IndexOutOfBounds);
-        }
-        for index in 0..1024 {
-            let id = level.biomes[index];
-            chunk.biomes_mut().as_slice_mut()[index] =
-                Biome::from_id(id as u32).ok_or(Error::InvalidBiomeId(id))?;
-        }
-
-        // chunk.recalculate_heightmap();
-
-        Ok((chunk, level.entities.clone(), level.block_entities.clone()))
+        // The compression type is indicated by the first byte.
+        let compression_type = buf.remove(0);
+        Ok((compression_type, buf))
     }
 
     /// Checks if the specified chunk position is generated in this region.
@@ -252,7 +499,7 @@ impl RegionHandle {
     }
 
     /// Saves the given chunk to this region file. The header will be updated
-    /// accordingly and saved as well.
+    /// accordingly and saved at the next checkpoint.
     ///
     /// Behavior may be unexpected if this region file does not contain the given
     /// chunk position.
@@ -262,18 +509,6 @@ impl RegionHandle {
         entities: &[EntityData],
         block_entities: &[BlockEntityData],
     ) -> Result<(), Error> {
-        let chunk_pos = chunk.position();
-
-        let (local_x, local_z) = (chunk_pos.x % 32, chunk_pos.z % 32);
-
-        // Find position in header and deallocate it if it currently exists.
-        let location = self
-            .header
-            .location_for_chunk(ChunkPosition::new(local_x, local_z));
-        if location.exists() {
-            self.allocator.free(location.0);
-        }
-
         // Write chunk to `ChunkRoot` tag.
         let root = chunk_to_chunk_root(chunk, entities, block_entities);
 
@@ -283,37 +518,327 @@ impl RegionHandle {
 
         nbt::to_zlib_writer(&mut buf, &root, None).map_err(Error::Nbt)?;
 
-        let total_len = buf.len() + 4; // 4 bytes for length header
+        self.write_chunk_data(chunk.position(), &buf)
+    }
 
-        let sectors = (total_len + SECTOR_BYTES - 1) / SECTOR_BYTES;
+    /// Writes compressed chunk data, prefixed by its compression type,
+    /// for the given chunk position. The header is updated in memory
+    /// and written at the next checkpoint.
+    fn write_chunk_data(&mut self, pos: ChunkPosition, buf: &[u8]) -> Result<(), Error> {
+        let external_path = self.external_chunk_path(pos);
+
+        let stub;
+        let buf = if sectors_for(buf.len()) > MAX_CHUNK_SECTORS {
+            // Too large for the region file: store the data in an
+            // external file, leaving only the compression type here.
+            write_external_chunk(&external_path, &buf[1..]).map_err(Error::Io)?;
+            // An earlier save or delete since the last checkpoint may have
+            // queued the file for removal, but the new header points to it.
+            self.pending_removals.retain(|path| *path != external_path);
+            stub = [buf[0] | EXTERNAL_FLAG];
+            &stub[..]
+        } else {
+            if external_path.exists() {
+                self.pending_removals.push(external_path);
+            }
+            buf
+        };
 
-        let block = self.allocator.allocate(sectors as u32);
+        // Allocate new sectors before freeing the old ones, so that
+        // the previous version of the chunk stays intact until
+        // the header on disk no longer points to it.
+        let block = self.allocator.allocate(sectors_for(buf.len()) as u32);
 
-        // Write to file
         self.file
             .seek(SeekFrom::Start(block.offset as u64 * SECTOR_BYTES as u64))
             .map_err(Error::Io)?;
-
         self.file
-            .write_u32::<BigEndian>(buf.len() as u32)
+            .write_all(&pad_to_sectors(buf))
+            .map_err(Error::Io)?;
+
+        // Update header
+        let location = self.header.location_for_chunk(pos);
+        if location.exists() {
+            self.pending_free.push(location.0);
+        }
+        self.header
+            .set_location_for_chunk(pos, ChunkLocation(block));
+        self.header.set_timestamp_for_chunk(pos, unix_timestamp());
+
+        self.unsynced_chunks += 1;
+        if self.unsynced_chunks >= CHECKPOINT_INTERVAL {
+            self.sync()?;
+        }
+
+        Ok(())
+    }
+
+    /// Deletes the chunk at the given position from this region.
+    /// The header is written at the next checkpoint.
+    pub fn delete_chunk(&mut self, pos: ChunkPosition) {
+        let location = self.header.location_for_chunk(pos);
+        if !location.exists() {
+            return;
+        }
+
+        self.pending_free.push(location.0);
+        self.pending_removals.push(self.external_chunk_path(pos));
+        self.header.set_location_for_chunk(
+            pos,
+            ChunkLocation(SectorBlock {
+                offset: 0,
+                count: 0,
+            }),
+        );
+        self.header.set_timestamp_for_chunk(pos, 0);
+        self.unsynced_chunks += 1;
+    }
+
+    /// Rewrites this region file with its chunks stored contiguously,
+    /// removing the free space between them. Returns the number of
+    /// sectors the file shrank by.
+    ///
+    /// The compacted file is written next to the region file and then
+    /// renamed over it, so a crash leaves either version intact.
+    pub fn compact(&mut self) -> Result<u32, Error> {
+        self.sync()?;
+        let old_sectors = self.file_sectors()?;
+
+        let path = self.dir.join(region_file_name(self.position));
+        let temp_path = path.with_extension("mca.tmp");
+        let mut file = open_opts()
+            .create(true)
+            .truncate(true)
+            .open(&temp_path)
             .map_err(Error::Io)?;
-        self.file.write_all(&buf).map_err(Error::Io)?;
 
-        // Write padding to align to sector count
-        let padding_count = SECTOR_BYTES - total_len % SECTOR_BYTES;
+        // Chunks are written after the header, which
+        // is filled in once their locations are known.
+        let mut header = RegionHeader::default();
+        header.write_to(&mut file).map_err(Error::Io)?;
+
+        let mut offset = 2;
+        for index in 0..REGION_SIZE * REGION_SIZE {
+            let location = self.header.locations[index];
+            if !location.exists() {
+                continue;
+            }
+
+            let (compression_type, buf) = self.read_sectors(location.0)?;
+            let mut chunk = Vec::with_capacity(buf.len() + 1);
+            chunk.push(compression_type);
+            chunk.extend_from_slice(&buf);
 
-        for _ in 0..padding_count {
-            self.file.write_u8(0).map_err(Error::Io)?;
+            let count = sectors_for(chunk.len()) as u32;
+            file.write_all(&pad_to_sectors(&chunk)).map_err(Error::Io)?;
+            header.locations[index] = ChunkLocation(SectorBlock { offset, count });
+            header.timestamps[index] = self.header.timestamps[index];
+            offset += count;
         }
 
-        // Update header
-        self.header
-            .set_location_for_chunk(ChunkPosition::new(local_x, local_z), ChunkLocation(block));
+        file.seek(SeekFrom::Start(0)).map_err(Error::Io)?;
+        header.write_to(&mut file).map_err(Error::Io)?;
+        file.sync_all().map_err(Error::Io)?;
+
+        self.file = file;
+        fs::rename(&temp_path, &path).map_err(Error::Io)?;
+        self.allocator = SectorAllocator::new(&header, offset);
+        self.header = header;
+
+        Ok(old_sectors.saturating_sub(offset))
+    }
+
+    /// Writes a checkpoint: flushes saved chunk data to the disk,
+    /// then writes and flushes the header pointing to it.
+    /// Afterwards, sectors of replaced chunks can be reused.
+    pub fn sync(&mut self) -> Result<(), Error> {
+        if self.unsynced_chunks == 0 {
+            return Ok(());
+        }
+
+        self.file.sync_data().map_err(Error::Io)?;
         self.save_header().map_err(Error::Io)?;
+        self.file.sync_data().map_err(Error::Io)?;
+
+        for block in self.pending_free.drain(..) {
+            self.allocator.free(block);
+        }
+        for path in self.pending_removals.drain(..) {
+            match fs::remove_file(&path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(Error::Io(e)),
+                _ => (),
+            }
+        }
+        self.unsynced_chunks = 0;
 
         Ok(())
     }
 
+    /// Checks the header for chunks whose sectors lie outside
+    /// the file or overlap the header or another chunk.
+    pub fn verify(&self) -> Result<Vec<SectorProblem>, Error> {
+        let file_sectors = self.file_sectors()?;
+        let mut owners: Vec<Option<ChunkPosition>> = vec![None; file_sectors as usize];
+
+        let mut problems = Vec::new();
+        for index in 0..REGION_SIZE * REGION_SIZE {
+            let location = self.header.locations[index];
+            if !location.exists() {
+                continue;
+            }
+            let chunk = self.chunk_at_index(index);
+
+            let block = location.0;
+            if block.offset < 2 || block.offset + block.count > file_sectors {
+                problems.push(SectorProblem {
+                    chunk,
+                    kind: SectorProblemKind::OutOfRange,
+                });
+                continue;
+            }
+
+            for sector in block.offset..block.offset + block.count {
+                if let Some(other) = owners[sector as usize] {
+                    problems.push(SectorProblem {
+                        chunk,
+                        kind: SectorProblemKind::Overlapping(other),
+                    });
+                    break;
+                }
+                owners[sector as usize] = Some(chunk);
+            }
+        }
+
+        Ok(problems)
+    }
+
+    /// Rebuilds the header from the chunks which can be read.
+    ///
+    /// Entries which lie outside the file, overlap sectors claimed by an
+    /// earlier entry or cannot be read are dropped. Chunks which are stored
+    /// in unclaimed sectors but missing from the header are recovered; if
+    /// several copies of such a chunk are found, the first one wins.
+    pub fn repair(&mut self) -> Result<RepairSummary, Error> {
+        self.sync()?;
+
+        let file_sectors = self.file_sectors()?;
+        let mut used = bitvec![0; file_sectors as usize];
+        used.set(0, true);
+        used.set(1, true);
+
+        let mut header = RegionHeader::default();
+        let mut summary = RepairSummary::default();
+
+        for index in 0..REGION_SIZE * REGION_SIZE {
+            let location = self.header.locations[index];
+            if !location.exists() {
+                continue;
+            }
+            let chunk = self.chunk_at_index(index);
+
+            let block = location.0;
+            let range = block.offset as usize..(block.offset + block.count) as usize;
+            let valid = block.offset >= 2
+                && block.offset + block.count <= file_sectors
+                && used[range.clone()].not_any()
+                && self.chunk_position_in(block, Some(chunk)) == Some((chunk, block.count));
+
+            if valid {
+                used[range].set_all(true);
+                header.locations[index] = location;
+                header.timestamps[index] = self.header.timestamps[index];
+                summary.kept += 1;
+            } else {
+                summary.dropped += 1;
+            }
+        }
+
+        let mut offset = 2;
+        while offset < file_sectors {
+            if used[offset as usize] {
+                offset += 1;
+                continue;
+            }
+
+            let block = SectorBlock {
+                offset,
+                count: file_sectors - offset,
+            };
+            match self.chunk_position_in(block, None) {
+                Some((chunk, count))
+                    if RegionPosition::from_chunk(chunk) == self.position
+                        && !header.location_for_chunk(chunk).exists()
+                        && used[offset as usize..(offset + count) as usize].not_any() =>
+                {
+                    let block = SectorBlock { offset, count };
+                    used[offset as usize..(offset + count) as usize].set_all(true);
+                    header.set_location_for_chunk(chunk, ChunkLocation(block));
+                    header.set_timestamp_for_chunk(chunk, unix_timestamp());
+                    summary.recovered += 1;
+                    offset += count;
+                }
+                _ => offset += 1,
+            }
+        }
+
+        self.allocator = SectorAllocator::new(&header, file_sectors);
+        self.header = header;
+        self.save_header().map_err(Error::Io)?;
+        self.file.sync_data().map_err(Error::Io)?;
+
+        Ok(summary)
+    }
+
+    /// Reads the chunk stored in the given block, returning its position
+    /// and the number of sectors it occupies, or `None` if it cannot be read.
+    ///
+    /// Chunks stored in external files can only be read if their
+    /// position is known in advance.
+    fn chunk_position_in(
+        &mut self,
+        block: SectorBlock,
+        expected: Option<ChunkPosition>,
+    ) -> Option<(ChunkPosition, u32)> {
+        let (compression_type, buf) = self.read_sectors(block).ok()?;
+        let count = sectors_for(buf.len() + 1) as u32;
+        let data = if compression_type & EXTERNAL_FLAG != 0 {
+            let buf = fs::read(self.external_chunk_path(expected?)).ok()?;
+            decompress(compression_type & !EXTERNAL_FLAG, &buf).ok()?
+        } else {
+            decompress(compression_type, &buf).ok()?
+        };
+
+        let root = read_compound(&data).ok()?;
+        let level = match root.get("Level")? {
+            Value::Compound(level) => level,
+            _ => return None,
+        };
+        match (level.get("xPos")?, level.get("zPos")?) {
+            (Value::Int(x), Value::Int(z)) => Some((ChunkPosition::new(*x, *z), count)),
+            _ => None,
+        }
+    }
+
+    /// Returns the global position of the chunk at the given header index.
+    fn chunk_at_index(&self, index: usize) -> ChunkPosition {
+        ChunkPosition::new(
+            self.position.x * REGION_SIZE as i32 + (index % REGION_SIZE) as i32,
+            self.position.z * REGION_SIZE as i32 + (index / REGION_SIZE) as i32,
+        )
+    }
+
+    /// Returns the length of the region file in whole sectors.
+    fn file_sectors(&self) -> Result<u32, Error> {
+        let len = self.file.metadata().map_err(Error::Io)?.len();
+        Ok((len / SECTOR_BYTES as u64) as u32)
+    }
+
+    /// Returns the path of the external file used to store
+    /// the given chunk if it is too large for the region file.
+    fn external_chunk_path(&self, pos: ChunkPosition) -> PathBuf {
+        self.dir.join(format!("c.{}.{}.mcc", pos.x, pos.z))
+    }
+
     fn save_header(&mut self) -> Result<(), io::Error> {
         self.file.seek(SeekFrom::Start(0))?;
 
@@ -321,7 +846,131 @@ impl RegionHandle {
     }
 }
 
-fn read_section_into_chunk(section: &mut LevelSection, chunk: &mut Chunk) -> Result<(), Error> {
+impl Drop for RegionHandle {
+    fn drop(&mut self) {
+        // Errors can't be reported here; the header on disk
+        // still points to the previous versions of the chunks.
+        let _ = self.sync();
+    }
+}
+
+/// A problem with a chunk's location in a region header,
+/// found by [`RegionHandle::verify`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SectorProblem {
+    /// The global position of the chunk.
+    pub chunk: ChunkPosition,
+    pub kind: SectorProblemKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectorProblemKind {
+    /// The chunk's sectors overlap the header or
+    /// extend past the end of the file.
+    OutOfRange,
+    /// The chunk's sectors overlap those of the given chunk.
+    Overlapping(ChunkPosition),
+}
+
+/// The result of [`RegionHandle::repair`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RepairSummary {
+    /// Number of chunks kept in the header.
+    pub kept: usize,
+    /// Number of entries dropped from the header.
+    pub dropped: usize,
+    /// Number of chunks missing from the header which were recovered.
+    pub recovered: usize,
+}
+
+/// Returns the number of sectors needed to store
+/// compressed chunk data of the given length.
+fn sectors_for(len: usize) -> usize {
+    let total_len = len + 4; // 4 bytes for length header
+    total_len.div_ceil(SECTOR_BYTES)
+}
+
+/// Prefixes compressed chunk data with its length
+/// and pads it to a whole number of sectors.
+fn pad_to_sectors(buf: &[u8]) -> Vec<u8> {
+    let mut sectors = Vec::with_capacity(sectors_for(buf.len()) * SECTOR_BYTES);
+    sectors.extend_from_slice(&(buf.len() as u32).to_be_bytes());
+    sectors.extend_from_slice(buf);
+    sectors.resize(sectors_for(buf.len()) * SECTOR_BYTES, 0);
+    sectors
+}
+
+/// Decompresses chunk data with the given compression type.
+fn decompress(compression_type: u8, buf: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut data = Vec::new();
+    match compression_type {
+        1 => GzDecoder::new(buf).read_to_end(&mut data),
+        2 => ZlibDecoder::new(buf).read_to_end(&mut data),
+        3 => return Ok(buf.to_vec()),
+        _ => return Err(Error::InvalidCompression(compression_type)),
+    }
+    .map_err(Error::Io)?;
+    Ok(data)
+}
+
+/// Writes an external chunk file, replacing it atomically
+/// if it already exists.
+fn write_external_chunk(path: &Path, data: &[u8]) -> Result<(), io::Error> {
+    let temp_path = path.with_extension("mcc.tmp");
+    let mut file = File::create(&temp_path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    fs::rename(&temp_path, path)
+}
+
+fn unix_timestamp() -> u32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() as u32)
+}
+
+/// Upgrades and deserializes a chunk saved by an older version.
+fn upgrade_chunk_root(data: &[u8]) -> Result<ChunkRoot, Error> {
+    let mut root = read_compound(data)?;
+    data_fixer::upgrade_chunk(&mut root)?;
+
+    let mut buf = Vec::with_capacity(data.len());
+    write_compound(root, &mut buf)?;
+    nbt::from_reader(Cursor::new(buf)).map_err(Error::Nbt)
+}
+
+/// Reads uncompressed chunk NBT into a compound.
+fn read_compound(mut data: &[u8]) -> Result<Compound, Error> {
+    if data.read_u8().map_err(Error::Io)? != TAG_COMPOUND {
+        return Err(Error::MissingRootTag);
+    }
+    // The root tag is named, though the name is usually empty.
+    let name_len = data.read_u16::<BigEndian>().map_err(Error::Io)?;
+    data = data
+        .get(usize::from(name_len)..)
+        .ok_or(Error::MissingRootTag)?;
+
+    match Value::from_reader(TAG_COMPOUND, &mut data).map_err(Error::Nbt)? {
+        Value::Compound(root) => Ok(root),
+        _ => Err(Error::MissingRootTag),
+    }
+}
+
+/// Writes a compound as uncompressed chunk NBT.
+fn write_compound<W: Write>(root: Compound, writer: &mut W) -> Result<(), Error> {
+    writer.write_u8(TAG_COMPOUND).map_err(Error::Io)?;
+    writer.write_u16::<BigEndian>(0).map_err(Error::Io)?; // Empty name
+    Value::Compound(root).to_writer(writer).map_err(Error::Nbt)
+}
+
+/// Reads a section into the chunk. Light is only read if `has_light`
+/// is set; otherwise, or if the section has no light stored, it keeps
+/// the defaults of full sky light and no block light.
+fn read_section_into_chunk(
+    section: &mut LevelSection,
+    chunk: &mut Chunk,
+    has_light: bool,
+) -> Result<(), Error> {
     let data = &section.states;
 
     // Create palette
@@ -354,7 +1003,14 @@ fn read_section_into_chunk(section: &mut LevelSection, chunk: &mut Chunk) -> Res
 
     // Light
     // convert raw lighting data (4bits / block) into a BitArray
-    let convert_light_data = |light_data: &Vec<i8>| {
+    let convert_light_data = |light_data: &[i8], default: &PackedArray| {
+        if !has_light || light_data.is_empty() {
+            return Ok(default.clone());
+        }
+        if light_data.len() != 2048 {
+            return Err(Error::IndexOutOfBounds);
+        }
+
         let data = light_data
             .chunks(8)
             .map(|chunk| {
@@ -372,22 +1028,12 @@ fn read_section_into_chunk(section: &mut LevelSection, chunk: &mut Chunk) -> Res
                 u64::from_le_bytes(chunk)
             })
             .collect();
-        PackedArray::from_u64_vec(data, 4096)
+        Ok(PackedArray::from_u64_vec(data, 4096))
     };
 
-    if section.sky_light.is_empty() {
-        section.sky_light = vec![0; 2048];
-    }
-    if section.block_light.is_empty() {
-        section.block_light = vec![0; 2048];
-    }
-
-    if section.block_light.len() != 2048 || section.sky_light.len() != 2048 {
-        return Err(Error::IndexOutOfBounds);
-    }
-
-    let block_light = convert_light_data(&section.block_light);
-    let sky_light = convert_light_data(&section.sky_light);
+    let default_light = LightStore::new();
+    let block_light = convert_light_data(&section.block_light, default_light.block_light())?;
+    let sky_light = convert_light_data(&section.sky_light, default_light.sky_light())?;
 
     let light =
         LightStore::from_packed_arrays(block_light, sky_light).ok_or(Error::IndexOutOfBounds)?;
@@ -400,6 +1046,28 @@ fn read_section_into_chunk(section: &mut LevelSection, chunk: &mut Chunk) -> Res
     Ok(())
 }
 
+/// Reads a heightmap stored in a region file, recalculating
+/// it if `recalculate` is set or the stored data is invalid.
+fn read_heightmap<F: HeightmapFunction>(
+    chunk: &Chunk,
+    data: &[i64],
+    recalculate: bool,
+) -> Heightmap<F> {
+    let mut heightmap = Heightmap::new();
+    if !recalculate && data.len() == heightmap.as_u64_slice().len() {
+        for (index, &heights) in data.iter().enumerate() {
+            heightmap.set_height_index(index, heights);
+        }
+    } else {
+        heightmap.recalculate(|x, y, z| chunk.block_at(x, y, z).unwrap_or_else(BlockId::air));
+    }
+    heightmap
+}
+
+fn heightmap_to_nbt<F: HeightmapFunction>(heightmap: &Heightmap<F>) -> Vec<i64> {
+    heightmap.as_u64_slice().iter().map(|x| *x as i64).collect()
+}
+
 fn chunk_to_chunk_root(
     chunk: &Chunk,
     entities: &[EntityData],
@@ -409,8 +1077,8 @@ fn chunk_to_chunk_root(
         level: ChunkLevel {
             x_pos: chunk.position().x,
             z_pos: chunk.position().z,
-            last_update: 0,    // TODO
-            inhabited_time: 0, // TODO
+            last_update: chunk.last_update() as i64,
+            inhabited_time: chunk.inhabited_time() as i64,
             block_entities: block_entities.into(),
             sections: chunk
                 .sections()
@@ -436,6 +1104,15 @@ fn chunk_to_chunk_root(
                     }
                 })
                 .collect(),
+            heightmaps: LevelHeightmaps {
+                motion_blocking: heightmap_to_nbt(&chunk.heightmaps().motion_blocking),
+                motion_blocking_no_leaves: heightmap_to_nbt(
+                    &chunk.heightmaps().motion_blocking_no_leaves,
+                ),
+                light_blocking: heightmap_to_nbt(&chunk.heightmaps().light_blocking),
+                ocean_floor: heightmap_to_nbt(&chunk.heightmaps().ocean_floor),
+                world_surface: heightmap_to_nbt(&chunk.heightmaps().world_surface),
+            },
             biomes: chunk
                 .biomes()
                 .as_slice()
@@ -448,7 +1125,7 @@ fn chunk_to_chunk_root(
             scheduled_block_updates: vec![],          // TODO
             scheduled_liquid_updates: vec![],
             post_processing: vec![vec![]; 16],
-            worldgen_status: "postprocessed".into(),
+            worldgen_status: ChunkStatus::Full.name().into(),
         },
         data_version: DATA_VERSION,
     }
@@ -514,6 +1191,10 @@ impl SectorAllocator {
 
             let offset = chunk_location.0.offset;
             let count = chunk_location.0.count;
+            if (offset + count) as usize > used_sectors.len() {
+                // Reported by `RegionHandle::verify`.
+                continue;
+            }
             (offset..offset + count).for_each(|sector| used_sectors.set(sector as usize, true));
         }
 
@@ -611,6 +1292,8 @@ pub enum Error {
     IndexOutOfBounds,
     /// Invalid biome ID
     InvalidBiomeId(i32),
+    /// The chunk has only been partially generated
+    IncompleteChunk(ChunkStatus),
 }
 
 impl Display for Error {
@@ -627,11 +1310,16 @@ impl Display for Error {
             }
             Error::InvalidBlock(name) => f.write_str(&format!("Chunk contains invalid block {}", name))?,
             Error::ChunkNotExist => f.write_str("The chunk does not exist")?,
-            Error::UnsupportedDataVersion(_) => f.write_str("The chunk uses an unsupported data version. Feather currently only supports 1.16.5 region files.")?,
+            Error::UnsupportedDataVersion(version) => write!(f, "The chunk uses unsupported data version {}. Feather currently supports region files from 1.13 to 1.16.5.", version)?,
             Error::InvalidBlockType => f.write_str("Chunk contains invalid block type")?,
             Error::MissingRootTag => f.write_str("Chunk is missing a root NBT tag")?,
             Error::IndexOutOfBounds => f.write_str("Section index out of bounds")?,
             Error::InvalidBiomeId(id) => write!(f, "Invalid biome ID {}", id)?,
+            Error::IncompleteChunk(status) => write!(
+                f,
+                "The chunk has only been generated up to {}",
+                status.name()
+            )?,
         }
 
         Ok(())
@@ -651,13 +1339,27 @@ impl std::error::Error for Error {}
 /// in the region into memory; it only reads the file's
 /// header so that chunks can be retrieved later.
 pub fn load_region(dir: &Path, pos: RegionPosition) -> Result<RegionHandle, Error> {
+    open_region(dir, pos, open_opts().create(false))
+}
+
+/// Loads the region at the specified position like [`load_region`],
+/// but only opens the region file for reading. This works for worlds
+/// on read-only mounts or without write permissions.
+///
+/// Saving or deleting chunks through the returned handle fails.
+pub fn load_region_read_only(dir: &Path, pos: RegionPosition) -> Result<RegionHandle, Error> {
+    open_region(dir, pos, OpenOptions::new().read(true))
+}
+
+fn open_region(
+    dir: &Path,
+    pos: RegionPosition,
+    options: &OpenOptions,
+) -> Result<RegionHandle, Error> {
     let mut file = {
         let buf = region_file_path(dir, pos);
 
-        open_opts()
-            .create(false)
-            .open(buf.as_path())
-            .map_err(Error::Io)?
+        options.open(buf.as_path()).map_err(Error::Io)?
     };
 
     let header = read_header(&mut file)?;
@@ -669,8 +1371,13 @@ pub fn load_region(dir: &Path, pos: RegionPosition) -> Result<RegionHandle, Erro
 
     Ok(RegionHandle {
         file,
+        dir: dir.join("region"),
+        position: pos,
         header,
         allocator,
+        pending_free: Vec::new(),
+        pending_removals: Vec::new(),
+        unsynced_chunks: 0,
     })
 }
 
@@ -700,8 +1407,13 @@ pub fn create_region(dir: &Path, pos: RegionPosition) -> Result<RegionHandle, Er
     let allocator = SectorAllocator::new(&header, 2);
     Ok(RegionHandle {
         file,
+        dir: dir.join("region"),
+        position: pos,
         header,
         allocator,
+        pending_free: Vec::new(),
+        pending_removals: Vec::new(),
+        unsynced_chunks: 0,
     })
 }
 
@@ -715,10 +1427,15 @@ fn open_opts() -> OpenOptions {
 
 fn region_file_path(dir: &Path, pos: RegionPosition) -> PathBuf {
     let mut buf = dir.to_path_buf();
-    buf.push(format!("region/r.{}.{}.mca", pos.x, pos.z));
+    buf.push("region");
+    buf.push(region_file_name(pos));
     buf
 }
 
+fn region_file_name(pos: RegionPosition) -> String {
+    format!("r.{}.{}.mca", pos.x, pos.z)
+}
+
 fn create_region_dir(dir: &Path) -> Result<(), io::Error> {
     let mut dir = dir.to_path_buf();
     dir.push("region");
@@ -811,6 +1528,12 @@ impl RegionHeader {
         self.locations[index] = location;
     }
 
+    /// Sets the modification timestamp for the given chunk position.
+    fn set_timestamp_for_chunk(&mut self, pos: ChunkPosition, timestamp: u32) {
+        let index = Self::index(pos);
+        self.timestamps[index] = timestamp;
+    }
+
     /// Writes this header to the given writer.
     fn write_to<W>(&self, w: &mut W) -> Result<(), io::Error>
     where
@@ -856,6 +1579,10 @@ pub struct RegionPosition {
 }
 
 impl RegionPosition {
+    pub fn new(x: i32, z: i32) -> Self {
+        Self { x, z }
+    }
+
     /// Returns the coordinates of the region corresponding
     /// to the specified chunk position.
     pub fn from_chunk(chunk_coords: ChunkPosition) -> Self {
@@ -869,6 +1596,8 @@ impl RegionPosition {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_pcg::Pcg64Mcg;
 
     #[test]
     fn test_sector_allocator() {
@@ -921,4 +1650,327 @@ mod tests {
             }
         );
     }
+
+    /// Builds a minimal chunk compound with the given payload.
+    fn chunk_nbt(pos: ChunkPosition, payload: Vec<i8>) -> Compound {
+        let mut level = Compound::new();
+        level.insert("xPos".to_owned(), Value::Int(pos.x));
+        level.insert("zPos".to_owned(), Value::Int(pos.z));
+        level.insert("Payload".to_owned(), Value::ByteArray(payload));
+
+        let mut root = Compound::new();
+        root.insert("Level".to_owned(), Value::Compound(level));
+        root
+    }
+
+    /// Builds the NBT of a chunk with a single stone block at (1, 64, 2),
+    /// block light 9 above it, and a `WORLD_SURFACE` heightmap of 100
+    /// at (0, 0) which doesn't match the blocks.
+    fn stone_chunk_nbt(pos: ChunkPosition, status: &str) -> Compound {
+        let index = (2 << 4) | 1;
+        let mut states = vec![0; 256];
+        states[index / 16] = 1 << (index % 16 * 4);
+        let light_index = (1 << 8) | index;
+        let mut block_light = vec![0; 2048];
+        block_light[light_index / 2] = 9 << (light_index % 2 * 4);
+
+        let palette_entry = |name: &str| {
+            let mut entry = Compound::new();
+            entry.insert("Name".to_owned(), Value::String(name.to_owned()));
+            Value::Compound(entry)
+        };
+        let mut section = Compound::new();
+        section.insert("Y".to_owned(), Value::Byte(4));
+        section.insert(
+            "Palette".to_owned(),
+            Value::List(vec![
+                palette_entry("minecraft:air"),
+                palette_entry("minecraft:stone"),
+            ]),
+        );
+        section.insert("BlockStates".to_owned(), Value::LongArray(states));
+        section.insert("BlockLight".to_owned(), Value::ByteArray(block_light));
+
+        let mut world_surface = vec![0; 37];
+        world_surface[0] = 100;
+        let mut heightmaps = Compound::new();
+        heightmaps.insert("WORLD_SURFACE".to_owned(), Value::LongArray(world_surface));
+
+        let mut level = Compound::new();
+        level.insert("xPos".to_owned(), Value::Int(pos.x));
+        level.insert("zPos".to_owned(), Value::Int(pos.z));
+        level.insert("LastUpdate".to_owned(), Value::Long(5678));
+        level.insert("InhabitedTime".to_owned(), Value::Long(1234));
+        level.insert("Status".to_owned(), Value::String(status.to_owned()));
+        level.insert("Biomes".to_owned(), Value::IntArray(vec![1; 1024]));
+        level.insert(
+            "Sections".to_owned(),
+            Value::List(vec![Value::Compound(section)]),
+        );
+        level.insert("Heightmaps".to_owned(), Value::Compound(heightmaps));
+
+        let mut root = Compound::new();
+        root.insert("DataVersion".to_owned(), Value::Int(DATA_VERSION));
+        root.insert("Level".to_owned(), Value::Compound(level));
+        root
+    }
+
+    #[test]
+    fn load_complete_chunk() {
+        let dir = tempfile::tempdir().unwrap();
+        let pos = ChunkPosition::new(3, -2);
+        let mut region = create_region(dir.path(), RegionPosition::from_chunk(pos)).unwrap();
+        region
+            .save_chunk_nbt(pos, stone_chunk_nbt(pos, "full"))
+            .unwrap();
+
+        let (chunk, _, _) = region.load_chunk(pos).unwrap();
+        assert_eq!(chunk.block_at(1, 64, 2), Some(BlockId::stone()));
+        assert_eq!(chunk.inhabited_time(), 1234);
+        assert_eq!(chunk.last_update(), 5678);
+        assert_eq!(chunk.block_light_at(1, 65, 2), Some(9));
+        assert_eq!(chunk.sky_light_at(1, 65, 2), Some(15));
+
+        // Stored heightmaps are kept, and missing ones are recalculated.
+        let heightmaps = chunk.heightmaps();
+        assert_eq!(heightmaps.world_surface.height(0, 0), Some(100));
+        assert_eq!(heightmaps.motion_blocking.height(1, 2), Some(65));
+    }
+
+    #[test]
+    fn load_incomplete_chunk() {
+        let dir = tempfile::tempdir().unwrap();
+        let pos = ChunkPosition::new(0, 0);
+        let mut region = create_region(dir.path(), RegionPosition::new(0, 0)).unwrap();
+
+        // Chunks without all their blocks are generated again.
+        region
+            .save_chunk_nbt(pos, stone_chunk_nbt(pos, "noise"))
+            .unwrap();
+        assert!(matches!(
+            region.load_chunk(pos),
+            Err(Error::IncompleteChunk(ChunkStatus::Noise))
+        ));
+
+        // ...unless they are loaded to be finished, keeping their blocks.
+        let (chunk, status, _, _) = region.load_partial_chunk(pos).unwrap();
+        assert_eq!(status, ChunkStatus::Noise);
+        assert_eq!(chunk.block_at(1, 64, 2), Some(BlockId::stone()));
+
+        // Chunks without terrain have no blocks to keep.
+        region
+            .save_chunk_nbt(pos, stone_chunk_nbt(pos, "biomes"))
+            .unwrap();
+        assert!(matches!(
+            region.load_partial_chunk(pos),
+            Err(Error::IncompleteChunk(ChunkStatus::Biomes))
+        ));
+
+        // Chunks which were never lit are finished on load.
+        region
+            .save_chunk_nbt(pos, stone_chunk_nbt(pos, "features"))
+            .unwrap();
+        let (chunk, _, _) = region.load_chunk(pos).unwrap();
+        assert_eq!(chunk.block_at(1, 64, 2), Some(BlockId::stone()));
+        assert_eq!(chunk.block_light_at(1, 65, 2), Some(0));
+        assert_eq!(chunk.heightmaps().world_surface.height(0, 0), Some(0));
+        assert_eq!(chunk.heightmaps().world_surface.height(1, 2), Some(65));
+    }
+
+    #[test]
+    fn chunk_root_fields() {
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        chunk.set_block_at(1, 64, 2, BlockId::stone());
+        chunk.set_inhabited_time(1234);
+        chunk.set_last_update(5678);
+
+        let level = chunk_to_chunk_root(&chunk, &[], &[]).level;
+        assert_eq!(level.inhabited_time, 1234);
+        assert_eq!(level.last_update, 5678);
+        assert_eq!(level.worldgen_status, "full");
+        assert_eq!(
+            level.heightmaps.motion_blocking,
+            heightmap_to_nbt(&chunk.heightmaps().motion_blocking)
+        );
+        assert_eq!(level.heightmaps.world_surface.len(), 37);
+    }
+
+    #[test]
+    fn header_written_at_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let pos = ChunkPosition::new(1, 2);
+        let mut region = create_region(dir.path(), RegionPosition::new(0, 0)).unwrap();
+        region
+            .save_chunk_nbt(pos, chunk_nbt(pos, vec![1; 16]))
+            .unwrap();
+
+        let path = region_file_path(dir.path(), RegionPosition::new(0, 0));
+        let on_disk = read_header(&mut File::open(&path).unwrap()).unwrap();
+        assert!(!on_disk.location_for_chunk(pos).exists());
+
+        region.sync().unwrap();
+        let on_disk = read_header(&mut File::open(&path).unwrap()).unwrap();
+        assert!(on_disk.location_for_chunk(pos).exists());
+
+        assert_eq!(
+            region.load_chunk_nbt(pos).unwrap(),
+            chunk_nbt(pos, vec![1; 16])
+        );
+    }
+
+    #[test]
+    fn read_only_region() {
+        let dir = tempfile::tempdir().unwrap();
+        let pos = ChunkPosition::new(1, 2);
+        let mut region = create_region(dir.path(), RegionPosition::new(0, 0)).unwrap();
+        region
+            .save_chunk_nbt(pos, chunk_nbt(pos, vec![1; 16]))
+            .unwrap();
+        region.sync().unwrap();
+        drop(region);
+
+        let path = region_file_path(dir.path(), RegionPosition::new(0, 0));
+        let mut permissions = fs::metadata(&path).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&path, permissions).unwrap();
+
+        let mut region = load_region_read_only(dir.path(), RegionPosition::new(0, 0)).unwrap();
+        assert_eq!(
+            region.load_chunk_nbt(pos).unwrap(),
+            chunk_nbt(pos, vec![1; 16])
+        );
+        assert!(region
+            .save_chunk_nbt(pos, chunk_nbt(pos, vec![2; 16]))
+            .is_err());
+    }
+
+    #[test]
+    fn oversized_chunk() {
+        let dir = tempfile::tempdir().unwrap();
+        let pos = ChunkPosition::new(-3, 4);
+        let mut rng = Pcg64Mcg::seed_from_u64(33);
+        let payload: Vec<i8> = (0..2 * 1024 * 1024).map(|_| rng.gen()).collect();
+
+        let mut region = create_region(dir.path(), RegionPosition::from_chunk(pos)).unwrap();
+        region
+            .save_chunk_nbt(pos, chunk_nbt(pos, payload.clone()))
+            .unwrap();
+        let external = dir.path().join("region/c.-3.4.mcc");
+        assert!(external.exists());
+        assert_eq!(region.load_chunk_nbt(pos).unwrap(), chunk_nbt(pos, payload));
+
+        region
+            .save_chunk_nbt(pos, chunk_nbt(pos, vec![2; 16]))
+            .unwrap();
+        region.sync().unwrap();
+        assert!(!external.exists());
+        assert_eq!(
+            region.load_chunk_nbt(pos).unwrap(),
+            chunk_nbt(pos, vec![2; 16])
+        );
+    }
+
+    #[test]
+    fn oversized_chunk_saved_again_before_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let pos = ChunkPosition::new(-3, 4);
+        let mut rng = Pcg64Mcg::seed_from_u64(34);
+        let payload: Vec<i8> = (0..2 * 1024 * 1024).map(|_| rng.gen()).collect();
+        let external = dir.path().join("region/c.-3.4.mcc");
+
+        let mut region = create_region(dir.path(), RegionPosition::from_chunk(pos)).unwrap();
+        region
+            .save_chunk_nbt(pos, chunk_nbt(pos, payload.clone()))
+            .unwrap();
+        region.sync().unwrap();
+
+        // Oversized, then small, then oversized again.
+        region
+            .save_chunk_nbt(pos, chunk_nbt(pos, vec![2; 16]))
+            .unwrap();
+        region
+            .save_chunk_nbt(pos, chunk_nbt(pos, payload.clone()))
+            .unwrap();
+        region.sync().unwrap();
+        assert!(external.exists());
+        assert_eq!(
+            region.load_chunk_nbt(pos).unwrap(),
+            chunk_nbt(pos, payload.clone())
+        );
+
+        // Deleted, then saved oversized.
+        region.delete_chunk(pos);
+        region
+            .save_chunk_nbt(pos, chunk_nbt(pos, payload.clone()))
+            .unwrap();
+        region.sync().unwrap();
+        assert!(external.exists());
+        assert_eq!(region.load_chunk_nbt(pos).unwrap(), chunk_nbt(pos, payload));
+    }
+
+    #[test]
+    fn verify_and_repair() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (ChunkPosition::new(0, 0), ChunkPosition::new(5, 7));
+        let mut region = create_region(dir.path(), RegionPosition::new(0, 0)).unwrap();
+        region.save_chunk_nbt(a, chunk_nbt(a, vec![1; 16])).unwrap();
+        region.save_chunk_nbt(b, chunk_nbt(b, vec![2; 16])).unwrap();
+        region.sync().unwrap();
+        assert_eq!(region.verify().unwrap(), vec![]);
+
+        // Point `b` at the sectors of `a`.
+        let location = region.header.location_for_chunk(a);
+        region.header.set_location_for_chunk(b, location);
+        assert_eq!(
+            region.verify().unwrap(),
+            vec![SectorProblem {
+                chunk: b,
+                kind: SectorProblemKind::Overlapping(a),
+            }]
+        );
+
+        let summary = region.repair().unwrap();
+        assert_eq!(
+            summary,
+            RepairSummary {
+                kept: 1,
+                dropped: 1,
+                recovered: 1,
+            }
+        );
+        assert_eq!(region.verify().unwrap(), vec![]);
+        assert_eq!(region.load_chunk_nbt(b).unwrap(), chunk_nbt(b, vec![2; 16]));
+    }
+
+    #[test]
+    fn delete_and_compact() {
+        let dir = tempfile::tempdir().unwrap();
+        let chunks = [
+            ChunkPosition::new(0, 0),
+            ChunkPosition::new(1, 0),
+            ChunkPosition::new(2, 0),
+        ];
+        let mut region = create_region(dir.path(), RegionPosition::new(0, 0)).unwrap();
+        for &pos in &chunks {
+            region
+                .save_chunk_nbt(pos, chunk_nbt(pos, vec![1; 16]))
+                .unwrap();
+        }
+        region.delete_chunk(chunks[1]);
+        assert!(!region.check_chunk_existence(chunks[1]));
+
+        assert_eq!(region.compact().unwrap(), 1);
+        assert_eq!(region.verify().unwrap(), vec![]);
+        drop(region);
+
+        let mut region = load_region(dir.path(), RegionPosition::new(0, 0)).unwrap();
+        assert_eq!(region.file_sectors().unwrap(), 4);
+        assert!(!region.check_chunk_existence(chunks[1]));
+        for &pos in &[chunks[0], chunks[2]] {
+            assert_eq!(
+                region.load_chunk_nbt(pos).unwrap(),
+                chunk_nbt(pos, vec![1; 16])
+            );
+        }
+    }
 }
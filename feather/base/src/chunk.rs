@@ -64,6 +64,12 @@ pub struct Chunk {
     heightmaps: HeightmapStore,
 
     position: ChunkPosition,
+
+    /// Number of ticks players have spent near this chunk.
+    inhabited_time: u64,
+
+    /// Game time at which this chunk was last saved.
+    last_update: u64,
 }
 
 impl Default for Chunk {
@@ -77,6 +83,8 @@ impl Default for Chunk {
             biomes: BiomeStore::default(),
             position: ChunkPosition::new(0, 0),
             heightmaps: HeightmapStore::new(),
+            inhabited_time: 0,
+            last_update: 0,
         }
     }
 }
@@ -207,7 +215,7 @@ impl Chunk {
 
     pub fn set_block_light_at(&mut self, x: usize, y: usize, z: usize, light: u8) -> Option<()> {
         if let Some(section) = self.section_for_y_mut(y)? {
-            section.set_block_light_at(x, y, z, light)
+            section.set_block_light_at(x, y % SECTION_HEIGHT, z, light)
         } else {
             Some(())
         }
@@ -215,7 +223,7 @@ impl Chunk {
 
     pub fn set_sky_light_at(&mut self, x: usize, y: usize, z: usize, light: u8) -> Option<()> {
         if let Some(section) = self.section_for_y_mut(y)? {
-            section.set_sky_light_at(x, y, z, light)
+            section.set_sky_light_at(x, y % SECTION_HEIGHT, z, light)
         } else {
             Some(())
         }
@@ -253,6 +261,26 @@ impl Chunk {
         &mut self.heightmaps
     }
 
+    /// Gets the number of ticks players have spent near this chunk.
+    pub fn inhabited_time(&self) -> u64 {
+        self.inhabited_time
+    }
+
+    /// Sets the number of ticks players have spent near this chunk.
+    pub fn set_inhabited_time(&mut self, ticks: u64) {
+        self.inhabited_time = ticks;
+    }
+
+    /// Gets the game time at which this chunk was last saved.
+    pub fn last_update(&self) -> u64 {
+        self.last_update
+    }
+
+    /// Sets the game time at which this chunk was last saved.
+    pub fn set_last_update(&mut self, game_time: u64) {
+        self.last_update = game_time;
+    }
+
     /// Gets the chunk section at index `y`.
     pub fn section(&self, y: isize) -> Option<&ChunkSection> {
         self.sections.get((y + 1) as usize)?.as_ref()
@@ -37,6 +37,27 @@ impl PackedArray {
         }
     }
 
+    /// Creates a `PackedArray` from raw `u64` data in the layout
+    /// used before Minecraft 1.16, where a value may span two `u64`s.
+    ///
+    /// # Panics
+    /// Panics if `bits` is too short to hold `length` values of
+    /// `bits_per_value` bits.
+    pub fn from_spanning_u64_vec(bits: &[u64], length: usize, bits_per_value: usize) -> Self {
+        assert!(bits.len() * 64 >= length * bits_per_value);
+        let mask = (1u64 << bits_per_value) - 1;
+        let values = (0..length).map(|index| {
+            let bit = index * bits_per_value;
+            let (u64_index, bit_index) = (bit / 64, bit % 64);
+            let mut value = bits[u64_index] >> bit_index;
+            if bit_index + bits_per_value > 64 {
+                value |= bits[u64_index + 1] << (64 - bit_index);
+            }
+            value & mask
+        });
+        Self::from_iter(values, bits_per_value)
+    }
+
     /// Gets the value at the given index.
     #[inline]
     pub fn get(&self, index: usize) -> Option<u64> {
@@ -289,6 +310,26 @@ mod tests {
         assert!(array.iter().all(|x| x == 256));
     }
 
+    #[test]
+    fn from_spanning() {
+        let mut rng = Pcg64Mcg::seed_from_u64(12);
+        let bits_per_value = 5;
+        let oracle: Vec<u64> = (0..4096).map(|_| rng.gen_range(0..32)).collect();
+
+        let mut spanning = vec![0u64; 4096 * bits_per_value / 64];
+        for (index, &value) in oracle.iter().enumerate() {
+            let bit = index * bits_per_value;
+            spanning[bit / 64] |= value << (bit % 64);
+            if bit % 64 + bits_per_value > 64 {
+                spanning[bit / 64 + 1] |= value >> (64 - bit % 64);
+            }
+        }
+
+        let array = PackedArray::from_spanning_u64_vec(&spanning, 4096, bits_per_value);
+        assert_eq!(array.bits_per_value(), bits_per_value);
+        assert!(array.iter().eq(oracle.iter().copied()));
+    }
+
     #[test]
     #[should_panic]
     fn fill_too_large() {
@@ -13,12 +13,15 @@ pub type ChunkHandle = Arc<ChunkLock>;
 #[derive(Debug)]
 pub struct ChunkLock {
     loaded: AtomicBool,
+    /// Whether the chunk changed since it was last saved.
+    dirty: AtomicBool,
     lock: RwLock<Chunk>,
 }
 impl ChunkLock {
     pub fn new(chunk: Chunk, loaded: bool) -> Self {
         Self {
             loaded: AtomicBool::new(loaded),
+            dirty: AtomicBool::new(false),
             lock: RwLock::new(chunk),
         }
     }
@@ -42,6 +45,21 @@ impl ChunkLock {
         self.loaded.swap(true, Ordering::SeqCst)
     }
 
+    /// Returns whether the chunk changed since it was last saved.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.load(Ordering::SeqCst)
+    }
+
+    /// Marks the chunk as changed, so that it is saved again.
+    pub fn mark_dirty(&self) {
+        self.dirty.store(true, Ordering::SeqCst);
+    }
+
+    /// Marks the chunk as saved and returns whether it was dirty.
+    pub fn take_dirty(&self) -> bool {
+        self.dirty.swap(false, Ordering::SeqCst)
+    }
+
     /// Locks this chunk with read acccess. Doesn't block.
     /// Returns None if the chunk is unloaded or locked for writing, Some otherwise.
     pub fn try_read(&self) -> Option<RwLockReadGuard<Chunk>> {
@@ -113,6 +131,15 @@ mod tests {
         assert!(lock.try_read().is_some())
     }
     #[test]
+    fn dirty_flag() {
+        let lock = empty_lock(0, 0, true);
+        assert!(!lock.is_dirty());
+        lock.mark_dirty();
+        assert!(lock.is_dirty());
+        assert!(lock.take_dirty());
+        assert!(!lock.take_dirty());
+    }
+    #[test]
     fn multithreaded() {
         let lock = Arc::new(empty_lock(0, 0, true));
         let mut handles: Vec<JoinHandle<()>> = vec![];
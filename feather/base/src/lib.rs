@@ -23,7 +23,8 @@ pub use chunk_lock::*;
 
 pub use libcraft_blocks::{BlockKind, BlockState};
 pub use libcraft_core::{
-    position, vec3, Biome, BlockPosition, ChunkPosition, EntityKind, Gamemode, Position, Vec3d,
+    position, vec3, Biome, BlockPosition, ChunkPosition, Difficulty, EntityKind, Gamemode,
+    Position, StatusEffect, Vec3d,
 };
 pub use libcraft_inventory::{Area, Inventory};
 pub use libcraft_items::{Item, ItemStack, ItemStackBuilder, ItemStackError};
@@ -0,0 +1,302 @@
+//! Mob AI, modeled on vanilla's goal selectors.
+//!
+//! Each mob has a [`GoalSelector`] holding prioritized [`Goal`]s such
+//! as wandering or attacking, and a [`TargetSelector`] whose goals choose
+//! the mob's [`AttackTarget`]. Goals move entities through their
+//! [`Navigator`], which follows paths computed by the A* pathfinder
+//! in [`pathfinding`]. Movement is applied to the entity's `Position`,
+//! so it reaches clients through the regular entity movement packets.
+
+use std::mem;
+
+use base::{inventory::SLOT_HOTBAR_OFFSET, ChunkPosition, EntityKind, Gamemode, Item, Position};
+use ecs::{Entity, EntityBuilder, SysResult, SystemExecutor};
+use quill_common::{
+    components::{Health, Invulnerable},
+    entities::Player,
+    entity_init::EntityInit,
+};
+
+use crate::{entities::player::HotbarSlot, Game, Window};
+
+pub mod goal;
+pub mod goals;
+pub mod navigation;
+pub mod pathfinding;
+pub mod target;
+
+pub use goal::{Goal, GoalControls, GoalSelector, TargetSelector};
+pub use navigation::{MovementSpeed, Navigator};
+pub use target::AttackTarget;
+
+/// Approximate eye height of mobs, used when looking at other entities.
+const EYE_HEIGHT: f64 = 1.5;
+
+/// Component storing the age of an animal in ticks.
+///
+/// Negative values indicate a baby, which grows up when the age reaches zero.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Age(pub i32);
+
+impl Age {
+    /// The age of a newly born baby.
+    pub const BABY: Age = Age(-24000);
+
+    pub fn is_baby(self) -> bool {
+        self.0 < 0
+    }
+}
+
+/// Damage dealt by an entity's melee attacks, in half-hearts.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AttackDamage(pub f32);
+
+/// Base attributes of a mob.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MobAttributes {
+    pub max_health: f32,
+    pub movement_speed: f64,
+    pub attack_damage: f32,
+    pub follow_range: f64,
+}
+
+impl MobAttributes {
+    const fn new(
+        max_health: f32,
+        movement_speed: f64,
+        attack_damage: f32,
+        follow_range: f64,
+    ) -> Self {
+        Self {
+            max_health,
+            movement_speed,
+            attack_damage,
+            follow_range,
+        }
+    }
+
+    /// Gets the attributes of mobs of the given kind,
+    /// or `None` if the kind has no AI.
+    pub fn of(kind: EntityKind) -> Option<Self> {
+        use EntityKind::*;
+        Some(match kind {
+            Zombie | Husk | Drowned | ZombieVillager => Self::new(20.0, 0.23, 3.0, 35.0),
+            Spider => Self::new(16.0, 0.3, 2.0, 16.0),
+            CaveSpider => Self::new(12.0, 0.3, 2.0, 16.0),
+            Silverfish => Self::new(8.0, 0.25, 1.0, 16.0),
+            Endermite => Self::new(8.0, 0.25, 2.0, 16.0),
+            Creeper => Self::new(20.0, 0.25, 2.0, 16.0),
+            Skeleton | Stray => Self::new(20.0, 0.25, 2.0, 16.0),
+            Cow | Mooshroom => Self::new(10.0, 0.2, 0.0, 16.0),
+            Pig => Self::new(10.0, 0.25, 0.0, 16.0),
+            Sheep => Self::new(8.0, 0.23, 0.0, 16.0),
+            Chicken => Self::new(4.0, 0.25, 0.0, 16.0),
+            Rabbit => Self::new(3.0, 0.3, 0.0, 16.0),
+            _ => return None,
+        })
+    }
+}
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    systems
+        .add_system(tick_selectors::<TargetSelector>)
+        .add_system(tick_selectors::<GoalSelector>)
+        .add_system(navigation::move_entities)
+        .add_system(grow_babies);
+
+    game.add_entity_spawn_callback(add_ai_components);
+}
+
+/// Adds AI components and the default goals for the entity's kind.
+fn add_ai_components(builder: &mut EntityBuilder, _init: &EntityInit) {
+    let kind = match builder.get::<EntityKind>() {
+        Some(&kind) => kind,
+        None => return,
+    };
+    let attributes = match MobAttributes::of(kind) {
+        Some(attributes) => attributes,
+        None => return,
+    };
+
+    let mut goals = GoalSelector::new();
+    let mut targets = TargetSelector::default();
+    add_default_goals(kind, attributes, &mut goals, &mut targets);
+
+    builder
+        .add(goals)
+        .add(targets)
+        .add(Navigator::new())
+        .add(MovementSpeed(attributes.movement_speed))
+        .add(AttackDamage(attributes.attack_damage))
+        .add(AttackTarget::default())
+        .add(Health(attributes.max_health));
+    if tempt_items(kind).is_some() {
+        builder.add(Age(0));
+    }
+}
+
+fn add_default_goals(
+    kind: EntityKind,
+    attributes: MobAttributes,
+    goals: &mut GoalSelector,
+    targets: &mut TargetSelector,
+) {
+    use goals::*;
+    use target::*;
+    use EntityKind::*;
+
+    match kind {
+        Zombie | Husk | Drowned | ZombieVillager | Spider | CaveSpider | Silverfish | Endermite => {
+            goals
+                .add_goal(2, MeleeAttackGoal::new(1.0))
+                .add_goal(7, WanderGoal::new(1.0))
+                .add_goal(8, LookAtPlayerGoal::new(8.0));
+            targets
+                .add_goal(1, HurtByTargetGoal::new())
+                .add_goal(2, NearestPlayerTargetGoal::new(attributes.follow_range));
+        }
+        Creeper | Skeleton | Stray => {
+            let avoid = if kind == Creeper {
+                vec![Cat, Ocelot]
+            } else {
+                vec![Wolf]
+            };
+            goals
+                .add_goal(3, FleeGoal::new(avoid, 6.0, 1.0, 1.2))
+                .add_goal(5, WanderGoal::new(0.8))
+                .add_goal(6, LookAtPlayerGoal::new(8.0));
+        }
+        _ => {
+            goals.add_goal(1, PanicGoal::new(1.25));
+            if kind == Rabbit {
+                goals.add_goal(2, FleeGoal::new(vec![Player, Wolf], 8.0, 2.2, 2.2));
+            }
+            if let Some(items) = tempt_items(kind) {
+                goals.add_goal(3, TemptGoal::new(items.to_vec(), 1.2));
+            }
+            goals
+                .add_goal(4, FollowParentGoal::new(1.1))
+                .add_goal(5, WanderGoal::new(1.0))
+                .add_goal(6, LookAtPlayerGoal::new(6.0));
+        }
+    }
+}
+
+/// Items which tempt animals of the given kind,
+/// or `None` if the kind is not a tameable or breedable animal.
+fn tempt_items(kind: EntityKind) -> Option<&'static [Item]> {
+    Some(match kind {
+        EntityKind::Cow | EntityKind::Mooshroom | EntityKind::Sheep => &[Item::Wheat],
+        EntityKind::Pig => &[Item::Carrot, Item::Potato, Item::Beetroot],
+        EntityKind::Chicken => &[
+            Item::WheatSeeds,
+            Item::MelonSeeds,
+            Item::PumpkinSeeds,
+            Item::BeetrootSeeds,
+        ],
+        EntityKind::Rabbit => &[Item::Carrot, Item::GoldenCarrot, Item::Dandelion],
+        _ => return None,
+    })
+}
+
+/// Ticks the goal or target selector of each entity.
+///
+/// The selector is taken out of the entity while it runs so that goals
+/// have mutable access to the `Game`. Goals added to the entity
+/// in the meantime (e.g. by plugins) are merged back afterward.
+fn tick_selectors<S>(game: &mut Game) -> SysResult
+where
+    S: AsMut<GoalSelector> + Default + Send + Sync + 'static,
+{
+    let entities: Vec<Entity> = game.ecs.query::<&S>().iter().map(|(e, _)| e).collect();
+    for entity in entities {
+        let mut selector = match game.ecs.get_mut::<S>(entity) {
+            Ok(mut selector) => mem::take(&mut *selector),
+            Err(_) => continue,
+        };
+
+        selector.as_mut().tick(game, entity);
+
+        if let Ok(mut slot) = game.ecs.get_mut::<S>(entity) {
+            let mut added = mem::replace(&mut *slot, selector);
+            slot.as_mut().append(mem::take(added.as_mut()));
+        }
+    }
+    Ok(())
+}
+
+fn grow_babies(game: &mut Game) -> SysResult {
+    for (_, age) in game.ecs.query::<&mut Age>().iter() {
+        if age.0 < 0 {
+            age.0 += 1;
+        }
+    }
+    Ok(())
+}
+
+/// Finds the entity nearest to `position` within `range`
+/// blocks for which `filter` returns `true`.
+pub fn nearest_entity(
+    game: &Game,
+    position: Position,
+    range: f64,
+    mut filter: impl FnMut(Entity) -> bool,
+) -> Option<(Entity, Position)> {
+    let center = position.chunk();
+    let chunk_range = (range / 16.0).ceil() as i32;
+    let mut nearest = None;
+    let mut nearest_distance = range * range;
+
+    for x in center.x - chunk_range..=center.x + chunk_range {
+        for z in center.z - chunk_range..=center.z + chunk_range {
+            for &entity in game
+                .chunk_entities
+                .entities_in_chunk(ChunkPosition::new(x, z))
+            {
+                let other = match game.ecs.get::<Position>(entity) {
+                    Ok(pos) => *pos,
+                    Err(_) => continue,
+                };
+                let distance = position.distance_squared_to(other);
+                if distance <= nearest_distance && filter(entity) {
+                    nearest_distance = distance;
+                    nearest = Some((entity, other));
+                }
+            }
+        }
+    }
+
+    nearest
+}
+
+/// Returns whether `entity` is a player mobs may target:
+/// alive, not invulnerable, and in survival or adventure mode.
+pub fn is_targetable_player(game: &Game, entity: Entity) -> bool {
+    if game.ecs.get::<Player>(entity).is_err() {
+        return false;
+    }
+    let gamemode_allows = game
+        .ecs
+        .get::<Gamemode>(entity)
+        .map(|gamemode| matches!(*gamemode, Gamemode::Survival | Gamemode::Adventure))
+        .unwrap_or(false);
+    let invulnerable = game
+        .ecs
+        .get::<Invulnerable>(entity)
+        .map(|invulnerable| invulnerable.0)
+        .unwrap_or(false);
+    let alive = game
+        .ecs
+        .get::<Health>(entity)
+        .map(|health| health.0 > 0.0)
+        .unwrap_or(true);
+    gamemode_allows && !invulnerable && alive
+}
+
+/// Gets the kind of item held in the main hand of `player`.
+fn held_item(game: &Game, player: Entity) -> Option<Item> {
+    let window = game.ecs.get::<Window>(player).ok()?;
+    let hotbar_slot = game.ecs.get::<HotbarSlot>(player).ok()?.get();
+    let slot = window.item(SLOT_HOTBAR_OFFSET + hotbar_slot).ok()?;
+    slot.item_kind()
+}
@@ -0,0 +1,190 @@
+use std::ops::{Deref, DerefMut};
+
+use ecs::Entity;
+
+use crate::Game;
+
+pub use quill_common::goal::GoalControls;
+
+/// A behavior an entity performs when its conditions are met,
+/// such as wandering around or attacking a target.
+///
+/// Goals are owned by a [`GoalSelector`], which decides
+/// each tick which goals should be running.
+pub trait Goal: Send + Sync + 'static {
+    /// The controls this goal needs exclusive access to.
+    fn controls(&self) -> GoalControls;
+
+    /// Returns whether this goal should start running.
+    fn can_start(&mut self, game: &mut Game, entity: Entity) -> bool;
+
+    /// Returns whether this goal should keep running.
+    ///
+    /// Defaults to [`Goal::can_start`].
+    fn should_continue(&mut self, game: &mut Game, entity: Entity) -> bool {
+        self.can_start(game, entity)
+    }
+
+    /// Called when the goal starts running.
+    fn start(&mut self, _game: &mut Game, _entity: Entity) {}
+
+    /// Called each tick while the goal is running.
+    fn tick(&mut self, _game: &mut Game, _entity: Entity) {}
+
+    /// Called when the goal stops running,
+    /// either because it finished or was interrupted.
+    fn stop(&mut self, _game: &mut Game, _entity: Entity) {}
+
+    /// Returns whether a higher-priority goal
+    /// may interrupt this goal.
+    fn can_be_interrupted(&self) -> bool {
+        true
+    }
+}
+
+struct PrioritizedGoal {
+    priority: u32,
+    running: bool,
+    goal: Box<dyn Goal>,
+}
+
+/// Component storing an entity's goals.
+///
+/// Goals with a lower priority number take precedence. A goal
+/// can start if none of its [`GoalControls`] are used by a running goal
+/// of equal or higher precedence; lower-precedence goals using those
+/// controls are stopped.
+#[derive(Default)]
+pub struct GoalSelector {
+    goals: Vec<PrioritizedGoal>,
+}
+
+impl GoalSelector {
+    /// Creates a `GoalSelector` with no goals.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a goal with the given priority.
+    pub fn add_goal(&mut self, priority: u32, goal: impl Goal) -> &mut Self {
+        self.add_boxed_goal(priority, Box::new(goal))
+    }
+
+    /// Adds a boxed goal with the given priority.
+    pub fn add_boxed_goal(&mut self, priority: u32, goal: Box<dyn Goal>) -> &mut Self {
+        // Keep goals sorted by priority. Goals with equal
+        // priority run in insertion order.
+        let index = self.goals.partition_point(|g| g.priority <= priority);
+        self.goals.insert(
+            index,
+            PrioritizedGoal {
+                priority,
+                running: false,
+                goal,
+            },
+        );
+        self
+    }
+
+    /// Returns the number of goals in this selector.
+    pub fn len(&self) -> usize {
+        self.goals.len()
+    }
+
+    /// Returns whether this selector has no goals.
+    pub fn is_empty(&self) -> bool {
+        self.goals.is_empty()
+    }
+
+    /// Returns the number of goals currently running.
+    pub fn running_count(&self) -> usize {
+        self.goals.iter().filter(|g| g.running).count()
+    }
+
+    /// Moves the goals from `other` into `self`.
+    pub(crate) fn append(&mut self, other: GoalSelector) {
+        for goal in other.goals {
+            let index = self.goals.partition_point(|g| g.priority <= goal.priority);
+            self.goals.insert(index, goal);
+        }
+    }
+
+    /// Stops goals that should no longer run, starts goals
+    /// whose conditions are met, and ticks all running goals.
+    pub fn tick(&mut self, game: &mut Game, entity: Entity) {
+        for goal in &mut self.goals {
+            if goal.running && !goal.goal.should_continue(game, entity) {
+                goal.goal.stop(game, entity);
+                goal.running = false;
+            }
+        }
+
+        for i in 0..self.goals.len() {
+            if self.goals[i].running {
+                continue;
+            }
+
+            let priority = self.goals[i].priority;
+            let controls = self.goals[i].goal.controls();
+            let blocked = self.goals.iter().any(|other| {
+                other.running
+                    && other.goal.controls().intersects(controls)
+                    && (other.priority <= priority || !other.goal.can_be_interrupted())
+            });
+            if blocked || !self.goals[i].goal.can_start(game, entity) {
+                continue;
+            }
+
+            for other in &mut self.goals {
+                if other.running && other.goal.controls().intersects(controls) {
+                    other.goal.stop(game, entity);
+                    other.running = false;
+                }
+            }
+
+            let goal = &mut self.goals[i];
+            goal.goal.start(game, entity);
+            goal.running = true;
+        }
+
+        for goal in &mut self.goals {
+            if goal.running {
+                goal.goal.tick(game, entity);
+            }
+        }
+    }
+}
+
+/// Component storing the goals which select an
+/// entity's [`AttackTarget`](super::AttackTarget).
+///
+/// Ticked before the entity's [`GoalSelector`], mirroring
+/// vanilla's separate target selector.
+#[derive(Default)]
+pub struct TargetSelector(pub GoalSelector);
+
+impl Deref for TargetSelector {
+    type Target = GoalSelector;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for TargetSelector {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl AsMut<GoalSelector> for GoalSelector {
+    fn as_mut(&mut self) -> &mut GoalSelector {
+        self
+    }
+}
+
+impl AsMut<GoalSelector> for TargetSelector {
+    fn as_mut(&mut self) -> &mut GoalSelector {
+        &mut self.0
+    }
+}
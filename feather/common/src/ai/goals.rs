@@ -0,0 +1,618 @@
+//! Built-in goals.
+
+use base::{BlockPosition, EntityKind, Item, Position};
+use ecs::Entity;
+use quill_common::{components::ActiveEffects, entities::Player};
+use rand::Rng;
+
+use crate::{damage::LastDamage, effects::modify_attack_damage, Game};
+
+use super::{
+    goal::{Goal, GoalControls},
+    held_item, is_targetable_player,
+    navigation::{look_at, Navigator},
+    nearest_entity,
+    pathfinding::is_walkable,
+    Age, AttackDamage, AttackTarget, EYE_HEIGHT,
+};
+
+/// How often goals which follow a moving entity recompute their path, in ticks.
+const REPATH_INTERVAL: u32 = 10;
+
+fn position_of(game: &Game, entity: Entity) -> Option<Position> {
+    game.ecs.get::<Position>(entity).ok().map(|pos| *pos)
+}
+
+fn navigate_to(game: &Game, entity: Entity, target: BlockPosition, speed: f64) -> bool {
+    let position = match position_of(game, entity) {
+        Some(pos) => pos,
+        None => return false,
+    };
+    match game.ecs.get_mut::<Navigator>(entity) {
+        Ok(mut navigator) => navigator.navigate_to(&game.world, position, target, speed),
+        Err(_) => false,
+    }
+}
+
+fn navigation_finished(game: &Game, entity: Entity) -> bool {
+    game.ecs
+        .get::<Navigator>(entity)
+        .map(|navigator| navigator.is_idle())
+        .unwrap_or(true)
+}
+
+fn stop_navigation(game: &Game, entity: Entity) {
+    if let Ok(mut navigator) = game.ecs.get_mut::<Navigator>(entity) {
+        navigator.stop();
+    }
+}
+
+fn look_at_entity(game: &Game, entity: Entity, target: Entity) {
+    let target = match position_of(game, target) {
+        Some(pos) => pos,
+        None => return,
+    };
+    if let Ok(mut position) = game.ecs.get_mut::<Position>(entity) {
+        let mut eye = *position;
+        eye.y += EYE_HEIGHT;
+        let mut target_eye = target;
+        target_eye.y += EYE_HEIGHT;
+        look_at(&mut eye, target_eye);
+        position.yaw = eye.yaw;
+        position.pitch = eye.pitch;
+    }
+}
+
+/// Picks a random walkable position within `horizontal` blocks
+/// horizontally and `vertical` blocks vertically of `origin`.
+///
+/// If `away_from` is set, only positions farther
+/// from it than `origin` are accepted.
+fn random_position(
+    game: &Game,
+    origin: Position,
+    horizontal: i32,
+    vertical: i32,
+    away_from: Option<Position>,
+) -> Option<BlockPosition> {
+    let mut rng = rand::thread_rng();
+    let origin_block = origin.block();
+    for _ in 0..10 {
+        let candidate = BlockPosition::new(
+            origin_block.x + rng.gen_range(-horizontal..=horizontal),
+            origin_block.y + rng.gen_range(-vertical..=vertical),
+            origin_block.z + rng.gen_range(-horizontal..=horizontal),
+        );
+        if let Some(threat) = away_from {
+            if candidate.position().distance_squared_to(threat)
+                <= origin.distance_squared_to(threat)
+            {
+                continue;
+            }
+        }
+        if is_walkable(&game.world, candidate) {
+            return Some(candidate);
+        }
+    }
+    None
+}
+
+/// Walks to random nearby positions.
+pub struct WanderGoal {
+    speed: f64,
+    /// The goal starts with a chance of 1 in `interval` each tick.
+    interval: u32,
+}
+
+impl WanderGoal {
+    pub fn new(speed: f64) -> Self {
+        Self {
+            speed,
+            interval: 120,
+        }
+    }
+}
+
+impl Goal for WanderGoal {
+    fn controls(&self) -> GoalControls {
+        GoalControls::MOVE
+    }
+
+    fn can_start(&mut self, game: &mut Game, entity: Entity) -> bool {
+        navigation_finished(game, entity) && rand::thread_rng().gen_ratio(1, self.interval)
+    }
+
+    fn should_continue(&mut self, game: &mut Game, entity: Entity) -> bool {
+        !navigation_finished(game, entity)
+    }
+
+    fn start(&mut self, game: &mut Game, entity: Entity) {
+        let target =
+            position_of(game, entity).and_then(|origin| random_position(game, origin, 10, 7, None));
+        if let Some(target) = target {
+            navigate_to(game, entity, target, self.speed);
+        }
+    }
+
+    fn stop(&mut self, game: &mut Game, entity: Entity) {
+        stop_navigation(game, entity);
+    }
+}
+
+/// Occasionally looks at a nearby player.
+pub struct LookAtPlayerGoal {
+    range: f64,
+    chance: f64,
+    target: Option<Entity>,
+    ticks_remaining: u32,
+}
+
+impl LookAtPlayerGoal {
+    pub fn new(range: f64) -> Self {
+        Self {
+            range,
+            chance: 0.02,
+            target: None,
+            ticks_remaining: 0,
+        }
+    }
+}
+
+impl Goal for LookAtPlayerGoal {
+    fn controls(&self) -> GoalControls {
+        GoalControls::LOOK
+    }
+
+    fn can_start(&mut self, game: &mut Game, entity: Entity) -> bool {
+        if !rand::thread_rng().gen_bool(self.chance) {
+            return false;
+        }
+        let position = match position_of(game, entity) {
+            Some(pos) => pos,
+            None => return false,
+        };
+        self.target = nearest_entity(game, position, self.range, |other| {
+            other != entity && game.ecs.get::<Player>(other).is_ok()
+        })
+        .map(|(player, _)| player);
+        self.target.is_some()
+    }
+
+    fn should_continue(&mut self, game: &mut Game, entity: Entity) -> bool {
+        let target = match self.target {
+            Some(target) => target,
+            None => return false,
+        };
+        match (position_of(game, entity), position_of(game, target)) {
+            (Some(position), Some(target_position)) => {
+                self.ticks_remaining > 0
+                    && position.distance_squared_to(target_position) <= self.range * self.range
+            }
+            _ => false,
+        }
+    }
+
+    fn start(&mut self, _game: &mut Game, _entity: Entity) {
+        self.ticks_remaining = 40 + rand::thread_rng().gen_range(0..40);
+    }
+
+    fn tick(&mut self, game: &mut Game, entity: Entity) {
+        if let Some(target) = self.target {
+            look_at_entity(game, entity, target);
+        }
+        self.ticks_remaining = self.ticks_remaining.saturating_sub(1);
+    }
+
+    fn stop(&mut self, _game: &mut Game, _entity: Entity) {
+        self.target = None;
+    }
+}
+
+/// Chases and attacks the entity's [`AttackTarget`].
+pub struct MeleeAttackGoal {
+    speed: f64,
+    cooldown: u32,
+    repath_in: u32,
+}
+
+/// Squared distance within which a melee attack can hit.
+const MELEE_REACH_SQUARED: f64 = 2.0 * 2.0;
+
+/// Ticks between melee attacks.
+const MELEE_COOLDOWN: u32 = 20;
+
+impl MeleeAttackGoal {
+    pub fn new(speed: f64) -> Self {
+        Self {
+            speed,
+            cooldown: 0,
+            repath_in: 0,
+        }
+    }
+
+    fn target(game: &Game, entity: Entity) -> Option<Entity> {
+        let target = game.ecs.get::<AttackTarget>(entity).ok()?.0?;
+        if game.ecs.entity(target).is_err() {
+            return None;
+        }
+        Some(target)
+    }
+}
+
+impl Goal for MeleeAttackGoal {
+    fn controls(&self) -> GoalControls {
+        GoalControls::MOVE | GoalControls::LOOK
+    }
+
+    fn can_start(&mut self, game: &mut Game, entity: Entity) -> bool {
+        Self::target(game, entity).is_some()
+    }
+
+    fn start(&mut self, _game: &mut Game, _entity: Entity) {
+        self.repath_in = 0;
+    }
+
+    fn tick(&mut self, game: &mut Game, entity: Entity) {
+        let target = match Self::target(game, entity) {
+            Some(target) => target,
+            None => return,
+        };
+        let (position, target_position) =
+            match (position_of(game, entity), position_of(game, target)) {
+                (Some(a), Some(b)) => (a, b),
+                _ => return,
+            };
+
+        look_at_entity(game, entity, target);
+
+        if self.repath_in == 0 {
+            navigate_to(game, entity, target_position.block(), self.speed);
+            self.repath_in = REPATH_INTERVAL;
+        } else {
+            self.repath_in -= 1;
+        }
+
+        self.cooldown = self.cooldown.saturating_sub(1);
+        if self.cooldown == 0
+            && position.distance_squared_to(target_position) <= MELEE_REACH_SQUARED
+        {
+            let mut damage = game
+                .ecs
+                .get::<AttackDamage>(entity)
+                .map(|damage| damage.0)
+                .unwrap_or(2.0);
+            if let Ok(effects) = game.ecs.get::<ActiveEffects>(entity) {
+                damage = modify_attack_damage(&effects, damage);
+            }
+            if let Err(e) = game.damage_entity(target, damage, Some(entity)) {
+                log::debug!("Failed to apply melee damage: {:?}", e);
+            }
+            self.cooldown = MELEE_COOLDOWN;
+        }
+    }
+
+    fn stop(&mut self, game: &mut Game, entity: Entity) {
+        stop_navigation(game, entity);
+    }
+}
+
+/// Runs away from nearby entities of certain kinds.
+pub struct FleeGoal {
+    avoid: Vec<EntityKind>,
+    distance: f64,
+    walk_speed: f64,
+    sprint_speed: f64,
+    threat: Option<Entity>,
+}
+
+impl FleeGoal {
+    pub fn new(avoid: Vec<EntityKind>, distance: f64, walk_speed: f64, sprint_speed: f64) -> Self {
+        Self {
+            avoid,
+            distance,
+            walk_speed,
+            sprint_speed,
+            threat: None,
+        }
+    }
+}
+
+impl Goal for FleeGoal {
+    fn controls(&self) -> GoalControls {
+        GoalControls::MOVE
+    }
+
+    fn can_start(&mut self, game: &mut Game, entity: Entity) -> bool {
+        let position = match position_of(game, entity) {
+            Some(pos) => pos,
+            None => return false,
+        };
+        let avoid = &self.avoid;
+        let threat = nearest_entity(game, position, self.distance, |other| {
+            let kind = match game.ecs.get::<EntityKind>(other) {
+                Ok(kind) => *kind,
+                Err(_) => return false,
+            };
+            other != entity
+                && avoid.contains(&kind)
+                && (kind != EntityKind::Player || is_targetable_player(game, other))
+        });
+        let (threat, threat_position) = match threat {
+            Some(threat) => threat,
+            None => return false,
+        };
+
+        match random_position(game, position, 16, 7, Some(threat_position)) {
+            Some(target) if navigate_to(game, entity, target, self.walk_speed) => {
+                self.threat = Some(threat);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn should_continue(&mut self, game: &mut Game, entity: Entity) -> bool {
+        !navigation_finished(game, entity)
+    }
+
+    fn tick(&mut self, game: &mut Game, entity: Entity) {
+        let threat_position = self.threat.and_then(|threat| position_of(game, threat));
+        if let (Some(position), Some(threat_position)) =
+            (position_of(game, entity), threat_position)
+        {
+            let speed = if position.distance_squared_to(threat_position) < 7.0 * 7.0 {
+                self.sprint_speed
+            } else {
+                self.walk_speed
+            };
+            if let Ok(mut navigator) = game.ecs.get_mut::<Navigator>(entity) {
+                navigator.set_speed_modifier(speed);
+            }
+        }
+    }
+
+    fn stop(&mut self, game: &mut Game, entity: Entity) {
+        self.threat = None;
+        stop_navigation(game, entity);
+    }
+}
+
+/// Runs around randomly after taking damage.
+pub struct PanicGoal {
+    speed: f64,
+}
+
+/// How long after being hurt an entity panics, in ticks.
+const PANIC_TICKS: u64 = 100;
+
+impl PanicGoal {
+    pub fn new(speed: f64) -> Self {
+        Self { speed }
+    }
+}
+
+impl Goal for PanicGoal {
+    fn controls(&self) -> GoalControls {
+        GoalControls::MOVE
+    }
+
+    fn can_start(&mut self, game: &mut Game, entity: Entity) -> bool {
+        let hurt_recently = game
+            .ecs
+            .get::<LastDamage>(entity)
+            .map(|damage| game.tick_count.saturating_sub(damage.tick) < PANIC_TICKS)
+            .unwrap_or(false);
+        if !hurt_recently {
+            return false;
+        }
+
+        let target =
+            position_of(game, entity).and_then(|origin| random_position(game, origin, 5, 4, None));
+        match target {
+            Some(target) => navigate_to(game, entity, target, self.speed),
+            None => false,
+        }
+    }
+
+    fn should_continue(&mut self, game: &mut Game, entity: Entity) -> bool {
+        !navigation_finished(game, entity)
+    }
+
+    fn stop(&mut self, game: &mut Game, entity: Entity) {
+        stop_navigation(game, entity);
+    }
+}
+
+/// Makes babies follow a nearby adult of the same kind.
+pub struct FollowParentGoal {
+    speed: f64,
+    parent: Option<Entity>,
+    repath_in: u32,
+}
+
+impl FollowParentGoal {
+    pub fn new(speed: f64) -> Self {
+        Self {
+            speed,
+            parent: None,
+            repath_in: 0,
+        }
+    }
+}
+
+impl Goal for FollowParentGoal {
+    fn controls(&self) -> GoalControls {
+        GoalControls::MOVE
+    }
+
+    fn can_start(&mut self, game: &mut Game, entity: Entity) -> bool {
+        let is_baby = game
+            .ecs
+            .get::<Age>(entity)
+            .map(|age| age.is_baby())
+            .unwrap_or(false);
+        if !is_baby {
+            return false;
+        }
+        let (position, kind) = match (
+            position_of(game, entity),
+            game.ecs.get::<EntityKind>(entity).ok(),
+        ) {
+            (Some(position), Some(kind)) => (position, *kind),
+            _ => return false,
+        };
+
+        let parent = nearest_entity(game, position, 8.0, |other| {
+            other != entity
+                && game
+                    .ecs
+                    .get::<EntityKind>(other)
+                    .map(|k| *k == kind)
+                    .unwrap_or(false)
+                && !game
+                    .ecs
+                    .get::<Age>(other)
+                    .map(|age| age.is_baby())
+                    .unwrap_or(false)
+        });
+        match parent {
+            Some((parent, parent_position))
+                if position.distance_squared_to(parent_position) >= 3.0 * 3.0 =>
+            {
+                self.parent = Some(parent);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn should_continue(&mut self, game: &mut Game, entity: Entity) -> bool {
+        let parent = match self.parent {
+            Some(parent) => parent,
+            None => return false,
+        };
+        match (position_of(game, entity), position_of(game, parent)) {
+            (Some(position), Some(parent_position)) => {
+                let distance_squared = position.distance_squared_to(parent_position);
+                (3.0 * 3.0..=16.0 * 16.0).contains(&distance_squared)
+            }
+            _ => false,
+        }
+    }
+
+    fn start(&mut self, _game: &mut Game, _entity: Entity) {
+        self.repath_in = 0;
+    }
+
+    fn tick(&mut self, game: &mut Game, entity: Entity) {
+        if self.repath_in > 0 {
+            self.repath_in -= 1;
+            return;
+        }
+        self.repath_in = REPATH_INTERVAL;
+        if let Some(parent_position) = self.parent.and_then(|parent| position_of(game, parent)) {
+            navigate_to(game, entity, parent_position.block(), self.speed);
+        }
+    }
+
+    fn stop(&mut self, game: &mut Game, entity: Entity) {
+        self.parent = None;
+        stop_navigation(game, entity);
+    }
+}
+
+/// Follows players holding one of a set of items.
+pub struct TemptGoal {
+    items: Vec<Item>,
+    speed: f64,
+    player: Option<Entity>,
+    cooldown: u32,
+    repath_in: u32,
+}
+
+/// Range within which players can tempt an entity.
+const TEMPT_RANGE: f64 = 10.0;
+
+impl TemptGoal {
+    pub fn new(items: Vec<Item>, speed: f64) -> Self {
+        Self {
+            items,
+            speed,
+            player: None,
+            cooldown: 0,
+            repath_in: 0,
+        }
+    }
+
+    fn is_tempting(&self, game: &Game, player: Entity) -> bool {
+        held_item(game, player)
+            .map(|item| self.items.contains(&item))
+            .unwrap_or(false)
+    }
+}
+
+impl Goal for TemptGoal {
+    fn controls(&self) -> GoalControls {
+        GoalControls::MOVE | GoalControls::LOOK
+    }
+
+    fn can_start(&mut self, game: &mut Game, entity: Entity) -> bool {
+        if self.cooldown > 0 {
+            self.cooldown -= 1;
+            return false;
+        }
+        let position = match position_of(game, entity) {
+            Some(pos) => pos,
+            None => return false,
+        };
+        self.player = nearest_entity(game, position, TEMPT_RANGE, |other| {
+            other != entity && is_targetable_player(game, other) && self.is_tempting(game, other)
+        })
+        .map(|(player, _)| player);
+        self.player.is_some()
+    }
+
+    fn should_continue(&mut self, game: &mut Game, entity: Entity) -> bool {
+        let player = match self.player {
+            Some(player) => player,
+            None => return false,
+        };
+        let in_range = match (position_of(game, entity), position_of(game, player)) {
+            (Some(a), Some(b)) => a.distance_squared_to(b) <= TEMPT_RANGE * TEMPT_RANGE,
+            _ => false,
+        };
+        in_range && self.is_tempting(game, player)
+    }
+
+    fn start(&mut self, _game: &mut Game, _entity: Entity) {
+        self.repath_in = 0;
+    }
+
+    fn tick(&mut self, game: &mut Game, entity: Entity) {
+        let player = match self.player {
+            Some(player) => player,
+            None => return,
+        };
+        look_at_entity(game, entity, player);
+
+        let (position, player_position) =
+            match (position_of(game, entity), position_of(game, player)) {
+                (Some(a), Some(b)) => (a, b),
+                _ => return,
+            };
+        if position.distance_squared_to(player_position) < 2.5 * 2.5 {
+            stop_navigation(game, entity);
+        } else if self.repath_in == 0 {
+            navigate_to(game, entity, player_position.block(), self.speed);
+            self.repath_in = REPATH_INTERVAL;
+        } else {
+            self.repath_in -= 1;
+        }
+    }
+
+    fn stop(&mut self, game: &mut Game, entity: Entity) {
+        self.player = None;
+        self.cooldown = 100;
+        stop_navigation(game, entity);
+    }
+}
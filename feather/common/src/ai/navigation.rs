@@ -0,0 +1,203 @@
+use base::{BlockPosition, Position};
+use ecs::SysResult;
+use quill_common::components::{ActiveEffects, OnGround};
+
+use crate::{effects::movement_speed_multiplier, Game, World};
+
+use super::pathfinding::{self, Path};
+
+/// How far from its start position a path may stray, in blocks.
+const MAX_PATH_DISTANCE: i32 = 32;
+
+/// Maximum vertical distance moved in one tick.
+const MAX_VERTICAL_STEP: f64 = 0.5;
+
+/// Number of ticks without progress after which
+/// the navigator gives up on its path.
+const STUCK_TICKS: u32 = 60;
+
+/// The base movement speed of a mob, in the units of
+/// vanilla's `generic.movement_speed` attribute.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MovementSpeed(pub f64);
+
+impl MovementSpeed {
+    /// Converts the attribute value to blocks per tick,
+    /// approximating vanilla's ground movement with friction.
+    pub fn blocks_per_tick(self, modifier: f64) -> f64 {
+        self.0 * modifier * 0.5
+    }
+}
+
+/// Component that moves an entity along a [`Path`].
+///
+/// Goals request movement through the navigator; the
+/// navigation system then updates the entity's `Position`,
+/// which is broadcast through the normal entity movement packets.
+#[derive(Debug, Default)]
+pub struct Navigator {
+    path: Option<Path>,
+    speed_modifier: f64,
+    ticks_without_progress: u32,
+    last_distance: f64,
+}
+
+impl Navigator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Computes a path from `from` to `to` and starts following it.
+    ///
+    /// Returns `false` if no path could be found.
+    pub fn navigate_to(
+        &mut self,
+        world: &World,
+        from: Position,
+        to: BlockPosition,
+        speed_modifier: f64,
+    ) -> bool {
+        match pathfinding::find_path(world, from.block(), to, MAX_PATH_DISTANCE) {
+            Some(path) => {
+                self.set_path(path, speed_modifier);
+                true
+            }
+            None => {
+                self.stop();
+                false
+            }
+        }
+    }
+
+    /// Starts following the given path.
+    pub fn set_path(&mut self, path: Path, speed_modifier: f64) {
+        self.path = Some(path);
+        self.speed_modifier = speed_modifier;
+        self.ticks_without_progress = 0;
+        self.last_distance = f64::MAX;
+    }
+
+    /// Changes the speed modifier of the current path.
+    pub fn set_speed_modifier(&mut self, speed_modifier: f64) {
+        self.speed_modifier = speed_modifier;
+    }
+
+    /// Stops following the current path.
+    pub fn stop(&mut self) {
+        self.path = None;
+    }
+
+    /// Returns whether the navigator has no path to follow.
+    pub fn is_idle(&self) -> bool {
+        self.path.is_none()
+    }
+
+    /// Gets the path currently being followed.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_ref()
+    }
+
+    /// Moves `position` one tick along the path.
+    fn tick(&mut self, position: &mut Position, on_ground: &mut OnGround, speed: MovementSpeed) {
+        let path = match &mut self.path {
+            Some(path) => path,
+            None => return,
+        };
+        let node = match path.current() {
+            Some(node) => node,
+            None => {
+                self.path = None;
+                return;
+            }
+        };
+
+        let target_x = node.x as f64 + 0.5;
+        let target_z = node.z as f64 + 0.5;
+        let target_y = node.y as f64;
+
+        let dx = target_x - position.x;
+        let dz = target_z - position.z;
+        let horizontal_distance = (dx * dx + dz * dz).sqrt();
+        let step = speed.blocks_per_tick(self.speed_modifier);
+
+        if horizontal_distance <= step {
+            position.x = target_x;
+            position.z = target_z;
+        } else {
+            position.x += dx / horizontal_distance * step;
+            position.z += dz / horizontal_distance * step;
+        }
+
+        let dy = target_y - position.y;
+        position.y += dy.clamp(-MAX_VERTICAL_STEP, MAX_VERTICAL_STEP);
+        on_ground.0 = (target_y - position.y).abs() < f64::EPSILON;
+
+        if horizontal_distance > f64::EPSILON {
+            position.yaw = yaw_toward(dx, dz);
+            position.pitch = 0.0;
+        }
+
+        if horizontal_distance <= step && on_ground.0 {
+            path.advance();
+            self.ticks_without_progress = 0;
+            self.last_distance = f64::MAX;
+            if path.is_finished() {
+                self.path = None;
+            }
+            return;
+        }
+
+        // Give up if we're stuck (e.g. the world changed under the path.)
+        if horizontal_distance < self.last_distance - 0.01 {
+            self.ticks_without_progress = 0;
+        } else {
+            self.ticks_without_progress += 1;
+            if self.ticks_without_progress >= STUCK_TICKS {
+                self.path = None;
+            }
+        }
+        self.last_distance = self.last_distance.min(horizontal_distance);
+    }
+}
+
+/// Computes the yaw, in degrees, of an entity facing along (`dx`, `dz`).
+pub fn yaw_toward(dx: f64, dz: f64) -> f32 {
+    (-dx).atan2(dz).to_degrees() as f32
+}
+
+/// Computes the pitch, in degrees, of an entity looking
+/// along (`dx`, `dy`, `dz`).
+pub fn pitch_toward(dx: f64, dy: f64, dz: f64) -> f32 {
+    let horizontal = (dx * dx + dz * dz).sqrt();
+    (-dy).atan2(horizontal).to_degrees() as f32
+}
+
+/// Turns `position` to look at `target`.
+pub fn look_at(position: &mut Position, target: Position) {
+    let dx = target.x - position.x;
+    let dy = target.y - position.y;
+    let dz = target.z - position.z;
+    position.yaw = yaw_toward(dx, dz);
+    position.pitch = pitch_toward(dx, dy, dz);
+}
+
+pub(super) fn move_entities(game: &mut Game) -> SysResult {
+    for (_, (navigator, position, on_ground, &speed, effects)) in game
+        .ecs
+        .query::<(
+            &mut Navigator,
+            &mut Position,
+            &mut OnGround,
+            &MovementSpeed,
+            Option<&ActiveEffects>,
+        )>()
+        .iter()
+    {
+        let speed = match effects {
+            Some(effects) => MovementSpeed(speed.0 * movement_speed_multiplier(effects)),
+            None => speed,
+        };
+        navigator.tick(position, on_ground, speed);
+    }
+    Ok(())
+}
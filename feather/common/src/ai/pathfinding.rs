@@ -0,0 +1,324 @@
+//! A* pathfinding over the block grid.
+//!
+//! Paths are sequences of block positions an entity's feet
+//! pass through. A position is walkable if the entity fits
+//! in it (the block and the one above are passable) and the
+//! block below is solid.
+
+use std::{cmp::Reverse, collections::BinaryHeap, convert::TryFrom};
+
+use ahash::AHashMap;
+use base::{BlockPosition, ValidBlockPosition};
+
+use crate::World;
+
+/// Maximum number of nodes expanded in a single search.
+/// Bounds the cost of searching for an unreachable target.
+const MAX_VISITED_NODES: usize = 1024;
+
+/// Maximum number of blocks an entity will drop down in one step.
+const MAX_DROP: i32 = 3;
+
+/// Movement costs, in tenths of a block.
+const COST_STRAIGHT: u32 = 10;
+const COST_DIAGONAL: u32 = 14;
+const COST_JUMP: u32 = 20;
+const COST_DROP_PER_BLOCK: u32 = 5;
+
+/// A path computed by [`find_path`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Path {
+    nodes: Vec<BlockPosition>,
+    index: usize,
+}
+
+impl Path {
+    /// Creates a path following the given nodes.
+    pub fn new(nodes: Vec<BlockPosition>) -> Self {
+        Self { nodes, index: 0 }
+    }
+
+    /// Gets all nodes in this path, including
+    /// those already reached.
+    pub fn nodes(&self) -> &[BlockPosition] {
+        &self.nodes
+    }
+
+    /// Gets the node the entity is currently moving toward.
+    pub fn current(&self) -> Option<BlockPosition> {
+        self.nodes.get(self.index).copied()
+    }
+
+    /// Gets the final node of this path.
+    pub fn destination(&self) -> Option<BlockPosition> {
+        self.nodes.last().copied()
+    }
+
+    /// Marks the current node as reached.
+    pub fn advance(&mut self) {
+        self.index += 1;
+    }
+
+    /// Returns whether every node in the path has been reached.
+    pub fn is_finished(&self) -> bool {
+        self.index >= self.nodes.len()
+    }
+}
+
+/// Returns whether an entity can stand inside the given block.
+pub fn is_passable(world: &World, pos: BlockPosition) -> bool {
+    let pos = match ValidBlockPosition::try_from(pos) {
+        Ok(pos) => pos,
+        Err(_) => return false,
+    };
+    match world.block_at(pos) {
+        Some(block) => !block.is_solid() && block.kind() != base::BlockKind::Lava,
+        None => false,
+    }
+}
+
+/// Returns whether the given block can support an entity standing on it.
+pub fn is_solid(world: &World, pos: BlockPosition) -> bool {
+    ValidBlockPosition::try_from(pos)
+        .ok()
+        .and_then(|pos| world.block_at(pos))
+        .map(|block| block.is_solid())
+        .unwrap_or(false)
+}
+
+/// Returns whether a two-block-tall entity can stand
+/// with its feet at `pos`.
+pub fn is_walkable(world: &World, pos: BlockPosition) -> bool {
+    is_passable(world, pos) && is_passable(world, pos.up()) && is_solid(world, pos.down())
+}
+
+/// Finds a path from `start` to `goal`, staying within
+/// `max_distance` blocks (horizontally) of `start`.
+///
+/// If `goal` cannot be reached, the returned path ends at the
+/// visited node closest to `goal`, which matches vanilla's
+/// behavior of moving as close as possible. Returns `None`
+/// if no progress toward the goal is possible.
+pub fn find_path(
+    world: &World,
+    start: BlockPosition,
+    goal: BlockPosition,
+    max_distance: i32,
+) -> Option<Path> {
+    let mut open = BinaryHeap::new();
+    // Maps each visited node to its cost from `start` and its parent.
+    let mut visited: AHashMap<BlockPosition, (u32, Option<BlockPosition>)> = AHashMap::new();
+
+    visited.insert(start, (0, None));
+    open.push(Reverse((heuristic(start, goal), 0, start)));
+
+    let mut closest = (heuristic(start, goal), start);
+    let mut expanded = 0;
+    let mut neighbors = Vec::with_capacity(8);
+
+    while let Some(Reverse((_, cost, node))) = open.pop() {
+        if node == goal {
+            closest = (0, node);
+            break;
+        }
+        if visited.get(&node).map(|(c, _)| *c) != Some(cost) {
+            // Stale heap entry; a cheaper route was found later.
+            continue;
+        }
+
+        expanded += 1;
+        if expanded > MAX_VISITED_NODES {
+            break;
+        }
+
+        neighbors.clear();
+        find_neighbors(world, node, &mut neighbors);
+        for &(neighbor, step_cost) in &neighbors {
+            if (neighbor.x - start.x).abs() > max_distance
+                || (neighbor.z - start.z).abs() > max_distance
+            {
+                continue;
+            }
+
+            let new_cost = cost + step_cost;
+            let is_better = visited
+                .get(&neighbor)
+                .map(|(c, _)| new_cost < *c)
+                .unwrap_or(true);
+            if is_better {
+                visited.insert(neighbor, (new_cost, Some(node)));
+                let h = heuristic(neighbor, goal);
+                if h < closest.0 {
+                    closest = (h, neighbor);
+                }
+                open.push(Reverse((new_cost + h, new_cost, neighbor)));
+            }
+        }
+    }
+
+    let end = closest.1;
+    if end == start {
+        return None;
+    }
+
+    let mut nodes = vec![end];
+    let mut current = end;
+    while let Some((_, Some(parent))) = visited.get(&current) {
+        if *parent == start {
+            break;
+        }
+        nodes.push(*parent);
+        current = *parent;
+    }
+    nodes.reverse();
+    Some(Path::new(nodes))
+}
+
+/// Octile distance on the horizontal plane plus vertical distance.
+fn heuristic(from: BlockPosition, to: BlockPosition) -> u32 {
+    let dx = (from.x - to.x).unsigned_abs();
+    let dy = (from.y - to.y).unsigned_abs();
+    let dz = (from.z - to.z).unsigned_abs();
+    let (min, max) = if dx < dz { (dx, dz) } else { (dz, dx) };
+    COST_DIAGONAL * min + COST_STRAIGHT * (max - min) + COST_STRAIGHT * dy
+}
+
+fn find_neighbors(world: &World, node: BlockPosition, neighbors: &mut Vec<(BlockPosition, u32)>) {
+    const CARDINALS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+    const DIAGONALS: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+
+    for &(dx, dz) in &CARDINALS {
+        let next = BlockPosition::new(node.x + dx, node.y, node.z + dz);
+        if is_walkable(world, next) {
+            neighbors.push((next, COST_STRAIGHT));
+        } else if is_walkable(world, next.up()) && is_passable(world, node.up().up()) {
+            // Jump up one block.
+            neighbors.push((next.up(), COST_JUMP));
+        } else if is_passable(world, next) && is_passable(world, next.up()) {
+            // Drop down, but not too far.
+            let mut below = next;
+            for drop in 1..=MAX_DROP {
+                below = below.down();
+                if is_walkable(world, below) {
+                    neighbors.push((below, COST_STRAIGHT + COST_DROP_PER_BLOCK * drop as u32));
+                    break;
+                }
+                if !is_passable(world, below) {
+                    break;
+                }
+            }
+        }
+    }
+
+    for &(dx, dz) in &DIAGONALS {
+        let next = BlockPosition::new(node.x + dx, node.y, node.z + dz);
+        let side_a = BlockPosition::new(node.x + dx, node.y, node.z);
+        let side_b = BlockPosition::new(node.x, node.y, node.z + dz);
+        // Don't cut corners.
+        if is_walkable(world, next)
+            && is_passable(world, side_a)
+            && is_passable(world, side_a.up())
+            && is_passable(world, side_b)
+            && is_passable(world, side_b.up())
+        {
+            neighbors.push((next, COST_DIAGONAL));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base::{BlockId, Chunk, ChunkPosition};
+
+    use super::*;
+
+    /// Creates a world with a flat stone floor at y = 63.
+    fn flat_world() -> World {
+        let mut world = World::new();
+        for x in -1..=1 {
+            for z in -1..=1 {
+                world
+                    .chunk_map_mut()
+                    .insert_chunk(Chunk::new(ChunkPosition::new(x, z)));
+            }
+        }
+        for x in -16..32 {
+            for z in -16..32 {
+                set(&world, BlockPosition::new(x, 63, z), BlockId::stone());
+            }
+        }
+        world
+    }
+
+    fn set(world: &World, pos: BlockPosition, block: BlockId) {
+        assert!(world.set_block_at(ValidBlockPosition::try_from(pos).unwrap(), block));
+    }
+
+    #[test]
+    fn straight_line() {
+        let world = flat_world();
+        let path = find_path(
+            &world,
+            BlockPosition::new(0, 64, 0),
+            BlockPosition::new(5, 64, 0),
+            16,
+        )
+        .unwrap();
+        assert_eq!(path.destination(), Some(BlockPosition::new(5, 64, 0)));
+        assert_eq!(path.nodes().len(), 5);
+    }
+
+    #[test]
+    fn around_wall() {
+        let world = flat_world();
+        for z in -3..=3 {
+            for y in 64..=65 {
+                set(&world, BlockPosition::new(2, y, z), BlockId::stone());
+            }
+        }
+        let path = find_path(
+            &world,
+            BlockPosition::new(0, 64, 0),
+            BlockPosition::new(4, 64, 0),
+            16,
+        )
+        .unwrap();
+        assert_eq!(path.destination(), Some(BlockPosition::new(4, 64, 0)));
+        assert!(path.nodes().iter().all(|node| is_walkable(&world, *node)));
+    }
+
+    #[test]
+    fn step_up() {
+        let world = flat_world();
+        set(&world, BlockPosition::new(2, 64, 0), BlockId::stone());
+        let path = find_path(
+            &world,
+            BlockPosition::new(0, 64, 0),
+            BlockPosition::new(2, 65, 0),
+            16,
+        )
+        .unwrap();
+        assert_eq!(path.destination(), Some(BlockPosition::new(2, 65, 0)));
+    }
+
+    #[test]
+    fn unreachable_goal_gives_partial_path() {
+        let world = flat_world();
+        // Box in the goal.
+        for (x, z) in [(9, 0), (11, 0), (10, 1), (10, -1)].iter().copied() {
+            for y in 64..=66 {
+                set(&world, BlockPosition::new(x, y, z), BlockId::stone());
+            }
+        }
+        let path = find_path(
+            &world,
+            BlockPosition::new(0, 64, 0),
+            BlockPosition::new(10, 64, 0),
+            16,
+        )
+        .unwrap();
+        let end = path.destination().unwrap();
+        assert_ne!(end, BlockPosition::new(10, 64, 0));
+        assert!(end.manhattan_distance(BlockPosition::new(10, 64, 0)) <= 2);
+    }
+}
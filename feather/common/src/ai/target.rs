@@ -0,0 +1,153 @@
+//! Goals which select an entity's [`AttackTarget`].
+
+use base::{EntityKind, Position};
+use ecs::Entity;
+use quill_common::entities::Player;
+
+use crate::{damage::LastDamage, Game};
+
+use super::{
+    goal::{Goal, GoalControls},
+    is_targetable_player, nearest_entity,
+};
+
+/// Component storing the entity a mob is attacking.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AttackTarget(pub Option<Entity>);
+
+fn set_target(game: &Game, entity: Entity, target: Option<Entity>) {
+    if let Ok(mut attack_target) = game.ecs.get_mut::<AttackTarget>(entity) {
+        attack_target.0 = target;
+    }
+}
+
+fn current_target(game: &Game, entity: Entity) -> Option<Entity> {
+    game.ecs.get::<AttackTarget>(entity).ok()?.0
+}
+
+/// Returns whether `target` exists and, if it is a player, may be targeted.
+fn is_valid_target(game: &Game, target: Entity) -> bool {
+    game.ecs.get::<EntityKind>(target).is_ok()
+        && (game.ecs.get::<Player>(target).is_err() || is_targetable_player(game, target))
+}
+
+fn is_within(game: &Game, entity: Entity, target: Entity, range: f64) -> bool {
+    match (
+        game.ecs.get::<Position>(entity),
+        game.ecs.get::<Position>(target),
+    ) {
+        (Ok(a), Ok(b)) => a.distance_squared_to(*b) <= range * range,
+        _ => false,
+    }
+}
+
+/// Targets the nearest player within range.
+///
+/// Players in creative or spectator mode are ignored.
+pub struct NearestPlayerTargetGoal {
+    range: f64,
+}
+
+impl NearestPlayerTargetGoal {
+    pub fn new(range: f64) -> Self {
+        Self { range }
+    }
+}
+
+impl Goal for NearestPlayerTargetGoal {
+    fn controls(&self) -> GoalControls {
+        GoalControls::TARGET
+    }
+
+    fn can_start(&mut self, game: &mut Game, entity: Entity) -> bool {
+        let position = match game.ecs.get::<Position>(entity) {
+            Ok(pos) => *pos,
+            Err(_) => return false,
+        };
+        match nearest_entity(game, position, self.range, |other| {
+            is_targetable_player(game, other)
+        }) {
+            Some((player, _)) => {
+                set_target(game, entity, Some(player));
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn should_continue(&mut self, game: &mut Game, entity: Entity) -> bool {
+        match current_target(game, entity) {
+            Some(target) => {
+                is_targetable_player(game, target) && is_within(game, entity, target, self.range)
+            }
+            None => false,
+        }
+    }
+
+    fn stop(&mut self, game: &mut Game, entity: Entity) {
+        set_target(game, entity, None);
+    }
+}
+
+/// Targets the entity which last damaged this entity.
+pub struct HurtByTargetGoal {
+    /// Tick of the last damage which was retaliated against.
+    last_handled_tick: Option<u64>,
+}
+
+/// Distance at which a retaliating mob loses interest in its target.
+const RETALIATION_RANGE: f64 = 32.0;
+
+impl HurtByTargetGoal {
+    pub fn new() -> Self {
+        Self {
+            last_handled_tick: None,
+        }
+    }
+}
+
+impl Default for HurtByTargetGoal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Goal for HurtByTargetGoal {
+    fn controls(&self) -> GoalControls {
+        GoalControls::TARGET
+    }
+
+    fn can_start(&mut self, game: &mut Game, entity: Entity) -> bool {
+        let damage = match game.ecs.get::<LastDamage>(entity) {
+            Ok(damage) => *damage,
+            Err(_) => return false,
+        };
+        if self.last_handled_tick == Some(damage.tick) {
+            return false;
+        }
+        let attacker = match damage.attacker {
+            Some(attacker) if attacker != entity => attacker,
+            _ => return false,
+        };
+        if !is_valid_target(game, attacker) {
+            return false;
+        }
+
+        self.last_handled_tick = Some(damage.tick);
+        set_target(game, entity, Some(attacker));
+        true
+    }
+
+    fn should_continue(&mut self, game: &mut Game, entity: Entity) -> bool {
+        match current_target(game, entity) {
+            Some(target) => {
+                is_valid_target(game, target) && is_within(game, entity, target, RETALIATION_RANGE)
+            }
+            None => false,
+        }
+    }
+
+    fn stop(&mut self, game: &mut Game, entity: Entity) {
+        set_target(game, entity, None);
+    }
+}
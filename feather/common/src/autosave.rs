@@ -0,0 +1,138 @@
+//! Periodic saving of changed chunks and online players.
+//!
+//! Each autosave cycle queues the chunks which changed since they were
+//! last saved, along with the online players. Only a few of them are
+//! saved per tick to avoid lag spikes. Player data is written by the
+//! server, which takes the queued players from [`Autosave::take_players`].
+
+use std::collections::VecDeque;
+
+use base::{ChunkPosition, TPS};
+use ecs::{Entity, SysResult, SystemExecutor};
+use quill_common::entities::Player;
+
+use crate::{Game, World};
+
+/// Default number of ticks between autosave cycles (five minutes).
+pub const DEFAULT_INTERVAL: u64 = 5 * 60 * TPS as u64;
+
+/// Maximum number of chunks queued for saving per tick.
+const CHUNKS_PER_TICK: usize = 32;
+
+/// Maximum number of players saved per tick.
+const PLAYERS_PER_TICK: usize = 2;
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    game.insert_resource(Autosave::default());
+    systems.group::<Autosave>().add_system(autosave_chunks);
+}
+
+/// Resource controlling autosaves and whether saving is enabled.
+#[derive(Debug)]
+pub struct Autosave {
+    /// Number of ticks between cycles, or `None` if autosaving is disabled.
+    interval: Option<u64>,
+    /// Whether saving is enabled. Disabled by the `save-off` command.
+    saving_enabled: bool,
+    /// Tick at which the next cycle starts.
+    next_cycle: u64,
+    chunks: VecDeque<ChunkPosition>,
+    players: VecDeque<Entity>,
+}
+
+impl Default for Autosave {
+    fn default() -> Self {
+        Self::new(Some(DEFAULT_INTERVAL))
+    }
+}
+
+impl Autosave {
+    /// Creates an `Autosave` running a cycle every `interval` ticks,
+    /// or never if `interval` is `None`.
+    pub fn new(interval: Option<u64>) -> Self {
+        Self {
+            interval,
+            saving_enabled: true,
+            next_cycle: interval.unwrap_or_default(),
+            chunks: VecDeque::new(),
+            players: VecDeque::new(),
+        }
+    }
+
+    pub fn is_saving_enabled(&self) -> bool {
+        self.saving_enabled
+    }
+
+    /// Enables or disables saving. While disabled, no autosaves run
+    /// and chunks saved on unload are held in memory instead of
+    /// being written. Returns `false` if the state didn't change.
+    pub fn set_saving_enabled(&mut self, world: &mut World, enabled: bool) -> bool {
+        if self.saving_enabled == enabled {
+            return false;
+        }
+        self.saving_enabled = enabled;
+        world.set_saving_enabled(enabled);
+        true
+    }
+
+    /// Takes the next few players to save in the current cycle.
+    pub fn take_players(&mut self) -> Vec<Entity> {
+        if !self.saving_enabled {
+            return Vec::new();
+        }
+        let count = self.players.len().min(PLAYERS_PER_TICK);
+        self.players.drain(..count).collect()
+    }
+
+    fn is_cycle_running(&self) -> bool {
+        !self.chunks.is_empty() || !self.players.is_empty()
+    }
+
+    fn start_cycle(&mut self, game: &Game) {
+        self.chunks = game.world.dirty_chunks().into();
+        self.players = game
+            .ecs
+            .query::<&Player>()
+            .iter()
+            .map(|(player, _)| player)
+            .collect();
+        log::debug!(
+            "Autosaving {} chunks and {} players",
+            self.chunks.len(),
+            self.players.len()
+        );
+    }
+}
+
+fn autosave_chunks(game: &mut Game, autosave: &mut Autosave) -> SysResult {
+    if !autosave.saving_enabled {
+        return Ok(());
+    }
+
+    if let Some(interval) = autosave.interval {
+        // A cycle that is still running when the next one
+        // is due is not interrupted; that cycle is skipped.
+        if game.tick_count >= autosave.next_cycle {
+            autosave.next_cycle = game.tick_count + interval;
+            if !autosave.is_cycle_running() {
+                autosave.start_cycle(game);
+            }
+        }
+    }
+
+    if autosave.chunks.is_empty() {
+        return Ok(());
+    }
+    for _ in 0..CHUNKS_PER_TICK {
+        match autosave.chunks.pop_front() {
+            Some(pos) => {
+                game.world.save_chunk(pos);
+            }
+            None => break,
+        }
+    }
+    if autosave.chunks.is_empty() {
+        game.world.flush_chunks();
+    }
+    Ok(())
+}
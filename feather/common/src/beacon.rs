@@ -0,0 +1,316 @@
+//! Beacons.
+//!
+//! Using a beacon opens its window, where players pay with an ingot, diamond
+//! or emerald to choose its effects. A beacon applies its effects to nearby
+//! players as long as it stands on a pyramid of mineral blocks.
+//!
+//! Block entities are not supported yet, so the chosen effects are kept in
+//! the [`Beacons`] resource and lost when the server restarts. Beacons do not
+//! check for access to the sky.
+
+use std::{collections::HashMap, convert::TryFrom};
+
+use base::{BlockKind, BlockPosition, Inventory, Position, StatusEffect, ValidBlockPosition};
+use ecs::{Entity, SysResult, SystemExecutor};
+use quill_common::{
+    components::{ActiveEffects, EffectInstance},
+    entities::Player,
+    events::BlockInteractEvent,
+};
+
+use crate::{
+    effects::apply_effect,
+    events::{
+        BeaconEffectSetEvent, BeaconUpdateEvent, WindowCloseEvent, WindowOpenEvent,
+        WindowUpdateEvent,
+    },
+    interactable::InteractableRegistry,
+    window::{close_container_window, BackingWindow},
+    Game, Window,
+};
+
+/// Window index of the payment slot.
+const PAYMENT_SLOT: usize = 0;
+
+/// Ticks between applications of beacon effects.
+const EFFECT_INTERVAL: u64 = 80;
+
+/// Number of pyramid levels needed for all effects.
+pub const MAX_LEVELS: u32 = 4;
+
+/// Primary effects unlocked by each pyramid level.
+const PRIMARY_EFFECTS: [&[StatusEffect]; 3] = [
+    &[StatusEffect::Speed, StatusEffect::Haste],
+    &[StatusEffect::Resistance, StatusEffect::JumpBoost],
+    &[StatusEffect::Strength],
+];
+
+/// The secondary effect unlocked by a full pyramid,
+/// besides a stronger primary effect.
+const SECONDARY_EFFECT: StatusEffect = StatusEffect::Regeneration;
+
+/// The effects chosen for a beacon.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BeaconEffects {
+    pub primary: Option<StatusEffect>,
+    pub secondary: Option<StatusEffect>,
+}
+
+/// Resource holding the effects of beacons, by position.
+#[derive(Debug, Default)]
+pub struct Beacons(HashMap<ValidBlockPosition, BeaconEffects>);
+
+impl Beacons {
+    pub fn get(&self, position: ValidBlockPosition) -> BeaconEffects {
+        self.0.get(&position).copied().unwrap_or_default()
+    }
+}
+
+/// Component on players who have a beacon open.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct OpenBeacon {
+    pub position: ValidBlockPosition,
+    /// The number of pyramid levels below the beacon.
+    pub levels: u32,
+    pub effects: BeaconEffects,
+}
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    game.resources
+        .get_mut::<InteractableRegistry>()
+        .expect("Failed to get the interactable registry")
+        .register(BlockKind::Beacon);
+    game.insert_resource(Beacons::default());
+
+    systems
+        .add_system(open_beacons)
+        .add_system(set_beacon_effects)
+        .add_system(apply_beacon_effects)
+        .add_system(close_beacons);
+}
+
+/// Counts the pyramid levels of mineral blocks below a beacon.
+pub fn pyramid_levels(game: &Game, beacon: ValidBlockPosition) -> u32 {
+    for level in 1..=MAX_LEVELS as i32 {
+        let y = beacon.y() - level;
+        for x in beacon.x() - level..=beacon.x() + level {
+            for z in beacon.z() - level..=beacon.z() + level {
+                let kind = ValidBlockPosition::try_from(BlockPosition::new(x, y, z))
+                    .ok()
+                    .and_then(|pos| game.block(pos))
+                    .map(|block| block.kind());
+                if !matches!(
+                    kind,
+                    Some(BlockKind::IronBlock)
+                        | Some(BlockKind::GoldBlock)
+                        | Some(BlockKind::DiamondBlock)
+                        | Some(BlockKind::EmeraldBlock)
+                        | Some(BlockKind::NetheriteBlock)
+                ) {
+                    return level as u32 - 1;
+                }
+            }
+        }
+    }
+    MAX_LEVELS
+}
+
+/// Whether the effects can be chosen for a beacon with the given pyramid levels.
+fn effects_allowed(effects: BeaconEffects, levels: u32) -> bool {
+    let primary_allowed = match effects.primary {
+        Some(primary) => PRIMARY_EFFECTS
+            .iter()
+            .take(levels as usize)
+            .any(|tier| tier.contains(&primary)),
+        None => false,
+    };
+    let secondary_allowed = match effects.secondary {
+        Some(secondary) => {
+            levels >= MAX_LEVELS
+                && (secondary == SECONDARY_EFFECT || Some(secondary) == effects.primary)
+        }
+        None => true,
+    };
+    primary_allowed && secondary_allowed
+}
+
+/// Opens the beacon window for players who use a beacon.
+fn open_beacons(game: &mut Game) -> SysResult {
+    let opened: Vec<(Entity, ValidBlockPosition)> = game
+        .ecs
+        .query::<(&Player, &BlockInteractEvent)>()
+        .iter()
+        .filter_map(|(player, (_, event))| {
+            let position = ValidBlockPosition::try_from(event.location).ok()?;
+            let is_beacon = game.block(position)?.kind() == BlockKind::Beacon;
+            is_beacon.then_some((player, position))
+        })
+        .collect();
+
+    for (player, position) in opened {
+        if game.ecs.get::<OpenBeacon>(player).is_ok() {
+            continue;
+        }
+
+        let inventory = game.ecs.get::<Inventory>(player)?.new_handle();
+        game.ecs
+            .get_mut::<Window>(player)?
+            .set_inner(BackingWindow::Beacon {
+                beacon: Inventory::beacon(),
+                player: inventory,
+            });
+        let effects = game.resources.get::<Beacons>()?.get(position);
+        game.ecs.insert(
+            player,
+            OpenBeacon {
+                position,
+                levels: pyramid_levels(game, position),
+                effects,
+            },
+        )?;
+        game.ecs.insert_entity_event(player, WindowOpenEvent)?;
+        game.ecs.insert_entity_event(player, BeaconUpdateEvent)?;
+    }
+    Ok(())
+}
+
+/// Sets the effects chosen by players, taking their payment.
+fn set_beacon_effects(game: &mut Game) -> SysResult {
+    let chosen: Vec<(Entity, OpenBeacon, BeaconEffects)> = game
+        .ecs
+        .query::<(&BeaconEffectSetEvent, &OpenBeacon)>()
+        .iter()
+        .map(|(player, (event, &beacon))| {
+            let effects = BeaconEffects {
+                primary: event.primary,
+                secondary: event.secondary,
+            };
+            (player, beacon, effects)
+        })
+        .collect();
+
+    for (player, beacon, effects) in chosen {
+        if !effects_allowed(effects, beacon.levels) {
+            log::debug!("Rejecting beacon effects {:?}", effects);
+            continue;
+        }
+        {
+            let window = game.ecs.get::<Window>(player)?;
+            let mut payment = window.item(PAYMENT_SLOT)?;
+            if payment.try_take(1).is_empty() {
+                continue;
+            }
+        }
+
+        game.resources
+            .get_mut::<Beacons>()?
+            .0
+            .insert(beacon.position, effects);
+        game.ecs.get_mut::<OpenBeacon>(player)?.effects = effects;
+        game.ecs.insert_entity_event(player, WindowUpdateEvent)?;
+        game.ecs.insert_entity_event(player, BeaconUpdateEvent)?;
+    }
+    Ok(())
+}
+
+/// Applies the effects of beacons to players in range.
+fn apply_beacon_effects(game: &mut Game) -> SysResult {
+    if !game.tick_count.is_multiple_of(EFFECT_INTERVAL) {
+        return Ok(());
+    }
+
+    let beacons: Vec<(ValidBlockPosition, BeaconEffects)> = game
+        .resources
+        .get::<Beacons>()?
+        .0
+        .iter()
+        .map(|(&position, &effects)| (position, effects))
+        .collect();
+    let players: Vec<(Entity, Position)> = game
+        .ecs
+        .query::<(&Player, &Position, &ActiveEffects)>()
+        .iter()
+        .map(|(player, (_, &position, _))| (player, position))
+        .collect();
+
+    for (position, effects) in beacons {
+        if game.block(position).map(|block| block.kind()) != Some(BlockKind::Beacon) {
+            game.resources.get_mut::<Beacons>()?.0.remove(&position);
+            continue;
+        }
+        let levels = pyramid_levels(game, position);
+        let primary = match effects.primary {
+            Some(primary) if levels > 0 => primary,
+            _ => continue,
+        };
+
+        let range = f64::from(levels * 10 + 10);
+        let duration = (9 + levels * 2) * 20;
+        let amplifier = if levels >= MAX_LEVELS && effects.secondary == Some(primary) {
+            1
+        } else {
+            0
+        };
+        let mut applied = vec![(primary, amplifier)];
+        if let Some(secondary) = effects.secondary {
+            if levels >= MAX_LEVELS && secondary != primary {
+                applied.push((secondary, 0));
+            }
+        }
+
+        let center = position.position();
+        for &(player, player_position) in &players {
+            if (player_position.x - center.x - 0.5).abs() > range
+                || (player_position.z - center.z - 0.5).abs() > range
+            {
+                continue;
+            }
+            for &(effect, amplifier) in &applied {
+                let instance = EffectInstance {
+                    ambient: true,
+                    ..EffectInstance::new(amplifier, duration)
+                };
+                apply_effect(game, player, effect, instance)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn close_beacons(game: &mut Game) -> SysResult {
+    let closed: Vec<Entity> = game
+        .ecs
+        .query::<(&WindowCloseEvent, &OpenBeacon)>()
+        .iter()
+        .map(|(player, _)| player)
+        .collect();
+
+    for player in closed {
+        game.ecs.remove::<OpenBeacon>(player)?;
+        close_container_window(game, player)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn effects_require_pyramid_levels() {
+        let effects = |primary, secondary| BeaconEffects { primary, secondary };
+        let speed = Some(StatusEffect::Speed);
+        let strength = Some(StatusEffect::Strength);
+        let regeneration = Some(StatusEffect::Regeneration);
+
+        assert!(effects_allowed(effects(speed, None), 1));
+        assert!(!effects_allowed(effects(strength, None), 2));
+        assert!(effects_allowed(effects(strength, None), 3));
+        assert!(!effects_allowed(effects(speed, regeneration), 3));
+        assert!(effects_allowed(effects(speed, regeneration), 4));
+        assert!(effects_allowed(effects(strength, strength), 4));
+        assert!(!effects_allowed(effects(strength, speed), 4));
+        assert!(!effects_allowed(effects(None, regeneration), 4));
+        assert!(!effects_allowed(effects(regeneration, None), 4));
+    }
+}
@@ -0,0 +1,57 @@
+//! Tracks the time players spend near each chunk, saved as the
+//! chunk's `InhabitedTime`. Vanilla uses it to scale local difficulty,
+//! and it is used to trim rarely visited chunks from worlds.
+
+use ahash::AHashSet;
+use base::{ChunkPosition, Gamemode, Position, CHUNK_WIDTH};
+use ecs::{SysResult, SystemExecutor};
+use quill_common::entities::Player;
+
+use crate::Game;
+
+/// Chunks whose centers are within this many blocks
+/// of a player are considered inhabited.
+const INHABITED_DISTANCE: f64 = 128.0;
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.add_system(increment_inhabited_time);
+}
+
+/// Adds a tick to the inhabited time of each loaded chunk
+/// near a player who isn't spectating.
+fn increment_inhabited_time(game: &mut Game) -> SysResult {
+    let radius = (INHABITED_DISTANCE / CHUNK_WIDTH as f64).ceil() as i32;
+    let mut inhabited = AHashSet::new();
+    for (_, (_, position, &gamemode)) in game.ecs.query::<(&Player, &Position, &Gamemode)>().iter()
+    {
+        if gamemode == Gamemode::Spectator {
+            continue;
+        }
+
+        let center = position.chunk();
+        for x in center.x - radius..=center.x + radius {
+            for z in center.z - radius..=center.z + radius {
+                let chunk = ChunkPosition::new(x, z);
+                if chunk_center_distance_squared(chunk, position)
+                    < INHABITED_DISTANCE * INHABITED_DISTANCE
+                {
+                    inhabited.insert(chunk);
+                }
+            }
+        }
+    }
+
+    for pos in inhabited {
+        if let Some(mut chunk) = game.world.chunk_map().chunk_at_mut(pos) {
+            let ticks = chunk.inhabited_time() + 1;
+            chunk.set_inhabited_time(ticks);
+        }
+    }
+    Ok(())
+}
+
+fn chunk_center_distance_squared(chunk: ChunkPosition, position: &Position) -> f64 {
+    let dx = f64::from(chunk.x * 16 + 8) - position.x;
+    let dz = f64::from(chunk.z * 16 + 8) - position.z;
+    dx * dx + dz * dz
+}
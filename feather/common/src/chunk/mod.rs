@@ -1,4 +1,8 @@
 pub mod cache;
 pub mod entities;
+pub mod inhabited;
 pub mod loading;
+pub mod pregen;
+pub mod storage;
+mod storage_worker;
 pub mod worker;
@@ -0,0 +1,261 @@
+//! Generation of the chunks inside the world border ahead of time.
+//!
+//! Chunks are queued in a square spiral around the border's center.
+//! Only a few chunks generate at once, and no chunks are queued on ticks
+//! following a slow tick, so that the tick loop stays healthy.
+//! Progress is saved to the world directory, so that pregeneration
+//! resumes after a restart.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use ahash::AHashMap;
+use base::ChunkPosition;
+use ecs::{SysResult, SystemExecutor};
+
+use crate::{world_border::WorldBorder, Game, World};
+
+/// Name of the file in the world directory storing pregeneration progress.
+pub const PROGRESS_FILE: &str = "pregeneration.txt";
+
+/// Maximum number of chunks being generated at once.
+const MAX_IN_FLIGHT: usize = 32;
+
+/// Maximum number of chunks queued per tick.
+const MAX_QUEUED_PER_TICK: usize = 8;
+
+/// No chunks are queued if the previous tick took longer than this.
+const SLOW_TICK: Duration = Duration::from_millis(60);
+
+/// Number of completed chunks after which progress is saved and logged.
+const SAVE_INTERVAL: u64 = 1024;
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.add_system(pregenerate_chunks);
+}
+
+/// A pregeneration job, stored as a resource while it runs.
+#[derive(Debug)]
+pub struct Pregeneration {
+    center: ChunkPosition,
+    /// Distance, in chunks, from the center to the edges of the spiral.
+    radius: i32,
+    /// Spiral index of the next chunk to queue.
+    next_index: u64,
+    /// Chunks being generated, with their spiral indices.
+    in_flight: AHashMap<ChunkPosition, u64>,
+    completed: u64,
+    progress_path: PathBuf,
+    last_tick: Option<Instant>,
+}
+
+impl Pregeneration {
+    /// Creates a job covering the chunks inside the given border.
+    pub fn for_border(border: &WorldBorder, world_dir: &Path) -> Self {
+        let (x, z) = border.center();
+        let center = ChunkPosition::new((x / 16.0).floor() as i32, (z / 16.0).floor() as i32);
+        let radius = (border.size() / 2.0 / 16.0).ceil() as i32 + 1;
+        Self {
+            center,
+            radius,
+            next_index: 0,
+            in_flight: AHashMap::new(),
+            completed: 0,
+            progress_path: world_dir.join(PROGRESS_FILE),
+            last_tick: None,
+        }
+    }
+
+    /// Creates a job covering the chunks inside the given border, resuming
+    /// from the saved progress if it was saved for the same area.
+    pub fn resume_or_start(border: &WorldBorder, world_dir: &Path) -> anyhow::Result<Self> {
+        let mut job = Self::for_border(border, world_dir);
+        if !job.progress_path.exists() {
+            return Ok(job);
+        }
+
+        let progress = fs::read_to_string(&job.progress_path)?;
+        let values: Vec<i64> = progress
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<_, _>>()?;
+        match values[..] {
+            [x, z, radius, next_index]
+                if (x, z, radius)
+                    == (job.center.x.into(), job.center.z.into(), job.radius.into()) =>
+            {
+                job.next_index = next_index as u64;
+                job.completed = next_index as u64;
+                log::info!("Resuming pregeneration at {:.1}%", job.progress() * 100.0);
+            }
+            _ => log::info!("The world border changed; restarting pregeneration"),
+        }
+        Ok(job)
+    }
+
+    /// Returns the fraction of the chunks which have been handled.
+    pub fn progress(&self) -> f64 {
+        (self.completed as f64 / self.total() as f64).min(1.0)
+    }
+
+    /// Determines whether all chunks have been generated.
+    pub fn is_finished(&self) -> bool {
+        self.next_index >= self.total() && self.in_flight.is_empty()
+    }
+
+    fn total(&self) -> u64 {
+        let side = 2 * self.radius as u64 + 1;
+        side * side
+    }
+
+    /// Returns the chunk at the given index of the spiral.
+    fn position(&self, index: u64) -> ChunkPosition {
+        let (x, z) = spiral_offset(index);
+        ChunkPosition::new(self.center.x + x, self.center.z + z)
+    }
+
+    /// Queues chunks for generation, up to the in-flight limit.
+    fn queue_chunks(&mut self, world: &mut World, border: &WorldBorder) {
+        let mut queued = 0;
+        while self.in_flight.len() < MAX_IN_FLIGHT
+            && queued < MAX_QUEUED_PER_TICK
+            && self.next_index < self.total()
+        {
+            let index = self.next_index;
+            self.next_index += 1;
+
+            let pos = self.position(index);
+            if !border.intersects_chunk(pos)
+                || world.is_chunk_loaded(pos)
+                || world.is_chunk_loading(pos)
+                || world.cache.contains(&pos)
+            {
+                self.completed += 1;
+                continue;
+            }
+
+            world.queue_pregeneration(pos);
+            self.in_flight.insert(pos, index);
+            queued += 1;
+        }
+    }
+
+    fn complete(&mut self, pos: ChunkPosition) -> anyhow::Result<()> {
+        if self.in_flight.remove(&pos).is_none() {
+            return Ok(());
+        }
+        self.completed += 1;
+        if self.completed.is_multiple_of(SAVE_INTERVAL) {
+            log::info!("Pregeneration {:.1}% done", self.progress() * 100.0);
+            self.save_progress()?;
+        }
+        Ok(())
+    }
+
+    /// Saves the spiral index before which all chunks are done.
+    fn save_progress(&self) -> anyhow::Result<()> {
+        let resume_index = self
+            .in_flight
+            .values()
+            .copied()
+            .min()
+            .unwrap_or(self.next_index);
+        fs::write(
+            &self.progress_path,
+            format!(
+                "{} {} {} {}\n",
+                self.center.x, self.center.z, self.radius, resume_index
+            ),
+        )?;
+        Ok(())
+    }
+}
+
+/// Returns the offset of the chunk at the given index of a square spiral
+/// starting at the origin.
+fn spiral_offset(index: u64) -> (i32, i32) {
+    if index == 0 {
+        return (0, 0);
+    }
+
+    // Ring `k` contains the 8k indices starting at (2k - 1)^2.
+    let mut root = (index as f64).sqrt() as u64;
+    while root * root > index {
+        root -= 1;
+    }
+    while (root + 1) * (root + 1) <= index {
+        root += 1;
+    }
+    let k = root.div_ceil(2);
+    let position = index - (2 * k - 1) * (2 * k - 1);
+    let (side, offset) = (position / (2 * k), (position % (2 * k)) as i32);
+    let k = k as i32;
+    match side {
+        0 => (k, -k + 1 + offset),
+        1 => (k - 1 - offset, k),
+        2 => (-k, k - 1 - offset),
+        _ => (-k + 1 + offset, -k),
+    }
+}
+
+fn pregenerate_chunks(game: &mut Game) -> SysResult {
+    let resources = Arc::clone(&game.resources);
+    let mut job = match resources.get_mut::<Pregeneration>() {
+        Ok(job) => job,
+        Err(_) => return Ok(()),
+    };
+    if job.is_finished() {
+        return Ok(());
+    }
+
+    for pos in game.world.poll_pregenerated() {
+        job.complete(pos)?;
+    }
+
+    if job.is_finished() {
+        log::info!("Pregeneration finished");
+        if job.progress_path.exists() {
+            fs::remove_file(&job.progress_path)?;
+        }
+        return Ok(());
+    }
+
+    let now = Instant::now();
+    let slow = job
+        .last_tick
+        .is_some_and(|last_tick| now - last_tick > SLOW_TICK);
+    job.last_tick = Some(now);
+    if !slow {
+        job.queue_chunks(&mut game.world, &*resources.get::<WorldBorder>()?);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ahash::AHashSet;
+
+    use super::*;
+
+    #[test]
+    fn spiral_covers_square() {
+        let radius = 3;
+        let side = 2 * radius as u64 + 1;
+        let offsets: AHashSet<(i32, i32)> = (0..side * side).map(spiral_offset).collect();
+        assert_eq!(offsets.len() as u64, side * side);
+        assert!(offsets
+            .iter()
+            .all(|&(x, z)| x.abs() <= radius && z.abs() <= radius));
+
+        // Each chunk borders the previous one.
+        for index in 1..side * side {
+            let (x0, z0) = spiral_offset(index - 1);
+            let (x1, z1) = spiral_offset(index);
+            assert!((x1 - x0).abs().max((z1 - z0).abs()) == 1);
+        }
+    }
+}
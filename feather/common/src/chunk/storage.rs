@@ -0,0 +1,83 @@
+//! Storage backends for the chunks of a world.
+//!
+//! The [`ChunkWorker`](super::worker::ChunkWorker) loads and saves chunks
+//! through a [`ChunkStorage`] on its own thread. Which storage a world uses
+//! is selected with [`StorageOptions`].
+
+use std::path::{Path, PathBuf};
+
+use base::{
+    anvil::{block_entity::BlockEntityData, entity::EntityData},
+    Chunk, ChunkPosition,
+};
+
+pub mod anvil;
+pub mod memory;
+pub mod overlay;
+
+pub use self::anvil::AnvilStorage;
+pub use memory::MemoryStorage;
+pub use overlay::OverlayStorage;
+
+/// A chunk along with the entities saved in it.
+#[derive(Debug, Clone)]
+pub struct StoredChunk {
+    pub chunk: Chunk,
+    pub entities: Vec<EntityData>,
+    pub block_entities: Vec<BlockEntityData>,
+    /// Whether only the chunk's terrain was generated, so the
+    /// world generator still has to finish it.
+    pub needs_finishing: bool,
+}
+
+/// A place where chunks are loaded from and saved to.
+pub trait ChunkStorage: Send + 'static {
+    /// Loads the chunk at the given position.
+    ///
+    /// Returns `None` if the chunk has not been saved.
+    fn load(&mut self, pos: ChunkPosition) -> anyhow::Result<Option<StoredChunk>>;
+
+    /// Saves a chunk, replacing any chunk saved at its position.
+    fn save(
+        &mut self,
+        chunk: &Chunk,
+        entities: &[EntityData],
+        block_entities: &[BlockEntityData],
+    ) -> anyhow::Result<()>;
+
+    /// Determines whether a chunk has been saved at the given position.
+    fn exists(&mut self, pos: ChunkPosition) -> bool;
+
+    /// Ensures saved chunks are written out and releases unused resources.
+    ///
+    /// Called periodically while the storage is idle and before it is dropped.
+    fn flush(&mut self) -> anyhow::Result<()>;
+}
+
+/// Selects the [`ChunkStorage`] of a world.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum StorageOptions {
+    /// Region files in the world directory.
+    #[default]
+    Anvil,
+    /// Memory only, so the world is reset on restart.
+    Memory,
+    /// Reads chunks from the region files of a template world without
+    /// ever modifying them. Changed chunks are kept in memory, so the
+    /// world is reset to the template on restart.
+    Overlay { template: PathBuf },
+}
+
+impl StorageOptions {
+    /// Opens the storage for the world in `world_dir`.
+    pub fn open(&self, world_dir: &Path) -> Box<dyn ChunkStorage> {
+        match self {
+            StorageOptions::Anvil => Box::new(AnvilStorage::new(world_dir)),
+            StorageOptions::Memory => Box::new(MemoryStorage::new()),
+            StorageOptions::Overlay { template } => Box::new(OverlayStorage::new(
+                Box::new(AnvilStorage::read_only(template)),
+                Box::new(MemoryStorage::new()),
+            )),
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! Stores chunks in the region files of a vanilla world.
+
+use std::{
+    collections::hash_map::Entry,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use ahash::AHashMap;
+use anyhow::bail;
+use base::{
+    anvil::{
+        self,
+        block_entity::BlockEntityData,
+        entity::EntityData,
+        region::{RegionHandle, RegionPosition},
+    },
+    Chunk, ChunkPosition,
+};
+
+use super::{ChunkStorage, StoredChunk};
+
+/// Duration to keep a region file open when not in use.
+const CACHE_TIME: Duration = Duration::from_secs(60);
+
+struct OpenRegionFile {
+    handle: RegionHandle,
+    last_used: Instant,
+}
+
+impl OpenRegionFile {
+    pub fn new(handle: RegionHandle) -> Self {
+        Self {
+            handle,
+            last_used: Instant::now(),
+        }
+    }
+
+    pub fn should_close(&self) -> bool {
+        self.last_used.elapsed() >= CACHE_TIME
+    }
+}
+
+/// A [`ChunkStorage`] backed by Anvil region files in a world directory.
+pub struct AnvilStorage {
+    world_dir: PathBuf,
+    read_only: bool,
+    region_files: AHashMap<RegionPosition, OpenRegionFile>,
+    last_cache_update: Instant,
+}
+
+impl AnvilStorage {
+    pub fn new(world_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            world_dir: world_dir.into(),
+            read_only: false,
+            region_files: AHashMap::new(),
+            last_cache_update: Instant::now(),
+        }
+    }
+
+    /// Creates a storage which only reads region files, so the
+    /// world can be on a read-only mount or lack write permissions.
+    /// Saving chunks fails.
+    pub fn read_only(world_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            read_only: true,
+            ..Self::new(world_dir)
+        }
+    }
+
+    fn region_file_handle(&mut self, region: RegionPosition) -> Option<&mut OpenRegionFile> {
+        self.update_cache();
+        match self.region_files.entry(region) {
+            Entry::Occupied(e) => Some(e.into_mut()),
+            Entry::Vacant(e) => {
+                let handle = if self.read_only {
+                    anvil::region::load_region_read_only(&self.world_dir, region)
+                } else {
+                    anvil::region::load_region(&self.world_dir, region)
+                };
+                if let Ok(handle) = handle {
+                    Some(e.insert(OpenRegionFile::new(handle)))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Closes region files which have not been used recently.
+    fn update_cache(&mut self) {
+        if self.last_cache_update.elapsed() >= CACHE_TIME {
+            let initial_len = self.region_files.len();
+
+            self.region_files.retain(|_, file| !file.should_close());
+            self.last_cache_update = Instant::now();
+
+            let closed_count = initial_len - self.region_files.len();
+            if closed_count != 0 {
+                log::debug!(
+                    "Closed {} region files ({} still open)",
+                    closed_count,
+                    self.region_files.len()
+                );
+            }
+        }
+    }
+}
+
+impl ChunkStorage for AnvilStorage {
+    fn load(&mut self, pos: ChunkPosition) -> anyhow::Result<Option<StoredChunk>> {
+        let file = match self.region_file_handle(RegionPosition::from_chunk(pos)) {
+            Some(file) => file,
+            None => return Ok(None),
+        };
+
+        let (chunk, status, entities, block_entities) = match file.handle.load_partial_chunk(pos) {
+            Ok(loaded) => loaded,
+            Err(anvil::region::Error::ChunkNotExist) => return Ok(None),
+            // Chunks without terrain have no blocks to keep, so they're generated again.
+            Err(anvil::region::Error::IncompleteChunk(status)) => {
+                log::debug!(
+                    "Regenerating chunk {:?}, which was only generated up to {}",
+                    pos,
+                    status.name()
+                );
+                return Ok(None);
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        file.last_used = Instant::now();
+
+        Ok(Some(StoredChunk {
+            chunk,
+            entities,
+            block_entities,
+            needs_finishing: !status.has_blocks(),
+        }))
+    }
+
+    fn save(
+        &mut self,
+        chunk: &Chunk,
+        entities: &[EntityData],
+        block_entities: &[BlockEntityData],
+    ) -> anyhow::Result<()> {
+        if self.read_only {
+            bail!(
+                "cannot save chunk {:?} to read-only world {}",
+                chunk.position(),
+                self.world_dir.display()
+            );
+        }
+
+        let reg_pos = RegionPosition::from_chunk(chunk.position());
+        let file = match self.region_file_handle(reg_pos) {
+            Some(file) => file,
+            None => {
+                let new_handle = anvil::region::create_region(&self.world_dir, reg_pos)?;
+                self.region_files
+                    .entry(reg_pos)
+                    .or_insert(OpenRegionFile::new(new_handle))
+            }
+        };
+        file.handle.save_chunk(chunk, entities, block_entities)?;
+        file.last_used = Instant::now();
+        Ok(())
+    }
+
+    fn exists(&mut self, pos: ChunkPosition) -> bool {
+        self.region_file_handle(RegionPosition::from_chunk(pos))
+            .is_some_and(|file| file.handle.check_chunk_existence(pos))
+    }
+
+    fn flush(&mut self) -> anyhow::Result<()> {
+        for file in self.region_files.values_mut() {
+            file.handle.sync()?;
+        }
+        self.update_cache();
+        Ok(())
+    }
+}
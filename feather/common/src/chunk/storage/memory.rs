@@ -0,0 +1,54 @@
+//! Keeps chunks in memory only.
+
+use ahash::AHashMap;
+use base::{
+    anvil::{block_entity::BlockEntityData, entity::EntityData},
+    Chunk, ChunkPosition,
+};
+
+use super::{ChunkStorage, StoredChunk};
+
+/// A [`ChunkStorage`] which keeps saved chunks in memory,
+/// losing them when the server stops.
+#[derive(Default)]
+pub struct MemoryStorage {
+    chunks: AHashMap<ChunkPosition, StoredChunk>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ChunkStorage for MemoryStorage {
+    fn load(&mut self, pos: ChunkPosition) -> anyhow::Result<Option<StoredChunk>> {
+        Ok(self.chunks.get(&pos).cloned())
+    }
+
+    fn save(
+        &mut self,
+        chunk: &Chunk,
+        entities: &[EntityData],
+        block_entities: &[BlockEntityData],
+    ) -> anyhow::Result<()> {
+        self.chunks.insert(
+            chunk.position(),
+            StoredChunk {
+                chunk: chunk.clone(),
+                entities: entities.to_vec(),
+                block_entities: block_entities.to_vec(),
+                needs_finishing: false,
+            },
+        );
+        Ok(())
+    }
+
+    fn exists(&mut self, pos: ChunkPosition) -> bool {
+        self.chunks.contains_key(&pos)
+    }
+
+    fn flush(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
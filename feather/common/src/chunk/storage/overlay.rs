@@ -0,0 +1,80 @@
+//! Copy-on-write storage on top of a read-only template world.
+
+use base::{
+    anvil::{block_entity::BlockEntityData, entity::EntityData},
+    Chunk, ChunkPosition,
+};
+
+use super::{ChunkStorage, StoredChunk};
+
+/// A [`ChunkStorage`] which reads chunks from a template storage
+/// but saves them to a separate storage of changes.
+///
+/// The template is never written to, so several worlds
+/// can share it.
+pub struct OverlayStorage {
+    template: Box<dyn ChunkStorage>,
+    changes: Box<dyn ChunkStorage>,
+}
+
+impl OverlayStorage {
+    pub fn new(template: Box<dyn ChunkStorage>, changes: Box<dyn ChunkStorage>) -> Self {
+        Self { template, changes }
+    }
+}
+
+impl ChunkStorage for OverlayStorage {
+    fn load(&mut self, pos: ChunkPosition) -> anyhow::Result<Option<StoredChunk>> {
+        match self.changes.load(pos)? {
+            Some(chunk) => Ok(Some(chunk)),
+            None => self.template.load(pos),
+        }
+    }
+
+    fn save(
+        &mut self,
+        chunk: &Chunk,
+        entities: &[EntityData],
+        block_entities: &[BlockEntityData],
+    ) -> anyhow::Result<()> {
+        self.changes.save(chunk, entities, block_entities)
+    }
+
+    fn exists(&mut self, pos: ChunkPosition) -> bool {
+        self.changes.exists(pos) || self.template.exists(pos)
+    }
+
+    fn flush(&mut self) -> anyhow::Result<()> {
+        self.changes.flush()?;
+        self.template.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base::{BlockId, ChunkPosition};
+
+    use super::*;
+    use crate::chunk::storage::MemoryStorage;
+
+    #[test]
+    fn changes_do_not_modify_template() {
+        let pos = ChunkPosition::new(1, 2);
+        let mut template = MemoryStorage::new();
+        template.save(&Chunk::new(pos), &[], &[]).unwrap();
+
+        let mut storage = OverlayStorage::new(Box::new(template), Box::new(MemoryStorage::new()));
+        assert!(storage.exists(pos));
+        assert!(!storage.exists(ChunkPosition::new(0, 0)));
+
+        let mut chunk = storage.load(pos).unwrap().unwrap().chunk;
+        assert_eq!(chunk.block_at(0, 0, 0), Some(BlockId::air()));
+        chunk.set_block_at(0, 0, 0, BlockId::stone());
+        storage.save(&chunk, &[], &[]).unwrap();
+
+        let loaded = storage.load(pos).unwrap().unwrap().chunk;
+        assert_eq!(loaded.block_at(0, 0, 0), Some(BlockId::stone()));
+        let template = storage.template.load(pos).unwrap().unwrap().chunk;
+        assert_eq!(template.block_at(0, 0, 0), Some(BlockId::air()));
+    }
+}
@@ -0,0 +1,307 @@
+use std::{
+    mem,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use ahash::AHashMap;
+use base::ChunkPosition;
+use flume::{Receiver, Sender};
+
+use super::{
+    storage::ChunkStorage,
+    worker::{ChunkLoadResult, LoadRequest, LoadedChunk, SaveRequest, WorkerRequest},
+};
+
+/// Duration without requests after which the storage is flushed.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Serves load and save requests from a [`ChunkStorage`] on its own thread.
+pub struct StorageWorker {
+    request_receiver: Receiver<WorkerRequest>,
+    result_sender: Sender<ChunkLoadResult>,
+    storage: Box<dyn ChunkStorage>,
+    saving_enabled: bool,
+    /// The latest save of each chunk received while saving is disabled.
+    held_saves: AHashMap<ChunkPosition, SaveRequest>,
+    /// Number of saves received which haven't been written yet.
+    pending_saves: Arc<AtomicUsize>,
+}
+
+impl StorageWorker {
+    pub fn new(
+        storage: Box<dyn ChunkStorage>,
+        request_receiver: Receiver<WorkerRequest>,
+        pending_saves: Arc<AtomicUsize>,
+    ) -> (Self, Receiver<ChunkLoadResult>) {
+        let (result_sender, result_receiver) = flume::bounded(256);
+        (
+            Self {
+                request_receiver,
+                result_sender,
+                storage,
+                saving_enabled: true,
+                held_saves: AHashMap::new(),
+                pending_saves,
+            },
+            result_receiver,
+        )
+    }
+
+    pub fn start(self) -> JoinHandle<()> {
+        std::thread::Builder::new()
+            .name("chunk_worker".to_owned())
+            .spawn(move || self.run())
+            .expect("failed to create chunk worker thread")
+    }
+
+    fn run(mut self) {
+        log::info!("Chunk worker started");
+        loop {
+            match self.request_receiver.recv_timeout(FLUSH_INTERVAL) {
+                Ok(req) => match req {
+                    WorkerRequest::Load(load) => self.load_chunk(load),
+                    WorkerRequest::Save(save) => self.save_chunk(save),
+                    WorkerRequest::Flush => self.write_all(),
+                    WorkerRequest::SetSavingEnabled(enabled) => {
+                        self.saving_enabled = enabled;
+                        if enabled {
+                            self.write_all();
+                        }
+                    }
+                    WorkerRequest::Sync(done) => {
+                        let _ = done.send(());
+                    }
+                    WorkerRequest::Pregenerate(pos) => {
+                        let exists = self.held_saves.contains_key(&pos) || self.storage.exists(pos);
+                        let _ = self
+                            .result_sender
+                            .send(ChunkLoadResult::Pregeneration { pos, exists });
+                    }
+                    WorkerRequest::Shutdown => {
+                        self.write_all();
+                        log::info!("Chunk worker shut down");
+                        return;
+                    }
+                },
+                Err(flume::RecvTimeoutError::Timeout) => {
+                    if self.saving_enabled {
+                        self.flush();
+                    }
+                }
+                Err(flume::RecvTimeoutError::Disconnected) => {
+                    self.write_all();
+                    log::info!("Chunk worker shutting down");
+                    return;
+                }
+            }
+        }
+    }
+
+    fn save_chunk(&mut self, req: SaveRequest) {
+        if !self.saving_enabled {
+            // A newer save replaces the held one, which is never written.
+            if self.held_saves.insert(req.pos, req).is_some() {
+                self.pending_saves.fetch_sub(1, Ordering::Relaxed);
+            }
+            return;
+        }
+        self.write_chunk(req);
+    }
+
+    fn write_chunk(&mut self, req: SaveRequest) {
+        if let Err(e) = self.storage.save(
+            &req.chunk.read(),
+            &req.entities[..],
+            &req.block_entities[..],
+        ) {
+            log::error!("Failed to save chunk {:?}: {:?}", req.pos, e);
+        }
+        self.pending_saves.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Writes the held saves and flushes the storage.
+    fn write_all(&mut self) {
+        for (_, req) in mem::take(&mut self.held_saves) {
+            self.write_chunk(req);
+        }
+        self.flush();
+    }
+
+    fn load_chunk(&mut self, req: LoadRequest) {
+        let result = self.get_chunk_load_result(req);
+        let _ = self.result_sender.send(result);
+    }
+
+    fn get_chunk_load_result(&mut self, req: LoadRequest) -> ChunkLoadResult {
+        let pos = req.pos;
+        if let Some(held) = self.held_saves.get(&pos) {
+            // The storage still contains an older version.
+            return ChunkLoadResult::Loaded(LoadedChunk {
+                pos,
+                chunk: held.chunk.read().clone(),
+                generated: false,
+            });
+        }
+        match self.storage.load(pos) {
+            Ok(Some(stored)) if stored.needs_finishing => ChunkLoadResult::Unfinished(stored.chunk),
+            Ok(Some(stored)) => ChunkLoadResult::Loaded(LoadedChunk {
+                pos,
+                chunk: stored.chunk,
+                generated: false,
+            }),
+            Ok(None) => ChunkLoadResult::Missing(pos),
+            Err(e) => ChunkLoadResult::Error(e),
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Err(e) = self.storage.flush() {
+            log::error!("Failed to flush chunk storage: {:?}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use base::{
+        anvil::{block_entity::BlockEntityData, entity::EntityData},
+        BlockId, Chunk, ChunkLock,
+    };
+    use parking_lot::Mutex;
+    use worldgen::{VoidWorldGenerator, WorldGenerator};
+
+    use super::*;
+    use crate::chunk::{
+        storage::{MemoryStorage, StoredChunk},
+        worker::ChunkWorker,
+    };
+
+    /// A memory storage which can be inspected while the worker owns it.
+    #[derive(Clone, Default)]
+    struct SharedStorage(Arc<Mutex<MemoryStorage>>);
+
+    impl ChunkStorage for SharedStorage {
+        fn load(&mut self, pos: ChunkPosition) -> anyhow::Result<Option<StoredChunk>> {
+            self.0.lock().load(pos)
+        }
+
+        fn save(
+            &mut self,
+            chunk: &Chunk,
+            entities: &[EntityData],
+            block_entities: &[BlockEntityData],
+        ) -> anyhow::Result<()> {
+            self.0.lock().save(chunk, entities, block_entities)
+        }
+
+        fn exists(&mut self, pos: ChunkPosition) -> bool {
+            self.0.lock().exists(pos)
+        }
+
+        fn flush(&mut self) -> anyhow::Result<()> {
+            self.0.lock().flush()
+        }
+    }
+
+    #[test]
+    fn saves_are_held_while_saving_is_disabled() {
+        let storage = SharedStorage::default();
+        let mut worker = ChunkWorker::new(Box::new(storage.clone()), Arc::new(VoidWorldGenerator));
+
+        let pos = ChunkPosition::new(3, -2);
+        let mut chunk = Chunk::new(pos);
+        chunk.set_block_at(0, 0, 0, BlockId::stone());
+        worker.set_saving_enabled(false);
+        worker.queue_chunk_save(SaveRequest {
+            pos,
+            chunk: Arc::new(ChunkLock::new(chunk, false)),
+            entities: vec![],
+            block_entities: vec![],
+        });
+        worker.sync();
+        assert!(!storage.0.lock().exists(pos));
+
+        // The held chunk is loaded instead of the stored one.
+        worker.queue_load(LoadRequest { pos });
+        worker.sync();
+        let loaded = worker.poll_loaded_chunk().unwrap().unwrap();
+        assert_eq!(loaded.chunk.block_at(0, 0, 0), Some(BlockId::stone()));
+
+        worker.set_saving_enabled(true);
+        worker.sync();
+        assert!(storage.0.lock().exists(pos));
+    }
+
+    /// A storage containing a single chunk whose generation was interrupted.
+    struct UnfinishedStorage(Chunk);
+
+    impl ChunkStorage for UnfinishedStorage {
+        fn load(&mut self, pos: ChunkPosition) -> anyhow::Result<Option<StoredChunk>> {
+            Ok((pos == self.0.position()).then(|| StoredChunk {
+                chunk: self.0.clone(),
+                entities: vec![],
+                block_entities: vec![],
+                needs_finishing: true,
+            }))
+        }
+
+        fn save(
+            &mut self,
+            _: &Chunk,
+            _: &[EntityData],
+            _: &[BlockEntityData],
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn exists(&mut self, pos: ChunkPosition) -> bool {
+            pos == self.0.position()
+        }
+
+        fn flush(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Finishes chunks by placing a single dirt block.
+    struct DirtFinisher;
+
+    impl WorldGenerator for DirtFinisher {
+        fn generate_chunk(&self, position: ChunkPosition) -> Chunk {
+            Chunk::new(position)
+        }
+
+        fn finish_chunk(&self, chunk: &mut Chunk) {
+            chunk.set_block_at(1, 0, 0, BlockId::dirt());
+        }
+    }
+
+    #[test]
+    fn unfinished_chunks_keep_their_blocks() {
+        let pos = ChunkPosition::new(-1, 5);
+        let mut chunk = Chunk::new(pos);
+        chunk.set_block_at(0, 0, 0, BlockId::stone());
+        let mut worker =
+            ChunkWorker::new(Box::new(UnfinishedStorage(chunk)), Arc::new(DirtFinisher));
+
+        worker.queue_load(LoadRequest { pos });
+        worker.sync();
+        let loaded = loop {
+            if let Some(loaded) = worker.poll_loaded_chunk().unwrap() {
+                break loaded;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        };
+
+        assert!(loaded.generated);
+        assert_eq!(loaded.chunk.block_at(0, 0, 0), Some(BlockId::stone()));
+        assert_eq!(loaded.chunk.block_at(1, 0, 0), Some(BlockId::dirt()));
+    }
+}
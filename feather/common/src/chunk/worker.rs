@@ -1,4 +1,10 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+};
 
 use anyhow::bail;
 use base::{
@@ -8,7 +14,7 @@ use base::{
 use flume::{Receiver, Sender};
 use worldgen::WorldGenerator;
 
-use crate::region_worker::RegionWorker;
+use super::{storage::ChunkStorage, storage_worker::StorageWorker};
 
 #[derive(Debug)]
 pub struct LoadRequest {
@@ -18,6 +24,9 @@ pub struct LoadRequest {
 pub struct LoadedChunk {
     pub pos: ChunkPosition,
     pub chunk: Chunk,
+    /// Whether the chunk was newly generated
+    /// rather than loaded from the world save.
+    pub generated: bool,
 }
 
 #[derive(Debug)]
@@ -29,6 +38,11 @@ pub enum ChunkLoadResult {
     Error(anyhow::Error),
     /// Successfully loaded the chunk.
     Loaded(LoadedChunk),
+    /// Loaded a chunk whose generation has to be finished.
+    Unfinished(Chunk),
+    /// Answers a pregeneration request with whether the chunk
+    /// already exists in this source.
+    Pregeneration { pos: ChunkPosition, exists: bool },
 }
 
 #[derive(Debug)]
@@ -44,6 +58,20 @@ pub struct SaveRequest {
 pub enum WorkerRequest {
     Load(LoadRequest),
     Save(SaveRequest),
+    /// Writes the saves held while saving is disabled
+    /// and flushes the chunk storage.
+    Flush,
+    /// Enables or disables writing chunks to the storage.
+    /// While disabled, saves are held in memory.
+    SetSavingEnabled(bool),
+    /// Answered once all previous requests are served.
+    Sync(Sender<()>),
+    /// Checks whether a chunk exists, so that it can be
+    /// generated ahead of time if it doesn't.
+    Pregenerate(ChunkPosition),
+    /// Flushes the chunk storage and stops the worker
+    /// once all previous requests are served.
+    Shutdown,
 }
 pub struct ChunkWorker {
     generator: Arc<dyn WorldGenerator>,
@@ -51,26 +79,57 @@ pub struct ChunkWorker {
     send_gen: Sender<LoadedChunk>,
     recv_gen: Receiver<LoadedChunk>, // Chunk generation should be infallible.
     recv_load: Receiver<ChunkLoadResult>,
+    send_pregen: Sender<Chunk>,
+    recv_pregen: Receiver<Chunk>,
+    /// Chunks queued for pregeneration which turned out to exist already.
+    pregen_existing: Vec<ChunkPosition>,
+    /// The storage worker thread, or `None` after a shutdown.
+    storage_thread: Option<JoinHandle<()>>,
+    /// Loads queued which haven't been returned yet.
+    pending_loads: usize,
+    /// Saves queued which the storage worker hasn't written yet.
+    pending_saves: Arc<AtomicUsize>,
 }
 
 impl ChunkWorker {
-    pub fn new(world_dir: impl Into<PathBuf>, generator: Arc<dyn WorldGenerator>) -> Self {
+    pub fn new(storage: Box<dyn ChunkStorage>, generator: Arc<dyn WorldGenerator>) -> Self {
         let (send_req, recv_req) = flume::unbounded();
         let (send_gen, recv_gen) = flume::unbounded();
-        let (region_worker, recv_load) = RegionWorker::new(world_dir.into(), recv_req);
-        region_worker.start();
+        let (send_pregen, recv_pregen) = flume::unbounded();
+        let pending_saves = Arc::new(AtomicUsize::new(0));
+        let (storage_worker, recv_load) =
+            StorageWorker::new(storage, recv_req, Arc::clone(&pending_saves));
+        let storage_thread = Some(storage_worker.start());
         Self {
             generator,
             send_req,
             send_gen,
             recv_gen,
             recv_load,
+            send_pregen,
+            recv_pregen,
+            pregen_existing: Vec::new(),
+            storage_thread,
+            pending_loads: 0,
+            pending_saves,
         }
     }
     pub fn queue_load(&mut self, request: LoadRequest) {
+        self.pending_loads += 1;
         self.send_req.send(WorkerRequest::Load(request)).unwrap()
     }
 
+    /// Returns the number of queued loads which
+    /// haven't been returned by `poll_loaded_chunk` yet.
+    pub fn pending_loads(&self) -> usize {
+        self.pending_loads
+    }
+
+    /// Returns the number of queued saves which haven't been written yet.
+    pub fn pending_saves(&self) -> usize {
+        self.pending_saves.load(Ordering::Relaxed)
+    }
+
     /// Helper function for poll_loaded_chunk. Attemts to receive a freshly generated chunk.
     /// Function signature identical to that of poll_loaded_chunk for ease of use.
     fn try_recv_gen(&mut self) -> Result<Option<LoadedChunk>, anyhow::Error> {
@@ -83,33 +142,127 @@ impl ChunkWorker {
         }
     }
     pub fn poll_loaded_chunk(&mut self) -> Result<Option<LoadedChunk>, anyhow::Error> {
-        match self.recv_load.try_recv() {
-            Ok(answer) => {
-                match answer {
-                    // RegionWorker answered
-                    ChunkLoadResult::Missing(pos) => {
-                        // chunk does not exist, queue it for generation
-                        let send_gen = self.send_gen.clone();
-                        let gen = self.generator.clone();
-                        rayon::spawn(move || {
-                            // spawn task to generate chunk
-                            let chunk = gen.generate_chunk(pos);
-                            send_gen.send(LoadedChunk { pos, chunk }).unwrap()
-                        });
-                        self.try_recv_gen() // check for generated chunks
+        let result = self.receive_loaded_chunk();
+        if !matches!(result, Ok(None)) {
+            self.pending_loads = self.pending_loads.saturating_sub(1);
+        }
+        result
+    }
+
+    fn receive_loaded_chunk(&mut self) -> Result<Option<LoadedChunk>, anyhow::Error> {
+        loop {
+            match self.recv_load.try_recv() {
+                Ok(answer) => {
+                    match answer {
+                        // StorageWorker answered
+                        ChunkLoadResult::Missing(pos) => {
+                            // chunk does not exist, queue it for generation
+                            let send_gen = self.send_gen.clone();
+                            let gen = self.generator.clone();
+                            rayon::spawn(move || {
+                                // spawn task to generate chunk
+                                let chunk = gen.generate_chunk(pos);
+                                send_gen
+                                    .send(LoadedChunk {
+                                        pos,
+                                        chunk,
+                                        generated: true,
+                                    })
+                                    .unwrap()
+                            });
+                            return self.try_recv_gen(); // check for generated chunks
+                        }
+                        ChunkLoadResult::Unfinished(mut chunk) => {
+                            // finish generating the chunk, keeping its blocks
+                            let send_gen = self.send_gen.clone();
+                            let gen = self.generator.clone();
+                            rayon::spawn(move || {
+                                gen.finish_chunk(&mut chunk);
+                                send_gen
+                                    .send(LoadedChunk {
+                                        pos: chunk.position(),
+                                        chunk,
+                                        generated: true,
+                                    })
+                                    .unwrap()
+                            });
+                            return self.try_recv_gen();
+                        }
+                        ChunkLoadResult::Error(e) => return Err(e),
+                        ChunkLoadResult::Loaded(l) => return Ok(Some(l)),
+                        ChunkLoadResult::Pregeneration { pos, exists: true } => {
+                            self.pregen_existing.push(pos)
+                        }
+                        ChunkLoadResult::Pregeneration { pos, exists: false } => {
+                            let send_pregen = self.send_pregen.clone();
+                            let gen = self.generator.clone();
+                            rayon::spawn(move || {
+                                let _ = send_pregen.send(gen.generate_chunk(pos));
+                            });
+                        }
                     }
-                    ChunkLoadResult::Error(e) => Err(e),
-                    ChunkLoadResult::Loaded(l) => Ok(Some(l)),
+                }
+                Err(e) => {
+                    return match e {
+                        flume::TryRecvError::Empty => self.try_recv_gen(), // check for generated chunks
+                        flume::TryRecvError::Disconnected => bail!("StorageWorker died"),
+                    };
                 }
             }
-            Err(e) => match e {
-                flume::TryRecvError::Empty => self.try_recv_gen(), // check for generated chunks
-                flume::TryRecvError::Disconnected => bail!("RegionWorker died"),
-            },
         }
     }
 
+    /// Queues the given chunk to be generated ahead of time if it doesn't
+    /// exist yet. The result is returned by [`ChunkWorker::poll_pregenerated`].
+    pub fn queue_pregeneration(&mut self, pos: ChunkPosition) {
+        self.send_req.send(WorkerRequest::Pregenerate(pos)).unwrap()
+    }
+
+    /// Returns the chunks queued for pregeneration which already existed,
+    /// along with the newly generated chunks, which have yet to be saved.
+    ///
+    /// Answers from the storage worker are received by [`ChunkWorker::poll_loaded_chunk`].
+    pub fn poll_pregenerated(&mut self) -> (Vec<ChunkPosition>, Vec<Chunk>) {
+        let existing = std::mem::take(&mut self.pregen_existing);
+        let generated = self.recv_pregen.try_iter().collect();
+        (existing, generated)
+    }
+
     pub fn queue_chunk_save(&mut self, req: SaveRequest) {
+        self.pending_saves.fetch_add(1, Ordering::Relaxed);
         self.send_req.send(WorkerRequest::Save(req)).unwrap()
     }
+
+    /// Queues a flush of the chunk storage, after all previously queued saves.
+    pub fn queue_flush(&mut self) {
+        self.send_req.send(WorkerRequest::Flush).unwrap()
+    }
+
+    /// Enables or disables writing chunks to the storage.
+    pub fn set_saving_enabled(&mut self, enabled: bool) {
+        self.send_req
+            .send(WorkerRequest::SetSavingEnabled(enabled))
+            .unwrap()
+    }
+
+    /// Blocks until all previously queued requests are served.
+    pub fn sync(&mut self) {
+        let (done_tx, done_rx) = flume::bounded(1);
+        self.send_req.send(WorkerRequest::Sync(done_tx)).unwrap();
+        // Fails only if the worker thread died.
+        done_rx.recv().ok();
+    }
+
+    /// Stops the storage worker, blocking until all previously
+    /// queued saves are written and the storage is flushed.
+    ///
+    /// No requests can be queued afterwards.
+    pub fn shutdown(&mut self) {
+        if let Some(thread) = self.storage_thread.take() {
+            self.send_req.send(WorkerRequest::Shutdown).unwrap();
+            if thread.join().is_err() {
+                log::error!("Chunk worker panicked while shutting down");
+            }
+        }
+    }
 }
@@ -0,0 +1,324 @@
+//! Commands run by players, the console and remote consoles.
+//!
+//! Commands are registered in the [`Commands`] resource. [`run_command`]
+//! checks that the sender's permission level or permission nodes allow
+//! the command, runs it and returns its output, which the caller
+//! delivers to the sender.
+//!
+//! Commands waiting on blocking work, like requests to other services,
+//! [defer](CommandContext::defer) their output to a later tick.
+
+use std::{collections::BTreeMap, mem};
+
+use ecs::Entity;
+use flume::{Receiver, Sender, TryRecvError};
+use quill_common::components::Name;
+
+use crate::{permissions, Game};
+
+mod builtin;
+mod permission;
+mod timings;
+
+pub fn register(game: &mut Game) {
+    let mut commands = Commands::default();
+    builtin::register(&mut commands);
+    permission::register(&mut commands);
+    timings::register(&mut commands);
+    game.insert_resource(commands);
+}
+
+/// The highest permission level, held by the console.
+pub const MAX_PERMISSION_LEVEL: u8 = 4;
+
+/// The sender of a command.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CommandSender {
+    /// The server console.
+    Console,
+    /// A remote console connected over RCON.
+    Rcon,
+    Player(Entity),
+}
+
+impl CommandSender {
+    /// Returns the permission level of the sender.
+    pub fn permission_level(self, game: &Game) -> u8 {
+        match self {
+            CommandSender::Console | CommandSender::Rcon => MAX_PERMISSION_LEVEL,
+            CommandSender::Player(player) => game
+                .ecs
+                .get::<PermissionLevel>(player)
+                .map_or(0, |level| level.0),
+        }
+    }
+
+    /// Returns whether the sender has a permission node.
+    /// The consoles have all permissions.
+    pub fn has_permission(self, game: &Game, node: &str) -> bool {
+        match self {
+            CommandSender::Console | CommandSender::Rcon => true,
+            CommandSender::Player(player) => permissions::has_permission(game, player, node),
+        }
+    }
+
+    /// Returns whether the sender may run a command, either because
+    /// of their permission level or their permission nodes.
+    pub fn can_run(self, game: &Game, command: &Command) -> bool {
+        self.permission_level(game) >= command.permission_level
+            || self.has_permission(game, &command.permission_node())
+    }
+
+    /// Returns the name of the sender, as shown to other players.
+    pub fn name(self, game: &Game) -> String {
+        match self {
+            CommandSender::Console => "Server".to_owned(),
+            CommandSender::Rcon => "Rcon".to_owned(),
+            CommandSender::Player(player) => game
+                .ecs
+                .get::<Name>(player)
+                .map_or_else(|_| "Unknown".to_owned(), |name| name.to_string()),
+        }
+    }
+}
+
+/// The permission level of a player. Players
+/// without this component have level 0.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PermissionLevel(pub u8);
+
+/// A function running a command. Returned errors
+/// are shown to the sender.
+pub type CommandHandler = fn(&mut Game, &mut CommandContext) -> anyhow::Result<()>;
+
+/// A command which can be run by name.
+#[derive(Copy, Clone, Debug)]
+pub struct Command {
+    pub name: &'static str,
+    /// The arguments of the command, shown in help and usage messages.
+    pub usage: &'static str,
+    pub description: &'static str,
+    /// The permission level required to run the command.
+    pub permission_level: u8,
+    pub handler: CommandHandler,
+}
+
+impl Command {
+    /// Returns the permission node allowing
+    /// the command, like `feather.command.say`.
+    pub fn permission_node(&self) -> String {
+        format!("feather.command.{}", self.name)
+    }
+}
+
+/// The sender and arguments of a running command,
+/// along with the output it has produced so far.
+#[derive(Debug)]
+pub struct CommandContext {
+    pub sender: CommandSender,
+    pub args: Vec<String>,
+    output: Vec<String>,
+    deferred: Option<PendingOutput>,
+}
+
+impl CommandContext {
+    pub fn new(sender: CommandSender, args: Vec<String>) -> Self {
+        Self {
+            sender,
+            args,
+            output: Vec::new(),
+            deferred: None,
+        }
+    }
+
+    /// Adds a line to the output of the command.
+    pub fn reply(&mut self, line: impl Into<String>) {
+        self.output.push(line.into());
+    }
+
+    pub fn output(&self) -> &[String] {
+        &self.output
+    }
+
+    /// Defers the rest of the output until the returned
+    /// [`DeferredReply`] is dropped, which may be in a later tick.
+    ///
+    /// # Panics
+    /// Panics if the output was already deferred.
+    pub fn defer(&mut self) -> DeferredReply {
+        assert!(self.deferred.is_none(), "command output already deferred");
+        let (done, deferred) = flume::bounded(1);
+        self.deferred = Some(PendingOutput(deferred));
+        DeferredReply {
+            output: Vec::new(),
+            done,
+        }
+    }
+}
+
+/// Collects the output of a command after it returned.
+/// The output is delivered once this is dropped.
+#[derive(Debug)]
+pub struct DeferredReply {
+    output: Vec<String>,
+    done: Sender<Vec<String>>,
+}
+
+impl DeferredReply {
+    /// Adds a line to the output of the command.
+    pub fn reply(&mut self, line: impl Into<String>) {
+        self.output.push(line.into());
+    }
+}
+
+impl Drop for DeferredReply {
+    fn drop(&mut self) {
+        // Nobody may be waiting for the output anymore.
+        self.done.send(mem::take(&mut self.output)).ok();
+    }
+}
+
+/// The rest of the output of a command which deferred it.
+#[derive(Debug)]
+pub struct PendingOutput(Receiver<Vec<String>>);
+
+impl PendingOutput {
+    /// Takes the rest of the output, or returns
+    /// `None` if the command hasn't finished yet.
+    pub fn try_take(&self) -> Option<Vec<String>> {
+        match self.0.try_recv() {
+            Ok(output) => Some(output),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Vec::new()),
+        }
+    }
+}
+
+/// The output of a command.
+#[derive(Debug, Default)]
+pub struct CommandOutput {
+    pub lines: Vec<String>,
+    /// The rest of the output, if the command deferred it.
+    pub deferred: Option<PendingOutput>,
+}
+
+/// The registered commands, stored as a resource.
+#[derive(Debug, Default)]
+pub struct Commands {
+    commands: BTreeMap<&'static str, Command>,
+}
+
+impl Commands {
+    /// Registers a command, replacing any command with the same name.
+    pub fn register(&mut self, command: Command) {
+        self.commands.insert(command.name, command);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Command> {
+        self.commands.get(name)
+    }
+
+    /// Iterates over the commands, ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = &Command> + '_ {
+        self.commands.values()
+    }
+}
+
+/// Runs a command line, which may start with a slash,
+/// and returns the output of the command.
+pub fn run_command(game: &mut Game, sender: CommandSender, line: &str) -> CommandOutput {
+    let mut words = line.trim_start_matches('/').split_whitespace();
+    let name = match words.next() {
+        Some(name) => name.to_lowercase(),
+        None => return CommandOutput::default(),
+    };
+    let mut context = CommandContext::new(sender, words.map(str::to_owned).collect());
+
+    let command = game
+        .resources
+        .get::<Commands>()
+        .ok()
+        .and_then(|commands| commands.get(&name).copied());
+    match command {
+        None => context.reply(format!(
+            "Unknown command \"{}\". Type \"help\" for a list of commands.",
+            name
+        )),
+        Some(command) if !sender.can_run(game, &command) => {
+            context.reply("You do not have permission to use this command.")
+        }
+        Some(command) => {
+            log::info!("{} ran command: {}", sender.name(game), line);
+            if let Err(e) = (command.handler)(game, &mut context) {
+                context.reply(e.to_string());
+            }
+        }
+    }
+    CommandOutput {
+        lines: context.output,
+        deferred: context.deferred,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game() -> Game {
+        let mut game = Game::new();
+        register(&mut game);
+        game
+    }
+
+    #[test]
+    fn unknown_command() {
+        let output = run_command(&mut game(), CommandSender::Console, "nonexistent");
+        assert_eq!(
+            output.lines,
+            vec!["Unknown command \"nonexistent\". Type \"help\" for a list of commands."]
+        );
+    }
+
+    #[test]
+    fn help_lists_commands() {
+        let output = run_command(&mut game(), CommandSender::Rcon, "/HELP");
+        assert!(output
+            .lines
+            .iter()
+            .any(|line| line.starts_with("say <message>")));
+    }
+
+    #[test]
+    fn usage_errors_are_shown() {
+        let output = run_command(&mut game(), CommandSender::Console, "say");
+        assert_eq!(output.lines, vec!["Usage: say <message>"]);
+        assert!(output.deferred.is_none());
+    }
+
+    #[test]
+    fn deferred_output() {
+        let mut game = game();
+        game.resources
+            .get_mut::<Commands>()
+            .unwrap()
+            .register(Command {
+                name: "later",
+                usage: "",
+                description: "",
+                permission_level: 0,
+                handler: |_, context| {
+                    context.reply("Starting");
+                    let mut reply = context.defer();
+                    std::thread::spawn(move || reply.reply("Done"))
+                        .join()
+                        .unwrap();
+                    Ok(())
+                },
+            });
+
+        let output = run_command(&mut game, CommandSender::Console, "later");
+        assert_eq!(output.lines, vec!["Starting"]);
+        let rest = output.deferred.unwrap();
+        assert_eq!(rest.try_take().unwrap(), vec!["Done"]);
+    }
+}
@@ -0,0 +1,74 @@
+//! Commands built into the server.
+
+use anyhow::bail;
+use quill_common::{components::Name, entities::Player};
+
+use crate::{chat::ChatKind, Game};
+
+use super::{Command, CommandContext, Commands};
+
+pub fn register(commands: &mut Commands) {
+    commands.register(Command {
+        name: "help",
+        usage: "",
+        description: "Lists the commands you can run",
+        permission_level: 0,
+        handler: help,
+    });
+    commands.register(Command {
+        name: "list",
+        usage: "",
+        description: "Lists the online players",
+        permission_level: 0,
+        handler: list,
+    });
+    commands.register(Command {
+        name: "say",
+        usage: "<message>",
+        description: "Sends a message to all players",
+        permission_level: 2,
+        handler: say,
+    });
+}
+
+fn help(game: &mut Game, context: &mut CommandContext) -> anyhow::Result<()> {
+    let sender = context.sender;
+    let lines: Vec<String> = game
+        .resources
+        .get::<Commands>()?
+        .iter()
+        .filter(|command| sender.can_run(game, command))
+        .map(|command| {
+            let usage = format!("{} {}", command.name, command.usage);
+            format!("{} - {}", usage.trim_end(), command.description)
+        })
+        .collect();
+    for line in lines {
+        context.reply(line);
+    }
+    Ok(())
+}
+
+fn list(game: &mut Game, context: &mut CommandContext) -> anyhow::Result<()> {
+    let names: Vec<String> = game
+        .ecs
+        .query::<(&Player, &Name)>()
+        .iter()
+        .map(|(_, (_, name))| name.to_string())
+        .collect();
+    context.reply(format!(
+        "There are {} players online: {}",
+        names.len(),
+        names.join(", ")
+    ));
+    Ok(())
+}
+
+fn say(game: &mut Game, context: &mut CommandContext) -> anyhow::Result<()> {
+    if context.args.is_empty() {
+        bail!("Usage: say <message>");
+    }
+    let message = format!("[{}] {}", context.sender.name(game), context.args.join(" "));
+    game.broadcast_chat(ChatKind::System, message);
+    Ok(())
+}
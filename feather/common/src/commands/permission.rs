@@ -0,0 +1,116 @@
+//! The command editing permissions at runtime.
+
+use anyhow::bail;
+use quill_common::{components::Name, entities::Player};
+use uuid::Uuid;
+
+use crate::{permissions::Permissions, Game};
+
+use super::{Command, CommandContext, Commands, PermissionLevel, MAX_PERMISSION_LEVEL};
+
+const USAGE: &str = "user <player> add|remove <node> | user <player> join|leave <group> \
+    | group <group> add|remove <node> | check <player> <node> | reload";
+
+pub fn register(commands: &mut Commands) {
+    commands.register(Command {
+        name: "permission",
+        usage: USAGE,
+        description: "Edits the permissions of players and groups",
+        permission_level: MAX_PERMISSION_LEVEL,
+        handler: permission,
+    });
+}
+
+/// A player named in the command.
+struct Target {
+    uuid: Uuid,
+    name: String,
+    /// The operator level of the player, or 0 if offline.
+    op_level: u8,
+}
+
+fn permission(game: &mut Game, context: &mut CommandContext) -> anyhow::Result<()> {
+    let mut permissions = game.resources.get_mut::<Permissions>()?;
+    let args: Vec<&str> = context.args.iter().map(String::as_str).collect();
+
+    let reply = match args.as_slice() {
+        ["user", player, action, value] => {
+            let target = find_player(game, &permissions, player)?;
+            let (uuid, name) = (target.uuid, target.name.as_str());
+            match *action {
+                "add" => match permissions.add_player_permission(uuid, name, value)? {
+                    true => format!("Gave {} to {}", value, name),
+                    false => bail!("Nothing changed. {} already has {}", name, value),
+                },
+                "remove" => match permissions.remove_player_permission(uuid, value)? {
+                    true => format!("Took {} from {}", value, name),
+                    false => bail!("Nothing changed. {} isn't given {}", name, value),
+                },
+                "join" => match permissions.add_player_group(uuid, name, value)? {
+                    true => format!("Added {} to group {}", name, value),
+                    false => bail!("Nothing changed. {} is already in group {}", name, value),
+                },
+                "leave" => match permissions.remove_player_group(uuid, value)? {
+                    true => format!("Removed {} from group {}", name, value),
+                    false => bail!("Nothing changed. {} isn't in group {}", name, value),
+                },
+                _ => bail!("Usage: permission {}", USAGE),
+            }
+        }
+        ["group", group, "add", node] => match permissions.add_group_permission(group, node)? {
+            true => format!("Gave {} to group {}", node, group),
+            false => bail!("Nothing changed. Group {} already has {}", group, node),
+        },
+        ["group", group, "remove", node] => {
+            match permissions.remove_group_permission(group, node)? {
+                true => format!("Took {} from group {}", node, group),
+                false => bail!("Nothing changed. Group {} isn't given {}", group, node),
+            }
+        }
+        ["check", player, node] => {
+            let target = find_player(game, &permissions, player)?;
+            if permissions.has_permission(target.uuid, target.op_level, node) {
+                format!("{} has {}", target.name, node)
+            } else {
+                format!("{} doesn't have {}", target.name, node)
+            }
+        }
+        ["reload"] => {
+            permissions.reload()?;
+            "Reloaded the permissions".to_owned()
+        }
+        _ => bail!("Usage: permission {}", USAGE),
+    };
+    context.reply(reply);
+    Ok(())
+}
+
+/// Finds an online player, or else a player listed in the permissions.
+fn find_player(game: &Game, permissions: &Permissions, name: &str) -> anyhow::Result<Target> {
+    let online = game
+        .ecs
+        .query::<(&Player, &Name, &Uuid)>()
+        .iter()
+        .find(|(_, (_, player_name, _))| player_name.eq_ignore_ascii_case(name))
+        .map(|(player, (_, player_name, &uuid))| (player, player_name.to_string(), uuid));
+    if let Some((player, name, uuid)) = online {
+        let op_level = game
+            .ecs
+            .get::<PermissionLevel>(player)
+            .map_or(0, |level| level.0);
+        return Ok(Target {
+            uuid,
+            name,
+            op_level,
+        });
+    }
+
+    match permissions.find_player(name) {
+        Some((uuid, player)) => Ok(Target {
+            uuid,
+            name: player.name.clone(),
+            op_level: 0,
+        }),
+        None => bail!("No player named {} is online or has permissions", name),
+    }
+}
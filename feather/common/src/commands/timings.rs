@@ -0,0 +1,90 @@
+//! Commands reporting tick and system timings.
+
+use std::time::Instant;
+
+use anyhow::bail;
+
+use crate::{
+    timings::{TickTimings, TimingWindow},
+    Game,
+};
+
+use super::{Command, CommandContext, Commands};
+
+/// Number of systems listed by `profile` by default.
+const DEFAULT_PROFILE_COUNT: usize = 10;
+
+pub fn register(commands: &mut Commands) {
+    commands.register(Command {
+        name: "tps",
+        usage: "",
+        description: "Shows the ticks per second and milliseconds per tick",
+        permission_level: 2,
+        handler: tps,
+    });
+    commands.register(Command {
+        name: "profile",
+        usage: "[count]",
+        description: "Lists the systems taking the longest to run",
+        permission_level: 2,
+        handler: profile,
+    });
+}
+
+fn tps(game: &mut Game, context: &mut CommandContext) -> anyhow::Result<()> {
+    let timings = game.resources.get::<TickTimings>()?;
+    let now = Instant::now();
+    let format = |value: &dyn Fn(TimingWindow) -> f64| {
+        TimingWindow::ALL
+            .iter()
+            .map(|&window| format!("{:.1}", value(window)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let labels = TimingWindow::ALL.map(TimingWindow::label).join(", ");
+    context.reply(format!(
+        "TPS ({}): {}",
+        labels,
+        format(&|window| timings.tps(window, now))
+    ));
+    context.reply(format!(
+        "MSPT ({}): {}",
+        labels,
+        format(&|window| timings.mspt(window, now))
+    ));
+    Ok(())
+}
+
+fn profile(game: &mut Game, context: &mut CommandContext) -> anyhow::Result<()> {
+    let count = match context.args.as_slice() {
+        [] => DEFAULT_PROFILE_COUNT,
+        [count] => match count.parse() {
+            Ok(count) => count,
+            Err(_) => bail!("Usage: profile [count]"),
+        },
+        _ => bail!("Usage: profile [count]"),
+    };
+
+    let timings = game.resources.get::<TickTimings>()?;
+    if let Some(last_tick) = timings.last_tick() {
+        context.reply(format!(
+            "Last tick took {:.2} ms. Slowest systems, averaged over 5 seconds:",
+            last_tick.as_secs_f64() * 1000.0
+        ));
+    }
+    for (i, system) in timings.slowest_systems(count).into_iter().enumerate() {
+        let plugin = match &system.plugin {
+            Some(plugin) => format!(" [plugin {}]", plugin),
+            None => String::new(),
+        };
+        context.reply(format!(
+            "{}. {}{} - {:.3} ms (last {:.3} ms)",
+            i + 1,
+            system.name,
+            plugin,
+            system.average.as_secs_f64() * 1000.0,
+            system.last.as_secs_f64() * 1000.0
+        ));
+    }
+    Ok(())
+}
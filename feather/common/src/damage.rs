@@ -0,0 +1,125 @@
+//! Entity damage bookkeeping and player melee attacks.
+//!
+//! Damage is dealt through [`Game::damage_entity`](crate::Game::damage_entity).
+
+use base::{EntityKind, Inventory, Item, ItemStack};
+use ecs::{Entity, SysResult, SystemExecutor};
+use libcraft_core::InteractionType;
+use libcraft_items::EnchantmentKind;
+use quill_common::{
+    components::{ActiveEffects, Health},
+    entities::Player,
+    events::InteractEntityEvent,
+};
+
+use crate::{
+    ai::MobAttributes,
+    effects::modify_attack_damage,
+    entities::player::{held_item, HotbarSlot},
+    Game,
+};
+
+/// Damage dealt by a player attacking with an empty hand.
+const FIST_DAMAGE: f32 = 1.0;
+
+/// Maximum health of a player, in half-hearts.
+pub const PLAYER_MAX_HEALTH: f32 = 20.0;
+
+/// Component recording the most recent damage an entity took.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LastDamage {
+    /// The tick at which the damage was dealt.
+    pub tick: u64,
+    pub amount: f32,
+    /// The entity which dealt the damage, if any.
+    pub attacker: Option<Entity>,
+}
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.add_system(damage_attacked_entities);
+}
+
+/// Heals an entity, up to its maximum health.
+///
+/// Has no effect on dead entities.
+pub fn heal_entity(game: &Game, entity: Entity, amount: f32) {
+    let max_health = if game.ecs.get::<Player>(entity).is_ok() {
+        PLAYER_MAX_HEALTH
+    } else {
+        match game
+            .ecs
+            .get::<EntityKind>(entity)
+            .ok()
+            .and_then(|kind| MobAttributes::of(*kind))
+        {
+            Some(attributes) => attributes.max_health,
+            None => return,
+        }
+    };
+    if let Ok(mut health) = game.ecs.get_mut::<Health>(entity) {
+        if health.0 > 0.0 {
+            health.0 = (health.0 + amount).min(max_health);
+        }
+    }
+}
+
+/// Damages entities attacked by players.
+fn damage_attacked_entities(game: &mut Game) -> SysResult {
+    let attacks: Vec<(Entity, Entity)> = game
+        .ecs
+        .query::<(&Player, &InteractEntityEvent)>()
+        .iter()
+        .filter(|(_, (_, event))| matches!(event.ty, InteractionType::Attack))
+        .map(|(player, (_, event))| (player, Entity::from_bits(event.target.0)))
+        .collect();
+
+    for (player, target) in attacks {
+        if player == target {
+            continue;
+        }
+
+        let weapon = held_item(
+            &*game.ecs.get::<Inventory>(player)?,
+            *game.ecs.get::<HotbarSlot>(player)?,
+        );
+        let mut damage = weapon.as_ref().map_or(FIST_DAMAGE, attack_damage);
+        if let Ok(effects) = game.ecs.get::<ActiveEffects>(player) {
+            damage = modify_attack_damage(&effects, damage);
+        }
+        game.damage_entity(target, damage, Some(player))?;
+    }
+    Ok(())
+}
+
+/// Gets the melee damage dealt with an item, including Sharpness.
+fn attack_damage(weapon: &ItemStack) -> f32 {
+    let base = match weapon.item() {
+        Item::WoodenSword | Item::GoldenSword => 4.0,
+        Item::StoneSword => 5.0,
+        Item::IronSword => 6.0,
+        Item::DiamondSword => 7.0,
+        Item::NetheriteSword => 8.0,
+        Item::WoodenAxe | Item::GoldenAxe => 7.0,
+        Item::StoneAxe | Item::IronAxe | Item::DiamondAxe => 9.0,
+        Item::NetheriteAxe => 10.0,
+        Item::WoodenPickaxe | Item::GoldenPickaxe => 2.0,
+        Item::StonePickaxe => 3.0,
+        Item::IronPickaxe => 4.0,
+        Item::DiamondPickaxe => 5.0,
+        Item::NetheritePickaxe => 6.0,
+        Item::WoodenShovel | Item::GoldenShovel => 2.5,
+        Item::StoneShovel => 3.5,
+        Item::IronShovel => 4.5,
+        Item::DiamondShovel => 5.5,
+        Item::NetheriteShovel => 6.5,
+        Item::Trident => 9.0,
+        _ => FIST_DAMAGE,
+    };
+
+    let sharpness = weapon
+        .enchantments()
+        .iter()
+        .find(|enchantment| enchantment.kind() == EnchantmentKind::Sharpness)
+        .map_or(0.0, |enchantment| 0.5 * enchantment.level() as f32 + 0.5);
+    base + sharpness
+}
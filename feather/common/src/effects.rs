@@ -0,0 +1,259 @@
+//! Status effects, stored in the [`ActiveEffects`] component.
+//!
+//! Effects which only change how a player moves or sees, like speed,
+//! jump boost, haste or night vision, are applied by the client once it
+//! receives the effect. The server applies effects which change game
+//! state: health, hunger, damage and mob movement speed.
+
+use base::{EntityKind, StatusEffect};
+use ecs::{Entity, EntityBuilder, SysResult, SystemExecutor};
+use quill_common::{
+    components::{ActiveEffects, EffectInstance, FoodLevel, Health, Saturation},
+    entity_init::EntityInit,
+};
+
+use crate::{
+    damage::heal_entity,
+    hunger::{add_exhaustion, MAX_FOOD_LEVEL},
+    Game,
+};
+
+pub mod area_effect_cloud;
+pub mod potions;
+
+/// Exhaustion added each tick per level of the hunger effect.
+const HUNGER_EXHAUSTION_PER_TICK: f32 = 0.005;
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    systems.add_system(tick_effects);
+    potions::register(systems);
+    area_effect_cloud::register(systems);
+
+    game.add_entity_spawn_callback(add_active_effects);
+}
+
+/// Adds `ActiveEffects` to players and mobs.
+fn add_active_effects(builder: &mut EntityBuilder, init: &EntityInit) {
+    if builder.has::<Health>() || matches!(init, EntityInit::Player) {
+        builder.add(ActiveEffects::new());
+    }
+}
+
+/// Applies a status effect to an entity.
+///
+/// Instant effects are applied at once at full potency.
+/// Entities without `ActiveEffects` are unaffected.
+pub fn apply_effect(
+    game: &mut Game,
+    entity: Entity,
+    effect: StatusEffect,
+    instance: EffectInstance,
+) -> SysResult {
+    if effect.is_instant() {
+        return apply_instant_effect(game, entity, effect, instance.amplifier, 1.0);
+    }
+    if let Ok(mut effects) = game.ecs.get_mut::<ActiveEffects>(entity) {
+        effects.add(effect, instance);
+    }
+    Ok(())
+}
+
+/// Applies an instant effect, scaled by `potency` from 0 to 1.
+///
+/// Instant health harms undead mobs and instant damage heals them.
+pub fn apply_instant_effect(
+    game: &mut Game,
+    entity: Entity,
+    effect: StatusEffect,
+    amplifier: u8,
+    potency: f32,
+) -> SysResult {
+    if game.ecs.get::<ActiveEffects>(entity).is_err() {
+        return Ok(());
+    }
+
+    let undead = game
+        .ecs
+        .get::<EntityKind>(entity)
+        .map(|kind| is_undead(*kind))
+        .unwrap_or(false);
+    let level = f32::from(amplifier) + 1.0;
+    match (effect, undead) {
+        (StatusEffect::InstantHealth, false) | (StatusEffect::InstantDamage, true) => {
+            heal_entity(game, entity, level * 4.0 * potency);
+        }
+        (StatusEffect::InstantDamage, false) | (StatusEffect::InstantHealth, true) => {
+            game.damage_entity(entity, level * 6.0 * potency, None)?;
+        }
+        (StatusEffect::Saturation, _) => {
+            if let Ok(mut food_level) = game.ecs.get_mut::<FoodLevel>(entity) {
+                food_level.0 = (food_level.0 + u32::from(amplifier) + 1).min(MAX_FOOD_LEVEL);
+                let mut saturation = game.ecs.get_mut::<Saturation>(entity)?;
+                saturation.0 = (saturation.0 + level * 2.0).min(food_level.0 as f32);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Whether an entity is undead. Undead mobs are immune to
+/// poison and regeneration and have instant effects reversed.
+pub fn is_undead(kind: EntityKind) -> bool {
+    matches!(
+        kind,
+        EntityKind::Zombie
+            | EntityKind::Husk
+            | EntityKind::Drowned
+            | EntityKind::ZombieVillager
+            | EntityKind::ZombifiedPiglin
+            | EntityKind::ZombieHorse
+            | EntityKind::Zoglin
+            | EntityKind::Skeleton
+            | EntityKind::Stray
+            | EntityKind::WitherSkeleton
+            | EntityKind::SkeletonHorse
+            | EntityKind::Wither
+            | EntityKind::Phantom
+    )
+}
+
+/// Gets the multiplier applied to a mob's movement speed
+/// by speed and slowness.
+pub fn movement_speed_multiplier(effects: &ActiveEffects) -> f64 {
+    let level = |effect| {
+        effects
+            .amplifier(effect)
+            .map_or(0.0, |amplifier| f64::from(amplifier) + 1.0)
+    };
+    ((1.0 + 0.2 * level(StatusEffect::Speed)) * (1.0 - 0.15 * level(StatusEffect::Slowness)))
+        .max(0.0)
+}
+
+/// Applies strength and weakness to the damage of a melee attack.
+pub fn modify_attack_damage(effects: &ActiveEffects, damage: f32) -> f32 {
+    let level = |effect| {
+        effects
+            .amplifier(effect)
+            .map_or(0.0, |amplifier| f32::from(amplifier) + 1.0)
+    };
+    (damage + 3.0 * level(StatusEffect::Strength) - 4.0 * level(StatusEffect::Weakness)).max(0.0)
+}
+
+/// Applies resistance to damage taken by an entity.
+pub fn modify_damage_taken(effects: &ActiveEffects, damage: f32) -> f32 {
+    match effects.amplifier(StatusEffect::Resistance) {
+        Some(amplifier) => damage * (1.0 - 0.2 * (f32::from(amplifier) + 1.0)).max(0.0),
+        None => damage,
+    }
+}
+
+/// Mixes the colors of effects which show particles, weighted by level.
+///
+/// Returns `None` if there are no such effects.
+pub fn particle_color<'a>(
+    effects: impl IntoIterator<Item = (StatusEffect, &'a EffectInstance)>,
+) -> Option<u32> {
+    let (mut r, mut g, mut b, mut total) = (0.0, 0.0, 0.0, 0.0);
+    for (effect, instance) in effects {
+        if !instance.show_particles {
+            continue;
+        }
+        let color = effect.color();
+        let weight = f32::from(instance.amplifier) + 1.0;
+        r += ((color >> 16) & 0xFF) as f32 * weight;
+        g += ((color >> 8) & 0xFF) as f32 * weight;
+        b += (color & 0xFF) as f32 * weight;
+        total += weight;
+    }
+
+    if total == 0.0 {
+        None
+    } else {
+        Some(((r / total) as u32) << 16 | ((g / total) as u32) << 8 | (b / total) as u32)
+    }
+}
+
+/// Whether an effect applied over time acts on this tick,
+/// given its remaining duration.
+fn acts_on_tick(effect: StatusEffect, amplifier: u8, duration: u32) -> bool {
+    let interval = match effect {
+        StatusEffect::Regeneration => 50,
+        StatusEffect::Poison => 25,
+        StatusEffect::Wither => 40,
+        StatusEffect::Hunger => return true,
+        _ => return false,
+    };
+    match interval >> amplifier.min(31) {
+        0 => true,
+        interval => duration.is_multiple_of(interval),
+    }
+}
+
+/// Applies effects which act over time and counts down durations.
+fn tick_effects(game: &mut Game) -> SysResult {
+    let mut acting = Vec::new();
+    for (entity, effects) in game.ecs.query::<&mut ActiveEffects>().iter() {
+        for (effect, instance) in effects.iter_mut() {
+            if acts_on_tick(effect, instance.amplifier, instance.duration) {
+                acting.push((entity, effect, instance.amplifier));
+            }
+            instance.duration = instance.duration.saturating_sub(1);
+        }
+        effects.retain(|_, instance| instance.duration > 0);
+    }
+
+    for (entity, effect, amplifier) in acting {
+        let undead = game
+            .ecs
+            .get::<EntityKind>(entity)
+            .map(|kind| is_undead(*kind))
+            .unwrap_or(false);
+        match effect {
+            StatusEffect::Regeneration if !undead => heal_entity(game, entity, 1.0),
+            StatusEffect::Poison if !undead => {
+                // Poison never kills.
+                let health = game.ecs.get::<Health>(entity).map(|health| health.0);
+                if matches!(health, Ok(health) if health > 1.0) {
+                    game.damage_entity(entity, 1.0, None)?;
+                }
+            }
+            StatusEffect::Wither => game.damage_entity(entity, 1.0, None)?,
+            StatusEffect::Hunger => add_exhaustion(
+                game,
+                entity,
+                HUNGER_EXHAUSTION_PER_TICK * (f32::from(amplifier) + 1.0),
+            ),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn effect_intervals_shorten_with_amplifier() {
+        assert!(acts_on_tick(StatusEffect::Regeneration, 0, 100));
+        assert!(!acts_on_tick(StatusEffect::Regeneration, 0, 99));
+        assert!(acts_on_tick(StatusEffect::Poison, 1, 12));
+        assert!(acts_on_tick(StatusEffect::Wither, 10, 7));
+        assert!(!acts_on_tick(StatusEffect::Speed, 0, 100));
+    }
+
+    #[test]
+    fn stronger_effects_replace_weaker_ones() {
+        let mut effects = ActiveEffects::new();
+        assert!(effects.add(StatusEffect::Speed, EffectInstance::new(0, 100)));
+        assert!(!effects.add(StatusEffect::Speed, EffectInstance::new(0, 50)));
+        assert!(effects.add(StatusEffect::Speed, EffectInstance::new(1, 20)));
+        assert!(!effects.add(StatusEffect::Speed, EffectInstance::new(0, 1000)));
+        assert_eq!(
+            effects.get(StatusEffect::Speed),
+            Some(&EffectInstance::new(1, 20))
+        );
+        assert!((movement_speed_multiplier(&effects) - 1.4).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,163 @@
+//! Area effect clouds left behind by lingering potions.
+
+use std::collections::HashMap;
+
+use base::{Position, StatusEffect};
+use ecs::{Entity, SysResult, SystemExecutor};
+use quill_common::{
+    components::{ActiveEffects, EffectInstance},
+    entity_init::EntityInit,
+};
+
+use crate::Game;
+
+use super::{apply_effect, apply_instant_effect};
+
+/// Ticks a cloud from a lingering potion lasts.
+const CLOUD_DURATION: u32 = 600;
+
+/// Ticks before a new cloud starts applying effects.
+const CLOUD_WAIT_TIME: u32 = 10;
+
+const CLOUD_RADIUS: f32 = 3.0;
+
+/// Change in radius each time the cloud affects an entity.
+const RADIUS_ON_USE: f32 = -0.5;
+
+/// Ticks before an entity can be affected by the same cloud again.
+const REAPPLICATION_DELAY: u64 = 20;
+
+/// Ticks between checks for entities inside clouds.
+const APPLY_INTERVAL: u32 = 5;
+
+/// Effects from lingering potions last this fraction of the drunk potion's duration.
+const DURATION_DIVISOR: u32 = 4;
+
+/// State of an area effect cloud entity.
+#[derive(Clone, Debug)]
+pub struct AreaEffectCloudState {
+    pub radius: f32,
+    /// RGB color of the cloud's particles.
+    pub color: u32,
+    effects: Vec<(StatusEffect, EffectInstance)>,
+    /// Ticks since the cloud was spawned.
+    age: u32,
+    /// Ticks after which the cloud disappears, counted from spawning.
+    duration: u32,
+    /// Change in radius each tick.
+    radius_per_tick: f32,
+    /// The tick at which each affected entity can be affected again.
+    affected: HashMap<Entity, u64>,
+}
+
+impl AreaEffectCloudState {
+    /// Creates a cloud applying the given `(effect, duration, amplifier)`
+    /// effects of a lingering potion.
+    pub fn from_potion(effects: &[(StatusEffect, u32, u8)], color: u32) -> Self {
+        Self {
+            radius: CLOUD_RADIUS,
+            color,
+            effects: effects
+                .iter()
+                .map(|&(effect, duration, amplifier)| {
+                    let duration = if effect.is_instant() {
+                        duration
+                    } else {
+                        duration / DURATION_DIVISOR
+                    };
+                    (effect, EffectInstance::new(amplifier, duration))
+                })
+                .collect(),
+            age: 0,
+            duration: CLOUD_DURATION + CLOUD_WAIT_TIME,
+            radius_per_tick: -CLOUD_RADIUS / CLOUD_DURATION as f32,
+            affected: HashMap::new(),
+        }
+    }
+
+    /// Whether the cloud is still waiting to start applying effects.
+    pub fn is_waiting(&self) -> bool {
+        self.age < CLOUD_WAIT_TIME
+    }
+}
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.add_system(tick_clouds);
+}
+
+/// Spawns the area effect cloud of a lingering potion.
+pub fn spawn_cloud(
+    game: &mut Game,
+    position: Position,
+    effects: &[(StatusEffect, u32, u8)],
+    color: u32,
+) {
+    let mut builder = game.create_entity_builder(position, EntityInit::AreaEffectCloud);
+    builder.add(AreaEffectCloudState::from_potion(effects, color));
+    game.spawn_entity(builder);
+}
+
+/// Shrinks clouds and applies their effects to entities inside them.
+fn tick_clouds(game: &mut Game) -> SysResult {
+    let tick = game.tick_count;
+    let targets: Vec<(Entity, Position)> = game
+        .ecs
+        .query::<(&ActiveEffects, &Position)>()
+        .iter()
+        .map(|(entity, (_, &position))| (entity, position))
+        .collect();
+
+    let mut expired = Vec::new();
+    let mut applied = Vec::new();
+    for (cloud, (state, &position)) in game
+        .ecs
+        .query::<(&mut AreaEffectCloudState, &Position)>()
+        .iter()
+    {
+        state.age += 1;
+        if state.age >= state.duration || state.radius <= 0.0 {
+            expired.push(cloud);
+            continue;
+        }
+        if state.is_waiting() {
+            continue;
+        }
+        state.radius += state.radius_per_tick;
+        if !state.age.is_multiple_of(APPLY_INTERVAL) {
+            continue;
+        }
+
+        state.affected.retain(|_, &mut until| until > tick);
+        let radius = f64::from(state.radius);
+        for &(entity, target) in &targets {
+            if state.affected.contains_key(&entity) {
+                continue;
+            }
+            let (dx, dz) = (target.x - position.x, target.z - position.z);
+            if dx * dx + dz * dz > radius * radius || (target.y - position.y).abs() > 2.0 {
+                continue;
+            }
+
+            state.affected.insert(entity, tick + REAPPLICATION_DELAY);
+            applied.push((entity, state.effects.clone()));
+            state.radius += RADIUS_ON_USE;
+            if state.radius <= 0.0 {
+                break;
+            }
+        }
+    }
+
+    for (entity, effects) in applied {
+        for (effect, instance) in effects {
+            if effect.is_instant() {
+                apply_instant_effect(game, entity, effect, instance.amplifier, 0.5)?;
+            } else {
+                apply_effect(game, entity, effect, instance)?;
+            }
+        }
+    }
+    for cloud in expired {
+        game.remove_entity(cloud)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,346 @@
+//! Drinkable, splash and lingering potions.
+//!
+//! Drinking goes through the same pipeline as eating in the
+//! [`hunger`](crate::hunger) module. Splash and lingering potions
+//! are thrown as [`ThrownPotion`] entities which break on impact.
+
+use std::convert::TryFrom;
+
+use base::{Item, ItemStack, Position, StatusEffect, ValidBlockPosition, Vec3d};
+use ecs::{Entity, SysResult, SystemExecutor};
+use libcraft_core::Hand;
+use quill_common::{
+    components::{ActiveEffects, EffectInstance, Instabreak},
+    entities::Player,
+    entity_init::EntityInit,
+    events::UseItemEvent,
+};
+
+use crate::{events::PotionSplashEvent, hunger::hand_slot, Game, Window};
+
+use super::{apply_effect, apply_instant_effect, area_effect_cloud, particle_color};
+
+/// Height of a player's eyes above their feet.
+const PLAYER_EYE_HEIGHT: f64 = 1.62;
+
+/// Speed at which potions are thrown.
+const THROW_SPEED: f64 = 0.5;
+
+/// Potions are thrown this many degrees above where the player is looking.
+const THROW_PITCH_OFFSET: f32 = -20.0;
+
+const GRAVITY: f64 = 0.05;
+const DRAG: f64 = 0.99;
+
+/// Entities within this distance of a splash are affected.
+const SPLASH_RANGE: f64 = 4.0;
+
+/// Distance within which a thrown potion hits an entity.
+const HIT_RANGE: f64 = 1.0;
+
+/// Ticks during which a thrown potion cannot hit its thrower.
+const THROWER_GRACE_TICKS: u32 = 5;
+
+/// Splashed effects lasting no longer than this are not applied.
+const MIN_SPLASH_DURATION: u32 = 20;
+
+/// Returns the effects of a potion as `(effect, duration, amplifier)`,
+/// given its identifier, e.g. `minecraft:strong_swiftness`.
+///
+/// Unknown potions and potions without effects, like water
+/// or awkward potions, return an empty slice.
+pub fn potion_effects(potion: &str) -> &'static [(StatusEffect, u32, u8)] {
+    use StatusEffect::*;
+    match potion.strip_prefix("minecraft:").unwrap_or(potion) {
+        "night_vision" => &[(NightVision, 3600, 0)],
+        "long_night_vision" => &[(NightVision, 9600, 0)],
+        "invisibility" => &[(Invisibility, 3600, 0)],
+        "long_invisibility" => &[(Invisibility, 9600, 0)],
+        "leaping" => &[(JumpBoost, 3600, 0)],
+        "long_leaping" => &[(JumpBoost, 9600, 0)],
+        "strong_leaping" => &[(JumpBoost, 1800, 1)],
+        "fire_resistance" => &[(FireResistance, 3600, 0)],
+        "long_fire_resistance" => &[(FireResistance, 9600, 0)],
+        "swiftness" => &[(Speed, 3600, 0)],
+        "long_swiftness" => &[(Speed, 9600, 0)],
+        "strong_swiftness" => &[(Speed, 1800, 1)],
+        "slowness" => &[(Slowness, 1800, 0)],
+        "long_slowness" => &[(Slowness, 4800, 0)],
+        "strong_slowness" => &[(Slowness, 400, 3)],
+        "turtle_master" => &[(Slowness, 400, 3), (Resistance, 400, 2)],
+        "long_turtle_master" => &[(Slowness, 800, 3), (Resistance, 800, 2)],
+        "strong_turtle_master" => &[(Slowness, 400, 5), (Resistance, 400, 3)],
+        "water_breathing" => &[(WaterBreathing, 3600, 0)],
+        "long_water_breathing" => &[(WaterBreathing, 9600, 0)],
+        "healing" => &[(InstantHealth, 1, 0)],
+        "strong_healing" => &[(InstantHealth, 1, 1)],
+        "harming" => &[(InstantDamage, 1, 0)],
+        "strong_harming" => &[(InstantDamage, 1, 1)],
+        "poison" => &[(Poison, 900, 0)],
+        "long_poison" => &[(Poison, 1800, 0)],
+        "strong_poison" => &[(Poison, 432, 1)],
+        "regeneration" => &[(Regeneration, 900, 0)],
+        "long_regeneration" => &[(Regeneration, 1800, 0)],
+        "strong_regeneration" => &[(Regeneration, 450, 1)],
+        "strength" => &[(Strength, 3600, 0)],
+        "long_strength" => &[(Strength, 9600, 0)],
+        "strong_strength" => &[(Strength, 1800, 1)],
+        "weakness" => &[(Weakness, 1800, 0)],
+        "long_weakness" => &[(Weakness, 4800, 0)],
+        "luck" => &[(Luck, 6000, 0)],
+        "slow_falling" => &[(SlowFalling, 1800, 0)],
+        "long_slow_falling" => &[(SlowFalling, 4800, 0)],
+        _ => &[],
+    }
+}
+
+/// Gets the particle color of a potion.
+///
+/// Potions without effects have the color of water.
+pub fn potion_color(potion: &str) -> u32 {
+    const WATER_COLOR: u32 = 0x385DC6;
+    let instances: Vec<(StatusEffect, EffectInstance)> = potion_effects(potion)
+        .iter()
+        .map(|&(effect, duration, amplifier)| (effect, EffectInstance::new(amplifier, duration)))
+        .collect();
+    particle_color(
+        instances
+            .iter()
+            .map(|(effect, instance)| (*effect, instance)),
+    )
+    .unwrap_or(WATER_COLOR)
+}
+
+/// Component of a thrown splash or lingering potion.
+#[derive(Clone, Debug)]
+pub struct ThrownPotion {
+    /// The potion item which was thrown.
+    pub item: ItemStack,
+    pub thrower: Entity,
+    velocity: Vec3d,
+    /// Ticks since the potion was thrown.
+    age: u32,
+}
+
+impl ThrownPotion {
+    pub fn new(item: ItemStack, thrower: Entity, velocity: Vec3d) -> Self {
+        Self {
+            item,
+            thrower,
+            velocity,
+            age: 0,
+        }
+    }
+
+    pub fn velocity(&self) -> Vec3d {
+        self.velocity
+    }
+
+    /// Whether the potion leaves an area effect cloud when it breaks.
+    pub fn is_lingering(&self) -> bool {
+        self.item.item() == Item::LingeringPotion
+    }
+}
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .add_system(throw_potions)
+        .add_system(move_thrown_potions);
+}
+
+/// Applies the effects of a drunk potion to a player.
+pub fn drink_potion(game: &mut Game, player: Entity, potion: &str) -> SysResult {
+    for &(effect, duration, amplifier) in potion_effects(potion) {
+        apply_effect(
+            game,
+            player,
+            effect,
+            EffectInstance::new(amplifier, duration),
+        )?;
+    }
+    Ok(())
+}
+
+/// Throws splash and lingering potions used by players.
+fn throw_potions(game: &mut Game) -> SysResult {
+    let uses: Vec<(Entity, Hand)> = game
+        .ecs
+        .query::<(&Player, &UseItemEvent)>()
+        .iter()
+        .map(|(player, (_, event))| (player, event.hand))
+        .collect();
+
+    for (player, hand) in uses {
+        let item = {
+            let slot = match hand_slot(game, player, hand) {
+                Some(slot) => slot,
+                None => continue,
+            };
+            let window = game.ecs.get::<Window>(player)?;
+            let mut item = window.item(slot)?;
+            match item.item_kind() {
+                Some(Item::SplashPotion) | Some(Item::LingeringPotion) => {}
+                _ => continue,
+            }
+
+            let instabuild = game
+                .ecs
+                .get::<Instabreak>(player)
+                .map(|instabreak| instabreak.0)
+                .unwrap_or(false);
+            if instabuild {
+                item.option_ref().cloned().map(|mut stack| {
+                    stack.unchecked_set_count(1);
+                    stack
+                })
+            } else {
+                item.try_take(1).into_option()
+            }
+        };
+        let item = match item {
+            Some(item) => item,
+            None => continue,
+        };
+
+        let mut position = *game.ecs.get::<Position>(player)?;
+        position.y += PLAYER_EYE_HEIGHT - 0.1;
+        let mut aim = position;
+        aim.pitch += THROW_PITCH_OFFSET;
+        let velocity = aim.direction() * THROW_SPEED;
+
+        let mut builder = game.create_entity_builder(position, EntityInit::Potion);
+        builder.add(ThrownPotion::new(item, player, velocity));
+        game.spawn_entity(builder);
+    }
+    Ok(())
+}
+
+/// Moves thrown potions and breaks them when they hit a block or an entity.
+fn move_thrown_potions(game: &mut Game) -> SysResult {
+    let targets: Vec<(Entity, Position)> = game
+        .ecs
+        .query::<(&ActiveEffects, &Position)>()
+        .iter()
+        .map(|(entity, (_, &position))| (entity, position))
+        .collect();
+
+    let mut impacts = Vec::new();
+    for (potion, (state, position)) in game
+        .ecs
+        .query::<(&mut ThrownPotion, &mut Position)>()
+        .iter()
+    {
+        state.age += 1;
+        let next = *position + state.velocity;
+
+        let hit = targets
+            .iter()
+            .filter(|(target, _)| *target != state.thrower || state.age > THROWER_GRACE_TICKS)
+            .find(|(_, target)| target.distance_to(next) < HIT_RANGE)
+            .map(|&(target, _)| target);
+        if hit.is_some() || is_solid_at(game, next) {
+            impacts.push((potion, next, hit));
+            continue;
+        }
+
+        *position = next;
+        state.velocity.y -= GRAVITY;
+        state.velocity *= DRAG;
+    }
+
+    for (potion, position, hit) in impacts {
+        let state = game.ecs.get::<ThrownPotion>(potion)?.clone();
+        splash(game, &state, position, hit)?;
+        game.remove_entity(potion)?;
+    }
+    Ok(())
+}
+
+fn is_solid_at(game: &Game, position: Position) -> bool {
+    let block = ValidBlockPosition::try_from(position.block())
+        .ok()
+        .and_then(|pos| game.block(pos));
+    match block {
+        Some(block) => block.is_solid(),
+        // Break potions which fall out of the world or into unloaded chunks.
+        None => true,
+    }
+}
+
+/// Breaks a thrown potion at `position`, either applying its
+/// effects to nearby entities or leaving an area effect cloud.
+///
+/// `hit` is the entity the potion hit directly, if any.
+fn splash(
+    game: &mut Game,
+    potion: &ThrownPotion,
+    position: Position,
+    hit: Option<Entity>,
+) -> SysResult {
+    let name = potion.item.potion().unwrap_or("minecraft:water");
+    let effects = potion_effects(name);
+    game.ecs.insert_event(PotionSplashEvent {
+        position,
+        color: potion_color(name),
+        instant: effects.iter().any(|(effect, _, _)| effect.is_instant()),
+    });
+
+    if potion.is_lingering() {
+        area_effect_cloud::spawn_cloud(game, position, effects, potion_color(name));
+        return Ok(());
+    }
+
+    let affected: Vec<(Entity, f64)> = game
+        .ecs
+        .query::<(&ActiveEffects, &Position)>()
+        .iter()
+        .map(|(entity, (_, target))| (entity, target.distance_to(position)))
+        .filter(|&(_, distance)| distance < SPLASH_RANGE)
+        .collect();
+    for (entity, distance) in affected {
+        let potency = if Some(entity) == hit {
+            1.0
+        } else {
+            1.0 - distance / SPLASH_RANGE
+        };
+        for &(effect, duration, amplifier) in effects {
+            if effect.is_instant() {
+                apply_instant_effect(game, entity, effect, amplifier, potency as f32)?;
+                continue;
+            }
+            let duration = (potency * f64::from(duration) + 0.5) as u32;
+            if duration > MIN_SPLASH_DURATION {
+                apply_effect(
+                    game,
+                    entity,
+                    effect,
+                    EffectInstance::new(amplifier, duration),
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn potion_lookup_accepts_namespaces() {
+        assert_eq!(
+            potion_effects("minecraft:strong_swiftness"),
+            &[(StatusEffect::Speed, 1800, 1)]
+        );
+        assert_eq!(
+            potion_effects("long_poison"),
+            potion_effects("minecraft:long_poison")
+        );
+        assert!(potion_effects("minecraft:awkward").is_empty());
+        assert_eq!(potion_color("minecraft:water"), 0x385DC6);
+        assert_eq!(
+            potion_color("minecraft:swiftness"),
+            StatusEffect::Speed.color()
+        );
+    }
+}
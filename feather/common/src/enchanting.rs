@@ -0,0 +1,357 @@
+//! Enchanting tables.
+//!
+//! Using an enchanting table opens its window. The three enchantments offered
+//! for the item placed in it are chosen with [`select_enchantments`], seeded by the
+//! player's [`EnchantmentSeed`] so that the offers only change once the player
+//! enchants something. Bookshelves around the table raise the offered levels.
+
+use std::convert::TryFrom;
+
+use base::{BlockKind, BlockPosition, Gamemode, Inventory, Item, ValidBlockPosition};
+use ecs::{Entity, EntityBuilder, SysResult, SystemExecutor};
+use libcraft_items::{select_enchantments, Enchantment, InventorySlot, ItemStack};
+use quill_common::{
+    components::ExperienceLevel, entities::Player, entity_init::EntityInit,
+    events::BlockInteractEvent,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    events::{
+        EnchantmentOffersUpdateEvent, WindowButtonClickEvent, WindowCloseEvent, WindowOpenEvent,
+        WindowUpdateEvent,
+    },
+    experience::remove_levels,
+    interactable::InteractableRegistry,
+    window::{close_container_window, BackingWindow},
+    Game, Window,
+};
+
+/// Window index of the slot holding the item to enchant.
+const ITEM_SLOT: usize = 0;
+
+/// Window index of the slot holding lapis lazuli.
+const LAPIS_SLOT: usize = 1;
+
+/// Bookshelves beyond this number do not increase offered levels.
+const MAX_BOOKSHELVES: u32 = 15;
+
+/// Seed used to generate the enchantments offered to a player.
+/// A new seed is chosen each time the player enchants an item.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EnchantmentSeed(pub i32);
+
+impl EnchantmentSeed {
+    pub fn random() -> Self {
+        Self(rand::random())
+    }
+}
+
+/// An enchantment offered by an enchanting table.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EnchantmentOffer {
+    /// The experience level required to choose this offer.
+    pub cost: u32,
+    /// One of the enchantments which will be applied, shown as a hint.
+    pub hint: Enchantment,
+}
+
+/// Component on players who have an enchanting table open.
+#[derive(Clone, Debug)]
+pub struct OpenEnchantingTable {
+    pub position: ValidBlockPosition,
+    /// The offers for the current item, one per button.
+    pub offers: [Option<EnchantmentOffer>; 3],
+    /// The item and seed the offers were generated for.
+    offered_for: Option<(InventorySlot, i32)>,
+}
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    game.resources
+        .get_mut::<InteractableRegistry>()
+        .expect("Failed to get the interactable registry")
+        .register(BlockKind::EnchantingTable);
+
+    systems
+        .add_system(open_enchanting_tables)
+        .add_system(update_offers)
+        .add_system(enchant_items)
+        .add_system(close_enchanting_tables);
+
+    game.add_entity_spawn_callback(add_enchantment_seed);
+}
+
+fn add_enchantment_seed(builder: &mut EntityBuilder, init: &EntityInit) {
+    if let EntityInit::Player = init {
+        builder.add(EnchantmentSeed::random());
+    }
+}
+
+/// Opens the enchanting table window for players who use an enchanting table.
+fn open_enchanting_tables(game: &mut Game) -> SysResult {
+    let opened: Vec<(Entity, ValidBlockPosition)> = game
+        .ecs
+        .query::<(&Player, &BlockInteractEvent)>()
+        .iter()
+        .filter_map(|(player, (_, event))| {
+            let position = ValidBlockPosition::try_from(event.location).ok()?;
+            let is_table = game.block(position)?.kind() == BlockKind::EnchantingTable;
+            is_table.then_some((player, position))
+        })
+        .collect();
+
+    for (player, position) in opened {
+        if game.ecs.get::<OpenEnchantingTable>(player).is_ok() {
+            continue;
+        }
+
+        let inventory = game.ecs.get::<Inventory>(player)?.new_handle();
+        game.ecs
+            .get_mut::<Window>(player)?
+            .set_inner(BackingWindow::Enchantment {
+                enchantment_table: Inventory::enchantment_table(),
+                player: inventory,
+            });
+        game.ecs.insert(
+            player,
+            OpenEnchantingTable {
+                position,
+                offers: [None; 3],
+                offered_for: None,
+            },
+        )?;
+        game.ecs.insert_entity_event(player, WindowOpenEvent)?;
+    }
+    Ok(())
+}
+
+/// Regenerates the offers when the item to enchant or the seed changes.
+fn update_offers(game: &mut Game) -> SysResult {
+    let mut updated = Vec::new();
+    for (player, (table, window, seed)) in game
+        .ecs
+        .query::<(&mut OpenEnchantingTable, &Window, &EnchantmentSeed)>()
+        .iter()
+    {
+        let item = window.item(ITEM_SLOT)?.clone();
+        if table.offered_for.as_ref() == Some(&(item.clone(), seed.0)) {
+            continue;
+        }
+
+        let bookshelves = count_bookshelves(game, table.position);
+        table.offers = match item.option_ref() {
+            Some(stack) => enchantment_offers(stack, seed.0, bookshelves),
+            None => [None; 3],
+        };
+        table.offered_for = Some((item, seed.0));
+        updated.push(player);
+    }
+
+    for player in updated {
+        game.ecs
+            .insert_entity_event(player, EnchantmentOffersUpdateEvent)?;
+    }
+    Ok(())
+}
+
+/// Counts the bookshelves around an enchanting table.
+///
+/// Bookshelves must be two blocks away from the table,
+/// with only air between them and the table.
+fn count_bookshelves(game: &Game, table: ValidBlockPosition) -> u32 {
+    let block_at = |dx: i32, dy: i32, dz: i32| {
+        let position = BlockPosition::new(table.x() + dx, table.y() + dy, table.z() + dz);
+        game.block(ValidBlockPosition::try_from(position).ok()?)
+    };
+    let is_air = |dx, dy, dz| matches!(block_at(dx, dy, dz), Some(block) if block.is_air());
+    let bookshelf = |dx, dy, dz| {
+        u32::from(matches!(
+            block_at(dx, dy, dz),
+            Some(block) if block.kind() == BlockKind::Bookshelf
+        ))
+    };
+
+    let mut count = 0;
+    for dz in -1..=1 {
+        for dx in -1..=1 {
+            if (dx == 0 && dz == 0) || !is_air(dx, 0, dz) || !is_air(dx, 1, dz) {
+                continue;
+            }
+
+            count += bookshelf(2 * dx, 0, 2 * dz) + bookshelf(2 * dx, 1, 2 * dz);
+            if dx != 0 && dz != 0 {
+                count += bookshelf(2 * dx, 0, dz)
+                    + bookshelf(2 * dx, 1, dz)
+                    + bookshelf(dx, 0, 2 * dz)
+                    + bookshelf(dx, 1, 2 * dz);
+            }
+        }
+    }
+    count
+}
+
+/// Whether an item stack can be enchanted in an enchanting table.
+fn is_enchantable(stack: &ItemStack) -> bool {
+    stack.item().enchantability().is_some() && stack.enchantments().is_empty() && stack.count() == 1
+}
+
+/// Generates the three offers for an item.
+pub fn enchantment_offers(
+    stack: &ItemStack,
+    seed: i32,
+    bookshelves: u32,
+) -> [Option<EnchantmentOffer>; 3] {
+    let mut offers = [None; 3];
+    if !is_enchantable(stack) {
+        return offers;
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed as u64);
+    let costs = [
+        offer_cost(&mut rng, 0, bookshelves),
+        offer_cost(&mut rng, 1, bookshelves),
+        offer_cost(&mut rng, 2, bookshelves),
+    ];
+    for (button, &cost) in costs.iter().enumerate() {
+        if cost < button as u32 + 1 {
+            continue;
+        }
+
+        let enchantments = offer_enchantments(stack.item(), seed, button, cost);
+        if !enchantments.is_empty() {
+            let hint = enchantments[rng.gen_range(0..enchantments.len())];
+            offers[button] = Some(EnchantmentOffer { cost, hint });
+        }
+    }
+    offers
+}
+
+/// Gets the level cost of the offer for the given button.
+fn offer_cost(rng: &mut impl Rng, button: usize, bookshelves: u32) -> u32 {
+    let bookshelves = bookshelves.min(MAX_BOOKSHELVES);
+    let base = rng.gen_range(1..=8) + bookshelves / 2 + rng.gen_range(0..=bookshelves);
+    match button {
+        0 => (base / 3).max(1),
+        1 => base * 2 / 3 + 1,
+        _ => base.max(bookshelves * 2),
+    }
+}
+
+/// Gets the enchantments applied by the offer for the given button.
+///
+/// Deterministic for a given seed, so the enchantments applied
+/// match the hint shown to the player.
+pub fn offer_enchantments(item: Item, seed: i32, button: usize, cost: u32) -> Vec<Enchantment> {
+    let mut rng = StdRng::seed_from_u64((seed as u64).wrapping_add(button as u64));
+    let mut enchantments = select_enchantments(&mut rng, item, cost, false);
+    if item == Item::Book && enchantments.len() > 1 {
+        enchantments.remove(rng.gen_range(0..enchantments.len()));
+    }
+    enchantments
+}
+
+/// Enchants items when players choose an offer.
+fn enchant_items(game: &mut Game) -> SysResult {
+    let clicks: Vec<(Entity, usize)> = game
+        .ecs
+        .query::<(&WindowButtonClickEvent, &OpenEnchantingTable)>()
+        .iter()
+        .map(|(player, (event, _))| (player, event.button as usize))
+        .collect();
+
+    for (player, button) in clicks {
+        enchant_item(game, player, button)?;
+    }
+    Ok(())
+}
+
+fn enchant_item(game: &mut Game, player: Entity, button: usize) -> SysResult {
+    let offer = match game
+        .ecs
+        .get::<OpenEnchantingTable>(player)?
+        .offers
+        .get(button)
+    {
+        Some(Some(offer)) => *offer,
+        _ => return Ok(()),
+    };
+    let creative = *game.ecs.get::<Gamemode>(player)? == Gamemode::Creative;
+    let level = game.ecs.get::<ExperienceLevel>(player)?.0;
+    let seed = game.ecs.get::<EnchantmentSeed>(player)?.0;
+    // Each button costs one more lapis and level than the previous one.
+    let price = button as u32 + 1;
+
+    {
+        let window = game.ecs.get::<Window>(player)?;
+        if !creative {
+            let lapis = window.item(LAPIS_SLOT)?;
+            if lapis.item_kind() != Some(Item::LapisLazuli)
+                || lapis.count() < price
+                || level < offer.cost
+            {
+                return Ok(());
+            }
+        }
+
+        {
+            let mut item = window.item(ITEM_SLOT)?;
+            let stack = match item.option_mut() {
+                Some(stack) => stack,
+                None => return Ok(()),
+            };
+            let enchantments = offer_enchantments(stack.item(), seed, button, offer.cost);
+            if stack.item() == Item::Book {
+                stack.unchecked_set_item(Item::EnchantedBook);
+            }
+            for enchantment in enchantments {
+                stack.set_enchantment_level(enchantment.kind(), enchantment.level());
+            }
+        }
+
+        if !creative {
+            let _ = window.item(LAPIS_SLOT)?.try_take(price);
+        }
+    }
+
+    remove_levels(game, player, price)?;
+    *game.ecs.get_mut::<EnchantmentSeed>(player)? = EnchantmentSeed::random();
+    game.ecs.insert_entity_event(player, WindowUpdateEvent)?;
+    Ok(())
+}
+
+fn close_enchanting_tables(game: &mut Game) -> SysResult {
+    let closed: Vec<Entity> = game
+        .ecs
+        .query::<(&WindowCloseEvent, &OpenEnchantingTable)>()
+        .iter()
+        .map(|(player, _)| player)
+        .collect();
+
+    for player in closed {
+        game.ecs.remove::<OpenEnchantingTable>(player)?;
+        close_container_window(game, player)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offers_depend_on_seed_and_bookshelves() {
+        let pickaxe = ItemStack::new(Item::DiamondPickaxe, 1).unwrap();
+        let offers = enchantment_offers(&pickaxe, 42, MAX_BOOKSHELVES);
+        assert_eq!(offers, enchantment_offers(&pickaxe, 42, MAX_BOOKSHELVES));
+        assert_eq!(offers[2].unwrap().cost, 30);
+
+        for (button, offer) in offers.iter().enumerate() {
+            let offer = offer.unwrap();
+            let enchantments = offer_enchantments(Item::DiamondPickaxe, 42, button, offer.cost);
+            assert!(enchantments.contains(&offer.hint));
+        }
+
+        let stone = ItemStack::new(Item::Stone, 1).unwrap();
+        assert_eq!(enchantment_offers(&stone, 42, MAX_BOOKSHELVES), [None; 3]);
+    }
+}
@@ -1,8 +1,11 @@
 use anyhow::bail;
-use base::EntityKind;
+use base::{Area, EntityKind, Inventory, ItemStack};
 use ecs::{EntityBuilder, SysResult};
 use quill_common::{
-    components::{CreativeFlying, Sneaking, Sprinting},
+    components::{
+        CreativeFlying, Exhaustion, ExperienceLevel, ExperienceProgress, FoodLevel, Saturation,
+        Sneaking, Sprinting, TotalExperience,
+    },
     entities::Player,
 };
 
@@ -13,6 +16,12 @@ pub fn build_default(builder: &mut EntityBuilder) {
         .add(CreativeFlying(false))
         .add(Sneaking(false))
         .add(Sprinting(false))
+        .add(FoodLevel::default())
+        .add(Saturation::default())
+        .add(Exhaustion::default())
+        .add(ExperienceLevel::default())
+        .add(ExperienceProgress::default())
+        .add(TotalExperience::default())
         .add(EntityKind::Player);
 }
 
@@ -38,3 +47,11 @@ impl HotbarSlot {
         Ok(())
     }
 }
+
+/// Gets the item stack in a player's main hand.
+pub fn held_item(inventory: &Inventory, hotbar_slot: HotbarSlot) -> Option<ItemStack> {
+    inventory
+        .item(Area::Hotbar, hotbar_slot.get())?
+        .option_ref()
+        .cloned()
+}
@@ -1,4 +1,5 @@
-use base::{ChunkHandle, ChunkPosition};
+use base::{ChunkHandle, ChunkPosition, Item, Position, StatusEffect};
+use ecs::Entity;
 
 use crate::view::View;
 
@@ -51,6 +52,9 @@ pub struct ChunkCrossEvent {
 pub struct ChunkLoadEvent {
     pub position: ChunkPosition,
     pub chunk: ChunkHandle,
+    /// Whether the chunk was newly generated
+    /// rather than loaded from the world save.
+    pub generated: bool,
 }
 
 /// Triggered when an error occurs while loading a chunk.
@@ -64,3 +68,74 @@ pub struct TablistExtrasUpdateEvent {
     pub header: Option<String>,
     pub footer: Option<String>,
 }
+
+/// Triggered when an entity takes damage.
+#[derive(Debug, Clone)]
+pub struct EntityDamageEvent {
+    pub amount: f32,
+    /// The entity which dealt the damage, if any.
+    pub attacker: Option<Entity>,
+}
+
+/// Triggered when a player finishes eating a food item or drinking a potion.
+#[derive(Debug, Clone)]
+pub struct FoodEatenEvent {
+    pub item: Item,
+    /// The window index of the slot the food was eaten from.
+    pub slot: usize,
+}
+
+/// Triggered on an experience orb when a player picks it up.
+#[derive(Debug, Clone)]
+pub struct ExperienceOrbPickupEvent {
+    /// The player who picked up the orb.
+    pub player: Entity,
+}
+
+/// Triggered when a player opens a window other than their inventory,
+/// after their `Window` component has been replaced.
+#[derive(Debug, Clone)]
+pub struct WindowOpenEvent;
+
+/// Triggered when a player closes the window they have open.
+#[derive(Debug, Clone)]
+pub struct WindowCloseEvent;
+
+/// Triggered when a player clicks a button in their open window,
+/// such as an enchantment in an enchanting table.
+#[derive(Debug, Clone)]
+pub struct WindowButtonClickEvent {
+    pub button: u8,
+}
+
+/// Triggered when the contents of a player's window are
+/// changed by the server rather than by the player clicking.
+#[derive(Debug, Clone)]
+pub struct WindowUpdateEvent;
+
+/// Triggered when the enchantments offered to a player
+/// by an enchanting table change.
+#[derive(Debug, Clone)]
+pub struct EnchantmentOffersUpdateEvent;
+
+/// Triggered when a thrown splash or lingering potion breaks.
+#[derive(Debug, Clone)]
+pub struct PotionSplashEvent {
+    pub position: Position,
+    /// RGB color of the splash particles.
+    pub color: u32,
+    /// Whether the potion has instant effects, which show different particles.
+    pub instant: bool,
+}
+
+/// Triggered when a player sets the effects of a beacon.
+#[derive(Debug, Clone)]
+pub struct BeaconEffectSetEvent {
+    pub primary: Option<StatusEffect>,
+    pub secondary: Option<StatusEffect>,
+}
+
+/// Triggered when the pyramid levels or effects shown
+/// in a player's open beacon window change.
+#[derive(Debug, Clone)]
+pub struct BeaconUpdateEvent;
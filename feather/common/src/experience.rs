@@ -0,0 +1,445 @@
+//! Experience orbs and player experience levels.
+//!
+//! Mining ores and killing mobs drops [`ExperienceOrbState`] entities.
+//! Orbs merge with nearby orbs, fly towards players within range and
+//! raise the [`ExperienceLevel`] of the player who picks them up.
+//! Smelting and breeding should drop experience through [`spawn_experience_orbs`].
+
+use std::convert::TryFrom;
+
+use base::{vec3, BlockKind, EntityKind, Gamemode, Position, ValidBlockPosition, Vec3d};
+use ecs::{Entity, EntityBuilder, SysResult, SystemExecutor};
+use libcraft_core::MobCategory;
+use libcraft_items::EnchantmentKind;
+use quill_common::{
+    components::{ExperienceLevel, ExperienceProgress, Instabreak, TotalExperience},
+    entities::Player,
+    entity_init::EntityInit,
+};
+use rand::Rng;
+
+use crate::{
+    entities::player::{held_item, HotbarSlot},
+    events::ExperienceOrbPickupEvent,
+    Game,
+};
+
+/// Players attract orbs within this distance.
+const ATTRACTION_RANGE: f64 = 8.0;
+
+/// Orbs are picked up within this distance of a player.
+const PICKUP_RANGE: f64 = 1.0;
+
+/// Height above a player's feet which orbs fly towards.
+const PLAYER_TARGET_HEIGHT: f64 = 0.8;
+
+/// Orbs closer together than this are merged.
+const MERGE_RANGE: f64 = 1.0;
+
+/// Ticks between attempts to merge orbs.
+const MERGE_INTERVAL: u64 = 20;
+
+/// Number of ticks after which orbs despawn.
+const ORB_LIFETIME: u32 = 6000;
+
+/// Ticks a player must wait between picking up orbs.
+const PICKUP_DELAY: u32 = 2;
+
+const GRAVITY: f64 = 0.03;
+const DRAG: f64 = 0.98;
+const ATTRACTION_ACCELERATION: f64 = 0.1;
+
+/// Orb values used to split dropped experience into orbs, largest first.
+const ORB_SIZES: [u32; 10] = [2477, 1237, 617, 307, 149, 73, 37, 17, 7, 3];
+
+/// State of an experience orb entity.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ExperienceOrbState {
+    /// Experience points granted when picked up.
+    pub value: u32,
+    /// Ticks since the orb was spawned.
+    age: u32,
+    velocity: Vec3d,
+}
+
+impl ExperienceOrbState {
+    pub fn new(value: u32, velocity: Vec3d) -> Self {
+        Self {
+            value,
+            age: 0,
+            velocity,
+        }
+    }
+}
+
+/// Ticks until a player can pick up another orb.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct OrbPickupCooldown(u32);
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    systems
+        .add_system(move_orbs)
+        .add_system(merge_orbs)
+        .add_system(pick_up_orbs);
+
+    game.add_entity_spawn_callback(add_pickup_cooldown);
+}
+
+fn add_pickup_cooldown(builder: &mut EntityBuilder, init: &EntityInit) {
+    if let EntityInit::Player = init {
+        builder.add(OrbPickupCooldown::default());
+    }
+}
+
+/// Gets the number of experience points needed to advance
+/// from `level` to the next level.
+pub fn experience_to_next_level(level: u32) -> u32 {
+    if level >= 30 {
+        112 + (level - 30) * 9
+    } else if level >= 15 {
+        37 + (level - 15) * 5
+    } else {
+        7 + level * 2
+    }
+}
+
+/// Gives experience points to a player, raising their level
+/// as the progress bar fills up.
+pub fn give_experience(game: &Game, player: Entity, amount: u32) -> SysResult {
+    let mut level = game.ecs.get_mut::<ExperienceLevel>(player)?;
+    let mut progress = game.ecs.get_mut::<ExperienceProgress>(player)?;
+    let mut total = game.ecs.get_mut::<TotalExperience>(player)?;
+
+    total.0 = total.0.saturating_add(amount);
+    progress.0 += amount as f32 / experience_to_next_level(level.0) as f32;
+    while progress.0 >= 1.0 {
+        progress.0 = (progress.0 - 1.0) * experience_to_next_level(level.0) as f32;
+        level.0 += 1;
+        progress.0 /= experience_to_next_level(level.0) as f32;
+    }
+    Ok(())
+}
+
+/// Removes experience levels from a player, e.g. when enchanting.
+///
+/// Removing more levels than the player has resets all of their experience.
+pub fn remove_levels(game: &Game, player: Entity, levels: u32) -> SysResult {
+    let mut level = game.ecs.get_mut::<ExperienceLevel>(player)?;
+    if levels > level.0 {
+        level.0 = 0;
+        game.ecs.get_mut::<ExperienceProgress>(player)?.0 = 0.0;
+        game.ecs.get_mut::<TotalExperience>(player)?.0 = 0;
+    } else {
+        level.0 -= levels;
+    }
+    Ok(())
+}
+
+/// Spawns experience orbs worth `amount` points in total at `position`.
+pub fn spawn_experience_orbs(game: &mut Game, position: Position, mut amount: u32) {
+    let mut rng = rand::thread_rng();
+    while amount > 0 {
+        let value = orb_value(amount);
+        amount -= value;
+
+        let velocity = vec3(
+            rng.gen_range(-0.2..0.2),
+            rng.gen_range(0.0..0.4),
+            rng.gen_range(-0.2..0.2),
+        );
+        let mut builder = game.create_entity_builder(position, EntityInit::ExperienceOrb);
+        builder.add(ExperienceOrbState::new(value, velocity));
+        game.spawn_entity(builder);
+    }
+}
+
+/// Gets the value of the largest orb which fits in `amount`.
+fn orb_value(amount: u32) -> u32 {
+    ORB_SIZES
+        .iter()
+        .copied()
+        .find(|&size| amount >= size)
+        .unwrap_or(1)
+}
+
+/// Drops the experience for a block broken by `player`.
+///
+/// Creative players and ores mined with Silk Touch drop no experience.
+pub fn drop_block_experience(
+    game: &mut Game,
+    player: Entity,
+    position: ValidBlockPosition,
+    block: BlockKind,
+) -> SysResult {
+    if game.ecs.get::<Instabreak>(player)?.0 {
+        return Ok(());
+    }
+    if block != BlockKind::Spawner {
+        let inventory = game.ecs.get::<base::Inventory>(player)?;
+        let hotbar_slot = *game.ecs.get::<HotbarSlot>(player)?;
+        let silk_touch = held_item(&inventory, hotbar_slot)
+            .map(|stack| {
+                stack
+                    .enchantments()
+                    .iter()
+                    .any(|e| e.kind() == EnchantmentKind::SilkTouch)
+            })
+            .unwrap_or(false);
+        if silk_touch {
+            return Ok(());
+        }
+    }
+
+    let amount = block_experience(block, &mut rand::thread_rng());
+    if amount > 0 {
+        let center = position.position() + vec3(0.5, 0.5, 0.5);
+        spawn_experience_orbs(game, center, amount);
+    }
+    Ok(())
+}
+
+/// Gets the experience dropped when breaking a block.
+fn block_experience(block: BlockKind, rng: &mut impl Rng) -> u32 {
+    let range = match block {
+        BlockKind::CoalOre => 0..=2,
+        BlockKind::DiamondOre | BlockKind::EmeraldOre => 3..=7,
+        BlockKind::LapisOre | BlockKind::NetherQuartzOre => 2..=5,
+        BlockKind::RedstoneOre => 1..=5,
+        BlockKind::NetherGoldOre => 0..=1,
+        BlockKind::Spawner => 15..=43,
+        _ => return 0,
+    };
+    rng.gen_range(range)
+}
+
+/// Drops the experience for an entity killed by `attacker`.
+///
+/// Experience is only dropped for kills by players.
+pub(crate) fn drop_kill_experience(
+    game: &mut Game,
+    entity: Entity,
+    attacker: Option<Entity>,
+) -> SysResult {
+    let killed_by_player = match attacker {
+        Some(attacker) => game.ecs.get::<Player>(attacker).is_ok(),
+        None => false,
+    };
+    if !killed_by_player {
+        return Ok(());
+    }
+
+    let kind = *game.ecs.get::<EntityKind>(entity)?;
+    let position = *game.ecs.get::<Position>(entity)?;
+    let amount = kill_experience(kind, &mut rand::thread_rng());
+    if amount > 0 {
+        spawn_experience_orbs(game, position, amount);
+    }
+    Ok(())
+}
+
+/// Gets the experience dropped when a player kills an entity.
+fn kill_experience(kind: EntityKind, rng: &mut impl Rng) -> u32 {
+    match kind {
+        EntityKind::EnderDragon => 500,
+        EntityKind::Wither => 50,
+        EntityKind::Ravager | EntityKind::PiglinBrute => 20,
+        EntityKind::Blaze
+        | EntityKind::Guardian
+        | EntityKind::ElderGuardian
+        | EntityKind::Evoker => 10,
+        EntityKind::Slime | EntityKind::MagmaCube => 2,
+        EntityKind::Vindicator
+        | EntityKind::Pillager
+        | EntityKind::Illusioner
+        | EntityKind::Vex
+        | EntityKind::Shulker
+        | EntityKind::Zoglin => 5,
+        _ => match MobCategory::of(kind) {
+            Some(MobCategory::Monster) => 5,
+            Some(MobCategory::Creature) | Some(MobCategory::WaterCreature) => rng.gen_range(1..=3),
+            Some(MobCategory::Ambient) | None => 0,
+        },
+    }
+}
+
+/// Ages orbs, applies gravity and moves them towards nearby players.
+fn move_orbs(game: &mut Game) -> SysResult {
+    let players: Vec<Position> = game
+        .ecs
+        .query::<(&Player, &Position, &Gamemode)>()
+        .iter()
+        .filter(|(_, (_, _, &gamemode))| gamemode != Gamemode::Spectator)
+        .map(|(_, (_, &position, _))| position)
+        .collect();
+
+    let mut despawned = Vec::new();
+    for (orb, (state, position)) in game
+        .ecs
+        .query::<(&mut ExperienceOrbState, &mut Position)>()
+        .iter()
+    {
+        state.age += 1;
+        if state.age >= ORB_LIFETIME {
+            despawned.push(orb);
+            continue;
+        }
+
+        let target = players
+            .iter()
+            .map(|player| player.vec() + vec3(0.0, PLAYER_TARGET_HEIGHT, 0.0))
+            .map(|target| (target, target.distance(position.vec())))
+            .filter(|&(_, distance)| distance < ATTRACTION_RANGE)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+        if let Some((target, distance)) = target {
+            let strength = (1.0 - distance / ATTRACTION_RANGE).powi(2);
+            if distance > 0.0 {
+                state.velocity +=
+                    (target - position.vec()) / distance * strength * ATTRACTION_ACCELERATION;
+            }
+        }
+        state.velocity.y -= GRAVITY;
+
+        let mut next = *position + state.velocity;
+        if is_solid_at(game, next) {
+            next.y = position.y;
+            state.velocity.y = 0.0;
+        }
+        *position = next;
+        state.velocity *= DRAG;
+    }
+
+    for orb in despawned {
+        game.remove_entity(orb)?;
+    }
+    Ok(())
+}
+
+fn is_solid_at(game: &Game, position: Position) -> bool {
+    let block = ValidBlockPosition::try_from(position.block())
+        .ok()
+        .and_then(|pos| game.block(pos));
+    match block {
+        Some(block) => block.is_solid(),
+        // Keep orbs from falling out of the world or into unloaded chunks.
+        None => true,
+    }
+}
+
+/// Merges orbs which are close together into a single orb.
+fn merge_orbs(game: &mut Game) -> SysResult {
+    if !game.tick_count.is_multiple_of(MERGE_INTERVAL) {
+        return Ok(());
+    }
+
+    let orbs: Vec<(Entity, Position, u32)> = game
+        .ecs
+        .query::<(&ExperienceOrbState, &Position)>()
+        .iter()
+        .map(|(orb, (state, &position))| (orb, position, state.value))
+        .collect();
+
+    let mut merged = vec![false; orbs.len()];
+    for i in 0..orbs.len() {
+        if merged[i] {
+            continue;
+        }
+        let (orb, position, mut value) = orbs[i];
+        for j in i + 1..orbs.len() {
+            let (other, other_position, other_value) = orbs[j];
+            if !merged[j] && position.distance_to(other_position) < MERGE_RANGE {
+                merged[j] = true;
+                value = value.saturating_add(other_value);
+                game.remove_entity(other)?;
+            }
+        }
+        game.ecs.get_mut::<ExperienceOrbState>(orb)?.value = value;
+    }
+    Ok(())
+}
+
+/// Gives the experience of orbs to players who touch them.
+fn pick_up_orbs(game: &mut Game) -> SysResult {
+    let orbs: Vec<(Entity, Position, u32)> = game
+        .ecs
+        .query::<(&ExperienceOrbState, &Position)>()
+        .iter()
+        .map(|(orb, (state, &position))| (orb, position, state.value))
+        .collect();
+    let mut taken = vec![false; orbs.len()];
+
+    let mut pickups = Vec::new();
+    for (player, (_, &position, &gamemode, cooldown)) in game
+        .ecs
+        .query::<(&Player, &Position, &Gamemode, &mut OrbPickupCooldown)>()
+        .iter()
+    {
+        if cooldown.0 > 0 {
+            cooldown.0 -= 1;
+            continue;
+        }
+        if gamemode == Gamemode::Spectator {
+            continue;
+        }
+
+        let target = position + vec3(0.0, PLAYER_TARGET_HEIGHT, 0.0);
+        let orb = orbs.iter().enumerate().find(|(i, (_, orb_position, _))| {
+            !taken[*i] && orb_position.distance_to(target) < PICKUP_RANGE
+        });
+        if let Some((i, &(orb, _, value))) = orb {
+            taken[i] = true;
+            cooldown.0 = PICKUP_DELAY;
+            pickups.push((player, orb, value));
+        }
+    }
+
+    for (player, orb, value) in pickups {
+        give_experience(game, player, value)?;
+        game.ecs
+            .insert_entity_event(orb, ExperienceOrbPickupEvent { player })?;
+        game.remove_entity(orb)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn experience_levels_up() {
+        let mut game = Game::new();
+        let player = game.ecs.spawn((
+            ExperienceLevel::default(),
+            ExperienceProgress::default(),
+            TotalExperience::default(),
+        ));
+
+        give_experience(&game, player, 7).unwrap();
+        assert_eq!(game.ecs.get::<ExperienceLevel>(player).unwrap().0, 1);
+        assert!(game.ecs.get::<ExperienceProgress>(player).unwrap().0 < 1e-4);
+
+        // Level 1 to 2 takes 9 points
+        give_experience(&game, player, 9 + 11 / 2).unwrap();
+        assert_eq!(game.ecs.get::<ExperienceLevel>(player).unwrap().0, 2);
+        assert!((game.ecs.get::<ExperienceProgress>(player).unwrap().0 - 5.0 / 11.0).abs() < 1e-4);
+        assert_eq!(game.ecs.get::<TotalExperience>(player).unwrap().0, 21);
+
+        remove_levels(&game, player, 1).unwrap();
+        assert_eq!(game.ecs.get::<ExperienceLevel>(player).unwrap().0, 1);
+        remove_levels(&game, player, 5).unwrap();
+        assert_eq!(game.ecs.get::<ExperienceLevel>(player).unwrap().0, 0);
+        assert_eq!(game.ecs.get::<TotalExperience>(player).unwrap().0, 0);
+    }
+
+    #[test]
+    fn orbs_split_into_sizes() {
+        let mut amount = 25;
+        let mut values = Vec::new();
+        while amount > 0 {
+            let value = orb_value(amount);
+            amount -= value;
+            values.push(value);
+        }
+        assert_eq!(values, vec![17, 7, 1]);
+    }
+}
@@ -5,13 +5,20 @@ use ecs::{
     Ecs, Entity, EntityBuilder, HasEcs, HasResources, NoSuchEntity, Resources, SysResult,
     SystemExecutor,
 };
+use libcraft_core::{Difficulty, GameRules};
 use quill_common::events::{EntityCreateEvent, EntityRemoveEvent, PlayerJoinEvent};
-use quill_common::{entities::Player, entity_init::EntityInit};
+use quill_common::{
+    components::{ActiveEffects, Health, Invulnerable},
+    entities::Player,
+    entity_init::EntityInit,
+};
 
 use crate::{
     chat::{ChatKind, ChatMessage},
     chunk::entities::ChunkEntities,
-    events::BlockChangeEvent,
+    damage::LastDamage,
+    effects::modify_damage_taken,
+    events::{BlockChangeEvent, EntityDamageEvent},
     ChatBox, World,
 };
 
@@ -52,6 +59,12 @@ pub struct Game {
     /// Total ticks elapsed since the server started.
     pub tick_count: u64,
 
+    /// The world's game rules.
+    pub game_rules: GameRules,
+
+    /// The world's difficulty.
+    pub difficulty: Difficulty,
+
     entity_spawn_callbacks: Vec<EntitySpawnCallback>,
 
     entity_builder: EntityBuilder,
@@ -73,6 +86,8 @@ impl Game {
             resources: Arc::new(Resources::new()),
             chunk_entities: ChunkEntities::default(),
             tick_count: 0,
+            game_rules: GameRules::default(),
+            difficulty: Difficulty::default(),
             entity_spawn_callbacks: Vec::new(),
             entity_builder: EntityBuilder::new(),
         }
@@ -158,6 +173,61 @@ impl Game {
         self.ecs.insert_entity_event(entity, EntityRemoveEvent)
     }
 
+    /// Deals `amount` damage to an entity, recording
+    /// `attacker` as the source of the damage.
+    ///
+    /// Entities without `Health` or which are `Invulnerable` are unaffected.
+    /// Non-player entities are removed when their health reaches zero.
+    /// Triggers `EntityDamageEvent`.
+    pub fn damage_entity(
+        &mut self,
+        entity: Entity,
+        amount: f32,
+        attacker: Option<Entity>,
+    ) -> SysResult {
+        if self
+            .ecs
+            .get::<Invulnerable>(entity)
+            .map(|invulnerable| invulnerable.0)
+            .unwrap_or(false)
+        {
+            return Ok(());
+        }
+
+        let amount = match self.ecs.get::<ActiveEffects>(entity) {
+            Ok(effects) => modify_damage_taken(&effects, amount),
+            Err(_) => amount,
+        };
+        let remaining = {
+            let mut health = match self.ecs.get_mut::<Health>(entity) {
+                Ok(health) => health,
+                Err(_) => return Ok(()),
+            };
+            if health.0 <= 0.0 {
+                return Ok(());
+            }
+            health.0 = (health.0 - amount).max(0.0);
+            health.0
+        };
+
+        self.ecs.insert(
+            entity,
+            LastDamage {
+                tick: self.tick_count,
+                amount,
+                attacker,
+            },
+        )?;
+        self.ecs
+            .insert_entity_event(entity, EntityDamageEvent { amount, attacker })?;
+
+        if remaining <= 0.0 && self.ecs.get::<Player>(entity).is_err() {
+            crate::experience::drop_kill_experience(self, entity, attacker)?;
+            self.remove_entity(entity)?;
+        }
+        Ok(())
+    }
+
     /// Broadcasts a chat message to all entities with
     /// a `ChatBox` component (usually just players).
     pub fn broadcast_chat(&self, kind: ChatKind, message: impl Into<Text>) {
@@ -212,10 +282,12 @@ impl Game {
         };
 
         let was_successful = chunk.fill_section(section_y + 1, block);
+        drop(chunk);
 
         if !was_successful {
             return false;
         }
+        self.world.chunk_map().mark_dirty(chunk_pos);
 
         self.ecs.insert_event(BlockChangeEvent::fill_chunk_section(
             chunk_pos,
@@ -0,0 +1,431 @@
+//! Hunger, saturation and natural regeneration, following vanilla's food mechanics.
+//!
+//! Players accumulate [`Exhaustion`] by sprinting, jumping, attacking and
+//! taking damage. Every 4 points of exhaustion consume a point of
+//! [`Saturation`], or of [`FoodLevel`] once saturation runs out. A high
+//! food level regenerates health, while an empty food bar starves the player.
+
+use base::{
+    inventory::{SLOT_HOTBAR_OFFSET, SLOT_OFFHAND},
+    Difficulty, Item, ItemStack, Position,
+};
+use ecs::{Entity, EntityBuilder, SysResult, SystemExecutor};
+use libcraft_core::{Hand, InteractionType};
+use libcraft_items::{FoodProperties, InventorySlot};
+use quill_common::{
+    components::{
+        Exhaustion, FoodLevel, Health, Instabreak, Invulnerable, OnGround, Saturation, Sprinting,
+    },
+    entities::Player,
+    entity_init::EntityInit,
+    events::{InteractEntityEvent, UseItemEvent},
+};
+
+use crate::{
+    damage::PLAYER_MAX_HEALTH,
+    effects::potions,
+    entities::player::HotbarSlot,
+    events::{EntityDamageEvent, FoodEatenEvent},
+    Game, Window,
+};
+
+/// The highest food level a player can have.
+pub const MAX_FOOD_LEVEL: u32 = 20;
+
+/// Exhaustion is capped at this value.
+const MAX_EXHAUSTION: f32 = 40.0;
+
+/// Amount of exhaustion which consumes one point of saturation or food.
+const EXHAUSTION_PER_FOOD_POINT: f32 = 4.0;
+
+const SPRINT_EXHAUSTION_PER_BLOCK: f32 = 0.1;
+const JUMP_EXHAUSTION: f32 = 0.05;
+const SPRINT_JUMP_EXHAUSTION: f32 = 0.2;
+const ATTACK_EXHAUSTION: f32 = 0.1;
+const DAMAGE_EXHAUSTION: f32 = 0.1;
+const REGENERATION_EXHAUSTION: f32 = 6.0;
+
+/// Food level at or above which players slowly regenerate health.
+const REGENERATION_FOOD_LEVEL: u32 = 18;
+
+/// Ticks between regenerating or starving while the food bar is not full.
+const SLOW_FOOD_TICK_INTERVAL: u32 = 80;
+
+/// Ticks between regenerating while the food bar is full and saturated.
+const FAST_FOOD_TICK_INTERVAL: u32 = 10;
+
+/// Ticks taken to drink a potion.
+const POTION_DRINK_TICKS: u32 = 32;
+
+/// Per-player state for hunger mechanics.
+#[derive(Debug, Default)]
+pub struct HungerState {
+    /// Ticks since health was last regenerated or lost to starvation.
+    tick_timer: u32,
+    /// Position on the previous tick, used to measure sprinting distance.
+    last_position: Option<Position>,
+    was_on_ground: bool,
+}
+
+/// Component present on a player while they are eating or drinking a potion.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Eating {
+    pub hand: Hand,
+    pub item: Item,
+    /// Ticks left until the food is eaten or the potion is drunk.
+    pub remaining_ticks: u32,
+}
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    systems
+        .add_system(exhaust_from_movement)
+        .add_system(exhaust_from_attacks)
+        .add_system(exhaust_from_damage)
+        .add_system(start_eating)
+        .add_system(tick_eating)
+        .add_system(tick_food);
+
+    game.add_entity_spawn_callback(add_hunger_state);
+}
+
+fn add_hunger_state(builder: &mut EntityBuilder, init: &EntityInit) {
+    if let EntityInit::Player = init {
+        builder.add(HungerState::default());
+    }
+}
+
+/// Adds exhaustion to a player.
+///
+/// Has no effect on invulnerable players, e.g. those in creative mode.
+pub fn add_exhaustion(game: &Game, player: Entity, amount: f32) {
+    let invulnerable = game
+        .ecs
+        .get::<Invulnerable>(player)
+        .map(|invulnerable| invulnerable.0)
+        .unwrap_or(false);
+    if invulnerable {
+        return;
+    }
+    if let Ok(mut exhaustion) = game.ecs.get_mut::<Exhaustion>(player) {
+        exhaustion.0 = (exhaustion.0 + amount).min(MAX_EXHAUSTION);
+    }
+}
+
+/// Adds exhaustion for sprinting and jumping.
+fn exhaust_from_movement(game: &mut Game) -> SysResult {
+    let mut exhausted = Vec::new();
+    for (player, (_, &position, &on_ground, &sprinting, state)) in game
+        .ecs
+        .query::<(&Player, &Position, &OnGround, &Sprinting, &mut HungerState)>()
+        .iter()
+    {
+        if let Some(last_position) = state.last_position {
+            let mut amount = 0.0;
+            let jumped = state.was_on_ground && !on_ground.0 && position.y > last_position.y;
+            if jumped {
+                amount += if sprinting.0 {
+                    SPRINT_JUMP_EXHAUSTION
+                } else {
+                    JUMP_EXHAUSTION
+                };
+            }
+            if sprinting.0 {
+                let (dx, dz) = (position.x - last_position.x, position.z - last_position.z);
+                amount += SPRINT_EXHAUSTION_PER_BLOCK * (dx * dx + dz * dz).sqrt() as f32;
+            }
+            if amount > 0.0 {
+                exhausted.push((player, amount));
+            }
+        }
+        state.last_position = Some(position);
+        state.was_on_ground = on_ground.0;
+    }
+
+    for (player, amount) in exhausted {
+        add_exhaustion(game, player, amount);
+    }
+    Ok(())
+}
+
+fn exhaust_from_attacks(game: &mut Game) -> SysResult {
+    let attackers: Vec<Entity> = game
+        .ecs
+        .query::<(&Player, &InteractEntityEvent)>()
+        .iter()
+        .filter(|(_, (_, event))| matches!(event.ty, InteractionType::Attack))
+        .map(|(player, _)| player)
+        .collect();
+    for player in attackers {
+        add_exhaustion(game, player, ATTACK_EXHAUSTION);
+    }
+    Ok(())
+}
+
+/// Adds exhaustion for damage dealt by other entities.
+///
+/// Damage without an attacker, such as starvation, causes no exhaustion.
+fn exhaust_from_damage(game: &mut Game) -> SysResult {
+    let damaged: Vec<Entity> = game
+        .ecs
+        .query::<(&Player, &EntityDamageEvent)>()
+        .iter()
+        .filter(|(_, (_, event))| event.attacker.is_some())
+        .map(|(player, _)| player)
+        .collect();
+    for player in damaged {
+        add_exhaustion(game, player, DAMAGE_EXHAUSTION);
+    }
+    Ok(())
+}
+
+/// Gets the window index of the slot in `player`'s `hand`.
+/// Gets the window index of the slot a player holds in `hand`.
+pub(crate) fn hand_slot(game: &Game, player: Entity, hand: Hand) -> Option<usize> {
+    match hand {
+        Hand::Main => Some(SLOT_HOTBAR_OFFSET + game.ecs.get::<HotbarSlot>(player).ok()?.get()),
+        Hand::Offhand => Some(SLOT_OFFHAND),
+    }
+}
+
+fn item_in_hand(game: &Game, player: Entity, hand: Hand) -> Option<Item> {
+    let slot = hand_slot(game, player, hand)?;
+    let window = game.ecs.get::<Window>(player).ok()?;
+    let item = window.item(slot).ok()?.item_kind();
+    item
+}
+
+/// Starts eating when a player uses a food item
+/// and drinking when they use a potion.
+fn start_eating(game: &mut Game) -> SysResult {
+    let uses: Vec<(Entity, Hand)> = game
+        .ecs
+        .query::<(&Player, &UseItemEvent)>()
+        .iter()
+        .map(|(player, (_, event))| (player, event.hand))
+        .collect();
+
+    for (player, hand) in uses {
+        let item = match item_in_hand(game, player, hand) {
+            Some(item) => item,
+            None => continue,
+        };
+        let remaining_ticks = match item.food() {
+            Some(food) if can_eat(game, player, &food) => food.eat_ticks,
+            None if item == Item::Potion => POTION_DRINK_TICKS,
+            _ => continue,
+        };
+
+        game.ecs.insert(
+            player,
+            Eating {
+                hand,
+                item,
+                remaining_ticks,
+            },
+        )?;
+    }
+    Ok(())
+}
+
+fn can_eat(game: &Game, player: Entity, food: &FoodProperties) -> bool {
+    let hungry = game
+        .ecs
+        .get::<FoodLevel>(player)
+        .map(|food_level| food_level.0 < MAX_FOOD_LEVEL)
+        .unwrap_or(false);
+    let invulnerable = game
+        .ecs
+        .get::<Invulnerable>(player)
+        .map(|invulnerable| invulnerable.0)
+        .unwrap_or(false);
+    food.always_edible || hungry || invulnerable
+}
+
+/// Advances eating players and applies food once it is eaten.
+///
+/// Eating is cancelled if the player switches to a different item.
+fn tick_eating(game: &mut Game) -> SysResult {
+    let mut cancelled = Vec::new();
+    let mut finished = Vec::new();
+    for (player, eating) in game.ecs.query::<&mut Eating>().iter() {
+        if item_in_hand(game, player, eating.hand) != Some(eating.item) {
+            cancelled.push(player);
+            continue;
+        }
+        eating.remaining_ticks = eating.remaining_ticks.saturating_sub(1);
+        if eating.remaining_ticks == 0 {
+            finished.push((player, *eating));
+        }
+    }
+
+    for player in cancelled {
+        game.ecs.remove::<Eating>(player)?;
+    }
+    for (player, eating) in finished {
+        game.ecs.remove::<Eating>(player)?;
+        finish_eating(game, player, eating)?;
+    }
+    Ok(())
+}
+
+fn finish_eating(game: &mut Game, player: Entity, eating: Eating) -> SysResult {
+    let slot = match hand_slot(game, player, eating.hand) {
+        Some(slot) => slot,
+        None => return Ok(()),
+    };
+
+    let remainder = if eating.item == Item::Potion {
+        let potion = game
+            .ecs
+            .get::<Window>(player)?
+            .item(slot)?
+            .option_ref()
+            .and_then(|stack| stack.potion().map(str::to_owned));
+        if let Some(potion) = potion {
+            potions::drink_potion(game, player, &potion)?;
+        }
+        Some(Item::GlassBottle)
+    } else {
+        let food = match eating.item.food() {
+            Some(food) => food,
+            None => return Ok(()),
+        };
+
+        let food_level = {
+            let mut food_level = game.ecs.get_mut::<FoodLevel>(player)?;
+            food_level.0 = (food_level.0 + food.hunger).min(MAX_FOOD_LEVEL);
+            food_level.0
+        };
+        let mut saturation = game.ecs.get_mut::<Saturation>(player)?;
+        saturation.0 = (saturation.0 + food.saturation()).min(food_level as f32);
+        food.remainder
+    };
+    let instabuild = game
+        .ecs
+        .get::<Instabreak>(player)
+        .map(|instabreak| instabreak.0)
+        .unwrap_or(false);
+    if !instabuild {
+        let window = game.ecs.get::<Window>(player)?;
+        let mut item = window.item(slot)?;
+        let _eaten = item.try_take(1);
+        // The remainder only replaces the food if the whole stack
+        // was eaten; otherwise it is lost.
+        if let (InventorySlot::Empty, Some(remainder)) = (&*item, remainder) {
+            *item = InventorySlot::Filled(ItemStack::new(remainder, 1)?);
+        }
+    }
+
+    game.ecs.insert_entity_event(
+        player,
+        FoodEatenEvent {
+            item: eating.item,
+            slot,
+        },
+    )?;
+    Ok(())
+}
+
+/// Updates food levels and applies natural regeneration and starvation.
+fn tick_food(game: &mut Game) -> SysResult {
+    let difficulty = game.difficulty;
+    let natural_regeneration = game.game_rules.natural_regeneration;
+    let tick = game.tick_count;
+
+    let mut starving = Vec::new();
+    for (player, (_, health, food_level, saturation, exhaustion, state)) in game
+        .ecs
+        .query::<(
+            &Player,
+            &mut Health,
+            &mut FoodLevel,
+            &mut Saturation,
+            &mut Exhaustion,
+            &mut HungerState,
+        )>()
+        .iter()
+    {
+        let is_hurt = health.0 > 0.0 && health.0 < PLAYER_MAX_HEALTH;
+
+        if difficulty == Difficulty::Peaceful && natural_regeneration {
+            if is_hurt && tick.is_multiple_of(20) {
+                heal(health, 1.0);
+            }
+            if food_level.0 < MAX_FOOD_LEVEL && tick.is_multiple_of(10) {
+                food_level.0 += 1;
+            }
+        }
+
+        if exhaustion.0 > EXHAUSTION_PER_FOOD_POINT {
+            exhaustion.0 -= EXHAUSTION_PER_FOOD_POINT;
+            if saturation.0 > 0.0 {
+                saturation.0 = (saturation.0 - 1.0).max(0.0);
+            } else if difficulty != Difficulty::Peaceful {
+                food_level.0 = food_level.0.saturating_sub(1);
+            }
+        }
+
+        if natural_regeneration && saturation.0 > 0.0 && is_hurt && food_level.0 >= MAX_FOOD_LEVEL {
+            state.tick_timer += 1;
+            if state.tick_timer >= FAST_FOOD_TICK_INTERVAL {
+                let amount = saturation.0.min(REGENERATION_EXHAUSTION);
+                heal(health, amount / REGENERATION_EXHAUSTION);
+                exhaustion.0 = (exhaustion.0 + amount).min(MAX_EXHAUSTION);
+                state.tick_timer = 0;
+            }
+        } else if natural_regeneration && food_level.0 >= REGENERATION_FOOD_LEVEL && is_hurt {
+            state.tick_timer += 1;
+            if state.tick_timer >= SLOW_FOOD_TICK_INTERVAL {
+                heal(health, 1.0);
+                exhaustion.0 = (exhaustion.0 + REGENERATION_EXHAUSTION).min(MAX_EXHAUSTION);
+                state.tick_timer = 0;
+            }
+        } else if food_level.0 == 0 {
+            state.tick_timer += 1;
+            if state.tick_timer >= SLOW_FOOD_TICK_INTERVAL {
+                if starves(health.0, difficulty) {
+                    starving.push(player);
+                }
+                state.tick_timer = 0;
+            }
+        } else {
+            state.tick_timer = 0;
+        }
+    }
+
+    for player in starving {
+        game.damage_entity(player, 1.0, None)?;
+    }
+    Ok(())
+}
+
+fn heal(health: &mut Health, amount: f32) {
+    health.0 = (health.0 + amount).min(PLAYER_MAX_HEALTH);
+}
+
+/// Returns whether a starving player with the given health takes damage.
+///
+/// Starvation stops at 10 health on peaceful and easy
+/// and at 1 health on normal, but can kill on hard.
+fn starves(health: f32, difficulty: Difficulty) -> bool {
+    match difficulty {
+        Difficulty::Peaceful | Difficulty::Easy => health > 10.0,
+        Difficulty::Normal => health > 1.0,
+        Difficulty::Hard => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starvation_limits() {
+        assert!(!starves(10.0, Difficulty::Peaceful));
+        assert!(starves(11.0, Difficulty::Easy));
+        assert!(!starves(10.0, Difficulty::Easy));
+        assert!(starves(2.0, Difficulty::Normal));
+        assert!(!starves(1.0, Difficulty::Normal));
+        assert!(starves(1.0, Difficulty::Hard));
+    }
+}
@@ -12,6 +12,9 @@ pub use game::Game;
 mod tick_loop;
 pub use tick_loop::TickLoop;
 
+pub mod scheduler;
+pub mod timings;
+
 pub mod view;
 
 pub mod window;
@@ -19,26 +22,56 @@ pub use window::Window;
 
 pub mod events;
 
+pub mod autosave;
+
 pub mod chunk;
-mod region_worker;
 
 pub mod world;
 pub use world::World;
 
+pub mod world_border;
+
 pub mod chat;
 pub use chat::ChatBox;
 
+pub mod commands;
+pub mod permissions;
+
 pub mod entities;
 
+pub mod ai;
+pub mod beacon;
+pub mod damage;
+pub mod effects;
+pub mod enchanting;
+pub mod experience;
+pub mod hunger;
+pub mod spawning;
+
 pub mod block_break;
 pub mod interactable;
 
 /// Registers gameplay systems with the given `Game` and `SystemExecutor`.
 pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    scheduler::register(game, systems);
     view::register(game, systems);
     chunk::loading::register(game, systems);
+    chunk::pregen::register(systems);
     chunk::entities::register(systems);
+    chunk::inhabited::register(systems);
+    autosave::register(game, systems);
     interactable::register(game);
+    commands::register(game);
+    game.insert_resource(timings::TickTimings::new());
 
     game.add_entity_spawn_callback(entities::add_entity_components);
+    ai::register(game, systems);
+    spawning::register(systems);
+    hunger::register(game, systems);
+    damage::register(systems);
+    experience::register(game, systems);
+    enchanting::register(game, systems);
+    effects::register(game, systems);
+    beacon::register(game, systems);
+    world_border::register(game, systems);
 }
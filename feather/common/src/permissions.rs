@@ -0,0 +1,402 @@
+//! Permission nodes of players and groups.
+//!
+//! A permission node is a dot-separated string like `feather.command.ban`.
+//! Nodes ending in `*` grant every node starting with what comes before,
+//! so `feather.command.*` grants all commands and `*` grants everything.
+//!
+//! Every player is in the `default` group, along with the groups they
+//! are assigned to and the groups whose `op_level` their operator
+//! level reaches. Groups also include the nodes of the groups they inherit.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use ecs::Entity;
+use quill_common::entities::Player;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{commands::PermissionLevel, Game};
+
+/// The group every player is in.
+pub const DEFAULT_GROUP: &str = "default";
+
+/// Contents of a newly created permissions file.
+const DEFAULT_FILE: &str = r#"# Permissions of players and groups.
+# Nodes ending in "*" grant all nodes starting with what comes before.
+
+# Every player is in the default group.
+[groups.default]
+permissions = []
+
+# Players whose operator level is at least `op_level` are in this group.
+[groups.operators]
+op_level = 4
+permissions = ["*"]
+
+# Players are listed by UUID:
+# [players.069a79f4-44e9-4726-a5be-fca90e38aaf5]
+# name = "Notch"
+# groups = ["moderators"]
+# permissions = ["feather.command.ban"]
+"#;
+
+/// A group of permission nodes.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Group {
+    /// Groups whose nodes are included in this group.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inherits: Vec<String>,
+    /// Operator level from which players are in this group.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub op_level: Option<u8>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+/// The groups and permission nodes assigned to a player.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerPermissions {
+    /// The name of the player when last changed,
+    /// so that the file can be edited by hand.
+    pub name: String,
+    #[serde(default)]
+    pub groups: Vec<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct PermissionsFile {
+    #[serde(default)]
+    groups: BTreeMap<String, Group>,
+    #[serde(default)]
+    players: BTreeMap<Uuid, PlayerPermissions>,
+}
+
+/// The permissions of players and groups, stored as a resource.
+///
+/// Changes are written to the file the permissions
+/// were loaded from, if any.
+#[derive(Debug, Default)]
+pub struct Permissions {
+    path: Option<PathBuf>,
+    file: PermissionsFile,
+}
+
+impl Permissions {
+    /// Loads the permissions from a file,
+    /// creating a default file if it doesn't exist.
+    pub fn load(path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let mut permissions = Self {
+            path: Some(path.into()),
+            file: PermissionsFile::default(),
+        };
+        permissions.reload()?;
+        Ok(permissions)
+    }
+
+    /// Reloads the permissions from their file.
+    pub fn reload(&mut self) -> anyhow::Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if !path.exists() {
+            fs::write(path, DEFAULT_FILE)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+        }
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        self.file =
+            toml::from_str(&contents).with_context(|| format!("invalid {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Returns whether a player has a permission node,
+    /// given their operator level.
+    pub fn has_permission(&self, player: Uuid, op_level: u8, node: &str) -> bool {
+        let player = self.file.players.get(&player);
+        if player.is_some_and(|player| grants(&player.permissions, node)) {
+            return true;
+        }
+
+        let mut groups: Vec<&str> = vec![DEFAULT_GROUP];
+        if let Some(player) = player {
+            groups.extend(player.groups.iter().map(String::as_str));
+        }
+        groups.extend(
+            self.file
+                .groups
+                .iter()
+                .filter(|(_, group)| group.op_level.is_some_and(|level| op_level >= level))
+                .map(|(name, _)| name.as_str()),
+        );
+
+        // Groups may inherit each other in cycles.
+        let mut visited = BTreeSet::new();
+        while let Some(name) = groups.pop() {
+            if !visited.insert(name) {
+                continue;
+            }
+            if let Some(group) = self.file.groups.get(name) {
+                if grants(&group.permissions, node) {
+                    return true;
+                }
+                groups.extend(group.inherits.iter().map(String::as_str));
+            }
+        }
+        false
+    }
+
+    pub fn group(&self, name: &str) -> Option<&Group> {
+        self.file.groups.get(name)
+    }
+
+    pub fn player(&self, player: Uuid) -> Option<&PlayerPermissions> {
+        self.file.players.get(&player)
+    }
+
+    /// Finds a player in the permissions by name.
+    pub fn find_player(&self, name: &str) -> Option<(Uuid, &PlayerPermissions)> {
+        self.file
+            .players
+            .iter()
+            .find(|(_, player)| player.name.eq_ignore_ascii_case(name))
+            .map(|(&uuid, player)| (uuid, player))
+    }
+
+    /// Grants a node to a player. Returns `false` if they already had it.
+    pub fn add_player_permission(
+        &mut self,
+        player: Uuid,
+        name: &str,
+        node: &str,
+    ) -> anyhow::Result<bool> {
+        let added = insert(&mut self.player_entry(player, name).permissions, node);
+        self.save_if(added)
+    }
+
+    /// Removes a node from a player. Returns `false` if they didn't have it.
+    pub fn remove_player_permission(&mut self, player: Uuid, node: &str) -> anyhow::Result<bool> {
+        match self.file.players.get_mut(&player) {
+            Some(entry) => {
+                let removed = remove(&mut entry.permissions, node);
+                self.save_if(removed)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Adds a player to a group. Returns `false` if they already were in it.
+    pub fn add_player_group(
+        &mut self,
+        player: Uuid,
+        name: &str,
+        group: &str,
+    ) -> anyhow::Result<bool> {
+        let added = insert(&mut self.player_entry(player, name).groups, group);
+        self.save_if(added)
+    }
+
+    /// Removes a player from a group. Returns `false` if they weren't in it.
+    pub fn remove_player_group(&mut self, player: Uuid, group: &str) -> anyhow::Result<bool> {
+        match self.file.players.get_mut(&player) {
+            Some(entry) => {
+                let removed = remove(&mut entry.groups, group);
+                self.save_if(removed)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Grants a node to a group, creating the group if needed.
+    /// Returns `false` if the group already had the node.
+    pub fn add_group_permission(&mut self, group: &str, node: &str) -> anyhow::Result<bool> {
+        let group = self.file.groups.entry(group.to_owned()).or_default();
+        let added = insert(&mut group.permissions, node);
+        self.save_if(added)
+    }
+
+    /// Removes a node from a group. Returns `false` if the group didn't have it.
+    pub fn remove_group_permission(&mut self, group: &str, node: &str) -> anyhow::Result<bool> {
+        match self.file.groups.get_mut(group) {
+            Some(group) => {
+                let removed = remove(&mut group.permissions, node);
+                self.save_if(removed)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn player_entry(&mut self, player: Uuid, name: &str) -> &mut PlayerPermissions {
+        let entry = self.file.players.entry(player).or_default();
+        entry.name = name.to_owned();
+        entry
+    }
+
+    fn save_if(&self, changed: bool) -> anyhow::Result<bool> {
+        if changed {
+            self.save()?;
+        }
+        Ok(changed)
+    }
+
+    /// Writes the permissions to their file.
+    pub fn save(&self) -> anyhow::Result<()> {
+        if let Some(path) = &self.path {
+            write(path, &self.file)?;
+        }
+        Ok(())
+    }
+}
+
+fn write(path: &Path, file: &PermissionsFile) -> anyhow::Result<()> {
+    fs::write(path, toml::to_string_pretty(file)?)
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Returns whether any of `nodes` grants `node`.
+fn grants(nodes: &[String], node: &str) -> bool {
+    nodes.iter().any(|granted| match granted.strip_suffix('*') {
+        Some(prefix) => node.starts_with(prefix),
+        None => granted == node,
+    })
+}
+
+fn insert(values: &mut Vec<String>, value: &str) -> bool {
+    if values.iter().any(|v| v == value) {
+        return false;
+    }
+    values.push(value.to_owned());
+    true
+}
+
+fn remove(values: &mut Vec<String>, value: &str) -> bool {
+    let len = values.len();
+    values.retain(|v| v != value);
+    values.len() != len
+}
+
+/// Returns whether a player has a permission node. Entities
+/// which aren't players have no permissions.
+pub fn has_permission(game: &Game, player: Entity, node: &str) -> bool {
+    if game.ecs.get::<Player>(player).is_err() {
+        return false;
+    }
+    let uuid = match game.ecs.get::<Uuid>(player) {
+        Ok(uuid) => *uuid,
+        Err(_) => return false,
+    };
+    let op_level = game
+        .ecs
+        .get::<PermissionLevel>(player)
+        .map_or(0, |level| level.0);
+    game.resources
+        .get::<Permissions>()
+        .is_ok_and(|permissions| permissions.has_permission(uuid, op_level, node))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn permissions(toml: &str) -> Permissions {
+        Permissions {
+            path: None,
+            file: toml::from_str(toml).unwrap(),
+        }
+    }
+
+    #[test]
+    fn wildcards() {
+        let nodes = vec!["feather.command.*".to_owned(), "plugin.fly".to_owned()];
+        assert!(grants(&nodes, "feather.command.ban"));
+        assert!(grants(&nodes, "plugin.fly"));
+        assert!(!grants(&nodes, "plugin.fly.fast"));
+        assert!(!grants(&nodes, "feather.kick"));
+        assert!(grants(&["*".to_owned()], "anything"));
+    }
+
+    #[test]
+    fn groups() {
+        let player = Uuid::new_v4();
+        let permissions = permissions(&format!(
+            r#"
+            [groups.default]
+            permissions = ["chat"]
+            [groups.builders]
+            inherits = ["default", "builders"]
+            permissions = ["build.*"]
+            [groups.admins]
+            op_level = 3
+            permissions = ["*"]
+            [players.{}]
+            name = "Player"
+            groups = ["builders"]
+            permissions = ["fly"]
+            "#,
+            player
+        ));
+
+        let other = Uuid::new_v4();
+        assert!(permissions.has_permission(other, 0, "chat"));
+        assert!(!permissions.has_permission(other, 0, "fly"));
+        assert!(permissions.has_permission(other, 3, "fly"));
+
+        assert!(permissions.has_permission(player, 0, "fly"));
+        assert!(permissions.has_permission(player, 0, "build.place"));
+        assert!(permissions.has_permission(player, 0, "chat"));
+        assert!(!permissions.has_permission(player, 2, "ban"));
+    }
+
+    #[test]
+    fn changes_are_saved() {
+        let dir = std::env::temp_dir().join(format!("feather-permissions-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("permissions.toml");
+
+        let mut permissions = Permissions::load(&path).unwrap();
+        let player = Uuid::new_v4();
+        assert!(permissions
+            .add_player_permission(player, "Player", "fly")
+            .unwrap());
+        assert!(!permissions
+            .add_player_permission(player, "Player", "fly")
+            .unwrap());
+        assert!(permissions
+            .add_group_permission("builders", "build.*")
+            .unwrap());
+        assert!(permissions
+            .add_player_group(player, "Player", "builders")
+            .unwrap());
+
+        let loaded = Permissions::load(&path).unwrap();
+        assert_eq!(loaded.file, permissions.file);
+        assert_eq!(loaded.find_player("player").unwrap().0, player);
+        assert!(loaded.has_permission(player, 0, "build.break"));
+        assert!(loaded.has_permission(Uuid::new_v4(), 4, "anything"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn only_players_have_permissions() {
+        let mut game = Game::new();
+        game.insert_resource(permissions(
+            r#"
+            [groups.default]
+            permissions = ["*"]
+            "#,
+        ));
+
+        let player = game.ecs.spawn((Player, Uuid::new_v4()));
+        let mob = game.ecs.spawn((Uuid::new_v4(),));
+        assert!(has_permission(&game, player, "chat"));
+        assert!(!has_permission(&game, mob, "chat"));
+    }
+}
@@ -0,0 +1,407 @@
+//! Runs delayed and repeating tasks on the main thread, and jobs
+//! on worker threads whose results are handed back to the main thread.
+//!
+//! Tasks are scheduled through the [`Scheduler`] resource, and
+//! cancelled through the [`TaskHandle`] returned when scheduling them.
+
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use ahash::AHashMap;
+use ecs::{SysResult, SystemExecutor};
+use flume::{Receiver, Sender};
+
+use crate::Game;
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    game.insert_resource(Scheduler::new());
+    systems.add_system(run_scheduled_tasks);
+}
+
+/// Work which runs on the main thread once or repeatedly.
+///
+/// Implemented for closures taking a `&mut Game`.
+pub trait Task: 'static {
+    fn run(&mut self, game: &mut Game) -> SysResult;
+
+    /// Called once the task won't run again, either
+    /// because it completed or because it was cancelled.
+    fn finish(&mut self, _game: &mut Game) {}
+}
+
+impl<F> Task for F
+where
+    F: FnMut(&mut Game) -> SysResult + 'static,
+{
+    fn run(&mut self, game: &mut Game) -> SysResult {
+        self(game)
+    }
+}
+
+/// Adapts a `FnOnce` to [`Task`] for one-shot tasks.
+struct OnceTask<F>(Option<F>);
+
+impl<F> Task for OnceTask<F>
+where
+    F: FnOnce(&mut Game) -> SysResult + 'static,
+{
+    fn run(&mut self, game: &mut Game) -> SysResult {
+        match self.0.take() {
+            Some(task) => task(game),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A handle to a scheduled task or job, used to cancel it.
+///
+/// Handles can be cloned and sent to other threads.
+#[derive(Clone, Debug)]
+pub struct TaskHandle {
+    id: u64,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl TaskHandle {
+    fn new(id: u64) -> Self {
+        Self {
+            id,
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Gets the task's ID, unique for the lifetime of the server.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Cancels the task so it won't run again. Cancelling a job
+    /// doesn't stop it, but its result is discarded.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns whether the task was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+struct ScheduledTask {
+    handle: TaskHandle,
+    /// Ticks between runs, or `None` for one-shot tasks.
+    period: Option<u64>,
+    task: Box<dyn Task>,
+}
+
+/// A job's callback and result, ready to run on the main thread.
+type CompletedJob = (TaskHandle, Box<dyn FnOnce(&mut Game) -> SysResult + Send>);
+
+/// Resource scheduling tasks a number of ticks in the future.
+///
+/// Delays and periods are counted in ticks of the scheduler, which
+/// runs its tasks once per game tick. Tasks scheduled for the same tick
+/// run in the order they were scheduled.
+pub struct Scheduler {
+    tick: u64,
+    next_id: u64,
+    /// Pending tasks, keyed by the tick they're due and their ID.
+    tasks: BTreeMap<(u64, u64), ScheduledTask>,
+    /// Handles of pending tasks and running jobs.
+    handles: AHashMap<u64, TaskHandle>,
+
+    send_completed: Sender<CompletedJob>,
+    recv_completed: Receiver<CompletedJob>,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        let (send_completed, recv_completed) = flume::unbounded();
+        Self {
+            tick: 0,
+            next_id: 0,
+            tasks: BTreeMap::new(),
+            handles: AHashMap::new(),
+            send_completed,
+            recv_completed,
+        }
+    }
+
+    /// Runs `task` once after `delay` ticks.
+    ///
+    /// A delay of zero runs the task on the next tick.
+    pub fn schedule_once(
+        &mut self,
+        delay: u64,
+        task: impl FnOnce(&mut Game) -> SysResult + 'static,
+    ) -> TaskHandle {
+        self.schedule(delay, None, OnceTask(Some(task)))
+    }
+
+    /// Runs `task` after `delay` ticks, then every `period` ticks
+    /// until it's cancelled.
+    ///
+    /// # Panics
+    /// Panics if `period` is zero.
+    pub fn schedule_repeating(
+        &mut self,
+        delay: u64,
+        period: u64,
+        task: impl FnMut(&mut Game) -> SysResult + 'static,
+    ) -> TaskHandle {
+        self.schedule(delay, Some(period), task)
+    }
+
+    /// Schedules a [`Task`] to run after `delay` ticks, and then every
+    /// `period` ticks if a period is given.
+    ///
+    /// # Panics
+    /// Panics if `period` is zero.
+    pub fn schedule(&mut self, delay: u64, period: Option<u64>, task: impl Task) -> TaskHandle {
+        assert_ne!(period, Some(0), "task period must be at least one tick");
+
+        let handle = self.new_handle();
+        let task = ScheduledTask {
+            handle: handle.clone(),
+            period,
+            task: Box::new(task),
+        };
+        self.insert_task(self.tick + delay.max(1), task);
+        handle
+    }
+
+    /// Runs `job` on a worker thread, then calls `then` with its
+    /// result on the main thread.
+    ///
+    /// Use this for blocking work like file IO or requests to other
+    /// services, which would otherwise stall the tick.
+    /// `then` runs during the first tick after `job` completes.
+    pub fn spawn_job<T>(
+        &mut self,
+        job: impl FnOnce() -> T + Send + 'static,
+        then: impl FnOnce(&mut Game, T) -> SysResult + Send + 'static,
+    ) -> TaskHandle
+    where
+        T: Send + 'static,
+    {
+        let handle = self.new_handle();
+        let sender = self.send_completed.clone();
+        let job_handle = handle.clone();
+        rayon::spawn(move || {
+            let result = job();
+            // The scheduler may have been dropped while the job ran.
+            let _ = sender.send((job_handle, Box::new(move |game| then(game, result))));
+        });
+        handle
+    }
+
+    /// Gets the handle of a pending task or running job.
+    pub fn handle(&self, id: u64) -> Option<TaskHandle> {
+        self.handles.get(&id).cloned()
+    }
+
+    /// Gets the number of pending tasks and running jobs.
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    /// Returns whether no tasks are pending and no jobs are running.
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+
+    fn new_handle(&mut self) -> TaskHandle {
+        let handle = TaskHandle::new(self.next_id);
+        self.next_id += 1;
+        self.handles.insert(handle.id, handle.clone());
+        handle
+    }
+
+    fn insert_task(&mut self, due: u64, task: ScheduledTask) {
+        self.tasks.insert((due, task.handle.id), task);
+    }
+
+    /// Advances to the next tick, returning the tasks due
+    /// in that tick and the jobs which completed.
+    fn advance(&mut self) -> (Vec<ScheduledTask>, Vec<CompletedJob>) {
+        self.tick += 1;
+        let pending = self.tasks.split_off(&(self.tick + 1, 0));
+        let due = std::mem::replace(&mut self.tasks, pending)
+            .into_values()
+            .collect();
+        let completed = self.recv_completed.try_iter().collect();
+        (due, completed)
+    }
+
+    /// Reschedules a repeating task after it ran, or forgets
+    /// the task if it won't run again. Returns the task if it
+    /// should be finished.
+    fn reschedule(&mut self, task: ScheduledTask) -> Option<ScheduledTask> {
+        match task.period {
+            Some(period) if !task.handle.is_cancelled() => {
+                self.insert_task(self.tick + period, task);
+                None
+            }
+            _ => {
+                self.handles.remove(&task.handle.id);
+                Some(task)
+            }
+        }
+    }
+}
+
+/// Runs the tasks due this tick and the callbacks of completed jobs.
+///
+/// The scheduler isn't borrowed while tasks run, so
+/// tasks may schedule or cancel other tasks.
+fn run_scheduled_tasks(game: &mut Game) -> SysResult {
+    let (due, completed) = game.resources.get_mut::<Scheduler>()?.advance();
+
+    for mut task in due {
+        if !task.handle.is_cancelled() {
+            if let Err(e) = task.task.run(game) {
+                log::error!("Scheduled task {} failed: {:?}", task.handle.id, e);
+            }
+        }
+
+        let finished = game.resources.get_mut::<Scheduler>()?.reschedule(task);
+        if let Some(mut task) = finished {
+            task.task.finish(game);
+        }
+    }
+
+    for (handle, then) in completed {
+        game.resources
+            .get_mut::<Scheduler>()?
+            .handles
+            .remove(&handle.id);
+        if handle.is_cancelled() {
+            continue;
+        }
+        if let Err(e) = then(game) {
+            log::error!("Callback of job {} failed: {:?}", handle.id, e);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::{RefCell, RefMut},
+        rc::Rc,
+        time::Duration,
+    };
+
+    use super::*;
+
+    fn game_with_scheduler() -> Game {
+        let mut game = Game::new();
+        game.insert_resource(Scheduler::new());
+        game
+    }
+
+    fn scheduler(game: &Game) -> RefMut<'_, Scheduler> {
+        game.resources.get_mut::<Scheduler>().unwrap()
+    }
+
+    #[test]
+    fn once_runs_after_delay() {
+        let mut game = game_with_scheduler();
+        let ran = Rc::new(RefCell::new(Vec::new()));
+        let ran2 = Rc::clone(&ran);
+        scheduler(&game).schedule_once(3, move |game| {
+            ran2.borrow_mut().push(game.tick_count);
+            Ok(())
+        });
+
+        for tick in 1..=5 {
+            game.tick_count = tick;
+            run_scheduled_tasks(&mut game).unwrap();
+        }
+        assert_eq!(*ran.borrow(), vec![3]);
+        assert!(scheduler(&game).is_empty());
+    }
+
+    #[test]
+    fn repeating_runs_every_period_until_cancelled() {
+        let mut game = game_with_scheduler();
+        let ran = Rc::new(RefCell::new(Vec::new()));
+        let ran2 = Rc::clone(&ran);
+        let handle = scheduler(&game).schedule_repeating(2, 3, move |game| {
+            ran2.borrow_mut().push(game.tick_count);
+            Ok(())
+        });
+
+        for tick in 1..=9 {
+            game.tick_count = tick;
+            run_scheduled_tasks(&mut game).unwrap();
+        }
+        handle.cancel();
+        for tick in 10..=20 {
+            game.tick_count = tick;
+            run_scheduled_tasks(&mut game).unwrap();
+        }
+
+        assert_eq!(*ran.borrow(), vec![2, 5, 8]);
+        assert!(scheduler(&game).handle(handle.id()).is_none());
+    }
+
+    #[test]
+    fn tasks_can_schedule_tasks() {
+        let mut game = game_with_scheduler();
+        let ran = Rc::new(RefCell::new(false));
+        let ran2 = Rc::clone(&ran);
+        scheduler(&game).schedule_once(0, move |game| {
+            game.resources
+                .get_mut::<Scheduler>()?
+                .schedule_once(0, move |_| {
+                    *ran2.borrow_mut() = true;
+                    Ok(())
+                });
+            Ok(())
+        });
+
+        run_scheduled_tasks(&mut game).unwrap();
+        assert!(!*ran.borrow());
+        run_scheduled_tasks(&mut game).unwrap();
+        assert!(*ran.borrow());
+    }
+
+    #[test]
+    fn job_result_handed_to_main_thread() {
+        let mut game = game_with_scheduler();
+        let main_thread = std::thread::current().id();
+        let job_handle = scheduler(&game).spawn_job(
+            || std::thread::current().id(),
+            move |game, job_thread| {
+                assert_ne!(job_thread, main_thread);
+                assert_eq!(std::thread::current().id(), main_thread);
+                game.tick_count = u64::MAX;
+                Ok(())
+            },
+        );
+
+        for _ in 0..500 {
+            run_scheduled_tasks(&mut game).unwrap();
+            if game.tick_count == u64::MAX {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(game.tick_count, u64::MAX);
+        assert!(scheduler(&game).handle(job_handle.id()).is_none());
+    }
+}
@@ -0,0 +1,522 @@
+//! Natural mob spawning and despawning, following vanilla's rules.
+//!
+//! Each tick, mobs spawn in packs in chunks near players as long as
+//! their category's mob cap has not been reached. Spawn lists come from
+//! the biome via [`Biome::spawn_entries`]. Newly generated chunks
+//! additionally receive an initial population of passive mobs.
+
+use std::convert::TryFrom;
+
+use ahash::{AHashMap, AHashSet};
+use base::{
+    Biome, BlockKind, BlockPosition, ChunkPosition, Difficulty, EntityKind, Gamemode, Position,
+    ValidBlockPosition, CHUNK_WIDTH,
+};
+use blocks::BlockId;
+use ecs::{SysResult, SystemExecutor};
+use libcraft_core::{MobCategory, SpawnEntry};
+use quill_common::{entities::Player, entity_init::EntityInit};
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{events::ChunkLoadEvent, Game};
+
+/// Chunks within this many chunks of a player are eligible for spawning.
+const SPAWN_CHUNK_RADIUS: i32 = 8;
+
+/// The number of chunks one player's mob caps account for.
+const CHUNKS_PER_CAP: usize = 17 * 17;
+
+/// Mobs never spawn closer than this to a player.
+const MIN_PLAYER_DISTANCE: f64 = 24.0;
+
+/// Mobs farther than this from every player despawn immediately.
+const DESPAWN_DISTANCE: f64 = 128.0;
+
+/// Mobs farther than this from every player despawn at random.
+const RANDOM_DESPAWN_DISTANCE: f64 = 32.0;
+
+/// Passive mobs only attempt to spawn once per this many ticks.
+const CREATURE_SPAWN_INTERVAL: u64 = 400;
+
+/// Maximum number of mobs spawned in one chunk per attempt.
+const MAX_PACK_SIZE: usize = 4;
+
+/// Mobs below this height count as underground for ambient and water spawns.
+const SEA_LEVEL: i32 = 63;
+
+/// Marker component for mobs which were spawned naturally.
+///
+/// Such mobs despawn when far from players unless their
+/// category is persistent.
+#[derive(Copy, Clone, Debug)]
+pub struct NaturallySpawned;
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .add_system(populate_generated_chunks)
+        .add_system(spawn_mobs)
+        .add_system(despawn_mobs);
+}
+
+/// Positions of players which cause mobs to spawn.
+fn spawning_players(game: &Game) -> Vec<Position> {
+    game.ecs
+        .query::<(&Player, &Position, &Gamemode)>()
+        .iter()
+        .filter(|(_, (_, _, &gamemode))| gamemode != Gamemode::Spectator)
+        .map(|(_, (_, &position, _))| position)
+        .collect()
+}
+
+fn nearest_player_distance_squared(players: &[Position], position: Position) -> Option<f64> {
+    players
+        .iter()
+        .map(|player| player.distance_squared_to(position))
+        .min_by(|a, b| a.partial_cmp(b).unwrap())
+}
+
+fn spawn_mobs(game: &mut Game) -> SysResult {
+    if !game.game_rules.do_mob_spawning {
+        return Ok(());
+    }
+
+    let players = spawning_players(game);
+    if players.is_empty() {
+        return Ok(());
+    }
+
+    let mut chunks: Vec<ChunkPosition> = players
+        .iter()
+        .flat_map(|player| {
+            let center = player.chunk();
+            (-SPAWN_CHUNK_RADIUS..=SPAWN_CHUNK_RADIUS).flat_map(move |dx| {
+                (-SPAWN_CHUNK_RADIUS..=SPAWN_CHUNK_RADIUS)
+                    .map(move |dz| ChunkPosition::new(center.x + dx, center.z + dz))
+            })
+        })
+        .filter(|&chunk| game.world.is_chunk_loaded(chunk))
+        .collect::<AHashSet<_>>()
+        .into_iter()
+        .collect();
+
+    let mut counts = count_mobs(game, &chunks);
+    let mut rng = rand::thread_rng();
+    chunks.shuffle(&mut rng);
+
+    for &category in &MobCategory::ALL {
+        if category == MobCategory::Creature
+            && !game.tick_count.is_multiple_of(CREATURE_SPAWN_INTERVAL)
+        {
+            continue;
+        }
+        if category == MobCategory::Monster && game.difficulty == Difficulty::Peaceful {
+            continue;
+        }
+
+        let cap = category.cap() * chunks.len() / CHUNKS_PER_CAP;
+        let count = counts.entry(category).or_insert(0);
+        for &chunk in &chunks {
+            if *count >= cap {
+                break;
+            }
+            *count += spawn_pack(game, &players, chunk, category, &mut rng);
+        }
+    }
+
+    Ok(())
+}
+
+/// Counts the mobs in each category within the given chunks.
+fn count_mobs(game: &Game, chunks: &[ChunkPosition]) -> AHashMap<MobCategory, usize> {
+    let mut counts = AHashMap::new();
+    for &chunk in chunks {
+        for &entity in game.chunk_entities.entities_in_chunk(chunk) {
+            let category = game
+                .ecs
+                .get::<EntityKind>(entity)
+                .ok()
+                .and_then(|kind| MobCategory::of(*kind));
+            if let Some(category) = category {
+                *counts.entry(category).or_insert(0) += 1;
+            }
+        }
+    }
+    counts
+}
+
+/// Attempts to spawn packs of mobs at a random position
+/// in `chunk`. Returns the number of mobs spawned.
+fn spawn_pack(
+    game: &mut Game,
+    players: &[Position],
+    chunk: ChunkPosition,
+    category: MobCategory,
+    rng: &mut impl Rng,
+) -> usize {
+    let x = chunk.x * CHUNK_WIDTH as i32 + rng.gen_range(0..CHUNK_WIDTH as i32);
+    let z = chunk.z * CHUNK_WIDTH as i32 + rng.gen_range(0..CHUNK_WIDTH as i32);
+    let height = match surface_height(game, x, z) {
+        Some(height) => height,
+        None => return 0,
+    };
+    let y = rng.gen_range(0..=height + 1);
+    match block_at(game, BlockPosition::new(x, y, z)) {
+        Some(block) if !block.is_solid() => {}
+        _ => return 0,
+    }
+
+    let mut spawned = 0;
+    for _ in 0..3 {
+        let (mut x, mut z) = (x, z);
+        let mut entry: Option<SpawnEntry> = None;
+        let attempts = rng.gen_range(1..=4);
+        for _ in 0..attempts {
+            x += rng.gen_range(0..6) - rng.gen_range(0..6);
+            z += rng.gen_range(0..6) - rng.gen_range(0..6);
+            let block = BlockPosition::new(x, y, z);
+            let position = Position {
+                x: x as f64 + 0.5,
+                y: y as f64,
+                z: z as f64 + 0.5,
+                pitch: 0.0,
+                yaw: rng.gen_range(0.0..360.0),
+            };
+
+            let allowed_distance =
+                MIN_PLAYER_DISTANCE * MIN_PLAYER_DISTANCE..=DESPAWN_DISTANCE * DESPAWN_DISTANCE;
+            match nearest_player_distance_squared(players, position) {
+                Some(distance) if allowed_distance.contains(&distance) => {}
+                _ => continue,
+            }
+
+            if entry.is_none() {
+                entry = biome_at(game, block)
+                    .and_then(|biome| choose_entry(biome.spawn_entries(category), rng));
+            }
+            let entry = match entry {
+                Some(entry) => entry,
+                None => break,
+            };
+
+            if can_spawn_at(game, category, entry.kind, block, rng) {
+                spawn_mob(game, entry.kind, position);
+                spawned += 1;
+                if spawned >= MAX_PACK_SIZE {
+                    return spawned;
+                }
+            }
+        }
+    }
+    spawned
+}
+
+/// Picks a random entry from a weighted spawn list.
+fn choose_entry(entries: &[SpawnEntry], rng: &mut impl Rng) -> Option<SpawnEntry> {
+    entries
+        .choose_weighted(rng, |entry| entry.weight)
+        .ok()
+        .copied()
+}
+
+fn spawn_mob(game: &mut Game, kind: EntityKind, position: Position) {
+    let mut builder = game.create_entity_builder(position, EntityInit::from(kind));
+    builder.add(NaturallySpawned);
+    game.spawn_entity(builder);
+}
+
+fn block_at(game: &Game, pos: BlockPosition) -> Option<BlockId> {
+    game.block(ValidBlockPosition::try_from(pos).ok()?)
+}
+
+fn biome_at(game: &Game, pos: BlockPosition) -> Option<Biome> {
+    let pos = ValidBlockPosition::try_from(pos).ok()?;
+    let chunk = game.world.chunk_map().chunk_at(pos.chunk())?;
+    Some(chunk.biomes().get_at_block(
+        pos.x().rem_euclid(CHUNK_WIDTH as i32) as usize,
+        pos.y() as usize,
+        pos.z().rem_euclid(CHUNK_WIDTH as i32) as usize,
+    ))
+}
+
+/// Gets the light level at a position: the greater of sky and block light.
+fn light_at(game: &Game, pos: BlockPosition) -> Option<u8> {
+    let pos = ValidBlockPosition::try_from(pos).ok()?;
+    let chunk = game.world.chunk_map().chunk_at(pos.chunk())?;
+    let (x, y, z) = (
+        pos.x().rem_euclid(CHUNK_WIDTH as i32) as usize,
+        pos.y() as usize,
+        pos.z().rem_euclid(CHUNK_WIDTH as i32) as usize,
+    );
+    Some(
+        chunk
+            .sky_light_at(x, y, z)?
+            .max(chunk.block_light_at(x, y, z)?),
+    )
+}
+
+fn sky_light_at(game: &Game, pos: BlockPosition) -> Option<u8> {
+    let pos = ValidBlockPosition::try_from(pos).ok()?;
+    let chunk = game.world.chunk_map().chunk_at(pos.chunk())?;
+    chunk.sky_light_at(
+        pos.x().rem_euclid(CHUNK_WIDTH as i32) as usize,
+        pos.y() as usize,
+        pos.z().rem_euclid(CHUNK_WIDTH as i32) as usize,
+    )
+}
+
+/// Gets the height of the highest non-air block in a column.
+fn surface_height(game: &Game, x: i32, z: i32) -> Option<i32> {
+    let chunk_pos = BlockPosition::new(x, 0, z).chunk();
+    let chunk = game.world.chunk_map().chunk_at(chunk_pos)?;
+    let (x, z) = (
+        x.rem_euclid(CHUNK_WIDTH as i32) as usize,
+        z.rem_euclid(CHUNK_WIDTH as i32) as usize,
+    );
+
+    match chunk.heightmaps().world_surface.height(x, z) {
+        Some(height) if height > 0 => Some(height as i32),
+        // Heightmaps may be missing for chunks loaded from
+        // older saves; fall back to scanning the column.
+        _ => Some(
+            (0..base::CHUNK_HEIGHT)
+                .rev()
+                .find(|&y| matches!(chunk.block_at(x, y, z), Some(block) if !block.is_air()))
+                .map_or(0, |y| y as i32 + 1),
+        ),
+    }
+}
+
+/// Checks the placement, light and height
+/// rules for spawning a mob at `pos`.
+fn can_spawn_at(
+    game: &Game,
+    category: MobCategory,
+    kind: EntityKind,
+    pos: BlockPosition,
+    rng: &mut impl Rng,
+) -> bool {
+    let (feet, head, below) = match (
+        block_at(game, pos),
+        block_at(game, pos.up()),
+        block_at(game, pos.down()),
+    ) {
+        (Some(feet), Some(head), Some(below)) => (feet, head, below),
+        _ => return false,
+    };
+
+    if category == MobCategory::WaterCreature {
+        return feet.kind() == BlockKind::Water
+            && !head.is_solid()
+            && pos.y > SEA_LEVEL - 18
+            && pos.y < SEA_LEVEL;
+    }
+
+    let is_clear = |block: BlockId| !block.is_solid() && !block.is_fluid();
+    let on_ground = below.is_solid() || spawns_on_lava(kind);
+    if !is_clear(feet) || !is_clear(head) || !on_ground {
+        return false;
+    }
+
+    let light = light_at(game, pos).unwrap_or(0);
+    match category {
+        MobCategory::Monster => {
+            let sky_light = sky_light_at(game, pos).unwrap_or(0);
+            below.kind() != BlockKind::Bedrock
+                && sky_light as u32 <= rng.gen_range(0..32)
+                && light as u32 <= rng.gen_range(0..8)
+        }
+        MobCategory::Creature => {
+            is_spawnable_ground(kind, below.kind()) && (light > 8 || spawns_on_lava(kind))
+        }
+        MobCategory::Ambient => pos.y < SEA_LEVEL && light as u32 <= rng.gen_range(0..4),
+        MobCategory::WaterCreature => unreachable!(),
+    }
+}
+
+/// Returns whether passive mobs of kind `kind` spawn on blocks of kind `ground`.
+fn is_spawnable_ground(kind: EntityKind, ground: BlockKind) -> bool {
+    match kind {
+        EntityKind::Mooshroom => ground == BlockKind::Mycelium,
+        EntityKind::Turtle => ground == BlockKind::Sand,
+        EntityKind::Rabbit | EntityKind::PolarBear => matches!(
+            ground,
+            BlockKind::GrassBlock | BlockKind::Sand | BlockKind::SnowBlock | BlockKind::Ice
+        ),
+        EntityKind::Strider => ground == BlockKind::Lava,
+        _ => ground == BlockKind::GrassBlock,
+    }
+}
+
+/// Returns whether mobs of kind `kind` spawn on top of lava, which
+/// isn't solid, regardless of the light level.
+fn spawns_on_lava(kind: EntityKind) -> bool {
+    kind == EntityKind::Strider
+}
+
+/// Gives newly generated chunks their initial population of passive mobs.
+fn populate_generated_chunks(game: &mut Game) -> SysResult {
+    let chunks: Vec<ChunkPosition> = game
+        .ecs
+        .query::<&ChunkLoadEvent>()
+        .iter()
+        .filter(|(_, event)| event.generated)
+        .map(|(_, event)| event.position)
+        .collect();
+
+    let mut rng = rand::thread_rng();
+    for chunk in chunks {
+        populate_chunk(game, chunk, &mut rng);
+    }
+    Ok(())
+}
+
+fn populate_chunk(game: &mut Game, chunk: ChunkPosition, rng: &mut impl Rng) {
+    let origin_x = chunk.x * CHUNK_WIDTH as i32;
+    let origin_z = chunk.z * CHUNK_WIDTH as i32;
+    let center = BlockPosition::new(origin_x + 8, 0, origin_z + 8);
+    let biome = match biome_at(game, center) {
+        Some(biome) => biome,
+        None => return,
+    };
+    let entries = biome.spawn_entries(MobCategory::Creature);
+
+    while rng.gen::<f32>() < biome.creature_spawn_probability() {
+        let entry = match choose_entry(entries, rng) {
+            Some(entry) => entry,
+            None => return,
+        };
+        let group_size = rng.gen_range(entry.min_group_size..=entry.max_group_size);
+        let mut x = origin_x + rng.gen_range(0..CHUNK_WIDTH as i32);
+        let mut z = origin_z + rng.gen_range(0..CHUNK_WIDTH as i32);
+
+        for _ in 0..group_size {
+            for _ in 0..4 {
+                let y = match surface_height(game, x, z) {
+                    Some(y) => y,
+                    None => break,
+                };
+                let pos = BlockPosition::new(x, y, z);
+                if can_populate_at(game, entry.kind, pos) {
+                    let position = Position {
+                        x: x as f64 + 0.5,
+                        y: y as f64,
+                        z: z as f64 + 0.5,
+                        pitch: 0.0,
+                        yaw: rng.gen_range(0.0..360.0),
+                    };
+                    spawn_mob(game, entry.kind, position);
+                    break;
+                }
+
+                x = (x + rng.gen_range(0..5) - rng.gen_range(0..5))
+                    .clamp(origin_x, origin_x + CHUNK_WIDTH as i32 - 1);
+                z = (z + rng.gen_range(0..5) - rng.gen_range(0..5))
+                    .clamp(origin_z, origin_z + CHUNK_WIDTH as i32 - 1);
+            }
+        }
+    }
+}
+
+/// Placement check for initial population, which ignores light levels.
+fn can_populate_at(game: &Game, kind: EntityKind, pos: BlockPosition) -> bool {
+    match (
+        block_at(game, pos),
+        block_at(game, pos.up()),
+        block_at(game, pos.down()),
+    ) {
+        (Some(feet), Some(head), Some(below)) => {
+            !feet.is_solid()
+                && !feet.is_fluid()
+                && !head.is_solid()
+                && is_spawnable_ground(kind, below.kind())
+        }
+        _ => false,
+    }
+}
+
+/// Despawns naturally spawned mobs which are far from all players.
+fn despawn_mobs(game: &mut Game) -> SysResult {
+    let players = spawning_players(game);
+    if players.is_empty() {
+        return Ok(());
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut despawned = Vec::new();
+    for (entity, (_, &kind, &position)) in game
+        .ecs
+        .query::<(&NaturallySpawned, &EntityKind, &Position)>()
+        .iter()
+    {
+        match MobCategory::of(kind) {
+            Some(category) if !category.is_persistent() => {}
+            _ => continue,
+        }
+        let distance = match nearest_player_distance_squared(&players, position) {
+            Some(distance) => distance,
+            None => continue,
+        };
+        if distance > DESPAWN_DISTANCE * DESPAWN_DISTANCE
+            || (distance > RANDOM_DESPAWN_DISTANCE * RANDOM_DESPAWN_DISTANCE
+                && rng.gen_ratio(1, 800))
+        {
+            despawned.push(entity);
+        }
+    }
+
+    for entity in despawned {
+        game.remove_entity(entity)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use base::{Chunk, ChunkPosition};
+
+    use super::*;
+
+    fn game_with_ground(ground: BlockId) -> Game {
+        let mut game = Game::new();
+        game.world
+            .chunk_map_mut()
+            .insert_chunk(Chunk::new(ChunkPosition::new(0, 0)));
+        let pos = ValidBlockPosition::try_from(BlockPosition::new(4, 30, 4)).unwrap();
+        assert!(game.world.set_block_at(pos, ground));
+        game
+    }
+
+    #[test]
+    fn striders_spawn_on_lava() {
+        let game = game_with_ground(BlockId::lava());
+        let pos = BlockPosition::new(4, 31, 4);
+        let mut rng = rand::thread_rng();
+        assert!(can_spawn_at(
+            &game,
+            MobCategory::Creature,
+            EntityKind::Strider,
+            pos,
+            &mut rng
+        ));
+        assert!(!can_spawn_at(
+            &game,
+            MobCategory::Creature,
+            EntityKind::Pig,
+            pos,
+            &mut rng
+        ));
+    }
+
+    #[test]
+    fn striders_need_lava() {
+        let game = game_with_ground(BlockId::grass_block());
+        let mut rng = rand::thread_rng();
+        assert!(!can_spawn_at(
+            &game,
+            MobCategory::Creature,
+            EntityKind::Strider,
+            BlockPosition::new(4, 31, 4),
+            &mut rng
+        ));
+    }
+}
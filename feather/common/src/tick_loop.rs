@@ -1,4 +1,3 @@
-use std::backtrace::Backtrace;
 use std::time::Instant;
 
 use base::TICK_DURATION;
@@ -28,13 +27,10 @@ impl TickLoop {
                 return;
             }
 
+            // Ticks taking too long are reported by the callback,
+            // which knows what they spent their time on.
             let elapsed = start.elapsed();
-            if elapsed > TICK_DURATION {
-                let bt = Backtrace::force_capture();
-
-                log::warn!("Tick took too long ({:?})", elapsed);
-                log::trace!("backtrace: {:?}", bt);
-            } else {
+            if elapsed < TICK_DURATION {
                 std::thread::sleep(TICK_DURATION - elapsed);
             }
         }
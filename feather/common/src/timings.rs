@@ -0,0 +1,237 @@
+//! Timings of ticks and systems, stored in the [`TickTimings`] resource.
+//!
+//! Ticks per second (TPS) and milliseconds per tick (MSPT) are averaged
+//! over the last 5 seconds, minute and 15 minutes.
+
+use std::{
+    cmp::Reverse,
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use base::TPS;
+use ecs::SystemTiming;
+
+/// A period over which tick timings are averaged.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TimingWindow {
+    FiveSeconds,
+    OneMinute,
+    FifteenMinutes,
+}
+
+impl TimingWindow {
+    pub const ALL: [TimingWindow; 3] = [
+        TimingWindow::FiveSeconds,
+        TimingWindow::OneMinute,
+        TimingWindow::FifteenMinutes,
+    ];
+
+    pub fn duration(self) -> Duration {
+        match self {
+            TimingWindow::FiveSeconds => Duration::from_secs(5),
+            TimingWindow::OneMinute => Duration::from_secs(60),
+            TimingWindow::FifteenMinutes => Duration::from_secs(15 * 60),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TimingWindow::FiveSeconds => "5s",
+            TimingWindow::OneMinute => "1m",
+            TimingWindow::FifteenMinutes => "15m",
+        }
+    }
+}
+
+/// Number of ticks the average duration of systems is taken over.
+const SYSTEM_AVERAGE_TICKS: f64 = 5.0 * TPS as f64;
+
+/// Timings of a system.
+#[derive(Clone, Debug)]
+pub struct SystemStats {
+    pub name: String,
+    /// The plugin which registered the system, if any.
+    pub plugin: Option<String>,
+    /// Duration of the last run.
+    pub last: Duration,
+    /// Moving average of the duration over about 5 seconds.
+    pub average: Duration,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Tick {
+    end: Instant,
+    duration: Duration,
+}
+
+/// Timings of recent ticks and of each system, stored as a resource.
+///
+/// Updated by the tick loop after each tick.
+#[derive(Debug, Default)]
+pub struct TickTimings {
+    /// Ticks ending within the longest window.
+    ticks: VecDeque<Tick>,
+    /// When the first tick started.
+    start: Option<Instant>,
+    systems: Vec<SystemStats>,
+}
+
+impl TickTimings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a tick which ended at `end` and took `duration`,
+    /// along with the timings of the systems it ran.
+    pub fn record_tick<'a>(
+        &mut self,
+        end: Instant,
+        duration: Duration,
+        systems: impl IntoIterator<Item = SystemTiming<'a>>,
+    ) {
+        self.start.get_or_insert(end - duration);
+        self.ticks.push_back(Tick { end, duration });
+        let longest = TimingWindow::FifteenMinutes.duration();
+        while let Some(tick) = self.ticks.front() {
+            if end.saturating_duration_since(tick.end) <= longest {
+                break;
+            }
+            self.ticks.pop_front();
+        }
+
+        let mut count = 0;
+        for (i, timing) in systems.into_iter().enumerate() {
+            count += 1;
+            match self.systems.get_mut(i) {
+                Some(stats) if stats.name == timing.name => {
+                    let average = stats.average.as_secs_f64();
+                    let last = timing.duration.as_secs_f64();
+                    stats.average =
+                        Duration::from_secs_f64(average + (last - average) / SYSTEM_AVERAGE_TICKS);
+                    stats.last = timing.duration;
+                }
+                _ => {
+                    // Systems were added; restart their statistics.
+                    let stats = SystemStats {
+                        name: timing.name.to_owned(),
+                        plugin: timing.plugin.map(str::to_owned),
+                        last: timing.duration,
+                        average: timing.duration,
+                    };
+                    if i < self.systems.len() {
+                        self.systems[i] = stats;
+                    } else {
+                        self.systems.push(stats);
+                    }
+                }
+            }
+        }
+        self.systems.truncate(count);
+    }
+
+    fn ticks_in(&self, window: TimingWindow, now: Instant) -> impl Iterator<Item = &Tick> {
+        self.ticks
+            .iter()
+            .rev()
+            .take_while(move |tick| now.saturating_duration_since(tick.end) <= window.duration())
+    }
+
+    /// Returns the average ticks per second over a window, at most [`TPS`].
+    pub fn tps(&self, window: TimingWindow, now: Instant) -> f64 {
+        let start = match self.start {
+            Some(start) => start,
+            None => return TPS as f64,
+        };
+        let span = window.duration().min(now.saturating_duration_since(start));
+        if span.is_zero() {
+            return TPS as f64;
+        }
+        let ticks = self.ticks_in(window, now).count();
+        (ticks as f64 / span.as_secs_f64()).min(TPS as f64)
+    }
+
+    /// Returns the average milliseconds per tick over a window.
+    pub fn mspt(&self, window: TimingWindow, now: Instant) -> f64 {
+        let (count, total) = self
+            .ticks_in(window, now)
+            .fold((0, Duration::ZERO), |(count, total), tick| {
+                (count + 1, total + tick.duration)
+            });
+        if count == 0 {
+            return 0.0;
+        }
+        total.as_secs_f64() * 1000.0 / count as f64
+    }
+
+    /// Returns the duration of the last tick.
+    pub fn last_tick(&self) -> Option<Duration> {
+        self.ticks.back().map(|tick| tick.duration)
+    }
+
+    /// Returns the timings of each system, in the order they run.
+    pub fn systems(&self) -> &[SystemStats] {
+        &self.systems
+    }
+
+    /// Returns the systems with the highest average duration, slowest first.
+    pub fn slowest_systems(&self, count: usize) -> Vec<&SystemStats> {
+        let mut systems: Vec<&SystemStats> = self.systems.iter().collect();
+        systems.sort_by_key(|system| Reverse(system.average));
+        systems.truncate(count);
+        systems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timing(name: &str, millis: u64) -> SystemTiming {
+        SystemTiming {
+            name,
+            plugin: None,
+            duration: Duration::from_millis(millis),
+        }
+    }
+
+    #[test]
+    fn tps_and_mspt() {
+        let mut timings = TickTimings::new();
+        let start = Instant::now();
+        let tick = Duration::from_millis(100);
+        let mut now = start;
+        // Ten seconds at half speed: 10 ticks per second taking 100 ms each.
+        for _ in 0..100 {
+            now += tick;
+            timings.record_tick(now, tick, []);
+        }
+
+        let tps = timings.tps(TimingWindow::FiveSeconds, now);
+        assert!((tps - 10.0).abs() < 0.5, "{}", tps);
+        let tps = timings.tps(TimingWindow::FifteenMinutes, now);
+        assert!((tps - 10.0).abs() < 0.5, "{}", tps);
+        let mspt = timings.mspt(TimingWindow::OneMinute, now);
+        assert!((mspt - 100.0).abs() < 0.01, "{}", mspt);
+        assert_eq!(timings.last_tick(), Some(tick));
+    }
+
+    #[test]
+    fn slowest_systems() {
+        let mut timings = TickTimings::new();
+        let now = Instant::now();
+        timings.record_tick(now, Duration::ZERO, [timing("fast", 1), timing("slow", 5)]);
+        let slowest = timings.slowest_systems(1);
+        assert_eq!(slowest.len(), 1);
+        assert_eq!(slowest[0].name, "slow");
+
+        timings.record_tick(
+            now,
+            Duration::ZERO,
+            [timing("fast", 1), timing("added", 9), timing("slow", 5)],
+        );
+        let names: Vec<&str> = timings.systems().iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["fast", "added", "slow"]);
+        assert_eq!(timings.slowest_systems(3)[0].name, "added");
+    }
+}
@@ -1,6 +1,6 @@
 use ahash::AHashSet;
 use base::{ChunkPosition, Position};
-use ecs::{SysResult, SystemExecutor};
+use ecs::{SysResult, SystemAccess, SystemContext, SystemExecutor};
 use itertools::Either;
 use quill_common::components::Name;
 use quill_common::events::PlayerJoinEvent;
@@ -10,15 +10,31 @@ use crate::{events::ViewUpdateEvent, Game};
 /// Registers systems to update the `View` of a player.
 pub fn register(_game: &mut Game, systems: &mut SystemExecutor<Game>) {
     systems
-        .add_system(update_player_views)
-        .add_system(update_view_on_join);
+        .add_parallel_system(
+            SystemAccess::new()
+                .reads::<Position>()
+                .reads::<Name>()
+                .writes::<View>()
+                .writes::<ViewUpdateEvent>(),
+            update_player_views,
+        )
+        .add_parallel_system(
+            SystemAccess::new()
+                .reads::<View>()
+                .reads::<Name>()
+                .reads::<PlayerJoinEvent>()
+                .writes::<ViewUpdateEvent>(),
+            update_view_on_join,
+        );
 }
 
 /// Updates players' views when they change chunks.
-fn update_player_views(game: &mut Game) -> SysResult {
+fn update_player_views(context: &mut SystemContext) -> SysResult {
     let mut events = Vec::new();
-    for (player, (view, &position, name)) in
-        game.ecs.query::<(&mut View, &Position, &Name)>().iter()
+    for (player, (view, &position, name)) in context
+        .ecs()
+        .query::<(&mut View, &Position, &Name)>()
+        .iter()
     {
         if position.chunk() != view.center() {
             let old_view = *view;
@@ -33,21 +49,25 @@ fn update_player_views(game: &mut Game) -> SysResult {
     }
 
     for (player, event) in events {
-        game.ecs.insert_entity_event(player, event)?;
+        context.insert_entity_event(player, event)?;
     }
     Ok(())
 }
 
 /// Triggers a ViewUpdateEvent when a player joins the game.
-fn update_view_on_join(game: &mut Game) -> SysResult {
+fn update_view_on_join(context: &mut SystemContext) -> SysResult {
     let mut events = Vec::new();
-    for (player, (&view, name, _)) in game.ecs.query::<(&View, &Name, &PlayerJoinEvent)>().iter() {
+    for (player, (&view, name, _)) in context
+        .ecs()
+        .query::<(&View, &Name, &PlayerJoinEvent)>()
+        .iter()
+    {
         let event = ViewUpdateEvent::new(View::empty(), view);
         events.push((player, event));
         log::trace!("View of {} has been updated (player joined)", name);
     }
     for (player, event) in events {
-        game.ecs.insert_entity_event(player, event)?;
+        context.insert_entity_event(player, event)?;
     }
     Ok(())
 }
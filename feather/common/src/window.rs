@@ -2,14 +2,20 @@ use std::mem;
 
 use anyhow::{anyhow, bail};
 
-use base::{Area, Item};
+use base::{Area, Inventory, Item};
 
-use ecs::SysResult;
+use ecs::{Entity, SysResult};
 pub use libcraft_inventory::Window as BackingWindow;
 use libcraft_inventory::WindowError;
 use libcraft_items::InventorySlot::{self, Empty};
 use parking_lot::MutexGuard;
 
+use crate::{events::WindowUpdateEvent, Game};
+
+/// Number of slots of the player's storage and hotbar,
+/// which are part of every container window.
+const PLAYER_WINDOW_SLOTS: usize = 36;
+
 /// A player's window. Wraps one or more inventories and handles
 /// conversion between protocol and slot indices.
 ///
@@ -276,16 +282,63 @@ impl Window {
         todo!()
     }
 
-    fn shift_click_in_enchantment(&mut self, _slot: usize) -> SysResult {
-        todo!()
+    fn shift_click_in_enchantment(&mut self, slot: usize) -> SysResult {
+        let (enchantment_table, player) = match &self.inner {
+            BackingWindow::Enchantment {
+                enchantment_table,
+                player,
+            } => (enchantment_table, player),
+            _ => unreachable!(),
+        };
+        let slot_item = &mut *self.inner.item(slot)?;
+        let (_, slot_area, _) = self.inner.index_to_slot(slot).unwrap();
+
+        match slot_area {
+            Area::EnchantmentItem | Area::EnchantmentLapis => {
+                transfer_to_areas(player, &[Area::Hotbar, Area::Storage], slot_item);
+            }
+            _ if slot_item.item_kind() == Some(Item::LapisLazuli) => {
+                transfer_to_areas(enchantment_table, &[Area::EnchantmentLapis], slot_item);
+            }
+            _ => {
+                // Only a single item can be enchanted at once.
+                let mut target = enchantment_table
+                    .item(Area::EnchantmentItem, 0)
+                    .ok_or_else(|| anyhow!("enchanting table has no item slot"))?;
+                if target.is_empty() {
+                    *target = slot_item.try_take(1);
+                }
+            }
+        }
+
+        Ok(())
     }
 
     fn shift_click_in_brewing_window(&mut self, _slot: usize) -> SysResult {
         todo!()
     }
 
-    fn shift_click_in_beacon(&mut self, _slot: usize) -> SysResult {
-        todo!()
+    fn shift_click_in_beacon(&mut self, slot: usize) -> SysResult {
+        let (beacon, player) = match &self.inner {
+            BackingWindow::Beacon { beacon, player } => (beacon, player),
+            _ => unreachable!(),
+        };
+        let slot_item = &mut *self.inner.item(slot)?;
+        let (_, slot_area, _) = self.inner.index_to_slot(slot).unwrap();
+
+        if slot_area == Area::BeaconPayment {
+            transfer_to_areas(player, &[Area::Hotbar, Area::Storage], slot_item);
+        } else if will_accept(Area::BeaconPayment, slot_item) {
+            // The payment slot only holds a single item.
+            let mut target = beacon
+                .item(Area::BeaconPayment, 0)
+                .ok_or_else(|| anyhow!("beacon has no payment slot"))?;
+            if target.is_empty() {
+                *target = slot_item.try_take(1);
+            }
+        }
+
+        Ok(())
     }
 
     fn shift_click_in_anvil(&mut self, _slot: usize) -> SysResult {
@@ -349,6 +402,11 @@ impl Window {
         &self.cursor_item
     }
 
+    /// Takes the item currently held in the cursor, leaving it empty.
+    pub fn take_cursor_item(&mut self) -> InventorySlot {
+        mem::take(&mut self.cursor_item)
+    }
+
     pub fn item(&self, index: usize) -> Result<MutexGuard<InventorySlot>, WindowError> {
         self.inner.item(index)
     }
@@ -363,6 +421,73 @@ impl Window {
     pub fn inner(&self) -> &BackingWindow {
         &self.inner
     }
+
+    /// Replaces the backing window, e.g. when the player
+    /// opens or closes a container. The cursor item is kept.
+    pub fn set_inner(&mut self, inner: BackingWindow) {
+        self.inner = inner;
+        self.paint_state = None;
+    }
+}
+
+/// Closes a player's container window, if they have one open,
+/// and shows their own inventory again.
+///
+/// Items left in the container and held by the cursor are
+/// returned to the player's inventory. Triggers `WindowUpdateEvent`.
+pub fn close_container_window(game: &mut Game, player: Entity) -> SysResult {
+    let inventory = game.ecs.get::<Inventory>(player)?.new_handle();
+    {
+        let mut window = game.ecs.get_mut::<Window>(player)?;
+        if let BackingWindow::Player { .. } = window.inner() {
+            return Ok(());
+        }
+
+        // Container windows list the container's slots
+        // before the player's storage and hotbar.
+        let container_slots = window.inner().to_vec().len() - PLAYER_WINDOW_SLOTS;
+        let mut returned = vec![window.take_cursor_item()];
+        for index in 0..container_slots {
+            returned.push(window.item(index)?.take_all());
+        }
+        window.set_inner(BackingWindow::Player {
+            player: inventory.new_handle(),
+        });
+
+        for mut item in returned {
+            transfer_to_areas(&inventory, &[Area::Hotbar, Area::Storage], &mut item);
+            if item.is_filled() {
+                log::debug!("Discarding {:?} which did not fit in the inventory", item);
+            }
+        }
+    }
+
+    game.ecs.insert_entity_event(player, WindowUpdateEvent)?;
+    Ok(())
+}
+
+/// Moves as much of `item` as possible into the given areas of an inventory,
+/// filling stacks of the same item before empty slots.
+pub fn transfer_to_areas(inventory: &Inventory, areas: &[Area], item: &mut InventorySlot) {
+    for fill_empty in [false, true] {
+        for &area in areas {
+            if !will_accept(area, item) {
+                continue;
+            }
+
+            let mut i = 0;
+            while let Some(mut stack) = inventory.item(area, i) {
+                if stack.is_filled() != fill_empty && stack.is_mergable(item) {
+                    stack.merge(item);
+                }
+                i += 1;
+            }
+
+            if item.is_empty() {
+                return;
+            }
+        }
+    }
 }
 
 /// Determines whether the given area will accept the given item
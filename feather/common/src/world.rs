@@ -14,6 +14,7 @@ use worldgen::{ComposableGenerator, WorldGenerator};
 
 use crate::{
     chunk::cache::ChunkCache,
+    chunk::storage::{AnvilStorage, StorageOptions},
     chunk::worker::{ChunkWorker, LoadRequest, SaveRequest},
     events::ChunkLoadEvent,
 };
@@ -31,6 +32,8 @@ pub struct World {
     loading_chunks: AHashSet<ChunkPosition>,
     canceled_chunk_loads: AHashSet<ChunkPosition>,
     world_dir: PathBuf,
+    /// Ticks the world has existed for, recorded in saved chunks.
+    game_time: u64,
 }
 
 impl Default for World {
@@ -38,13 +41,14 @@ impl Default for World {
         Self {
             chunk_map: ChunkMap::new(),
             chunk_worker: ChunkWorker::new(
-                "world",
+                Box::new(AnvilStorage::new("world")),
                 Arc::new(ComposableGenerator::default_with_seed(0)),
             ),
             cache: ChunkCache::new(),
             loading_chunks: AHashSet::new(),
             canceled_chunk_loads: AHashSet::new(),
             world_dir: "world".into(),
+            game_time: 0,
         }
     }
 }
@@ -58,13 +62,47 @@ impl World {
         generator: Arc<dyn WorldGenerator>,
         world_dir: impl Into<PathBuf> + Clone,
     ) -> Self {
+        Self::with_storage(generator, world_dir, &StorageOptions::Anvil)
+    }
+
+    /// Creates a world in `world_dir` whose chunks are
+    /// stored in the given kind of storage.
+    pub fn with_storage(
+        generator: Arc<dyn WorldGenerator>,
+        world_dir: impl Into<PathBuf>,
+        storage: &StorageOptions,
+    ) -> Self {
+        let world_dir = world_dir.into();
         Self {
-            world_dir: world_dir.clone().into(),
-            chunk_worker: ChunkWorker::new(world_dir, generator),
+            chunk_worker: ChunkWorker::new(storage.open(&world_dir), generator),
+            world_dir,
             ..Default::default()
         }
     }
 
+    /// Gets the number of ticks the world has existed for.
+    pub fn game_time(&self) -> u64 {
+        self.game_time
+    }
+
+    /// Sets the number of ticks the world has existed for,
+    /// like when resuming from the time stored in `level.dat`.
+    pub fn set_game_time(&mut self, ticks: u64) {
+        self.game_time = ticks;
+    }
+
+    /// Advances the game time by one tick.
+    pub fn advance_game_time(&mut self) {
+        self.game_time += 1;
+    }
+
+    /// Records the current game time in a chunk about to be saved.
+    fn stamp_last_update(&self, chunk: &ChunkLock) {
+        if let Some(mut chunk) = chunk.write() {
+            chunk.set_last_update(self.game_time);
+        }
+    }
+
     /// Queues the given chunk to be loaded. If the chunk was cached, it is loaded immediately.
     pub fn queue_chunk_load(&mut self, req: LoadRequest) {
         let pos = req.pos;
@@ -91,9 +129,14 @@ impl World {
             let chunk = loaded.chunk;
 
             self.chunk_map.insert_chunk(chunk);
+            if loaded.generated {
+                // Generated chunks don't exist in the storage yet.
+                self.chunk_map.mark_dirty(loaded.pos);
+            }
             ecs.insert_event(ChunkLoadEvent {
                 chunk: Arc::clone(&self.chunk_map.0[&loaded.pos]),
                 position: loaded.pos,
+                generated: loaded.generated,
             });
             log::trace!("Loaded chunk {:?}", loaded.pos);
         }
@@ -103,6 +146,7 @@ impl World {
     /// Unloads the given chunk.
     pub fn unload_chunk(&mut self, pos: ChunkPosition) -> anyhow::Result<()> {
         if let Some((pos, handle)) = self.chunk_map.0.remove_entry(&pos) {
+            self.stamp_last_update(&handle);
             handle.set_unloaded()?;
             self.chunk_worker.queue_chunk_save(SaveRequest {
                 pos,
@@ -121,6 +165,110 @@ impl World {
         Ok(())
     }
 
+    /// Returns the positions of the loaded chunks
+    /// which changed since they were last saved.
+    pub fn dirty_chunks(&self) -> Vec<ChunkPosition> {
+        self.chunk_map
+            .0
+            .iter()
+            .filter(|(_, handle)| handle.is_dirty())
+            .map(|(&pos, _)| pos)
+            .collect()
+    }
+
+    /// Queues the given chunk to be saved if it is loaded and
+    /// changed since it was last saved. Returns whether it was queued.
+    pub fn save_chunk(&mut self, pos: ChunkPosition) -> bool {
+        let handle = match self.chunk_map.0.get(&pos) {
+            Some(handle) if handle.take_dirty() => Arc::clone(handle),
+            _ => return false,
+        };
+        self.stamp_last_update(&handle);
+        self.chunk_worker.queue_chunk_save(SaveRequest {
+            pos,
+            chunk: handle,
+            entities: vec![],
+            block_entities: vec![],
+        });
+        true
+    }
+
+    /// Saves all changed chunks and flushes the storage, blocking until
+    /// the chunks are written. Chunks held while saving is disabled are
+    /// written as well. Returns the number of chunks saved.
+    pub fn save_all_chunks(&mut self) -> usize {
+        let saved = self
+            .dirty_chunks()
+            .into_iter()
+            .filter(|&pos| self.save_chunk(pos))
+            .count();
+        self.flush_chunks();
+        self.chunk_worker.sync();
+        saved
+    }
+
+    /// Queues a flush of the chunk storage after all queued saves.
+    ///
+    /// Saves held while saving is disabled are written as well.
+    pub fn flush_chunks(&mut self) {
+        self.chunk_worker.queue_flush();
+    }
+
+    /// Enables or disables writing chunks to the storage.
+    ///
+    /// While disabled, saved chunks are held in memory
+    /// until saving is enabled again or the storage is flushed.
+    pub fn set_saving_enabled(&mut self, enabled: bool) {
+        self.chunk_worker.set_saving_enabled(enabled);
+    }
+
+    /// Unloads all loaded chunks, queueing them to be saved.
+    pub fn unload_all_chunks(&mut self) -> anyhow::Result<()> {
+        let positions: Vec<ChunkPosition> = self.chunk_map.0.keys().copied().collect();
+        for pos in positions {
+            self.unload_chunk(pos)?;
+        }
+        Ok(())
+    }
+
+    /// Blocks until all queued chunk saves are written
+    /// and stops the chunk worker.
+    ///
+    /// Chunks can't be loaded or saved afterwards.
+    pub fn shutdown_chunk_worker(&mut self) {
+        self.chunk_worker.shutdown();
+    }
+
+    /// Queues the given chunk to be generated and saved if it
+    /// doesn't exist yet, without loading it into the world.
+    pub fn queue_pregeneration(&mut self, pos: ChunkPosition) {
+        self.chunk_worker.queue_pregeneration(pos);
+    }
+
+    /// Returns the chunks queued by [`World::queue_pregeneration`]
+    /// which have been generated or turned out to exist already.
+    pub fn poll_pregenerated(&mut self) -> Vec<ChunkPosition> {
+        let (mut done, generated) = self.chunk_worker.poll_pregenerated();
+        for mut chunk in generated {
+            let pos = chunk.position();
+            chunk.set_last_update(self.game_time);
+            // Don't overwrite a chunk which was loaded in the meantime.
+            if !self.is_chunk_loaded(pos)
+                && !self.is_chunk_loading(pos)
+                && !self.cache.contains(&pos)
+            {
+                self.chunk_worker.queue_chunk_save(SaveRequest {
+                    pos,
+                    chunk: Arc::new(ChunkLock::new(chunk, false)),
+                    entities: vec![],
+                    block_entities: vec![],
+                });
+            }
+            done.push(pos);
+        }
+        done
+    }
+
     /// Returns whether the given chunk is loaded.
     pub fn is_chunk_loaded(&self, pos: ChunkPosition) -> bool {
         self.chunk_map.0.contains_key(&pos)
@@ -154,6 +302,16 @@ impl World {
         &self.chunk_map
     }
 
+    /// Returns the number of chunk loads which are still in progress.
+    pub fn pending_chunk_loads(&self) -> usize {
+        self.chunk_worker.pending_loads()
+    }
+
+    /// Returns the number of chunk saves which haven't been written yet.
+    pub fn pending_chunk_saves(&self) -> usize {
+        self.chunk_worker.pending_saves()
+    }
+
     /// Mutably gets the chunk map.
     pub fn chunk_map_mut(&mut self) -> &mut ChunkMap {
         &mut self.chunk_map
@@ -190,6 +348,15 @@ impl ChunkMap {
         Self::default()
     }
 
+    /// Returns the number of loaded chunks.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     /// Retrieves a handle to the chunk at the given
     /// position, or `None` if it is not loaded.
     pub fn chunk_at(&self, pos: ChunkPosition) -> Option<RwLockReadGuard<Chunk>> {
@@ -222,9 +389,22 @@ impl ChunkMap {
 
         let (x, y, z) = chunk_relative_pos(pos.into());
 
-        self.chunk_at_mut(pos.chunk())
+        let was_set = self
+            .chunk_at_mut(pos.chunk())
             .map(|mut chunk| chunk.set_block_at(x, y, z, block))
-            .is_some()
+            .is_some();
+        if was_set {
+            self.mark_dirty(pos.chunk());
+        }
+        was_set
+    }
+
+    /// Marks the chunk at the given position as changed,
+    /// so that it is saved by the next autosave.
+    pub fn mark_dirty(&self, pos: ChunkPosition) {
+        if let Some(handle) = self.0.get(&pos) {
+            handle.mark_dirty();
+        }
     }
 
     /// Returns an iterator over chunks.
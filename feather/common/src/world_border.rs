@@ -0,0 +1,319 @@
+//! The world border: a square around a center which players can't
+//! leave and which damages players outside it. Its size can change
+//! gradually over time.
+
+use std::{mem, time::Duration};
+
+use base::{anvil::level::LevelData, ChunkPosition, Position};
+use ecs::{Entity, SysResult, SystemExecutor};
+use quill_common::entities::Player;
+
+use crate::Game;
+
+/// The default diameter of the world border, in blocks.
+pub const DEFAULT_SIZE: f64 = 59_999_968.0;
+
+/// Coordinates beyond which the border can't extend.
+pub const MAX_COORDINATE: f64 = 29_999_984.0;
+
+/// Duration of a tick, used to convert resize durations.
+const TICK_MILLIS: u64 = 50;
+
+/// Ticks between applying damage to players outside the border.
+const DAMAGE_INTERVAL: u64 = 10;
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    game.insert_resource(WorldBorder::default());
+    systems
+        .group::<WorldBorder>()
+        .add_system(tick_border)
+        .add_system(damage_players_outside_border);
+}
+
+/// A change to the world border which has yet to be sent to clients.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WorldBorderUpdate {
+    Size,
+    Center,
+    WarningTime,
+    WarningBlocks,
+}
+
+/// The world border, stored as a resource.
+#[derive(Clone, Debug)]
+pub struct WorldBorder {
+    center_x: f64,
+    center_z: f64,
+    /// Diameter at the start of the current resize.
+    old_size: f64,
+    /// Diameter at the end of the current resize.
+    new_size: f64,
+    /// Total duration of the current resize, in ticks.
+    resize_ticks: u64,
+    /// Ticks left until the current resize completes.
+    remaining_ticks: u64,
+    /// Damage dealt per block a player is outside the safe zone.
+    pub damage_per_block: f64,
+    /// Distance outside the border within which players take no damage.
+    pub safe_zone: f64,
+    /// Time, in seconds, before a shrinking border reaches a player
+    /// at which their screen starts turning red.
+    warning_time: i32,
+    /// Distance from the border at which a player's screen starts turning red.
+    warning_blocks: i32,
+    updates: Vec<WorldBorderUpdate>,
+}
+
+impl Default for WorldBorder {
+    fn default() -> Self {
+        Self {
+            center_x: 0.0,
+            center_z: 0.0,
+            old_size: DEFAULT_SIZE,
+            new_size: DEFAULT_SIZE,
+            resize_ticks: 0,
+            remaining_ticks: 0,
+            damage_per_block: 0.2,
+            safe_zone: 5.0,
+            warning_time: 15,
+            warning_blocks: 5,
+            updates: Vec::new(),
+        }
+    }
+}
+
+impl WorldBorder {
+    /// Creates a world border from the fields of a `level.dat` file,
+    /// resuming a resize which was in progress.
+    pub fn from_level(level: &LevelData) -> Self {
+        let size = if level.border_size > 0.0 {
+            level.border_size
+        } else {
+            DEFAULT_SIZE
+        };
+        let mut border = Self {
+            center_x: level.border_center_x,
+            center_z: level.border_center_z,
+            old_size: size,
+            new_size: size,
+            damage_per_block: level.border_damage_per_block,
+            safe_zone: level.border_safe_zone,
+            warning_time: level.border_warning_time as i32,
+            warning_blocks: level.border_warning_blocks as i32,
+            ..Default::default()
+        };
+        if level.border_size_lerp_time > 0 {
+            border.resize(
+                level.border_size_lerp_target,
+                Duration::from_millis(level.border_size_lerp_time as u64),
+            );
+        }
+        border.updates.clear();
+        border
+    }
+
+    /// Writes this border to the fields of a `level.dat` file.
+    pub fn write_to_level(&self, level: &mut LevelData) {
+        level.border_center_x = self.center_x;
+        level.border_center_z = self.center_z;
+        level.border_size = self.size();
+        level.border_size_lerp_target = self.new_size;
+        level.border_size_lerp_time = self.remaining_millis() as i64;
+        level.border_damage_per_block = self.damage_per_block;
+        level.border_safe_zone = self.safe_zone;
+        level.border_warning_time = f64::from(self.warning_time);
+        level.border_warning_blocks = f64::from(self.warning_blocks);
+    }
+
+    /// Returns the X and Z coordinates of the center of the border.
+    pub fn center(&self) -> (f64, f64) {
+        (self.center_x, self.center_z)
+    }
+
+    /// Moves the center of the border.
+    pub fn set_center(&mut self, x: f64, z: f64) {
+        self.center_x = x.clamp(-MAX_COORDINATE, MAX_COORDINATE);
+        self.center_z = z.clamp(-MAX_COORDINATE, MAX_COORDINATE);
+        self.updates.push(WorldBorderUpdate::Center);
+    }
+
+    /// Returns the current diameter of the border.
+    pub fn size(&self) -> f64 {
+        if self.remaining_ticks == 0 {
+            return self.new_size;
+        }
+        let progress = 1.0 - self.remaining_ticks as f64 / self.resize_ticks as f64;
+        self.old_size + (self.new_size - self.old_size) * progress
+    }
+
+    /// Returns the diameter the border is resizing to, or
+    /// the current diameter if it isn't resizing.
+    pub fn target_size(&self) -> f64 {
+        self.new_size
+    }
+
+    /// Returns the time until the current resize completes, in milliseconds.
+    pub fn remaining_millis(&self) -> u64 {
+        self.remaining_ticks * TICK_MILLIS
+    }
+
+    /// Sets the diameter of the border immediately.
+    pub fn set_size(&mut self, size: f64) {
+        self.old_size = size;
+        self.new_size = size;
+        self.resize_ticks = 0;
+        self.remaining_ticks = 0;
+        self.updates.push(WorldBorderUpdate::Size);
+    }
+
+    /// Gradually changes the diameter of the border over the given duration,
+    /// starting from its current diameter.
+    pub fn resize(&mut self, size: f64, duration: Duration) {
+        let ticks = duration.as_millis() as u64 / TICK_MILLIS;
+        if ticks == 0 {
+            self.set_size(size);
+            return;
+        }
+        self.old_size = self.size();
+        self.new_size = size;
+        self.resize_ticks = ticks;
+        self.remaining_ticks = ticks;
+        self.updates.push(WorldBorderUpdate::Size);
+    }
+
+    pub fn warning_time(&self) -> i32 {
+        self.warning_time
+    }
+
+    pub fn set_warning_time(&mut self, seconds: i32) {
+        self.warning_time = seconds;
+        self.updates.push(WorldBorderUpdate::WarningTime);
+    }
+
+    pub fn warning_blocks(&self) -> i32 {
+        self.warning_blocks
+    }
+
+    pub fn set_warning_blocks(&mut self, blocks: i32) {
+        self.warning_blocks = blocks;
+        self.updates.push(WorldBorderUpdate::WarningBlocks);
+    }
+
+    /// Returns the minimum X, minimum Z, maximum X and maximum Z
+    /// coordinates inside the border.
+    pub fn bounds(&self) -> (f64, f64, f64, f64) {
+        let radius = self.size() / 2.0;
+        (
+            (self.center_x - radius).max(-MAX_COORDINATE),
+            (self.center_z - radius).max(-MAX_COORDINATE),
+            (self.center_x + radius).min(MAX_COORDINATE),
+            (self.center_z + radius).min(MAX_COORDINATE),
+        )
+    }
+
+    /// Returns the distance from the given coordinates to the nearest
+    /// edge of the border, which is negative outside the border.
+    pub fn distance_inside(&self, x: f64, z: f64) -> f64 {
+        let (min_x, min_z, max_x, max_z) = self.bounds();
+        (x - min_x).min(max_x - x).min(z - min_z).min(max_z - z)
+    }
+
+    /// Determines whether the given coordinates are inside the border.
+    pub fn contains(&self, x: f64, z: f64) -> bool {
+        self.distance_inside(x, z) >= 0.0
+    }
+
+    /// Determines whether any part of the given chunk is inside the border.
+    pub fn intersects_chunk(&self, chunk: ChunkPosition) -> bool {
+        let (min_x, min_z, max_x, max_z) = self.bounds();
+        let (x, z) = (f64::from(chunk.x * 16), f64::from(chunk.z * 16));
+        x + 16.0 > min_x && x < max_x && z + 16.0 > min_z && z < max_z
+    }
+
+    /// Takes the changes which have yet to be sent to clients.
+    pub fn take_updates(&mut self) -> Vec<WorldBorderUpdate> {
+        mem::take(&mut self.updates)
+    }
+
+    fn tick(&mut self) {
+        if self.remaining_ticks > 0 {
+            self.remaining_ticks -= 1;
+            if self.remaining_ticks == 0 {
+                self.old_size = self.new_size;
+            }
+        }
+    }
+}
+
+fn tick_border(_game: &mut Game, border: &mut WorldBorder) -> SysResult {
+    border.tick();
+    Ok(())
+}
+
+/// Damages players outside the border's safe zone, in proportion
+/// to their distance from it.
+fn damage_players_outside_border(game: &mut Game, border: &mut WorldBorder) -> SysResult {
+    if !game.tick_count.is_multiple_of(DAMAGE_INTERVAL) || border.damage_per_block <= 0.0 {
+        return Ok(());
+    }
+
+    let damaged: Vec<(Entity, f32)> = game
+        .ecs
+        .query::<(&Player, &Position)>()
+        .iter()
+        .filter_map(|(player, (_, position))| {
+            let distance = border.distance_inside(position.x, position.z) + border.safe_zone;
+            if distance >= 0.0 {
+                return None;
+            }
+            let damage = (-distance * border.damage_per_block).floor().max(1.0);
+            Some((player, damage as f32))
+        })
+        .collect();
+
+    for (player, damage) in damaged {
+        game.damage_entity(player, damage, None)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resize_interpolates() {
+        let mut border = WorldBorder::default();
+        border.set_size(100.0);
+        border.resize(200.0, Duration::from_secs(1));
+        assert_eq!(border.remaining_millis(), 1000);
+
+        for _ in 0..10 {
+            border.tick();
+        }
+        assert_eq!(border.size(), 150.0);
+
+        for _ in 0..10 {
+            border.tick();
+        }
+        assert_eq!(border.size(), 200.0);
+        assert_eq!(border.remaining_millis(), 0);
+        assert_eq!(
+            border.take_updates(),
+            vec![WorldBorderUpdate::Size, WorldBorderUpdate::Size]
+        );
+    }
+
+    #[test]
+    fn distance_inside() {
+        let mut border = WorldBorder::default();
+        border.set_center(10.0, 0.0);
+        border.set_size(20.0);
+
+        assert!(border.contains(10.0, 0.0));
+        assert_eq!(border.distance_inside(15.0, 0.0), 5.0);
+        assert_eq!(border.distance_inside(25.0, 0.0), -5.0);
+        assert!(border.intersects_chunk(ChunkPosition::new(0, -1)));
+        assert!(!border.intersects_chunk(ChunkPosition::new(2, 0)));
+    }
+}
@@ -3,7 +3,7 @@
 //! This is implemented as a wrapper around the Bevy Engine's fork of the
 //!  `hecs` crate, but we've made some interface changes:
 //! * A system framework has been implemented, with systems written as plain functions and
-//! executed sequentially.
+//! executed sequentially, or in parallel if they declare what they access.
 //! * `World` is renamed to `Ecs` so as to avoid conflict with Minecraft's concept of worlds.
 //! * We add support for events based on components.
 //!
@@ -21,7 +21,10 @@ pub use hecs::{
 };
 
 mod system;
-pub use system::{GroupBuilder, HasEcs, HasResources, SysResult, SystemExecutor};
+pub use system::{GroupBuilder, HasEcs, HasResources, SysResult, SystemExecutor, SystemTiming};
+
+mod parallel;
+pub use parallel::{SystemAccess, SystemContext};
 
 mod resources;
 pub use resources::{ResourceError, Resources};
@@ -0,0 +1,292 @@
+//! Systems which run in parallel with each other.
+//!
+//! A parallel system declares the components and resources it
+//! reads and writes in a [`SystemAccess`]. Consecutive parallel
+//! systems form a _stage_, which is split into batches of systems
+//! whose accesses don't conflict. Batches run in order, and the
+//! systems of a batch run on a thread pool.
+//!
+//! Parallel systems get a [`SystemContext`] instead of the executor's
+//! input. Events they trigger are inserted after their batch finishes,
+//! so systems which read an event type must declare it to run after
+//! the systems writing it.
+
+use std::{
+    any::{type_name, Any, TypeId},
+    cell::{Ref, RefMut},
+};
+
+use hecs::{Component, Entity};
+
+use crate::{Ecs, ResourceError, Resources, SysResult};
+
+/// The components and resources a parallel system reads and writes.
+///
+/// Two systems conflict if one of them writes a component or resource
+/// the other accesses. Conflicting systems never run at the same time,
+/// and run in the order they were added.
+///
+/// Component accesses aren't enforced: querying undeclared components
+/// panics if another system is borrowing them at the same time.
+/// Resources and events must be declared to be used.
+#[derive(Debug, Default, Clone)]
+pub struct SystemAccess {
+    reads: Vec<TypeId>,
+    writes: Vec<TypeId>,
+    resources: Vec<ResourceAccess>,
+}
+
+impl SystemAccess {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares that the system reads `T` components.
+    pub fn reads<T: Component>(mut self) -> Self {
+        self.reads.push(TypeId::of::<T>());
+        self
+    }
+
+    /// Declares that the system writes `T` components,
+    /// or triggers `T` events.
+    pub fn writes<T: Component>(mut self) -> Self {
+        self.writes.push(TypeId::of::<T>());
+        self
+    }
+
+    /// Declares that the system reads the resource of type `T`.
+    pub fn reads_resource<T: Send + Sync + 'static>(mut self) -> Self {
+        self.resources.push(ResourceAccess::new::<T>(false));
+        self
+    }
+
+    /// Declares that the system writes the resource of type `T`.
+    pub fn writes_resource<T: Send + Sync + 'static>(mut self) -> Self {
+        self.resources.push(ResourceAccess::new::<T>(true));
+        self
+    }
+
+    /// Returns whether a system with this access may not
+    /// run at the same time as a system with `other`.
+    pub fn conflicts_with(&self, other: &SystemAccess) -> bool {
+        let components_conflict = |a: &SystemAccess, b: &SystemAccess| {
+            a.writes
+                .iter()
+                .any(|ty| b.reads.contains(ty) || b.writes.contains(ty))
+        };
+        let resources_conflict = self.resources.iter().any(|a| {
+            other
+                .resources
+                .iter()
+                .any(|b| a.type_id == b.type_id && (a.write || b.write))
+        });
+        components_conflict(self, other) || components_conflict(other, self) || resources_conflict
+    }
+
+    fn writes_component(&self, ty: TypeId) -> bool {
+        self.writes.contains(&ty)
+    }
+
+    /// Borrows the declared resources for a run of the system.
+    pub(crate) fn borrow_resources<'a>(
+        &self,
+        resources: &'a Resources,
+    ) -> Result<Vec<ResourceGuard<'a>>, ResourceError> {
+        self.resources
+            .iter()
+            .map(|access| (access.borrow)(resources, access.write))
+            .collect()
+    }
+}
+
+type BorrowFn = for<'a> fn(&'a Resources, bool) -> Result<ResourceGuard<'a>, ResourceError>;
+
+#[derive(Clone)]
+struct ResourceAccess {
+    type_id: TypeId,
+    name: &'static str,
+    write: bool,
+    borrow: BorrowFn,
+}
+
+impl ResourceAccess {
+    fn new<T: Send + Sync + 'static>(write: bool) -> Self {
+        Self {
+            type_id: TypeId::of::<T>(),
+            name: type_name::<T>(),
+            write,
+            borrow: borrow_resource::<T>,
+        }
+    }
+}
+
+impl std::fmt::Debug for ResourceAccess {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResourceAccess")
+            .field("name", &self.name)
+            .field("write", &self.write)
+            .finish()
+    }
+}
+
+fn borrow_resource<T: Send + Sync + 'static>(
+    resources: &Resources,
+    write: bool,
+) -> Result<ResourceGuard<'_>, ResourceError> {
+    let ty = TypeId::of::<T>();
+    Ok(if write {
+        let resource = resources.get_mut::<T>()?;
+        ResourceGuard::Unique(
+            ty,
+            RefMut::map(resource, |r| r as &mut (dyn Any + Send + Sync)),
+        )
+    } else {
+        let resource = resources.get::<T>()?;
+        ResourceGuard::Shared(ty, Ref::map(resource, |r| r as &(dyn Any + Send + Sync)))
+    })
+}
+
+/// A borrowed resource, held by the executor while a system runs.
+pub(crate) enum ResourceGuard<'a> {
+    Shared(TypeId, Ref<'a, dyn Any + Send + Sync>),
+    Unique(TypeId, RefMut<'a, dyn Any + Send + Sync>),
+}
+
+enum ResourceRef<'a> {
+    Shared(&'a (dyn Any + Send + Sync)),
+    Unique(&'a mut (dyn Any + Send + Sync)),
+}
+
+pub(crate) type DeferredEvent = Box<dyn FnOnce(&mut Ecs) -> SysResult + Send>;
+
+/// The input of a parallel system, providing the entities and
+/// the resources it declared.
+pub struct SystemContext<'a> {
+    ecs: &'a Ecs,
+    resources: Vec<(TypeId, ResourceRef<'a>)>,
+    access: &'a SystemAccess,
+    events: Vec<DeferredEvent>,
+}
+
+impl<'a> SystemContext<'a> {
+    pub(crate) fn new(
+        ecs: &'a Ecs,
+        guards: &'a mut [ResourceGuard<'_>],
+        access: &'a SystemAccess,
+    ) -> Self {
+        let resources = guards
+            .iter_mut()
+            .map(|guard| match guard {
+                ResourceGuard::Shared(ty, resource) => (*ty, ResourceRef::Shared(&**resource)),
+                ResourceGuard::Unique(ty, resource) => (*ty, ResourceRef::Unique(&mut **resource)),
+            })
+            .collect();
+        Self {
+            ecs,
+            resources,
+            access,
+            events: Vec::new(),
+        }
+    }
+
+    /// Returns the entities. Structural changes, like spawning
+    /// entities or adding components, aren't possible in parallel systems.
+    pub fn ecs(&self) -> &'a Ecs {
+        self.ecs
+    }
+
+    /// Gets a resource the system declared to read or write.
+    pub fn resource<T: 'static>(&self) -> Result<&T, ResourceError> {
+        let resource = match self.find_resource::<T>()? {
+            ResourceRef::Shared(resource) => &**resource,
+            ResourceRef::Unique(resource) => &**resource,
+        };
+        Ok(resource.downcast_ref().unwrap())
+    }
+
+    /// Mutably gets a resource the system declared to write.
+    pub fn resource_mut<T: 'static>(&mut self) -> Result<&mut T, ResourceError> {
+        let index = self.resource_index::<T>()?;
+        match &mut self.resources[index].1 {
+            ResourceRef::Unique(resource) => Ok(resource.downcast_mut().unwrap()),
+            ResourceRef::Shared(_) => Err(ResourceError::Undeclared(type_name::<T>())),
+        }
+    }
+
+    fn find_resource<T: 'static>(&self) -> Result<&ResourceRef<'a>, ResourceError> {
+        self.resource_index::<T>()
+            .map(|index| &self.resources[index].1)
+    }
+
+    fn resource_index<T: 'static>(&self) -> Result<usize, ResourceError> {
+        self.resources
+            .iter()
+            .position(|(ty, _)| *ty == TypeId::of::<T>())
+            .ok_or_else(|| ResourceError::Undeclared(type_name::<T>()))
+    }
+
+    /// Creates an event not related to any entity, like [`Ecs::insert_event`].
+    ///
+    /// The event is inserted once the system's batch finishes.
+    /// The system must declare that it writes `T`.
+    pub fn insert_event<T: Component>(&mut self, event: T) -> SysResult {
+        self.check_event::<T>()?;
+        self.events.push(Box::new(move |ecs| {
+            ecs.insert_event(event);
+            Ok(())
+        }));
+        Ok(())
+    }
+
+    /// Adds an event to an entity, like [`Ecs::insert_entity_event`].
+    ///
+    /// The event is inserted once the system's batch finishes.
+    /// The system must declare that it writes `T`.
+    pub fn insert_entity_event<T: Component>(&mut self, entity: Entity, event: T) -> SysResult {
+        self.check_event::<T>()?;
+        self.events.push(Box::new(move |ecs| {
+            ecs.insert_entity_event(entity, event)?;
+            Ok(())
+        }));
+        Ok(())
+    }
+
+    fn check_event<T: Component>(&self) -> SysResult {
+        if !self.access.writes_component(TypeId::of::<T>()) {
+            anyhow::bail!(
+                "event type {} must be declared as written by the system",
+                type_name::<T>()
+            );
+        }
+        Ok(())
+    }
+
+    pub(crate) fn into_events(self) -> Vec<DeferredEvent> {
+        self.events
+    }
+}
+
+/// Splits a stage of systems into batches of systems which may run
+/// at the same time. Each system goes in the batch after the last one
+/// containing a system it conflicts with, so conflicting systems keep
+/// their order.
+///
+/// Returns the indices into `accesses` of the systems in each batch.
+pub(crate) fn batches(accesses: &[&SystemAccess]) -> Vec<Vec<usize>> {
+    let mut batches: Vec<Vec<usize>> = Vec::new();
+    for (i, access) in accesses.iter().enumerate() {
+        let batch = batches
+            .iter()
+            .rposition(|batch| {
+                batch
+                    .iter()
+                    .any(|&other| accesses[other].conflicts_with(access))
+            })
+            .map_or(0, |last| last + 1);
+        match batches.get_mut(batch) {
+            Some(batch) => batch.push(i),
+            None => batches.push(vec![i]),
+        }
+    }
+    batches
+}
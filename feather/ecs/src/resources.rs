@@ -13,6 +13,8 @@ pub enum ResourceError {
         "resource of type '{0}' borrowed invalidly (mutably and immutable borrow at the same time)"
     )]
     Borrow(&'static str),
+    #[error("resource of type '{0}' was not declared in the system's access")]
+    Undeclared(&'static str),
 }
 
 /// Structure storing _resources_, where each
@@ -1,8 +1,18 @@
 //! System execution, using a simple "systems as functions" model.
 
-use std::{any::type_name, marker::PhantomData, sync::Arc};
+use std::{
+    any::type_name,
+    marker::PhantomData,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use crate::{Ecs, Resources};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+use crate::{
+    parallel::{self, SystemAccess, SystemContext},
+    Ecs, Resources,
+};
 
 /// The result type returned by a system function.
 ///
@@ -22,18 +32,55 @@ pub type SysResult<T = ()> = anyhow::Result<T>;
 
 type SystemFn<Input> = Box<dyn FnMut(&mut Input) -> SysResult>;
 
+type ParallelSystemFn = Box<dyn FnMut(&mut SystemContext) -> SysResult + Send>;
+
+enum SystemFunction<Input> {
+    Serial(SystemFn<Input>),
+    Parallel(ParallelSystemFn, SystemAccess),
+}
+
 struct System<Input> {
-    function: SystemFn<Input>,
+    function: SystemFunction<Input>,
     name: String,
+    /// The plugin which registered the system, if any.
+    plugin: Option<String>,
+    /// How long the system took in the last run.
+    last_duration: Duration,
 }
 
 impl<Input> System<Input> {
     fn from_fn<F: FnMut(&mut Input) -> SysResult + 'static>(f: F) -> Self {
         Self {
-            function: Box::new(f),
+            function: SystemFunction::Serial(Box::new(f)),
             name: type_name::<F>().to_owned(),
+            plugin: None,
+            last_duration: Duration::ZERO,
         }
     }
+
+    fn access(&self) -> Option<&SystemAccess> {
+        match &self.function {
+            SystemFunction::Serial(_) => None,
+            SystemFunction::Parallel(_, access) => Some(access),
+        }
+    }
+
+    fn log_error(&self, e: anyhow::Error) {
+        log::error!(
+            "System {} returned an error; this is a bug: {:?}",
+            self.name,
+            e
+        );
+    }
+}
+
+/// How long a system took in the last run of its executor.
+#[derive(Copy, Clone, Debug)]
+pub struct SystemTiming<'a> {
+    pub name: &'a str,
+    /// The plugin which registered the system, if any.
+    pub plugin: Option<&'a str>,
+    pub duration: Duration,
 }
 
 /// A type containing a `Resources`.
@@ -68,11 +115,18 @@ impl HasEcs for Ecs {
 /// For example, the `Server` group has state contained in the `Server`
 /// struct, so all its systems get `Server` as an extra parameter.
 ///
-/// Systems run sequentially in the order they are added to the executor.
+/// Systems run sequentially in the order they are added to the executor,
+/// except for consecutive parallel systems, which may run at the same time
+/// unless their accesses conflict. See [`add_parallel_system`](Self::add_parallel_system).
 pub struct SystemExecutor<Input> {
     systems: Vec<System<Input>>,
 
     is_first_run: bool,
+
+    /// Gets the resources of the input. Set once a parallel system is added.
+    resources: Option<fn(&Input) -> Arc<Resources>>,
+    /// Runs parallel systems; created on first use.
+    thread_pool: Option<ThreadPool>,
 }
 
 impl<Input> Default for SystemExecutor<Input> {
@@ -80,6 +134,8 @@ impl<Input> Default for SystemExecutor<Input> {
         Self {
             systems: Vec::new(),
             is_first_run: true,
+            resources: None,
+            thread_pool: None,
         }
     }
 }
@@ -109,6 +165,41 @@ impl<Input> SystemExecutor<Input> {
         self.systems.push(system);
     }
 
+    /// Adds a system registered by a plugin, which is
+    /// labeled with the plugin's name in timings.
+    pub fn add_plugin_system(
+        &mut self,
+        system: impl FnMut(&mut Input) -> SysResult + 'static,
+        name: &str,
+        plugin: &str,
+    ) {
+        let mut system = System::from_fn(system);
+        system.name = name.to_owned();
+        system.plugin = Some(plugin.to_owned());
+        self.systems.push(system);
+    }
+
+    /// Adds a system which may run at the same time as the parallel
+    /// systems added directly before or after it, if their accesses
+    /// don't conflict. Serial systems never run at the same time as others.
+    ///
+    /// The system gets a [`SystemContext`] providing the entities and
+    /// the resources declared in `access`.
+    pub fn add_parallel_system<F>(&mut self, access: SystemAccess, system: F) -> &mut Self
+    where
+        F: FnMut(&mut SystemContext) -> SysResult + Send + 'static,
+        Input: HasResources,
+    {
+        self.resources = Some(Input::resources);
+        self.systems.push(System {
+            function: SystemFunction::Parallel(Box::new(system), access),
+            name: type_name::<F>().to_owned(),
+            plugin: None,
+            last_duration: Duration::ZERO,
+        });
+        self
+    }
+
     /// Begins a group with the provided group state type.
     ///
     /// The group state must be added to the `resources`.
@@ -122,42 +213,195 @@ impl<Input> SystemExecutor<Input> {
         }
     }
 
-    /// Runs all systems in order.
+    /// Runs all systems in order, measuring how long each takes.
     ///
     /// Errors are logged using the `log` crate.
     pub fn run(&mut self, input: &mut Input)
     where
         Input: HasEcs,
     {
-        for (i, system) in self.systems.iter_mut().enumerate() {
-            input.ecs_mut().set_current_system_index(i);
-
-            // For the first cycle, we don't want to clear
-            // events because some code may have triggered
-            // events _before_ the first system run. Without
-            // this check, these events would be cleared before
-            // any system could observe them.
-            if !self.is_first_run {
-                input.ecs_mut().remove_old_events();
+        let mut i = 0;
+        while i < self.systems.len() {
+            let stage_len = self.systems[i..]
+                .iter()
+                .take_while(|system| system.access().is_some())
+                .count();
+            if stage_len > 0 {
+                self.run_stage(input, i, stage_len);
+                i += stage_len;
+                continue;
             }
 
-            let result = (system.function)(input);
-            if let Err(e) = result {
-                log::error!(
-                    "System {} returned an error; this is a bug: {:?}",
-                    system.name,
-                    e
-                );
+            self.start_system(input, i);
+            let system = &mut self.systems[i];
+            if let SystemFunction::Serial(function) = &mut system.function {
+                let start = Instant::now();
+                let result = function(input);
+                system.last_duration = start.elapsed();
+                if let Err(e) = result {
+                    system.log_error(e);
+                }
             }
+            i += 1;
         }
 
         self.is_first_run = false;
     }
 
+    /// Prepares the event tracker for running the system at `index`.
+    fn start_system(&self, input: &mut Input, index: usize)
+    where
+        Input: HasEcs,
+    {
+        input.ecs_mut().set_current_system_index(index);
+
+        // For the first cycle, we don't want to clear
+        // events because some code may have triggered
+        // events _before_ the first system run. Without
+        // this check, these events would be cleared before
+        // any system could observe them.
+        if !self.is_first_run {
+            input.ecs_mut().remove_old_events();
+        }
+    }
+
+    /// Runs the parallel systems `start..start + len`, in batches
+    /// of systems which don't conflict.
+    fn run_stage(&mut self, input: &mut Input, start: usize, len: usize)
+    where
+        Input: HasEcs,
+    {
+        let accesses: Vec<&SystemAccess> = self.systems[start..start + len]
+            .iter()
+            .filter_map(System::access)
+            .collect();
+        let batches = parallel::batches(&accesses);
+        for batch in batches {
+            let indices: Vec<usize> = batch.into_iter().map(|i| start + i).collect();
+            self.run_batch(input, &indices);
+        }
+    }
+
+    fn run_batch(&mut self, input: &mut Input, indices: &[usize])
+    where
+        Input: HasEcs,
+    {
+        for &index in indices {
+            self.start_system(input, index);
+        }
+
+        let resources = (self.resources.expect("parallel system without resources"))(input);
+        let thread_pool = self.thread_pool.get_or_insert_with(|| {
+            ThreadPoolBuilder::new()
+                .thread_name(|i| format!("system-worker-{}", i))
+                .build()
+                .expect("failed to create the system thread pool")
+        });
+
+        let mut systems = Vec::with_capacity(indices.len());
+        for (index, system) in self.systems.iter_mut().enumerate() {
+            if let SystemFunction::Parallel(function, access) = &mut system.function {
+                if indices.contains(&index) {
+                    systems.push((index, function, &*access, &mut system.last_duration));
+                }
+            }
+        }
+
+        // Resources are borrowed on this thread, since
+        // they can't be borrowed from the pool's threads.
+        let mut guards: Vec<_> = systems
+            .iter()
+            .map(|(_, _, access, _)| access.borrow_resources(&resources))
+            .collect();
+        let ecs = input.ecs();
+        let mut runs: Vec<BatchRun> = systems
+            .into_iter()
+            .zip(&mut guards)
+            .map(|((index, function, access, duration), guards)| {
+                let (context, result) = match guards {
+                    Ok(guards) => (Some(SystemContext::new(ecs, guards, access)), Ok(())),
+                    Err(e) => (
+                        None,
+                        Err(anyhow::anyhow!("failed to borrow a resource: {}", e)),
+                    ),
+                };
+                BatchRun {
+                    index,
+                    function,
+                    context,
+                    duration,
+                    result,
+                    events: Vec::new(),
+                }
+            })
+            .collect();
+
+        // This thread runs the first system while the pool runs the others.
+        if let Some((first, others)) = runs.split_first_mut() {
+            thread_pool.in_place_scope(|scope| {
+                for run in others {
+                    scope.spawn(move |_| run.run());
+                }
+                first.run();
+            });
+        }
+
+        let results: Vec<_> = runs
+            .into_iter()
+            .map(|run| (run.index, run.result, run.events))
+            .collect();
+        drop(guards);
+
+        // Insert events as if each system triggered them
+        // itself, so they are removed before it runs again.
+        for (index, result, events) in results {
+            if let Err(e) = result {
+                self.systems[index].log_error(e);
+            }
+            input.ecs_mut().set_current_system_index(index);
+            for event in events {
+                if let Err(e) = event(input.ecs_mut()) {
+                    self.systems[index].log_error(e);
+                }
+            }
+        }
+    }
+
     /// Gets an iterator over system names.
     pub fn system_names(&self) -> impl Iterator<Item = &'_ str> + '_ {
         self.systems.iter().map(|system| system.name.as_str())
     }
+
+    /// Gets how long each system took in the last run, in order.
+    pub fn timings(&self) -> impl Iterator<Item = SystemTiming<'_>> + '_ {
+        self.systems.iter().map(|system| SystemTiming {
+            name: &system.name,
+            plugin: system.plugin.as_deref(),
+            duration: system.last_duration,
+        })
+    }
+}
+
+/// A parallel system being run in a batch.
+struct BatchRun<'a> {
+    index: usize,
+    function: &'a mut ParallelSystemFn,
+    /// `None` if the system's resources couldn't be borrowed.
+    context: Option<SystemContext<'a>>,
+    duration: &'a mut Duration,
+    result: SysResult,
+    events: Vec<parallel::DeferredEvent>,
+}
+
+impl BatchRun<'_> {
+    fn run(&mut self) {
+        if let Some(mut context) = self.context.take() {
+            let start = Instant::now();
+            self.result = (self.function)(&mut context);
+            *self.duration = start.elapsed();
+            self.events = context.into_events();
+        }
+    }
 }
 
 /// Builder for a group. Created with [`SystemExecutor::group`].
@@ -1,6 +1,10 @@
-#![allow(clippy::unnecessary_wraps)]
+#![allow(clippy::unnecessary_wraps, clippy::arc_with_non_send_sync)]
 
-use feather_ecs::{Ecs, HasEcs, SysResult, SystemExecutor};
+use std::sync::Arc;
+
+use feather_ecs::{
+    Ecs, HasEcs, HasResources, Resources, SysResult, SystemAccess, SystemContext, SystemExecutor,
+};
 
 #[derive(Debug, PartialEq, Eq)]
 struct Event {
@@ -72,3 +76,82 @@ fn events_observed_once() {
 
     assert_eq!(input.ecs.inner().len(), 1);
 }
+
+struct ParallelInput {
+    ecs: Ecs,
+    resources: Arc<Resources>,
+}
+
+impl HasEcs for ParallelInput {
+    fn ecs(&self) -> &Ecs {
+        &self.ecs
+    }
+
+    fn ecs_mut(&mut self) -> &mut Ecs {
+        &mut self.ecs
+    }
+}
+
+impl HasResources for ParallelInput {
+    fn resources(&self) -> Arc<Resources> {
+        Arc::clone(&self.resources)
+    }
+}
+
+struct IsFirstRun(bool);
+
+fn count_events(ecs: &Ecs) -> usize {
+    ecs.query::<&Event>().iter().count()
+}
+
+#[test]
+fn events_from_parallel_systems_observed_once() {
+    let mut systems = SystemExecutor::<ParallelInput>::new();
+    systems
+        .add_parallel_system(
+            SystemAccess::new()
+                .reads::<Event>()
+                .reads_resource::<IsFirstRun>(),
+            |context: &mut SystemContext| {
+                let expected = if context.resource::<IsFirstRun>()?.0 {
+                    0
+                } else {
+                    1
+                };
+                assert_eq!(count_events(context.ecs()), expected);
+                Ok(())
+            },
+        )
+        .add_parallel_system(
+            SystemAccess::new()
+                .writes::<Event>()
+                .reads_resource::<IsFirstRun>(),
+            |context: &mut SystemContext| {
+                if context.resource::<IsFirstRun>()?.0 {
+                    context.insert_event(Event { x: 10 })?;
+                } else {
+                    assert_eq!(count_events(context.ecs()), 0);
+                }
+                Ok(())
+            },
+        )
+        .add_system(|input: &mut ParallelInput| {
+            let expected = if input.resources.get::<IsFirstRun>()?.0 {
+                1
+            } else {
+                0
+            };
+            assert_eq!(count_events(&input.ecs), expected);
+            Ok(())
+        });
+
+    let mut resources = Resources::new();
+    resources.insert(IsFirstRun(true));
+    let mut input = ParallelInput {
+        ecs: Ecs::new(),
+        resources: Arc::new(resources),
+    };
+    systems.run(&mut input);
+    input.resources.get_mut::<IsFirstRun>().unwrap().0 = false;
+    systems.run(&mut input);
+}
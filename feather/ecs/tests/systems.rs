@@ -1,6 +1,14 @@
-#![allow(clippy::unnecessary_wraps)]
+#![allow(clippy::unnecessary_wraps, clippy::arc_with_non_send_sync)]
 
-use feather_ecs::{Ecs, HasEcs, SysResult, SystemExecutor};
+use std::{
+    sync::{mpsc, Arc},
+    time::Duration,
+};
+
+use feather_ecs::{
+    Ecs, HasEcs, HasResources, ResourceError, Resources, SysResult, SystemAccess, SystemContext,
+    SystemExecutor,
+};
 
 struct Input {
     x: i32,
@@ -40,3 +48,162 @@ fn systems_are_executed_in_order() {
     executor.run(&mut input);
     assert_eq!(input.x, 110);
 }
+
+struct ParallelInput {
+    ecs: Ecs,
+    resources: Arc<Resources>,
+}
+
+impl ParallelInput {
+    fn new(resources: Resources) -> Self {
+        Self {
+            ecs: Ecs::new(),
+            resources: Arc::new(resources),
+        }
+    }
+}
+
+impl HasEcs for ParallelInput {
+    fn ecs(&self) -> &Ecs {
+        &self.ecs
+    }
+
+    fn ecs_mut(&mut self) -> &mut Ecs {
+        &mut self.ecs
+    }
+}
+
+impl HasResources for ParallelInput {
+    fn resources(&self) -> Arc<Resources> {
+        Arc::clone(&self.resources)
+    }
+}
+
+#[derive(Default)]
+struct Log(Vec<&'static str>);
+
+#[derive(Default)]
+struct Other(u32);
+
+struct Position;
+struct Velocity;
+
+#[test]
+fn access_conflicts() {
+    let reads = SystemAccess::new().reads::<Position>();
+    let writes = SystemAccess::new().writes::<Position>();
+    assert!(!reads.conflicts_with(&reads));
+    assert!(reads.conflicts_with(&writes));
+    assert!(writes.conflicts_with(&reads));
+    assert!(!writes.conflicts_with(&SystemAccess::new().writes::<Velocity>()));
+
+    let reads = SystemAccess::new().reads_resource::<Log>();
+    let writes = SystemAccess::new().writes_resource::<Log>();
+    assert!(!reads.conflicts_with(&reads));
+    assert!(reads.conflicts_with(&writes));
+    assert!(writes.conflicts_with(&writes));
+    assert!(!writes.conflicts_with(&SystemAccess::new().writes_resource::<Other>()));
+}
+
+#[test]
+fn non_conflicting_systems_run_concurrently() {
+    let (first_tx, first_rx) = mpsc::sync_channel(1);
+    let (second_tx, second_rx) = mpsc::sync_channel(1);
+    let timeout = Duration::from_secs(5);
+
+    let mut executor = SystemExecutor::new();
+    // Each system waits for the other, which only
+    // succeeds if they run at the same time.
+    executor
+        .add_parallel_system(
+            SystemAccess::new().writes_resource::<Log>(),
+            move |context: &mut SystemContext| {
+                first_tx.send(())?;
+                second_rx.recv_timeout(timeout)?;
+                context.resource_mut::<Log>()?.0.push("first");
+                Ok(())
+            },
+        )
+        .add_parallel_system(
+            SystemAccess::new().writes_resource::<Other>(),
+            move |context: &mut SystemContext| {
+                second_tx.send(())?;
+                first_rx.recv_timeout(timeout)?;
+                context.resource_mut::<Other>()?.0 += 1;
+                Ok(())
+            },
+        );
+
+    let mut resources = Resources::new();
+    resources.insert(Log::default());
+    resources.insert(Other::default());
+    let mut input = ParallelInput::new(resources);
+    executor.run(&mut input);
+
+    assert_eq!(input.resources.get::<Log>().unwrap().0, ["first"]);
+    assert_eq!(input.resources.get::<Other>().unwrap().0, 1);
+}
+
+fn push(entry: &'static str) -> impl FnMut(&mut SystemContext) -> SysResult + Send {
+    move |context| {
+        context.resource_mut::<Log>()?.0.push(entry);
+        Ok(())
+    }
+}
+
+#[test]
+fn conflicting_systems_keep_their_order() {
+    let mut executor = SystemExecutor::new();
+    executor
+        .add_parallel_system(SystemAccess::new().writes_resource::<Log>(), push("a"))
+        .add_parallel_system(
+            SystemAccess::new().writes_resource::<Other>(),
+            |context: &mut SystemContext| {
+                context.resource_mut::<Other>()?.0 += 1;
+                Ok(())
+            },
+        )
+        .add_parallel_system(SystemAccess::new().writes_resource::<Log>(), push("b"))
+        .add_system(|input: &mut ParallelInput| {
+            input.resources.get_mut::<Log>()?.0.push("serial");
+            Ok(())
+        })
+        .add_parallel_system(SystemAccess::new().writes_resource::<Log>(), push("c"));
+
+    let mut resources = Resources::new();
+    resources.insert(Log::default());
+    resources.insert(Other::default());
+    let mut input = ParallelInput::new(resources);
+    executor.run(&mut input);
+    executor.run(&mut input);
+
+    assert_eq!(
+        input.resources.get::<Log>().unwrap().0,
+        ["a", "b", "serial", "c", "a", "b", "serial", "c"]
+    );
+    assert_eq!(input.resources.get::<Other>().unwrap().0, 2);
+}
+
+#[test]
+fn undeclared_resources_are_unavailable() {
+    let mut executor = SystemExecutor::new();
+    executor.add_parallel_system(
+        SystemAccess::new().reads_resource::<Other>(),
+        |context: &mut SystemContext| {
+            assert!(matches!(
+                context.resource::<Log>(),
+                Err(ResourceError::Undeclared(_))
+            ));
+            assert!(matches!(
+                context.resource_mut::<Other>(),
+                Err(ResourceError::Undeclared(_))
+            ));
+            assert_eq!(context.resource::<Other>()?.0, 7);
+            Ok(())
+        },
+    );
+
+    let mut resources = Resources::new();
+    resources.insert(Other(7));
+    executor.run(&mut ParallelInput::new(resources));
+}
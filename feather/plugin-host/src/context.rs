@@ -178,6 +178,8 @@ pub struct PluginContext {
 
     /// ID of the plugin.
     id: PluginId,
+    /// Name of the plugin.
+    name: String,
 
     /// Active entity builders for the plugin.
     pub entity_builders: ThreadPinned<Arena<EntityBuilder>>,
@@ -185,23 +187,25 @@ pub struct PluginContext {
 
 impl PluginContext {
     /// Creates a new WASM plugin context.
-    pub fn new_wasm(id: PluginId) -> Self {
+    pub fn new_wasm(id: PluginId, name: String) -> Self {
         Self {
             inner: Inner::Wasm(ThreadPinned::new(wasm::WasmPluginContext::new())),
             invoking_on_main_thread: AtomicBool::new(false),
             game: ThreadPinned::new(None),
             id,
+            name,
             entity_builders: ThreadPinned::new(Arena::new()),
         }
     }
 
     /// Creates a new native plugin context.
-    pub fn new_native(id: PluginId) -> Self {
+    pub fn new_native(id: PluginId, name: String) -> Self {
         Self {
             inner: Inner::Native(native::NativePluginContext::new()),
             invoking_on_main_thread: AtomicBool::new(false),
             game: ThreadPinned::new(None),
             id,
+            name,
             entity_builders: ThreadPinned::new(Arena::new()),
         }
     }
@@ -261,6 +265,11 @@ impl PluginContext {
         self.id
     }
 
+    /// Gets the name of the plugin.
+    pub fn plugin_name(&self) -> &str {
+        &self.name
+    }
+
     /// Accesses a byte slice in the plugin's memory space.
     ///
     /// # Safety
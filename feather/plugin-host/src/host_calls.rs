@@ -13,8 +13,10 @@ mod component;
 mod entity;
 mod entity_builder;
 mod event;
+mod goal;
 mod plugin_message;
 mod query;
+mod scheduler;
 mod system;
 
 macro_rules! host_calls {
@@ -51,8 +53,10 @@ use component::*;
 use entity::*;
 use entity_builder::*;
 use event::*;
+use goal::*;
 use plugin_message::*;
 use query::*;
+use scheduler::*;
 use system::*;
 
 host_calls! {
@@ -68,7 +72,11 @@ host_calls! {
     "entity_query" => entity_query,
     "entity_exists" => entity_exists,
     "entity_send_message" => entity_send_message,
+    "entity_has_permission" => entity_has_permission,
     "entity_send_title" => entity_send_title,
+    "entity_add_goal" => entity_add_goal,
+    "schedule_task" => schedule_task,
+    "cancel_task" => cancel_task,
     "block_get" => block_get,
     "block_set" => block_set,
     "block_fill_chunk_section" => block_fill_chunk_section,
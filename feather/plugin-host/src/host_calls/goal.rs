@@ -0,0 +1,99 @@
+use std::{cell::RefCell, rc::Rc};
+
+use feather_common::{
+    ai::{Goal, GoalControls, GoalSelector},
+    Game,
+};
+use feather_ecs::Entity;
+use feather_plugin_host_macros::host_function;
+use quill_common::goal::GoalAction;
+
+use crate::{
+    context::{PluginContext, PluginPtrMut},
+    PluginId, PluginManager,
+};
+
+#[host_function]
+pub fn entity_add_goal(
+    cx: &PluginContext,
+    entity: u64,
+    priority: u32,
+    controls: u32,
+    goal_data: PluginPtrMut<u8>,
+) -> anyhow::Result<()> {
+    let entity = Entity::from_bits(entity);
+    let goal = PluginGoal {
+        plugin: cx.plugin_id(),
+        data: goal_data,
+        controls: GoalControls::from_bits(controls),
+    };
+
+    let mut game = cx.game_mut();
+    if let Ok(mut goals) = game.ecs.get_mut::<GoalSelector>(entity) {
+        goals.add_goal(priority, goal);
+        return Ok(());
+    }
+
+    let mut goals = GoalSelector::new();
+    goals.add_goal(priority, goal);
+    game.ecs.insert(entity, goals)?;
+    Ok(())
+}
+
+/// A goal implemented by a plugin.
+///
+/// Each action is forwarded to the plugin's `quill_run_goal` export.
+struct PluginGoal {
+    plugin: PluginId,
+    data: PluginPtrMut<u8>,
+    controls: GoalControls,
+}
+
+// SAFETY: goals only run on the main thread, and the
+// data pointer is only dereferenced by the plugin.
+unsafe impl Send for PluginGoal {}
+unsafe impl Sync for PluginGoal {}
+
+impl PluginGoal {
+    fn run(&self, game: &mut Game, entity: Entity, action: GoalAction) -> u32 {
+        let result = (|| {
+            let plugin_manager = Rc::clone(&*game.resources.get::<Rc<RefCell<PluginManager>>>()?);
+            let plugin_manager = plugin_manager.borrow();
+            match plugin_manager.plugin(self.plugin) {
+                Some(plugin) => plugin.run_goal(game, self.data, entity, action),
+                None => Ok(0),
+            }
+        })();
+
+        result.unwrap_or_else(|e| {
+            log::error!("Failed to run plugin goal: {:?}", e);
+            0
+        })
+    }
+}
+
+impl Goal for PluginGoal {
+    fn controls(&self) -> GoalControls {
+        self.controls
+    }
+
+    fn can_start(&mut self, game: &mut Game, entity: Entity) -> bool {
+        self.run(game, entity, GoalAction::CanStart) != 0
+    }
+
+    fn should_continue(&mut self, game: &mut Game, entity: Entity) -> bool {
+        self.run(game, entity, GoalAction::ShouldContinue) != 0
+    }
+
+    fn start(&mut self, game: &mut Game, entity: Entity) {
+        self.run(game, entity, GoalAction::Start);
+    }
+
+    fn tick(&mut self, game: &mut Game, entity: Entity) {
+        self.run(game, entity, GoalAction::Tick);
+    }
+
+    fn stop(&mut self, game: &mut Game, entity: Entity) {
+        self.run(game, entity, GoalAction::Stop);
+    }
+}
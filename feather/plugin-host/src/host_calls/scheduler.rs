@@ -0,0 +1,75 @@
+use std::{cell::RefCell, rc::Rc};
+
+use feather_common::{
+    scheduler::{Scheduler, Task},
+    Game,
+};
+use feather_ecs::SysResult;
+use feather_plugin_host_macros::host_function;
+use quill_common::task::TaskAction;
+
+use crate::{
+    context::{PluginContext, PluginPtrMut},
+    PluginId, PluginManager,
+};
+
+#[host_function]
+pub fn schedule_task(
+    cx: &PluginContext,
+    task_data: PluginPtrMut<u8>,
+    delay: u64,
+    period: u64,
+) -> anyhow::Result<u64> {
+    let task = PluginTask {
+        plugin: cx.plugin_id(),
+        data: task_data,
+    };
+    let period = if period == 0 { None } else { Some(period) };
+
+    let game = cx.game_mut();
+    let handle = game
+        .resources
+        .get_mut::<Scheduler>()?
+        .schedule(delay, period, task);
+    Ok(handle.id())
+}
+
+#[host_function]
+pub fn cancel_task(cx: &PluginContext, task: u64) -> anyhow::Result<()> {
+    let game = cx.game_mut();
+    if let Some(handle) = game.resources.get::<Scheduler>()?.handle(task) {
+        handle.cancel();
+    }
+    Ok(())
+}
+
+/// A task scheduled by a plugin.
+///
+/// Runs are forwarded to the plugin's `quill_run_task` export.
+struct PluginTask {
+    plugin: PluginId,
+    data: PluginPtrMut<u8>,
+}
+
+impl PluginTask {
+    fn run_action(&self, game: &mut Game, action: TaskAction) -> SysResult {
+        let plugin_manager = Rc::clone(&*game.resources.get::<Rc<RefCell<PluginManager>>>()?);
+        let plugin_manager = plugin_manager.borrow();
+        if let Some(plugin) = plugin_manager.plugin(self.plugin) {
+            plugin.run_task(game, self.data, action)?;
+        }
+        Ok(())
+    }
+}
+
+impl Task for PluginTask {
+    fn run(&mut self, game: &mut Game) -> SysResult {
+        self.run_action(game, TaskAction::Run)
+    }
+
+    fn finish(&mut self, game: &mut Game) {
+        if let Err(e) = self.run_action(game, TaskAction::Finish) {
+            log::error!("Failed to finish plugin task: {:?}", e);
+        }
+    }
+}
@@ -130,6 +130,36 @@ impl PluginManager {
     pub fn plugin_mut(&mut self, id: PluginId) -> Option<&mut Plugin> {
         self.plugins.get_mut(id.0)
     }
+
+    /// Iterates over all loaded plugins.
+    pub fn plugins(&self) -> impl Iterator<Item = &Plugin> + '_ {
+        self.plugins.iter().map(|(_, plugin)| plugin)
+    }
+
+    /// Disables and unloads all plugins, in reverse order of loading.
+    ///
+    /// Plugins which fail to disable are still unloaded.
+    /// Returns the first error encountered.
+    pub fn disable_all(&mut self, game: &mut Game) -> anyhow::Result<()> {
+        let mut ids: Vec<usize> = self.plugins.iter().map(|(id, _)| id).collect();
+        ids.reverse();
+
+        let mut result = Ok(());
+        for id in ids {
+            let mut plugin = self.plugins.remove(id).unwrap();
+            if let Err(e) = plugin.disable(game) {
+                let e = e.context(format!(
+                    "failed to disable plugin {}",
+                    plugin.metadata().name
+                ));
+                log::error!("{:?}", e);
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+        result
+    }
 }
 
 #[cfg(all(feature = "cranelift", not(feature = "llvm")))]
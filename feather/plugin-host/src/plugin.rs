@@ -2,6 +2,8 @@ use std::sync::Arc;
 
 use anyhow::bail;
 use feather_common::Game;
+use feather_ecs::Entity;
+use quill_common::{goal::GoalAction, task::TaskAction};
 use quill_plugin_format::{PluginFile, PluginMetadata, PluginTarget, Triple};
 
 use crate::{
@@ -36,7 +38,7 @@ impl Plugin {
 
         let (inner, context) = match &file.metadata().target {
             PluginTarget::Wasm => {
-                let context = Arc::new(PluginContext::new_wasm(id));
+                let context = Arc::new(PluginContext::new_wasm(id, file.metadata().name.clone()));
                 let plugin =
                     wasm::WasmPlugin::load(manager, &context, file.module(), file.metadata())?;
                 (Inner::Wasm(plugin), context)
@@ -50,7 +52,7 @@ impl Plugin {
                     );
                 }
                 let plugin = native::NativePlugin::load(file.module())?;
-                let context = PluginContext::new_native(id);
+                let context = PluginContext::new_native(id, file.metadata().name.clone());
                 (Inner::Native(plugin), Arc::new(context))
             }
         };
@@ -62,6 +64,11 @@ impl Plugin {
         })
     }
 
+    /// Gets the metadata of the plugin.
+    pub fn metadata(&self) -> &PluginMetadata {
+        &self.metadata
+    }
+
     /// Enables the plugin.
     ///
     /// # Panics
@@ -81,6 +88,20 @@ impl Plugin {
         Ok(())
     }
 
+    /// Disables the plugin. Its systems must not be run afterwards.
+    pub fn disable(&mut self, game: &mut Game) -> anyhow::Result<()> {
+        self.context.enter(game, || match &self.inner {
+            Inner::Wasm(w) => w.disable(),
+            Inner::Native(n) => {
+                n.disable();
+                Ok(())
+            }
+        })?;
+
+        log::info!("Disabled plugin {}", self.metadata.name);
+        Ok(())
+    }
+
     /// Runs a plugin system.
     ///
     /// `data` must be the data pointer passed
@@ -94,6 +115,46 @@ impl Plugin {
             }
         })
     }
+
+    /// Runs one action of a plugin-defined AI goal
+    /// for `entity`, returning the plugin's result.
+    ///
+    /// `data` must be the data pointer passed
+    /// to the `entity_add_goal` host call.
+    pub fn run_goal(
+        &self,
+        game: &mut Game,
+        data: PluginPtrMut<u8>,
+        entity: Entity,
+        action: GoalAction,
+    ) -> anyhow::Result<u32> {
+        let entity = entity.to_bits();
+        let action = action as u32;
+        self.context.enter(game, || match &self.inner {
+            Inner::Wasm(w) => w.run_goal(data, entity, action),
+            Inner::Native(n) => Ok(n.run_goal(data, entity, action)),
+        })
+    }
+
+    /// Runs or finishes a task the plugin scheduled.
+    ///
+    /// `data` must be the data pointer passed
+    /// to the `schedule_task` host call.
+    pub fn run_task(
+        &self,
+        game: &mut Game,
+        data: PluginPtrMut<u8>,
+        action: TaskAction,
+    ) -> anyhow::Result<()> {
+        let action = action as u32;
+        self.context.enter(game, || match &self.inner {
+            Inner::Wasm(w) => w.run_task(data, action),
+            Inner::Native(n) => {
+                n.run_task(data, action);
+                Ok(())
+            }
+        })
+    }
 }
 
 enum Inner {
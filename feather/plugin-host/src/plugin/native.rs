@@ -22,11 +22,29 @@ pub struct NativePlugin {
     /// 3. Length of bincode-encoded vtable
     enable: unsafe extern "C" fn(*const u8, *const u8, usize),
 
+    /// The plugin's exported quill_disable function.
+    disable: unsafe extern "C" fn(),
+
     /// The plugin's exported quill_run_system function.
     ///
     /// Parameters:
     /// 1. Plugin data pointer for this system
     run_system: unsafe extern "C" fn(*mut u8),
+
+    /// The plugin's exported quill_run_goal function.
+    ///
+    /// Parameters:
+    /// 1. Plugin data pointer for this goal
+    /// 2. Bits of the entity running the goal
+    /// 3. The `GoalAction` to run
+    run_goal: unsafe extern "C" fn(*mut u8, u64, u32) -> u32,
+
+    /// The plugin's exported quill_run_task function.
+    ///
+    /// Parameters:
+    /// 1. Plugin data pointer for this task
+    /// 2. The `TaskAction` to run
+    run_task: unsafe extern "C" fn(*mut u8, u32),
 }
 
 impl NativePlugin {
@@ -50,17 +68,35 @@ impl NativePlugin {
                 .get("quill_setup".as_bytes())
                 .context("plugin is missing quill_setup export")?
         };
+        let disable = unsafe {
+            *library
+                .get("quill_disable".as_bytes())
+                .context("plugin is missing quill_disable export")?
+        };
         let run_system = unsafe {
             *library
                 .get("quill_run_system".as_bytes())
                 .context("plugin is missing quill_run_system export")?
         };
+        let run_goal = unsafe {
+            *library
+                .get("quill_run_goal".as_bytes())
+                .context("plugin is missing quill_run_goal export")?
+        };
+        let run_task = unsafe {
+            *library
+                .get("quill_run_task".as_bytes())
+                .context("plugin is missing quill_run_task export")?
+        };
 
         Ok(Self {
             tempfile: path,
             library,
             enable,
+            disable,
             run_system,
+            run_goal,
+            run_task,
         })
     }
 
@@ -80,6 +116,11 @@ impl NativePlugin {
         }
     }
 
+    pub fn disable(&self) {
+        // SAFETY: we assume the plugin is sound.
+        unsafe { (self.disable)() }
+    }
+
     fn generate_vtable(&self) -> Vec<u8> {
         let vtable = crate::host_calls::generate_vtable();
         bincode::serialize(&vtable).expect("can't serialize vtable")
@@ -89,4 +130,14 @@ impl NativePlugin {
         // SAFETY: we assume the plugin is sound.
         unsafe { (self.run_system)(data.as_native()) }
     }
+
+    pub fn run_goal(&self, data: PluginPtrMut<u8>, entity: u64, action: u32) -> u32 {
+        // SAFETY: we assume the plugin is sound.
+        unsafe { (self.run_goal)(data.as_native(), entity, action) }
+    }
+
+    pub fn run_task(&self, data: PluginPtrMut<u8>, action: u32) {
+        // SAFETY: we assume the plugin is sound.
+        unsafe { (self.run_task)(data.as_native(), action) }
+    }
 }
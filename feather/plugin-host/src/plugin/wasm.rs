@@ -20,8 +20,19 @@ pub struct WasmPlugin {
     /// Exported function to enable the plugin.
     enable: Function,
 
+    /// Exported function to disable the plugin.
+    disable: Function,
+
     /// Exported function to run a system given its data pointer.
     run_system: NativeFunc<u32>,
+
+    /// Exported function to run an action of an AI goal
+    /// given its data pointer, the entity, and the action.
+    run_goal: NativeFunc<(u32, u64, u32), u32>,
+
+    /// Exported function to run or finish a scheduled
+    /// task given its data pointer and the action.
+    run_task: NativeFunc<(u32, u32)>,
 }
 
 impl WasmPlugin {
@@ -46,12 +57,26 @@ impl WasmPlugin {
             .get_function("quill_run_system")?
             .native()?
             .clone();
+        let run_goal = instance
+            .exports
+            .get_function("quill_run_goal")?
+            .native()?
+            .clone();
+        let run_task = instance
+            .exports
+            .get_function("quill_run_task")?
+            .native()?
+            .clone();
         let enable = instance.exports.get_function("quill_setup")?.clone();
+        let disable = instance.exports.get_function("quill_disable")?.clone();
 
         Ok(Self {
             instance,
             run_system,
+            run_goal,
+            run_task,
             enable,
+            disable,
         })
     }
 
@@ -60,10 +85,29 @@ impl WasmPlugin {
         Ok(())
     }
 
+    pub fn disable(&self) -> anyhow::Result<()> {
+        self.disable.call(&[])?;
+        Ok(())
+    }
+
     pub fn run_system(&self, data_ptr: PluginPtrMut<u8>) -> anyhow::Result<()> {
         self.run_system.call(data_ptr.ptr as u32)?;
         Ok(())
     }
+
+    pub fn run_goal(
+        &self,
+        data_ptr: PluginPtrMut<u8>,
+        entity: u64,
+        action: u32,
+    ) -> anyhow::Result<u32> {
+        Ok(self.run_goal.call(data_ptr.ptr as u32, entity, action)?)
+    }
+
+    pub fn run_task(&self, data_ptr: PluginPtrMut<u8>, action: u32) -> anyhow::Result<()> {
+        self.run_task.call(data_ptr.ptr as u32, action)?;
+        Ok(())
+    }
 }
 
 fn generate_wasi_import_object(store: &Store, plugin_name: &str) -> anyhow::Result<ImportObject> {
@@ -1,5 +1,6 @@
 use crate::{io::VarInt, ProtocolVersion, Readable, Writeable};
 use aes::Aes128;
+use anyhow::bail;
 use bytes::BytesMut;
 use cfb8::{
     cipher::{AsyncStreamCipher, NewCipher},
@@ -17,6 +18,12 @@ pub type CompressionThreshold = usize;
 /// An encryption key for use with AES-CFB8.
 pub type CryptKey = [u8; 16];
 
+/// The maximum length of a packet allowed by the protocol,
+/// the largest value of a three-byte VarInt.
+pub const MAX_PACKET_LENGTH: usize = 2_097_151;
+/// The maximum length of a packet after decompression.
+pub const MAX_DECOMPRESSED_LENGTH: usize = 8_388_608;
+
 /// State to serialize and deserialize packets from a byte stream.
 #[derive(Default)]
 pub struct MinecraftCodec {
@@ -25,6 +32,11 @@ pub struct MinecraftCodec {
     crypt_key: Option<CryptKey>,
     /// If compression is enabled, then this is the compression threshold.
     compression: Option<CompressionThreshold>,
+    /// The protocol version packets are translated to and from.
+    version: ProtocolVersion,
+    /// The maximum length of received packets, if lower
+    /// than [`MAX_PACKET_LENGTH`].
+    max_packet_length: Option<usize>,
 
     /// A buffer of received bytes.
     received_buf: BytesMut,
@@ -51,8 +63,30 @@ impl MinecraftCodec {
         self.compression = Some(threshold);
     }
 
+    /// Sets the protocol version packets are translated to and from.
+    /// Defaults to [`ProtocolVersion::LATEST`].
+    pub fn set_version(&mut self, version: ProtocolVersion) {
+        self.version = version;
+    }
+
+    /// Gets the protocol version packets are translated to and from.
+    pub fn version(&self) -> ProtocolVersion {
+        self.version
+    }
+
+    /// Sets the maximum length of received packets. Receiving
+    /// a longer packet is an error. Can't exceed [`MAX_PACKET_LENGTH`].
+    pub fn set_max_packet_length(&mut self, length: usize) {
+        self.max_packet_length = Some(length.min(MAX_PACKET_LENGTH));
+    }
+
+    /// Gets the maximum length of received packets.
+    pub fn max_packet_length(&self) -> usize {
+        self.max_packet_length.unwrap_or(MAX_PACKET_LENGTH)
+    }
+
     /// Gets another `MinecraftCodec` with the same compression and encryption
-    /// parameters.
+    /// parameters and protocol version.
     pub fn clone_with_settings(&self) -> MinecraftCodec {
         MinecraftCodec {
             cryptor: self
@@ -60,6 +94,8 @@ impl MinecraftCodec {
                 .map(|key| AesCfb8::new_from_slices(&key, &key).expect("key size is invalid")),
             crypt_key: self.crypt_key,
             compression: self.compression,
+            version: self.version,
+            max_packet_length: self.max_packet_length,
             received_buf: BytesMut::new(),
             staging_buf: Vec::new(),
             compression_target: Vec::new(),
@@ -68,7 +104,7 @@ impl MinecraftCodec {
 
     /// Writes a packet into the provided writer.
     pub fn encode(&mut self, packet: &impl Writeable, output: &mut Vec<u8>) -> anyhow::Result<()> {
-        packet.write(&mut self.staging_buf, ProtocolVersion::V1_16_2)?;
+        packet.write(&mut self.staging_buf, self.version)?;
 
         if let Some(threshold) = self.compression {
             self.encode_compressed(output, threshold)?;
@@ -90,6 +126,7 @@ impl MinecraftCodec {
         output: &mut Vec<u8>,
         threshold: CompressionThreshold,
     ) -> anyhow::Result<()> {
+        let version = self.version;
         let (data_length, data) = if self.staging_buf.len() >= threshold {
             self.data_compressed()
         } else {
@@ -104,8 +141,8 @@ impl MinecraftCodec {
             .unwrap();
 
         let packet_length = data_length_bytes.position() as usize + data.len();
-        VarInt(packet_length as i32).write(output, ProtocolVersion::V1_16_2)?;
-        VarInt(data_length as i32).write(output, ProtocolVersion::V1_16_2)?;
+        VarInt(packet_length as i32).write(output, version)?;
+        VarInt(data_length as i32).write(output, version)?;
         output.extend_from_slice(data);
 
         self.compression_target.clear();
@@ -129,7 +166,7 @@ impl MinecraftCodec {
         // TODO: we should probably be able to determine the length without writing the packet,
         // which could remove an unnecessary copy.
         let length = self.staging_buf.len() as i32;
-        VarInt(length).write(output, ProtocolVersion::V1_16_2)?;
+        VarInt(length).write(output, self.version)?;
         output.extend_from_slice(&self.staging_buf);
 
         Ok(())
@@ -153,8 +190,15 @@ impl MinecraftCodec {
         T: Readable,
     {
         let mut cursor = Cursor::new(&self.received_buf[..]);
-        let packet = if let Ok(length) = VarInt::read(&mut cursor, ProtocolVersion::V1_16_2) {
+        let packet = if let Ok(length) = VarInt::read(&mut cursor, self.version) {
             let length_field_length = cursor.position() as usize;
+            if length.0 < 0 || length.0 as usize > self.max_packet_length() {
+                bail!(
+                    "packet length {} exceeds the maximum of {}",
+                    length.0,
+                    self.max_packet_length()
+                );
+            }
 
             if self.received_buf.len() - length_field_length >= length.0 as usize {
                 cursor = Cursor::new(
@@ -163,16 +207,25 @@ impl MinecraftCodec {
                 );
 
                 if self.compression.is_some() {
-                    let data_length = VarInt::read(&mut cursor, ProtocolVersion::V1_16_2)?;
+                    let data_length = VarInt::read(&mut cursor, self.version)?;
+                    if data_length.0 < 0 || data_length.0 as usize > MAX_DECOMPRESSED_LENGTH {
+                        bail!(
+                            "decompressed packet length {} exceeds the maximum of {}",
+                            data_length.0,
+                            MAX_DECOMPRESSED_LENGTH
+                        );
+                    }
                     if data_length.0 != 0 {
-                        let mut decoder =
+                        let decoder =
                             ZlibDecoder::new(&cursor.get_ref()[cursor.position() as usize..]);
-                        decoder.read_to_end(&mut self.compression_target)?;
+                        decoder
+                            .take(data_length.0 as u64)
+                            .read_to_end(&mut self.compression_target)?;
                         cursor = Cursor::new(&self.compression_target);
                     }
                 }
 
-                let packet = T::read(&mut cursor, ProtocolVersion::V1_16_2)?;
+                let packet = T::read(&mut cursor, self.version)?;
 
                 let bytes_read = length.0 as usize + length_field_length;
                 self.received_buf = self.received_buf.split_off(bytes_read);
@@ -189,3 +242,25 @@ impl MinecraftCodec {
         Ok(packet)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ClientPlayPacket;
+
+    #[test]
+    fn rejects_oversized_packets() {
+        let mut codec = MinecraftCodec::new();
+        codec.set_max_packet_length(16);
+        let mut bytes = Vec::new();
+        VarInt(17).write(&mut bytes, codec.version()).unwrap();
+        codec.accept(&bytes);
+        assert!(codec.next_packet::<ClientPlayPacket>().is_err());
+
+        let mut codec = MinecraftCodec::new();
+        let mut bytes = Vec::new();
+        VarInt(-1).write(&mut bytes, codec.version()).unwrap();
+        codec.accept(&bytes);
+        assert!(codec.next_packet::<ClientPlayPacket>().is_err());
+    }
+}
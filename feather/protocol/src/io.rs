@@ -558,16 +558,16 @@ impl Readable for Slot {
                 buffer.set_position(position + 1); // account for TAG_End, which is 1 byte
             }
 
-            let item = Item::from_id(item_id.try_into()?)
+            let item = Item::from_id(version.item_id_to_latest(item_id.try_into()?))
                 .ok_or_else(|| anyhow!("unknown item ID {}", item_id))?;
 
             // Todo fix: Panics if count is zero
-            Ok(Filled(
-                ItemStackBuilder::with_item(item)
-                    .count(count)
-                    .apply_damage(tags.and_then(|t| t.damage))
-                    .into(),
-            ))
+            let builder = ItemStackBuilder::with_item(item).count(count);
+            let builder = match tags {
+                Some(tags) => tags.apply(builder),
+                None => builder,
+            };
+            Ok(Filled(builder.into()))
         } else {
             Ok(Empty)
         }
@@ -579,7 +579,7 @@ impl Writeable for Slot {
         self.is_filled().write(buffer, version)?;
 
         if let Filled(stack) = self {
-            VarInt(stack.item().id() as i32).write(buffer, version)?;
+            VarInt(version.item_id(stack.item().id()) as i32).write(buffer, version)?;
             (stack.count() as u8).write(buffer, version)?;
 
             let tags: ItemNbt = stack.into();
@@ -859,14 +859,14 @@ impl Readable for BlockId {
     {
         let id = VarInt::read(buffer, version)?.0;
 
-        let block = BlockId::from_vanilla_id(id.try_into()?);
+        let block = BlockId::from_vanilla_id(version.block_state_id_to_latest(id.try_into()?));
         Ok(block)
     }
 }
 
 impl Writeable for BlockId {
     fn write(&self, buffer: &mut Vec<u8>, version: ProtocolVersion) -> anyhow::Result<()> {
-        VarInt(self.vanilla_id().into()).write(buffer, version)?;
+        VarInt(version.block_state_id(self.vanilla_id()).into()).write(buffer, version)?;
         Ok(())
     }
 }
@@ -3,6 +3,8 @@ use anyhow::anyhow;
 pub mod codec;
 pub mod io;
 pub mod packets;
+pub mod translation;
+pub mod version;
 
 use crate::codec::CompressionThreshold;
 #[doc(inline)]
@@ -17,13 +19,9 @@ pub use packets::{
     VariantOf,
 };
 
-pub type Slot = InventorySlot;
+pub use version::ProtocolVersion;
 
-/// A protocol version.
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
-pub enum ProtocolVersion {
-    V1_16_2,
-}
+pub type Slot = InventorySlot;
 
 /// A protocol state.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
@@ -67,6 +65,10 @@ impl ClientPacketCodec {
         self.codec.enable_compression(threshold)
     }
 
+    pub fn set_version(&mut self, version: ProtocolVersion) {
+        self.codec.set_version(version)
+    }
+
     /// Decodes a `ClientPacket` using the provided data.
     pub fn decode(&mut self, data: &[u8]) -> anyhow::Result<Option<ClientPacket>> {
         self.codec.accept(data);
@@ -129,6 +131,10 @@ impl ServerPacketCodec {
         self.codec.enable_compression(threshold)
     }
 
+    pub fn set_version(&mut self, version: ProtocolVersion) {
+        self.codec.set_version(version)
+    }
+
     /// Decodes a `ServerPacket` using the provided data.
     pub fn decode(&mut self, data: &[u8]) -> anyhow::Result<Option<ServerPacket>> {
         self.codec.accept(data);
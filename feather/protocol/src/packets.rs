@@ -224,10 +224,16 @@ macro_rules! packet_enum {
             where
                 Self: Sized
             {
-                let packet_id = VarInt::read(buffer, version)?.0;
-                match packet_id {
+                use crate::translation::Translate;
+
+                let packet_id = VarInt::read(buffer, version)?.0 as u32;
+                if let Some(packet) = Self::read_legacy(packet_id, buffer, version)? {
+                    return Ok(packet);
+                }
+
+                match Self::id_to_latest(version, packet_id) {
                     $(
-                        id if id == $id => Ok($ident::$packet($packet::read(buffer, version)?)),
+                        Some(id) if id == $id => Ok($ident::$packet($packet::read(buffer, version)?)),
                     )*
                     _ => Err(anyhow::anyhow!("unknown packet ID {}", packet_id)),
                 }
@@ -236,7 +242,12 @@ macro_rules! packet_enum {
 
         impl crate::Writeable for $ident {
             fn write(&self, buffer: &mut Vec<u8>, version: crate::ProtocolVersion) -> anyhow::Result<()> {
-                VarInt(self.id() as i32).write(buffer, version)?;
+                use crate::translation::Translate;
+
+                let id = Self::id_from_latest(version, self.id()).ok_or_else(|| {
+                    anyhow::anyhow!("packet ID {:#04x} does not exist in {}", self.id(), version)
+                })?;
+                VarInt(id as i32).write(buffer, version)?;
                 match self {
                     $(
                         $ident::$packet(packet) => {
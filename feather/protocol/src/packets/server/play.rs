@@ -219,12 +219,6 @@ def_enum! {
 }
 
 packets! {
-    MultiBlockChange {
-        chunk_section_coordinate u64;
-        dont_trust_edges bool;
-        records VarIntPrefixedVec<VarLong>;
-    }
-
     TabComplete {
         id VarInt;
         start VarInt;
@@ -667,11 +661,13 @@ impl Readable for Particle {
             }
             ParticleKind::Block(ref mut block_state) => {
                 let state = VarInt::read(buffer, version)?;
-                *block_state = BlockState::from_id(state.0 as u16).unwrap();
+                *block_state =
+                    BlockState::from_id(version.block_state_id_to_latest(state.0 as u16)).unwrap();
             }
             ParticleKind::FallingDust(ref mut block_state) => {
                 let state = VarInt::read(buffer, version)?;
-                *block_state = BlockState::from_id(state.0 as u16).unwrap();
+                *block_state =
+                    BlockState::from_id(version.block_state_id_to_latest(state.0 as u16)).unwrap();
             }
             ParticleKind::Item(ref mut item) => {
                 let _slot = Slot::read(buffer, version)?;
@@ -721,10 +717,10 @@ impl Writeable for Particle {
                 scale.write(buffer, version)?;
             }
             ParticleKind::Block(block_state) => {
-                VarInt(block_state.id() as i32).write(buffer, version)?;
+                VarInt(version.block_state_id(block_state.id()) as i32).write(buffer, version)?;
             }
             ParticleKind::FallingDust(block_state) => {
-                VarInt(block_state.id() as i32).write(buffer, version)?;
+                VarInt(version.block_state_id(block_state.id()) as i32).write(buffer, version)?;
             }
             ParticleKind::Item(_item) => {
                 todo![];
@@ -988,7 +984,7 @@ def_enum! {
         1 = LerpSize {
             old_diameter f64;
             new_diameter f64;
-            speed u64;
+            speed VarLong;
         },
         2 = SetCenter {
             x f64;
@@ -1054,6 +1050,52 @@ packets! {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct MultiBlockChange {
+    pub chunk_section_coordinate: u64,
+    pub dont_trust_edges: bool,
+    /// Each record holds a block state ID, shifted left by 12 bits,
+    /// followed by the X, Z and Y coordinates within the section.
+    pub records: Vec<VarLong>,
+}
+
+/// Replaces the block state ID of a `MultiBlockChange` record.
+fn map_record_block(record: VarLong, map: impl Fn(u16) -> u16) -> VarLong {
+    let block = map((record.0 >> 12) as u16);
+    VarLong(i64::from(block) << 12 | (record.0 & 0xFFF))
+}
+
+impl Readable for MultiBlockChange {
+    fn read(buffer: &mut Cursor<&[u8]>, version: ProtocolVersion) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        let chunk_section_coordinate = u64::read(buffer, version)?;
+        let dont_trust_edges = bool::read(buffer, version)?;
+        let records = Vec::from(VarIntPrefixedVec::<VarLong>::read(buffer, version)?)
+            .into_iter()
+            .map(|record| map_record_block(record, |id| version.block_state_id_to_latest(id)))
+            .collect();
+        Ok(Self {
+            chunk_section_coordinate,
+            dont_trust_edges,
+            records,
+        })
+    }
+}
+
+impl Writeable for MultiBlockChange {
+    fn write(&self, buffer: &mut Vec<u8>, version: ProtocolVersion) -> anyhow::Result<()> {
+        self.chunk_section_coordinate.write(buffer, version)?;
+        self.dont_trust_edges.write(buffer, version)?;
+        VarInt(self.records.len() as i32).write(buffer, version)?;
+        for &record in &self.records {
+            map_record_block(record, |id| version.block_state_id(id)).write(buffer, version)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct EntityEquipment {
     pub entity_id: i32,
@@ -132,11 +132,22 @@ fn encode_section(
     if let Some(palette) = section.blocks().palette() {
         VarInt(palette.len() as i32).write(buffer, version)?;
         for &block in palette.as_slice() {
-            VarInt(block.vanilla_id() as i32).write(buffer, version)?;
+            VarInt(version.block_state_id(block.vanilla_id()) as i32).write(buffer, version)?;
         }
     }
 
-    let data = section.blocks().data().as_u64_slice();
+    // Sections without a palette store block state IDs directly.
+    let remapped;
+    let data = if section.blocks().palette().is_none() && !version.has_latest_block_state_ids() {
+        let mut data = section.blocks().data().clone();
+        for (index, id) in section.blocks().data().iter().enumerate() {
+            data.set(index, version.block_state_id(id as u16).into());
+        }
+        remapped = data;
+        remapped.as_u64_slice()
+    } else {
+        section.blocks().data().as_u64_slice()
+    };
     VarInt(data.len() as i32).write(buffer, version)?;
     for &x in data {
         x.write(buffer, version)?;
@@ -214,7 +225,9 @@ impl Readable for ChunkData {
                             let pallete_length = VarInt::read(buffer, version)?.0 as usize;
                             for _ in 0..pallete_length {
                                 let block_id = VarInt::read(buffer, version)?.0;
-                                pallete.index_or_insert(BlockId::from_vanilla_id(block_id as u16));
+                                pallete.index_or_insert(BlockId::from_vanilla_id(
+                                    version.block_state_id_to_latest(block_id as u16),
+                                ));
                             }
                         }
                     }
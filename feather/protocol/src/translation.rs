@@ -0,0 +1,106 @@
+//! Translation of packets between older protocol versions and the
+//! latest version, which the packet definitions follow.
+//!
+//! Every packet enum implements [`Translate`]. Packets which only moved
+//! to a different ID are handled by per-version [`IdTable`]s, while packets
+//! whose fields changed are read in their old layout and converted into
+//! the packets replacing them.
+//!
+//! 1.16.2 through 1.16.5 share the same packet IDs and layouts,
+//! so no packet enum overrides the defaults yet.
+
+use std::{io::Cursor, ops::RangeInclusive};
+
+use crate::{
+    ClientHandshakePacket, ClientLoginPacket, ClientPlayPacket, ClientStatusPacket,
+    ProtocolVersion, ServerLoginPacket, ServerPlayPacket, ServerStatusPacket,
+};
+
+/// The packet IDs of an older version which differ from the latest version.
+#[derive(Debug)]
+pub struct IdTable {
+    /// IDs, in the latest version, of packets which don't exist in the older version.
+    pub removed: &'static [u32],
+    /// Ranges of IDs in the latest version, along with the offset
+    /// added to them to get the IDs in the older version.
+    pub shifted: &'static [(RangeInclusive<u32>, i32)],
+}
+
+impl IdTable {
+    /// Converts an ID of the older version to the latest version,
+    /// returning `None` if no packet of the latest version has it.
+    pub fn to_latest(&self, id: u32) -> Option<u32> {
+        let latest = self
+            .shifted
+            .iter()
+            .map(|(range, offset)| (range, (id as i32 - offset) as u32))
+            .find(|(range, latest)| range.contains(latest))
+            .map_or(id, |(_, latest)| latest);
+        let shifted_away = self.shifted.iter().any(|(range, _)| range.contains(&id));
+        if self.removed.contains(&latest) || (latest == id && shifted_away) {
+            None
+        } else {
+            Some(latest)
+        }
+    }
+
+    /// Converts an ID of the latest version to the older version,
+    /// returning `None` if the packet doesn't exist in the older version.
+    pub fn from_latest(&self, id: u32) -> Option<u32> {
+        if self.removed.contains(&id) {
+            return None;
+        }
+        let offset = self
+            .shifted
+            .iter()
+            .find(|(range, _)| range.contains(&id))
+            .map_or(0, |(_, offset)| *offset);
+        Some((id as i32 + offset) as u32)
+    }
+}
+
+/// Translates a packet enum between protocol versions.
+pub trait Translate: Sized {
+    /// Returns the packet IDs of the given version which differ from
+    /// the latest version, or `None` if all IDs are the same.
+    fn id_table(_version: ProtocolVersion) -> Option<&'static IdTable> {
+        None
+    }
+
+    /// Reads a packet whose fields in the given version differ from the
+    /// latest version, converting it into the latest version's packet.
+    ///
+    /// Returns `None` if the packet with the given ID is
+    /// read the same way as in the latest version.
+    fn read_legacy(
+        _id: u32,
+        _buffer: &mut Cursor<&[u8]>,
+        _version: ProtocolVersion,
+    ) -> anyhow::Result<Option<Self>> {
+        Ok(None)
+    }
+
+    /// Converts a packet ID of the given version to the latest version.
+    fn id_to_latest(version: ProtocolVersion, id: u32) -> Option<u32> {
+        match Self::id_table(version) {
+            Some(table) => table.to_latest(id),
+            None => Some(id),
+        }
+    }
+
+    /// Converts a packet ID of the latest version to the given version.
+    fn id_from_latest(version: ProtocolVersion, id: u32) -> Option<u32> {
+        match Self::id_table(version) {
+            Some(table) => table.from_latest(id),
+            None => Some(id),
+        }
+    }
+}
+
+impl Translate for ClientHandshakePacket {}
+impl Translate for ClientStatusPacket {}
+impl Translate for ClientLoginPacket {}
+impl Translate for ServerStatusPacket {}
+impl Translate for ServerLoginPacket {}
+impl Translate for ServerPlayPacket {}
+impl Translate for ClientPlayPacket {}
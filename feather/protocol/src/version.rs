@@ -0,0 +1,109 @@
+//! The protocol versions supported by Feather.
+//!
+//! Packet definitions follow the latest version. The differences of
+//! older versions are handled by the [`translation`](crate::translation)
+//! layer and by the ID mappings of [`ProtocolVersion`].
+
+use std::fmt::{self, Display, Formatter};
+
+/// A protocol version.
+///
+/// Versions are ordered from oldest to newest, so that
+/// changes can be checked with comparisons.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ProtocolVersion {
+    V1_16_2,
+    V1_16_3,
+    /// Also used by 1.16.4.
+    #[default]
+    V1_16_5,
+}
+
+impl ProtocolVersion {
+    /// The version which the packet definitions follow.
+    pub const LATEST: ProtocolVersion = ProtocolVersion::V1_16_5;
+
+    /// All supported versions, from oldest to newest.
+    pub const SUPPORTED: &'static [ProtocolVersion] = &[
+        ProtocolVersion::V1_16_2,
+        ProtocolVersion::V1_16_3,
+        ProtocolVersion::V1_16_5,
+    ];
+
+    /// Gets the version with the given protocol number, as sent
+    /// in the handshake, or `None` if it isn't supported.
+    pub fn from_id(id: i32) -> Option<Self> {
+        Self::SUPPORTED
+            .iter()
+            .copied()
+            .find(|version| version.id() == id)
+    }
+
+    /// Returns the protocol number of this version.
+    pub fn id(self) -> i32 {
+        match self {
+            ProtocolVersion::V1_16_2 => 751,
+            ProtocolVersion::V1_16_3 => 753,
+            ProtocolVersion::V1_16_5 => 754,
+        }
+    }
+
+    /// Returns the names of the game versions using this protocol version.
+    pub fn name(self) -> &'static str {
+        match self {
+            ProtocolVersion::V1_16_2 => "1.16.2",
+            ProtocolVersion::V1_16_3 => "1.16.3",
+            ProtocolVersion::V1_16_5 => "1.16.4/1.16.5",
+        }
+    }
+
+    /// Converts a block state ID of the latest version to this version.
+    ///
+    /// 1.16.2 through 1.16.5 share block state IDs.
+    pub fn block_state_id(self, latest_id: u16) -> u16 {
+        match self {
+            ProtocolVersion::V1_16_2 | ProtocolVersion::V1_16_3 | ProtocolVersion::V1_16_5 => {
+                latest_id
+            }
+        }
+    }
+
+    /// Converts a block state ID of this version to the latest version.
+    pub fn block_state_id_to_latest(self, id: u16) -> u16 {
+        match self {
+            ProtocolVersion::V1_16_2 | ProtocolVersion::V1_16_3 | ProtocolVersion::V1_16_5 => id,
+        }
+    }
+
+    /// Determines whether this version uses the block state IDs
+    /// of the latest version, so that block data needs no remapping.
+    pub fn has_latest_block_state_ids(self) -> bool {
+        match self {
+            ProtocolVersion::V1_16_2 | ProtocolVersion::V1_16_3 | ProtocolVersion::V1_16_5 => true,
+        }
+    }
+
+    /// Converts an item ID of the latest version to this version.
+    ///
+    /// 1.16.2 through 1.16.5 share item IDs.
+    pub fn item_id(self, latest_id: u32) -> u32 {
+        match self {
+            ProtocolVersion::V1_16_2 | ProtocolVersion::V1_16_3 | ProtocolVersion::V1_16_5 => {
+                latest_id
+            }
+        }
+    }
+
+    /// Converts an item ID of this version to the latest version.
+    pub fn item_id_to_latest(self, id: u32) -> u32 {
+        match self {
+            ProtocolVersion::V1_16_2 | ProtocolVersion::V1_16_3 | ProtocolVersion::V1_16_5 => id,
+        }
+    }
+}
+
+impl Display for ProtocolVersion {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
@@ -0,0 +1,518 @@
+//! The whitelist, ban lists and operator list.
+//!
+//! The lists are stored in the JSON files used by vanilla servers, so
+//! existing files can be reused. They are checked when players log in
+//! and can be changed at runtime through commands.
+
+use std::{
+    fs,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::Context;
+use base::Text;
+use parking_lot::RwLock;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use time::{format_description::FormatItem, macros::format_description, OffsetDateTime};
+use uuid::Uuid;
+
+pub const WHITELIST_FILE: &str = "whitelist.json";
+pub const OPS_FILE: &str = "ops.json";
+pub const BANNED_PLAYERS_FILE: &str = "banned-players.json";
+pub const BANNED_IPS_FILE: &str = "banned-ips.json";
+
+/// Reason recorded for bans made without one.
+pub const DEFAULT_BAN_REASON: &str = "Banned by an operator.";
+
+/// Format of the dates in the ban lists.
+const DATE_FORMAT: &[FormatItem] = format_description!(
+    "[year]-[month]-[day] [hour]:[minute]:[second] [offset_hour sign:mandatory][offset_minute]"
+);
+
+/// Value of `expires` for bans which never expire.
+const FOREVER: &str = "forever";
+
+/// A player in the whitelist.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserEntry {
+    pub uuid: Uuid,
+    pub name: String,
+}
+
+/// A player in the operator list.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpEntry {
+    pub uuid: Uuid,
+    pub name: String,
+    /// The permission level of the operator.
+    pub level: u8,
+    /// Whether the operator can join when the server is full.
+    #[serde(default)]
+    pub bypasses_player_limit: bool,
+}
+
+/// When, why and by whom a player or IP address was banned.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ban {
+    #[serde(with = "date")]
+    pub created: OffsetDateTime,
+    /// The name of who made the ban.
+    pub source: String,
+    /// When the ban expires, or `None` if it is permanent.
+    #[serde(with = "expiry")]
+    pub expires: Option<OffsetDateTime>,
+    pub reason: String,
+}
+
+impl Ban {
+    /// Creates a permanent ban made now.
+    pub fn new(source: impl Into<String>, reason: Option<String>) -> Self {
+        Self {
+            created: now(),
+            source: source.into(),
+            expires: None,
+            reason: reason.unwrap_or_else(|| DEFAULT_BAN_REASON.to_owned()),
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires.is_some_and(|expires| expires <= now())
+    }
+
+    /// Returns the message shown to a banned client which tries to log in.
+    fn message(&self, reason_key: &'static str, expiration_key: &'static str) -> Text {
+        let message = Text::translate_with(reason_key, vec![self.reason.clone()]);
+        match self.expires {
+            Some(expires) => {
+                message + Text::translate_with(expiration_key, vec![format_date(expires)])
+            }
+            None => message,
+        }
+    }
+}
+
+/// A banned player.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerBan {
+    pub uuid: Uuid,
+    pub name: String,
+    #[serde(flatten)]
+    pub ban: Ban,
+}
+
+/// A banned IP address.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IpBan {
+    pub ip: IpAddr,
+    #[serde(flatten)]
+    pub ban: Ban,
+}
+
+#[derive(Debug, Default)]
+struct Lists {
+    whitelist_enabled: bool,
+    whitelist: Vec<UserEntry>,
+    ops: Vec<OpEntry>,
+    banned_players: Vec<PlayerBan>,
+    banned_ips: Vec<IpBan>,
+}
+
+/// The whitelist, ban lists and operator list.
+///
+/// Can be cloned to create a new handle.
+#[derive(Clone, Debug)]
+pub struct AccessLists {
+    dir: Arc<Path>,
+    inner: Arc<RwLock<Lists>>,
+}
+
+impl AccessLists {
+    /// Loads the lists from the given directory,
+    /// creating the files which don't exist.
+    pub fn load(dir: impl Into<PathBuf>, whitelist_enabled: bool) -> anyhow::Result<Self> {
+        let lists = Self {
+            dir: dir.into().into(),
+            inner: Arc::new(RwLock::new(Lists {
+                whitelist_enabled,
+                ..Default::default()
+            })),
+        };
+        lists.reload()?;
+        Ok(lists)
+    }
+
+    /// Reloads all lists from their files.
+    pub fn reload(&self) -> anyhow::Result<()> {
+        let whitelist = self.load_file(WHITELIST_FILE)?;
+        let ops = self.load_file(OPS_FILE)?;
+        let banned_players = self.load_file(BANNED_PLAYERS_FILE)?;
+        let banned_ips = self.load_file(BANNED_IPS_FILE)?;
+
+        let mut lists = self.inner.write();
+        lists.whitelist = whitelist;
+        lists.ops = ops;
+        lists.banned_players = banned_players;
+        lists.banned_ips = banned_ips;
+        Ok(())
+    }
+
+    /// Checks whether a player may log in. Returns the
+    /// message to disconnect the player with if not.
+    pub fn check_login(&self, uuid: Uuid, ip: IpAddr) -> Result<(), Text> {
+        if let Some(ban) = self.player_ban(uuid) {
+            return Err(ban.ban.message(
+                "multiplayer.disconnect.banned.reason",
+                "multiplayer.disconnect.banned.expiration",
+            ));
+        }
+        if !self.is_whitelisted(uuid) {
+            return Err(Text::translate("multiplayer.disconnect.not_whitelisted"));
+        }
+        if let Some(ban) = self.ip_ban(ip) {
+            return Err(ban.ban.message(
+                "multiplayer.disconnect.banned_ip.reason",
+                "multiplayer.disconnect.banned_ip.expiration",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn is_whitelist_enabled(&self) -> bool {
+        self.inner.read().whitelist_enabled
+    }
+
+    /// Enables or disables the whitelist until the server restarts.
+    pub fn set_whitelist_enabled(&self, enabled: bool) {
+        self.inner.write().whitelist_enabled = enabled;
+    }
+
+    /// Returns whether the player may join with the
+    /// whitelist enabled. Operators are always allowed.
+    pub fn is_whitelisted(&self, uuid: Uuid) -> bool {
+        let lists = self.inner.read();
+        !lists.whitelist_enabled
+            || lists.whitelist.iter().any(|entry| entry.uuid == uuid)
+            || lists.ops.iter().any(|op| op.uuid == uuid)
+    }
+
+    pub fn whitelist(&self) -> Vec<UserEntry> {
+        self.inner.read().whitelist.clone()
+    }
+
+    /// Adds a player to the whitelist. Returns `false` if already whitelisted.
+    pub fn add_to_whitelist(&self, entry: UserEntry) -> anyhow::Result<bool> {
+        let mut lists = self.inner.write();
+        if lists.whitelist.iter().any(|e| e.uuid == entry.uuid) {
+            return Ok(false);
+        }
+        lists.whitelist.push(entry);
+        self.save_file(WHITELIST_FILE, &lists.whitelist)?;
+        Ok(true)
+    }
+
+    /// Removes the player with the given name from the whitelist,
+    /// returning the removed entry.
+    pub fn remove_from_whitelist(&self, name: &str) -> anyhow::Result<Option<UserEntry>> {
+        let mut lists = self.inner.write();
+        let removed = remove_by(&mut lists.whitelist, |e| e.name.eq_ignore_ascii_case(name));
+        if removed.is_some() {
+            self.save_file(WHITELIST_FILE, &lists.whitelist)?;
+        }
+        Ok(removed)
+    }
+
+    /// Returns the operator entry of the given player.
+    pub fn op(&self, uuid: Uuid) -> Option<OpEntry> {
+        self.inner
+            .read()
+            .ops
+            .iter()
+            .find(|op| op.uuid == uuid)
+            .cloned()
+    }
+
+    pub fn ops(&self) -> Vec<OpEntry> {
+        self.inner.read().ops.clone()
+    }
+
+    /// Makes a player an operator. Returns `false` if already an operator.
+    pub fn add_op(&self, entry: OpEntry) -> anyhow::Result<bool> {
+        let mut lists = self.inner.write();
+        if lists.ops.iter().any(|op| op.uuid == entry.uuid) {
+            return Ok(false);
+        }
+        lists.ops.push(entry);
+        self.save_file(OPS_FILE, &lists.ops)?;
+        Ok(true)
+    }
+
+    /// Removes the operator with the given name, returning the removed entry.
+    pub fn remove_op(&self, name: &str) -> anyhow::Result<Option<OpEntry>> {
+        let mut lists = self.inner.write();
+        let removed = remove_by(&mut lists.ops, |op| op.name.eq_ignore_ascii_case(name));
+        if removed.is_some() {
+            self.save_file(OPS_FILE, &lists.ops)?;
+        }
+        Ok(removed)
+    }
+
+    /// Returns the ban of the given player, unless it expired.
+    pub fn player_ban(&self, uuid: Uuid) -> Option<PlayerBan> {
+        self.inner
+            .read()
+            .banned_players
+            .iter()
+            .find(|ban| ban.uuid == uuid && !ban.ban.is_expired())
+            .cloned()
+    }
+
+    /// Returns the bans of players which didn't expire.
+    pub fn banned_players(&self) -> Vec<PlayerBan> {
+        let lists = self.inner.read();
+        active_bans(&lists.banned_players, |ban| &ban.ban)
+    }
+
+    /// Bans a player, replacing any previous ban.
+    pub fn ban_player(&self, ban: PlayerBan) -> anyhow::Result<()> {
+        let mut lists = self.inner.write();
+        lists.banned_players.retain(|b| b.uuid != ban.uuid);
+        lists.banned_players.push(ban);
+        let bans = active_bans(&lists.banned_players, |ban| &ban.ban);
+        self.save_file(BANNED_PLAYERS_FILE, &bans)
+    }
+
+    /// Removes the ban of the player with the given name,
+    /// returning the removed ban.
+    pub fn pardon_player(&self, name: &str) -> anyhow::Result<Option<PlayerBan>> {
+        let mut lists = self.inner.write();
+        let removed = remove_by(&mut lists.banned_players, |ban| {
+            ban.name.eq_ignore_ascii_case(name)
+        });
+        if removed.is_some() {
+            let bans = active_bans(&lists.banned_players, |ban| &ban.ban);
+            self.save_file(BANNED_PLAYERS_FILE, &bans)?;
+        }
+        Ok(removed)
+    }
+
+    /// Returns the ban of the given IP address, unless it expired.
+    pub fn ip_ban(&self, ip: IpAddr) -> Option<IpBan> {
+        self.inner
+            .read()
+            .banned_ips
+            .iter()
+            .find(|ban| ban.ip == ip && !ban.ban.is_expired())
+            .cloned()
+    }
+
+    /// Returns the bans of IP addresses which didn't expire.
+    pub fn banned_ips(&self) -> Vec<IpBan> {
+        let lists = self.inner.read();
+        active_bans(&lists.banned_ips, |ban| &ban.ban)
+    }
+
+    /// Bans an IP address, replacing any previous ban.
+    pub fn ban_ip(&self, ban: IpBan) -> anyhow::Result<()> {
+        let mut lists = self.inner.write();
+        lists.banned_ips.retain(|b| b.ip != ban.ip);
+        lists.banned_ips.push(ban);
+        let bans = active_bans(&lists.banned_ips, |ban| &ban.ban);
+        self.save_file(BANNED_IPS_FILE, &bans)
+    }
+
+    /// Removes the ban of the given IP address, returning `false` if it wasn't banned.
+    pub fn pardon_ip(&self, ip: IpAddr) -> anyhow::Result<bool> {
+        let mut lists = self.inner.write();
+        if remove_by(&mut lists.banned_ips, |ban| ban.ip == ip).is_none() {
+            return Ok(false);
+        }
+        let bans = active_bans(&lists.banned_ips, |ban| &ban.ban);
+        self.save_file(BANNED_IPS_FILE, &bans)?;
+        Ok(true)
+    }
+
+    fn load_file<T: DeserializeOwned + Serialize>(&self, name: &str) -> anyhow::Result<Vec<T>> {
+        let path = self.dir.join(name);
+        if !path.exists() {
+            let entries = Vec::new();
+            self.save_file(name, &entries)?;
+            return Ok(entries);
+        }
+        let json = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        serde_json::from_str(&json).with_context(|| format!("invalid {}", path.display()))
+    }
+
+    fn save_file<T: Serialize>(&self, name: &str, entries: &[T]) -> anyhow::Result<()> {
+        let path = self.dir.join(name);
+        fs::write(&path, serde_json::to_string_pretty(entries)?)
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+}
+
+fn remove_by<T>(entries: &mut Vec<T>, predicate: impl FnMut(&T) -> bool) -> Option<T> {
+    let index = entries.iter().position(predicate)?;
+    Some(entries.remove(index))
+}
+
+fn active_bans<T: Clone>(bans: &[T], ban: impl Fn(&T) -> &Ban) -> Vec<T> {
+    bans.iter()
+        .filter(|b| !ban(b).is_expired())
+        .cloned()
+        .collect()
+}
+
+fn now() -> OffsetDateTime {
+    OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc())
+}
+
+/// Formats a date like the ban lists.
+pub fn format_date(date: OffsetDateTime) -> String {
+    date.format(DATE_FORMAT)
+        .expect("dates can always be formatted")
+}
+
+mod date {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use time::OffsetDateTime;
+
+    pub fn serialize<S: Serializer>(
+        date: &OffsetDateTime,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::format_date(*date))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<OffsetDateTime, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        OffsetDateTime::parse(&string, super::DATE_FORMAT).map_err(D::Error::custom)
+    }
+}
+
+mod expiry {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use time::OffsetDateTime;
+
+    use super::FOREVER;
+
+    pub fn serialize<S: Serializer>(
+        expires: &Option<OffsetDateTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match expires {
+            Some(date) => super::date::serialize(date, serializer),
+            None => serializer.serialize_str(FOREVER),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<OffsetDateTime>, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        if string == FOREVER {
+            return Ok(None);
+        }
+        OffsetDateTime::parse(&string, super::DATE_FORMAT)
+            .map(Some)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use time::Duration;
+
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("feather-access-{}-{}", name, Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn read_vanilla_files() {
+        let dir = temp_dir("vanilla");
+        let uuid = "069a79f4-44e9-4726-a5be-fca90e38aaf5";
+        fs::write(
+            dir.join(BANNED_PLAYERS_FILE),
+            format!(
+                r#"[{{"uuid": "{}", "name": "Notch", "created": "2021-03-14 15:09:26 +0100",
+                    "source": "Server", "expires": "forever", "reason": "Griefing"}}]"#,
+                uuid
+            ),
+        )
+        .unwrap();
+        fs::write(
+            dir.join(OPS_FILE),
+            format!(
+                r#"[{{"uuid": "{}", "name": "Notch", "level": 4, "bypassesPlayerLimit": true}}]"#,
+                uuid
+            ),
+        )
+        .unwrap();
+
+        let lists = AccessLists::load(&dir, false).unwrap();
+        let uuid = uuid.parse().unwrap();
+        let ban = lists.player_ban(uuid).unwrap();
+        assert_eq!(ban.ban.reason, "Griefing");
+        assert_eq!(ban.ban.expires, None);
+        assert_eq!(format_date(ban.ban.created), "2021-03-14 15:09:26 +0100");
+        assert!(lists.op(uuid).unwrap().bypasses_player_limit);
+        assert!(dir.join(WHITELIST_FILE).exists());
+        assert!(dir.join(BANNED_IPS_FILE).exists());
+        assert!(lists.check_login(uuid, Ipv4Addr::LOCALHOST.into()).is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn check_login() {
+        let dir = temp_dir("login");
+        let lists = AccessLists::load(&dir, true).unwrap();
+        let ip = IpAddr::from(Ipv4Addr::new(10, 0, 0, 1));
+        let player = UserEntry {
+            uuid: Uuid::new_v4(),
+            name: "Player".to_owned(),
+        };
+
+        assert!(lists.check_login(player.uuid, ip).is_err());
+        assert!(lists.add_to_whitelist(player.clone()).unwrap());
+        assert!(!lists.add_to_whitelist(player.clone()).unwrap());
+        assert!(lists.check_login(player.uuid, ip).is_ok());
+
+        lists
+            .ban_ip(IpBan {
+                ip,
+                ban: Ban::new("Server", None),
+            })
+            .unwrap();
+        assert!(lists.check_login(player.uuid, ip).is_err());
+        assert!(lists.pardon_ip(ip).unwrap());
+
+        let mut ban = Ban::new("Server", None);
+        ban.expires = Some(now() - Duration::minutes(1));
+        lists
+            .ban_player(PlayerBan {
+                uuid: player.uuid,
+                name: player.name.clone(),
+                ban,
+            })
+            .unwrap();
+        assert!(lists.check_login(player.uuid, ip).is_ok());
+        assert!(lists.banned_players().is_empty());
+
+        // Changes are written to the files.
+        lists.reload().unwrap();
+        assert_eq!(lists.whitelist(), vec![player]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
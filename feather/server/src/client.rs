@@ -1,21 +1,28 @@
 use std::{
-    cell::{Cell, RefCell},
     collections::VecDeque,
     io::Cursor,
-    sync::Arc,
+    net::IpAddr,
+    sync::{
+        atomic::{AtomicBool, AtomicI32, AtomicU8, Ordering},
+        Arc,
+    },
 };
 
 use ahash::AHashSet;
 use flume::{Receiver, Sender};
+use parking_lot::Mutex;
 use slab::Slab;
 use uuid::Uuid;
 
 use base::{
-    BlockId, ChunkHandle, ChunkPosition, EntityKind, EntityMetadata, Gamemode, Position,
-    ProfileProperty, Text, ValidBlockPosition,
+    BlockId, ChunkHandle, ChunkPosition, Difficulty, EntityKind, EntityMetadata, Gamemode,
+    Position, ProfileProperty, StatusEffect, Text, ValidBlockPosition, Vec3d,
 };
 use common::{
     chat::{ChatKind, ChatMessage},
+    commands::MAX_PERMISSION_LEVEL,
+    window::BackingWindow,
+    world_border::{WorldBorder, WorldBorderUpdate, MAX_COORDINATE},
     Window,
 };
 use libcraft_items::InventorySlot;
@@ -24,8 +31,10 @@ use packets::server::{
     WindowConfirmation,
 };
 use protocol::packets::server::{
-    ChangeGameState, EntityPosition, EntityPositionAndRotation, EntityTeleport, GameStateChange,
-    HeldItemChange, PlayerAbilities,
+    ChangeGameState, CollectItem, Effect, EntityEffect, EntityPosition, EntityPositionAndRotation,
+    EntityStatus, EntityTeleport, GameStateChange, HeldItemChange, OpenWindow, PlayerAbilities,
+    RemoveEntityEffect, ServerDifficulty, SetExperience, SpawnEntity, SpawnExperienceOrb,
+    UpdateHealth, WindowProperty, WorldBorder as WorldBorderPacket,
 };
 use protocol::{
     packets::{
@@ -37,9 +46,9 @@ use protocol::{
             Title, UnloadChunk, UpdateViewPosition, WindowItems,
         },
     },
-    ClientPlayPacket, Nbt, ProtocolVersion, ServerPlayPacket, Writeable,
+    ClientPlayPacket, Nbt, ProtocolVersion, ServerPlayPacket, VarLong, Writeable,
 };
-use quill_common::components::{OnGround, PreviousGamemode};
+use quill_common::components::{EffectInstance, OnGround, PreviousGamemode};
 
 use crate::{
     entities::{PreviousOnGround, PreviousPosition},
@@ -51,6 +60,18 @@ use crate::{
 /// Max number of chunks to send to a client per tick.
 const MAX_CHUNKS_PER_TICK: usize = 10;
 
+/// Window IDs given to opened windows cycle through `1..=MAX_WINDOW_ID`.
+const MAX_WINDOW_ID: u8 = 100;
+
+/// Window ID which targets the item held by the cursor (-1 on the wire).
+const CURSOR_WINDOW_ID: u8 = 255;
+
+/// Window type ID of the enchanting table window.
+const WINDOW_KIND_ENCHANTMENT: i32 = 12;
+
+/// Window type ID of a beacon.
+const WINDOW_KIND_BEACON: i32 = 8;
+
 /// ID of a client. Can be reused.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct ClientId(usize);
@@ -90,7 +111,8 @@ impl Clients {
 /// A client connected to a server.
 ///
 /// This struct provides methods to send packets
-/// to the client.
+/// to the client. Its state is synchronized, so that
+/// systems running in parallel can send packets.
 pub struct Client {
     packets_to_send: Sender<ServerPlayPacket>,
     received_packets: Receiver<ClientPlayPacket>,
@@ -98,22 +120,31 @@ pub struct Client {
     username: String,
     profile: Vec<ProfileProperty>,
     uuid: Uuid,
+    ip: IpAddr,
+    protocol_version: ProtocolVersion,
 
-    teleport_id_counter: Cell<i32>,
+    teleport_id_counter: AtomicI32,
+    /// The ID of the last teleport sent to the client,
+    /// until the client confirms it.
+    pending_teleport: Mutex<Option<i32>>,
 
     network_id: Option<NetworkId>,
-    sent_entities: RefCell<AHashSet<NetworkId>>,
+    sent_entities: Mutex<AHashSet<NetworkId>>,
 
-    knows_position: Cell<bool>,
-    known_chunks: RefCell<AHashSet<ChunkPosition>>,
+    knows_position: AtomicBool,
+    known_chunks: Mutex<AHashSet<ChunkPosition>>,
 
-    chunk_send_queue: RefCell<VecDeque<ChunkData>>,
+    chunk_send_queue: Mutex<VecDeque<ChunkData>>,
 
     /// The previous own position sent by the client.
     /// Used to detect when we need to teleport the client.
-    client_known_position: Cell<Option<Position>>,
+    client_known_position: Mutex<Option<Position>>,
+
+    disconnected: AtomicBool,
 
-    disconnected: Cell<bool>,
+    /// The ID of the window the client has open,
+    /// or 0 if it only has its own inventory open.
+    open_window_id: AtomicU8,
 }
 
 impl Client {
@@ -123,25 +154,43 @@ impl Client {
             received_packets: player.received_packets,
             options,
             username: player.username,
-            teleport_id_counter: Cell::new(0),
+            teleport_id_counter: AtomicI32::new(0),
+            pending_teleport: Mutex::new(None),
             network_id: None,
             profile: player.profile,
             uuid: player.uuid,
-            sent_entities: RefCell::new(AHashSet::new()),
-            knows_position: Cell::new(false),
-            known_chunks: RefCell::new(AHashSet::new()),
-            chunk_send_queue: RefCell::new(VecDeque::new()),
-            client_known_position: Cell::new(None),
-            disconnected: Cell::new(false),
+            ip: player.ip,
+            protocol_version: player.protocol_version,
+            sent_entities: Mutex::new(AHashSet::new()),
+            knows_position: AtomicBool::new(false),
+            known_chunks: Mutex::new(AHashSet::new()),
+            chunk_send_queue: Mutex::new(VecDeque::new()),
+            client_known_position: Mutex::new(None),
+            disconnected: AtomicBool::new(false),
+            open_window_id: AtomicU8::new(0),
         }
     }
 
     pub fn set_client_known_position(&self, pos: Position) {
-        self.client_known_position.set(Some(pos));
+        *self.client_known_position.lock() = Some(pos);
     }
 
     pub fn client_known_position(&self) -> Option<Position> {
-        self.client_known_position.get()
+        *self.client_known_position.lock()
+    }
+
+    /// Handles the client confirming a teleport.
+    pub fn confirm_teleport(&self, teleport_id: i32) {
+        let mut pending_teleport = self.pending_teleport.lock();
+        if *pending_teleport == Some(teleport_id) {
+            *pending_teleport = None;
+        }
+    }
+
+    /// Returns whether the client was teleported and hasn't confirmed it yet.
+    /// Movement sent meanwhile is from before the teleport.
+    pub fn is_awaiting_teleport(&self) -> bool {
+        self.pending_teleport.lock().is_some()
     }
 
     pub fn profile(&self) -> &[ProfileProperty] {
@@ -156,6 +205,17 @@ impl Client {
         self.uuid
     }
 
+    /// Returns the IP address of the client, as
+    /// forwarded by the proxy if there is one.
+    pub fn ip(&self) -> IpAddr {
+        self.ip
+    }
+
+    /// Returns the protocol version the client connected with.
+    pub fn protocol_version(&self) -> ProtocolVersion {
+        self.protocol_version
+    }
+
     pub fn username(&self) -> &str {
         &self.username
     }
@@ -165,20 +225,21 @@ impl Client {
     }
 
     pub fn is_disconnected(&self) -> bool {
-        self.received_packets.is_disconnected() || self.disconnected.get()
+        self.received_packets.is_disconnected() || self.disconnected.load(Ordering::Relaxed)
     }
 
     pub fn known_chunks(&self) -> usize {
-        self.known_chunks.borrow().len()
+        self.known_chunks.lock().len()
     }
 
     pub fn knows_own_position(&self) -> bool {
-        self.knows_position.get()
+        self.knows_position.load(Ordering::Relaxed)
     }
 
     pub fn tick(&self) {
-        let send_count = MAX_CHUNKS_PER_TICK.min(self.chunk_send_queue.borrow().len());
-        for packet in self.chunk_send_queue.borrow_mut().drain(0..send_count) {
+        let mut chunk_send_queue = self.chunk_send_queue.lock();
+        let send_count = MAX_CHUNKS_PER_TICK.min(chunk_send_queue.len());
+        for packet in chunk_send_queue.drain(0..send_count) {
             log::trace!(
                 "Sending chunk at {:?} to {}",
                 packet.chunk.read().position(),
@@ -193,7 +254,7 @@ impl Client {
     /// Returns whether the entity with the given ID
     /// is currently loaded on the client.
     pub fn is_entity_loaded(&self, network_id: NetworkId) -> bool {
-        self.sent_entities.borrow().contains(&network_id)
+        self.sent_entities.lock().contains(&network_id)
     }
 
     pub fn set_network_id(&mut self, network_id: NetworkId) {
@@ -235,7 +296,7 @@ impl Client {
         let mut data = Vec::new();
         "Feather"
             .to_owned()
-            .write(&mut data, ProtocolVersion::V1_16_2)
+            .write(&mut data, self.protocol_version)
             .unwrap();
         self.send_plugin_message("minecraft:brand", data)
     }
@@ -255,6 +316,7 @@ impl Client {
             self.username,
             new_position
         );
+        let teleport_id = self.teleport_id_counter.fetch_add(1, Ordering::Relaxed);
         self.send_packet(PlayerPositionAndLook {
             x: new_position.x,
             y: new_position.y,
@@ -262,12 +324,11 @@ impl Client {
             yaw: new_position.yaw,
             pitch: new_position.pitch,
             flags: 0,
-            teleport_id: self.teleport_id_counter.get(),
+            teleport_id,
         });
-        self.teleport_id_counter
-            .set(self.teleport_id_counter.get() + 1);
-        self.knows_position.set(true);
-        self.client_known_position.set(Some(new_position));
+        *self.pending_teleport.lock() = Some(teleport_id);
+        self.knows_position.store(true, Ordering::Relaxed);
+        *self.client_known_position.lock() = Some(new_position);
     }
 
     pub fn update_own_chunk(&self, pos: ChunkPosition) {
@@ -279,13 +340,11 @@ impl Client {
     }
 
     pub fn send_chunk(&self, chunk: &ChunkHandle) {
-        self.chunk_send_queue.borrow_mut().push_back(ChunkData {
+        self.chunk_send_queue.lock().push_back(ChunkData {
             chunk: Arc::clone(chunk),
             kind: ChunkDataKind::LoadChunk,
         });
-        self.known_chunks
-            .borrow_mut()
-            .insert(chunk.read().position());
+        self.known_chunks.lock().insert(chunk.read().position());
     }
 
     pub fn overwrite_chunk_sections(&self, chunk: &ChunkHandle, sections: Vec<usize>) {
@@ -308,7 +367,7 @@ impl Client {
             chunk_x: pos.x,
             chunk_z: pos.z,
         });
-        self.known_chunks.borrow_mut().remove(&pos);
+        self.known_chunks.lock().remove(&pos);
     }
 
     pub fn add_tablist_player(
@@ -350,7 +409,7 @@ impl Client {
 
     pub fn unload_entity(&self, id: NetworkId) {
         log::trace!("Unloading {:?} on {}", id, self.username);
-        self.sent_entities.borrow_mut().remove(&id);
+        self.sent_entities.lock().remove(&id);
         self.send_packet(DestroyEntities {
             entity_ids: vec![id.0.into()],
         });
@@ -358,7 +417,7 @@ impl Client {
 
     pub fn send_player(&self, network_id: NetworkId, uuid: Uuid, pos: Position) {
         log::trace!("Sending {:?} to {}", uuid, self.username);
-        assert!(!self.sent_entities.borrow().contains(&network_id));
+        assert!(!self.sent_entities.lock().contains(&network_id));
         self.send_packet(SpawnPlayer {
             entity_id: network_id.0,
             player_uuid: uuid,
@@ -400,6 +459,33 @@ impl Client {
         });
     }
 
+    /// Spawns a non-living entity, such as a thrown potion.
+    pub fn send_object_entity(
+        &self,
+        network_id: NetworkId,
+        uuid: Uuid,
+        pos: Position,
+        kind: EntityKind,
+        velocity: Vec3d,
+    ) {
+        log::trace!("Spawning a {:?} on {}", kind, self.username);
+        let [velocity_x, velocity_y, velocity_z] = protocol_velocity(velocity);
+        self.send_packet(SpawnEntity {
+            entity_id: network_id.0,
+            uuid,
+            kind: kind.id() as i32,
+            x: pos.x,
+            y: pos.y,
+            z: pos.z,
+            pitch: pos.pitch,
+            yaw: pos.yaw,
+            data: 0,
+            velocity_x,
+            velocity_y,
+            velocity_z,
+        });
+    }
+
     pub fn update_entity_position(
         &self,
         network_id: NetworkId,
@@ -412,7 +498,7 @@ impl Client {
             // This entity is the client. Only update
             // the position if it has changed from the client's
             // known position.
-            if Some(position) != self.client_known_position.get() {
+            if Some(position) != self.client_known_position() {
                 self.update_own_position(position);
             }
             return;
@@ -541,10 +627,44 @@ impl Client {
         });
     }
 
+    /// Opens a window other than the player's inventory on the client.
+    pub fn open_window(&self, window: &Window) {
+        let (window_kind, window_title) = match window.inner() {
+            BackingWindow::Enchantment { .. } => (WINDOW_KIND_ENCHANTMENT, "Enchant"),
+            BackingWindow::Beacon { .. } => (WINDOW_KIND_BEACON, "Beacon"),
+            _ => {
+                log::warn!("Opening this window kind is not supported");
+                return;
+            }
+        };
+
+        let window_id = self.open_window_id.load(Ordering::Relaxed) % MAX_WINDOW_ID + 1;
+        self.open_window_id.store(window_id, Ordering::Relaxed);
+        self.send_packet(OpenWindow {
+            window_id: window_id.into(),
+            window_kind,
+            window_title: Text::from(window_title).to_string(),
+        });
+        self.send_window_items(window);
+    }
+
+    /// Marks the open window as closed. Called when the client closes it.
+    pub fn close_window(&self) {
+        self.open_window_id.store(0, Ordering::Relaxed);
+    }
+
+    pub fn send_window_property(&self, property: i16, value: i16) {
+        self.send_packet(WindowProperty {
+            window_id: self.open_window_id.load(Ordering::Relaxed),
+            property,
+            value,
+        });
+    }
+
     pub fn send_window_items(&self, window: &Window) {
         log::trace!("Updating window for {}", self.username);
         let packet = WindowItems {
-            window_id: 0,
+            window_id: self.open_window_id.load(Ordering::Relaxed),
             items: window.inner().to_vec(),
         };
         self.send_packet(packet);
@@ -553,7 +673,7 @@ impl Client {
     pub fn set_slot(&self, slot: i16, item: &InventorySlot) {
         log::trace!("Setting slot {} of {} to {:?}", slot, self.username, item);
         self.send_packet(SetSlot {
-            window_id: 0,
+            window_id: self.open_window_id.load(Ordering::Relaxed),
             slot,
             slot_data: item.clone(),
         });
@@ -576,7 +696,11 @@ impl Client {
 
     pub fn set_cursor_slot(&self, item: &InventorySlot) {
         log::trace!("Setting cursor slot of {} to {:?}", self.username, item);
-        self.set_slot(-1, item);
+        self.send_packet(SetSlot {
+            window_id: CURSOR_WINDOW_ID,
+            slot: -1,
+            slot_data: item.clone(),
+        });
     }
 
     pub fn send_player_model_flags(&self, netowrk_id: NetworkId, model_flags: u8) {
@@ -629,8 +753,149 @@ impl Client {
         })
     }
 
+    pub fn send_server_difficulty(&self, difficulty: Difficulty) {
+        self.send_packet(ServerDifficulty {
+            difficulty: difficulty.id(),
+            locked: false,
+        });
+    }
+
+    pub fn send_health(&self, health: f32, food: u32, saturation: f32) {
+        self.send_packet(UpdateHealth {
+            health,
+            food: food as i32,
+            food_saturation: saturation,
+        });
+    }
+
+    pub fn send_experience(&self, progress: f32, level: u32, total: u32) {
+        self.send_packet(SetExperience {
+            experience_bar: progress,
+            level: level as i32,
+            total_experience: total as i32,
+        });
+    }
+
+    pub fn send_experience_orb(&self, network_id: NetworkId, pos: Position, count: u16) {
+        log::trace!("Spawning an experience orb on {}", self.username);
+        self.send_packet(SpawnExperienceOrb {
+            entity_id: network_id.0,
+            x: pos.x,
+            y: pos.y,
+            z: pos.z,
+            count,
+        });
+    }
+
+    pub fn send_collect_item(&self, collected: NetworkId, collector: NetworkId, count: u32) {
+        self.send_packet(CollectItem {
+            collected_entity_id: collected.0,
+            collector_entity_id: collector.0,
+            item_count: count as i32,
+        });
+    }
+
+    pub fn send_entity_effect(
+        &self,
+        network_id: NetworkId,
+        effect: StatusEffect,
+        instance: &EffectInstance,
+    ) {
+        let mut flags = 0;
+        if instance.ambient {
+            flags |= 1 << 0;
+        }
+        if instance.show_particles {
+            flags |= 1 << 1;
+        }
+        if instance.show_icon {
+            flags |= 1 << 2;
+        }
+        self.send_packet(EntityEffect {
+            entity_id: network_id.0,
+            effect_id: effect.id(),
+            amplifier: instance.amplifier as i8,
+            duration: instance.duration.min(i32::MAX as u32) as i32,
+            flags,
+        });
+    }
+
+    pub fn send_remove_entity_effect(&self, network_id: NetworkId, effect: StatusEffect) {
+        self.send_packet(RemoveEntityEffect {
+            entity_id: network_id.0,
+            effect_id: effect.id(),
+        });
+    }
+
+    /// Plays a world event, such as a splash potion breaking.
+    pub fn send_world_event(&self, event_id: i32, position: ValidBlockPosition, data: i32) {
+        self.send_packet(Effect {
+            effect_id: event_id,
+            position,
+            data,
+            disable_relative_volume: false,
+        });
+    }
+
+    pub fn send_entity_status(&self, network_id: NetworkId, status: i8) {
+        self.send_packet(EntityStatus {
+            entity_id: network_id.0,
+            status,
+        });
+    }
+
+    /// Tells the client its permission level, which
+    /// determines whether it shows operator features.
+    pub fn send_permission_level(&self, level: u8) {
+        // Entity statuses 24 to 28 set permission levels 0 to 4.
+        let status = 24 + level.min(MAX_PERMISSION_LEVEL) as i8;
+        self.send_entity_status(self.network_id.expect("no network ID"), status);
+    }
+
+    /// Sends the full state of the world border.
+    pub fn send_world_border(&self, border: &WorldBorder) {
+        let (x, z) = border.center();
+        self.send_packet(WorldBorderPacket::Initialize {
+            x,
+            z,
+            old_diameter: border.size(),
+            new_diameter: border.target_size(),
+            speed: VarLong(border.remaining_millis() as i64),
+            portal_teeport_boundary: MAX_COORDINATE as i32,
+            warning_time: border.warning_time(),
+            warning_blocks: border.warning_blocks(),
+        });
+    }
+
+    /// Sends a change to the world border.
+    pub fn send_world_border_update(&self, border: &WorldBorder, update: WorldBorderUpdate) {
+        let packet = match update {
+            WorldBorderUpdate::Size if border.remaining_millis() > 0 => {
+                WorldBorderPacket::LerpSize {
+                    old_diameter: border.size(),
+                    new_diameter: border.target_size(),
+                    speed: VarLong(border.remaining_millis() as i64),
+                }
+            }
+            WorldBorderUpdate::Size => WorldBorderPacket::SetSize {
+                diameter: border.size(),
+            },
+            WorldBorderUpdate::Center => {
+                let (x, z) = border.center();
+                WorldBorderPacket::SetCenter { x, z }
+            }
+            WorldBorderUpdate::WarningTime => WorldBorderPacket::SetWarningTime {
+                warning_time: border.warning_time(),
+            },
+            WorldBorderUpdate::WarningBlocks => WorldBorderPacket::SetWarningBlocks {
+                warning_blocks: border.warning_blocks(),
+            },
+        };
+        self.send_packet(packet);
+    }
+
     fn register_entity(&self, network_id: NetworkId) {
-        self.sent_entities.borrow_mut().insert(network_id);
+        self.sent_entities.lock().insert(network_id);
     }
 
     fn send_packet(&self, packet: impl Into<ServerPlayPacket>) {
@@ -638,9 +903,14 @@ impl Client {
     }
 
     pub fn disconnect(&self, reason: &str) {
-        self.disconnected.set(true);
+        self.disconnect_with(Text::from(reason.to_owned()));
+    }
+
+    /// Disconnects the client with a formatted reason.
+    pub fn disconnect_with(&self, reason: Text) {
+        self.disconnected.store(true, Ordering::Relaxed);
         self.send_packet(Disconnect {
-            reason: Text::from(reason.to_owned()).to_string(),
+            reason: reason.to_string(),
         });
     }
 }
@@ -656,3 +926,14 @@ fn chat_packet(message: ChatMessage) -> packets::server::ChatMessage {
         sender: Uuid::default(),
     }
 }
+
+/// Converts a velocity in blocks per tick to the
+/// protocol's units of 1/8000 of a block per tick.
+fn protocol_velocity(velocity: Vec3d) -> [i16; 3] {
+    let convert = |v: f64| (v.clamp(-3.9, 3.9) * 8000.0) as i16;
+    [
+        convert(velocity.x),
+        convert(velocity.y),
+        convert(velocity.z),
+    ]
+}
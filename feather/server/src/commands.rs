@@ -0,0 +1,198 @@
+//! Runs the commands sent by players, the console and RCON clients.
+//!
+//! Commands are queued and run by a system outside the `Server` group,
+//! so that commands can access the `Server` resource. Output which
+//! commands deferred is delivered in the first tick after it's complete.
+
+use std::{mem, sync::Arc};
+
+use common::{
+    autosave::Autosave,
+    commands::{
+        self, Command, CommandContext, CommandSender, Commands, PendingOutput, MAX_PERMISSION_LEVEL,
+    },
+    ChatBox, Game,
+};
+use ecs::{SysResult, SystemExecutor};
+use flume::Sender;
+
+use crate::{systems::autosave::save_all_players, Server};
+
+mod access;
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    let mut commands = game
+        .resources
+        .get_mut::<Commands>()
+        .expect("common commands must be registered first");
+    commands.register(Command {
+        name: "stop",
+        usage: "",
+        description: "Saves the world and stops the server",
+        permission_level: MAX_PERMISSION_LEVEL,
+        handler: stop,
+    });
+    commands.register(Command {
+        name: "save-all",
+        usage: "",
+        description: "Saves all chunks and players now",
+        permission_level: MAX_PERMISSION_LEVEL,
+        handler: save_all,
+    });
+    commands.register(Command {
+        name: "save-off",
+        usage: "",
+        description: "Stops writing to the world files",
+        permission_level: MAX_PERMISSION_LEVEL,
+        handler: save_off,
+    });
+    commands.register(Command {
+        name: "save-on",
+        usage: "",
+        description: "Resumes writing to the world files",
+        permission_level: MAX_PERMISSION_LEVEL,
+        handler: save_on,
+    });
+    access::register(&mut commands);
+    drop(commands);
+
+    game.insert_resource(DeferredOutputs::default());
+    systems.add_system(run_commands);
+}
+
+/// A command line waiting to be run.
+#[derive(Debug)]
+pub struct CommandRequest {
+    pub sender: CommandSender,
+    pub line: String,
+    /// Receives the output of the command. If `None`, the output
+    /// is sent to the sender's chat box or logged.
+    pub reply: Option<Sender<Vec<String>>>,
+}
+
+impl CommandRequest {
+    pub fn new(sender: CommandSender, line: impl Into<String>) -> Self {
+        Self {
+            sender,
+            line: line.into(),
+            reply: None,
+        }
+    }
+}
+
+/// A command whose output was deferred, waiting for the rest of it.
+struct DeferredOutput {
+    sender: CommandSender,
+    reply: Option<Sender<Vec<String>>>,
+    lines: Vec<String>,
+    rest: PendingOutput,
+}
+
+#[derive(Default)]
+struct DeferredOutputs(Vec<DeferredOutput>);
+
+fn run_commands(game: &mut Game) -> SysResult {
+    let requests: Vec<CommandRequest> = game
+        .resources
+        .get::<Server>()?
+        .pending_commands
+        .try_iter()
+        .collect();
+
+    for request in requests {
+        let output = commands::run_command(game, request.sender, &request.line);
+        match output.deferred {
+            // Replies hold the whole output, so they wait for the rest of it.
+            Some(rest) => game
+                .resources
+                .get_mut::<DeferredOutputs>()?
+                .0
+                .push(DeferredOutput {
+                    sender: request.sender,
+                    reply: request.reply,
+                    lines: output.lines,
+                    rest,
+                }),
+            None => deliver_output(game, request.sender, request.reply, output.lines),
+        }
+    }
+
+    let deferred = mem::take(&mut game.resources.get_mut::<DeferredOutputs>()?.0);
+    for mut output in deferred {
+        match output.rest.try_take() {
+            Some(rest) => {
+                output.lines.extend(rest);
+                deliver_output(game, output.sender, output.reply, output.lines);
+            }
+            None => game.resources.get_mut::<DeferredOutputs>()?.0.push(output),
+        }
+    }
+    Ok(())
+}
+
+fn stop(game: &mut Game, context: &mut CommandContext) -> anyhow::Result<()> {
+    if game.resources.get::<Server>()?.shutdown.request() {
+        context.reply("Stopping the server");
+    } else {
+        context.reply("The server is already stopping");
+    }
+    Ok(())
+}
+
+fn save_all(game: &mut Game, context: &mut CommandContext) -> anyhow::Result<()> {
+    let resources = Arc::clone(&game.resources);
+    let players = save_all_players(game, &*resources.get::<Server>()?);
+    let chunks = game.world.save_all_chunks();
+    log::info!("Saved {} chunks and {} players", chunks, players);
+    context.reply("Saved the game");
+    Ok(())
+}
+
+fn save_off(game: &mut Game, context: &mut CommandContext) -> anyhow::Result<()> {
+    let mut autosave = game.resources.get_mut::<Autosave>()?;
+    if autosave.set_saving_enabled(&mut game.world, false) {
+        context.reply("Automatic saving is now disabled");
+    } else {
+        context.reply("Saving is already turned off");
+    }
+    Ok(())
+}
+
+fn save_on(game: &mut Game, context: &mut CommandContext) -> anyhow::Result<()> {
+    let mut autosave = game.resources.get_mut::<Autosave>()?;
+    if autosave.set_saving_enabled(&mut game.world, true) {
+        context.reply("Automatic saving is now enabled");
+    } else {
+        context.reply("Saving is already turned on");
+    }
+    Ok(())
+}
+
+/// Sends the output of a command to the requester, if it
+/// asked for it, or else to the sender's chat box or log.
+fn deliver_output(
+    game: &mut Game,
+    sender: CommandSender,
+    reply: Option<Sender<Vec<String>>>,
+    output: Vec<String>,
+) {
+    if let Some(reply) = reply {
+        // The requester may have disconnected.
+        reply.send(output).ok();
+        return;
+    }
+    match sender {
+        CommandSender::Player(player) => {
+            if let Ok(mut chat_box) = game.ecs.get_mut::<ChatBox>(player) {
+                for line in output {
+                    chat_box.send_system(line);
+                }
+            }
+        }
+        CommandSender::Console | CommandSender::Rcon => {
+            for line in output {
+                log::info!("{}", line);
+            }
+        }
+    }
+}
@@ -0,0 +1,472 @@
+//! Commands managing the whitelist, ban lists and operator list.
+
+use std::{mem, net::IpAddr, sync::Arc};
+
+use anyhow::{bail, Context};
+use base::Text;
+use common::{
+    commands::{Command, CommandContext, Commands, PermissionLevel, MAX_PERMISSION_LEVEL},
+    scheduler::Scheduler,
+    Game,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    access::{self, Ban, IpBan, OpEntry, PlayerBan, UserEntry},
+    initial_handler::offline_mode_uuid,
+    Client, ClientId, Server,
+};
+
+/// Permission level required to run the commands.
+const PERMISSION_LEVEL: u8 = 3;
+
+pub fn register(commands: &mut Commands) {
+    commands.register(Command {
+        name: "whitelist",
+        usage: "on|off|list|add <player>|remove <player>|reload",
+        description: "Manages the whitelist",
+        permission_level: PERMISSION_LEVEL,
+        handler: whitelist,
+    });
+    commands.register(Command {
+        name: "op",
+        usage: "<player>",
+        description: "Makes a player a server operator",
+        permission_level: PERMISSION_LEVEL,
+        handler: op,
+    });
+    commands.register(Command {
+        name: "deop",
+        usage: "<player>",
+        description: "Makes a player no longer a server operator",
+        permission_level: PERMISSION_LEVEL,
+        handler: deop,
+    });
+    commands.register(Command {
+        name: "ban",
+        usage: "<player> [reason]",
+        description: "Bans a player from the server",
+        permission_level: PERMISSION_LEVEL,
+        handler: ban,
+    });
+    commands.register(Command {
+        name: "ban-ip",
+        usage: "<address|player> [reason]",
+        description: "Bans an IP address from the server",
+        permission_level: PERMISSION_LEVEL,
+        handler: ban_ip,
+    });
+    commands.register(Command {
+        name: "pardon",
+        usage: "<player>",
+        description: "Removes a player from the ban list",
+        permission_level: PERMISSION_LEVEL,
+        handler: pardon,
+    });
+    commands.register(Command {
+        name: "pardon-ip",
+        usage: "<address>",
+        description: "Removes an IP address from the ban list",
+        permission_level: PERMISSION_LEVEL,
+        handler: pardon_ip,
+    });
+    commands.register(Command {
+        name: "banlist",
+        usage: "[ips|players]",
+        description: "Lists the banned players or IP addresses",
+        permission_level: PERMISSION_LEVEL,
+        handler: banlist,
+    });
+}
+
+fn whitelist(game: &mut Game, context: &mut CommandContext) -> anyhow::Result<()> {
+    let resources = Arc::clone(&game.resources);
+    let server = resources.get::<Server>()?;
+    let lists = server.access_lists();
+
+    // Taken so that the context can be borrowed mutably in the match.
+    let args = mem::take(&mut context.args);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["on"] => {
+            if lists.is_whitelist_enabled() {
+                bail!("Whitelist is already turned on");
+            }
+            lists.set_whitelist_enabled(true);
+            enforce_whitelist(&server);
+            context.reply("Whitelist is now turned on");
+        }
+        ["off"] => {
+            if !lists.is_whitelist_enabled() {
+                bail!("Whitelist is already turned off");
+            }
+            lists.set_whitelist_enabled(false);
+            context.reply("Whitelist is now turned off");
+        }
+        ["list"] => {
+            let names: Vec<String> = lists.whitelist().into_iter().map(|e| e.name).collect();
+            if names.is_empty() {
+                context.reply("There are no whitelisted players");
+            } else {
+                context.reply(format!(
+                    "There are {} whitelisted players: {}",
+                    names.len(),
+                    names.join(", ")
+                ));
+            }
+        }
+        ["add", name] => with_profile(game, context, name, |game, profile| {
+            let server = game.resources.get::<Server>()?;
+            let name = profile.name.clone();
+            if !server.access_lists().add_to_whitelist(profile)? {
+                bail!("Player is already whitelisted");
+            }
+            Ok(format!("Added {} to the whitelist", name))
+        })?,
+        ["remove", name] => match lists.remove_from_whitelist(name)? {
+            Some(entry) => {
+                enforce_whitelist(&server);
+                context.reply(format!("Removed {} from the whitelist", entry.name));
+            }
+            None => bail!("Player is not whitelisted"),
+        },
+        ["reload"] => {
+            lists.reload()?;
+            enforce_whitelist(&server);
+            update_permission_levels(game, &server);
+            context.reply("Reloaded the whitelist, ban lists and operator list");
+        }
+        _ => bail!("Usage: whitelist on|off|list|add <player>|remove <player>|reload"),
+    }
+    Ok(())
+}
+
+fn op(game: &mut Game, context: &mut CommandContext) -> anyhow::Result<()> {
+    let name = single_arg(context, "Usage: op <player>")?;
+    with_profile(game, context, &name, |game, profile| {
+        let resources = Arc::clone(&game.resources);
+        let server = resources.get::<Server>()?;
+
+        let entry = OpEntry {
+            uuid: profile.uuid,
+            name: profile.name,
+            level: MAX_PERMISSION_LEVEL,
+            bypasses_player_limit: false,
+        };
+        let name = entry.name.clone();
+        if !server.access_lists().add_op(entry)? {
+            bail!("Nothing changed. The player already is an operator");
+        }
+        update_permission_levels(game, &server);
+        Ok(format!("Made {} a server operator", name))
+    })
+}
+
+fn deop(game: &mut Game, context: &mut CommandContext) -> anyhow::Result<()> {
+    let name = single_arg(context, "Usage: deop <player>")?;
+    let resources = Arc::clone(&game.resources);
+    let server = resources.get::<Server>()?;
+
+    match server.access_lists().remove_op(&name)? {
+        Some(entry) => {
+            update_permission_levels(game, &server);
+            enforce_whitelist(&server);
+            context.reply(format!("Made {} no longer a server operator", entry.name));
+        }
+        None => bail!("Nothing changed. The player is not an operator"),
+    }
+    Ok(())
+}
+
+fn ban(game: &mut Game, context: &mut CommandContext) -> anyhow::Result<()> {
+    let (name, reason) = target_and_reason(context, "Usage: ban <player> [reason]")?;
+    let source = context.sender.name(game);
+    with_profile(game, context, &name, move |game, profile| {
+        let server = game.resources.get::<Server>()?;
+        let lists = server.access_lists();
+
+        if lists.player_ban(profile.uuid).is_some() {
+            bail!("Nothing changed. The player is already banned");
+        }
+        let ban = Ban::new(source, reason);
+        let message = format!("Banned {}: {}", profile.name, ban.reason);
+        let uuid = profile.uuid;
+        lists.ban_player(PlayerBan {
+            uuid,
+            name: profile.name,
+            ban,
+        })?;
+
+        kick_players(&server, "multiplayer.disconnect.banned", |client| {
+            client.uuid() == uuid
+        });
+        Ok(message)
+    })
+}
+
+fn ban_ip(game: &mut Game, context: &mut CommandContext) -> anyhow::Result<()> {
+    let (target, reason) = target_and_reason(context, "Usage: ban-ip <address|player> [reason]")?;
+    let resources = Arc::clone(&game.resources);
+    let server = resources.get::<Server>()?;
+    let lists = server.access_lists();
+
+    let ip = match target.parse::<IpAddr>() {
+        Ok(ip) => ip,
+        Err(_) => match find_online_player(&server, &target) {
+            Some(client) => client.ip(),
+            None => bail!("Invalid IP address or unknown player"),
+        },
+    };
+    if lists.ip_ban(ip).is_some() {
+        bail!("Nothing changed. That IP is already banned");
+    }
+    let ban = Ban::new(context.sender.name(game), reason);
+    let message = format!("Banned IP {}: {}", ip, ban.reason);
+    lists.ban_ip(IpBan { ip, ban })?;
+    context.reply(message);
+
+    kick_players(&server, "multiplayer.disconnect.ip_banned", |client| {
+        client.ip() == ip
+    });
+    Ok(())
+}
+
+fn pardon(game: &mut Game, context: &mut CommandContext) -> anyhow::Result<()> {
+    let name = single_arg(context, "Usage: pardon <player>")?;
+    let server = game.resources.get::<Server>()?;
+
+    match server.access_lists().pardon_player(&name)? {
+        Some(ban) => context.reply(format!("Unbanned {}", ban.name)),
+        None => bail!("Nothing changed. The player isn't banned"),
+    }
+    Ok(())
+}
+
+fn pardon_ip(game: &mut Game, context: &mut CommandContext) -> anyhow::Result<()> {
+    let address = single_arg(context, "Usage: pardon-ip <address>")?;
+    let ip: IpAddr = match address.parse() {
+        Ok(ip) => ip,
+        Err(_) => bail!("Invalid IP address"),
+    };
+    let server = game.resources.get::<Server>()?;
+
+    if !server.access_lists().pardon_ip(ip)? {
+        bail!("Nothing changed. That IP isn't banned");
+    }
+    context.reply(format!("Unbanned IP {}", ip));
+    Ok(())
+}
+
+fn banlist(game: &mut Game, context: &mut CommandContext) -> anyhow::Result<()> {
+    let server = game.resources.get::<Server>()?;
+    let lists = server.access_lists();
+
+    let (show_players, show_ips) = match context.args.first().map(String::as_str) {
+        None => (true, true),
+        Some("players") => (true, false),
+        Some("ips") => (false, true),
+        Some(_) => bail!("Usage: banlist [ips|players]"),
+    };
+    let mut lines = Vec::new();
+    if show_ips {
+        for ban in lists.banned_ips() {
+            lines.push(ban_line(&ban.ip.to_string(), &ban.ban));
+        }
+    }
+    if show_players {
+        for ban in lists.banned_players() {
+            lines.push(ban_line(&ban.name, &ban.ban));
+        }
+    }
+
+    if lines.is_empty() {
+        context.reply("There are no bans");
+    } else {
+        context.reply(format!("There are {} ban(s):", lines.len()));
+        for line in lines {
+            context.reply(line);
+        }
+    }
+    Ok(())
+}
+
+fn ban_line(target: &str, ban: &Ban) -> String {
+    let mut line = format!("{} was banned by {}: {}", target, ban.source, ban.reason);
+    if let Some(expires) = ban.expires {
+        line.push_str(&format!(" (until {})", access::format_date(expires)));
+    }
+    line
+}
+
+fn single_arg(context: &CommandContext, usage: &str) -> anyhow::Result<String> {
+    match context.args.as_slice() {
+        [arg] => Ok(arg.clone()),
+        _ => bail!("{}", usage),
+    }
+}
+
+/// Returns the first argument and the reason made of the others, if any.
+fn target_and_reason(
+    context: &CommandContext,
+    usage: &str,
+) -> anyhow::Result<(String, Option<String>)> {
+    match context.args.split_first() {
+        Some((target, [])) => Ok((target.clone(), None)),
+        Some((target, reason)) => Ok((target.clone(), Some(reason.join(" ")))),
+        None => bail!("{}", usage),
+    }
+}
+
+fn find_online_player<'a>(server: &'a Server, name: &str) -> Option<&'a Client> {
+    server
+        .clients
+        .iter()
+        .find(|client| client.username().eq_ignore_ascii_case(name))
+}
+
+/// Finds the UUID and correctly cased name of a player, then finishes
+/// the command with `then`, replying with the line it returns.
+///
+/// Players who are offline are looked up with Mojang's API in online
+/// mode. The lookup runs on a worker thread, so the output of the
+/// command is deferred and `then` runs in a later tick.
+fn with_profile(
+    game: &mut Game,
+    context: &mut CommandContext,
+    name: &str,
+    then: impl FnOnce(&mut Game, UserEntry) -> anyhow::Result<String> + Send + 'static,
+) -> anyhow::Result<()> {
+    if !is_valid_username(name) {
+        bail!("Invalid player name");
+    }
+    let known = known_profile(&*game.resources.get::<Server>()?, name);
+    if let Some(profile) = known {
+        context.reply(then(game, profile)?);
+        return Ok(());
+    }
+
+    let mut reply = context.defer();
+    let name = name.to_owned();
+    game.resources.get_mut::<Scheduler>()?.spawn_job(
+        move || lookup_profile(&name),
+        move |game, profile| {
+            match profile.and_then(|profile| then(game, profile)) {
+                Ok(line) => reply.reply(line),
+                Err(e) => reply.reply(e.to_string()),
+            }
+            Ok(())
+        },
+    );
+    Ok(())
+}
+
+/// Gets the profile of a player if it's known without looking it up,
+/// which is the case for online players and in offline mode.
+fn known_profile(server: &Server, name: &str) -> Option<UserEntry> {
+    if let Some(client) = find_online_player(server, name) {
+        return Some(UserEntry {
+            uuid: client.uuid(),
+            name: client.username().to_owned(),
+        });
+    }
+    if server.options.online_mode {
+        None
+    } else {
+        Some(UserEntry {
+            uuid: offline_mode_uuid(name),
+            name: name.to_owned(),
+        })
+    }
+}
+
+/// Returns whether a name could belong to a player,
+/// i.e. it matches `^[A-Za-z0-9_]{1,16}$`.
+fn is_valid_username(name: &str) -> bool {
+    (1..=16).contains(&name.len()) && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+}
+
+#[derive(Debug, Deserialize)]
+struct ProfileResponse {
+    id: Uuid,
+    name: String,
+}
+
+/// Looks up a player with Mojang's API, blocking until it responds.
+fn lookup_profile(name: &str) -> anyhow::Result<UserEntry> {
+    let url = format!("https://api.mojang.com/users/profiles/minecraft/{}", name);
+    match ureq::get(&url).call() {
+        // Unknown names get either 204 No Content or 404 Not Found.
+        Ok(response) if response.status() == 200 => {
+            let profile: ProfileResponse = response.into_json()?;
+            Ok(UserEntry {
+                uuid: profile.id,
+                name: profile.name,
+            })
+        }
+        Ok(_) | Err(ureq::Error::Status(..)) => bail!("That player does not exist"),
+        Err(e) => Err(e).context("failed to look up the player"),
+    }
+}
+
+/// Disconnects the online players matching `predicate`
+/// with the message of the given translation key.
+fn kick_players(server: &Server, key: &'static str, predicate: impl Fn(&Client) -> bool) {
+    server.broadcast_with(|client| {
+        if predicate(client) {
+            log::info!("Kicking {}", client.username());
+            client.disconnect_with(Text::translate(key));
+        }
+    });
+}
+
+/// Kicks players who aren't whitelisted, if the whitelist is enforced.
+fn enforce_whitelist(server: &Server) {
+    if server.options.enforce_whitelist {
+        let lists = server.access_lists();
+        kick_players(server, "multiplayer.disconnect.not_whitelisted", |client| {
+            !lists.is_whitelisted(client.uuid())
+        });
+    }
+}
+
+/// Sets the permission levels of online players from the operator list.
+fn update_permission_levels(game: &mut Game, server: &Server) {
+    let players: Vec<_> = game
+        .ecs
+        .query::<(&ClientId, &PermissionLevel)>()
+        .iter()
+        .map(|(player, (&client_id, &level))| (player, client_id, level))
+        .collect();
+
+    for (player, client_id, PermissionLevel(old_level)) in players {
+        let client = match server.clients.get(client_id) {
+            Some(client) => client,
+            None => continue,
+        };
+        let level = server
+            .access_lists()
+            .op(client.uuid())
+            .map_or(0, |op| op.level);
+        if level != old_level {
+            game.ecs.insert(player, PermissionLevel(level)).ok();
+            client.send_permission_level(level);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_usernames() {
+        assert!(is_valid_username("Notch"));
+        assert!(is_valid_username("a_b_1234567890XY"));
+        assert!(!is_valid_username(""));
+        assert!(!is_valid_username("a_b_1234567890XYZ"));
+        assert!(!is_valid_username("Notch/../x"));
+        assert!(!is_valid_username("Notch?a=b"));
+        assert!(!is_valid_username("Nötch"));
+    }
+}
@@ -1,12 +1,23 @@
 //! Loads an `Options` from a TOML config.
 
-use std::{fs, net::IpAddr, path::Path, str::FromStr};
+use std::{
+    fs,
+    net::{IpAddr, SocketAddr},
+    path::Path,
+    str::FromStr,
+    time::Duration,
+};
 
-use anyhow::Context;
-use base::Gamemode;
+use anyhow::{bail, Context};
+use base::{Difficulty, Gamemode, TPS};
+use common::chunk::storage::StorageOptions;
 use serde::{Deserialize, Deserializer};
 
-use crate::{favicon::Favicon, Options};
+use crate::{
+    favicon::Favicon,
+    options::{LimitOptions, MovementOptions},
+    Options,
+};
 
 const DEFAULT_DEBUG_CONFIG: &str = include_str!("../config_debug.toml");
 const DEFAULT_RELEASE_CONFIG: &str = include_str!("../config_release.toml");
@@ -48,7 +59,19 @@ pub struct Config {
     pub server: ServerConfig,
     pub log: Log,
     pub world: World,
+    #[serde(default)]
+    pub autosave: Autosave,
+    #[serde(default)]
+    pub movement: Movement,
+    #[serde(default)]
+    pub limits: Limits,
     pub proxy: Proxy,
+    #[serde(default)]
+    pub query: Query,
+    #[serde(default)]
+    pub rcon: Rcon,
+    #[serde(default)]
+    pub metrics: Metrics,
 }
 
 impl Config {
@@ -58,6 +81,23 @@ impl Config {
             bind_address: self.network.address.to_string(),
             favicon: Favicon::load_default(),
             motd: self.server.motd.clone(),
+            level_name: self.world.name.clone(),
+            query_port: if self.query.enabled {
+                Some(self.query.port)
+            } else {
+                None
+            },
+            rcon_port: if self.rcon.enabled {
+                Some(self.rcon.port)
+            } else {
+                None
+            },
+            rcon_password: self.rcon.password.clone(),
+            metrics_address: if self.metrics.enabled {
+                Some(self.metrics.address)
+            } else {
+                None
+            },
             online_mode: if self.proxy.proxy_mode != ProxyMode::None {
                 false
             } else {
@@ -72,6 +112,10 @@ impl Config {
             max_players: self.server.max_players,
             default_gamemode: self.server.default_gamemode,
             enforce_gamemode: self.server.enforce_gamemode,
+            whitelist: self.server.whitelist,
+            enforce_whitelist: self.server.enforce_whitelist,
+            movement: self.movement.to_options(),
+            limits: self.limits.to_options(),
             proxy_mode: match self.proxy.proxy_mode {
                 ProxyMode::None => None,
                 ProxyMode::Bungee => Some(crate::options::ProxyMode::Bungeecord),
@@ -97,6 +141,15 @@ pub struct ServerConfig {
     pub default_gamemode: Gamemode,
     pub enforce_gamemode: bool,
     pub view_distance: u32,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    /// Whether only players in whitelist.json and operators may join.
+    #[serde(default)]
+    pub whitelist: bool,
+    /// Whether to kick players who aren't whitelisted
+    /// when the whitelist is enabled or reloaded.
+    #[serde(default)]
+    pub enforce_whitelist: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -110,6 +163,38 @@ pub struct World {
     pub name: String,
     pub generator: String,
     pub seed: String,
+    #[serde(default)]
+    pub storage: StorageKind,
+    /// The template world of overlay storage.
+    #[serde(default)]
+    pub template: Option<String>,
+    /// Whether to generate the chunks inside the world border in the background.
+    #[serde(default)]
+    pub pregenerate: bool,
+}
+
+impl World {
+    pub fn storage_options(&self) -> anyhow::Result<StorageOptions> {
+        Ok(match self.storage {
+            StorageKind::Anvil => StorageOptions::Anvil,
+            StorageKind::Memory => StorageOptions::Memory,
+            StorageKind::Overlay => match &self.template {
+                Some(template) => StorageOptions::Overlay {
+                    template: template.into(),
+                },
+                None => bail!("world.template must be set to use overlay storage"),
+            },
+        })
+    }
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageKind {
+    #[default]
+    Anvil,
+    Memory,
+    Overlay,
 }
 
 #[derive(Debug, Deserialize)]
@@ -126,6 +211,168 @@ pub enum ProxyMode {
     Velocity,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct Autosave {
+    pub enabled: bool,
+    /// Seconds between autosaves.
+    pub interval: u64,
+}
+
+impl Autosave {
+    /// Returns the number of ticks between autosaves,
+    /// or `None` if autosaving is disabled.
+    pub fn interval_ticks(&self) -> Option<u64> {
+        if self.enabled && self.interval > 0 {
+            Some(self.interval * TPS as u64)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for Autosave {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: 300,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Movement {
+    pub enabled: bool,
+    /// Factor applied to the maximum speeds.
+    pub speed_tolerance: f64,
+    pub check_flight: bool,
+    pub check_collisions: bool,
+    pub check_ground: bool,
+}
+
+impl Movement {
+    pub fn to_options(&self) -> MovementOptions {
+        MovementOptions {
+            enabled: self.enabled,
+            speed_tolerance: self.speed_tolerance,
+            check_flight: self.check_flight,
+            check_collisions: self.check_collisions,
+            check_ground: self.check_ground,
+        }
+    }
+}
+
+impl Default for Movement {
+    fn default() -> Self {
+        let options = MovementOptions::default();
+        Self {
+            enabled: options.enabled,
+            speed_tolerance: options.speed_tolerance,
+            check_flight: options.check_flight,
+            check_collisions: options.check_collisions,
+            check_ground: options.check_ground,
+        }
+    }
+}
+
+/// Limits on clients. A limit of 0 disables it.
+#[derive(Debug, Deserialize)]
+pub struct Limits {
+    /// Seconds a client may take to log in.
+    pub login_timeout: u64,
+    /// Milliseconds between logins from the same IP address.
+    pub connection_throttle: u64,
+    pub max_connections_per_ip: u32,
+    pub max_packets_per_second: u32,
+    /// Maximum packet size in bytes.
+    pub max_packet_size: usize,
+}
+
+impl Limits {
+    pub fn to_options(&self) -> LimitOptions {
+        LimitOptions {
+            login_timeout: nonzero(self.login_timeout).map(Duration::from_secs),
+            connection_throttle: nonzero(self.connection_throttle).map(Duration::from_millis),
+            max_connections_per_ip: nonzero(self.max_connections_per_ip),
+            max_packets_per_second: nonzero(self.max_packets_per_second),
+            max_packet_size: if self.max_packet_size == 0 {
+                protocol::codec::MAX_PACKET_LENGTH
+            } else {
+                self.max_packet_size
+            },
+        }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        let options = LimitOptions::default();
+        Self {
+            login_timeout: options.login_timeout.map_or(0, |timeout| timeout.as_secs()),
+            connection_throttle: options
+                .connection_throttle
+                .map_or(0, |throttle| throttle.as_millis() as u64),
+            max_connections_per_ip: options.max_connections_per_ip.unwrap_or(0),
+            max_packets_per_second: options.max_packets_per_second.unwrap_or(0),
+            max_packet_size: options.max_packet_size,
+        }
+    }
+}
+
+fn nonzero<T: Default + PartialEq>(value: T) -> Option<T> {
+    if value == T::default() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Query {
+    pub enabled: bool,
+    pub port: u16,
+}
+
+impl Default for Query {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 25565,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Rcon {
+    pub enabled: bool,
+    pub port: u16,
+    pub password: String,
+}
+
+impl Default for Rcon {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 25575,
+            password: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Metrics {
+    pub enabled: bool,
+    pub address: SocketAddr,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: SocketAddr::from(([127, 0, 0, 1], 9225)),
+        }
+    }
+}
+
 fn deserialize_log_level<'de, D: Deserializer<'de>>(
     deserializer: D,
 ) -> Result<log::LevelFilter, D::Error> {
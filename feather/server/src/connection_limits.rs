@@ -0,0 +1,179 @@
+//! Limits on the connections and packets of clients.
+
+use std::{
+    net::IpAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use ahash::AHashMap;
+use parking_lot::Mutex;
+
+use crate::Options;
+
+/// Tracks the connections and logins of each IP address.
+///
+/// Can be cloned to create a new handle.
+#[derive(Clone)]
+pub struct ConnectionLimiter {
+    inner: Arc<Mutex<Inner>>,
+    max_connections_per_ip: Option<u32>,
+    connection_throttle: Option<Duration>,
+}
+
+#[derive(Default)]
+struct Inner {
+    open_connections: AHashMap<IpAddr, u32>,
+    last_logins: AHashMap<IpAddr, Instant>,
+}
+
+impl ConnectionLimiter {
+    pub fn new(options: &Options) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner::default())),
+            // Behind a proxy, every connection comes from the proxy.
+            max_connections_per_ip: options
+                .limits
+                .max_connections_per_ip
+                .filter(|_| options.proxy_mode.is_none()),
+            connection_throttle: options.limits.connection_throttle,
+        }
+    }
+
+    /// Registers a connection from `ip`, returning `None` if there
+    /// are too many connections from it. The connection is counted
+    /// until the returned `ConnectionSlot` is dropped.
+    pub fn try_open(&self, ip: IpAddr) -> Option<ConnectionSlot> {
+        let mut inner = self.inner.lock();
+        let count = inner.open_connections.entry(ip).or_insert(0);
+        if self.max_connections_per_ip.is_some_and(|max| *count >= max) {
+            return None;
+        }
+        *count += 1;
+        Some(ConnectionSlot {
+            limiter: self.clone(),
+            ip,
+        })
+    }
+
+    /// Registers a login attempt from `ip`, returning `false` if
+    /// the previous attempt was too recent. Throttled attempts
+    /// restart the time to wait.
+    pub fn try_login(&self, ip: IpAddr, now: Instant) -> bool {
+        let throttle = match self.connection_throttle {
+            Some(throttle) => throttle,
+            None => return true,
+        };
+        let mut inner = self.inner.lock();
+        inner
+            .last_logins
+            .retain(|_, last_login| now.saturating_duration_since(*last_login) < throttle);
+        inner.last_logins.insert(ip, now).is_none()
+    }
+}
+
+/// A connection counted by a [`ConnectionLimiter`].
+pub struct ConnectionSlot {
+    limiter: ConnectionLimiter,
+    ip: IpAddr,
+}
+
+impl ConnectionSlot {
+    pub fn limiter(&self) -> &ConnectionLimiter {
+        &self.limiter
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        let mut inner = self.limiter.inner.lock();
+        if let Some(count) = inner.open_connections.get_mut(&self.ip) {
+            *count -= 1;
+            if *count == 0 {
+                inner.open_connections.remove(&self.ip);
+            }
+        }
+    }
+}
+
+/// Limits the number of packets a client sends per second.
+pub struct PacketRateLimiter {
+    max_per_second: u32,
+    window_start: Instant,
+    count: u32,
+}
+
+impl PacketRateLimiter {
+    pub fn new(max_per_second: u32, now: Instant) -> Self {
+        Self {
+            max_per_second,
+            window_start: now,
+            count: 0,
+        }
+    }
+
+    /// Counts a received packet, returning `false` if the
+    /// client exceeded the limit.
+    pub fn allow(&mut self, now: Instant) -> bool {
+        if now.saturating_duration_since(self.window_start) >= Duration::from_secs(1) {
+            self.window_start = now;
+            self.count = 0;
+        }
+        self.count += 1;
+        self.count <= self.max_per_second
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::LimitOptions;
+
+    fn limiter(limits: LimitOptions) -> ConnectionLimiter {
+        ConnectionLimiter {
+            inner: Arc::new(Mutex::new(Inner::default())),
+            max_connections_per_ip: limits.max_connections_per_ip,
+            connection_throttle: limits.connection_throttle,
+        }
+    }
+
+    #[test]
+    fn connections_per_ip() {
+        let limiter = limiter(LimitOptions {
+            max_connections_per_ip: Some(2),
+            ..Default::default()
+        });
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        let first = limiter.try_open(ip).unwrap();
+        let _second = limiter.try_open(ip).unwrap();
+        assert!(limiter.try_open(ip).is_none());
+        assert!(limiter.try_open("10.0.0.2".parse().unwrap()).is_some());
+
+        drop(first);
+        assert!(limiter.try_open(ip).is_some());
+    }
+
+    #[test]
+    fn login_throttle() {
+        let limiter = limiter(LimitOptions {
+            connection_throttle: Some(Duration::from_secs(4)),
+            ..Default::default()
+        });
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        let start = Instant::now();
+        assert!(limiter.try_login(ip, start));
+        assert!(!limiter.try_login(ip, start + Duration::from_secs(3)));
+        assert!(!limiter.try_login(ip, start + Duration::from_secs(6)));
+        assert!(limiter.try_login(ip, start + Duration::from_secs(11)));
+        assert!(limiter.try_login("10.0.0.2".parse().unwrap(), start));
+    }
+
+    #[test]
+    fn packet_rate() {
+        let start = Instant::now();
+        let mut limiter = PacketRateLimiter::new(3, start);
+        assert!((0..3).all(|_| limiter.allow(start)));
+        assert!(!limiter.allow(start + Duration::from_millis(500)));
+        assert!(limiter.allow(start + Duration::from_secs(1)));
+    }
+}
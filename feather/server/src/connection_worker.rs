@@ -1,12 +1,18 @@
-use std::{fmt::Debug, io, net::SocketAddr, sync::Arc, time::Duration};
+use std::{
+    fmt::Debug,
+    io,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
+use anyhow::bail;
 use base::Text;
 use flume::{Receiver, Sender};
-use futures_lite::FutureExt;
 use io::ErrorKind;
 use protocol::{
-    codec::CryptKey, packets::server::Disconnect, ClientPlayPacket, MinecraftCodec, Readable,
-    ServerPlayPacket, Writeable,
+    codec::CryptKey, packets::server::Disconnect, ClientPlayPacket, MinecraftCodec,
+    ProtocolVersion, Readable, ServerPlayPacket, Writeable,
 };
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
@@ -14,15 +20,25 @@ use tokio::{
         tcp::{OwnedReadHalf, OwnedWriteHalf},
         TcpStream,
     },
-    time::timeout,
+    time::{error::Elapsed, timeout},
 };
 
 use crate::{
-    initial_handler::{InitialHandling, NewPlayer},
+    access::AccessLists,
+    connection_limits::{ConnectionLimiter, ConnectionSlot, PacketRateLimiter},
+    initial_handler::{
+        legacy_ping::{self, LegacyPing},
+        InitialHandling, NewPlayer,
+    },
+    metrics::TrafficCounters,
     options::Options,
     player_count::PlayerCount,
 };
 
+/// Time the writer is given to send pending packets,
+/// like a disconnect, after the reader stopped.
+const DISCONNECT_GRACE_PERIOD: Duration = Duration::from_secs(1);
+
 /// Tokio task which handles a connection and processes
 /// packets.
 ///
@@ -30,37 +46,60 @@ use crate::{
 /// * A connection is made, and the `Listener` spawns a `Worker`.
 /// * Connection goes through initial handling, i.e., the handshake process.
 /// * If the connection was not a status ping, then the main server thread
-/// is notified of the new connection via a channel.
+///   is notified of the new connection via a channel.
+/// * Once either half of the connection fails, both are closed.
 pub struct Worker {
     reader: Reader,
     writer: Writer,
+    addr: SocketAddr,
     options: Arc<Options>,
     player_count: PlayerCount,
+    access: AccessLists,
+    slot: ConnectionSlot,
     packets_to_send_tx: Sender<ServerPlayPacket>,
     received_packets_rx: Receiver<ClientPlayPacket>,
     new_players: Sender<NewPlayer>,
 }
 
 impl Worker {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         stream: TcpStream,
-        _addr: SocketAddr,
+        addr: SocketAddr,
         options: Arc<Options>,
         player_count: PlayerCount,
+        access: AccessLists,
+        slot: ConnectionSlot,
+        traffic: TrafficCounters,
         new_players: Sender<NewPlayer>,
     ) -> Self {
         let (reader, writer) = stream.into_split();
 
         let (received_packets_tx, received_packets_rx) = flume::bounded(32);
         let (packets_to_send_tx, packets_to_send_rx) = flume::unbounded();
-        let reader = Reader::new(reader, received_packets_tx);
-        let writer = Writer::new(writer, packets_to_send_rx);
+        let mut reader = Reader::new(
+            reader,
+            received_packets_tx,
+            packets_to_send_tx.clone(),
+            traffic.clone(),
+        );
+        reader
+            .codec
+            .set_max_packet_length(options.limits.max_packet_size);
+        reader.rate_limiter = options
+            .limits
+            .max_packets_per_second
+            .map(|max| PacketRateLimiter::new(max, Instant::now()));
+        let writer = Writer::new(writer, packets_to_send_rx, traffic);
 
         Self {
             reader,
             writer,
+            addr,
             options,
             player_count,
+            access,
+            slot,
             packets_to_send_tx,
             received_packets_rx,
             new_players,
@@ -74,7 +113,14 @@ impl Worker {
     }
 
     async fn run(mut self) {
-        let result = crate::initial_handler::handle(&mut self).await;
+        let login_timeout = self.options.limits.login_timeout;
+        let handling = crate::initial_handler::handle(&mut self);
+        let result = match login_timeout {
+            Some(duration) => timeout(duration, handling)
+                .await
+                .unwrap_or_else(|_| Err(anyhow::anyhow!("timed out"))),
+            None => handling.await,
+        };
         match result {
             Ok(result) => self.proceed(result).await,
             Err(e) => log::debug!("Initial handling failed: {:?}", e),
@@ -86,25 +132,51 @@ impl Worker {
             InitialHandling::Disconnect => (),
             InitialHandling::Join(new_player) => {
                 if self.player_count.try_add_player().is_err() {
-                    self.write(ServerPlayPacket::Disconnect(Disconnect {
-                        reason: Text::from("The server is full!").to_string(),
-                    }))
-                    .await
-                    .ok();
-                    return;
+                    if self.may_bypass_player_limit(&new_player) {
+                        self.player_count.add_player_unchecked();
+                    } else {
+                        self.write(ServerPlayPacket::Disconnect(Disconnect {
+                            reason: Text::from("The server is full!").to_string(),
+                        }))
+                        .await
+                        .ok();
+                        return;
+                    }
                 }
 
                 let username = new_player.username.clone();
+                self.player_count.add_username(&username);
                 let _ = self.new_players.send_async(new_player).await;
                 self.split(username);
             }
         }
     }
 
+    /// Operators with `bypassesPlayerLimit` set may join a full server.
+    fn may_bypass_player_limit(&self, player: &NewPlayer) -> bool {
+        self.access
+            .op(player.uuid)
+            .is_some_and(|op| op.bypasses_player_limit)
+    }
+
     pub fn options(&self) -> &Options {
         &self.options
     }
 
+    /// Returns the address of the connection. This is
+    /// the proxy's address if the client uses a proxy.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn access_lists(&self) -> &AccessLists {
+        &self.access
+    }
+
+    pub fn connections(&self) -> &ConnectionLimiter {
+        self.slot.limiter()
+    }
+
     pub fn player_count(&self) -> u32 {
         self.player_count.get()
     }
@@ -124,6 +196,17 @@ impl Worker {
         log::debug!("Enabled encryption");
     }
 
+    pub fn set_protocol_version(&mut self, version: ProtocolVersion) {
+        self.reader.codec.set_version(version);
+        self.writer.codec.set_version(version);
+
+        log::debug!("Using protocol version {}", version);
+    }
+
+    pub fn protocol_version(&self) -> ProtocolVersion {
+        self.reader.codec.version()
+    }
+
     pub async fn read<P: Readable>(&mut self) -> anyhow::Result<P> {
         self.reader.read().await
     }
@@ -132,23 +215,54 @@ impl Worker {
         self.writer.write(packet).await
     }
 
+    /// Reads the first bytes sent by the client, returning
+    /// the legacy ping they contain, if any.
+    ///
+    /// Must be called before reading any packet.
+    pub async fn read_legacy_ping(&mut self) -> anyhow::Result<Option<LegacyPing>> {
+        self.reader.read_legacy_ping().await
+    }
+
+    /// Writes bytes which aren't a packet, bypassing the codec.
+    pub async fn write_raw(&mut self, bytes: &[u8]) -> anyhow::Result<()> {
+        self.writer.stream.write_all(bytes).await?;
+        self.writer.traffic.add_sent(bytes.len());
+        Ok(())
+    }
+
     pub fn split(self, username: String) {
         let Self {
             reader,
             writer,
             player_count,
+            slot,
             ..
         } = self;
-        let reader = tokio::task::spawn(async move { reader.run().await });
-        let writer = tokio::task::spawn(async move { writer.run().await });
+        let mut reader = tokio::task::spawn(async move { reader.run().await });
+        let mut writer = tokio::task::spawn(async move { writer.run().await });
 
         tokio::task::spawn(async move {
-            let result = reader.race(writer).await.expect("task panicked");
+            let result = tokio::select! {
+                result = &mut reader => {
+                    // Give the writer time to send the reason for a disconnect.
+                    if timeout(DISCONNECT_GRACE_PERIOD, &mut writer).await.is_err() {
+                        writer.abort();
+                    }
+                    result
+                }
+                result = &mut writer => {
+                    reader.abort();
+                    result
+                }
+            }
+            .expect("task panicked");
             if let Err(e) = result {
                 let message = disconnected_message(e);
                 log::debug!("{} lost connection: {}", username, message);
             }
             player_count.remove_player();
+            player_count.remove_username(&username);
+            drop(slot);
         });
     }
 
@@ -166,21 +280,51 @@ struct Reader {
     codec: MinecraftCodec,
     buffer: [u8; 512],
     received_packets: Sender<ClientPlayPacket>,
+    /// Used to send the reason when disconnecting the client.
+    packets_to_send: Sender<ServerPlayPacket>,
+    rate_limiter: Option<PacketRateLimiter>,
+    traffic: TrafficCounters,
 }
 
 impl Reader {
-    pub fn new(stream: OwnedReadHalf, received_packets: Sender<ClientPlayPacket>) -> Self {
+    pub fn new(
+        stream: OwnedReadHalf,
+        received_packets: Sender<ClientPlayPacket>,
+        packets_to_send: Sender<ServerPlayPacket>,
+        traffic: TrafficCounters,
+    ) -> Self {
         Self {
             stream,
             codec: MinecraftCodec::new(),
             buffer: [0; 512],
             received_packets,
+            packets_to_send,
+            rate_limiter: None,
+            traffic,
         }
     }
 
     pub async fn run(mut self) -> anyhow::Result<()> {
         loop {
-            let packet = self.read::<ClientPlayPacket>().await?;
+            let packet = match self.read::<ClientPlayPacket>().await {
+                Ok(packet) => packet,
+                Err(e) => {
+                    // Connection errors leave nobody to tell.
+                    if e.downcast_ref::<io::Error>().is_none()
+                        && e.downcast_ref::<Elapsed>().is_none()
+                    {
+                        self.disconnect("Received an invalid packet");
+                    }
+                    return Err(e);
+                }
+            };
+            if let Some(rate_limiter) = &mut self.rate_limiter {
+                if !rate_limiter.allow(Instant::now()) {
+                    self.disconnect("Sent too many packets");
+                    bail!("exceeded the packet rate limit");
+                }
+            }
+
             let result = self.received_packets.send_async(packet).await;
             if result.is_err() {
                 // server dropped connection
@@ -189,6 +333,14 @@ impl Reader {
         }
     }
 
+    fn disconnect(&self, reason: &'static str) {
+        let _ = self
+            .packets_to_send
+            .send(ServerPlayPacket::Disconnect(Disconnect {
+                reason: Text::from(reason).to_string(),
+            }));
+    }
+
     pub async fn read<P: Readable>(&mut self) -> anyhow::Result<P> {
         // Keep reading bytes and trying to get the packet.
         loop {
@@ -196,16 +348,44 @@ impl Reader {
                 return Ok(packet);
             }
 
-            let duration = Duration::from_secs(10);
-            let read_bytes = timeout(duration, self.stream.read(&mut self.buffer)).await??;
-            if read_bytes == 0 {
-                return Err(io::Error::new(ErrorKind::UnexpectedEof, "read 0 bytes").into());
-            }
-
+            let read_bytes = self.read_bytes().await?;
             let bytes = &self.buffer[..read_bytes];
             self.codec.accept(bytes);
         }
     }
+
+    pub async fn read_legacy_ping(&mut self) -> anyhow::Result<Option<LegacyPing>> {
+        let mut read_bytes = self.read_bytes().await?;
+        if read_bytes == 1 && self.buffer[0] == legacy_ping::PACKET_ID {
+            // Clients from 1.4 onwards follow the packet ID with more bytes,
+            // which may arrive separately.
+            let duration = Duration::from_millis(100);
+            if let Ok(more) = timeout(duration, self.stream.read(&mut self.buffer[1..])).await {
+                let more = more?;
+                self.traffic.add_received(more);
+                read_bytes += more;
+            }
+        }
+
+        let bytes = &self.buffer[..read_bytes];
+        match LegacyPing::detect(bytes) {
+            Some(ping) => Ok(Some(ping)),
+            None => {
+                self.codec.accept(bytes);
+                Ok(None)
+            }
+        }
+    }
+
+    async fn read_bytes(&mut self) -> anyhow::Result<usize> {
+        let duration = Duration::from_secs(10);
+        let read_bytes = timeout(duration, self.stream.read(&mut self.buffer)).await??;
+        if read_bytes == 0 {
+            return Err(io::Error::new(ErrorKind::UnexpectedEof, "read 0 bytes").into());
+        }
+        self.traffic.add_received(read_bytes);
+        Ok(read_bytes)
+    }
 }
 
 struct Writer {
@@ -213,15 +393,21 @@ struct Writer {
     codec: MinecraftCodec,
     packets_to_send: Receiver<ServerPlayPacket>,
     buffer: Vec<u8>,
+    traffic: TrafficCounters,
 }
 
 impl Writer {
-    pub fn new(stream: OwnedWriteHalf, packets_to_send: Receiver<ServerPlayPacket>) -> Self {
+    pub fn new(
+        stream: OwnedWriteHalf,
+        packets_to_send: Receiver<ServerPlayPacket>,
+        traffic: TrafficCounters,
+    ) -> Self {
         Self {
             stream,
             codec: MinecraftCodec::new(),
             packets_to_send,
             buffer: Vec::new(),
+            traffic,
         }
     }
 
@@ -235,6 +421,7 @@ impl Writer {
     pub async fn write(&mut self, packet: impl Writeable + Debug) -> anyhow::Result<()> {
         self.codec.encode(&packet, &mut self.buffer)?;
         self.stream.write_all(&self.buffer).await?;
+        self.traffic.add_sent(self.buffer.len());
         self.buffer.clear();
         Ok(())
     }
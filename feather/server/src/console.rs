@@ -0,0 +1,367 @@
+//! The interactive server console.
+//!
+//! A background thread reads commands from stdin and queues them
+//! to be run by the console. When stdin and stdout are terminals,
+//! input is edited on a prompt line with history, and log output
+//! is written above the prompt through [`ConsoleWriter`].
+
+use std::{
+    io::{self, BufRead, Read, Write},
+    mem, thread,
+};
+
+use common::commands::CommandSender;
+use flume::Sender;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::{commands::CommandRequest, Server};
+
+mod terminal;
+
+const PROMPT: &str = "> ";
+
+/// Maximum number of lines kept in the history.
+const MAX_HISTORY: usize = 100;
+
+static EDITOR: Lazy<Mutex<LineEditor>> = Lazy::new(|| Mutex::new(LineEditor::default()));
+
+/// Starts reading commands from stdin.
+pub fn start(server: &Server) {
+    let commands = server.command_requests.clone();
+    thread::Builder::new()
+        .name("console".to_owned())
+        .spawn(move || {
+            let result = if terminal::is_interactive() {
+                run_interactive(&commands)
+            } else {
+                read_lines(&commands)
+            };
+            if let Err(e) = result {
+                log::error!("Failed to read console input: {}", e);
+            }
+        })
+        .expect("failed to spawn console thread");
+}
+
+fn run_interactive(commands: &Sender<CommandRequest>) -> io::Result<()> {
+    terminal::enable_raw_mode()?;
+    {
+        let mut editor = EDITOR.lock();
+        editor.interactive = true;
+        editor.draw(&mut io::stdout().lock())?;
+    }
+
+    let mut stdin = io::stdin().lock();
+    let mut parser = KeyParser::default();
+    let mut buffer = [0; 64];
+    loop {
+        let read_bytes = stdin.read(&mut buffer)?;
+        if read_bytes == 0 {
+            return Ok(());
+        }
+
+        for &byte in &buffer[..read_bytes] {
+            let key = match parser.push(byte) {
+                Some(key) => key,
+                None => continue,
+            };
+            let mut editor = EDITOR.lock();
+            let mut stdout = io::stdout().lock();
+            if let Some(line) = editor.handle_key(key) {
+                // Keep the entered command visible above the new prompt.
+                write!(stdout, "\r\x1b[2K{}{}\n", PROMPT, line)?;
+                queue_command(commands, line);
+            }
+            editor.draw(&mut stdout)?;
+        }
+    }
+}
+
+fn read_lines(commands: &Sender<CommandRequest>) -> io::Result<()> {
+    for line in io::stdin().lock().lines() {
+        queue_command(commands, line?);
+    }
+    Ok(())
+}
+
+fn queue_command(commands: &Sender<CommandRequest>, line: String) {
+    if !line.trim().is_empty() {
+        commands
+            .send(CommandRequest::new(CommandSender::Console, line))
+            .ok();
+    }
+}
+
+/// Writes log output to stdout above the prompt line.
+#[derive(Debug)]
+pub struct ConsoleWriter;
+
+impl Write for ConsoleWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut editor = EDITOR.lock();
+        let mut stdout = io::stdout().lock();
+        if editor.interactive && !editor.output_pending {
+            write!(stdout, "\r\x1b[2K")?;
+        }
+        stdout.write_all(buf)?;
+        editor.output_pending = !buf.ends_with(b"\n");
+        if editor.interactive && !editor.output_pending {
+            editor.draw(&mut stdout)?;
+        }
+        stdout.flush()?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stdout().flush()
+    }
+}
+
+/// A key pressed in the console.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Key {
+    Char(char),
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    Enter,
+}
+
+const ESCAPE: u8 = 0x1B;
+
+/// Control sequences longer than this are discarded.
+const MAX_SEQUENCE_LENGTH: usize = 16;
+
+/// Returns the key sent as the given control sequence, without its
+/// leading escape. Keys with modifiers aren't supported.
+fn control_sequence_key(sequence: &[u8]) -> Option<Key> {
+    match sequence {
+        b"A" => Some(Key::Up),
+        b"B" => Some(Key::Down),
+        b"C" => Some(Key::Right),
+        b"D" => Some(Key::Left),
+        b"H" | b"1~" | b"7~" => Some(Key::Home),
+        b"F" | b"4~" | b"8~" => Some(Key::End),
+        b"3~" => Some(Key::Delete),
+        _ => None,
+    }
+}
+
+/// Decodes keys from the bytes read from a terminal.
+#[derive(Debug, Default)]
+struct KeyParser {
+    pending: Vec<u8>,
+}
+
+impl KeyParser {
+    /// Adds a byte, returning the key it completes, if any.
+    fn push(&mut self, byte: u8) -> Option<Key> {
+        self.pending.push(byte);
+        let (key, complete) = match self.pending.as_slice() {
+            [b'\r'] | [b'\n'] => (Some(Key::Enter), true),
+            [0x7F] | [0x08] => (Some(Key::Backspace), true),
+            [ESCAPE] | [ESCAPE, b'O'] => (None, false),
+            [ESCAPE, b'O', code] => (control_sequence_key(&[*code]), true),
+            // Control sequences end with a byte in this range.
+            [ESCAPE, b'[', sequence @ ..] if matches!(sequence.last(), Some(0x40..=0x7E)) => {
+                (control_sequence_key(sequence), true)
+            }
+            [ESCAPE, b'[', sequence @ ..] => (None, sequence.len() > MAX_SEQUENCE_LENGTH),
+            // Other escape sequences are ignored.
+            [ESCAPE, ..] => (None, true),
+            bytes => match std::str::from_utf8(bytes) {
+                Ok(s) => {
+                    let c = s.chars().next().unwrap();
+                    let key = if c.is_control() {
+                        None
+                    } else {
+                        Some(Key::Char(c))
+                    };
+                    (key, true)
+                }
+                // The rest of a multi-byte character is still to come.
+                Err(e) if e.error_len().is_none() => (None, false),
+                Err(_) => (None, true),
+            },
+        };
+        if complete {
+            self.pending.clear();
+        }
+        key
+    }
+}
+
+/// The prompt line and its history.
+#[derive(Debug, Default)]
+struct LineEditor {
+    /// Whether the prompt is shown.
+    interactive: bool,
+    /// Whether log output which doesn't end in a newline was written,
+    /// in which case the prompt is hidden until the line ends.
+    output_pending: bool,
+    line: Vec<char>,
+    cursor: usize,
+    history: Vec<String>,
+    /// Index of the history entry shown, or `None` if a new line is edited.
+    history_index: Option<usize>,
+    /// The new line, kept while browsing the history.
+    draft: Vec<char>,
+}
+
+impl LineEditor {
+    /// Handles a key, returning the entered line if it was `Enter`.
+    fn handle_key(&mut self, key: Key) -> Option<String> {
+        match key {
+            Key::Char(c) => {
+                self.line.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+            Key::Backspace => {
+                if self.cursor > 0 {
+                    self.cursor -= 1;
+                    self.line.remove(self.cursor);
+                }
+            }
+            Key::Delete => {
+                if self.cursor < self.line.len() {
+                    self.line.remove(self.cursor);
+                }
+            }
+            Key::Left => self.cursor = self.cursor.saturating_sub(1),
+            Key::Right => self.cursor = (self.cursor + 1).min(self.line.len()),
+            Key::Home => self.cursor = 0,
+            Key::End => self.cursor = self.line.len(),
+            Key::Up => self.history_previous(),
+            Key::Down => self.history_next(),
+            Key::Enter => return Some(self.submit()),
+        }
+        None
+    }
+
+    fn history_previous(&mut self) {
+        let index = match self.history_index {
+            Some(0) => return,
+            Some(index) => index - 1,
+            None if self.history.is_empty() => return,
+            None => {
+                self.draft = mem::take(&mut self.line);
+                self.history.len() - 1
+            }
+        };
+        self.history_index = Some(index);
+        self.set_line(self.history[index].chars().collect());
+    }
+
+    fn history_next(&mut self) {
+        match self.history_index {
+            None => (),
+            Some(index) if index + 1 < self.history.len() => {
+                self.history_index = Some(index + 1);
+                self.set_line(self.history[index + 1].chars().collect());
+            }
+            Some(_) => {
+                self.history_index = None;
+                let draft = mem::take(&mut self.draft);
+                self.set_line(draft);
+            }
+        }
+    }
+
+    fn set_line(&mut self, line: Vec<char>) {
+        self.cursor = line.len();
+        self.line = line;
+    }
+
+    fn submit(&mut self) -> String {
+        let line: String = mem::take(&mut self.line).into_iter().collect();
+        self.cursor = 0;
+        self.history_index = None;
+        self.draft.clear();
+
+        if !line.trim().is_empty() && self.history.last() != Some(&line) {
+            self.history.push(line.clone());
+            if self.history.len() > MAX_HISTORY {
+                self.history.remove(0);
+            }
+        }
+        line
+    }
+
+    /// Redraws the prompt line.
+    fn draw(&self, out: &mut impl Write) -> io::Result<()> {
+        let line: String = self.line.iter().collect();
+        write!(out, "\r\x1b[2K{}{}", PROMPT, line)?;
+        let back = self.line.len() - self.cursor;
+        if back > 0 {
+            write!(out, "\x1b[{}D", back)?;
+        }
+        out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(bytes: &[u8]) -> Vec<Key> {
+        let mut parser = KeyParser::default();
+        bytes.iter().filter_map(|&b| parser.push(b)).collect()
+    }
+
+    fn type_line(editor: &mut LineEditor, line: &str) -> Option<String> {
+        for c in line.chars() {
+            editor.handle_key(Key::Char(c));
+        }
+        editor.handle_key(Key::Enter)
+    }
+
+    #[test]
+    fn parse_keys() {
+        assert_eq!(
+            parse("sé\x7f\r".as_bytes()),
+            vec![Key::Char('s'), Key::Char('é'), Key::Backspace, Key::Enter]
+        );
+        assert_eq!(
+            parse(b"\x1b[A\x1bOB\x1b[3~\x1b[1;5Cx"),
+            vec![Key::Up, Key::Down, Key::Delete, Key::Char('x')]
+        );
+    }
+
+    #[test]
+    fn edit_line() {
+        let mut editor = LineEditor::default();
+        for c in "sy".chars() {
+            editor.handle_key(Key::Char(c));
+        }
+        editor.handle_key(Key::Left);
+        editor.handle_key(Key::Char('a'));
+        editor.handle_key(Key::End);
+        editor.handle_key(Key::Backspace);
+        assert_eq!(type_line(&mut editor, "y hi"), Some("say hi".to_owned()));
+    }
+
+    #[test]
+    fn browse_history() {
+        let mut editor = LineEditor::default();
+        type_line(&mut editor, "list");
+        type_line(&mut editor, "say hi");
+        type_line(&mut editor, "say hi");
+        assert_eq!(editor.history, vec!["list", "say hi"]);
+
+        editor.handle_key(Key::Char('x'));
+        editor.handle_key(Key::Up);
+        editor.handle_key(Key::Up);
+        editor.handle_key(Key::Up);
+        assert_eq!(editor.line.iter().collect::<String>(), "list");
+        editor.handle_key(Key::Down);
+        editor.handle_key(Key::Down);
+        assert_eq!(editor.line.iter().collect::<String>(), "x");
+        assert_eq!(editor.handle_key(Key::Enter), Some("x".to_owned()));
+    }
+}
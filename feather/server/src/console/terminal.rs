@@ -0,0 +1,72 @@
+//! Switches the terminal to reading input key by key.
+//!
+//! Only supported on Unix. Elsewhere, the console reads whole lines.
+
+use std::io;
+
+/// Determines whether stdin and stdout are terminals
+/// on which the prompt line can be edited.
+pub fn is_interactive() -> bool {
+    imp::is_interactive()
+}
+
+/// Disables line buffering and echoing of input.
+/// The original mode is restored when the process exits.
+pub fn enable_raw_mode() -> io::Result<()> {
+    imp::enable_raw_mode()
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::{io, mem};
+
+    use once_cell::sync::OnceCell;
+
+    static ORIGINAL_MODE: OnceCell<libc::termios> = OnceCell::new();
+
+    pub fn is_interactive() -> bool {
+        unsafe { libc::isatty(libc::STDIN_FILENO) == 1 && libc::isatty(libc::STDOUT_FILENO) == 1 }
+    }
+
+    pub fn enable_raw_mode() -> io::Result<()> {
+        unsafe {
+            let mut mode: libc::termios = mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut mode) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if ORIGINAL_MODE.set(mode).is_ok() {
+                libc::atexit(restore_mode);
+            }
+
+            // Signals stay enabled, so Ctrl+C still interrupts the server.
+            mode.c_lflag &= !(libc::ICANON | libc::ECHO);
+            mode.c_cc[libc::VMIN] = 1;
+            mode.c_cc[libc::VTIME] = 0;
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &mode) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    extern "C" fn restore_mode() {
+        if let Some(mode) = ORIGINAL_MODE.get() {
+            unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, mode);
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    use std::io;
+
+    pub fn is_interactive() -> bool {
+        false
+    }
+
+    pub fn enable_raw_mode() -> io::Result<()> {
+        Ok(())
+    }
+}
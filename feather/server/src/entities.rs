@@ -1,6 +1,16 @@
-use base::{EntityKind, Position};
+use std::convert::TryFrom;
+
+use base::{EntityKind, EntityMetadata, Position};
+use common::{
+    effects::{area_effect_cloud::AreaEffectCloudState, potions::ThrownPotion},
+    experience::ExperienceOrbState,
+};
 use ecs::{EntityBuilder, EntityRef, SysResult};
-use quill_common::{components::OnGround, entity_init::EntityInit};
+use libcraft_items::InventorySlot;
+use quill_common::{
+    components::{ActiveEffects, OnGround},
+    entity_init::EntityInit,
+};
 use uuid::Uuid;
 
 use crate::{Client, NetworkId};
@@ -25,6 +35,29 @@ pub struct PreviousPosition(pub Position);
 /// what movement packet to send.
 #[derive(Copy, Clone, Debug)]
 pub struct PreviousOnGround(pub OnGround);
+/// Stores the health and food values last
+/// sent to a player. Used to determine
+/// when to send health updates.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct PreviousHealth(pub Option<(f32, u32, f32)>);
+/// Stores the experience progress, level and total
+/// last sent to a player. Used to determine when
+/// to send experience updates.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct PreviousExperience(pub Option<(f32, u32, u32)>);
+/// Stores the status effects last sent for an entity.
+/// Used to determine when to send effect updates.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PreviousEffects(pub ActiveEffects);
+
+/// Metadata index of the item shown for a thrown potion.
+const META_INDEX_THROWN_ITEM: u8 = 7;
+
+/// Metadata index of the radius of an area effect cloud.
+pub const META_INDEX_CLOUD_RADIUS: u8 = 7;
+
+/// Metadata index of the particle color of an area effect cloud.
+const META_INDEX_CLOUD_COLOR: u8 = 8;
 
 pub fn add_entity_components(builder: &mut EntityBuilder, init: &EntityInit) {
     if !builder.has::<NetworkId>() {
@@ -40,6 +73,9 @@ pub fn add_entity_components(builder: &mut EntityBuilder, init: &EntityInit) {
     builder
         .add(PreviousPosition(prev_position))
         .add(PreviousOnGround(on_ground));
+    if builder.has::<ActiveEffects>() {
+        builder.add(PreviousEffects::default());
+    }
     add_spawn_packet(builder, init);
 }
 
@@ -48,6 +84,9 @@ fn add_spawn_packet(builder: &mut EntityBuilder, init: &EntityInit) {
     // (minecarts, items, ...)
     let spawn_packet = match init {
         EntityInit::Player => spawn_player,
+        EntityInit::ExperienceOrb => spawn_experience_orb,
+        EntityInit::Potion => spawn_thrown_potion,
+        EntityInit::AreaEffectCloud => spawn_area_effect_cloud,
         _ => spawn_living_entity,
     };
     builder.add(SpawnPacketSender(spawn_packet));
@@ -59,6 +98,7 @@ fn spawn_player(entity: &EntityRef, client: &Client) -> SysResult {
     let pos = *entity.get::<Position>()?;
 
     client.send_player(network_id, uuid, pos);
+    send_effects(entity, client, network_id);
     Ok(())
 }
 
@@ -69,5 +109,63 @@ fn spawn_living_entity(entity: &EntityRef, client: &Client) -> SysResult {
     let kind = *entity.get::<EntityKind>()?;
 
     client.send_living_entity(network_id, uuid, pos, kind);
+    send_effects(entity, client, network_id);
+    Ok(())
+}
+
+/// Sends the status effects of a newly visible entity.
+fn send_effects(entity: &EntityRef, client: &Client, network_id: NetworkId) {
+    if let Ok(effects) = entity.get::<ActiveEffects>() {
+        for (effect, instance) in effects.iter() {
+            client.send_entity_effect(network_id, effect, instance);
+        }
+    }
+}
+
+fn spawn_experience_orb(entity: &EntityRef, client: &Client) -> SysResult {
+    let network_id = *entity.get::<NetworkId>()?;
+    let pos = *entity.get::<Position>()?;
+    let value = entity.get::<ExperienceOrbState>()?.value;
+
+    client.send_experience_orb(network_id, pos, u16::try_from(value).unwrap_or(u16::MAX));
+    Ok(())
+}
+
+fn spawn_thrown_potion(entity: &EntityRef, client: &Client) -> SysResult {
+    let network_id = *entity.get::<NetworkId>()?;
+    let uuid = *entity.get::<Uuid>()?;
+    let pos = *entity.get::<Position>()?;
+    let potion = entity.get::<ThrownPotion>()?;
+
+    client.send_object_entity(network_id, uuid, pos, EntityKind::Potion, potion.velocity());
+    client.send_entity_metadata(
+        network_id,
+        EntityMetadata::entity_base().with(
+            META_INDEX_THROWN_ITEM,
+            InventorySlot::Filled(potion.item.clone()),
+        ),
+    );
+    Ok(())
+}
+
+fn spawn_area_effect_cloud(entity: &EntityRef, client: &Client) -> SysResult {
+    let network_id = *entity.get::<NetworkId>()?;
+    let uuid = *entity.get::<Uuid>()?;
+    let pos = *entity.get::<Position>()?;
+    let cloud = entity.get::<AreaEffectCloudState>()?;
+
+    client.send_object_entity(
+        network_id,
+        uuid,
+        pos,
+        EntityKind::AreaEffectCloud,
+        Default::default(),
+    );
+    client.send_entity_metadata(
+        network_id,
+        EntityMetadata::entity_base()
+            .with(META_INDEX_CLOUD_RADIUS, cloud.radius)
+            .with(META_INDEX_CLOUD_COLOR, cloud.color as i32),
+    );
     Ok(())
 }
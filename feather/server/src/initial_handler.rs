@@ -16,20 +16,20 @@ use protocol::{
         },
     },
     ClientHandshakePacket, ClientLoginPacket, ClientPlayPacket, ClientStatusPacket,
-    ServerLoginPacket, ServerPlayPacket, ServerStatusPacket,
+    ProtocolVersion, ServerLoginPacket, ServerPlayPacket, ServerStatusPacket,
 };
 use rand::rngs::OsRng;
 use rsa::{PaddingScheme, PublicKeyParts, RsaPrivateKey};
 use serde::{Deserialize, Serialize};
 use sha1_smol::Sha1;
-use std::convert::TryInto;
+use std::{convert::TryInto, net::IpAddr, time::Instant};
 use uuid::Uuid;
 
 use self::proxy::ProxyData;
 
 const SERVER_NAME: &str = "Feather 1.16.5";
-const PROTOCOL_VERSION: i32 = 754;
 
+pub mod legacy_ping;
 mod proxy;
 
 /// Information for a newly connected player.
@@ -38,6 +38,10 @@ pub struct NewPlayer {
     pub uuid: Uuid,
     pub username: String,
     pub profile: Vec<ProfileProperty>,
+    pub protocol_version: ProtocolVersion,
+    /// The IP address of the client, as forwarded
+    /// by the proxy if there is one.
+    pub ip: IpAddr,
 
     pub received_packets: Receiver<ClientPlayPacket>,
     pub packets_to_send: Sender<ServerPlayPacket>,
@@ -55,27 +59,36 @@ pub enum InitialHandling {
 /// Handles a connection until the protocol state is switched to Play;
 /// that is, until we send Login Success. Returns the client's information.
 pub async fn handle(worker: &mut Worker) -> anyhow::Result<InitialHandling> {
+    if let Some(ping) = worker.read_legacy_ping().await? {
+        return legacy_ping::handle(worker, ping).await;
+    }
+
     // Get the handshake packet.
     let handshake = worker.read::<ClientHandshakePacket>().await?;
 
     let ClientHandshakePacket::Handshake(handshake) = handshake;
 
+    let version = ProtocolVersion::from_id(handshake.protocol_version);
     match handshake.next_state {
-        HandshakeState::Status => handle_status(worker).await,
+        HandshakeState::Status => handle_status(worker, version).await,
         HandshakeState::Login => {
-            if handshake.protocol_version != PROTOCOL_VERSION {
-                worker
-                    .write(ServerLoginPacket::DisconnectLogin(DisconnectLogin {
-                        reason: Text::from(format!(
-                            "Invalid protocol! The server is running on version {}",
-                            SERVER_NAME
-                        ))
-                        .to_string(),
-                    }))
-                    .await
-                    .ok();
-                return Ok(InitialHandling::Disconnect);
-            }
+            let version = match version {
+                Some(version) => version,
+                None => {
+                    worker
+                        .write(ServerLoginPacket::DisconnectLogin(DisconnectLogin {
+                            reason: Text::from(format!(
+                                "Invalid protocol! The server supports versions {}",
+                                supported_versions()
+                            ))
+                            .to_string(),
+                        }))
+                        .await
+                        .ok();
+                    return Ok(InitialHandling::Disconnect);
+                }
+            };
+            worker.set_protocol_version(version);
             let proxy_data =
                 if let Some(crate::options::ProxyMode::Bungeecord) = worker.options().proxy_mode {
                     Some(proxy::do_bungee_ip_forwarding(&handshake)?)
@@ -87,6 +100,15 @@ pub async fn handle(worker: &mut Worker) -> anyhow::Result<InitialHandling> {
     }
 }
 
+/// Returns the names of the supported game versions, for display to players.
+fn supported_versions() -> String {
+    ProtocolVersion::SUPPORTED
+        .iter()
+        .map(|version| version.name())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 #[derive(Debug, Serialize)]
 struct StatusResponse<'a> {
     version: Version,
@@ -108,13 +130,20 @@ struct Players {
     online: u32,
 }
 
-async fn handle_status(worker: &mut Worker) -> anyhow::Result<InitialHandling> {
+async fn handle_status(
+    worker: &mut Worker,
+    version: Option<ProtocolVersion>,
+) -> anyhow::Result<InitialHandling> {
     let _request = worker.read::<ClientStatusPacket>().await?;
 
+    // Supported clients are shown their own version, so that
+    // they don't display the server as incompatible.
+    let version = version.unwrap_or(ProtocolVersion::LATEST);
+
     let payload = StatusResponse {
         version: Version {
             name: SERVER_NAME,
-            protocol: PROTOCOL_VERSION,
+            protocol: version.id(),
         },
         players: Players {
             max: worker.options().max_players,
@@ -164,19 +193,52 @@ async fn handle_login(
         proxy_data = Some(proxy::do_velocity_ip_forwarding(worker).await?);
     }
 
-    if worker.options().online_mode {
-        enable_encryption(worker, login_start.name).await
+    let ip = client_ip(worker, proxy_data.as_ref());
+    if !worker.connections().try_login(ip, Instant::now()) {
+        log::debug!("Throttled login of {} from {}", login_start.name, ip);
+        worker
+            .write(ServerLoginPacket::DisconnectLogin(DisconnectLogin {
+                reason: Text::from("Connection throttled! Please wait before reconnecting.")
+                    .to_string(),
+            }))
+            .await
+            .ok();
+        return Ok(InitialHandling::Disconnect);
+    }
+
+    let profile = if worker.options().online_mode {
+        enable_encryption(worker, login_start.name).await?
     } else {
-        let profile = match proxy_data {
+        match proxy_data {
             Some(proxy_data) => AuthResponse {
                 id: proxy_data.uuid,
                 name: login_start.name.clone(),
                 properties: proxy_data.profile,
             },
             None => offline_mode_profile(login_start.name),
-        };
-        finish_login(worker, profile).await
+        }
+    };
+
+    if let Err(reason) = worker.access_lists().check_login(profile.id, ip) {
+        log::info!("{} ({}) was denied access to the server", profile.name, ip);
+        worker
+            .write(ServerLoginPacket::DisconnectLogin(DisconnectLogin {
+                reason: reason.to_string(),
+            }))
+            .await
+            .ok();
+        return Ok(InitialHandling::Disconnect);
     }
+
+    finish_login(worker, profile, ip).await
+}
+
+/// Returns the IP address of the client, which is
+/// forwarded by the proxy if the client uses one.
+fn client_ip(worker: &Worker, proxy_data: Option<&ProxyData>) -> IpAddr {
+    proxy_data
+        .and_then(|data| data.client.parse().ok())
+        .unwrap_or_else(|| worker.addr().ip())
 }
 
 fn offline_mode_profile(username: String) -> AuthResponse {
@@ -188,7 +250,7 @@ fn offline_mode_profile(username: String) -> AuthResponse {
     }
 }
 
-fn offline_mode_uuid(username: &str) -> Uuid {
+pub(crate) fn offline_mode_uuid(username: &str) -> Uuid {
     // See: https://gist.github.com/games647/2b6a00a8fc21fd3b88375f03c9e2e603
     let mut hasher = md5::Md5::default();
     hasher.update(format!("OfflinePlayer:{}", username).as_bytes());
@@ -212,17 +274,12 @@ static RSA_KEY_ENCODED: Lazy<Vec<u8>> = Lazy::new(|| {
     rsa_der::public_key_to_der(&RSA_KEY.n().to_bytes_be(), &RSA_KEY.e().to_bytes_be())
 });
 
-async fn enable_encryption(
-    worker: &mut Worker,
-    username: String,
-) -> anyhow::Result<InitialHandling> {
+async fn enable_encryption(worker: &mut Worker, username: String) -> anyhow::Result<AuthResponse> {
     log::debug!("Authenticating {}", username);
     let shared_secret = do_encryption_handshake(worker).await?;
     worker.enable_encryption(shared_secret);
 
-    let response = authenticate(shared_secret, username).await?;
-
-    finish_login(worker, response).await
+    authenticate(shared_secret, username).await
 }
 
 async fn do_encryption_handshake(worker: &mut Worker) -> anyhow::Result<CryptKey> {
@@ -295,6 +352,7 @@ fn hexdigest(bytes: &[u8]) -> String {
 async fn finish_login(
     worker: &mut Worker,
     response: AuthResponse,
+    ip: IpAddr,
 ) -> anyhow::Result<InitialHandling> {
     enable_compression(worker).await?;
 
@@ -310,6 +368,8 @@ async fn finish_login(
         username: response.name,
         uuid: response.id,
         profile: response.properties,
+        protocol_version: worker.protocol_version(),
+        ip,
         received_packets: worker.received_packets(),
         packets_to_send: worker.packets_to_send(),
     };
@@ -0,0 +1,113 @@
+//! The server list ping of clients older than 1.7, which
+//! is sent in place of a handshake.
+
+use crate::connection_worker::Worker;
+
+use super::{InitialHandling, SERVER_NAME};
+
+/// First byte of a legacy ping. Handshakes start with
+/// a short packet length, so they never begin with this byte.
+pub const PACKET_ID: u8 = 0xFE;
+
+/// ID of the kick packet carrying the response.
+const KICK_PACKET_ID: u8 = 0xFF;
+
+/// Protocol number sent in the response. No legacy client
+/// supports it, so they display the server's version name instead.
+const LEGACY_PROTOCOL: i32 = 127;
+
+/// A legacy server list ping.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LegacyPing {
+    /// Sent by Beta 1.8 through 1.3, which only display
+    /// the MOTD and the player count.
+    Beta,
+    /// Sent by 1.4 through 1.6, which also display the version.
+    V1_4,
+}
+
+impl LegacyPing {
+    /// Detects a legacy ping from the first bytes sent by a client.
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [PACKET_ID, 0x01, ..] => Some(LegacyPing::V1_4),
+            [PACKET_ID, ..] => Some(LegacyPing::Beta),
+            _ => None,
+        }
+    }
+}
+
+/// Encodes the kick packet answering the given ping.
+pub fn encode_response(ping: LegacyPing, motd: &str, online: u32, max: u32) -> Vec<u8> {
+    let message = match ping {
+        // The fields are separated by section signs, so the MOTD can't contain them.
+        LegacyPing::Beta => format!("{}§{}§{}", motd.replace('§', ""), online, max),
+        LegacyPing::V1_4 => format!(
+            "§1\0{}\0{}\0{}\0{}\0{}",
+            LEGACY_PROTOCOL, SERVER_NAME, motd, online, max
+        ),
+    };
+
+    let chars: Vec<u16> = message.encode_utf16().collect();
+    let mut response = Vec::with_capacity(3 + chars.len() * 2);
+    response.push(KICK_PACKET_ID);
+    response.extend_from_slice(&(chars.len() as u16).to_be_bytes());
+    for c in chars {
+        response.extend_from_slice(&c.to_be_bytes());
+    }
+    response
+}
+
+pub async fn handle(worker: &mut Worker, ping: LegacyPing) -> anyhow::Result<InitialHandling> {
+    log::debug!("Answering legacy server list ping ({:?})", ping);
+    let response = encode_response(
+        ping,
+        &worker.options().motd,
+        worker.player_count(),
+        worker.options().max_players,
+    );
+    worker.write_raw(&response).await?;
+    Ok(InitialHandling::Disconnect)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(response: &[u8]) -> String {
+        assert_eq!(response[0], KICK_PACKET_ID);
+        let length = u16::from_be_bytes([response[1], response[2]]) as usize;
+        let chars: Vec<u16> = response[3..]
+            .chunks(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]))
+            .collect();
+        assert_eq!(chars.len(), length);
+        String::from_utf16(&chars).unwrap()
+    }
+
+    #[test]
+    fn detect() {
+        assert_eq!(LegacyPing::detect(&[0xFE]), Some(LegacyPing::Beta));
+        assert_eq!(
+            LegacyPing::detect(&[0xFE, 0x01, 0xFA]),
+            Some(LegacyPing::V1_4)
+        );
+        // A modern handshake starts with its length.
+        assert_eq!(LegacyPing::detect(&[0x10, 0x00]), None);
+    }
+
+    #[test]
+    fn beta_response() {
+        let response = encode_response(LegacyPing::Beta, "§aA server", 3, 16);
+        assert_eq!(decode(&response), "aA server§3§16");
+    }
+
+    #[test]
+    fn v1_4_response() {
+        let response = encode_response(LegacyPing::V1_4, "A server", 3, 16);
+        assert_eq!(
+            decode(&response),
+            format!("§1\x00127\x00{}\x00A server\x003\x0016", SERVER_NAME)
+        );
+    }
+}
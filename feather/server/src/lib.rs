@@ -2,32 +2,47 @@
 
 use std::{sync::Arc, time::Instant};
 
+use access::AccessLists;
 use base::Position;
 use chunk_subscriptions::ChunkSubscriptions;
+use commands::CommandRequest;
 use common::Game;
 use ecs::SystemExecutor;
-use flume::Receiver;
+use flume::{Receiver, Sender};
 use initial_handler::NewPlayer;
 use listener::Listener;
+use metrics::{Metrics, MetricsListener};
+use query::QueryListener;
+use rcon::RconListener;
+use shutdown::ShutdownHandle;
 
+pub mod access;
 mod chunk_subscriptions;
 pub mod client;
+mod commands;
 pub mod config;
+mod connection_limits;
 mod connection_worker;
+pub mod console;
 mod entities;
 pub mod favicon;
 mod initial_handler;
 mod listener;
+mod metrics;
 mod network_id_registry;
 mod options;
 mod packet_handlers;
 mod player_count;
+mod query;
+mod rcon;
+pub mod shutdown;
 mod systems;
 
 pub use client::{Client, ClientId, Clients};
 pub use network_id_registry::NetworkId;
-pub use options::Options;
+pub use options::{LimitOptions, MovementOptions, Options};
 use player_count::PlayerCount;
+pub use query::PluginNames;
 use systems::view::WaitingChunks;
 
 /// A Minecraft server.
@@ -48,6 +63,14 @@ pub struct Server {
     last_keepalive_time: Instant,
 
     player_count: PlayerCount,
+    plugin_names: PluginNames,
+    access: AccessLists,
+    metrics: Metrics,
+
+    command_requests: Sender<CommandRequest>,
+    pending_commands: Receiver<CommandRequest>,
+
+    shutdown: ShutdownHandle,
 }
 
 impl Server {
@@ -57,9 +80,49 @@ impl Server {
     pub async fn bind(options: Options) -> anyhow::Result<Self> {
         let options = Arc::new(options);
         let player_count = PlayerCount::new(options.max_players);
+        let access = AccessLists::load(".", options.whitelist)?;
+
+        let metrics = Metrics::default();
 
         let (new_players_tx, new_players) = flume::bounded(4);
-        Listener::start(Arc::clone(&options), player_count.clone(), new_players_tx).await?;
+        Listener::start(
+            Arc::clone(&options),
+            player_count.clone(),
+            access.clone(),
+            metrics.traffic().clone(),
+            new_players_tx,
+        )
+        .await?;
+
+        let plugin_names = PluginNames::default();
+        if let Some(port) = options.query_port {
+            QueryListener::start(
+                port,
+                Arc::clone(&options),
+                player_count.clone(),
+                plugin_names.clone(),
+            )
+            .await?;
+        }
+
+        let (command_requests, pending_commands) = flume::unbounded();
+        if let Some(port) = options.rcon_port {
+            if options.rcon_password.is_empty() {
+                log::warn!("RCON is enabled but has no password; not starting it");
+            } else {
+                RconListener::start(
+                    &options.bind_address,
+                    port,
+                    &options.rcon_password,
+                    command_requests.clone(),
+                )
+                .await?;
+            }
+        }
+
+        if let Some(address) = options.metrics_address {
+            MetricsListener::start(address, metrics.clone(), player_count.clone()).await?;
+        }
 
         log::info!(
             "Server is listening on {}:{}",
@@ -75,6 +138,12 @@ impl Server {
             chunk_subscriptions: ChunkSubscriptions::default(),
             last_keepalive_time: Instant::now(),
             player_count,
+            plugin_names,
+            access,
+            metrics,
+            command_requests,
+            pending_commands,
+            shutdown: ShutdownHandle::default(),
         })
     }
 
@@ -89,6 +158,21 @@ impl Server {
     pub fn player_count(&self) -> u32 {
         self.player_count.get()
     }
+
+    /// Gets a handle to the plugin names reported to Query clients.
+    pub fn plugin_names(&self) -> PluginNames {
+        self.plugin_names.clone()
+    }
+
+    /// Gets the whitelist, ban lists and operator list.
+    pub fn access_lists(&self) -> &AccessLists {
+        &self.access
+    }
+
+    /// Gets a handle to request a shutdown of the server.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
 }
 
 /// Low-level functions, mostly used internally.
@@ -115,6 +199,12 @@ impl Server {
         }
     }
 
+    /// Queues a command to be run after packets are handled.
+    fn queue_command(&self, request: CommandRequest) {
+        // The receiver is owned by the server, so this can't fail.
+        self.command_requests.send(request).ok();
+    }
+
     fn create_client(&mut self, player: NewPlayer) -> ClientId {
         log::debug!("Creating client for {}", player.username);
         let client = Client::new(player, Arc::clone(&self.options));
@@ -1,19 +1,47 @@
-use std::{net::SocketAddr, sync::Arc};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
 
 use anyhow::Context;
 use flume::Sender;
 use tokio::net::{TcpListener, TcpStream};
 
 use crate::{
-    connection_worker::Worker, initial_handler::NewPlayer, options::Options,
+    access::AccessLists, connection_limits::ConnectionLimiter, connection_worker::Worker,
+    initial_handler::NewPlayer, metrics::TrafficCounters, options::Options,
     player_count::PlayerCount,
 };
 
+/// Delays before accepting connections again after an error, like
+/// running out of file descriptors, doubling up to the maximum.
+const MIN_ACCEPT_BACKOFF: Duration = Duration::from_millis(50);
+const MAX_ACCEPT_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Accepts a connection from `listener`, retrying after errors.
+///
+/// Errors are logged and followed by a growing delay, so that lasting
+/// errors don't turn accept loops into busy loops. `kind` names the
+/// connections in the log.
+pub async fn accept_with_backoff(listener: &TcpListener, kind: &str) -> (TcpStream, SocketAddr) {
+    let mut backoff = MIN_ACCEPT_BACKOFF;
+    loop {
+        match listener.accept().await {
+            Ok(connection) => return connection,
+            Err(e) => {
+                log::warn!("Failed to accept an incoming {} connection: {}", kind, e);
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_ACCEPT_BACKOFF);
+            }
+        }
+    }
+}
+
 /// Listens for and accepts incoming connections.
 pub struct Listener {
     listener: TcpListener,
     options: Arc<Options>,
     player_count: PlayerCount,
+    access: AccessLists,
+    connections: ConnectionLimiter,
+    traffic: TrafficCounters,
     new_players: Sender<NewPlayer>,
 }
 
@@ -21,6 +49,8 @@ impl Listener {
     pub async fn start(
         options: Arc<Options>,
         player_count: PlayerCount,
+        access: AccessLists,
+        traffic: TrafficCounters,
         new_players: Sender<NewPlayer>,
     ) -> anyhow::Result<()> {
         let listener = TcpListener::bind(format!("{}:{}", options.bind_address, options.port))
@@ -29,8 +59,11 @@ impl Listener {
 
         let listener = Listener {
             listener,
+            connections: ConnectionLimiter::new(&options),
             options,
             player_count,
+            access,
+            traffic,
             new_players,
         };
         tokio::task::spawn(async move {
@@ -49,11 +82,21 @@ impl Listener {
     }
 
     async fn accept(&mut self, stream: TcpStream, addr: SocketAddr) {
+        let slot = match self.connections.try_open(addr.ip()) {
+            Some(slot) => slot,
+            None => {
+                log::debug!("Refusing connection from {}: too many connections", addr);
+                return;
+            }
+        };
         let worker = Worker::new(
             stream,
             addr,
             Arc::clone(&self.options),
             self.player_count.clone(),
+            self.access.clone(),
+            slot,
+            self.traffic.clone(),
             self.new_players.clone(),
         );
         worker.start();
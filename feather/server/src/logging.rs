@@ -1,4 +1,7 @@
+use std::io::Write;
+
 use colored::Colorize;
+use feather_server::console::ConsoleWriter;
 use log::{Level, LevelFilter};
 use time::macros::format_description;
 use time::OffsetDateTime;
@@ -41,7 +44,7 @@ pub fn init(level: LevelFilter) {
         .level_for("regalloc", LevelFilter::Off)
         .level_for("wasmer_wasi::syscalls", LevelFilter::Info)
         .level_for("wasmer_compiler_cranelift::translator", LevelFilter::Warn)
-        .chain(std::io::stdout())
+        .chain(Box::new(ConsoleWriter) as Box<dyn Write + Send>)
         .apply()
         .unwrap();
 }
@@ -1,10 +1,30 @@
-use std::{cell::RefCell, env, path::Path, rc::Rc, sync::Arc};
+use std::{
+    cell::RefCell,
+    cmp::Reverse,
+    env,
+    fs::File,
+    path::Path,
+    process,
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use anyhow::Context;
-use base::anvil::level::SuperflatGeneratorOptions;
-use common::{Game, TickLoop, World};
-use ecs::SystemExecutor;
-use feather_server::{config::Config, Server};
+use base::{
+    anvil::level::{LevelData, SuperflatGeneratorOptions},
+    TICK_DURATION,
+};
+use common::{
+    autosave::Autosave, chunk::pregen::Pregeneration, permissions::Permissions,
+    timings::TickTimings, world_border::WorldBorder, Game, TickLoop, World,
+};
+use ecs::{SystemExecutor, SystemTiming};
+use feather_server::{
+    config::Config,
+    shutdown::{self, ShutdownHandle},
+    PluginNames, Server,
+};
 use plugin_host::PluginManager;
 use utils::enable_ansi_support;
 use worldgen::{ComposableGenerator, SuperflatWorldGenerator, VoidWorldGenerator, WorldGenerator};
@@ -13,6 +33,9 @@ mod logging;
 
 const PLUGINS_DIRECTORY: &str = "plugins";
 const CONFIG_PATH: &str = "config.toml";
+const PERMISSIONS_PATH: &str = "permissions.toml";
+/// Number of systems named when a tick takes too long.
+const SLOW_TICK_SYSTEMS: usize = 3;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -43,19 +66,25 @@ async fn main() -> anyhow::Result<()> {
     log::info!("Creating server");
     let options = config.to_options();
     let server = Server::bind(options).await?;
+    feather_server::console::start(&server);
+    let shutdown = server.shutdown_handle();
+    shutdown::handle_signals(shutdown.clone());
 
     let game = init_game(server, &config)?;
 
-    run(game);
+    let game = run(game, shutdown);
 
-    Ok(())
+    process::exit(shut_down(game));
 }
 
 fn init_game(server: Server, config: &Config) -> anyhow::Result<Game> {
     let mut game = Game::new();
+    game.difficulty = config.server.difficulty;
+    game.insert_resource(Permissions::load(PERMISSIONS_PATH)?);
+    let plugin_names = server.plugin_names();
     init_systems(&mut game, server);
-    init_world_source(&mut game, config);
-    init_plugin_manager(&mut game)?;
+    init_world_source(&mut game, config)?;
+    init_plugin_manager(&mut game, &plugin_names)?;
     Ok(game)
 }
 
@@ -73,7 +102,7 @@ fn init_systems(game: &mut Game, server: Server) {
     game.system_executor = Rc::new(RefCell::new(systems));
 }
 
-fn init_world_source(game: &mut Game, config: &Config) {
+fn init_world_source(game: &mut Game, config: &Config) -> anyhow::Result<()> {
     // Load chunks from the world save first,
     // and fall back to generating a superflat
     // world otherwise. This is a placeholder:
@@ -88,12 +117,52 @@ fn init_world_source(game: &mut Game, config: &Config) {
         "void" => Arc::new(VoidWorldGenerator),
         _ => Arc::new(ComposableGenerator::default_with_seed(seed)),
     };
-    game.world = World::with_gen_and_path(generator, config.world.name.clone());
+    let storage = config.world.storage_options()?;
+    game.world = World::with_storage(generator, &config.world.name, &storage);
+
+    let world_dir = Path::new(&config.world.name);
+    let level = load_level(world_dir);
+    let border = level
+        .as_ref()
+        .map_or_else(WorldBorder::default, WorldBorder::from_level);
+    if let Some(level) = &level {
+        game.world.set_game_time(level.time.max(0) as u64);
+    }
+    if config.world.pregenerate {
+        game.insert_resource(Pregeneration::resume_or_start(&border, world_dir)?);
+    }
+    game.insert_resource(border);
+    game.insert_resource(Autosave::new(config.autosave.interval_ticks()));
+    Ok(())
 }
 
-fn init_plugin_manager(game: &mut Game) -> anyhow::Result<()> {
+/// Loads the world's `level.dat`, if it exists and is valid.
+fn load_level(world_dir: &Path) -> Option<LevelData> {
+    let path = world_dir.join("level.dat");
+    if !path.exists() {
+        return None;
+    }
+    match File::open(&path)
+        .map_err(anyhow::Error::from)
+        .and_then(|mut file| LevelData::load_from_file(&mut file))
+    {
+        Ok(level) => Some(level),
+        Err(e) => {
+            log::warn!("Failed to load level.dat: {:?}", e);
+            None
+        }
+    }
+}
+
+fn init_plugin_manager(game: &mut Game, plugin_names: &PluginNames) -> anyhow::Result<()> {
     let mut plugin_manager = PluginManager::new();
     plugin_manager.load_dir(game, PLUGINS_DIRECTORY)?;
+    plugin_names.set(
+        plugin_manager
+            .plugins()
+            .map(|plugin| format!("{} {}", plugin.metadata().name, plugin.metadata().version))
+            .collect(),
+    );
 
     let plugin_manager_rc = Rc::new(RefCell::new(plugin_manager));
     game.insert_resource(plugin_manager_rc);
@@ -105,18 +174,96 @@ fn print_systems(systems: &SystemExecutor<Game>) {
     log::debug!("---SYSTEMS---\n{:#?}\n", systems);
 }
 
-fn run(game: Game) {
-    let tick_loop = create_tick_loop(game);
+/// Runs the game loop until a shutdown is requested.
+fn run(game: Game, shutdown: ShutdownHandle) -> Game {
+    let game = Rc::new(RefCell::new(game));
+    let tick_loop = create_tick_loop(Rc::clone(&game), shutdown);
     log::debug!("Launching the game loop");
     tick_loop.run();
+
+    match Rc::try_unwrap(game) {
+        Ok(game) => game.into_inner(),
+        Err(_) => unreachable!("the tick loop is dropped after running"),
+    }
 }
 
-fn create_tick_loop(mut game: Game) -> TickLoop {
+fn create_tick_loop(game: Rc<RefCell<Game>>, shutdown: ShutdownHandle) -> TickLoop {
     TickLoop::new(move || {
+        let mut game = game.borrow_mut();
         let systems = Rc::clone(&game.system_executor);
+        let start = Instant::now();
         systems.borrow_mut().run(&mut game);
         game.tick_count += 1;
+        game.world.advance_game_time();
+
+        let end = Instant::now();
+        let duration = end - start;
+        if let Ok(mut timings) = game.resources.get_mut::<TickTimings>() {
+            timings.record_tick(end, duration, systems.borrow().timings());
+        }
+        if duration > TICK_DURATION {
+            log_slow_tick(&systems.borrow(), duration);
+        }
 
-        false
+        shutdown.is_requested()
     })
 }
+
+/// Warns about a tick which took too long, naming the systems which took longest.
+fn log_slow_tick(systems: &SystemExecutor<Game>, duration: Duration) {
+    let mut timings: Vec<SystemTiming> = systems.timings().collect();
+    timings.sort_by_key(|timing| Reverse(timing.duration));
+    let slowest: Vec<String> = timings
+        .iter()
+        .take(SLOW_TICK_SYSTEMS)
+        .map(|timing| match timing.plugin {
+            Some(plugin) => format!(
+                "{} [plugin {}] ({:?})",
+                timing.name, plugin, timing.duration
+            ),
+            None => format!("{} ({:?})", timing.name, timing.duration),
+        })
+        .collect();
+    log::warn!(
+        "Tick took too long ({:?}); slowest systems: {}",
+        duration,
+        slowest.join(", ")
+    );
+}
+
+/// Disconnects and saves all players, saves the world
+/// and disables plugins. Returns the status code to exit with.
+fn shut_down(mut game: Game) -> i32 {
+    log::info!("Stopping the server");
+    let mut status = 0;
+    let mut check = |step: &str, result: anyhow::Result<()>| {
+        if let Err(e) = result {
+            log::error!("Failed to {}: {:?}", step, e);
+            status = 1;
+        }
+    };
+
+    check(
+        "disconnect players",
+        shutdown::disconnect_players(&mut game, shutdown::SHUTDOWN_REASON),
+    );
+
+    log::info!("Saving chunks");
+    check("unload chunks", game.world.unload_all_chunks());
+    game.world.shutdown_chunk_worker();
+
+    let plugin_manager = game
+        .resources
+        .get::<Rc<RefCell<PluginManager>>>()
+        .map(|plugin_manager| Rc::clone(&plugin_manager));
+    match plugin_manager {
+        Ok(plugin_manager) => check(
+            "disable plugins",
+            plugin_manager.borrow_mut().disable_all(&mut game),
+        ),
+        Err(e) => check("disable plugins", Err(e.into())),
+    }
+
+    log::info!("Server stopped");
+    status
+}
@@ -0,0 +1,513 @@
+//! An HTTP endpoint exporting server metrics in the Prometheus text format.
+//!
+//! Metrics are gathered into a snapshot by a system each tick,
+//! except the player count and network traffic which are read
+//! when the endpoint is scraped.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context};
+use base::{EntityKind, TPS};
+use common::{
+    timings::{TickTimings, TimingWindow},
+    Game,
+};
+use ecs::{SysResult, SystemExecutor};
+use parking_lot::Mutex;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    time::timeout,
+};
+
+use crate::{listener::accept_with_backoff, player_count::PlayerCount, Server};
+
+/// Maximum length of a request's head.
+const MAX_REQUEST_LENGTH: usize = 8192;
+
+/// Time a client may take to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Counts the bytes sent to and received from clients.
+///
+/// Can be cloned to create a new handle.
+#[derive(Clone, Debug, Default)]
+pub struct TrafficCounters {
+    inner: Arc<Traffic>,
+}
+
+#[derive(Debug, Default)]
+struct Traffic {
+    received: AtomicU64,
+    sent: AtomicU64,
+}
+
+impl TrafficCounters {
+    pub fn add_received(&self, bytes: usize) {
+        self.inner
+            .received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn add_sent(&self, bytes: usize) {
+        self.inner.sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn received(&self) -> u64 {
+        self.inner.received.load(Ordering::Relaxed)
+    }
+
+    pub fn sent(&self) -> u64 {
+        self.inner.sent.load(Ordering::Relaxed)
+    }
+}
+
+/// Metrics gathered from the game.
+#[derive(Debug, Default)]
+struct Snapshot {
+    ticks: u64,
+    tick_seconds_total: f64,
+    /// Average tick duration in seconds and TPS of each window.
+    windows: Vec<(TimingWindow, f64, f64)>,
+    chunks_loaded: usize,
+    chunks_cached: usize,
+    chunk_loads_pending: usize,
+    chunk_saves_pending: usize,
+    entities: BTreeMap<&'static str, usize>,
+    /// Total time spent in the systems of each plugin.
+    plugin_seconds_total: BTreeMap<String, f64>,
+}
+
+/// The metrics exported by the endpoint.
+///
+/// Can be cloned to create a new handle.
+#[derive(Clone, Debug, Default)]
+pub struct Metrics {
+    snapshot: Arc<Mutex<Snapshot>>,
+    traffic: TrafficCounters,
+}
+
+impl Metrics {
+    pub fn traffic(&self) -> &TrafficCounters {
+        &self.traffic
+    }
+
+    /// Renders the metrics in the Prometheus text format.
+    fn render(&self, player_count: &PlayerCount) -> String {
+        let snapshot = self.snapshot.lock();
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "feather_ticks_total",
+            "counter",
+            "Ticks run since the server started.",
+        );
+        sample(&mut out, "feather_ticks_total", None, snapshot.ticks);
+        header(
+            &mut out,
+            "feather_tick_duration_seconds_total",
+            "counter",
+            "Total time spent running ticks.",
+        );
+        sample(
+            &mut out,
+            "feather_tick_duration_seconds_total",
+            None,
+            snapshot.tick_seconds_total,
+        );
+        header(
+            &mut out,
+            "feather_tick_duration_seconds",
+            "gauge",
+            "Average tick duration over a window.",
+        );
+        for (window, duration, _) in &snapshot.windows {
+            let label = ("window", window.label());
+            sample(
+                &mut out,
+                "feather_tick_duration_seconds",
+                Some(label),
+                duration,
+            );
+        }
+        header(
+            &mut out,
+            "feather_tps",
+            "gauge",
+            "Average ticks per second over a window.",
+        );
+        for (window, _, tps) in &snapshot.windows {
+            sample(
+                &mut out,
+                "feather_tps",
+                Some(("window", window.label())),
+                tps,
+            );
+        }
+
+        header(
+            &mut out,
+            "feather_players_online",
+            "gauge",
+            "Players online.",
+        );
+        sample(&mut out, "feather_players_online", None, player_count.get());
+        header(
+            &mut out,
+            "feather_players_max",
+            "gauge",
+            "Maximum number of players.",
+        );
+        sample(&mut out, "feather_players_max", None, player_count.max());
+
+        header(
+            &mut out,
+            "feather_chunks_loaded",
+            "gauge",
+            "Chunks loaded in the world.",
+        );
+        sample(
+            &mut out,
+            "feather_chunks_loaded",
+            None,
+            snapshot.chunks_loaded,
+        );
+        header(
+            &mut out,
+            "feather_chunks_cached",
+            "gauge",
+            "Unloaded chunks kept in the chunk cache.",
+        );
+        sample(
+            &mut out,
+            "feather_chunks_cached",
+            None,
+            snapshot.chunks_cached,
+        );
+        header(
+            &mut out,
+            "feather_chunk_loads_pending",
+            "gauge",
+            "Chunks queued to be loaded or generated.",
+        );
+        sample(
+            &mut out,
+            "feather_chunk_loads_pending",
+            None,
+            snapshot.chunk_loads_pending,
+        );
+        header(
+            &mut out,
+            "feather_chunk_saves_pending",
+            "gauge",
+            "Chunks queued to be saved.",
+        );
+        sample(
+            &mut out,
+            "feather_chunk_saves_pending",
+            None,
+            snapshot.chunk_saves_pending,
+        );
+
+        header(
+            &mut out,
+            "feather_entities",
+            "gauge",
+            "Entities in the world by kind.",
+        );
+        for (kind, count) in &snapshot.entities {
+            sample(&mut out, "feather_entities", Some(("kind", *kind)), count);
+        }
+
+        header(
+            &mut out,
+            "feather_network_received_bytes_total",
+            "counter",
+            "Bytes received from clients.",
+        );
+        sample(
+            &mut out,
+            "feather_network_received_bytes_total",
+            None,
+            self.traffic.received(),
+        );
+        header(
+            &mut out,
+            "feather_network_sent_bytes_total",
+            "counter",
+            "Bytes sent to clients.",
+        );
+        sample(
+            &mut out,
+            "feather_network_sent_bytes_total",
+            None,
+            self.traffic.sent(),
+        );
+
+        header(
+            &mut out,
+            "feather_plugin_system_duration_seconds_total",
+            "counter",
+            "Total time spent running the systems of each plugin.",
+        );
+        for (plugin, seconds) in &snapshot.plugin_seconds_total {
+            sample(
+                &mut out,
+                "feather_plugin_system_duration_seconds_total",
+                Some(("plugin", plugin.as_str())),
+                seconds,
+            );
+        }
+
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    writeln!(out, "# HELP {} {}", name, help).unwrap();
+    writeln!(out, "# TYPE {} {}", name, kind).unwrap();
+}
+
+fn sample(out: &mut String, name: &str, label: Option<(&str, &str)>, value: impl ToString) {
+    out.push_str(name);
+    if let Some((key, value)) = label {
+        let value = value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n");
+        write!(out, "{{{}=\"{}\"}}", key, value).unwrap();
+    }
+    writeln!(out, " {}", value.to_string()).unwrap();
+}
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.group::<Server>().add_system(update_metrics);
+}
+
+/// Updates the snapshot served by the endpoint. Tick timings and plugin
+/// time are counted each tick, while the other gauges are refreshed
+/// once per second.
+fn update_metrics(game: &mut Game, server: &mut Server) -> SysResult {
+    let mut snapshot = server.metrics.snapshot.lock();
+    let refresh = game.tick_count.is_multiple_of(TPS as u64);
+
+    if let Ok(timings) = game.resources.get::<TickTimings>() {
+        if let Some(duration) = timings.last_tick() {
+            snapshot.ticks += 1;
+            snapshot.tick_seconds_total += duration.as_secs_f64();
+        }
+        for system in timings.systems() {
+            if let Some(plugin) = &system.plugin {
+                *snapshot
+                    .plugin_seconds_total
+                    .entry(plugin.clone())
+                    .or_default() += system.last.as_secs_f64();
+            }
+        }
+
+        if refresh {
+            let now = Instant::now();
+            snapshot.windows = TimingWindow::ALL
+                .iter()
+                .map(|&window| {
+                    let duration = timings.mspt(window, now) / 1000.0;
+                    (window, duration, timings.tps(window, now))
+                })
+                .collect();
+        }
+    }
+
+    if !refresh {
+        return Ok(());
+    }
+
+    snapshot.chunks_loaded = game.world.chunk_map().len();
+    snapshot.chunks_cached = game.world.cache.len();
+    snapshot.chunk_loads_pending = game.world.pending_chunk_loads();
+    snapshot.chunk_saves_pending = game.world.pending_chunk_saves();
+
+    snapshot.entities.clear();
+    for (_, kind) in game.ecs.query::<&EntityKind>().iter() {
+        *snapshot.entities.entry(kind.name()).or_default() += 1;
+    }
+
+    Ok(())
+}
+
+/// Serves metrics over HTTP.
+pub struct MetricsListener {
+    listener: TcpListener,
+    metrics: Metrics,
+    player_count: PlayerCount,
+}
+
+impl MetricsListener {
+    /// Starts listening, returning the bound address.
+    pub async fn start(
+        address: SocketAddr,
+        metrics: Metrics,
+        player_count: PlayerCount,
+    ) -> anyhow::Result<SocketAddr> {
+        let listener = TcpListener::bind(address)
+            .await
+            .context("failed to bind the metrics address")?;
+        let local_addr = listener.local_addr()?;
+        log::info!("Serving metrics on http://{}/metrics", local_addr);
+
+        let listener = MetricsListener {
+            listener,
+            metrics,
+            player_count,
+        };
+        tokio::task::spawn(async move {
+            listener.run().await;
+        });
+
+        Ok(local_addr)
+    }
+
+    async fn run(self) {
+        loop {
+            let (stream, addr) = accept_with_backoff(&self.listener, "metrics").await;
+            let metrics = self.metrics.clone();
+            let player_count = self.player_count.clone();
+            tokio::task::spawn(async move {
+                if let Err(e) = serve(stream, &metrics, &player_count).await {
+                    log::debug!("Metrics request from {} failed: {:?}", addr, e);
+                }
+            });
+        }
+    }
+}
+
+/// Answers a single request, then closes the connection.
+async fn serve(
+    mut stream: TcpStream,
+    metrics: &Metrics,
+    player_count: &PlayerCount,
+) -> anyhow::Result<()> {
+    let request = timeout(REQUEST_TIMEOUT, read_request_head(&mut stream)).await??;
+    let mut parts = request.split(' ');
+    let method = parts.next().unwrap_or_default();
+    let path = parts.next().unwrap_or_default();
+
+    let (status, body) = match (method, path) {
+        ("GET", "/metrics") => ("200 OK", metrics.render(player_count)),
+        ("GET", _) => ("404 Not Found", "Not Found\n".to_owned()),
+        _ => ("405 Method Not Allowed", "Method Not Allowed\n".to_owned()),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        CONTENT_TYPE,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Reads the head of a request, returning its request line.
+async fn read_request_head(stream: &mut TcpStream) -> anyhow::Result<String> {
+    let mut head = Vec::new();
+    let mut buffer = [0; 512];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        if head.len() > MAX_REQUEST_LENGTH {
+            bail!("request too long");
+        }
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            bail!("connection closed before the end of the request");
+        }
+        head.extend_from_slice(&buffer[..read]);
+    }
+    let head = String::from_utf8_lossy(&head);
+    Ok(head.lines().next().unwrap_or_default().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpStream,
+    };
+
+    use super::*;
+
+    #[test]
+    fn render_metrics() {
+        let metrics = Metrics::default();
+        {
+            let mut snapshot = metrics.snapshot.lock();
+            snapshot.ticks = 40;
+            snapshot.windows = vec![(TimingWindow::FiveSeconds, 0.0125, 20.0)];
+            snapshot.chunks_loaded = 9;
+            snapshot.entities.insert("zombie", 3);
+            snapshot
+                .plugin_seconds_total
+                .insert("say \"hi\"".to_owned(), 0.5);
+        }
+        metrics.traffic().add_received(100);
+        metrics.traffic().add_sent(250);
+        let player_count = PlayerCount::new(16);
+        player_count.try_add_player().unwrap();
+
+        let text = metrics.render(&player_count);
+        for line in [
+            "# TYPE feather_ticks_total counter",
+            "feather_ticks_total 40",
+            "feather_tick_duration_seconds{window=\"5s\"} 0.0125",
+            "feather_tps{window=\"5s\"} 20",
+            "feather_players_online 1",
+            "feather_players_max 16",
+            "feather_chunks_loaded 9",
+            "feather_entities{kind=\"zombie\"} 3",
+            "feather_network_received_bytes_total 100",
+            "feather_network_sent_bytes_total 250",
+            "feather_plugin_system_duration_seconds_total{plugin=\"say \\\"hi\\\"\"} 0.5",
+        ] {
+            assert!(text.lines().any(|l| l == line), "missing {}", line);
+        }
+    }
+
+    #[tokio::test]
+    async fn serve_over_http() {
+        let metrics = Metrics::default();
+        metrics.snapshot.lock().ticks = 5;
+        let addr =
+            MetricsListener::start("127.0.0.1:0".parse().unwrap(), metrics, PlayerCount::new(1))
+                .await
+                .unwrap();
+
+        let get = move |path: &str| {
+            let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path);
+            let mut stream = TcpStream::connect(addr).unwrap();
+            stream.write_all(request.as_bytes()).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+        let (found, missing) =
+            tokio::task::spawn_blocking(move || (get("/metrics"), get("/other")))
+                .await
+                .unwrap();
+
+        assert!(found.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(found.contains(CONTENT_TYPE));
+        assert!(found.contains("\nfeather_ticks_total 5\n"));
+        assert!(missing.starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+}
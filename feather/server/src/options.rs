@@ -1,3 +1,5 @@
+use std::{net::SocketAddr, time::Duration};
+
 use base::Gamemode;
 
 use crate::favicon::Favicon;
@@ -16,6 +18,23 @@ pub struct Options {
     /// The server MOTD.
     pub motd: String,
 
+    /// The name of the world, reported to Query clients.
+    pub level_name: String,
+
+    /// UDP port to answer Query requests on, or `None`
+    /// if Query is disabled.
+    pub query_port: Option<u16>,
+
+    /// Port to accept RCON connections on, or `None`
+    /// if RCON is disabled.
+    pub rcon_port: Option<u16>,
+    /// Password RCON clients must send to run commands.
+    pub rcon_password: String,
+
+    /// Address to serve Prometheus metrics on, or `None`
+    /// if the metrics endpoint is disabled.
+    pub metrics_address: Option<SocketAddr>,
+
     /// Whether the server should authenticate players.
     pub online_mode: bool,
 
@@ -34,6 +53,18 @@ pub struct Options {
     /// if those values are set in server.properties after world creation.
     pub enforce_gamemode: bool,
 
+    /// Whether the whitelist is enabled when the server starts.
+    pub whitelist: bool,
+    /// Whether to kick players who aren't whitelisted
+    /// when the whitelist is enabled or reloaded.
+    pub enforce_whitelist: bool,
+
+    /// Validation of player movement.
+    pub movement: MovementOptions,
+
+    /// Limits protecting the server from abusive clients.
+    pub limits: LimitOptions,
+
     /// Proxy IP forwarding mode
     pub proxy_mode: Option<ProxyMode>,
     // HMAC key used with Velocity IP forwarding.
@@ -43,6 +74,66 @@ pub struct Options {
     pub compression_threshold: Option<usize>,
 }
 
+/// Options for validating the movement players claim.
+#[derive(Debug, Clone)]
+pub struct MovementOptions {
+    /// Whether movement is validated. When disabled,
+    /// players move wherever they claim to.
+    pub enabled: bool,
+    /// Factor applied to the maximum speeds, to allow for
+    /// latency and movement the server doesn't simulate.
+    pub speed_tolerance: f64,
+    /// Whether players who can't fly are kept from rising
+    /// higher than a jump or hovering in the air.
+    pub check_flight: bool,
+    /// Whether players are kept from moving into or through blocks.
+    pub check_collisions: bool,
+    /// Whether players claiming to stand on the ground while
+    /// in the air are considered to be in the air.
+    pub check_ground: bool,
+}
+
+impl Default for MovementOptions {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            speed_tolerance: 1.5,
+            check_flight: true,
+            check_collisions: true,
+            check_ground: true,
+        }
+    }
+}
+
+/// Limits on the connections and packets of clients.
+/// `None` disables a limit.
+#[derive(Debug, Clone)]
+pub struct LimitOptions {
+    /// Time a client may take from connecting to joining the game.
+    pub login_timeout: Option<Duration>,
+    /// Time a client must wait between logins from the same IP address.
+    pub connection_throttle: Option<Duration>,
+    /// Maximum number of simultaneous connections from one IP address.
+    /// Not enforced behind a proxy, since all connections come from it.
+    pub max_connections_per_ip: Option<u32>,
+    /// Maximum number of packets a player may send per second.
+    pub max_packets_per_second: Option<u32>,
+    /// Maximum size in bytes of packets sent by clients.
+    pub max_packet_size: usize,
+}
+
+impl Default for LimitOptions {
+    fn default() -> Self {
+        Self {
+            login_timeout: Some(Duration::from_secs(30)),
+            connection_throttle: Some(Duration::from_millis(4000)),
+            max_connections_per_ip: Some(8),
+            max_packets_per_second: Some(500),
+            max_packet_size: protocol::codec::MAX_PACKET_LENGTH,
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ProxyMode {
     Bungeecord,
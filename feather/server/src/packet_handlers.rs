@@ -1,9 +1,9 @@
 use base::{Position, Text};
-use common::{chat::ChatKind, Game};
+use common::{chat::ChatKind, commands::CommandSender, Game};
 use ecs::{Entity, EntityRef, SysResult};
 use interaction::{
     handle_held_item_change, handle_interact_entity, handle_player_block_placement,
-    handle_player_digging,
+    handle_player_digging, handle_use_item,
 };
 use protocol::{
     packets::{
@@ -14,7 +14,7 @@ use protocol::{
 };
 use quill_common::components::Name;
 
-use crate::{NetworkId, Server};
+use crate::{commands::CommandRequest, ClientId, NetworkId, Server};
 
 mod entity_action;
 mod interaction;
@@ -31,21 +31,29 @@ pub fn handle_packet(
     let player = game.ecs.entity(player_id)?;
     match packet {
         ClientPlayPacket::PlayerPosition(packet) => {
-            movement::handle_player_position(server, player, packet)
+            movement::handle_player_position(game, server, player_id, packet)
         }
         ClientPlayPacket::PlayerPositionAndRotation(packet) => {
-            movement::handle_player_position_and_rotation(server, player, packet)
+            movement::handle_player_position_and_rotation(game, server, player_id, packet)
         }
         ClientPlayPacket::PlayerRotation(packet) => {
-            movement::handle_player_rotation(server, player, packet)
+            movement::handle_player_rotation(game, server, player_id, packet)
         }
         ClientPlayPacket::PlayerMovement(packet) => {
-            movement::handle_player_movement(player, packet)
+            movement::handle_player_movement(game, server, player_id, packet)
+        }
+        ClientPlayPacket::TeleportConfirm(packet) => {
+            if let Some(client) = server.clients.get(*player.get::<ClientId>()?) {
+                client.confirm_teleport(packet.teleport_id);
+            }
+            Ok(())
         }
 
         ClientPlayPacket::Animation(packet) => handle_animation(server, player, packet),
 
-        ClientPlayPacket::ChatMessage(packet) => handle_chat_message(game, player, packet),
+        ClientPlayPacket::ChatMessage(packet) => {
+            handle_chat_message(game, server, player_id, player, packet)
+        }
 
         ClientPlayPacket::PlayerDigging(packet) => {
             handle_player_digging(game, server, packet, player_id)
@@ -57,6 +65,15 @@ pub fn handle_packet(
         ClientPlayPacket::ClickWindow(packet) => {
             inventory::handle_click_window(server, player, packet)
         }
+        ClientPlayPacket::ClickWindowButton(packet) => {
+            inventory::handle_click_window_button(game, player_id, packet)
+        }
+        ClientPlayPacket::CloseWindow(packet) => {
+            inventory::handle_close_window(game, server, player_id, packet)
+        }
+        ClientPlayPacket::SetBeaconEffect(packet) => {
+            inventory::handle_set_beacon_effect(game, player_id, packet)
+        }
 
         ClientPlayPacket::PlayerBlockPlacement(packet) => {
             handle_player_block_placement(game, server, packet, player_id)
@@ -77,14 +94,13 @@ pub fn handle_packet(
             entity_action::handle_entity_action(game, player_id, packet)
         }
 
-        ClientPlayPacket::TeleportConfirm(_)
-        | ClientPlayPacket::QueryBlockNbt(_)
+        ClientPlayPacket::UseItem(packet) => handle_use_item(game, server, packet, player_id),
+
+        ClientPlayPacket::QueryBlockNbt(_)
         | ClientPlayPacket::SetDifficulty(_)
         | ClientPlayPacket::ClientStatus(_)
         | ClientPlayPacket::TabComplete(_)
         | ClientPlayPacket::WindowConfirmation(_)
-        | ClientPlayPacket::ClickWindowButton(_)
-        | ClientPlayPacket::CloseWindow(_)
         | ClientPlayPacket::PluginMessage(_)
         | ClientPlayPacket::EditBook(_)
         | ClientPlayPacket::QueryEntityNbt(_)
@@ -102,14 +118,12 @@ pub fn handle_packet(
         | ClientPlayPacket::ResourcePackStatus(_)
         | ClientPlayPacket::AdvancementTab(_)
         | ClientPlayPacket::SelectTrade(_)
-        | ClientPlayPacket::SetBeaconEffect(_)
         | ClientPlayPacket::UpdateCommandBlock(_)
         | ClientPlayPacket::UpdateCommandBlockMinecart(_)
         | ClientPlayPacket::UpdateJigsawBlock(_)
         | ClientPlayPacket::UpdateStructureBlock(_)
         | ClientPlayPacket::UpdateSign(_)
-        | ClientPlayPacket::Spectate(_)
-        | ClientPlayPacket::UseItem(_) => Ok(()),
+        | ClientPlayPacket::Spectate(_) => Ok(()),
     }
 }
 
@@ -132,7 +146,21 @@ fn handle_animation(
     Ok(())
 }
 
-fn handle_chat_message(game: &Game, player: EntityRef, packet: client::ChatMessage) -> SysResult {
+fn handle_chat_message(
+    game: &Game,
+    server: &mut Server,
+    player_id: Entity,
+    player: EntityRef,
+    packet: client::ChatMessage,
+) -> SysResult {
+    if packet.message.starts_with('/') {
+        server.queue_command(CommandRequest::new(
+            CommandSender::Player(player_id),
+            packet.message,
+        ));
+        return Ok(());
+    }
+
     let name = player.get::<Name>()?;
     let message = Text::translate_with("chat.type.text", vec![name.to_string(), packet.message]);
     game.broadcast_chat(ChatKind::PlayerChat, message);
@@ -57,7 +57,7 @@ pub fn handle_entity_action(game: &mut Game, player: Entity, packet: EntityActio
             //TODO issue #423
         }
         EntityActionKind::StartElytraFlight => {
-            //TODO issue #423
+            super::movement::start_gliding(game, player)?;
         }
     }
 
@@ -1,6 +1,7 @@
 use crate::{ClientId, NetworkId, Server};
 use base::inventory::{SLOT_HOTBAR_OFFSET, SLOT_OFFHAND};
 use common::entities::player::HotbarSlot;
+use common::hunger::Eating;
 use common::interactable::InteractableRegistry;
 use common::{Game, Window};
 use ecs::{Entity, EntityRef, SysResult};
@@ -8,10 +9,10 @@ use libcraft_core::{BlockFace as LibcraftBlockFace, Hand};
 use libcraft_core::{InteractionType, Vec3f};
 use protocol::packets::client::{
     BlockFace, HeldItemChange, InteractEntity, InteractEntityKind, PlayerBlockPlacement,
-    PlayerDigging, PlayerDiggingStatus,
+    PlayerDigging, PlayerDiggingStatus, UseItem,
 };
 use quill_common::{
-    events::{BlockInteractEvent, BlockPlacementEvent, InteractEntityEvent},
+    events::{BlockInteractEvent, BlockPlacementEvent, InteractEntityEvent, UseItemEvent},
     EntityId,
 };
 /// Handles the player block placement packet. Currently just removes the block client side for the player.
@@ -119,7 +120,16 @@ pub fn handle_player_digging(
 ) -> SysResult {
     log::trace!("Got player digging with status {:?}", packet.status);
     match packet.status {
-        PlayerDiggingStatus::StartDigging | PlayerDiggingStatus::CancelDigging => {
+        PlayerDiggingStatus::StartDigging => {
+            let broken = game.block(packet.position).map(|block| block.kind());
+            match broken {
+                Some(kind) if game.break_block(packet.position) => {
+                    common::experience::drop_block_experience(game, player, packet.position, kind)
+                }
+                _ => Ok(()),
+            }
+        }
+        PlayerDiggingStatus::CancelDigging => {
             game.break_block(packet.position);
             Ok(())
         }
@@ -145,10 +155,43 @@ pub fn handle_player_digging(
 
             Ok(())
         }
+        PlayerDiggingStatus::ShootArrow => {
+            // Sent when the player releases the use key,
+            // which cancels eating.
+            let _ = game.ecs.remove::<Eating>(player);
+            Ok(())
+        }
         _ => Ok(()),
     }
 }
 
+/// Handles the Use Item packet, sent when a player
+/// right-clicks with an item, e.g. to start eating.
+pub fn handle_use_item(
+    game: &mut Game,
+    server: &mut Server,
+    packet: UseItem,
+    player: Entity,
+) -> SysResult {
+    let hand = match packet.hand {
+        0 => Hand::Main,
+        1 => Hand::Offhand,
+        _ => {
+            let client_id = game.ecs.get::<ClientId>(player).unwrap();
+
+            let client = server.clients.get(*client_id).unwrap();
+
+            client.disconnect("Malformed Packet!");
+
+            anyhow::bail!("Player sent a malformed `UseItem` packet. {:?}", packet)
+        }
+    };
+
+    game.ecs
+        .insert_entity_event(player, UseItemEvent { hand })?;
+    Ok(())
+}
+
 pub fn handle_interact_entity(
     game: &mut Game,
     _server: &mut Server,
@@ -180,14 +223,14 @@ pub fn handle_interact_entity(
 
     let event = match packet.kind {
         InteractEntityKind::Attack => InteractEntityEvent {
-            target: EntityId(target.id() as u64),
+            target: EntityId(target.to_bits()),
             ty: InteractionType::Attack,
             target_pos: None,
             hand: None,
             sneaking: packet.sneaking,
         },
         InteractEntityKind::Interact => InteractEntityEvent {
-            target: EntityId(target.id() as u64),
+            target: EntityId(target.to_bits()),
             ty: InteractionType::Interact,
             target_pos: None,
             hand: None,
@@ -206,13 +249,9 @@ pub fn handle_interact_entity(
             };
 
             InteractEntityEvent {
-                target: EntityId(target.id() as u64),
-                ty: InteractionType::Attack,
-                target_pos: Some(Vec3f::new(
-                    target_x,
-                    target_y,
-                    target_z,
-                )),
+                target: EntityId(target.to_bits()),
+                ty: InteractionType::Interact,
+                target_pos: Some(Vec3f::new(target_x, target_y, target_z)),
                 hand: Some(hand),
                 sneaking: packet.sneaking,
             }
@@ -1,8 +1,16 @@
+use std::convert::TryFrom;
+
 use anyhow::bail;
-use base::Gamemode;
-use common::{window::BackingWindow, Window};
-use ecs::{EntityRef, SysResult};
-use protocol::packets::client::{ClickWindow, CreativeInventoryAction};
+use base::{Gamemode, StatusEffect};
+use common::{
+    events::{BeaconEffectSetEvent, WindowButtonClickEvent, WindowCloseEvent},
+    window::BackingWindow,
+    Game, Window,
+};
+use ecs::{Entity, EntityRef, SysResult};
+use protocol::packets::client::{
+    ClickWindow, ClickWindowButton, CloseWindow, CreativeInventoryAction, SetBeaconEffect,
+};
 
 use crate::{ClientId, Server};
 
@@ -82,3 +90,47 @@ fn _handle_click_window(player: &EntityRef, packet: &ClickWindow) -> SysResult {
 
     Ok(())
 }
+
+pub fn handle_click_window_button(
+    game: &mut Game,
+    player: Entity,
+    packet: ClickWindowButton,
+) -> SysResult {
+    game.ecs.insert_entity_event(
+        player,
+        WindowButtonClickEvent {
+            button: packet.button_id,
+        },
+    )?;
+    Ok(())
+}
+
+pub fn handle_close_window(
+    game: &mut Game,
+    server: &mut Server,
+    player: Entity,
+    _packet: CloseWindow,
+) -> SysResult {
+    if let Some(client) = server.clients.get(*game.ecs.get::<ClientId>(player)?) {
+        client.close_window();
+    }
+    game.ecs.insert_entity_event(player, WindowCloseEvent)?;
+    Ok(())
+}
+
+pub fn handle_set_beacon_effect(
+    game: &mut Game,
+    player: Entity,
+    packet: SetBeaconEffect,
+) -> SysResult {
+    // Effect IDs outside the valid range mean "no effect".
+    let effect = |id: i32| u8::try_from(id).ok().and_then(StatusEffect::from_id);
+    game.ecs.insert_entity_event(
+        player,
+        BeaconEffectSetEvent {
+            primary: effect(packet.primary_effect),
+            secondary: effect(packet.secondary_effect),
+        },
+    )?;
+    Ok(())
+}
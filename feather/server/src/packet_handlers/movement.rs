@@ -1,23 +1,34 @@
 use base::Position;
-use common::Game;
-use ecs::{Entity, EntityRef, SysResult};
+use common::{world_border::WorldBorder, Game};
+use ecs::{Entity, SysResult};
 use protocol::packets::client::{
     PlayerAbilities, PlayerMovement, PlayerPosition, PlayerPositionAndRotation, PlayerRotation,
 };
 use quill_common::{
-    components::{CreativeFlying, OnGround},
-    events::CreativeFlyingEvent,
+    components::{CanCreativeFly, CreativeFlying, Name, OnGround},
+    events::{CreativeFlyingEvent, MovementCheck, MovementViolationEvent},
 };
 
 use crate::{ClientId, Server};
 
+use self::validation::MovementState;
+
+mod validation;
+
+/// Violations of a player between warnings in the log. Occasional
+/// violations are normal with lag, so the others are logged at debug level.
+const VIOLATIONS_PER_WARNING: u32 = 50;
+
 /// If a player has been teleported by the server,
 /// we don't want to override their position if
 /// we receive a movement packet before the client
 /// is aware of the position update.
-fn should_skip_movement(server: &Server, player: &EntityRef) -> SysResult<bool> {
-    if let Some(client) = server.clients.get(*player.get::<ClientId>()?) {
-        let server_position = *player.get::<Position>()?;
+fn should_skip_movement(game: &Game, server: &Server, player: Entity) -> SysResult<bool> {
+    if let Some(client) = server.clients.get(*game.ecs.get::<ClientId>(player)?) {
+        if client.is_awaiting_teleport() {
+            return Ok(true);
+        }
+        let server_position = *game.ecs.get::<Position>(player)?;
         let client_position = client.client_known_position();
         if let Some(client_position) = client_position {
             if client_position != server_position {
@@ -30,66 +41,157 @@ fn should_skip_movement(server: &Server, player: &EntityRef) -> SysResult<bool>
     Ok(false)
 }
 
-pub fn handle_player_movement(player: EntityRef, packet: PlayerMovement) -> SysResult {
-    player.get_mut::<OnGround>()?.0 = packet.on_ground;
-    Ok(())
+pub fn handle_player_movement(
+    game: &mut Game,
+    server: &Server,
+    player: Entity,
+    packet: PlayerMovement,
+) -> SysResult {
+    let position = *game.ecs.get::<Position>(player)?;
+    move_player(game, server, player, position, packet.on_ground)
 }
 
 pub fn handle_player_position(
+    game: &mut Game,
     server: &Server,
-    player: EntityRef,
+    player: Entity,
     packet: PlayerPosition,
 ) -> SysResult {
-    if should_skip_movement(server, &player)? {
-        return Ok(());
-    }
-    let mut pos = player.get_mut::<Position>()?;
-    pos.x = packet.x;
-    pos.y = packet.feet_y;
-    pos.z = packet.z;
-    player.get_mut::<OnGround>()?.0 = packet.on_ground;
-    update_client_position(server, player, *pos)?;
-    Ok(())
+    let position = Position {
+        x: packet.x,
+        y: packet.feet_y,
+        z: packet.z,
+        ..*game.ecs.get::<Position>(player)?
+    };
+    move_player(game, server, player, position, packet.on_ground)
 }
 
 pub fn handle_player_position_and_rotation(
+    game: &mut Game,
     server: &Server,
-    player: EntityRef,
+    player: Entity,
     packet: PlayerPositionAndRotation,
 ) -> SysResult {
-    if should_skip_movement(server, &player)? {
-        return Ok(());
-    }
-    let mut pos = player.get_mut::<Position>()?;
-    pos.x = packet.x;
-    pos.y = packet.feet_y;
-    pos.z = packet.z;
-    pos.yaw = packet.yaw;
-    pos.pitch = packet.pitch;
-    player.get_mut::<OnGround>()?.0 = packet.on_ground;
-    update_client_position(server, player, *pos)?;
-    Ok(())
+    let position = Position {
+        x: packet.x,
+        y: packet.feet_y,
+        z: packet.z,
+        yaw: packet.yaw,
+        pitch: packet.pitch,
+    };
+    move_player(game, server, player, position, packet.on_ground)
 }
 
 pub fn handle_player_rotation(
+    game: &mut Game,
     server: &Server,
-    player: EntityRef,
+    player: Entity,
     packet: PlayerRotation,
 ) -> SysResult {
-    if should_skip_movement(server, &player)? {
+    let position = Position {
+        yaw: packet.yaw,
+        pitch: packet.pitch,
+        ..*game.ecs.get::<Position>(player)?
+    };
+    move_player(game, server, player, position, packet.on_ground)
+}
+
+/// Moves a player to the position sent by their client, unless the
+/// movement leaves the world border or fails validation, in which
+/// case the player is moved back.
+fn move_player(
+    game: &mut Game,
+    server: &Server,
+    player: Entity,
+    new_position: Position,
+    on_ground: bool,
+) -> SysResult {
+    if should_skip_movement(game, server, player)? {
         return Ok(());
     }
-    let mut pos = player.get_mut::<Position>()?;
-    pos.yaw = packet.yaw;
-    pos.pitch = packet.pitch;
-    player.get_mut::<OnGround>()?.0 = packet.on_ground;
-    update_client_position(server, player, *pos)?;
+    let client = match server.clients.get(*game.ecs.get::<ClientId>(player)?) {
+        Some(client) => client,
+        None => return Ok(()),
+    };
+    let old_position = *game.ecs.get::<Position>(player)?;
+
+    if crosses_border(
+        &*game.resources.get::<WorldBorder>()?,
+        old_position,
+        new_position,
+    ) {
+        client.update_own_position(old_position);
+        return Ok(());
+    }
+
+    let options = &server.options.movement;
+    if !options.enabled {
+        set_position(game, player, new_position, on_ground)?;
+        client.set_client_known_position(new_position);
+        return Ok(());
+    }
+
+    let verdict =
+        validation::validate(game, options, player, old_position, new_position, on_ground)?;
+    if let Some(check) = verdict.failed {
+        let level = if verdict.violations.is_multiple_of(VIOLATIONS_PER_WARNING) {
+            log::Level::Warn
+        } else {
+            log::Level::Debug
+        };
+        log::log!(
+            level,
+            "{} failed the {:?} movement check moving from {:?} to {:?} ({} violations)",
+            &**game.ecs.get::<Name>(player)?,
+            check,
+            old_position,
+            new_position,
+            verdict.violations
+        );
+        game.ecs.insert_entity_event(
+            player,
+            MovementViolationEvent {
+                check,
+                from: old_position,
+                to: new_position,
+                violations: verdict.violations,
+            },
+        )?;
+        if check != MovementCheck::Ground {
+            let setback = Position {
+                yaw: new_position.yaw,
+                pitch: new_position.pitch,
+                ..verdict.setback
+            };
+            set_position(game, player, setback, false)?;
+            client.update_own_position(setback);
+            return Ok(());
+        }
+    }
+
+    set_position(game, player, new_position, verdict.on_ground)?;
+    client.set_client_known_position(new_position);
     Ok(())
 }
 
-fn update_client_position(server: &Server, player: EntityRef, pos: Position) -> SysResult {
-    if let Some(client) = server.clients.get(*player.get::<ClientId>()?) {
-        client.set_client_known_position(pos);
+fn set_position(game: &mut Game, player: Entity, position: Position, on_ground: bool) -> SysResult {
+    *game.ecs.get_mut::<Position>(player)? = position;
+    game.ecs.get_mut::<OnGround>(player)?.0 = on_ground;
+    Ok(())
+}
+
+/// Returns whether a movement leaves the world border or moves further outside it.
+fn crosses_border(border: &WorldBorder, old_position: Position, new_position: Position) -> bool {
+    let old_distance = border.distance_inside(old_position.x, old_position.z);
+    let new_distance = border.distance_inside(new_position.x, new_position.z);
+    new_distance < 0.0 && new_distance < old_distance
+}
+
+/// Marks a player as gliding with an elytra, which exempts
+/// them from the speed and flight checks until they land.
+pub fn start_gliding(game: &mut Game, player: Entity) -> SysResult {
+    if let Ok(mut state) = game.ecs.get_mut::<MovementState>(player) {
+        state.start_gliding();
     }
     Ok(())
 }
@@ -119,7 +221,9 @@ pub fn handle_player_abilities(
         }
         2 => {
             // Flying started
-            if !flying {
+            if !game.ecs.get::<CanCreativeFly>(player)?.0 {
+                log::debug!("Ignoring a player starting to fly without being allowed to");
+            } else if !flying {
                 // Then it used to not fly, therefor we need to trigger a event.
                 // The vanilla client is actually quite good at keeping track of sending
                 // this packet only when there is a change, so this if should basically
@@ -0,0 +1,599 @@
+//! Validation of the movement claimed by clients.
+//!
+//! Clients send the positions they move to, so without validation a
+//! modified client could teleport, fly or move through walls. Each
+//! movement is compared against what the player's state allows.
+//!
+//! Speed is checked against a budget of distance which grows with every
+//! tick, so that movement packets arriving in bursts are accepted. Block
+//! shapes aren't known, so only solid, opaque blocks are treated as
+//! obstacles, and any block which isn't air or fluid counts as ground.
+
+use std::convert::TryFrom;
+
+use base::{
+    BlockId, BlockPosition, Gamemode, Position, SimplifiedBlockKind, StatusEffect,
+    ValidBlockPosition,
+};
+use common::Game;
+use ecs::{Entity, SysResult};
+use quill_common::{
+    components::{
+        ActiveEffects, CanCreativeFly, CreativeFlying, CreativeFlyingSpeed, Sneaking, Sprinting,
+        WalkSpeed,
+    },
+    events::MovementCheck,
+};
+
+use crate::options::MovementOptions;
+
+/// Horizontal blocks per tick of a walking player.
+const WALKING_SPEED: f64 = 0.22;
+/// Horizontal blocks per tick of a sprinting player,
+/// including the boost of jumping while sprinting.
+const SPRINTING_SPEED: f64 = 0.36;
+/// Horizontal blocks per tick of a flying player.
+/// Doubled when sprinting.
+const FLYING_SPEED: f64 = 0.55;
+/// Vertical blocks per tick of a flying player.
+const FLYING_VERTICAL_SPEED: f64 = 0.375;
+
+/// The `WalkSpeed` and `CreativeFlyingSpeed` which
+/// the speeds above correspond to.
+const DEFAULT_WALK_SPEED: f64 = 0.1;
+const DEFAULT_FLYING_SPEED: f64 = 0.05;
+
+/// Factor applied to horizontal speeds for a while after being on ice.
+const ICE_FACTOR: f64 = 2.5;
+/// Ticks for which players keep the speed gained on ice.
+const ICE_TICKS: u64 = 20;
+
+/// Height of a jump, and the extra height per level of Jump Boost.
+const JUMP_HEIGHT: f64 = 1.25;
+const JUMP_BOOST_HEIGHT: f64 = 0.75;
+
+/// Ticks of unused speed a player may accumulate.
+const MAX_BUDGET_TICKS: f64 = 20.0;
+/// Movements in the air without descending before
+/// a player who can't fly is considered to hover.
+const MAX_HOVER_MOVEMENTS: u32 = 20;
+
+const PLAYER_WIDTH: f64 = 0.6;
+const PLAYER_HEIGHT: f64 = 1.8;
+const SNEAKING_HEIGHT: f64 = 1.5;
+/// Margin by which the box of a player is shrunk for collisions,
+/// so that touching blocks and rounding errors don't count.
+const COLLISION_MARGIN: f64 = 0.01;
+/// Maximum distance between the positions checked for
+/// collisions along a movement, and their maximum number.
+const COLLISION_STEP: f64 = 0.25;
+const MAX_COLLISION_STEPS: usize = 128;
+/// How far below the feet of a player ground is detected.
+const GROUND_DISTANCE: f64 = 0.6;
+
+/// Tolerance for floating point errors when comparing distances.
+const EPSILON: f64 = 1e-6;
+
+/// The movement validation state of a player.
+#[derive(Debug)]
+pub struct MovementState {
+    /// The last validated position.
+    position: Position,
+    /// The last position where the player stood on the ground.
+    ground_position: Position,
+    /// Tick of the last validated movement.
+    last_tick: u64,
+    /// Tick at which the player was last on ice.
+    last_ice_tick: Option<u64>,
+    /// Distances the player may still move before exceeding their speed.
+    horizontal_budget: f64,
+    vertical_budget: f64,
+    /// Height gained since the player last stood on the ground,
+    /// and the height they may gain.
+    ascent: f64,
+    max_ascent: f64,
+    /// Height fallen since the player was last rising,
+    /// which slime blocks and beds turn into bounce height.
+    fall_distance: f64,
+    /// Consecutive movements in the air without descending.
+    hover_movements: u32,
+    /// Whether the player is gliding with an elytra.
+    gliding: bool,
+    violations: u32,
+}
+
+impl MovementState {
+    pub fn new(position: Position, tick: u64) -> Self {
+        Self {
+            position,
+            ground_position: position,
+            last_tick: tick,
+            last_ice_tick: None,
+            horizontal_budget: f64::INFINITY,
+            vertical_budget: f64::INFINITY,
+            ascent: 0.0,
+            max_ascent: JUMP_HEIGHT,
+            fall_distance: 0.0,
+            hover_movements: 0,
+            gliding: false,
+            violations: 0,
+        }
+    }
+
+    /// Marks the player as gliding until they land.
+    pub fn start_gliding(&mut self) {
+        self.gliding = true;
+    }
+
+    /// Restarts the checks at a position the server moved the player to.
+    fn reset(&mut self, position: Position) {
+        self.position = position;
+        self.ground_position = position;
+        self.ascent = 0.0;
+        self.fall_distance = 0.0;
+        self.hover_movements = 0;
+    }
+}
+
+/// The result of validating a movement.
+#[derive(Debug)]
+pub struct Verdict {
+    /// Whether the player stands on the ground.
+    pub on_ground: bool,
+    /// The check the movement failed, if any.
+    pub failed: Option<MovementCheck>,
+    /// The position to move the player back to if the movement failed.
+    pub setback: Position,
+    /// The number of violations of the player, including this one.
+    pub violations: u32,
+}
+
+/// The state of a player relevant to their movement.
+struct Mover {
+    gamemode: Gamemode,
+    flying: bool,
+    sprinting: bool,
+    height: f64,
+    walk_speed: f64,
+    flying_speed: f64,
+    speed_amplifier: Option<u8>,
+    jump_boost_amplifier: Option<u8>,
+    levitating: bool,
+}
+
+impl Mover {
+    fn get(game: &Game, player: Entity) -> SysResult<Self> {
+        let effects = game.ecs.get::<ActiveEffects>(player).ok();
+        let amplifier = |effect| effects.as_ref().and_then(|e| e.amplifier(effect));
+        let flag = |value: Option<bool>| value.unwrap_or(false);
+        Ok(Self {
+            gamemode: *game.ecs.get::<Gamemode>(player)?,
+            flying: flag(game.ecs.get::<CreativeFlying>(player).ok().map(|f| f.0))
+                && flag(game.ecs.get::<CanCreativeFly>(player).ok().map(|f| f.0)),
+            sprinting: flag(game.ecs.get::<Sprinting>(player).ok().map(|s| s.0)),
+            height: if flag(game.ecs.get::<Sneaking>(player).ok().map(|s| s.0)) {
+                SNEAKING_HEIGHT
+            } else {
+                PLAYER_HEIGHT
+            },
+            walk_speed: game
+                .ecs
+                .get::<WalkSpeed>(player)
+                .map_or(DEFAULT_WALK_SPEED, |speed| speed.0 as f64),
+            flying_speed: game
+                .ecs
+                .get::<CreativeFlyingSpeed>(player)
+                .map_or(DEFAULT_FLYING_SPEED, |speed| speed.0 as f64),
+            speed_amplifier: amplifier(StatusEffect::Speed),
+            jump_boost_amplifier: amplifier(StatusEffect::JumpBoost),
+            levitating: amplifier(StatusEffect::Levitation).is_some(),
+        })
+    }
+
+    /// Returns the maximum horizontal blocks per tick.
+    fn horizontal_speed(&self) -> f64 {
+        if self.flying {
+            let sprint = if self.sprinting { 2.0 } else { 1.0 };
+            return FLYING_SPEED * sprint * self.flying_speed / DEFAULT_FLYING_SPEED;
+        }
+        let base = if self.sprinting {
+            SPRINTING_SPEED
+        } else {
+            WALKING_SPEED
+        };
+        let effect = self
+            .speed_amplifier
+            .map_or(1.0, |amplifier| 1.0 + 0.2 * (amplifier as f64 + 1.0));
+        base * effect * self.walk_speed.max(DEFAULT_WALK_SPEED) / DEFAULT_WALK_SPEED
+    }
+
+    fn jump_height(&self) -> f64 {
+        JUMP_HEIGHT
+            + self.jump_boost_amplifier.map_or(0.0, |amplifier| {
+                JUMP_BOOST_HEIGHT * (amplifier as f64 + 1.0)
+            })
+    }
+}
+
+/// Validates a movement of a player from `from` to `to`, updating
+/// the player's `MovementState`.
+pub fn validate(
+    game: &mut Game,
+    options: &MovementOptions,
+    player: Entity,
+    from: Position,
+    to: Position,
+    claimed_on_ground: bool,
+) -> SysResult<Verdict> {
+    if game.ecs.get::<MovementState>(player).is_err() {
+        game.ecs
+            .insert(player, MovementState::new(from, game.tick_count))?;
+    }
+    let mover = Mover::get(game, player)?;
+    let mut state = game.ecs.get_mut::<MovementState>(player)?;
+    if state.position != from {
+        // The server moved the player since the last movement.
+        state.reset(from);
+    }
+
+    let elapsed = game.tick_count.saturating_sub(state.last_tick) as f64;
+    state.last_tick = game.tick_count;
+
+    let mut verdict = Verdict {
+        on_ground: claimed_on_ground,
+        failed: None,
+        setback: from,
+        violations: state.violations,
+    };
+    if mover.gamemode == Gamemode::Spectator {
+        state.position = to;
+        return Ok(verdict);
+    }
+
+    let on_ground = is_on_ground(game, to);
+    if options.check_ground && claimed_on_ground && !on_ground {
+        verdict.on_ground = false;
+        verdict.failed = Some(MovementCheck::Ground);
+    }
+
+    let failed = check_speed(game, options, &mover, &mut state, elapsed, from, to)
+        .or_else(|| {
+            if options.check_collisions && collides_along(game, &mover, from, to) {
+                Some(MovementCheck::Collision)
+            } else {
+                None
+            }
+        })
+        .or_else(|| {
+            if options.check_flight {
+                check_flight(
+                    game,
+                    options,
+                    &mover,
+                    &mut state,
+                    verdict.on_ground,
+                    from,
+                    to,
+                )
+            } else {
+                None
+            }
+        });
+
+    if let Some(check) = failed {
+        verdict.failed = Some(check);
+        if check == MovementCheck::Flight {
+            verdict.setback = state.ground_position;
+            state.reset(verdict.setback);
+        }
+    } else {
+        state.position = to;
+        if verdict.on_ground {
+            state.ground_position = to;
+            state.gliding = false;
+        }
+    }
+    if verdict.failed.is_some() {
+        state.violations += 1;
+        verdict.violations = state.violations;
+    }
+    Ok(verdict)
+}
+
+/// Checks the distance moved against the distance budget of the player.
+fn check_speed(
+    game: &Game,
+    options: &MovementOptions,
+    mover: &Mover,
+    state: &mut MovementState,
+    elapsed: f64,
+    from: Position,
+    to: Position,
+) -> Option<MovementCheck> {
+    if state.gliding {
+        return None;
+    }
+    if touches_blocks(game, ground_bounds(from), is_ice) {
+        state.last_ice_tick = Some(game.tick_count);
+    }
+    let on_ice = state
+        .last_ice_tick
+        .is_some_and(|tick| game.tick_count <= tick + ICE_TICKS);
+
+    let mut horizontal_speed = mover.horizontal_speed() * options.speed_tolerance;
+    if on_ice {
+        horizontal_speed *= ICE_FACTOR;
+    }
+    let horizontal = (to.x - from.x).hypot(to.z - from.z);
+    if !spend(
+        &mut state.horizontal_budget,
+        horizontal_speed,
+        elapsed,
+        horizontal,
+    ) {
+        return Some(MovementCheck::Speed);
+    }
+
+    if mover.flying {
+        let vertical_speed = FLYING_VERTICAL_SPEED * mover.flying_speed / DEFAULT_FLYING_SPEED
+            * options.speed_tolerance;
+        let vertical = (to.y - from.y).abs();
+        if !spend(
+            &mut state.vertical_budget,
+            vertical_speed,
+            elapsed,
+            vertical,
+        ) {
+            return Some(MovementCheck::Speed);
+        }
+    }
+    None
+}
+
+/// Adds the distance allowed in `elapsed` ticks to `budget`, then spends
+/// `distance` from it. Returns `false` if the budget is insufficient.
+fn spend(budget: &mut f64, speed: f64, elapsed: f64, distance: f64) -> bool {
+    let max = speed * MAX_BUDGET_TICKS;
+    *budget = (*budget + speed * elapsed).min(max);
+    if distance > *budget + EPSILON {
+        return false;
+    }
+    *budget -= distance;
+    true
+}
+
+/// Checks that a player who can't fly doesn't rise
+/// higher than a jump or hover in the air.
+fn check_flight(
+    game: &Game,
+    options: &MovementOptions,
+    mover: &Mover,
+    state: &mut MovementState,
+    on_ground: bool,
+    from: Position,
+    to: Position,
+) -> Option<MovementCheck> {
+    let exempt = mover.flying
+        || mover.levitating
+        || state.gliding
+        || touches_blocks(game, bounds(to, mover.height, 0.0), is_climbable);
+    if exempt {
+        state.ascent = 0.0;
+        state.fall_distance = 0.0;
+        state.hover_movements = 0;
+        return None;
+    }
+
+    if on_ground {
+        state.max_ascent = mover.jump_height();
+        if touches_blocks(game, ground_bounds(to), is_bouncy) {
+            state.max_ascent = state.max_ascent.max(state.fall_distance);
+        }
+        state.ascent = 0.0;
+        state.fall_distance = 0.0;
+        state.hover_movements = 0;
+        return None;
+    }
+
+    let rise = to.y - from.y;
+    if rise < 0.0 {
+        state.fall_distance -= rise;
+        state.hover_movements = 0;
+        return None;
+    }
+    state.ascent += rise;
+    state.fall_distance = 0.0;
+    state.hover_movements += 1;
+    if state.ascent > state.max_ascent * options.speed_tolerance
+        || state.hover_movements > MAX_HOVER_MOVEMENTS
+    {
+        Some(MovementCheck::Flight)
+    } else {
+        None
+    }
+}
+
+/// Returns whether the player moves into an obstacle on the way from
+/// `from` to `to`. Players already inside an obstacle may move out.
+fn collides_along(game: &Game, mover: &Mover, from: Position, to: Position) -> bool {
+    let collides = |position| {
+        touches_blocks(
+            game,
+            bounds(position, mover.height, COLLISION_MARGIN),
+            is_obstacle,
+        )
+    };
+    if collides(from) {
+        return false;
+    }
+
+    let distance = from.distance_to(to);
+    let steps = ((distance / COLLISION_STEP).ceil() as usize).clamp(1, MAX_COLLISION_STEPS);
+    (1..=steps).any(|step| {
+        let t = step as f64 / steps as f64;
+        collides(Position {
+            x: from.x + (to.x - from.x) * t,
+            y: from.y + (to.y - from.y) * t,
+            z: from.z + (to.z - from.z) * t,
+            ..to
+        })
+    })
+}
+
+fn is_on_ground(game: &Game, position: Position) -> bool {
+    touches_blocks(game, ground_bounds(position), is_ground)
+}
+
+fn is_obstacle(block: BlockId) -> bool {
+    block.is_solid() && block.is_opaque()
+}
+
+fn is_ground(block: BlockId) -> bool {
+    !block.is_air() && !block.is_fluid()
+}
+
+fn is_ice(block: BlockId) -> bool {
+    matches!(
+        block.simplified_kind(),
+        SimplifiedBlockKind::Ice
+            | SimplifiedBlockKind::PackedIce
+            | SimplifiedBlockKind::BlueIce
+            | SimplifiedBlockKind::FrostedIce
+    )
+}
+
+fn is_bouncy(block: BlockId) -> bool {
+    matches!(
+        block.simplified_kind(),
+        SimplifiedBlockKind::SlimeBlock | SimplifiedBlockKind::Bed
+    )
+}
+
+/// Returns whether players in the block can rise without jumping.
+fn is_climbable(block: BlockId) -> bool {
+    block.is_fluid()
+        || matches!(
+            block.simplified_kind(),
+            SimplifiedBlockKind::Ladder
+                | SimplifiedBlockKind::Vine
+                | SimplifiedBlockKind::Scaffolding
+                | SimplifiedBlockKind::TwistingVines
+                | SimplifiedBlockKind::TwistingVinesPlant
+                | SimplifiedBlockKind::WeepingVines
+                | SimplifiedBlockKind::WeepingVinesPlant
+                | SimplifiedBlockKind::BubbleColumn
+                | SimplifiedBlockKind::Cobweb
+                | SimplifiedBlockKind::Kelp
+                | SimplifiedBlockKind::KelpPlant
+                | SimplifiedBlockKind::Seagrass
+                | SimplifiedBlockKind::TallSeagrass
+        )
+}
+
+/// An axis-aligned box.
+#[derive(Copy, Clone, Debug)]
+struct Bounds {
+    min: [f64; 3],
+    max: [f64; 3],
+}
+
+/// Returns the box of a player at a position, shrunk by `margin`.
+fn bounds(position: Position, height: f64, margin: f64) -> Bounds {
+    let half_width = PLAYER_WIDTH / 2.0 - margin;
+    Bounds {
+        min: [
+            position.x - half_width,
+            position.y + margin,
+            position.z - half_width,
+        ],
+        max: [
+            position.x + half_width,
+            position.y + height - margin,
+            position.z + half_width,
+        ],
+    }
+}
+
+/// Returns the box below the feet of a player in which ground is detected.
+fn ground_bounds(position: Position) -> Bounds {
+    let half_width = PLAYER_WIDTH / 2.0 - COLLISION_MARGIN;
+    Bounds {
+        min: [
+            position.x - half_width,
+            position.y - GROUND_DISTANCE,
+            position.z - half_width,
+        ],
+        max: [
+            position.x + half_width,
+            position.y - COLLISION_MARGIN,
+            position.z + half_width,
+        ],
+    }
+}
+
+/// Returns whether any loaded block in the box matches `predicate`.
+fn touches_blocks(game: &Game, bounds: Bounds, predicate: impl Fn(BlockId) -> bool) -> bool {
+    let [min_x, min_y, min_z] = bounds.min.map(|c| c.floor() as i32);
+    let [max_x, max_y, max_z] = bounds.max.map(|c| c.floor() as i32);
+    for x in min_x..=max_x {
+        for y in min_y..=max_y {
+            for z in min_z..=max_z {
+                let block = ValidBlockPosition::try_from(BlockPosition::new(x, y, z))
+                    .ok()
+                    .and_then(|pos| game.block(pos));
+                if block.is_some_and(&predicate) {
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_accumulates_up_to_a_limit() {
+        let mut budget = 0.0;
+        assert!(!spend(&mut budget, 0.5, 1.0, 1.0));
+        assert!(spend(&mut budget, 0.5, 1.0, 1.0));
+        assert!(budget.abs() < EPSILON);
+
+        // Standing still doesn't allow moving arbitrarily far later.
+        assert!(!spend(
+            &mut budget,
+            0.5,
+            1000.0,
+            0.5 * MAX_BUDGET_TICKS + 0.1
+        ));
+        assert!(spend(&mut budget, 0.5, 0.0, 0.5 * MAX_BUDGET_TICKS));
+    }
+
+    #[test]
+    fn sprinting_and_effects_increase_speed() {
+        let mut mover = Mover {
+            gamemode: Gamemode::Survival,
+            flying: false,
+            sprinting: false,
+            height: PLAYER_HEIGHT,
+            walk_speed: DEFAULT_WALK_SPEED,
+            flying_speed: DEFAULT_FLYING_SPEED,
+            speed_amplifier: None,
+            jump_boost_amplifier: None,
+            levitating: false,
+        };
+        let walking = mover.horizontal_speed();
+        mover.sprinting = true;
+        let sprinting = mover.horizontal_speed();
+        mover.speed_amplifier = Some(1);
+        let boosted = mover.horizontal_speed();
+        mover.flying = true;
+        let flying = mover.horizontal_speed();
+        assert!(walking < sprinting && sprinting < boosted && boosted < flying);
+
+        mover.jump_boost_amplifier = Some(0);
+        assert!(mover.jump_height() > JUMP_HEIGHT);
+    }
+}
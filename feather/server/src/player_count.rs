@@ -3,10 +3,12 @@ use std::sync::{
     Arc,
 };
 
+use parking_lot::Mutex;
+
 #[derive(Debug)]
 pub struct MaxPlayersReached;
 
-/// Maintains the server player count.
+/// Maintains the server player count and the names of online players.
 ///
 /// Can be cloned to create a new handle.
 #[derive(Clone)]
@@ -20,6 +22,7 @@ impl PlayerCount {
             inner: Arc::new(Inner {
                 count: AtomicU32::new(0),
                 max_players,
+                usernames: Mutex::new(Vec::new()),
             }),
         }
     }
@@ -43,6 +46,11 @@ impl PlayerCount {
         }
     }
 
+    /// Adds a player even if the server is full.
+    pub fn add_player_unchecked(&self) {
+        self.inner.count.fetch_add(1, Ordering::SeqCst);
+    }
+
     pub fn remove_player(&self) {
         self.inner.count.fetch_sub(1, Ordering::SeqCst);
     }
@@ -50,11 +58,32 @@ impl PlayerCount {
     pub fn get(&self) -> u32 {
         self.inner.count.load(Ordering::Acquire)
     }
+
+    pub fn max(&self) -> u32 {
+        self.inner.max_players
+    }
+
+    pub fn add_username(&self, username: &str) {
+        self.inner.usernames.lock().push(username.to_owned());
+    }
+
+    pub fn remove_username(&self, username: &str) {
+        let mut usernames = self.inner.usernames.lock();
+        if let Some(index) = usernames.iter().position(|name| name == username) {
+            usernames.swap_remove(index);
+        }
+    }
+
+    /// Returns the names of online players.
+    pub fn usernames(&self) -> Vec<String> {
+        self.inner.usernames.lock().clone()
+    }
 }
 
 struct Inner {
     count: AtomicU32,
     max_players: u32,
+    usernames: Mutex<Vec<String>>,
 }
 
 #[cfg(test)]
@@ -0,0 +1,354 @@
+//! The GameSpy4 Query protocol, used by server lists
+//! and monitoring tools to fetch server information over UDP.
+//!
+//! Clients first send a handshake and receive a challenge token,
+//! which they must include in their stat requests. Basic stats contain
+//! the MOTD and player count, while full stats also list the players
+//! and plugins.
+
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use ahash::AHashMap;
+use anyhow::Context;
+use parking_lot::RwLock;
+use tokio::net::UdpSocket;
+
+use crate::{options::Options, player_count::PlayerCount};
+
+/// Game version reported in full stats.
+const GAME_VERSION: &str = "1.16.5";
+
+/// Name of the server software reported with the plugin list.
+const SERVER_MOD: &str = "Feather 1.16.5";
+
+/// Magic bytes starting every request.
+const MAGIC: [u8; 2] = [0xFE, 0xFD];
+
+const HANDSHAKE_TYPE: u8 = 9;
+const STAT_TYPE: u8 = 0;
+
+/// Time after which challenge tokens expire.
+const TOKEN_LIFETIME: Duration = Duration::from_secs(30);
+
+/// Names of the loaded plugins, reported in full stats.
+///
+/// Can be cloned to create a new handle.
+#[derive(Clone, Debug, Default)]
+pub struct PluginNames {
+    inner: Arc<RwLock<Vec<String>>>,
+}
+
+impl PluginNames {
+    pub fn set(&self, names: Vec<String>) {
+        *self.inner.write() = names;
+    }
+
+    pub fn get(&self) -> Vec<String> {
+        self.inner.read().clone()
+    }
+}
+
+/// A request sent by a Query client.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Request {
+    Handshake { session_id: i32 },
+    BasicStat { session_id: i32, token: i32 },
+    FullStat { session_id: i32, token: i32 },
+}
+
+impl Request {
+    fn parse(bytes: &[u8]) -> Option<Self> {
+        let (kind, session_id, payload) = match bytes.strip_prefix(&MAGIC)? {
+            [kind, a, b, c, d, payload @ ..] => {
+                (*kind, i32::from_be_bytes([*a, *b, *c, *d]), payload)
+            }
+            _ => return None,
+        };
+        match (kind, payload) {
+            (HANDSHAKE_TYPE, []) => Some(Request::Handshake { session_id }),
+            (STAT_TYPE, [a, b, c, d]) => Some(Request::BasicStat {
+                session_id,
+                token: i32::from_be_bytes([*a, *b, *c, *d]),
+            }),
+            // Full stat requests are padded with four bytes.
+            (STAT_TYPE, [a, b, c, d, _, _, _, _]) => Some(Request::FullStat {
+                session_id,
+                token: i32::from_be_bytes([*a, *b, *c, *d]),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// The server information reported by stats.
+#[derive(Debug)]
+struct Status {
+    motd: String,
+    level_name: String,
+    online: u32,
+    max_players: u32,
+    host_ip: String,
+    host_port: u16,
+    usernames: Vec<String>,
+    plugins: Vec<String>,
+}
+
+/// Appends a null-terminated string. Query clients
+/// expect ISO-8859-1, so other characters are replaced.
+fn write_string(out: &mut Vec<u8>, string: &str) {
+    out.extend(
+        string
+            .chars()
+            .map(|c| if (c as u32) < 0x100 { c as u8 } else { b'?' }),
+    );
+    out.push(0);
+}
+
+fn write_header(out: &mut Vec<u8>, kind: u8, session_id: i32) {
+    out.push(kind);
+    out.extend_from_slice(&session_id.to_be_bytes());
+}
+
+fn encode_handshake(session_id: i32, token: i32) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_header(&mut out, HANDSHAKE_TYPE, session_id);
+    write_string(&mut out, &token.to_string());
+    out
+}
+
+fn encode_basic_stat(session_id: i32, status: &Status) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_header(&mut out, STAT_TYPE, session_id);
+    write_string(&mut out, &status.motd);
+    write_string(&mut out, "SMP");
+    write_string(&mut out, &status.level_name);
+    write_string(&mut out, &status.online.to_string());
+    write_string(&mut out, &status.max_players.to_string());
+    out.extend_from_slice(&status.host_port.to_le_bytes());
+    write_string(&mut out, &status.host_ip);
+    out
+}
+
+fn encode_full_stat(session_id: i32, status: &Status) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_header(&mut out, STAT_TYPE, session_id);
+    out.extend_from_slice(b"splitnum\0\x80\0");
+
+    let plugins = if status.plugins.is_empty() {
+        SERVER_MOD.to_owned()
+    } else {
+        format!("{}: {}", SERVER_MOD, status.plugins.join("; "))
+    };
+    let values = [
+        ("hostname", status.motd.clone()),
+        ("gametype", "SMP".to_owned()),
+        ("game_id", "MINECRAFT".to_owned()),
+        ("version", GAME_VERSION.to_owned()),
+        ("plugins", plugins),
+        ("map", status.level_name.clone()),
+        ("numplayers", status.online.to_string()),
+        ("maxplayers", status.max_players.to_string()),
+        ("hostport", status.host_port.to_string()),
+        ("hostip", status.host_ip.clone()),
+    ];
+    for (key, value) in &values {
+        write_string(&mut out, key);
+        write_string(&mut out, value);
+    }
+    out.push(0);
+
+    out.extend_from_slice(b"\x01player_\0\0");
+    for username in &status.usernames {
+        write_string(&mut out, username);
+    }
+    out.push(0);
+    out
+}
+
+/// Challenge tokens handed out to clients.
+#[derive(Default)]
+struct ChallengeTokens {
+    tokens: AHashMap<SocketAddr, (i32, Instant)>,
+}
+
+impl ChallengeTokens {
+    fn create(&mut self, addr: SocketAddr, now: Instant) -> i32 {
+        self.tokens
+            .retain(|_, (_, created)| now.duration_since(*created) < TOKEN_LIFETIME);
+        let token = rand::random::<i32>() & 0x7FFF_FFFF;
+        self.tokens.insert(addr, (token, now));
+        token
+    }
+
+    fn is_valid(&self, addr: SocketAddr, token: i32, now: Instant) -> bool {
+        self.tokens.get(&addr).is_some_and(|&(expected, created)| {
+            expected == token && now.duration_since(created) < TOKEN_LIFETIME
+        })
+    }
+}
+
+/// Listens for and answers Query requests.
+pub struct QueryListener {
+    socket: UdpSocket,
+    options: Arc<Options>,
+    player_count: PlayerCount,
+    plugins: PluginNames,
+    tokens: ChallengeTokens,
+}
+
+impl QueryListener {
+    pub async fn start(
+        port: u16,
+        options: Arc<Options>,
+        player_count: PlayerCount,
+        plugins: PluginNames,
+    ) -> anyhow::Result<()> {
+        let socket = UdpSocket::bind(format!("{}:{}", options.bind_address, port))
+            .await
+            .context("failed to bind the query port")?;
+        log::info!("Query is listening on {}:{}", options.bind_address, port);
+
+        let listener = QueryListener {
+            socket,
+            options,
+            player_count,
+            plugins,
+            tokens: ChallengeTokens::default(),
+        };
+        tokio::task::spawn(async move {
+            listener.run().await;
+        });
+
+        Ok(())
+    }
+
+    async fn run(mut self) {
+        let mut buffer = [0; 1460];
+        loop {
+            let (length, addr) = match self.socket.recv_from(&mut buffer).await {
+                Ok(received) => received,
+                Err(e) => {
+                    log::debug!("Failed to receive query request: {}", e);
+                    continue;
+                }
+            };
+            if let Some(response) = self.handle(&buffer[..length], addr) {
+                if let Err(e) = self.socket.send_to(&response, addr).await {
+                    log::debug!("Failed to send query response to {}: {}", addr, e);
+                }
+            }
+        }
+    }
+
+    fn handle(&mut self, bytes: &[u8], addr: SocketAddr) -> Option<Vec<u8>> {
+        let now = Instant::now();
+        match Request::parse(bytes)? {
+            Request::Handshake { session_id } => {
+                let token = self.tokens.create(addr, now);
+                Some(encode_handshake(session_id, token))
+            }
+            Request::BasicStat { session_id, token } if self.tokens.is_valid(addr, token, now) => {
+                Some(encode_basic_stat(session_id, &self.status()))
+            }
+            Request::FullStat { session_id, token } if self.tokens.is_valid(addr, token, now) => {
+                Some(encode_full_stat(session_id, &self.status()))
+            }
+            _ => None,
+        }
+    }
+
+    fn status(&self) -> Status {
+        Status {
+            motd: self.options.motd.clone(),
+            level_name: self.options.level_name.clone(),
+            online: self.player_count.get(),
+            max_players: self.options.max_players,
+            host_ip: self.options.bind_address.clone(),
+            host_port: self.options.port,
+            usernames: self.player_count.usernames(),
+            plugins: self.plugins.get(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status() -> Status {
+        Status {
+            motd: "A server".to_owned(),
+            level_name: "world".to_owned(),
+            online: 2,
+            max_players: 16,
+            host_ip: "0.0.0.0".to_owned(),
+            host_port: 25565,
+            usernames: vec!["Alice".to_owned(), "Bob".to_owned()],
+            plugins: vec!["Example 1.0".to_owned()],
+        }
+    }
+
+    #[test]
+    fn parse_requests() {
+        let handshake = [0xFE, 0xFD, 9, 0, 0, 0, 1];
+        assert_eq!(
+            Request::parse(&handshake),
+            Some(Request::Handshake { session_id: 1 })
+        );
+
+        let basic = [0xFE, 0xFD, 0, 0, 0, 0, 1, 0, 0, 1, 0];
+        assert_eq!(
+            Request::parse(&basic),
+            Some(Request::BasicStat {
+                session_id: 1,
+                token: 256
+            })
+        );
+
+        let full = [0xFE, 0xFD, 0, 0, 0, 0, 1, 0, 0, 1, 0, 0, 0, 0, 0];
+        assert_eq!(
+            Request::parse(&full),
+            Some(Request::FullStat {
+                session_id: 1,
+                token: 256
+            })
+        );
+
+        assert_eq!(Request::parse(&[0xFE, 0xFD, 0, 0, 0, 0, 1, 0]), None);
+    }
+
+    #[test]
+    fn basic_stat() {
+        let response = encode_basic_stat(1, &status());
+        let mut expected = vec![0, 0, 0, 0, 1];
+        expected.extend_from_slice(b"A server\0SMP\0world\x002\x0016\0");
+        expected.extend_from_slice(&25565u16.to_le_bytes());
+        expected.extend_from_slice(b"0.0.0.0\0");
+        assert_eq!(response, expected);
+    }
+
+    #[test]
+    fn full_stat_lists_players_and_plugins() {
+        let response = encode_full_stat(1, &status());
+        let plugins = format!("plugins\0{}: Example 1.0\0", SERVER_MOD);
+        let contains = |needle: &[u8]| response.windows(needle.len()).any(|w| w == needle);
+        assert!(contains(plugins.as_bytes()));
+        assert!(contains(b"numplayers\x002\0"));
+        assert!(response.ends_with(b"\0\x01player_\0\0Alice\0Bob\0\0"));
+    }
+
+    #[test]
+    fn challenge_tokens() {
+        let mut tokens = ChallengeTokens::default();
+        let addr = "127.0.0.1:1234".parse().unwrap();
+        let now = Instant::now();
+        let token = tokens.create(addr, now);
+        assert!(tokens.is_valid(addr, token, now));
+        assert!(!tokens.is_valid(addr, token.wrapping_add(1), now));
+        assert!(!tokens.is_valid(addr, token, now + TOKEN_LIFETIME));
+    }
+}
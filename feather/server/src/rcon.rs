@@ -0,0 +1,365 @@
+//! The RCON protocol, which lets administrators run commands remotely.
+//!
+//! Packets use the framing of the Source RCON protocol: a little-endian
+//! length, request ID and type, followed by a null-terminated body and
+//! an empty string. Clients authenticate with the configured password
+//! before running commands as a console sender.
+
+use std::{io::ErrorKind, net::SocketAddr, sync::Arc};
+
+use anyhow::{bail, Context};
+use common::commands::CommandSender;
+use flume::Sender;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{commands::CommandRequest, listener::accept_with_backoff};
+
+const AUTH_TYPE: i32 = 3;
+const EXEC_COMMAND_TYPE: i32 = 2;
+const AUTH_RESPONSE_TYPE: i32 = 2;
+const RESPONSE_VALUE_TYPE: i32 = 0;
+
+/// Request ID of the auth response sent for a wrong password.
+const AUTH_FAILED_ID: i32 = -1;
+
+/// Maximum length of a received packet, excluding its length field.
+const MAX_REQUEST_LENGTH: usize = 1460;
+
+/// Maximum body length of a response packet.
+/// Longer output is split across several packets.
+const MAX_RESPONSE_BODY: usize = 4096;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Packet {
+    id: i32,
+    kind: i32,
+    body: String,
+}
+
+impl Packet {
+    fn decode(data: &[u8]) -> anyhow::Result<Self> {
+        if data.len() < 10 {
+            bail!("packet too short ({} bytes)", data.len());
+        }
+        let id = i32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        let kind = i32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+        let body = &data[8..];
+        let body = match body.iter().position(|&b| b == 0) {
+            Some(end) => &body[..end],
+            None => body,
+        };
+        Ok(Self {
+            id,
+            kind,
+            body: String::from_utf8_lossy(body).into_owned(),
+        })
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let length = 4 + 4 + self.body.len() + 2;
+        let mut data = Vec::with_capacity(4 + length);
+        data.extend_from_slice(&(length as i32).to_le_bytes());
+        data.extend_from_slice(&self.id.to_le_bytes());
+        data.extend_from_slice(&self.kind.to_le_bytes());
+        data.extend_from_slice(self.body.as_bytes());
+        data.extend_from_slice(&[0, 0]);
+        data
+    }
+}
+
+/// Splits a response body into parts which fit in a packet,
+/// without splitting characters.
+fn split_body(mut body: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    while body.len() > MAX_RESPONSE_BODY {
+        let mut end = MAX_RESPONSE_BODY;
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (part, rest) = body.split_at(end);
+        parts.push(part);
+        body = rest;
+    }
+    parts.push(body);
+    parts
+}
+
+/// Compares a received password with the configured one in time
+/// independent of where they differ, so it can't be guessed byte by byte.
+fn password_matches(received: &str, password: &str) -> bool {
+    received.len() == password.len()
+        && received
+            .bytes()
+            .zip(password.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Listens for and accepts RCON connections.
+pub struct RconListener {
+    listener: TcpListener,
+    password: Arc<str>,
+    commands: Sender<CommandRequest>,
+}
+
+impl RconListener {
+    /// Starts listening, returning the bound address.
+    pub async fn start(
+        address: &str,
+        port: u16,
+        password: &str,
+        commands: Sender<CommandRequest>,
+    ) -> anyhow::Result<SocketAddr> {
+        let listener = TcpListener::bind(format!("{}:{}", address, port))
+            .await
+            .context("failed to bind the RCON port")?;
+        let local_addr = listener.local_addr()?;
+        log::info!("RCON is listening on {}", local_addr);
+
+        let listener = RconListener {
+            listener,
+            password: password.into(),
+            commands,
+        };
+        tokio::task::spawn(async move {
+            listener.run().await;
+        });
+
+        Ok(local_addr)
+    }
+
+    async fn run(self) {
+        loop {
+            let (stream, addr) = accept_with_backoff(&self.listener, "RCON").await;
+            self.accept(stream, addr);
+        }
+    }
+
+    fn accept(&self, stream: TcpStream, addr: SocketAddr) {
+        let connection = Connection {
+            stream,
+            addr,
+            password: Arc::clone(&self.password),
+            commands: self.commands.clone(),
+            authenticated: false,
+        };
+        tokio::task::spawn(async move {
+            if let Err(e) = connection.run().await {
+                log::debug!("RCON connection from {} failed: {:?}", addr, e);
+            }
+        });
+    }
+}
+
+struct Connection {
+    stream: TcpStream,
+    addr: SocketAddr,
+    password: Arc<str>,
+    commands: Sender<CommandRequest>,
+    authenticated: bool,
+}
+
+impl Connection {
+    async fn run(mut self) -> anyhow::Result<()> {
+        while let Some(packet) = self.read().await? {
+            match packet.kind {
+                // Like vanilla, a wrong password closes the connection,
+                // so each guess needs a new one.
+                AUTH_TYPE => {
+                    if !self.authenticate(packet).await? {
+                        break;
+                    }
+                }
+                EXEC_COMMAND_TYPE if self.authenticated => self.run_command(packet).await?,
+                // Clients send an empty packet of this type after a command
+                // to find the end of a response split across several packets,
+                // since it is answered after all of them.
+                RESPONSE_VALUE_TYPE if self.authenticated => {
+                    self.write(packet.id, RESPONSE_VALUE_TYPE, "").await?
+                }
+                _ => self.write(AUTH_FAILED_ID, AUTH_RESPONSE_TYPE, "").await?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the password sent by the client, returning whether it was right.
+    async fn authenticate(&mut self, packet: Packet) -> anyhow::Result<bool> {
+        self.authenticated = password_matches(&packet.body, &self.password);
+        if self.authenticated {
+            log::info!("RCON client {} logged in", self.addr);
+            self.write(packet.id, AUTH_RESPONSE_TYPE, "").await?;
+        } else {
+            log::warn!("RCON client {} sent a wrong password", self.addr);
+            self.write(AUTH_FAILED_ID, AUTH_RESPONSE_TYPE, "").await?;
+        }
+        Ok(self.authenticated)
+    }
+
+    async fn run_command(&mut self, packet: Packet) -> anyhow::Result<()> {
+        let (reply, output) = flume::bounded(1);
+        self.commands
+            .send_async(CommandRequest {
+                sender: CommandSender::Rcon,
+                line: packet.body,
+                reply: Some(reply),
+            })
+            .await?;
+        let output = output.recv_async().await?.join("\n");
+        for part in split_body(&output) {
+            self.write(packet.id, RESPONSE_VALUE_TYPE, part).await?;
+        }
+        Ok(())
+    }
+
+    async fn read(&mut self) -> anyhow::Result<Option<Packet>> {
+        let mut length = [0; 4];
+        match self.stream.read_exact(&mut length).await {
+            Ok(_) => (),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+        let length = i32::from_le_bytes(length);
+        if length < 10 || length as usize > MAX_REQUEST_LENGTH {
+            bail!("invalid packet length {}", length);
+        }
+
+        let mut data = vec![0; length as usize];
+        self.stream.read_exact(&mut data).await?;
+        Packet::decode(&data).map(Some)
+    }
+
+    async fn write(&mut self, id: i32, kind: i32, body: &str) -> anyhow::Result<()> {
+        let packet = Packet {
+            id,
+            kind,
+            body: body.to_owned(),
+        };
+        self.stream.write_all(&packet.encode()).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpStream,
+    };
+
+    use super::*;
+
+    /// A plain, blocking RCON client.
+    struct Client(TcpStream);
+
+    impl Client {
+        fn send(&mut self, id: i32, kind: i32, body: &str) {
+            let packet = Packet {
+                id,
+                kind,
+                body: body.to_owned(),
+            };
+            self.0.write_all(&packet.encode()).unwrap();
+        }
+
+        fn receive(&mut self) -> Packet {
+            let mut length = [0; 4];
+            self.0.read_exact(&mut length).unwrap();
+            let mut data = vec![0; i32::from_le_bytes(length) as usize];
+            self.0.read_exact(&mut data).unwrap();
+            Packet::decode(&data).unwrap()
+        }
+
+        fn is_closed(&mut self) -> bool {
+            matches!(self.0.read(&mut [0]), Ok(0))
+        }
+    }
+
+    #[test]
+    fn packet_round_trip() {
+        let packet = Packet {
+            id: 7,
+            kind: EXEC_COMMAND_TYPE,
+            body: "list".to_owned(),
+        };
+        let data = packet.encode();
+        assert_eq!(data.len(), 4 + 14);
+        assert_eq!(Packet::decode(&data[4..]).unwrap(), packet);
+    }
+
+    #[test]
+    fn split_long_bodies() {
+        let body = "é".repeat(MAX_RESPONSE_BODY);
+        let parts = split_body(&body);
+        assert_eq!(parts.len(), 2);
+        assert!(parts.iter().all(|part| part.len() <= MAX_RESPONSE_BODY));
+        assert_eq!(parts.concat(), body);
+        assert_eq!(split_body(""), vec![""]);
+    }
+
+    #[test]
+    fn compare_passwords() {
+        assert!(password_matches("secret", "secret"));
+        assert!(!password_matches("secreT", "secret"));
+        assert!(!password_matches("secret2", "secret"));
+        assert!(!password_matches("", "secret"));
+    }
+
+    #[tokio::test]
+    async fn run_commands_over_tcp() {
+        let (commands, requests) = flume::unbounded::<CommandRequest>();
+        let addr = RconListener::start("127.0.0.1", 0, "secret", commands)
+            .await
+            .unwrap();
+
+        // Answers commands in place of the game.
+        let long_line = "a".repeat(MAX_RESPONSE_BODY + 100);
+        let output = vec![format!("ran {}", "say hi"), long_line.clone()];
+        let expected = output.join("\n");
+        tokio::task::spawn(async move {
+            while let Ok(request) = requests.recv_async().await {
+                assert_eq!(request.sender, CommandSender::Rcon);
+                assert_eq!(request.line, "say hi");
+                request.reply.unwrap().send(output.clone()).unwrap();
+            }
+        });
+
+        let received = tokio::task::spawn_blocking(move || {
+            let mut client = Client(TcpStream::connect(addr).unwrap());
+
+            client.send(1, EXEC_COMMAND_TYPE, "say hi");
+            assert_eq!(client.receive().id, AUTH_FAILED_ID);
+
+            client.send(2, AUTH_TYPE, "wrong");
+            assert_eq!(client.receive().id, AUTH_FAILED_ID);
+            assert!(client.is_closed());
+
+            let mut client = Client(TcpStream::connect(addr).unwrap());
+
+            client.send(3, AUTH_TYPE, "secret");
+            let response = client.receive();
+            assert_eq!((response.id, response.kind), (3, AUTH_RESPONSE_TYPE));
+
+            client.send(4, EXEC_COMMAND_TYPE, "say hi");
+            client.send(5, RESPONSE_VALUE_TYPE, "");
+            let mut parts = Vec::new();
+            loop {
+                let packet = client.receive();
+                if packet.id == 5 {
+                    break;
+                }
+                assert_eq!((packet.id, packet.kind), (4, RESPONSE_VALUE_TYPE));
+                parts.push(packet.body);
+            }
+            parts
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(received.len(), 2);
+        assert_eq!(received.concat(), expected);
+    }
+}
@@ -0,0 +1,120 @@
+//! Orderly shutdown of the server.
+//!
+//! A shutdown is requested by the `stop` command or by SIGINT and SIGTERM.
+//! The tick loop then exits, and [`disconnect_players`] kicks all players
+//! and saves their data before the world is saved.
+
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use common::Game;
+use ecs::SysResult;
+
+use crate::{systems::player_leave, Server};
+
+/// Reason shown to players disconnected by a shutdown.
+pub const SHUTDOWN_REASON: &str = "Server closed";
+
+/// Status code the process exits with when a second
+/// signal interrupts the shutdown.
+const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Time given to connections to send the disconnect packets,
+/// which are written asynchronously.
+const DISCONNECT_GRACE_PERIOD: Duration = Duration::from_millis(250);
+
+/// Records whether a shutdown was requested.
+///
+/// Can be cloned to create a new handle.
+#[derive(Clone, Debug, Default)]
+pub struct ShutdownHandle {
+    requested: Arc<AtomicBool>,
+}
+
+impl ShutdownHandle {
+    /// Requests a shutdown. Returns `false` if one was already requested.
+    pub fn request(&self) -> bool {
+        !self.requested.swap(true, Ordering::SeqCst)
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+}
+
+/// Requests a shutdown when the process receives SIGINT or SIGTERM.
+/// A second signal exits immediately, without saving.
+///
+/// Must be called within the context of a Tokio runtime.
+pub fn handle_signals(shutdown: ShutdownHandle) {
+    tokio::task::spawn(async move {
+        if let Err(e) = listen_for_signals(&shutdown).await {
+            log::error!("Failed to listen for signals: {}", e);
+        }
+    });
+}
+
+#[cfg(unix)]
+async fn listen_for_signals(shutdown: &ShutdownHandle) -> io::Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    loop {
+        tokio::select! {
+            _ = interrupt.recv() => (),
+            _ = terminate.recv() => (),
+        }
+        on_signal(shutdown);
+    }
+}
+
+#[cfg(not(unix))]
+async fn listen_for_signals(shutdown: &ShutdownHandle) -> io::Result<()> {
+    loop {
+        tokio::signal::ctrl_c().await?;
+        on_signal(shutdown);
+    }
+}
+
+fn on_signal(shutdown: &ShutdownHandle) {
+    if shutdown.request() {
+        log::info!("Received a signal; stopping the server");
+    } else {
+        log::warn!("Received a second signal; exiting without saving");
+        std::process::exit(INTERRUPTED_EXIT_CODE);
+    }
+}
+
+/// Disconnects all players with the given reason
+/// and saves their data.
+pub fn disconnect_players(game: &mut Game, reason: &str) -> SysResult {
+    let resources = Arc::clone(&game.resources);
+    let mut server = resources.get_mut::<Server>()?;
+    server.broadcast_with(|client| client.disconnect(reason));
+    player_leave::remove_disconnected_clients(game, &mut server)?;
+
+    std::thread::sleep(DISCONNECT_GRACE_PERIOD);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_once() {
+        let shutdown = ShutdownHandle::default();
+        let handle = shutdown.clone();
+        assert!(!shutdown.is_requested());
+        assert!(handle.request());
+        assert!(shutdown.is_requested());
+        assert!(!shutdown.request());
+    }
+}
@@ -1,15 +1,21 @@
 //! Systems linking a `Server` and a `Game`.
 
+pub(crate) mod autosave;
 mod block;
 mod chat;
+mod effects;
 mod entity;
+mod experience;
 mod gamemode;
+mod health;
 mod particle;
 mod player_join;
-mod player_leave;
+pub(crate) mod player_leave;
 mod plugin_message;
 mod tablist;
 pub mod view;
+mod window;
+mod world_border;
 
 use std::time::{Duration, Instant};
 
@@ -21,6 +27,7 @@ use crate::{client::ClientId, Server};
 
 /// Registers systems for a `Server` with a `Game`.
 pub fn register(server: Server, game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    let metrics_enabled = server.options.metrics_address.is_some();
     game.insert_resource(server);
 
     player_join::register(systems);
@@ -28,16 +35,28 @@ pub fn register(server: Server, game: &mut Game, systems: &mut SystemExecutor<Ga
         .group::<Server>()
         .add_system(handle_packets)
         .add_system(send_keepalives);
+    crate::commands::register(game, systems);
     view::register(game, systems);
     crate::chunk_subscriptions::register(systems);
     player_leave::register(systems);
+    autosave::register(systems);
     tablist::register(systems);
     block::register(systems);
+    // Orb pickups must be sent before the collected orbs are unloaded.
+    experience::register(systems);
     entity::register(game, systems);
+    // Effects are sent after spawn packets so that new entities show them.
+    effects::register(systems);
     chat::register(game, systems);
     particle::register(systems);
     plugin_message::register(systems);
     gamemode::register(systems);
+    health::register(systems);
+    window::register(systems);
+    world_border::register(systems);
+    if metrics_enabled {
+        crate::metrics::register(systems);
+    }
 
     systems.group::<Server>().add_system(tick_clients);
 }
@@ -0,0 +1,57 @@
+//! Saves the players queued by the autosave in `common`.
+
+use common::{autosave::Autosave, Game};
+use ecs::{Entity, SysResult, SystemExecutor};
+use quill_common::entities::Player;
+
+use crate::{systems::player_leave::save_player_data, ClientId, Server};
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.group::<Server>().add_system(autosave_players);
+}
+
+fn autosave_players(game: &mut Game, server: &mut Server) -> SysResult {
+    let players = match game.resources.get_mut::<Autosave>() {
+        Ok(mut autosave) => autosave.take_players(),
+        Err(_) => return Ok(()),
+    };
+    for player in players {
+        save_player(game, server, player);
+    }
+    Ok(())
+}
+
+/// Saves the data of all online players.
+/// Returns the number of players saved.
+pub fn save_all_players(game: &Game, server: &Server) -> usize {
+    let players: Vec<Entity> = game
+        .ecs
+        .query::<&Player>()
+        .iter()
+        .map(|(player, _)| player)
+        .collect();
+    players
+        .into_iter()
+        .filter(|&player| save_player(game, server, player))
+        .count()
+}
+
+/// Saves the data of the given player, if still online.
+/// Returns whether the data was saved.
+fn save_player(game: &Game, server: &Server, player: Entity) -> bool {
+    let client = match game.ecs.get::<ClientId>(player) {
+        Ok(client_id) => match server.clients.get(*client_id) {
+            Some(client) => client,
+            None => return false,
+        },
+        // The player left after being queued.
+        Err(_) => return false,
+    };
+    match save_player_data(game, player, client.uuid()) {
+        Ok(()) => true,
+        Err(e) => {
+            log::error!("Failed to save data for {}: {:?}", client.username(), e);
+            false
+        }
+    }
+}
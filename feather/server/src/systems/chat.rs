@@ -37,7 +37,7 @@ fn flush_chat_boxes(game: &mut Game, server: &mut Server) -> SysResult {
 fn flush_console_chat_box(game: &mut Game) -> SysResult {
     for (_, (_console, mailbox)) in game.ecs.query::<(&Console, &mut ChatBox)>().iter() {
         for message in mailbox.drain() {
-            log::info!("{:?}", message.text().as_ansi());
+            log::info!("{}", message.text().as_ansi());
         }
     }
 
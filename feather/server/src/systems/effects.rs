@@ -0,0 +1,133 @@
+//! Sends status effects, potion splashes and area effect clouds to clients.
+
+use std::convert::TryInto;
+
+use base::{EntityMetadata, Position};
+use common::{
+    effects::{area_effect_cloud::AreaEffectCloudState, particle_color},
+    events::PotionSplashEvent,
+    Game,
+};
+use ecs::{SysResult, SystemExecutor};
+use quill_common::components::ActiveEffects;
+
+use crate::{
+    entities::{PreviousEffects, META_INDEX_CLOUD_RADIUS},
+    NetworkId, Server,
+};
+
+/// World event played when a splash potion breaks.
+const WORLD_EVENT_SPLASH_POTION: i32 = 2002;
+
+/// World event played when a splash potion with instant effects breaks.
+const WORLD_EVENT_INSTANT_SPLASH_POTION: i32 = 2007;
+
+/// Metadata index of the particle color of a living entity's effects.
+const META_INDEX_EFFECT_COLOR: u8 = 9;
+
+/// Metadata index of whether a living entity's effects are all ambient.
+const META_INDEX_EFFECT_AMBIENT: u8 = 10;
+
+/// Ticks between updates of area effect cloud radii.
+const CLOUD_RADIUS_UPDATE_INTERVAL: u64 = 5;
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .group::<Server>()
+        .add_system(send_effect_updates)
+        .add_system(send_potion_splashes)
+        .add_system(send_cloud_radii);
+}
+
+/// Sends effects which were added, strengthened or
+/// renewed since the last tick, and removes expired ones.
+fn send_effect_updates(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (effects, previous, &network_id, &position)) in game
+        .ecs
+        .query::<(&ActiveEffects, &mut PreviousEffects, &NetworkId, &Position)>()
+        .iter()
+    {
+        if *effects == previous.0 {
+            continue;
+        }
+
+        for (effect, instance) in effects.iter() {
+            let changed = match previous.0.get(effect) {
+                Some(old) => {
+                    old.amplifier != instance.amplifier
+                        || old.ambient != instance.ambient
+                        || old.show_particles != instance.show_particles
+                        || old.show_icon != instance.show_icon
+                        || instance.duration > old.duration
+                }
+                None => true,
+            };
+            if changed {
+                server.broadcast_nearby_with(position, |client| {
+                    client.send_entity_effect(network_id, effect, instance)
+                });
+            }
+        }
+        for (effect, _) in previous.0.iter() {
+            if !effects.contains(effect) {
+                server.broadcast_nearby_with(position, |client| {
+                    client.send_remove_entity_effect(network_id, effect)
+                });
+            }
+        }
+
+        let appearance = |effects: &ActiveEffects| {
+            let color = particle_color(effects.iter()).unwrap_or(0);
+            let ambient = !effects.is_empty() && effects.iter().all(|(_, e)| e.ambient);
+            (color, ambient)
+        };
+        let (color, ambient) = appearance(effects);
+        if (color, ambient) != appearance(&previous.0) {
+            let metadata = EntityMetadata::new()
+                .with(META_INDEX_EFFECT_COLOR, color as i32)
+                .with(META_INDEX_EFFECT_AMBIENT, ambient);
+            server.broadcast_nearby_with(position, |client| {
+                client.send_entity_metadata(network_id, metadata.clone())
+            });
+        }
+
+        previous.0 = effects.clone();
+    }
+    Ok(())
+}
+
+fn send_potion_splashes(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, event) in game.ecs.query::<&PotionSplashEvent>().iter() {
+        let block = match event.position.block().try_into() {
+            Ok(block) => block,
+            Err(_) => continue,
+        };
+        let event_id = if event.instant {
+            WORLD_EVENT_INSTANT_SPLASH_POTION
+        } else {
+            WORLD_EVENT_SPLASH_POTION
+        };
+        server.broadcast_nearby_with(event.position, |client| {
+            client.send_world_event(event_id, block, event.color as i32)
+        });
+    }
+    Ok(())
+}
+
+/// Periodically sends the shrinking radius of area effect clouds.
+fn send_cloud_radii(game: &mut Game, server: &mut Server) -> SysResult {
+    if !game.tick_count.is_multiple_of(CLOUD_RADIUS_UPDATE_INTERVAL) {
+        return Ok(());
+    }
+    for (_, (cloud, &network_id, &position)) in game
+        .ecs
+        .query::<(&AreaEffectCloudState, &NetworkId, &Position)>()
+        .iter()
+    {
+        let metadata = EntityMetadata::new().with(META_INDEX_CLOUD_RADIUS, cloud.radius);
+        server.broadcast_nearby_with(position, |client| {
+            client.send_entity_metadata(network_id, metadata.clone())
+        });
+    }
+    Ok(())
+}
@@ -6,7 +6,7 @@ use base::{
     EntityMetadata, Position,
 };
 use common::Game;
-use ecs::{SysResult, SystemExecutor};
+use ecs::{SysResult, SystemAccess, SystemContext, SystemExecutor};
 use quill_common::{
     components::{OnGround, Sprinting},
     events::{SneakEvent, SprintEvent},
@@ -21,17 +21,42 @@ mod spawn_packet;
 
 pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
     spawn_packet::register(game, systems);
+    // These only read the server, so they run in parallel.
     systems
-        .group::<Server>()
-        .add_system(send_entity_movement)
-        .add_system(send_entity_sneak_metadata)
-        .add_system(send_entity_sprint_metadata);
+        .add_parallel_system(
+            SystemAccess::new()
+                .reads::<Position>()
+                .reads::<OnGround>()
+                .reads::<NetworkId>()
+                .writes::<PreviousPosition>()
+                .writes::<PreviousOnGround>()
+                .reads_resource::<Server>(),
+            send_entity_movement,
+        )
+        .add_parallel_system(
+            SystemAccess::new()
+                .reads::<Position>()
+                .reads::<SneakEvent>()
+                .reads::<Sprinting>()
+                .reads::<NetworkId>()
+                .reads_resource::<Server>(),
+            send_entity_sneak_metadata,
+        )
+        .add_parallel_system(
+            SystemAccess::new()
+                .reads::<Position>()
+                .reads::<SprintEvent>()
+                .reads::<NetworkId>()
+                .reads_resource::<Server>(),
+            send_entity_sprint_metadata,
+        );
 }
 
 /// Sends entity movement packets.
-fn send_entity_movement(game: &mut Game, server: &mut Server) -> SysResult {
-    for (_, (&position, prev_position, &on_ground, &network_id, prev_on_ground)) in game
-        .ecs
+fn send_entity_movement(context: &mut SystemContext) -> SysResult {
+    let server = context.resource::<Server>()?;
+    for (_, (&position, prev_position, &on_ground, &network_id, prev_on_ground)) in context
+        .ecs()
         .query::<(
             &Position,
             &mut PreviousPosition,
@@ -61,9 +86,10 @@ fn send_entity_movement(game: &mut Game, server: &mut Server) -> SysResult {
 }
 
 /// Sends [SendEntityMetadata](protocol::packets::server::play::SendEntityMetadata) packet for when an entity is sneaking.
-fn send_entity_sneak_metadata(game: &mut Game, server: &mut Server) -> SysResult {
-    for (_, (&position, &SneakEvent { is_sneaking }, is_sprinting, &network_id)) in game
-        .ecs
+fn send_entity_sneak_metadata(context: &mut SystemContext) -> SysResult {
+    let server = context.resource::<Server>()?;
+    for (_, (&position, &SneakEvent { is_sneaking }, is_sprinting, &network_id)) in context
+        .ecs()
         .query::<(&Position, &SneakEvent, &Sprinting, &NetworkId)>()
         .iter()
     {
@@ -89,9 +115,10 @@ fn send_entity_sneak_metadata(game: &mut Game, server: &mut Server) -> SysResult
 }
 
 /// Sends [SendEntityMetadata](protocol::packets::server::play::SendEntityMetadata) packet for when an entity is sprinting.
-fn send_entity_sprint_metadata(game: &mut Game, server: &mut Server) -> SysResult {
-    for (_, (&position, &SprintEvent { is_sprinting }, &network_id)) in game
-        .ecs
+fn send_entity_sprint_metadata(context: &mut SystemContext) -> SysResult {
+    let server = context.resource::<Server>()?;
+    for (_, (&position, &SprintEvent { is_sprinting }, &network_id)) in context
+        .ecs()
         .query::<(&Position, &SprintEvent, &NetworkId)>()
         .iter()
     {
@@ -0,0 +1,62 @@
+//! Sends experience updates to players.
+
+use base::Position;
+use common::{events::ExperienceOrbPickupEvent, experience::ExperienceOrbState, Game};
+use ecs::{SysResult, SystemExecutor};
+use quill_common::components::{ExperienceLevel, ExperienceProgress, TotalExperience};
+
+use crate::{entities::PreviousExperience, ClientId, NetworkId, Server};
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .group::<Server>()
+        .add_system(send_orb_pickups)
+        .add_system(send_experience_updates);
+}
+
+/// Sends the Set Experience packet when a player's
+/// experience progress, level or total changes.
+fn send_experience_updates(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (&client_id, progress, level, total, previous)) in game
+        .ecs
+        .query::<(
+            &ClientId,
+            &ExperienceProgress,
+            &ExperienceLevel,
+            &TotalExperience,
+            &mut PreviousExperience,
+        )>()
+        .iter()
+    {
+        let current = Some((progress.0, level.0, total.0));
+        if previous.0 == current {
+            continue;
+        }
+        previous.0 = current;
+
+        if let Some(client) = server.clients.get(client_id) {
+            client.send_experience(progress.0, level.0, total.0);
+        }
+    }
+    Ok(())
+}
+
+/// Plays the pickup animation for collected experience orbs.
+fn send_orb_pickups(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (event, orb, &network_id, position)) in game
+        .ecs
+        .query::<(
+            &ExperienceOrbPickupEvent,
+            &ExperienceOrbState,
+            &NetworkId,
+            &Position,
+        )>()
+        .iter()
+    {
+        let collector = *game.ecs.get::<NetworkId>(event.player)?;
+        server.broadcast_nearby_with(*position, |client| {
+            client.send_collect_item(network_id, collector, orb.value)
+        });
+    }
+    Ok(())
+}
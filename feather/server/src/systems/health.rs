@@ -0,0 +1,60 @@
+//! Sends health, food and eating updates to players.
+
+use common::{events::FoodEatenEvent, Game, Window};
+use ecs::{SysResult, SystemExecutor};
+use quill_common::components::{FoodLevel, Health, Saturation};
+
+use crate::{entities::PreviousHealth, ClientId, NetworkId, Server};
+
+/// Entity status which finishes the eating animation.
+const STATUS_FINISH_EATING: i8 = 9;
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .group::<Server>()
+        .add_system(send_eating_finished)
+        .add_system(send_health_updates);
+}
+
+/// Sends the Update Health packet when a player's
+/// health, food level or saturation changes.
+fn send_health_updates(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (&client_id, health, food_level, saturation, previous)) in game
+        .ecs
+        .query::<(
+            &ClientId,
+            &Health,
+            &FoodLevel,
+            &Saturation,
+            &mut PreviousHealth,
+        )>()
+        .iter()
+    {
+        let current = Some((health.0, food_level.0, saturation.0));
+        if previous.0 == current {
+            continue;
+        }
+        previous.0 = current;
+
+        if let Some(client) = server.clients.get(client_id) {
+            client.send_health(health.0, food_level.0, saturation.0);
+        }
+    }
+    Ok(())
+}
+
+/// Updates the slot a player ate from and
+/// ends their eating animation.
+fn send_eating_finished(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (event, &client_id, &network_id, window)) in game
+        .ecs
+        .query::<(&FoodEatenEvent, &ClientId, &NetworkId, &Window)>()
+        .iter()
+    {
+        if let Some(client) = server.clients.get(client_id) {
+            client.set_slot(event.slot as i16, &*window.item(event.slot)?);
+            client.send_entity_status(network_id, STATUS_FINISH_EATING);
+        }
+    }
+    Ok(())
+}
@@ -7,20 +7,27 @@ use base::{Gamemode, Inventory, ItemStack, Position, Text};
 use common::{
     block_break::BlockBreaker,
     chat::{ChatKind, ChatPreference},
+    commands::PermissionLevel,
+    enchanting::EnchantmentSeed,
     entities::player::HotbarSlot,
     view::View,
     window::BackingWindow,
+    world_border::WorldBorder,
     ChatBox, Game, Window,
 };
 use ecs::{SysResult, SystemExecutor};
 use quill_common::components::{
-    CanBuild, CanCreativeFly, CreativeFlying, CreativeFlyingSpeed, Health, Instabreak,
-    Invulnerable, PreviousGamemode, WalkSpeed,
+    CanBuild, CanCreativeFly, CreativeFlying, CreativeFlyingSpeed, Exhaustion, ExperienceLevel,
+    ExperienceProgress, FoodLevel, Health, Instabreak, Invulnerable, PreviousGamemode, Saturation,
+    TotalExperience, WalkSpeed,
 };
 use quill_common::events::GamemodeEvent;
 use quill_common::{components::Name, entity_init::EntityInit};
 
-use crate::{ClientId, NetworkId, Server};
+use crate::{
+    entities::{PreviousExperience, PreviousHealth},
+    ClientId, NetworkId, Server,
+};
 
 pub fn register(systems: &mut SystemExecutor<Game>) {
     systems.group::<Server>().add_system(poll_new_players);
@@ -67,6 +74,8 @@ fn accept_new_player(game: &mut Game, server: &mut Server, client_id: ClientId)
 
     client.send_join_game(gamemode, previous_gamemode);
     client.send_brand();
+    client.send_server_difficulty(game.difficulty);
+    client.send_world_border(&*game.resources.get::<WorldBorder>()?);
 
     // Abilities
     let abilities = player_abilities_or_default(
@@ -75,6 +84,9 @@ fn accept_new_player(game: &mut Game, server: &mut Server, client_id: ClientId)
     );
     client.send_abilities(&abilities);
 
+    let permission_level = server.access.op(client.uuid()).map_or(0, |op| op.level);
+    client.send_permission_level(permission_level);
+
     let hotbar_slot = player_data
         .as_ref()
         .map(|data| HotbarSlot::new(data.held_item as usize))
@@ -118,6 +130,7 @@ fn accept_new_player(game: &mut Game, server: &mut Server, client_id: ClientId)
         .add(client.uuid())
         .add(client.profile().to_vec())
         .add(ChatBox::new(ChatPreference::All))
+        .add(PermissionLevel(permission_level))
         .add(inventory)
         .add(window)
         .add(hotbar_slot)
@@ -127,6 +140,8 @@ fn accept_new_player(game: &mut Game, server: &mut Server, client_id: ClientId)
                 .map(|data| data.animal.health)
                 .unwrap_or(20.0),
         ))
+        .add(PreviousHealth::default())
+        .add(PreviousExperience::default())
         .add(abilities.walk_speed)
         .add(abilities.fly_speed)
         .add(abilities.is_flying)
@@ -135,6 +150,18 @@ fn accept_new_player(game: &mut Game, server: &mut Server, client_id: ClientId)
         .add(abilities.instabreak)
         .add(abilities.invulnerable);
 
+    if let Ok(data) = player_data.as_ref() {
+        builder
+            .add(FoodLevel(data.food_level.max(0) as u32))
+            .add(Saturation(data.food_saturation_level))
+            .add(Exhaustion(data.food_exhaustion_level))
+            .add(ExperienceLevel(data.xp_level.max(0) as u32))
+            .add(ExperienceProgress(data.xp_progress))
+            .add(TotalExperience(data.xp_total.max(0) as u32))
+            .add(EnchantmentSeed(data.xp_seed))
+            .add(data.animal.read_active_effects());
+    }
+
     builder.add(GamemodeEvent(gamemode));
 
     game.spawn_entity(builder);
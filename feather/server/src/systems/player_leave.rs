@@ -1,16 +1,19 @@
 use num_traits::cast::ToPrimitive;
 
-use base::anvil::entity::{AnimalData, BaseEntityData};
+use base::anvil::entity::{AnimalData, BaseEntityData, EffectNbt};
 use base::anvil::player::{InventorySlot, PlayerAbilities, PlayerData};
 use base::{Gamemode, Inventory, Position, Text};
 use common::entities::player::HotbarSlot;
-use common::{chat::ChatKind, Game};
-use ecs::{SysResult, SystemExecutor};
+use common::{chat::ChatKind, enchanting::EnchantmentSeed, window::close_container_window, Game};
+use ecs::{Entity, SysResult, SystemExecutor};
 use quill_common::components::{
-    CanBuild, CanCreativeFly, CreativeFlying, CreativeFlyingSpeed, Health, Instabreak,
-    Invulnerable, Name, PreviousGamemode, WalkSpeed,
+    ActiveEffects, CanBuild, CanCreativeFly, CreativeFlying, CreativeFlyingSpeed, Exhaustion,
+    ExperienceLevel, ExperienceProgress, FoodLevel, Health, Instabreak, Invulnerable, Name,
+    PreviousGamemode, Saturation, TotalExperience, WalkSpeed,
 };
 
+use uuid::Uuid;
+
 use crate::{ClientId, Server};
 
 pub fn register(systems: &mut SystemExecutor<Game>) {
@@ -19,98 +22,89 @@ pub fn register(systems: &mut SystemExecutor<Game>) {
         .add_system(remove_disconnected_clients);
 }
 
-fn remove_disconnected_clients(game: &mut Game, server: &mut Server) -> SysResult {
-    let mut entities_to_remove = Vec::new();
-    for (
-        player,
-        (
-            &client_id,
-            name,
-            position,
-            gamemode,
-            previous_gamemode,
-            health,
-            walk_speed,
-            fly_speed,
-            can_fly,
-            is_flying,
-            can_build,
-            instabreak,
-            invulnerable,
-            hotbar_slot,
-            inventory,
-        ),
-    ) in game
+pub(crate) fn remove_disconnected_clients(game: &mut Game, server: &mut Server) -> SysResult {
+    let disconnected: Vec<(Entity, ClientId)> = game
         .ecs
-        .query::<(
-            &ClientId,
-            &Name,
-            &Position,
-            &Gamemode,
-            &PreviousGamemode,
-            &Health,
-            &WalkSpeed,
-            &CreativeFlyingSpeed,
-            &CanCreativeFly,
-            &CreativeFlying,
-            &CanBuild,
-            &Instabreak,
-            &Invulnerable,
-            &HotbarSlot,
-            &Inventory,
-        )>()
+        .query::<&ClientId>()
         .iter()
-    {
-        let client = server.clients.get(client_id).unwrap();
-        if client.is_disconnected() {
-            entities_to_remove.push(player);
-            broadcast_player_leave(game, name);
-            game.world
-                .save_player_data(
-                    client.uuid(),
-                    &create_player_data(
-                        *position,
-                        *gamemode,
-                        *previous_gamemode,
-                        *health,
-                        PlayerAbilities {
-                            walk_speed: *walk_speed,
-                            fly_speed: *fly_speed,
-                            may_fly: *can_fly,
-                            is_flying: *is_flying,
-                            may_build: *can_build,
-                            instabreak: *instabreak,
-                            invulnerable: *invulnerable,
-                        },
-                        *hotbar_slot,
-                        inventory,
-                    ),
-                )
-                .unwrap_or_else(|e| panic!("Couldn't save data for {}: {}", client.username(), e));
-            server.remove_client(client_id);
-        }
+        .filter(|(_, &client_id)| server.clients.get(client_id).unwrap().is_disconnected())
+        .map(|(player, &client_id)| (player, client_id))
+        .collect();
+
+    // Return items left in open windows to the inventory so they are saved.
+    for &(player, _) in &disconnected {
+        close_container_window(game, player)?;
     }
 
-    for player in entities_to_remove {
+    for (player, client_id) in disconnected {
+        let client = server.clients.get(client_id).unwrap();
+        broadcast_player_leave(game, &*game.ecs.get::<Name>(player)?);
+        save_player_data(game, player, client.uuid())
+            .unwrap_or_else(|e| panic!("Couldn't save data for {}: {}", client.username(), e));
+        server.remove_client(client_id);
         game.remove_entity(player)?;
     }
 
     Ok(())
 }
 
+/// Saves the `PlayerData` of the given player to the world directory.
+pub(crate) fn save_player_data(game: &Game, player: Entity, uuid: Uuid) -> anyhow::Result<()> {
+    let ecs = &game.ecs;
+    let data = create_player_data(
+        *ecs.get::<Position>(player)?,
+        *ecs.get::<Gamemode>(player)?,
+        *ecs.get::<PreviousGamemode>(player)?,
+        *ecs.get::<Health>(player)?,
+        &*ecs.get::<ActiveEffects>(player)?,
+        PlayerAbilities {
+            walk_speed: *ecs.get::<WalkSpeed>(player)?,
+            fly_speed: *ecs.get::<CreativeFlyingSpeed>(player)?,
+            may_fly: *ecs.get::<CanCreativeFly>(player)?,
+            is_flying: *ecs.get::<CreativeFlying>(player)?,
+            may_build: *ecs.get::<CanBuild>(player)?,
+            instabreak: *ecs.get::<Instabreak>(player)?,
+            invulnerable: *ecs.get::<Invulnerable>(player)?,
+        },
+        *ecs.get::<HotbarSlot>(player)?,
+        &*ecs.get::<Inventory>(player)?,
+        (
+            *ecs.get::<FoodLevel>(player)?,
+            *ecs.get::<Saturation>(player)?,
+            *ecs.get::<Exhaustion>(player)?,
+        ),
+        (
+            *ecs.get::<ExperienceLevel>(player)?,
+            *ecs.get::<ExperienceProgress>(player)?,
+            *ecs.get::<TotalExperience>(player)?,
+            *ecs.get::<EnchantmentSeed>(player)?,
+        ),
+    );
+    game.world.save_player_data(uuid, &data)
+}
+
 fn broadcast_player_leave(game: &Game, username: &Name) {
     let message = Text::translate_with("multiplayer.player.left", vec![username.to_string()]);
     game.broadcast_chat(ChatKind::System, message);
 }
 
+#[allow(clippy::too_many_arguments)]
 fn create_player_data(
     position: Position,
     gamemode: Gamemode,
     previous_gamemode: PreviousGamemode,
     health: Health,
+    active_effects: &ActiveEffects,
     abilities: PlayerAbilities,
     hotbar_slot: HotbarSlot,
     inventory: &Inventory,
+    (food_level, saturation, exhaustion): (FoodLevel, Saturation, Exhaustion),
+    (xp_level, xp_progress, xp_total, xp_seed): (
+        ExperienceLevel,
+        ExperienceProgress,
+        TotalExperience,
+        EnchantmentSeed,
+    ),
 ) -> PlayerData {
     PlayerData {
         animal: AnimalData {
@@ -120,6 +114,7 @@ fn create_player_data(
                 velocity: [0.0, 0.0, 0.0].into(),
             },
             health: *health,
+            active_effects: EffectNbt::from_active_effects(active_effects),
         },
         gamemode: gamemode.to_i32().unwrap(),
         previous_gamemode: previous_gamemode.id() as i32,
@@ -149,5 +144,12 @@ fn create_player_data(
             .collect(),
         held_item: hotbar_slot.get() as i32,
         abilities,
+        food_level: food_level.0 as i32,
+        food_saturation_level: saturation.0,
+        food_exhaustion_level: exhaustion.0,
+        xp_level: xp_level.0 as i32,
+        xp_progress: xp_progress.0,
+        xp_total: xp_total.0 as i32,
+        xp_seed: xp_seed.0,
     }
 }
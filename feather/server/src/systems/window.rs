@@ -0,0 +1,124 @@
+//! Sends window updates to players: opening windows,
+//! refreshing their contents, enchanting table offers and beacon effects.
+
+use base::StatusEffect;
+use common::{
+    beacon::OpenBeacon,
+    enchanting::{EnchantmentSeed, OpenEnchantingTable},
+    events::{BeaconUpdateEvent, EnchantmentOffersUpdateEvent, WindowOpenEvent, WindowUpdateEvent},
+    Game, Window,
+};
+use ecs::{SysResult, SystemExecutor};
+
+use crate::{ClientId, Server};
+
+/// Enchanting table window property holding the enchantment seed.
+const PROPERTY_ENCHANTMENT_SEED: i16 = 3;
+
+/// First of the window properties holding the hinted enchantment IDs.
+const PROPERTY_ENCHANTMENT_HINT_ID: i16 = 4;
+
+/// First of the window properties holding the hinted enchantment levels.
+const PROPERTY_ENCHANTMENT_HINT_LEVEL: i16 = 7;
+
+/// Beacon window property holding the number of pyramid levels.
+const PROPERTY_BEACON_LEVELS: i16 = 0;
+
+/// Beacon window property holding the primary effect ID.
+const PROPERTY_BEACON_PRIMARY: i16 = 1;
+
+/// Beacon window property holding the secondary effect ID.
+const PROPERTY_BEACON_SECONDARY: i16 = 2;
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .group::<Server>()
+        .add_system(send_opened_windows)
+        .add_system(send_window_updates)
+        .add_system(send_enchantment_offers)
+        .add_system(send_beacon_properties);
+}
+
+fn send_opened_windows(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (_, &client_id, window)) in game
+        .ecs
+        .query::<(&WindowOpenEvent, &ClientId, &Window)>()
+        .iter()
+    {
+        if let Some(client) = server.clients.get(client_id) {
+            client.open_window(window);
+        }
+    }
+    Ok(())
+}
+
+fn send_window_updates(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (_, &client_id, window)) in game
+        .ecs
+        .query::<(&WindowUpdateEvent, &ClientId, &Window)>()
+        .iter()
+    {
+        if let Some(client) = server.clients.get(client_id) {
+            client.send_window_items(window);
+            client.set_cursor_slot(window.cursor_item());
+        }
+    }
+    Ok(())
+}
+
+fn send_enchantment_offers(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (_, &client_id, table, seed)) in game
+        .ecs
+        .query::<(
+            &EnchantmentOffersUpdateEvent,
+            &ClientId,
+            &OpenEnchantingTable,
+            &EnchantmentSeed,
+        )>()
+        .iter()
+    {
+        let client = match server.clients.get(client_id) {
+            Some(client) => client,
+            None => continue,
+        };
+
+        for (button, offer) in (0i16..).zip(table.offers.iter()) {
+            let (cost, id, level) = match offer {
+                Some(offer) => (
+                    offer.cost as i16,
+                    offer.hint.kind().id() as i16,
+                    offer.hint.level() as i16,
+                ),
+                None => (0, -1, -1),
+            };
+            client.send_window_property(button, cost);
+            client.send_window_property(PROPERTY_ENCHANTMENT_HINT_ID + button, id);
+            client.send_window_property(PROPERTY_ENCHANTMENT_HINT_LEVEL + button, level);
+        }
+        // The client only uses the seed to generate the glyphs shown on buttons.
+        client.send_window_property(PROPERTY_ENCHANTMENT_SEED, (seed.0 & -16) as i16);
+    }
+    Ok(())
+}
+
+fn send_beacon_properties(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (_, &client_id, beacon)) in game
+        .ecs
+        .query::<(&BeaconUpdateEvent, &ClientId, &OpenBeacon)>()
+        .iter()
+    {
+        let client = match server.clients.get(client_id) {
+            Some(client) => client,
+            None => continue,
+        };
+
+        let effect_id = |effect: Option<StatusEffect>| effect.map_or(-1, |e| i16::from(e.id()));
+        client.send_window_property(PROPERTY_BEACON_LEVELS, beacon.levels as i16);
+        client.send_window_property(PROPERTY_BEACON_PRIMARY, effect_id(beacon.effects.primary));
+        client.send_window_property(
+            PROPERTY_BEACON_SECONDARY,
+            effect_id(beacon.effects.secondary),
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,20 @@
+//! Sends changes to the world border to clients.
+
+use common::{world_border::WorldBorder, Game};
+use ecs::{SysResult, SystemExecutor};
+
+use crate::Server;
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .group::<Server>()
+        .add_system(send_world_border_updates);
+}
+
+fn send_world_border_updates(game: &mut Game, server: &mut Server) -> SysResult {
+    let mut border = game.resources.get_mut::<WorldBorder>()?;
+    for update in border.take_updates() {
+        server.broadcast_with(|client| client.send_world_border_update(&border, update));
+    }
+    Ok(())
+}
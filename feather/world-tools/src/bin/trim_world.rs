@@ -0,0 +1,191 @@
+//! Compacts the region files of a world, removing the free space
+//! left behind by chunks which were rewritten, and optionally trims
+//! chunks which players barely visited or which lie outside a radius.
+//!
+//! Usage: `trim-world <world directory> [--min-inhabited-time <ticks>] [--radius <chunks>]`
+//!
+//! The world is rewritten in place, so make a backup first and
+//! make sure no server is running on it.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use argh::FromArgs;
+use base::{
+    anvil::{
+        data_fixer::Compound,
+        region::{self, RegionHandle, RegionPosition},
+    },
+    ChunkPosition,
+};
+use feather_world_tools::{chunks_in_region, region_file_path, region_positions, DIMENSIONS};
+use nbt::Value;
+
+#[derive(FromArgs)]
+/// Compacts the region files of a world and trims unwanted chunks.
+struct Args {
+    /// the world directory
+    #[argh(positional)]
+    world: PathBuf,
+    /// delete chunks in which players spent fewer than this many ticks
+    #[argh(option)]
+    min_inhabited_time: Option<i64>,
+    /// delete chunks outside the square of this radius, in chunks, around the center
+    #[argh(option)]
+    radius: Option<i32>,
+    /// chunk X coordinate of the center used by --radius
+    #[argh(option, default = "0")]
+    center_x: i32,
+    /// chunk Z coordinate of the center used by --radius
+    #[argh(option, default = "0")]
+    center_z: i32,
+    /// rebuild the headers of region files with overlapping or out-of-range sectors
+    /// instead of skipping them
+    #[argh(switch)]
+    repair: bool,
+}
+
+#[derive(Debug, Default)]
+struct Stats {
+    kept: usize,
+    trimmed: usize,
+    bytes_before: u64,
+    bytes_after: u64,
+    failed_regions: usize,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args: Args = argh::from_env();
+
+    let mut total = Stats::default();
+    for dimension in DIMENSIONS {
+        let dir = args.world.join(dimension);
+        for position in region_positions(&dir)? {
+            let stats = match trim_region(&args, &dir, position) {
+                Ok(stats) => stats,
+                Err(e) => {
+                    eprintln!(
+                        "Failed to trim region {}: {}",
+                        region_file_path(&dir, position).display(),
+                        e
+                    );
+                    total.failed_regions += 1;
+                    continue;
+                }
+            };
+
+            println!(
+                "{}: kept {} chunks, trimmed {}, {} KiB -> {} KiB",
+                region_file_path(&dir, position).display(),
+                stats.kept,
+                stats.trimmed,
+                stats.bytes_before / 1024,
+                stats.bytes_after / 1024
+            );
+            total.kept += stats.kept;
+            total.trimmed += stats.trimmed;
+            total.bytes_before += stats.bytes_before;
+            total.bytes_after += stats.bytes_after;
+        }
+    }
+
+    println!(
+        "Kept {} chunks and trimmed {}; regions shrank from {} KiB to {} KiB.",
+        total.kept,
+        total.trimmed,
+        total.bytes_before / 1024,
+        total.bytes_after / 1024
+    );
+    if total.failed_regions > 0 {
+        eprintln!("{} regions could not be trimmed.", total.failed_regions);
+        process::exit(1);
+    }
+    Ok(())
+}
+
+fn trim_region(args: &Args, dir: &Path, (x, z): (i32, i32)) -> anyhow::Result<Stats> {
+    let path = region_file_path(dir, (x, z));
+    let mut stats = Stats {
+        bytes_before: fs::metadata(&path)?.len(),
+        ..Default::default()
+    };
+
+    let mut region = region::load_region(dir, RegionPosition::new(x, z))?;
+
+    let problems = region.verify()?;
+    if !problems.is_empty() {
+        for problem in &problems {
+            eprintln!("{}: {:?}", path.display(), problem);
+        }
+        if !args.repair {
+            anyhow::bail!("the region file is damaged; rerun with --repair to rebuild its header");
+        }
+        let summary = region.repair()?;
+        println!(
+            "{}: repaired header, kept {} chunks, dropped {} entries and recovered {} chunks",
+            path.display(),
+            summary.kept,
+            summary.dropped,
+            summary.recovered
+        );
+    }
+
+    for pos in chunks_in_region((x, z)) {
+        if !region.check_chunk_existence(pos) {
+            continue;
+        }
+
+        if should_trim(args, &mut region, pos) {
+            region.delete_chunk(pos);
+            stats.trimmed += 1;
+        } else {
+            stats.kept += 1;
+        }
+    }
+
+    region.compact()?;
+    drop(region);
+
+    if stats.kept == 0 {
+        fs::remove_file(&path)?;
+    } else {
+        stats.bytes_after = fs::metadata(&path)?.len();
+    }
+
+    Ok(stats)
+}
+
+/// Determines whether the chunk at the given position should be deleted.
+fn should_trim(args: &Args, region: &mut RegionHandle, pos: ChunkPosition) -> bool {
+    if let Some(radius) = args.radius {
+        if (pos.x - args.center_x).abs() > radius || (pos.z - args.center_z).abs() > radius {
+            return true;
+        }
+    }
+
+    if let Some(min_inhabited_time) = args.min_inhabited_time {
+        match region.load_chunk_nbt(pos) {
+            Ok(root) => return inhabited_time(&root) < min_inhabited_time,
+            Err(e) => eprintln!("Failed to read chunk at {:?}, keeping it: {}", pos, e),
+        }
+    }
+
+    false
+}
+
+/// Reads the number of ticks players spent in a chunk.
+/// Chunks without the tag count as never visited.
+fn inhabited_time(root: &Compound) -> i64 {
+    let level = match root.get("Level") {
+        Some(Value::Compound(level)) => level,
+        _ => return 0,
+    };
+    match level.get("InhabitedTime") {
+        Some(Value::Long(ticks)) => *ticks,
+        Some(Value::Int(ticks)) => i64::from(*ticks),
+        _ => 0,
+    }
+}
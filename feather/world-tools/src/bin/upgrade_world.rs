@@ -0,0 +1,73 @@
+//! Upgrades the chunks of a world saved by an older version
+//! of Minecraft to the data version supported by Feather.
+//!
+//! Usage: `upgrade-world <world directory>`
+//!
+//! The world is rewritten in place, so make a backup first.
+
+use std::{
+    path::{Path, PathBuf},
+    process,
+};
+
+use argh::FromArgs;
+use base::anvil::region::{self, RegionPosition, DATA_VERSION};
+use feather_world_tools::{chunks_in_region, region_positions, DIMENSIONS};
+
+#[derive(FromArgs)]
+/// Upgrades the chunks of a world to the data version supported by Feather.
+struct Args {
+    /// the world directory
+    #[argh(positional)]
+    world: PathBuf,
+}
+
+#[derive(Debug, Default)]
+struct Stats {
+    upgraded: usize,
+    current: usize,
+    failed: usize,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args: Args = argh::from_env();
+
+    let mut stats = Stats::default();
+    for dimension in DIMENSIONS {
+        let dir = args.world.join(dimension);
+        for (x, z) in region_positions(&dir)? {
+            upgrade_region(&dir, x, z, &mut stats)?;
+        }
+    }
+
+    println!(
+        "Upgraded {} chunks to data version {}; {} were already up to date and {} failed.",
+        stats.upgraded, DATA_VERSION, stats.current, stats.failed
+    );
+    if stats.failed > 0 {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+fn upgrade_region(dir: &Path, x: i32, z: i32, stats: &mut Stats) -> anyhow::Result<()> {
+    println!("Upgrading region {}/r.{}.{}.mca", dir.display(), x, z);
+    let mut region = region::load_region(dir, RegionPosition::new(x, z))?;
+
+    for pos in chunks_in_region((x, z)) {
+        if !region.check_chunk_existence(pos) {
+            continue;
+        }
+
+        match region.upgrade_chunk(pos) {
+            Ok(DATA_VERSION) => stats.current += 1,
+            Ok(_) => stats.upgraded += 1,
+            Err(e) => {
+                eprintln!("Failed to upgrade chunk at {:?}: {}", pos, e);
+                stats.failed += 1;
+            }
+        }
+    }
+    region.sync()?;
+    Ok(())
+}
@@ -0,0 +1,57 @@
+//! Helpers shared by the offline world maintenance tools.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use base::ChunkPosition;
+
+/// Directories of the dimensions in a world,
+/// relative to the world directory.
+pub const DIMENSIONS: &[&str] = &["", "DIM-1", "DIM1"];
+
+/// The length and width of a region, in chunks.
+pub const REGION_SIZE: i32 = 32;
+
+/// Finds the regions in a dimension from the names of its region files.
+pub fn region_positions(dir: &Path) -> anyhow::Result<Vec<(i32, i32)>> {
+    let dir = dir.join("region");
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut positions = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let name = entry?.file_name();
+        if let Some(position) = name.to_str().and_then(parse_region_file_name) {
+            positions.push(position);
+        }
+    }
+    Ok(positions)
+}
+
+/// Parses a region file name of the form `r.x.z.mca`.
+fn parse_region_file_name(name: &str) -> Option<(i32, i32)> {
+    let mut parts = name.strip_prefix("r.")?.strip_suffix(".mca")?.split('.');
+    let x = parts.next()?.parse().ok()?;
+    let z = parts.next()?.parse().ok()?;
+    match parts.next() {
+        Some(_) => None,
+        None => Some((x, z)),
+    }
+}
+
+/// Returns the path of the region file at the given position.
+pub fn region_file_path(dir: &Path, (x, z): (i32, i32)) -> PathBuf {
+    dir.join("region").join(format!("r.{}.{}.mca", x, z))
+}
+
+/// Iterates over the global positions of the chunks in a region.
+pub fn chunks_in_region((x, z): (i32, i32)) -> impl Iterator<Item = ChunkPosition> {
+    (0..REGION_SIZE).flat_map(move |local_x| {
+        (0..REGION_SIZE).map(move |local_z| {
+            ChunkPosition::new(x * REGION_SIZE + local_x, z * REGION_SIZE + local_z)
+        })
+    })
+}
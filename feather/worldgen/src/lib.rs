@@ -39,6 +39,13 @@ const OCEAN_DEPTH: usize = 30;
 pub trait WorldGenerator: Send + Sync {
     /// Generates the chunk at the given position.
     fn generate_chunk(&self, position: ChunkPosition) -> Chunk;
+
+    /// Runs the stages after terrain generation, such as foliage
+    /// and snow, on a chunk whose terrain was generated before.
+    ///
+    /// Used to finish partially generated chunks
+    /// without discarding their blocks.
+    fn finish_chunk(&self, _chunk: &mut Chunk) {}
 }
 
 pub struct VoidWorldGenerator;
@@ -150,33 +157,35 @@ impl WorldGenerator for ComposableGenerator {
             seed_shuffler.gen(),
         );
 
-        // Calculate top blocks in chunk.
-        // TODO: perhaps this should be moved to `Chunk`?
-        let mut top_blocks = TopBlocks::new();
-        for x in 0..16 {
-            for z in 0..16 {
-                for y in (0..256).rev() {
-                    if chunk.block_at(x, y, z).unwrap() != BlockId::air() {
-                        top_blocks.set_top_block_at(x, z, y);
-                        break;
-                    }
-                }
-            }
+        let top_blocks = TopBlocks::calculate(&chunk);
+        chunk.recalculate_heightmaps();
+
+        self.run_finishers(&mut chunk, &top_blocks, &mut seed_shuffler);
+
+        chunk
+    }
+
+    fn finish_chunk(&self, chunk: &mut Chunk) {
+        // Skip the seeds of the biome, density and composition
+        // stages, so the finishers get the same seeds as when
+        // generating the chunk in one go.
+        let mut seed_shuffler = XorShiftRng::seed_from_u64(self.seed);
+        for _ in 0..3 {
+            seed_shuffler.gen::<u64>();
         }
 
+        let top_blocks = TopBlocks::calculate(chunk);
         chunk.recalculate_heightmaps();
+        self.run_finishers(chunk, &top_blocks, &mut seed_shuffler);
+    }
+}
 
-        // Finishers.
+impl ComposableGenerator {
+    fn run_finishers(&self, chunk: &mut Chunk, top_blocks: &TopBlocks, seeds: &mut XorShiftRng) {
+        let biomes = *chunk.biomes();
         for finisher in &self.finishers {
-            finisher.generate_for_chunk(
-                &mut chunk,
-                &biomes.biome_stores[4],
-                &top_blocks,
-                seed_shuffler.gen(),
-            );
+            finisher.generate_for_chunk(chunk, &biomes, top_blocks, seeds.gen());
         }
-
-        chunk
     }
 }
 
@@ -261,6 +270,22 @@ impl TopBlocks {
     pub fn set_top_block_at(&mut self, x: usize, z: usize, top: usize) {
         self.top_blocks[x + (z << 4)] = top as u8;
     }
+
+    /// Finds the highest non-air block in each column of `chunk`.
+    pub fn calculate(chunk: &Chunk) -> Self {
+        let mut top_blocks = Self::new();
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in (0..256).rev() {
+                    if chunk.block_at(x, y, z).unwrap() != BlockId::air() {
+                        top_blocks.set_top_block_at(x, z, y);
+                        break;
+                    }
+                }
+            }
+        }
+        top_blocks
+    }
 }
 /// Represents the biomes in a 3x3 grid of chunks,
 /// centered on the chunk currently being generated.
@@ -403,6 +428,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn finishing_keeps_blocks() {
+        let gen = ComposableGenerator::default_with_seed(3243);
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..64 {
+                    chunk.set_block_at(x, y, z, BlockId::stone());
+                }
+            }
+        }
+        chunk.set_block_at(3, 64, 4, BlockId::diamond_block());
+
+        gen.finish_chunk(&mut chunk);
+        assert_eq!(chunk.block_at(3, 64, 4), Some(BlockId::diamond_block()));
+        assert_eq!(chunk.block_at(7, 10, 7), Some(BlockId::stone()));
+    }
+
     fn test_chunks_eq(a: &Chunk, b: &Chunk) {
         for x in 0..16 {
             for z in 0..16 {
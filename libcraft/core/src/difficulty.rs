@@ -0,0 +1,44 @@
+use num_derive::{FromPrimitive, ToPrimitive};
+use serde::{Deserialize, Serialize};
+
+/// The difficulty of a world.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    FromPrimitive,
+    ToPrimitive,
+)]
+#[serde(rename_all = "snake_case")]
+#[repr(u8)]
+pub enum Difficulty {
+    Peaceful = 0,
+    #[default]
+    Easy = 1,
+    Normal = 2,
+    Hard = 3,
+}
+
+impl Difficulty {
+    /// Gets a difficulty from its ID.
+    pub fn from_id(id: u8) -> Option<Self> {
+        Some(match id {
+            0 => Difficulty::Peaceful,
+            1 => Difficulty::Easy,
+            2 => Difficulty::Normal,
+            3 => Difficulty::Hard,
+            _ => return None,
+        })
+    }
+
+    /// Gets the ID of this difficulty.
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+}
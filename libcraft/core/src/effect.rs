@@ -0,0 +1,167 @@
+use num_derive::{FromPrimitive, ToPrimitive};
+use serde::{Deserialize, Serialize};
+
+/// A status effect which can be applied to living entities.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    FromPrimitive,
+    ToPrimitive,
+)]
+#[serde(rename_all = "snake_case")]
+#[repr(u8)]
+pub enum StatusEffect {
+    Speed = 1,
+    Slowness = 2,
+    Haste = 3,
+    MiningFatigue = 4,
+    Strength = 5,
+    InstantHealth = 6,
+    InstantDamage = 7,
+    JumpBoost = 8,
+    Nausea = 9,
+    Regeneration = 10,
+    Resistance = 11,
+    FireResistance = 12,
+    WaterBreathing = 13,
+    Invisibility = 14,
+    Blindness = 15,
+    NightVision = 16,
+    Hunger = 17,
+    Weakness = 18,
+    Poison = 19,
+    Wither = 20,
+    HealthBoost = 21,
+    Absorption = 22,
+    Saturation = 23,
+    Glowing = 24,
+    Levitation = 25,
+    Luck = 26,
+    Unluck = 27,
+    SlowFalling = 28,
+    ConduitPower = 29,
+    DolphinsGrace = 30,
+    BadOmen = 31,
+    HeroOfTheVillage = 32,
+}
+
+impl StatusEffect {
+    /// Gets a status effect from its protocol ID.
+    pub fn from_id(id: u8) -> Option<Self> {
+        num_traits::FromPrimitive::from_u8(id)
+    }
+
+    /// Gets the protocol ID of this status effect.
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+
+    /// Gets the name of this effect, e.g. `jump_boost`.
+    pub fn name(self) -> &'static str {
+        match self {
+            StatusEffect::Speed => "speed",
+            StatusEffect::Slowness => "slowness",
+            StatusEffect::Haste => "haste",
+            StatusEffect::MiningFatigue => "mining_fatigue",
+            StatusEffect::Strength => "strength",
+            StatusEffect::InstantHealth => "instant_health",
+            StatusEffect::InstantDamage => "instant_damage",
+            StatusEffect::JumpBoost => "jump_boost",
+            StatusEffect::Nausea => "nausea",
+            StatusEffect::Regeneration => "regeneration",
+            StatusEffect::Resistance => "resistance",
+            StatusEffect::FireResistance => "fire_resistance",
+            StatusEffect::WaterBreathing => "water_breathing",
+            StatusEffect::Invisibility => "invisibility",
+            StatusEffect::Blindness => "blindness",
+            StatusEffect::NightVision => "night_vision",
+            StatusEffect::Hunger => "hunger",
+            StatusEffect::Weakness => "weakness",
+            StatusEffect::Poison => "poison",
+            StatusEffect::Wither => "wither",
+            StatusEffect::HealthBoost => "health_boost",
+            StatusEffect::Absorption => "absorption",
+            StatusEffect::Saturation => "saturation",
+            StatusEffect::Glowing => "glowing",
+            StatusEffect::Levitation => "levitation",
+            StatusEffect::Luck => "luck",
+            StatusEffect::Unluck => "unluck",
+            StatusEffect::SlowFalling => "slow_falling",
+            StatusEffect::ConduitPower => "conduit_power",
+            StatusEffect::DolphinsGrace => "dolphins_grace",
+            StatusEffect::BadOmen => "bad_omen",
+            StatusEffect::HeroOfTheVillage => "hero_of_the_village",
+        }
+    }
+
+    /// Whether this effect is applied once instead of over time.
+    pub fn is_instant(self) -> bool {
+        matches!(
+            self,
+            StatusEffect::InstantHealth | StatusEffect::InstantDamage | StatusEffect::Saturation
+        )
+    }
+
+    /// Whether this effect harms the entity it is applied to.
+    pub fn is_harmful(self) -> bool {
+        matches!(
+            self,
+            StatusEffect::Slowness
+                | StatusEffect::MiningFatigue
+                | StatusEffect::InstantDamage
+                | StatusEffect::Nausea
+                | StatusEffect::Blindness
+                | StatusEffect::Hunger
+                | StatusEffect::Weakness
+                | StatusEffect::Poison
+                | StatusEffect::Wither
+                | StatusEffect::Levitation
+                | StatusEffect::Unluck
+        )
+    }
+
+    /// Gets the RGB color of this effect's particles.
+    pub fn color(self) -> u32 {
+        match self {
+            StatusEffect::Speed => 0x7CAFC6,
+            StatusEffect::Slowness => 0x5A6C81,
+            StatusEffect::Haste => 0xD9C043,
+            StatusEffect::MiningFatigue => 0x4A4217,
+            StatusEffect::Strength => 0x932423,
+            StatusEffect::InstantHealth | StatusEffect::Saturation => 0xF82423,
+            StatusEffect::InstantDamage => 0x430A09,
+            StatusEffect::JumpBoost => 0x22FF4C,
+            StatusEffect::Nausea => 0x551D4A,
+            StatusEffect::Regeneration => 0xCD5CAB,
+            StatusEffect::Resistance => 0x99453A,
+            StatusEffect::FireResistance => 0xE49A3A,
+            StatusEffect::WaterBreathing => 0x2E5299,
+            StatusEffect::Invisibility => 0x7F8392,
+            StatusEffect::Blindness => 0x1F1F23,
+            StatusEffect::NightVision => 0x1F1FA1,
+            StatusEffect::Hunger => 0x587653,
+            StatusEffect::Weakness => 0x484D48,
+            StatusEffect::Poison => 0x4E9331,
+            StatusEffect::Wither => 0x352A27,
+            StatusEffect::HealthBoost => 0xF87D23,
+            StatusEffect::Absorption => 0x2552A5,
+            StatusEffect::Glowing => 0x94A061,
+            StatusEffect::Levitation => 0xCEFFFF,
+            StatusEffect::Luck => 0x339900,
+            StatusEffect::Unluck => 0xC0A44D,
+            StatusEffect::SlowFalling => 0xFFEFD1,
+            StatusEffect::ConduitPower => 0x1DC2D1,
+            StatusEffect::DolphinsGrace => 0x88A3BE,
+            StatusEffect::BadOmen => 0x0B6138,
+            StatusEffect::HeroOfTheVillage => 0x44FF44,
+        }
+    }
+}
@@ -3,42 +3,42 @@
 use serde::{Deserialize, Serialize};
 
 /// All game rules.
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GameRules {
-    announce_advancements: bool,
-    command_block_output: bool,
-    disable_elytra_movement_check: bool,
-    disable_raids: bool,
-    do_daylight_cycle: bool,
-    do_entity_drops: bool,
-    do_fire_tick: bool,
-    do_insomnia: bool,
-    do_immediate_respawn: bool,
-    do_limited_crafting: bool,
-    do_mob_loot: bool,
-    do_mob_spawning: bool,
-    do_patrol_spawning: bool,
-    do_tile_drops: bool,
-    do_trader_spawning: bool,
-    do_weather_cycle: bool,
-    drowning_damage: bool,
-    fall_damage: bool,
-    fire_damage: bool,
-    forgive_dead_players: bool,
-    keep_inventory: bool,
-    log_admin_commands: bool,
-    max_command_chain_length: u32,
-    max_entity_cramming: u32,
-    mob_griefing: bool,
-    natural_regeneration: bool,
-    random_tick_speed: u32,
-    reduced_debug_info: bool,
-    send_command_feedback: bool,
-    show_death_messages: bool,
-    spawn_radius: u32,
-    spectators_generate_chunks: bool,
-    universal_anger: bool,
+    pub announce_advancements: bool,
+    pub command_block_output: bool,
+    pub disable_elytra_movement_check: bool,
+    pub disable_raids: bool,
+    pub do_daylight_cycle: bool,
+    pub do_entity_drops: bool,
+    pub do_fire_tick: bool,
+    pub do_insomnia: bool,
+    pub do_immediate_respawn: bool,
+    pub do_limited_crafting: bool,
+    pub do_mob_loot: bool,
+    pub do_mob_spawning: bool,
+    pub do_patrol_spawning: bool,
+    pub do_tile_drops: bool,
+    pub do_trader_spawning: bool,
+    pub do_weather_cycle: bool,
+    pub drowning_damage: bool,
+    pub fall_damage: bool,
+    pub fire_damage: bool,
+    pub forgive_dead_players: bool,
+    pub keep_inventory: bool,
+    pub log_admin_commands: bool,
+    pub max_command_chain_length: u32,
+    pub max_entity_cramming: u32,
+    pub mob_griefing: bool,
+    pub natural_regeneration: bool,
+    pub random_tick_speed: u32,
+    pub reduced_debug_info: bool,
+    pub send_command_feedback: bool,
+    pub show_death_messages: bool,
+    pub spawn_radius: u32,
+    pub spectators_generate_chunks: bool,
+    pub universal_anger: bool,
 }
 
 impl Default for GameRules {
@@ -3,17 +3,22 @@
 mod biome;
 pub mod block;
 mod consts;
+mod difficulty;
 mod dimension;
+mod effect;
 mod entity;
 mod gamemode;
 mod gamerules;
 mod interaction;
 mod player;
 mod positions;
+mod spawning;
 
 pub use biome::Biome;
 pub use consts::*;
+pub use difficulty::Difficulty;
 pub use dimension::Dimension;
+pub use effect::StatusEffect;
 pub use entity::EntityKind;
 pub use gamemode::Gamemode;
 pub use gamerules::GameRules;
@@ -23,3 +28,4 @@ pub use positions::{
     vec3, Aabb, BlockFace, BlockPosition, ChunkPosition, Mat4f, Position, Vec2d, Vec2f, Vec2i,
     Vec3d, Vec3f, Vec3i, Vec4d, Vec4f, Vec4i,
 };
+pub use spawning::{MobCategory, SpawnEntry};
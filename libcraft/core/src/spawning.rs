@@ -0,0 +1,390 @@
+//! Natural spawning data for biomes.
+//!
+//! Data sourced from vanilla 1.16's `DefaultBiomeFeatures`.
+
+use crate::{Biome, EntityKind};
+
+use EntityKind as E;
+use SpawnGroup as G;
+
+/// A category of naturally spawning mobs.
+///
+/// Each category has its own mob cap and spawn rules.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MobCategory {
+    Monster,
+    Creature,
+    Ambient,
+    WaterCreature,
+}
+
+impl MobCategory {
+    /// All mob categories.
+    pub const ALL: [MobCategory; 4] = [
+        MobCategory::Monster,
+        MobCategory::Creature,
+        MobCategory::Ambient,
+        MobCategory::WaterCreature,
+    ];
+
+    /// The maximum number of mobs of this category
+    /// for a single player's worth of spawnable chunks.
+    pub fn cap(self) -> usize {
+        match self {
+            MobCategory::Monster => 70,
+            MobCategory::Creature => 10,
+            MobCategory::Ambient => 15,
+            MobCategory::WaterCreature => 5,
+        }
+    }
+
+    /// Whether mobs of this category are passive and never despawn.
+    pub fn is_persistent(self) -> bool {
+        self == MobCategory::Creature
+    }
+
+    /// Gets the category of the given entity kind,
+    /// or `None` if it does not spawn naturally.
+    pub fn of(kind: EntityKind) -> Option<Self> {
+        use EntityKind::*;
+        Some(match kind {
+            Spider | CaveSpider | Zombie | ZombieVillager | Husk | Drowned | Skeleton | Stray
+            | Creeper | Slime | Enderman | Witch | ZombifiedPiglin | Ghast | MagmaCube | Piglin
+            | Hoglin | Blaze | WitherSkeleton | Silverfish | Endermite | Phantom | Guardian => {
+                MobCategory::Monster
+            }
+            Sheep | Pig | Chicken | Cow | Mooshroom | Horse | Donkey | Rabbit | Wolf | Fox
+            | Llama | Parrot | Panda | Ocelot | PolarBear | Turtle | Cat | Strider => {
+                MobCategory::Creature
+            }
+            Bat => MobCategory::Ambient,
+            Squid | Dolphin | Cod | Salmon | Pufferfish | TropicalFish => {
+                MobCategory::WaterCreature
+            }
+            _ => return None,
+        })
+    }
+}
+
+/// A weighted entry in a biome's spawn list.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SpawnEntry {
+    pub kind: EntityKind,
+    pub weight: u32,
+    pub min_group_size: u32,
+    pub max_group_size: u32,
+}
+
+const fn entry(kind: EntityKind, weight: u32, min: u32, max: u32) -> SpawnEntry {
+    SpawnEntry {
+        kind,
+        weight,
+        min_group_size: min,
+        max_group_size: max,
+    }
+}
+
+const MONSTERS: &[SpawnEntry] = &[
+    entry(E::Spider, 100, 4, 4),
+    entry(E::Zombie, 95, 4, 4),
+    entry(E::ZombieVillager, 5, 1, 1),
+    entry(E::Skeleton, 100, 4, 4),
+    entry(E::Creeper, 100, 4, 4),
+    entry(E::Slime, 100, 4, 4),
+    entry(E::Enderman, 10, 1, 4),
+    entry(E::Witch, 5, 1, 1),
+];
+const DESERT_MONSTERS: &[SpawnEntry] = &[
+    entry(E::Spider, 100, 4, 4),
+    entry(E::Zombie, 19, 4, 4),
+    entry(E::ZombieVillager, 1, 1, 1),
+    entry(E::Husk, 80, 4, 4),
+    entry(E::Skeleton, 100, 4, 4),
+    entry(E::Creeper, 100, 4, 4),
+    entry(E::Slime, 100, 4, 4),
+    entry(E::Enderman, 10, 1, 4),
+    entry(E::Witch, 5, 1, 1),
+];
+const SNOWY_MONSTERS: &[SpawnEntry] = &[
+    entry(E::Spider, 100, 4, 4),
+    entry(E::Zombie, 95, 4, 4),
+    entry(E::ZombieVillager, 5, 1, 1),
+    entry(E::Skeleton, 20, 4, 4),
+    entry(E::Stray, 80, 4, 4),
+    entry(E::Creeper, 100, 4, 4),
+    entry(E::Slime, 100, 4, 4),
+    entry(E::Enderman, 10, 1, 4),
+    entry(E::Witch, 5, 1, 1),
+];
+const OCEAN_MONSTERS: &[SpawnEntry] = &[
+    entry(E::Spider, 100, 4, 4),
+    entry(E::Zombie, 95, 4, 4),
+    entry(E::Drowned, 5, 1, 1),
+    entry(E::ZombieVillager, 5, 1, 1),
+    entry(E::Skeleton, 100, 4, 4),
+    entry(E::Creeper, 100, 4, 4),
+    entry(E::Slime, 100, 4, 4),
+    entry(E::Enderman, 10, 1, 4),
+    entry(E::Witch, 5, 1, 1),
+];
+const RIVER_MONSTERS: &[SpawnEntry] = &[
+    entry(E::Spider, 100, 4, 4),
+    entry(E::Zombie, 95, 4, 4),
+    entry(E::Drowned, 100, 1, 1),
+    entry(E::ZombieVillager, 5, 1, 1),
+    entry(E::Skeleton, 100, 4, 4),
+    entry(E::Creeper, 100, 4, 4),
+    entry(E::Slime, 100, 4, 4),
+    entry(E::Enderman, 10, 1, 4),
+    entry(E::Witch, 5, 1, 1),
+];
+const NETHER_MONSTERS: &[SpawnEntry] = &[
+    entry(E::Ghast, 50, 4, 4),
+    entry(E::ZombifiedPiglin, 100, 4, 4),
+    entry(E::MagmaCube, 2, 4, 4),
+    entry(E::Enderman, 1, 4, 4),
+    entry(E::Piglin, 15, 4, 4),
+];
+const END_MONSTERS: &[SpawnEntry] = &[entry(E::Enderman, 10, 4, 4)];
+
+const FARM_ANIMALS: &[SpawnEntry] = &[
+    entry(E::Sheep, 12, 4, 4),
+    entry(E::Pig, 10, 4, 4),
+    entry(E::Chicken, 10, 4, 4),
+    entry(E::Cow, 8, 4, 4),
+];
+const PLAINS_ANIMALS: &[SpawnEntry] = &[
+    entry(E::Sheep, 12, 4, 4),
+    entry(E::Pig, 10, 4, 4),
+    entry(E::Chicken, 10, 4, 4),
+    entry(E::Cow, 8, 4, 4),
+    entry(E::Horse, 5, 2, 6),
+    entry(E::Donkey, 1, 1, 3),
+];
+const FOREST_ANIMALS: &[SpawnEntry] = &[
+    entry(E::Sheep, 12, 4, 4),
+    entry(E::Pig, 10, 4, 4),
+    entry(E::Chicken, 10, 4, 4),
+    entry(E::Cow, 8, 4, 4),
+    entry(E::Wolf, 5, 4, 4),
+];
+const TAIGA_ANIMALS: &[SpawnEntry] = &[
+    entry(E::Sheep, 12, 4, 4),
+    entry(E::Pig, 10, 4, 4),
+    entry(E::Chicken, 10, 4, 4),
+    entry(E::Cow, 8, 4, 4),
+    entry(E::Wolf, 8, 4, 4),
+    entry(E::Rabbit, 4, 2, 3),
+    entry(E::Fox, 8, 2, 4),
+];
+const JUNGLE_ANIMALS: &[SpawnEntry] = &[
+    entry(E::Sheep, 12, 4, 4),
+    entry(E::Pig, 10, 4, 4),
+    entry(E::Chicken, 10, 4, 4),
+    entry(E::Cow, 8, 4, 4),
+    entry(E::Parrot, 40, 1, 2),
+    entry(E::Panda, 1, 1, 2),
+    entry(E::Ocelot, 2, 1, 3),
+];
+const SAVANNA_ANIMALS: &[SpawnEntry] = &[
+    entry(E::Sheep, 12, 4, 4),
+    entry(E::Pig, 10, 4, 4),
+    entry(E::Chicken, 10, 4, 4),
+    entry(E::Cow, 8, 4, 4),
+    entry(E::Horse, 1, 2, 6),
+    entry(E::Donkey, 1, 1, 1),
+    entry(E::Llama, 8, 4, 4),
+];
+const MOUNTAIN_ANIMALS: &[SpawnEntry] = &[
+    entry(E::Sheep, 12, 4, 4),
+    entry(E::Pig, 10, 4, 4),
+    entry(E::Chicken, 10, 4, 4),
+    entry(E::Cow, 8, 4, 4),
+    entry(E::Llama, 5, 4, 6),
+];
+const DESERT_ANIMALS: &[SpawnEntry] = &[entry(E::Rabbit, 4, 2, 3)];
+const SNOWY_ANIMALS: &[SpawnEntry] = &[entry(E::Rabbit, 10, 2, 3), entry(E::PolarBear, 1, 1, 2)];
+const MUSHROOM_ANIMALS: &[SpawnEntry] = &[entry(E::Mooshroom, 8, 4, 8)];
+const BEACH_ANIMALS: &[SpawnEntry] = &[entry(E::Turtle, 5, 2, 5)];
+const NETHER_ANIMALS: &[SpawnEntry] = &[entry(E::Strider, 60, 1, 2)];
+
+const AMBIENT: &[SpawnEntry] = &[entry(E::Bat, 10, 8, 8)];
+
+const OCEAN_WATER: &[SpawnEntry] = &[
+    entry(E::Squid, 10, 1, 4),
+    entry(E::Cod, 10, 3, 6),
+    entry(E::Dolphin, 1, 1, 2),
+];
+const COLD_OCEAN_WATER: &[SpawnEntry] = &[
+    entry(E::Squid, 3, 1, 4),
+    entry(E::Cod, 15, 3, 6),
+    entry(E::Salmon, 15, 1, 5),
+];
+const WARM_OCEAN_WATER: &[SpawnEntry] = &[
+    entry(E::Squid, 10, 4, 4),
+    entry(E::Pufferfish, 15, 1, 3),
+    entry(E::TropicalFish, 25, 8, 8),
+    entry(E::Dolphin, 2, 1, 2),
+];
+const RIVER_WATER: &[SpawnEntry] = &[entry(E::Squid, 2, 1, 4), entry(E::Salmon, 5, 1, 5)];
+
+/// Groups of biomes which share spawn lists.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum SpawnGroup {
+    Plains,
+    Forest,
+    Taiga,
+    SnowyTaiga,
+    Jungle,
+    Savanna,
+    Mountains,
+    Desert,
+    Snowy,
+    Swamp,
+    Badlands,
+    Mushroom,
+    Beach,
+    River,
+    Ocean,
+    ColdOcean,
+    WarmOcean,
+    Nether,
+    End,
+    Void,
+}
+
+impl SpawnGroup {
+    fn of(biome: Biome) -> Self {
+        use Biome::*;
+        match biome {
+            Plains | SunflowerPlains => G::Plains,
+            Forest | WoodedHills | FlowerForest | BirchForest | BirchForestHills
+            | TallBirchForest | TallBirchHills | DarkForest | DarkForestHills => G::Forest,
+            Taiga
+            | TaigaHills
+            | TaigaMountains
+            | GiantTreeTaiga
+            | GiantTreeTaigaHills
+            | GiantSpruceTaiga
+            | GiantSpruceTaigaHills => G::Taiga,
+            SnowyTaiga | SnowyTaigaHills | SnowyTaigaMountains => G::SnowyTaiga,
+            Jungle | JungleHills | JungleEdge | ModifiedJungle | ModifiedJungleEdge
+            | BambooJungle | BambooJungleHills => G::Jungle,
+            Savanna | SavannaPlateau | ShatteredSavanna | ShatteredSavannaPlateau => G::Savanna,
+            Mountains
+            | MountainEdge
+            | WoodedMountains
+            | GravellyMountains
+            | ModifiedGravellyMountains => G::Mountains,
+            Desert | DesertHills | DesertLakes => G::Desert,
+            SnowyTundra | SnowyMountains | IceSpikes => G::Snowy,
+            Swamp | SwampHills => G::Swamp,
+            Badlands
+            | WoodedBadlandsPlateau
+            | BadlandsPlateau
+            | ErodedBadlands
+            | ModifiedWoodedBadlandsPlateau
+            | ModifiedBadlandsPlateau => G::Badlands,
+            MushroomFields | MushroomFieldShore => G::Mushroom,
+            Beach | SnowyBeach | StoneShore => G::Beach,
+            River | FrozenRiver => G::River,
+            Ocean | DeepOcean => G::Ocean,
+            FrozenOcean | ColdOcean | DeepColdOcean | DeepFrozenOcean => G::ColdOcean,
+            WarmOcean | LukewarmOcean | DeepWarmOcean | DeepLukewarmOcean => G::WarmOcean,
+            NetherWastes | SoulSandValley | CrimsonForest | WarpedForest | BasaltDeltas => {
+                G::Nether
+            }
+            TheEnd | SmallEndIslands | EndMidlands | EndHighlands | EndBarrens => G::End,
+            TheVoid => G::Void,
+        }
+    }
+}
+
+impl Biome {
+    /// Gets the mobs of the given category which
+    /// spawn naturally in this biome.
+    pub fn spawn_entries(self, category: MobCategory) -> &'static [SpawnEntry] {
+        let group = SpawnGroup::of(self);
+        match category {
+            MobCategory::Monster => match group {
+                G::Mushroom | G::Void => &[],
+                G::Desert => DESERT_MONSTERS,
+                G::Snowy => SNOWY_MONSTERS,
+                G::Ocean | G::ColdOcean | G::WarmOcean => OCEAN_MONSTERS,
+                G::River => RIVER_MONSTERS,
+                G::Nether => NETHER_MONSTERS,
+                G::End => END_MONSTERS,
+                _ => MONSTERS,
+            },
+            MobCategory::Creature => match group {
+                G::Plains => PLAINS_ANIMALS,
+                G::Forest => FOREST_ANIMALS,
+                G::Swamp => FARM_ANIMALS,
+                G::Taiga | G::SnowyTaiga => TAIGA_ANIMALS,
+                G::Jungle => JUNGLE_ANIMALS,
+                G::Savanna => SAVANNA_ANIMALS,
+                G::Mountains => MOUNTAIN_ANIMALS,
+                G::Desert => DESERT_ANIMALS,
+                G::Snowy => SNOWY_ANIMALS,
+                G::Mushroom => MUSHROOM_ANIMALS,
+                G::Beach => {
+                    if self == Biome::Beach {
+                        BEACH_ANIMALS
+                    } else {
+                        &[]
+                    }
+                }
+                G::Nether => NETHER_ANIMALS,
+                G::Badlands
+                | G::River
+                | G::Ocean
+                | G::ColdOcean
+                | G::WarmOcean
+                | G::End
+                | G::Void => &[],
+            },
+            MobCategory::Ambient => match group {
+                G::Nether | G::End | G::Void => &[],
+                _ => AMBIENT,
+            },
+            MobCategory::WaterCreature => match group {
+                G::Ocean => OCEAN_WATER,
+                G::ColdOcean => COLD_OCEAN_WATER,
+                G::WarmOcean => WARM_OCEAN_WATER,
+                G::River | G::Swamp => RIVER_WATER,
+                _ => &[],
+            },
+        }
+    }
+
+    /// The chance per newly generated chunk of spawning
+    /// an initial group of creatures.
+    pub fn creature_spawn_probability(self) -> f32 {
+        match SpawnGroup::of(self) {
+            G::Snowy | G::SnowyTaiga => 0.07,
+            _ => 0.1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spawn_entries_match_category() {
+        for category in MobCategory::ALL.iter().copied() {
+            for biome in [
+                Biome::Plains,
+                Biome::Desert,
+                Biome::Ocean,
+                Biome::NetherWastes,
+            ] {
+                for entry in biome.spawn_entries(category) {
+                    assert_eq!(MobCategory::of(entry.kind), Some(category), "{:?}", entry);
+                    assert!(entry.min_group_size <= entry.max_group_size);
+                }
+            }
+        }
+    }
+}
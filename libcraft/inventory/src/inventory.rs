@@ -1066,6 +1066,13 @@ pub enum InventoryBacking<T> {
         furnace_fuel: [T; 1],
         furnace_output: [T; 1],
     },
+    EnchantmentTable {
+        enchantment_item: [T; 1],
+        enchantment_lapis: [T; 1],
+    },
+    Beacon {
+        beacon_payment: [T; 1],
+    },
 }
 impl<T> InventoryBacking<T> {
     pub fn area_slice(&self, area: Area) -> Option<&[T]> {
@@ -1114,6 +1121,18 @@ impl<T> InventoryBacking<T> {
                 Area::FurnaceOutput => Some(furnace_output.as_ref()),
                 _ => None,
             },
+            InventoryBacking::EnchantmentTable {
+                enchantment_item,
+                enchantment_lapis,
+            } => match area {
+                Area::EnchantmentItem => Some(enchantment_item.as_ref()),
+                Area::EnchantmentLapis => Some(enchantment_lapis.as_ref()),
+                _ => None,
+            },
+            InventoryBacking::Beacon { beacon_payment } => match area {
+                Area::BeaconPayment => Some(beacon_payment.as_ref()),
+                _ => None,
+            },
         }
     }
     pub fn areas(&self) -> &'static [Area] {
@@ -1148,6 +1167,14 @@ impl<T> InventoryBacking<T> {
                 ];
                 &AREAS
             }
+            InventoryBacking::EnchantmentTable { .. } => {
+                static AREAS: [Area; 2] = [Area::EnchantmentItem, Area::EnchantmentLapis];
+                &AREAS
+            }
+            InventoryBacking::Beacon { .. } => {
+                static AREAS: [Area; 1] = [Area::BeaconPayment];
+                &AREAS
+            }
         }
     }
     pub fn player() -> Self
@@ -1193,6 +1220,23 @@ impl<T> InventoryBacking<T> {
             furnace_output: Default::default(),
         }
     }
+    pub fn enchantment_table() -> Self
+    where
+        T: Default,
+    {
+        InventoryBacking::EnchantmentTable {
+            enchantment_item: Default::default(),
+            enchantment_lapis: Default::default(),
+        }
+    }
+    pub fn beacon() -> Self
+    where
+        T: Default,
+    {
+        InventoryBacking::Beacon {
+            beacon_payment: Default::default(),
+        }
+    }
 }
 impl crate::Inventory {
     pub fn player() -> Self {
@@ -1215,4 +1259,14 @@ impl crate::Inventory {
             backing: std::sync::Arc::new(InventoryBacking::furnace()),
         }
     }
+    pub fn enchantment_table() -> Self {
+        Self {
+            backing: std::sync::Arc::new(InventoryBacking::enchantment_table()),
+        }
+    }
+    pub fn beacon() -> Self {
+        Self {
+            backing: std::sync::Arc::new(InventoryBacking::beacon()),
+        }
+    }
 }
@@ -1,7 +1,15 @@
 //! Data sourced from: <https://minecraft.wiki/w/Enchanting#Enchantments>
+//! and <https://minecraft.wiki/w/Enchanting/Levels>
 
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
+use crate::Item;
+
+/// The highest level that enchantments can be
+/// selected at, after applying item enchantability.
+const MAX_SELECTION_LEVEL: u32 = 50;
+
 /// An enchantment attached to an item.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Enchantment {
@@ -153,3 +161,686 @@ pub enum EnchantmentKind {
     /// Reduces durability damage.
     Unbreaking = 21,
 }
+
+impl EnchantmentKind {
+    /// All enchantment kinds, ordered by protocol ID.
+    pub const ALL: [EnchantmentKind; 38] = [
+        EnchantmentKind::Protection,
+        EnchantmentKind::FireProtection,
+        EnchantmentKind::FeatherFalling,
+        EnchantmentKind::BlastProtection,
+        EnchantmentKind::ProjectileProtection,
+        EnchantmentKind::Respiration,
+        EnchantmentKind::AquaAffinity,
+        EnchantmentKind::Thorns,
+        EnchantmentKind::DepthStrider,
+        EnchantmentKind::FrostWalker,
+        EnchantmentKind::BindingCurse,
+        EnchantmentKind::SoulSpeed,
+        EnchantmentKind::Sharpness,
+        EnchantmentKind::Smite,
+        EnchantmentKind::BaneOfArthropods,
+        EnchantmentKind::Knockback,
+        EnchantmentKind::FireAspect,
+        EnchantmentKind::Looting,
+        EnchantmentKind::Sweeping,
+        EnchantmentKind::Efficiency,
+        EnchantmentKind::SilkTouch,
+        EnchantmentKind::Unbreaking,
+        EnchantmentKind::Fortune,
+        EnchantmentKind::Power,
+        EnchantmentKind::Punch,
+        EnchantmentKind::Flame,
+        EnchantmentKind::Infinity,
+        EnchantmentKind::LuckOfTheSea,
+        EnchantmentKind::Lure,
+        EnchantmentKind::Loyalty,
+        EnchantmentKind::Impaling,
+        EnchantmentKind::Riptide,
+        EnchantmentKind::Channeling,
+        EnchantmentKind::Multishot,
+        EnchantmentKind::QuickCharge,
+        EnchantmentKind::Piercing,
+        EnchantmentKind::Mending,
+        EnchantmentKind::VanishingCurse,
+    ];
+
+    /// Gets the protocol ID of this enchantment.
+    #[must_use]
+    pub const fn id(self) -> u32 {
+        self as u32
+    }
+
+    /// Gets an enchantment by its protocol ID.
+    #[must_use]
+    pub fn from_id(id: u32) -> Option<Self> {
+        Self::ALL.get(id as usize).copied()
+    }
+
+    /// Gets the name of this enchantment, without a namespace.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            EnchantmentKind::Protection => "protection",
+            EnchantmentKind::FireProtection => "fire_protection",
+            EnchantmentKind::FeatherFalling => "feather_falling",
+            EnchantmentKind::BlastProtection => "blast_protection",
+            EnchantmentKind::ProjectileProtection => "projectile_protection",
+            EnchantmentKind::Respiration => "respiration",
+            EnchantmentKind::AquaAffinity => "aqua_affinity",
+            EnchantmentKind::Thorns => "thorns",
+            EnchantmentKind::DepthStrider => "depth_strider",
+            EnchantmentKind::FrostWalker => "frost_walker",
+            EnchantmentKind::BindingCurse => "binding_curse",
+            EnchantmentKind::SoulSpeed => "soul_speed",
+            EnchantmentKind::Sharpness => "sharpness",
+            EnchantmentKind::Smite => "smite",
+            EnchantmentKind::BaneOfArthropods => "bane_of_arthropods",
+            EnchantmentKind::Knockback => "knockback",
+            EnchantmentKind::FireAspect => "fire_aspect",
+            EnchantmentKind::Looting => "looting",
+            EnchantmentKind::Sweeping => "sweeping",
+            EnchantmentKind::Efficiency => "efficiency",
+            EnchantmentKind::SilkTouch => "silk_touch",
+            EnchantmentKind::Unbreaking => "unbreaking",
+            EnchantmentKind::Fortune => "fortune",
+            EnchantmentKind::Power => "power",
+            EnchantmentKind::Punch => "punch",
+            EnchantmentKind::Flame => "flame",
+            EnchantmentKind::Infinity => "infinity",
+            EnchantmentKind::LuckOfTheSea => "luck_of_the_sea",
+            EnchantmentKind::Lure => "lure",
+            EnchantmentKind::Loyalty => "loyalty",
+            EnchantmentKind::Impaling => "impaling",
+            EnchantmentKind::Riptide => "riptide",
+            EnchantmentKind::Channeling => "channeling",
+            EnchantmentKind::Multishot => "multishot",
+            EnchantmentKind::QuickCharge => "quick_charge",
+            EnchantmentKind::Piercing => "piercing",
+            EnchantmentKind::Mending => "mending",
+            EnchantmentKind::VanishingCurse => "vanishing_curse",
+        }
+    }
+
+    /// Gets an enchantment by its name, without a namespace.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|kind| kind.name() == name)
+    }
+
+    /// Gets the namespaced identifier of this enchantment,
+    /// e.g. `minecraft:sharpness`.
+    #[must_use]
+    pub fn identifier(self) -> String {
+        format!("minecraft:{}", self.name())
+    }
+
+    /// Gets an enchantment by its namespaced identifier.
+    #[must_use]
+    pub fn from_identifier(identifier: &str) -> Option<Self> {
+        Self::from_name(identifier.strip_prefix("minecraft:").unwrap_or(identifier))
+    }
+
+    /// Gets the highest level of this enchantment
+    /// obtainable in survival.
+    #[must_use]
+    pub const fn max_level(self) -> u32 {
+        match self {
+            EnchantmentKind::Sharpness
+            | EnchantmentKind::Smite
+            | EnchantmentKind::BaneOfArthropods
+            | EnchantmentKind::Efficiency
+            | EnchantmentKind::Power
+            | EnchantmentKind::Impaling => 5,
+            EnchantmentKind::Protection
+            | EnchantmentKind::FireProtection
+            | EnchantmentKind::FeatherFalling
+            | EnchantmentKind::BlastProtection
+            | EnchantmentKind::ProjectileProtection
+            | EnchantmentKind::Piercing => 4,
+            EnchantmentKind::Respiration
+            | EnchantmentKind::Thorns
+            | EnchantmentKind::DepthStrider
+            | EnchantmentKind::SoulSpeed
+            | EnchantmentKind::Looting
+            | EnchantmentKind::Sweeping
+            | EnchantmentKind::Unbreaking
+            | EnchantmentKind::Fortune
+            | EnchantmentKind::LuckOfTheSea
+            | EnchantmentKind::Lure
+            | EnchantmentKind::Loyalty
+            | EnchantmentKind::Riptide
+            | EnchantmentKind::QuickCharge => 3,
+            EnchantmentKind::FrostWalker
+            | EnchantmentKind::Knockback
+            | EnchantmentKind::FireAspect
+            | EnchantmentKind::Punch => 2,
+            EnchantmentKind::AquaAffinity
+            | EnchantmentKind::BindingCurse
+            | EnchantmentKind::SilkTouch
+            | EnchantmentKind::Flame
+            | EnchantmentKind::Infinity
+            | EnchantmentKind::Channeling
+            | EnchantmentKind::Multishot
+            | EnchantmentKind::Mending
+            | EnchantmentKind::VanishingCurse => 1,
+        }
+    }
+
+    /// Gets the weight of this enchantment when
+    /// randomly choosing between enchantments.
+    /// Rarer enchantments have a lower weight.
+    #[must_use]
+    pub const fn weight(self) -> u32 {
+        match self {
+            EnchantmentKind::Protection
+            | EnchantmentKind::Sharpness
+            | EnchantmentKind::Efficiency
+            | EnchantmentKind::Power
+            | EnchantmentKind::Piercing => 10,
+            EnchantmentKind::FireProtection
+            | EnchantmentKind::FeatherFalling
+            | EnchantmentKind::ProjectileProtection
+            | EnchantmentKind::Smite
+            | EnchantmentKind::BaneOfArthropods
+            | EnchantmentKind::Knockback
+            | EnchantmentKind::Unbreaking
+            | EnchantmentKind::Loyalty
+            | EnchantmentKind::QuickCharge => 5,
+            EnchantmentKind::BlastProtection
+            | EnchantmentKind::Respiration
+            | EnchantmentKind::AquaAffinity
+            | EnchantmentKind::DepthStrider
+            | EnchantmentKind::FrostWalker
+            | EnchantmentKind::FireAspect
+            | EnchantmentKind::Looting
+            | EnchantmentKind::Sweeping
+            | EnchantmentKind::Fortune
+            | EnchantmentKind::Punch
+            | EnchantmentKind::Flame
+            | EnchantmentKind::LuckOfTheSea
+            | EnchantmentKind::Lure
+            | EnchantmentKind::Impaling
+            | EnchantmentKind::Riptide
+            | EnchantmentKind::Multishot
+            | EnchantmentKind::Mending => 2,
+            EnchantmentKind::Thorns
+            | EnchantmentKind::BindingCurse
+            | EnchantmentKind::SoulSpeed
+            | EnchantmentKind::SilkTouch
+            | EnchantmentKind::Infinity
+            | EnchantmentKind::Channeling
+            | EnchantmentKind::VanishingCurse => 1,
+        }
+    }
+
+    /// Gets the lowest modified enchantment level
+    /// at which the given level of this enchantment can be selected.
+    #[must_use]
+    pub const fn min_cost(self, level: u32) -> u32 {
+        let level = if level == 0 { 1 } else { level };
+        match self {
+            EnchantmentKind::FireProtection => 10 + (level - 1) * 8,
+            EnchantmentKind::FeatherFalling => 5 + (level - 1) * 6,
+            EnchantmentKind::ProjectileProtection => 3 + (level - 1) * 6,
+            EnchantmentKind::Respiration
+            | EnchantmentKind::DepthStrider
+            | EnchantmentKind::FrostWalker
+            | EnchantmentKind::SoulSpeed => 10 * level,
+            EnchantmentKind::AquaAffinity => 1,
+            EnchantmentKind::BindingCurse
+            | EnchantmentKind::Channeling
+            | EnchantmentKind::VanishingCurse => 25,
+            EnchantmentKind::Protection | EnchantmentKind::Sharpness => 1 + (level - 1) * 11,
+            EnchantmentKind::BlastProtection
+            | EnchantmentKind::Smite
+            | EnchantmentKind::BaneOfArthropods
+            | EnchantmentKind::Unbreaking => 5 + (level - 1) * 8,
+            EnchantmentKind::Knockback => 5 + 20 * (level - 1),
+            EnchantmentKind::Thorns | EnchantmentKind::FireAspect => 10 + 20 * (level - 1),
+            EnchantmentKind::Looting
+            | EnchantmentKind::Fortune
+            | EnchantmentKind::LuckOfTheSea
+            | EnchantmentKind::Lure => 15 + (level - 1) * 9,
+            EnchantmentKind::Sweeping => 5 + (level - 1) * 9,
+            EnchantmentKind::Efficiency | EnchantmentKind::Power | EnchantmentKind::Piercing => {
+                1 + (level - 1) * 10
+            }
+            EnchantmentKind::SilkTouch => 15,
+            EnchantmentKind::Punch | EnchantmentKind::QuickCharge => 12 + (level - 1) * 20,
+            EnchantmentKind::Flame | EnchantmentKind::Infinity | EnchantmentKind::Multishot => 20,
+            EnchantmentKind::Loyalty => 5 + level * 7,
+            EnchantmentKind::Impaling => 1 + (level - 1) * 8,
+            EnchantmentKind::Riptide => 10 + level * 7,
+            EnchantmentKind::Mending => level * 25,
+        }
+    }
+
+    /// Gets the highest modified enchantment level
+    /// at which the given level of this enchantment can be selected.
+    #[must_use]
+    pub const fn max_cost(self, level: u32) -> u32 {
+        let level = if level == 0 { 1 } else { level };
+        let min_cost = self.min_cost(level);
+        match self {
+            EnchantmentKind::Protection => min_cost + 11,
+            EnchantmentKind::FireProtection | EnchantmentKind::BlastProtection => min_cost + 8,
+            EnchantmentKind::FeatherFalling | EnchantmentKind::ProjectileProtection => min_cost + 6,
+            EnchantmentKind::Respiration => min_cost + 30,
+            EnchantmentKind::AquaAffinity => min_cost + 40,
+            EnchantmentKind::DepthStrider
+            | EnchantmentKind::FrostWalker
+            | EnchantmentKind::SoulSpeed
+            | EnchantmentKind::Sweeping
+            | EnchantmentKind::Power => min_cost + 15,
+            EnchantmentKind::Sharpness
+            | EnchantmentKind::Smite
+            | EnchantmentKind::BaneOfArthropods
+            | EnchantmentKind::Impaling => min_cost + 20,
+            EnchantmentKind::Punch => min_cost + 25,
+            EnchantmentKind::Mending => min_cost + 50,
+            EnchantmentKind::Thorns
+            | EnchantmentKind::Knockback
+            | EnchantmentKind::FireAspect
+            | EnchantmentKind::Looting
+            | EnchantmentKind::Efficiency
+            | EnchantmentKind::SilkTouch
+            | EnchantmentKind::Unbreaking
+            | EnchantmentKind::Fortune
+            | EnchantmentKind::LuckOfTheSea
+            | EnchantmentKind::Lure => 51 + level * 10,
+            EnchantmentKind::BindingCurse
+            | EnchantmentKind::Flame
+            | EnchantmentKind::Infinity
+            | EnchantmentKind::Loyalty
+            | EnchantmentKind::Riptide
+            | EnchantmentKind::Channeling
+            | EnchantmentKind::Multishot
+            | EnchantmentKind::QuickCharge
+            | EnchantmentKind::Piercing
+            | EnchantmentKind::VanishingCurse => 50,
+        }
+    }
+
+    /// Whether this enchantment is a treasure enchantment,
+    /// which cannot be obtained from an enchanting table.
+    #[must_use]
+    pub const fn is_treasure(self) -> bool {
+        matches!(
+            self,
+            EnchantmentKind::FrostWalker
+                | EnchantmentKind::BindingCurse
+                | EnchantmentKind::SoulSpeed
+                | EnchantmentKind::Mending
+                | EnchantmentKind::VanishingCurse
+        )
+    }
+
+    /// Whether this enchantment is a curse.
+    #[must_use]
+    pub const fn is_curse(self) -> bool {
+        matches!(
+            self,
+            EnchantmentKind::BindingCurse | EnchantmentKind::VanishingCurse
+        )
+    }
+
+    /// Whether this enchantment can be put on the given item
+    /// by an enchanting table.
+    ///
+    /// Books accept every enchantment.
+    #[must_use]
+    pub fn can_enchant(self, item: Item) -> bool {
+        if item == Item::Book {
+            return true;
+        }
+        match self {
+            EnchantmentKind::Protection
+            | EnchantmentKind::FireProtection
+            | EnchantmentKind::BlastProtection
+            | EnchantmentKind::ProjectileProtection => is_armor(item),
+            EnchantmentKind::FeatherFalling
+            | EnchantmentKind::DepthStrider
+            | EnchantmentKind::FrostWalker
+            | EnchantmentKind::SoulSpeed => is_boots(item),
+            EnchantmentKind::Respiration | EnchantmentKind::AquaAffinity => is_helmet(item),
+            EnchantmentKind::Thorns => is_chestplate(item),
+            EnchantmentKind::BindingCurse => {
+                is_armor(item) || matches!(item, Item::Elytra | Item::CarvedPumpkin)
+            }
+            EnchantmentKind::Sharpness
+            | EnchantmentKind::Smite
+            | EnchantmentKind::BaneOfArthropods
+            | EnchantmentKind::Knockback
+            | EnchantmentKind::FireAspect
+            | EnchantmentKind::Looting
+            | EnchantmentKind::Sweeping => is_sword(item),
+            EnchantmentKind::Efficiency | EnchantmentKind::SilkTouch | EnchantmentKind::Fortune => {
+                is_digger(item)
+            }
+            EnchantmentKind::Unbreaking
+            | EnchantmentKind::Mending
+            | EnchantmentKind::VanishingCurse => item.durability().is_some(),
+            EnchantmentKind::Power
+            | EnchantmentKind::Punch
+            | EnchantmentKind::Flame
+            | EnchantmentKind::Infinity => item == Item::Bow,
+            EnchantmentKind::LuckOfTheSea | EnchantmentKind::Lure => item == Item::FishingRod,
+            EnchantmentKind::Loyalty
+            | EnchantmentKind::Impaling
+            | EnchantmentKind::Riptide
+            | EnchantmentKind::Channeling => item == Item::Trident,
+            EnchantmentKind::Multishot
+            | EnchantmentKind::QuickCharge
+            | EnchantmentKind::Piercing => item == Item::Crossbow,
+        }
+    }
+
+    /// Whether this enchantment can be on the same item
+    /// as `other`.
+    #[must_use]
+    pub fn is_compatible_with(self, other: EnchantmentKind) -> bool {
+        use EnchantmentKind::{
+            BaneOfArthropods, BlastProtection, Channeling, DepthStrider, FireProtection, Fortune,
+            FrostWalker, Infinity, Looting, Loyalty, LuckOfTheSea, Mending, Multishot, Piercing,
+            ProjectileProtection, Protection, Riptide, Sharpness, SilkTouch, Smite,
+        };
+
+        fn exclusive(a: EnchantmentKind, b: EnchantmentKind) -> bool {
+            match a {
+                Protection | FireProtection | BlastProtection | ProjectileProtection => matches!(
+                    b,
+                    Protection | FireProtection | BlastProtection | ProjectileProtection
+                ),
+                Sharpness | Smite | BaneOfArthropods => {
+                    matches!(b, Sharpness | Smite | BaneOfArthropods)
+                }
+                SilkTouch => matches!(b, Fortune | Looting | LuckOfTheSea),
+                DepthStrider => b == FrostWalker,
+                Infinity => b == Mending,
+                Riptide => matches!(b, Loyalty | Channeling),
+                Multishot => b == Piercing,
+                _ => false,
+            }
+        }
+
+        self != other && !exclusive(self, other) && !exclusive(other, self)
+    }
+}
+
+impl Item {
+    /// Gets the enchantability of this item, which increases
+    /// the level of enchantments obtained from an enchanting table.
+    ///
+    /// Returns `None` if the item cannot be enchanted in an enchanting table.
+    #[must_use]
+    #[allow(clippy::match_same_arms)]
+    pub fn enchantability(self) -> Option<u32> {
+        Some(match self {
+            Item::WoodenSword
+            | Item::WoodenShovel
+            | Item::WoodenPickaxe
+            | Item::WoodenAxe
+            | Item::WoodenHoe => 15,
+            Item::StoneSword
+            | Item::StoneShovel
+            | Item::StonePickaxe
+            | Item::StoneAxe
+            | Item::StoneHoe => 5,
+            Item::IronSword
+            | Item::IronShovel
+            | Item::IronPickaxe
+            | Item::IronAxe
+            | Item::IronHoe => 14,
+            Item::GoldenSword
+            | Item::GoldenShovel
+            | Item::GoldenPickaxe
+            | Item::GoldenAxe
+            | Item::GoldenHoe => 22,
+            Item::DiamondSword
+            | Item::DiamondShovel
+            | Item::DiamondPickaxe
+            | Item::DiamondAxe
+            | Item::DiamondHoe => 10,
+            Item::NetheriteSword
+            | Item::NetheriteShovel
+            | Item::NetheritePickaxe
+            | Item::NetheriteAxe
+            | Item::NetheriteHoe => 15,
+            Item::LeatherHelmet
+            | Item::LeatherChestplate
+            | Item::LeatherLeggings
+            | Item::LeatherBoots => 15,
+            Item::ChainmailHelmet
+            | Item::ChainmailChestplate
+            | Item::ChainmailLeggings
+            | Item::ChainmailBoots => 12,
+            Item::IronHelmet | Item::IronChestplate | Item::IronLeggings | Item::IronBoots => 9,
+            Item::GoldenHelmet
+            | Item::GoldenChestplate
+            | Item::GoldenLeggings
+            | Item::GoldenBoots => 25,
+            Item::DiamondHelmet
+            | Item::DiamondChestplate
+            | Item::DiamondLeggings
+            | Item::DiamondBoots => 10,
+            Item::NetheriteHelmet
+            | Item::NetheriteChestplate
+            | Item::NetheriteLeggings
+            | Item::NetheriteBoots => 15,
+            Item::TurtleHelmet => 9,
+            Item::Bow | Item::Crossbow | Item::Trident | Item::FishingRod | Item::Book => 1,
+            _ => return None,
+        })
+    }
+}
+
+/// Randomly selects enchantments for an item, as done
+/// by enchanting tables.
+///
+/// `level` is the number of levels the enchantment costs;
+/// it is modified by the item's enchantability before choosing
+/// enchantments. Returns an empty list if the item is not enchantable.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss,
+    clippy::manual_let_else
+)]
+pub fn select_enchantments<R: Rng + ?Sized>(
+    rng: &mut R,
+    item: Item,
+    level: u32,
+    allow_treasure: bool,
+) -> Vec<Enchantment> {
+    let enchantability = match item.enchantability() {
+        Some(enchantability) => enchantability,
+        None => return Vec::new(),
+    };
+
+    let mut level =
+        level + 1 + rng.gen_range(0..=enchantability / 4) + rng.gen_range(0..=enchantability / 4);
+    let bonus = (rng.gen::<f32>() + rng.gen::<f32>() - 1.0) * 0.15;
+    level = ((level as f32 + level as f32 * bonus).round() as u32).clamp(1, MAX_SELECTION_LEVEL);
+
+    let mut available = available_enchantments(item, level, allow_treasure);
+    let mut selected = Vec::new();
+    if let Some(first) = choose_weighted(rng, &available) {
+        selected.push(first);
+
+        while rng.gen_range(0..50) <= level {
+            let last = selected[selected.len() - 1].kind();
+            available.retain(|enchantment| enchantment.kind().is_compatible_with(last));
+            match choose_weighted(rng, &available) {
+                Some(enchantment) => selected.push(enchantment),
+                None => break,
+            }
+            level /= 2;
+        }
+    }
+    selected
+}
+
+/// Gets the highest level of each enchantment applicable to `item`
+/// which can be selected at the given modified enchantment level.
+fn available_enchantments(item: Item, level: u32, allow_treasure: bool) -> Vec<Enchantment> {
+    EnchantmentKind::ALL
+        .iter()
+        .copied()
+        .filter(|kind| (allow_treasure || !kind.is_treasure()) && kind.can_enchant(item))
+        .filter_map(|kind| {
+            (1..=kind.max_level())
+                .rev()
+                .find(|&l| level >= kind.min_cost(l) && level <= kind.max_cost(l))
+                .map(|l| Enchantment::new(kind, l))
+        })
+        .collect()
+}
+
+fn choose_weighted<R: Rng + ?Sized>(rng: &mut R, options: &[Enchantment]) -> Option<Enchantment> {
+    let total: u32 = options.iter().map(|e| e.kind().weight()).sum();
+    if total == 0 {
+        return None;
+    }
+    let mut choice = rng.gen_range(0..total);
+    for enchantment in options {
+        let weight = enchantment.kind().weight();
+        if choice < weight {
+            return Some(*enchantment);
+        }
+        choice -= weight;
+    }
+    None
+}
+
+fn is_helmet(item: Item) -> bool {
+    matches!(
+        item,
+        Item::LeatherHelmet
+            | Item::ChainmailHelmet
+            | Item::IronHelmet
+            | Item::GoldenHelmet
+            | Item::DiamondHelmet
+            | Item::NetheriteHelmet
+            | Item::TurtleHelmet
+    )
+}
+
+fn is_chestplate(item: Item) -> bool {
+    matches!(
+        item,
+        Item::LeatherChestplate
+            | Item::ChainmailChestplate
+            | Item::IronChestplate
+            | Item::GoldenChestplate
+            | Item::DiamondChestplate
+            | Item::NetheriteChestplate
+    )
+}
+
+fn is_leggings(item: Item) -> bool {
+    matches!(
+        item,
+        Item::LeatherLeggings
+            | Item::ChainmailLeggings
+            | Item::IronLeggings
+            | Item::GoldenLeggings
+            | Item::DiamondLeggings
+            | Item::NetheriteLeggings
+    )
+}
+
+fn is_boots(item: Item) -> bool {
+    matches!(
+        item,
+        Item::LeatherBoots
+            | Item::ChainmailBoots
+            | Item::IronBoots
+            | Item::GoldenBoots
+            | Item::DiamondBoots
+            | Item::NetheriteBoots
+    )
+}
+
+fn is_armor(item: Item) -> bool {
+    is_helmet(item) || is_chestplate(item) || is_leggings(item) || is_boots(item)
+}
+
+fn is_sword(item: Item) -> bool {
+    matches!(
+        item,
+        Item::WoodenSword
+            | Item::StoneSword
+            | Item::IronSword
+            | Item::GoldenSword
+            | Item::DiamondSword
+            | Item::NetheriteSword
+    )
+}
+
+fn is_digger(item: Item) -> bool {
+    matches!(
+        item,
+        Item::WoodenShovel
+            | Item::WoodenPickaxe
+            | Item::WoodenAxe
+            | Item::WoodenHoe
+            | Item::StoneShovel
+            | Item::StonePickaxe
+            | Item::StoneAxe
+            | Item::StoneHoe
+            | Item::IronShovel
+            | Item::IronPickaxe
+            | Item::IronAxe
+            | Item::IronHoe
+            | Item::GoldenShovel
+            | Item::GoldenPickaxe
+            | Item::GoldenAxe
+            | Item::GoldenHoe
+            | Item::DiamondShovel
+            | Item::DiamondPickaxe
+            | Item::DiamondAxe
+            | Item::DiamondHoe
+            | Item::NetheriteShovel
+            | Item::NetheritePickaxe
+            | Item::NetheriteAxe
+            | Item::NetheriteHoe
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn enchantment_ids_round_trip() {
+        for (id, kind) in EnchantmentKind::ALL.iter().enumerate() {
+            assert_eq!(kind.id() as usize, id);
+            assert_eq!(EnchantmentKind::from_name(kind.name()), Some(*kind));
+        }
+        assert_eq!(
+            EnchantmentKind::from_identifier("minecraft:silk_touch"),
+            Some(EnchantmentKind::SilkTouch)
+        );
+    }
+
+    #[test]
+    fn selected_enchantments_apply_to_item() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let enchantments = select_enchantments(&mut rng, Item::DiamondPickaxe, 30, false);
+            assert!(!enchantments.is_empty());
+            for (i, enchantment) in enchantments.iter().enumerate() {
+                assert!(enchantment.kind().can_enchant(Item::DiamondPickaxe));
+                assert!(!enchantment.kind().is_treasure());
+                for other in &enchantments[i + 1..] {
+                    assert!(enchantment.kind().is_compatible_with(other.kind()));
+                }
+            }
+        }
+
+        assert!(select_enchantments(&mut rng, Item::Stone, 30, false).is_empty());
+    }
+}
@@ -0,0 +1,131 @@
+//! Data sourced from: <https://minecraft.wiki/w/Food#Foods>
+
+use crate::Item;
+
+/// Number of ticks it takes to eat most foods.
+const DEFAULT_EAT_TICKS: u32 = 32;
+
+/// The nutritional properties of a food item.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FoodProperties {
+    /// Food points restored by eating the item.
+    pub hunger: u32,
+    /// Multiplier used to compute the saturation
+    /// restored by eating the item.
+    pub saturation_modifier: f32,
+    /// Whether the item can be eaten when the
+    /// player's food bar is full.
+    pub always_edible: bool,
+    /// Number of ticks it takes to eat the item.
+    pub eat_ticks: u32,
+    /// The item left behind after eating, such as
+    /// the bowl of a stew.
+    pub remainder: Option<Item>,
+}
+
+impl FoodProperties {
+    const fn new(hunger: u32, saturation_modifier: f32) -> Self {
+        Self {
+            hunger,
+            saturation_modifier,
+            always_edible: false,
+            eat_ticks: DEFAULT_EAT_TICKS,
+            remainder: None,
+        }
+    }
+
+    const fn always_edible(mut self) -> Self {
+        self.always_edible = true;
+        self
+    }
+
+    const fn eat_ticks(mut self, ticks: u32) -> Self {
+        self.eat_ticks = ticks;
+        self
+    }
+
+    const fn remainder(mut self, item: Item) -> Self {
+        self.remainder = Some(item);
+        self
+    }
+
+    /// Gets the saturation restored by eating the item.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn saturation(&self) -> f32 {
+        self.hunger as f32 * self.saturation_modifier * 2.0
+    }
+}
+
+impl Item {
+    /// Gets the food properties of this item,
+    /// or `None` if it cannot be eaten.
+    #[must_use]
+    #[allow(clippy::match_same_arms)]
+    pub fn food(self) -> Option<FoodProperties> {
+        Some(match self {
+            Item::Apple => FoodProperties::new(4, 0.3),
+            Item::BakedPotato => FoodProperties::new(5, 0.6),
+            Item::Beef => FoodProperties::new(3, 0.3),
+            Item::Beetroot => FoodProperties::new(1, 0.6),
+            Item::BeetrootSoup => FoodProperties::new(6, 0.6).remainder(Item::Bowl),
+            Item::Bread => FoodProperties::new(5, 0.6),
+            Item::Carrot => FoodProperties::new(3, 0.6),
+            Item::Chicken => FoodProperties::new(2, 0.3),
+            Item::ChorusFruit => FoodProperties::new(4, 0.3).always_edible(),
+            Item::Cod => FoodProperties::new(2, 0.1),
+            Item::CookedBeef => FoodProperties::new(8, 0.8),
+            Item::CookedChicken => FoodProperties::new(6, 0.6),
+            Item::CookedCod => FoodProperties::new(5, 0.6),
+            Item::CookedMutton => FoodProperties::new(6, 0.8),
+            Item::CookedPorkchop => FoodProperties::new(8, 0.8),
+            Item::CookedRabbit => FoodProperties::new(5, 0.6),
+            Item::CookedSalmon => FoodProperties::new(6, 0.8),
+            Item::Cookie => FoodProperties::new(2, 0.1),
+            Item::DriedKelp => FoodProperties::new(1, 0.3).eat_ticks(16),
+            Item::EnchantedGoldenApple => FoodProperties::new(4, 1.2).always_edible(),
+            Item::GoldenApple => FoodProperties::new(4, 1.2).always_edible(),
+            Item::GoldenCarrot => FoodProperties::new(6, 1.2),
+            Item::HoneyBottle => FoodProperties::new(6, 0.1)
+                .eat_ticks(40)
+                .remainder(Item::GlassBottle),
+            Item::MelonSlice => FoodProperties::new(2, 0.3),
+            Item::MushroomStew => FoodProperties::new(6, 0.6).remainder(Item::Bowl),
+            Item::Mutton => FoodProperties::new(2, 0.3),
+            Item::PoisonousPotato => FoodProperties::new(2, 0.3),
+            Item::Porkchop => FoodProperties::new(3, 0.3),
+            Item::Potato => FoodProperties::new(1, 0.3),
+            Item::Pufferfish => FoodProperties::new(1, 0.1),
+            Item::PumpkinPie => FoodProperties::new(8, 0.3),
+            Item::Rabbit => FoodProperties::new(3, 0.3),
+            Item::RabbitStew => FoodProperties::new(10, 0.6).remainder(Item::Bowl),
+            Item::RottenFlesh => FoodProperties::new(4, 0.1),
+            Item::Salmon => FoodProperties::new(2, 0.1),
+            Item::SpiderEye => FoodProperties::new(2, 0.8),
+            Item::SuspiciousStew => FoodProperties::new(6, 0.6)
+                .always_edible()
+                .remainder(Item::Bowl),
+            Item::SweetBerries => FoodProperties::new(2, 0.1),
+            Item::TropicalFish => FoodProperties::new(1, 0.1),
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn food_saturation() {
+        let steak = Item::CookedBeef.food().unwrap();
+        assert_eq!(steak.hunger, 8);
+        assert!((steak.saturation() - 12.8).abs() < 1e-4);
+
+        assert_eq!(Item::Stone.food(), None);
+        assert_eq!(
+            Item::MushroomStew.food().unwrap().remainder,
+            Some(Item::Bowl)
+        );
+    }
+}
@@ -48,6 +48,9 @@ pub struct ItemStackMeta {
 
     /// The enchantments applied to this `ItemStack`.
     enchantments: Vec<Enchantment>,
+
+    /// The potion contained in a potion item, e.g. `minecraft:swiftness`.
+    potion: Option<String>,
 }
 
 impl ItemStack {
@@ -66,6 +69,7 @@ impl ItemStack {
                 damage: None,
                 repair_cost: None,
                 enchantments: vec![],
+                potion: None,
             }),
         })
     }
@@ -332,6 +336,37 @@ impl ItemStack {
         self.meta.as_ref().map_or(Some(0), |meta| meta.damage)
     }
 
+    /// Returns the enchantments applied to this `ItemStack`.
+    #[must_use]
+    pub fn enchantments(&self) -> &[Enchantment] {
+        self.meta
+            .as_ref()
+            .map_or(&[], |meta| meta.enchantments.as_slice())
+    }
+
+    /// Returns the identifier of the potion contained in this `ItemStack`.
+    #[must_use]
+    pub fn potion(&self) -> Option<&str> {
+        self.meta.as_ref().and_then(|meta| meta.potion.as_deref())
+    }
+
+    /// Sets the identifier of the potion contained in this `ItemStack`.
+    pub fn set_potion(&mut self, potion: Option<String>) {
+        let item = self.item;
+        self.meta
+            .get_or_insert_with(|| ItemStackMeta::new(item))
+            .potion = potion;
+    }
+
+    /// Changes the level of the given enchantment in-place
+    /// or adds it at the end of the list.
+    pub fn set_enchantment_level(&mut self, ench: EnchantmentKind, level: u32) {
+        let item = self.item;
+        self.meta
+            .get_or_insert_with(|| ItemStackMeta::new(item))
+            .set_enchantment_level(ench, level);
+    }
+
     /// Returns true is the contents of other could be merged with the contents
     /// of self. This does not look at the item count, just the kind.
     /// Items can be merged when they have the same kind, damage, and enchantment.
@@ -389,6 +424,7 @@ impl ItemStackMeta {
             damage: None,
             repair_cost: None,
             enchantments: vec![],
+            potion: None,
         }
     }
 
@@ -497,6 +533,13 @@ impl ItemStackBuilder {
         self
     }
 
+    /// Set the identifier of the contained potion, e.g. `minecraft:swiftness`.
+    #[must_use]
+    pub fn potion(mut self, potion: impl Into<String>) -> Self {
+        self.get_or_init_meta().potion = Some(potion.into());
+        self
+    }
+
     /// If `damage` is some, then its value is applied, else this is a no-op.
     #[must_use]
     pub fn apply_damage(self, damage: Option<i32>) -> Self {
@@ -6,11 +6,13 @@
 //! Libcraft crate for item manipulation.
 
 mod enchantment;
+mod food;
 mod inventory_slot;
 mod item;
 mod item_stack;
 
-pub use enchantment::{Enchantment, EnchantmentKind};
+pub use enchantment::{select_enchantments, Enchantment, EnchantmentKind};
+pub use food::FoodProperties;
 pub use inventory_slot::InventorySlot;
 pub use item::*;
 pub use item_stack::{ItemStack, ItemStackBuilder, ItemStackError, ItemStackMeta};
@@ -1093,6 +1093,10 @@ impl Text {
         Text::from(text)
     }
 
+    pub fn translate<A: Into<Translate>>(translate: A) -> Self {
+        Text::from(TextValue::translate(translate))
+    }
+
     pub fn translate_with<A, B>(translate: A, with: B) -> Self
     where
         A: Into<Translate>,
@@ -74,6 +74,14 @@ pub fn plugin(_attr: TokenStream, mut item: TokenStream) -> TokenStream {
             PLUGIN = Some(plugin);
         }
 
+        #[no_mangle]
+        #[doc(hidden)]
+        pub unsafe extern "C" fn quill_disable() {
+            if let Some(plugin) = PLUGIN.take() {
+                quill::Plugin::disable(plugin, &mut ::quill::Game::new());
+            }
+        }
+
         #[no_mangle]
         #[doc(hidden)]
         pub unsafe extern "C" fn quill_allocate(size: usize, align: usize) -> *mut u8 {
@@ -97,6 +105,19 @@ pub fn plugin(_attr: TokenStream, mut item: TokenStream) -> TokenStream {
             system(plugin, &mut ::quill::Game::new());
         }
 
+        #[no_mangle]
+        #[doc(hidden)]
+        pub unsafe extern "C" fn quill_run_goal(data: *mut u8, entity: u64, action: u32) -> u32 {
+            ::quill::goal::run_goal(data, entity, action)
+        }
+
+        #[no_mangle]
+        #[doc(hidden)]
+        pub unsafe extern "C" fn quill_run_task(data: *mut u8, action: u32) {
+            let plugin = PLUGIN.as_mut().map(|plugin| plugin as &mut dyn ::std::any::Any);
+            ::quill::scheduler::run_task(data, action, plugin)
+        }
+
         /// Never called by Quill, but this is needed
         /// to avoid linker errors with WASI.
         #[doc(hidden)]
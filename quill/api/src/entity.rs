@@ -1,7 +1,11 @@
 use libcraft_text::Text;
 use std::{marker::PhantomData, ptr};
 
-use quill_common::{Component, Pointer, PointerMut};
+use libcraft_core::StatusEffect;
+use quill_common::{
+    components::{ActiveEffects, EffectInstance},
+    Component, Pointer, PointerMut,
+};
 
 /// Unique internal ID of an entity.
 ///
@@ -118,6 +122,15 @@ impl Entity {
         }
     }
 
+    /// Determines whether this entity has a permission node,
+    /// like `myplugin.fly`. Always `false` for entities
+    /// which aren't players.
+    pub fn has_permission(&self, node: &str) -> bool {
+        unsafe {
+            quill_sys::entity_has_permission(self.id.0, node.as_ptr().into(), node.len() as u32)
+        }
+    }
+
     /// Sends the given title to this entity.
     pub fn send_title(&self, title: &libcraft_text::Title) {
         let title = serde_json::to_string(title).expect("failed to serialize Title");
@@ -136,6 +149,31 @@ impl Entity {
         self.send_title(&libcraft_text::title::Title::RESET)
     }
 
+    /// Applies a status effect to this entity, following the same
+    /// rules as potions: an existing effect is only replaced by a
+    /// stronger or longer-lasting one.
+    ///
+    /// Has no effect on entities which cannot have status effects,
+    /// and instant effects like `InstantHealth` are not applied.
+    pub fn add_effect(&self, effect: StatusEffect, instance: EffectInstance) {
+        if effect.is_instant() {
+            return;
+        }
+        if let Ok(mut effects) = self.get::<ActiveEffects>() {
+            effects.add(effect, instance);
+            self.insert(effects);
+        }
+    }
+
+    /// Removes a status effect from this entity.
+    pub fn remove_effect(&self, effect: StatusEffect) {
+        if let Ok(mut effects) = self.get::<ActiveEffects>() {
+            if effects.remove(effect).is_some() {
+                self.insert(effects);
+            }
+        }
+    }
+
     /// Gets the unique ID of this entity.
     pub fn id(&self) -> EntityId {
         self.id
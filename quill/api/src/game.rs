@@ -8,6 +8,7 @@ use quill_common::Component;
 
 use crate::{
     query::{Query, QueryIter},
+    scheduler::{self, TaskHandle},
     EntityBuilder,
 };
 use crate::{Entity, EntityId};
@@ -220,6 +221,28 @@ impl Game {
         }
     }
 
+    /// Runs `task` once after `delay` ticks.
+    ///
+    /// A delay of zero runs the task on the next tick.
+    pub fn schedule_once(&self, delay: u64, task: impl FnOnce(&mut Game) + 'static) -> TaskHandle {
+        scheduler::schedule(delay, 0, scheduler::once(move |_, game| task(game)))
+    }
+
+    /// Runs `task` after `delay` ticks, then every `period` ticks
+    /// until it's cancelled through the returned [`TaskHandle`].
+    ///
+    /// # Panics
+    /// Panics if `period` is zero.
+    pub fn schedule_repeating(
+        &self,
+        delay: u64,
+        period: u64,
+        mut task: impl FnMut(&mut Game) + 'static,
+    ) -> TaskHandle {
+        assert_ne!(period, 0, "task period must be at least one tick");
+        scheduler::schedule(delay, period, Box::new(move |_, game| task(game)))
+    }
+
     /// Inserts an event to the world.
     pub fn insert_event<T: Component>(&self, event: T) {
         let host_component = T::host_component();
@@ -0,0 +1,78 @@
+//! Custom AI goals for mobs.
+
+use quill_common::goal::GoalAction;
+
+use crate::{Entity, EntityId, Game};
+
+#[doc(inline)]
+pub use quill_common::goal::GoalControls;
+
+/// A behavior a mob performs when its conditions are met.
+///
+/// Add a goal to an entity with [`Entity::add_goal`]. The server
+/// decides each tick which of the entity's goals should run, based
+/// on their priorities and [`GoalControls`], the same way it does
+/// for built-in goals like wandering or attacking.
+pub trait Goal: 'static {
+    /// Returns whether this goal should start running.
+    fn can_start(&mut self, game: &mut Game, entity: &Entity) -> bool;
+
+    /// Returns whether this goal should keep running.
+    ///
+    /// Defaults to [`Goal::can_start`].
+    fn should_continue(&mut self, game: &mut Game, entity: &Entity) -> bool {
+        self.can_start(game, entity)
+    }
+
+    /// Called when the goal starts running.
+    fn start(&mut self, _game: &mut Game, _entity: &Entity) {}
+
+    /// Called each tick while the goal is running.
+    fn tick(&mut self, _game: &mut Game, _entity: &Entity) {}
+
+    /// Called when the goal stops running.
+    fn stop(&mut self, _game: &mut Game, _entity: &Entity) {}
+}
+
+impl Entity {
+    /// Adds an AI goal to this entity.
+    ///
+    /// Goals with a lower `priority` take precedence. Two goals
+    /// sharing any of their `controls` never run at the same time.
+    pub fn add_goal(&self, priority: u32, controls: GoalControls, goal: impl Goal) {
+        let goal: Box<dyn Goal> = Box::new(goal);
+        let goal_data = Box::leak(Box::new(goal)) as *mut Box<_> as *mut u8;
+
+        unsafe {
+            quill_sys::entity_add_goal(self.id().0, priority, controls.bits(), goal_data.into());
+        }
+    }
+}
+
+/// For Quill internal use only. Do not call.
+///
+/// # Safety
+/// `data` must be a goal data pointer created by [`Entity::add_goal`].
+#[doc(hidden)]
+pub unsafe fn run_goal(data: *mut u8, entity: u64, action: u32) -> u32 {
+    let goal = &mut *data.cast::<Box<dyn Goal>>();
+    let entity = Entity::new(EntityId(quill_common::EntityId(entity)));
+    let game = &mut Game::new();
+    match GoalAction::from_u32(action) {
+        Some(GoalAction::CanStart) => goal.can_start(game, &entity) as u32,
+        Some(GoalAction::ShouldContinue) => goal.should_continue(game, &entity) as u32,
+        Some(GoalAction::Start) => {
+            goal.start(game, &entity);
+            0
+        }
+        Some(GoalAction::Tick) => {
+            goal.tick(game, &entity);
+            0
+        }
+        Some(GoalAction::Stop) => {
+            goal.stop(game, &entity);
+            0
+        }
+        None => 0,
+    }
+}
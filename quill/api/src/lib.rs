@@ -4,18 +4,22 @@ pub mod entities;
 mod entity;
 mod entity_builder;
 mod game;
+pub mod goal;
 pub mod query;
+pub mod scheduler;
 mod setup;
 
 pub use entity::{Entity, EntityId};
 pub use entity_builder::EntityBuilder;
 pub use game::Game;
+pub use goal::{Goal, GoalControls};
+pub use scheduler::TaskHandle;
 pub use setup::Setup;
 
 #[doc(inline)]
 pub use libcraft_blocks::{BlockKind, BlockState};
 #[doc(inline)]
-pub use libcraft_core::{BlockPosition, ChunkPosition, Gamemode, Position};
+pub use libcraft_core::{BlockPosition, ChunkPosition, Gamemode, Position, StatusEffect};
 #[doc(inline)]
 pub use libcraft_particles::{Particle, ParticleKind};
 #[doc(inline)]
@@ -0,0 +1,59 @@
+//! Delayed and repeating tasks.
+
+use std::any::Any;
+
+use quill_common::task::TaskAction;
+
+use crate::Game;
+
+type TaskFn = Box<dyn FnMut(&mut dyn Any, &mut Game)>;
+
+/// A handle to a task scheduled with [`Game::schedule_once`],
+/// [`Setup::schedule_repeating`](crate::Setup::schedule_repeating), etc.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TaskHandle(u64);
+
+impl TaskHandle {
+    /// Cancels the task, so it won't run again.
+    ///
+    /// Does nothing if the task already finished.
+    pub fn cancel(self) {
+        unsafe { quill_sys::cancel_task(self.0) }
+    }
+}
+
+/// Schedules a task with the host. A `period` of zero runs it once.
+pub(crate) fn schedule(delay: u64, period: u64, task: TaskFn) -> TaskHandle {
+    let task_data = Box::into_raw(Box::new(task)) as *mut u8;
+    let id = unsafe { quill_sys::schedule_task(task_data.into(), delay, period) };
+    TaskHandle(id)
+}
+
+/// Wraps a one-shot task as a `TaskFn`.
+pub(crate) fn once(task: impl FnOnce(&mut dyn Any, &mut Game) + 'static) -> TaskFn {
+    let mut task = Some(task);
+    Box::new(move |plugin, game| {
+        if let Some(task) = task.take() {
+            task(plugin, game);
+        }
+    })
+}
+
+/// For Quill internal use only. Do not call.
+///
+/// # Safety
+/// `data` must be a task data pointer created by [`schedule`],
+/// and must not be used again after a [`TaskAction::Finish`].
+#[doc(hidden)]
+pub unsafe fn run_task(data: *mut u8, action: u32, plugin: Option<&mut dyn Any>) {
+    let data = data.cast::<TaskFn>();
+    match TaskAction::from_u32(action) {
+        Some(TaskAction::Run) => {
+            if let Some(plugin) = plugin {
+                (*data)(plugin, &mut Game::new());
+            }
+        }
+        Some(TaskAction::Finish) => drop(Box::from_raw(data)),
+        None => {}
+    }
+}
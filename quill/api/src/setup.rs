@@ -1,6 +1,9 @@
-use std::marker::PhantomData;
+use std::{any::Any, marker::PhantomData};
 
-use crate::Game;
+use crate::{
+    scheduler::{self, TaskHandle},
+    Game,
+};
 
 /// Struct passed to your plugin's `enable()` function.
 ///
@@ -36,4 +39,51 @@ impl<Plugin> Setup<Plugin> {
 
         self
     }
+
+    /// Runs `task` once after `delay` ticks.
+    ///
+    /// Like systems, the task is given your plugin instance
+    /// and an `&mut Game`.
+    pub fn schedule_once(
+        &mut self,
+        delay: u64,
+        task: impl FnOnce(&mut Plugin, &mut Game) + 'static,
+    ) -> TaskHandle
+    where
+        Plugin: 'static,
+    {
+        scheduler::schedule(
+            delay,
+            0,
+            scheduler::once(move |plugin, game| task(downcast_plugin(plugin), game)),
+        )
+    }
+
+    /// Runs `task` after `delay` ticks, then every `period` ticks
+    /// until it's cancelled through the returned [`TaskHandle`].
+    ///
+    /// # Panics
+    /// Panics if `period` is zero.
+    pub fn schedule_repeating(
+        &mut self,
+        delay: u64,
+        period: u64,
+        mut task: impl FnMut(&mut Plugin, &mut Game) + 'static,
+    ) -> TaskHandle
+    where
+        Plugin: 'static,
+    {
+        assert_ne!(period, 0, "task period must be at least one tick");
+        scheduler::schedule(
+            delay,
+            period,
+            Box::new(move |plugin, game| task(downcast_plugin(plugin), game)),
+        )
+    }
+}
+
+fn downcast_plugin<Plugin: 'static>(plugin: &mut dyn Any) -> &mut Plugin {
+    plugin
+        .downcast_mut()
+        .expect("task was run with another plugin")
 }
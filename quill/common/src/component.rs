@@ -206,6 +206,15 @@ host_component_enum! {
         FlyingAbilityEvent = 1028,
         BuildingAbilityEvent = 1029,
         InvulnerabilityEvent = 1030,
+        FoodLevel = 1031,
+        Saturation = 1032,
+        Exhaustion = 1033,
+        UseItemEvent = 1034,
+        ExperienceLevel = 1035,
+        ExperienceProgress = 1036,
+        TotalExperience = 1037,
+        ActiveEffects = 1038,
+        MovementViolationEvent = 1039,
     }
 }
 
@@ -366,6 +375,8 @@ bincode_component_impl!(Particle);
 bincode_component_impl!(InteractEntityEvent);
 bincode_component_impl!(BlockPlacementEvent);
 bincode_component_impl!(BlockInteractEvent);
+bincode_component_impl!(UseItemEvent);
+bincode_component_impl!(MovementViolationEvent);
 bincode_component_impl!(CreativeFlyingEvent);
 bincode_component_impl!(SneakEvent);
 bincode_component_impl!(SprintEvent);
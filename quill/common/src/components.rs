@@ -3,12 +3,12 @@
 //! See the [entities module](crate::entities) for entity-specific
 //! components.
 
-use std::fmt::Display;
+use std::{collections::BTreeMap, fmt::Display};
 
 use serde::{Deserialize, Serialize};
 use smartstring::{LazyCompact, SmartString};
 
-use libcraft_core::Gamemode;
+use libcraft_core::{Gamemode, StatusEffect};
 
 /// Whether an entity is touching the ground.
 #[derive(
@@ -264,6 +264,217 @@ impl PreviousGamemode {
 pub struct Health(pub f32);
 bincode_component_impl!(Health);
 
+/// A player's food level, from 0 to 20.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    derive_more::Deref,
+    derive_more::DerefMut,
+)]
+pub struct FoodLevel(pub u32);
+bincode_component_impl!(FoodLevel);
+
+impl Default for FoodLevel {
+    fn default() -> Self {
+        FoodLevel(20)
+    }
+}
+
+/// A player's food saturation, which is depleted
+/// before the food level. Never exceeds the food level.
+#[derive(
+    Copy, Clone, Debug, PartialEq, Serialize, Deserialize, derive_more::Deref, derive_more::DerefMut,
+)]
+pub struct Saturation(pub f32);
+bincode_component_impl!(Saturation);
+
+impl Default for Saturation {
+    fn default() -> Self {
+        Saturation(5.0)
+    }
+}
+
+/// A player's food exhaustion. Each time it reaches 4,
+/// one point of saturation or food level is consumed.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    derive_more::Deref,
+    derive_more::DerefMut,
+)]
+pub struct Exhaustion(pub f32);
+bincode_component_impl!(Exhaustion);
+
+/// A player's experience level.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    derive_more::Deref,
+    derive_more::DerefMut,
+)]
+pub struct ExperienceLevel(pub u32);
+bincode_component_impl!(ExperienceLevel);
+
+/// A player's progress towards the next experience
+/// level, from 0 to 1.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    derive_more::Deref,
+    derive_more::DerefMut,
+)]
+pub struct ExperienceProgress(pub f32);
+bincode_component_impl!(ExperienceProgress);
+
+/// The total experience points a player has collected,
+/// shown as their score on death.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    derive_more::Deref,
+    derive_more::DerefMut,
+)]
+pub struct TotalExperience(pub u32);
+bincode_component_impl!(TotalExperience);
+
+/// A status effect applied to an entity.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EffectInstance {
+    /// The effect level, starting at 0 for level I.
+    pub amplifier: u8,
+    /// Remaining duration in ticks.
+    pub duration: u32,
+    /// Whether the effect comes from a beacon.
+    /// Ambient effects show fewer particles.
+    pub ambient: bool,
+    pub show_particles: bool,
+    pub show_icon: bool,
+}
+
+impl EffectInstance {
+    /// Creates a non-ambient effect which shows particles and an icon.
+    pub fn new(amplifier: u8, duration: u32) -> Self {
+        Self {
+            amplifier,
+            duration,
+            ambient: false,
+            show_particles: true,
+            show_icon: true,
+        }
+    }
+}
+
+/// The status effects active on an entity.
+///
+/// Adding or removing effects through this component
+/// applies them and updates clients.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ActiveEffects(BTreeMap<StatusEffect, EffectInstance>);
+bincode_component_impl!(ActiveEffects);
+
+impl ActiveEffects {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, effect: StatusEffect) -> Option<&EffectInstance> {
+        self.0.get(&effect)
+    }
+
+    pub fn contains(&self, effect: StatusEffect) -> bool {
+        self.0.contains_key(&effect)
+    }
+
+    /// Gets the amplifier of an effect, if it is active.
+    pub fn amplifier(&self, effect: StatusEffect) -> Option<u8> {
+        self.get(effect).map(|instance| instance.amplifier)
+    }
+
+    /// Applies an effect.
+    ///
+    /// As in vanilla, an active effect is only replaced by a stronger
+    /// one, or extended by one of the same strength with a longer duration.
+    /// Returns whether the effect was applied.
+    pub fn add(&mut self, effect: StatusEffect, instance: EffectInstance) -> bool {
+        match self.0.get_mut(&effect) {
+            Some(active) if instance.amplifier < active.amplifier => false,
+            Some(active)
+                if instance.amplifier == active.amplifier
+                    && instance.duration <= active.duration =>
+            {
+                false
+            }
+            Some(active) => {
+                *active = instance;
+                true
+            }
+            None => {
+                self.0.insert(effect, instance);
+                true
+            }
+        }
+    }
+
+    /// Removes an effect, returning it if it was active.
+    pub fn remove(&mut self, effect: StatusEffect) -> Option<EffectInstance> {
+        self.0.remove(&effect)
+    }
+
+    /// Removes all effects.
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (StatusEffect, &EffectInstance)> + '_ {
+        self.0.iter().map(|(&effect, instance)| (effect, instance))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (StatusEffect, &mut EffectInstance)> + '_ {
+        self.0
+            .iter_mut()
+            .map(|(&effect, instance)| (effect, instance))
+    }
+
+    /// Keeps only the effects for which `f` returns `true`.
+    pub fn retain(&mut self, mut f: impl FnMut(StatusEffect, &mut EffectInstance) -> bool) {
+        self.0.retain(|&effect, instance| f(effect, instance));
+    }
+}
+
 /// A component on players that tracks if they are sprinting or not.
 #[derive(
     Copy,
@@ -5,8 +5,12 @@ pub use change::{
 };
 pub use entity::{EntityCreateEvent, EntityRemoveEvent, PlayerJoinEvent};
 pub use interact_entity::InteractEntityEvent;
+pub use movement::{MovementCheck, MovementViolationEvent};
+pub use use_item::UseItemEvent;
 
 mod block_interact;
 mod change;
 mod entity;
 mod interact_entity;
+mod movement;
+mod use_item;
@@ -0,0 +1,30 @@
+use libcraft_core::Position;
+use serde::{Deserialize, Serialize};
+
+/// Triggered when the movement of a player fails a check of the
+/// server's movement validation. Unless the failed check is
+/// [`MovementCheck::Ground`], the player was moved back to `from`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MovementViolationEvent {
+    pub check: MovementCheck,
+    /// The position the player moved from.
+    pub from: Position,
+    /// The position the player claimed to move to.
+    pub to: Position,
+    /// The number of violations of the player since they joined,
+    /// including this one.
+    pub violations: u32,
+}
+
+/// A check of the server's movement validation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MovementCheck {
+    /// The player moved faster than possible.
+    Speed,
+    /// The player rose or hovered in the air without being able to fly.
+    Flight,
+    /// The player moved into or through blocks.
+    Collision,
+    /// The player claimed to stand on the ground while in the air.
+    Ground,
+}
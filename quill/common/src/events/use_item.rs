@@ -0,0 +1,9 @@
+use libcraft_core::Hand;
+use serde::{Deserialize, Serialize};
+
+/// Triggered when a player uses the item in one of their hands,
+/// e.g. to start eating.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UseItemEvent {
+    pub hand: Hand,
+}
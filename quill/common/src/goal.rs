@@ -0,0 +1,104 @@
+//! Types shared between the host and plugins for mob AI goals.
+
+use std::ops::{BitOr, BitOrAssign};
+
+use serde::{Deserialize, Serialize};
+
+/// The set of controls an AI goal needs exclusive
+/// access to while it is running.
+///
+/// Two goals which share a control cannot run at the same
+/// time. A goal with a higher priority (lower number) will
+/// interrupt a running goal if their controls overlap.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[repr(transparent)]
+pub struct GoalControls(u32);
+
+impl GoalControls {
+    /// The goal does not require any controls.
+    pub const NONE: GoalControls = GoalControls(0);
+    /// The goal moves the entity.
+    pub const MOVE: GoalControls = GoalControls(1 << 0);
+    /// The goal changes where the entity is looking.
+    pub const LOOK: GoalControls = GoalControls(1 << 1);
+    /// The goal makes the entity jump.
+    pub const JUMP: GoalControls = GoalControls(1 << 2);
+    /// The goal selects the entity's attack target.
+    pub const TARGET: GoalControls = GoalControls(1 << 3);
+
+    /// Creates a set of controls from its raw bits.
+    ///
+    /// Unknown bits are discarded.
+    pub fn from_bits(bits: u32) -> Self {
+        Self(bits & 0b1111)
+    }
+
+    /// Gets the raw bits of this set of controls.
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Returns whether all controls in `other` are in `self`.
+    pub fn contains(self, other: GoalControls) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns whether `self` and `other` have any controls in common.
+    pub fn intersects(self, other: GoalControls) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl BitOr for GoalControls {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for GoalControls {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// An operation the host asks a plugin-defined goal
+/// to perform. Passed to the plugin's exported `quill_run_goal`
+/// function.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum GoalAction {
+    CanStart = 0,
+    ShouldContinue = 1,
+    Start = 2,
+    Tick = 3,
+    Stop = 4,
+}
+
+impl GoalAction {
+    pub fn from_u32(x: u32) -> Option<Self> {
+        Some(match x {
+            0 => GoalAction::CanStart,
+            1 => GoalAction::ShouldContinue,
+            2 => GoalAction::Start,
+            3 => GoalAction::Tick,
+            4 => GoalAction::Stop,
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn controls_intersect() {
+        let controls = GoalControls::MOVE | GoalControls::LOOK;
+        assert!(controls.contains(GoalControls::MOVE));
+        assert!(controls.intersects(GoalControls::LOOK | GoalControls::JUMP));
+        assert!(!controls.intersects(GoalControls::TARGET));
+        assert_eq!(GoalControls::from_bits(controls.bits()), controls);
+    }
+}
@@ -8,6 +8,8 @@ pub mod entities;
 pub mod entity;
 pub mod entity_init;
 pub mod events;
+pub mod goal;
+pub mod task;
 
 use std::marker::PhantomData;
 
@@ -0,0 +1,23 @@
+//! Types shared between the host and plugins for scheduled tasks.
+
+/// An operation the host asks a plugin-defined task
+/// to perform. Passed to the plugin's exported `quill_run_task`
+/// function.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum TaskAction {
+    /// Run the task.
+    Run = 0,
+    /// Free the task, which won't run again.
+    Finish = 1,
+}
+
+impl TaskAction {
+    pub fn from_u32(x: u32) -> Option<Self> {
+        Some(match x {
+            0 => TaskAction::Run,
+            1 => TaskAction::Finish,
+            _ => return None,
+        })
+    }
+}
@@ -1,6 +1,6 @@
 use quill::{
     components::{CustomName, Name},
-    entities::Cow,
+    entities::{Cow, Player},
     EntityInit, Game, Gamemode, Plugin, Position, Setup, Uuid,
 };
 use rand::Rng;
@@ -13,6 +13,7 @@ struct SimplePlugin {
 impl Plugin for SimplePlugin {
     fn enable(_game: &mut Game, setup: &mut Setup<Self>) -> Self {
         setup.add_system(test_system);
+        setup.schedule_repeating(0, 100, spawn_mobs);
         SimplePlugin { tick_counter: 0 }
     }
 
@@ -31,13 +32,6 @@ fn test_system(plugin: &mut SimplePlugin, game: &mut Game) {
             position,
             uuid.as_hyphenated()
         ));
-
-        if plugin.tick_counter % 100 == 0 {
-            entity.send_message("Spawning a mob on you");
-            game.create_entity_builder(position, random_mob())
-                .with(CustomName::new("Custom name"))
-                .finish();
-        }
     }
     for (_, (mut position, _)) in game.query::<(&mut Position, &Cow)>() {
         position.y += 0.1;
@@ -46,6 +40,15 @@ fn test_system(plugin: &mut SimplePlugin, game: &mut Game) {
     plugin.tick_counter += 1;
 }
 
+fn spawn_mobs(_plugin: &mut SimplePlugin, game: &mut Game) {
+    for (entity, (position, _)) in game.query::<(&Position, &Player)>() {
+        entity.send_message("Spawning a mob on you");
+        game.create_entity_builder(position, random_mob())
+            .with(CustomName::new("Custom name"))
+            .finish();
+    }
+}
+
 fn random_mob() -> EntityInit {
     let mut entities = vec![
         EntityInit::Zombie,
@@ -111,6 +111,11 @@ extern "C" {
     /// Does nothing if the entity does not exist or it does not have the `Chat` component.
     pub fn entity_send_message(entity: EntityId, message_ptr: Pointer<u8>, message_len: u32);
 
+    /// Determines whether an entity has a permission node.
+    ///
+    /// Returns `false` if the entity does not exist or is not a player.
+    pub fn entity_has_permission(entity: EntityId, node_ptr: Pointer<u8>, node_len: u32) -> bool;
+
     /// Sends a title to an entity.
     ///
     /// The given `Title` should contain at least a `title` or a `sub_title`
@@ -118,6 +123,37 @@ extern "C" {
     /// Does nothing if the entity does not exist or if it does not have the `Chat` component.
     pub fn entity_send_title(entity: EntityId, title_json_ptr: Pointer<u8>, title_len: u32);
 
+    /// Adds an AI goal to an entity.
+    ///
+    /// Each tick the goal's actions are invoked by calling
+    /// the plugin's exported `quill_run_goal` method with
+    /// the `goal_data` pointer passed to this host call.
+    ///
+    /// `controls` are the bits of a `GoalControls`.
+    /// Does nothing if `entity` does not exist.
+    pub fn entity_add_goal(
+        entity: EntityId,
+        priority: u32,
+        controls: u32,
+        goal_data: PointerMut<u8>,
+    );
+
+    /// Schedules a task to run after `delay` ticks, then every
+    /// `period` ticks. A period of zero runs the task once.
+    ///
+    /// Each time the task runs, the plugin's exported `quill_run_task`
+    /// method is called with the `task_data` pointer passed to this
+    /// host call and `TaskAction::Run`. Once the task won't run again,
+    /// `quill_run_task` is called with `TaskAction::Finish`.
+    ///
+    /// Returns the ID of the task.
+    pub fn schedule_task(task_data: PointerMut<u8>, delay: u64, period: u64) -> u64;
+
+    /// Cancels a scheduled task.
+    ///
+    /// Does nothing if the task already finished.
+    pub fn cancel_task(task: u64);
+
     /// Creates an empty entity builder.
     ///
     /// This builder is used for creating an ecs-entity